@@ -0,0 +1,40 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use untyped_arith::eval::eval_term;
+use untyped_arith::parse;
+use untyped_arith::parser::Term;
+use untyped_arith::trace::eval_trace;
+
+// `succ(succ(... 0))`, the parsers accept at most 256 nested terms
+fn succ_chain(depth: usize) -> String {
+    format!("{}0{};", "succ(".repeat(depth), ")".repeat(depth))
+}
+
+// `if true then if true then ... 0 else 0 else 0`
+fn if_tower(depth: usize) -> Term {
+    let input = format!(
+        "{}0{};",
+        "if true then ".repeat(depth),
+        " else 0".repeat(depth)
+    );
+    parse(&input).unwrap().1
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let input = succ_chain(200);
+    c.bench_function("parse succ chain 200", |b| {
+        b.iter(|| parse(&input).unwrap())
+    });
+}
+
+fn bench_eval(c: &mut Criterion) {
+    let term = if_tower(200);
+
+    let mut group = c.benchmark_group("if tower 200");
+    group.bench_function("big-step", |b| b.iter(|| eval_term(&term).unwrap()));
+    group.bench_function("small-step", |b| b.iter(|| eval_trace(&term)));
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse, bench_eval);
+criterion_main!(benches);
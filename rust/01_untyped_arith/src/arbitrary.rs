@@ -0,0 +1,60 @@
+use proptest::prelude::*;
+
+use crate::parser::{Op, Term};
+
+// Terms of at most a few levels of nesting, so the eval properties stay fast
+impl Arbitrary for Term {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Term>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let leaf = prop_oneof![
+            Just(Term::TmTrue),
+            Just(Term::TmFalse),
+            Just(Term::TmZero),
+            (1..1000u64).prop_map(Term::TmNumber),
+        ];
+        leaf.prop_recursive(4, 32, 3, |inner| {
+            prop_oneof![
+                inner.clone().prop_map(|term| Term::TmSucc(Box::new(term))),
+                inner.clone().prop_map(|term| Term::TmPred(Box::new(term))),
+                inner
+                    .clone()
+                    .prop_map(|term| Term::TmIsZero(Box::new(term))),
+                (inner.clone(), inner.clone(), inner.clone()).prop_map(
+                    |(cond, then_term, else_term)| {
+                        Term::TmIf(Box::new(cond), Box::new(then_term), Box::new(else_term))
+                    }
+                ),
+                inner.clone().prop_map(|term| Term::TmNot(Box::new(term))),
+                (inner.clone(), inner.clone())
+                    .prop_map(|(left, right)| Term::TmAnd(Box::new(left), Box::new(right))),
+                (inner.clone(), inner.clone())
+                    .prop_map(|(left, right)| Term::TmOr(Box::new(left), Box::new(right))),
+                (prop::sample::select(&Op::ALL[..]), inner.clone(), inner).prop_map(
+                    |(op, left, right)| Term::TmPrim(op, Box::new(left), Box::new(right))
+                ),
+            ]
+        })
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{eval::eval_term, parser::parse};
+
+    proptest! {
+        #[test]
+        fn test_display_parses_back(term: Term) {
+            let source = format!("{};", term);
+            prop_assert_eq!(parse(&source), Ok(("", term)));
+        }
+
+        #[test]
+        fn test_eval_never_panics(term: Term) {
+            let _ = eval_term(&term);
+        }
+    }
+}
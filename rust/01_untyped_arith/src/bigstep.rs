@@ -0,0 +1,93 @@
+use crate::parser::Term;
+
+// Big-step evaluation read off the natural semantics rules of exercise 3.5.17,
+// t ⇓ v. `None` when no derivation exists, which is the case for exactly the
+// terms whose evaluation gets stuck.
+
+fn is_numeric_val(term: &Term) -> bool {
+    match term {
+        Term::TmZero | Term::TmNumber(_) => true,
+        Term::TmSucc(term) => is_numeric_val(term),
+        _ => false,
+    }
+}
+
+pub fn eval_big_step(term: &Term) -> Option<Term> {
+    // B-IfTrue and B-IfFalse, for the boolean operators too
+    if let Some(conditional) = term.conditional() {
+        return match eval_big_step(conditional.cond_term)? {
+            Term::TmTrue => eval_big_step(conditional.then_term),
+            Term::TmFalse => eval_big_step(conditional.else_term),
+            _ => None,
+        };
+    }
+    match term {
+        // B-Value
+        Term::TmTrue | Term::TmFalse | Term::TmZero | Term::TmNumber(_) => Some(term.clone()),
+        // B-Succ
+        Term::TmSucc(term) => match eval_big_step(term)? {
+            number if is_numeric_val(&number) => Some(Term::TmSucc(Box::new(number))),
+            _ => None,
+        },
+        // B-PredZero and B-PredSucc
+        Term::TmPred(term) => match &eval_big_step(term)? {
+            Term::TmZero => Some(Term::TmZero),
+            number => number.predecessor(),
+        },
+        // B-IszeroZero and B-IszeroSucc
+        Term::TmIsZero(term) => match eval_big_step(term)? {
+            Term::TmZero => Some(Term::TmTrue),
+            Term::TmSucc(_) | Term::TmNumber(_) => Some(Term::TmFalse),
+            _ => None,
+        },
+        // B-Plus, B-Times and B-Leq
+        Term::TmPrim(op, left, right) => {
+            let m = eval_big_step(left)?.to_numeral()?;
+            let n = eval_big_step(right)?.to_numeral()?;
+            op.apply(m, n)
+        }
+        Term::TmIf(..) | Term::TmNot(_) | Term::TmAnd(..) | Term::TmOr(..) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{parser::parse, trace::eval_trace};
+
+    fn big_step(input: &str) -> Option<String> {
+        let (_, term) = parse(input).unwrap();
+        eval_big_step(&term).map(|value| value.to_string())
+    }
+
+    #[test]
+    fn test_eval_big_step() {
+        assert_eq!(
+            big_step("if iszero(pred(succ(0))) then succ(0) else 0;"),
+            Some("succ(0)".to_string())
+        );
+        assert_eq!(big_step("pred(0);"), Some("0".to_string()));
+        assert_eq!(
+            big_step("leq(succ(0), plus(0, 1));"),
+            Some("true".to_string())
+        );
+        assert_eq!(big_step("succ(true);"), None);
+        assert_eq!(big_step("if 0 then true else false;"), None);
+    }
+
+    proptest! {
+        // exercise 3.5.17: t ->* v iff t ⇓ v
+        #[test]
+        fn test_agrees_with_small_step(term: Term) {
+            let normal_form = eval_trace(&term).pop().map_or(term.clone(), |step| step.term);
+            let value = match normal_form {
+                Term::TmTrue | Term::TmFalse => Some(normal_form),
+                _ if is_numeric_val(&normal_form) => Some(normal_form),
+                _ => None,
+            };
+            prop_assert_eq!(eval_big_step(&term), value);
+        }
+    }
+}
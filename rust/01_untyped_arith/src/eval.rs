@@ -1,86 +1,240 @@
-use core::panic;
 use std::fmt::Display;
 use std::fmt::Formatter;
 
-use crate::parser::parse;
-use crate::parser::Term;
+use tapl_frontend::outcome::{evaluate, Outcome};
+
+use crate::parser::{parse, ParseError};
+use crate::parser::{Op, Term};
+use crate::trace::eval1;
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Value {
     Boolean(bool),
-    Numeric(u8),
+    Numeric(u64),
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Boolean(value) => write!(f, "{}", value),
+            Value::Numeric(value) => write!(f, "{}", value),
+        }
+    }
 }
 
-#[derive(Clone, Debug)]
-pub struct Error {
-    msg: String,
+#[derive(Clone, Debug, PartialEq)]
+pub enum EvalError {
+    // numbers are stored in a u64, so the successor of 2^64 - 1 is an error
+    NumericOverflow,
+    // `succ`, `pred`, `iszero` or a primitive operator of a boolean, with
+    // the operator name
+    NotNumeric(&'static str, Value),
+    // an `if` whose condition is a number, or a boolean operator of one,
+    // with the keyword of the conditional
+    NotBoolean(&'static str, Value),
 }
 
-impl From<nom::Err<nom::error::VerboseError<&str>>> for Error {
-    fn from(error: nom::Err<nom::error::VerboseError<&str>>) -> Self {
-        Error {
-            msg: format!("nom parser error: {}", error.to_string()),
+impl Display for EvalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvalError::NumericOverflow => write!(f, "numeric overflow"),
+            EvalError::NotNumeric(operator, value) => {
+                write!(f, "{} MUST operate with Numeric, found {}", operator, value)
+            }
+            EvalError::NotBoolean(keyword, value) => {
+                let operator = match *keyword {
+                    "if" => "if condition",
+                    operator => operator,
+                };
+                write!(f, "{} MUST operate with Boolean, found {}", operator, value)
+            }
         }
     }
 }
 
+impl std::error::Error for EvalError {}
+
+// Everything `eval` can fail with, from the source text to a value
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    Parse(ParseError),
+    Eval(EvalError),
+}
+
+impl From<ParseError> for Error {
+    fn from(error: ParseError) -> Self {
+        Error::Parse(error)
+    }
+}
+
+impl From<EvalError> for Error {
+    fn from(error: EvalError) -> Self {
+        Error::Eval(error)
+    }
+}
+
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.msg)?;
-        Ok(())
+        match self {
+            Error::Parse(error) => write!(f, "{}", error),
+            Error::Eval(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Parse(error) => Some(error),
+            Error::Eval(error) => Some(error),
+        }
     }
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
-pub fn eval_term(term: &Term) -> Result<Value> {
-    let value = match term {
-        Term::TmTrue => Value::Boolean(true),
-        Term::TmFalse => Value::Boolean(false),
-        Term::TmZero => Value::Numeric(0),
-        Term::TmSucc(term) => {
-            let value = if let Value::Numeric(number) = eval_term(term.as_ref())? {
-                Value::Numeric(number + 1)
-            } else {
-                panic!("succ MUST operate with Numeric");
-            };
-            value
-        }
-        Term::TmPred(term) => {
-            let value = if let Value::Numeric(number) = eval_term(term.as_ref())? {
-                Value::Numeric(number - 1)
-            } else {
-                panic!("pred MUST operate with Numeric");
-            };
-            value
-        }
-        Term::TmIsZero(term) => Value::Boolean(term.is_zero()),
-        Term::TmIf(cond_term, then_term, else_term) => {
-            if let Value::Boolean(cond) = eval_term(cond_term.as_ref())? {
-                if cond {
-                    eval_term(&then_term.as_ref())?
-                } else {
-                    eval_term(&else_term.as_ref())?
+fn succ(number: u64) -> Result<u64, EvalError> {
+    number.checked_add(1).ok_or(EvalError::NumericOverflow)
+}
+
+fn apply(op: Op, m: u64, n: u64) -> Result<Value, EvalError> {
+    let number = |number: Option<u64>| number.map(Value::Numeric);
+    match op {
+        Op::Plus => number(m.checked_add(n)),
+        Op::Times => number(m.checked_mul(n)),
+        Op::Leq => Some(Value::Boolean(m <= n)),
+    }
+    .ok_or(EvalError::NumericOverflow)
+}
+
+// The evaluation still to do once the current subterm has a value
+enum Frame<'a> {
+    Succ,
+    Pred,
+    IsZero,
+    If(&'static str, &'a Term, &'a Term),
+    // the operator and its right operand, then the value of the left one
+    PrimLeft(Op, &'a Term),
+    PrimRight(Op, u64),
+}
+
+// Big-step evaluation with the pending work kept on a heap stack, so the
+// depth of a term is not limited by the Rust stack
+pub fn eval_term(term: &Term) -> Result<Value, EvalError> {
+    let mut frames = vec![];
+    let mut term = term;
+    loop {
+        let mut value = loop {
+            if let Some(conditional) = term.conditional() {
+                frames.push(Frame::If(
+                    conditional.keyword,
+                    conditional.then_term,
+                    conditional.else_term,
+                ));
+                term = conditional.cond_term;
+                continue;
+            }
+            match term {
+                Term::TmTrue => break Value::Boolean(true),
+                Term::TmFalse => break Value::Boolean(false),
+                Term::TmZero => break Value::Numeric(0),
+                Term::TmNumber(n) => break Value::Numeric(*n),
+                Term::TmSucc(inner) => {
+                    frames.push(Frame::Succ);
+                    term = inner;
+                }
+                Term::TmPred(inner) => {
+                    frames.push(Frame::Pred);
+                    term = inner;
+                }
+                Term::TmIsZero(inner) => {
+                    frames.push(Frame::IsZero);
+                    term = inner;
+                }
+                Term::TmPrim(op, left, right) => {
+                    frames.push(Frame::PrimLeft(*op, right));
+                    term = left;
+                }
+                Term::TmIf(..) | Term::TmNot(_) | Term::TmAnd(..) | Term::TmOr(..) => {
+                    unreachable!("a conditional is evaluated above")
                 }
-            } else {
-                panic!("if condition MUST operate with Boolean");
             }
-        }
-    };
-    Ok(value)
+        };
+        // an `if` continues with one of its branches and a primitive with
+        // its right operand, everything else
+        // finishes with the value it was waiting for
+        term = loop {
+            value = match (frames.pop(), value) {
+                (None, value) => return Ok(value),
+                (Some(Frame::Succ), Value::Numeric(number)) => Value::Numeric(succ(number)?),
+                // pred(0) is 0 (E-PredZero)
+                (Some(Frame::Pred), Value::Numeric(number)) => {
+                    Value::Numeric(number.saturating_sub(1))
+                }
+                (Some(Frame::IsZero), Value::Numeric(number)) => Value::Boolean(number == 0),
+                (Some(Frame::If(_, then_term, _)), Value::Boolean(true)) => break then_term,
+                (Some(Frame::If(_, _, else_term)), Value::Boolean(false)) => break else_term,
+                (Some(Frame::PrimLeft(op, right)), Value::Numeric(m)) => {
+                    frames.push(Frame::PrimRight(op, m));
+                    break right;
+                }
+                (Some(Frame::PrimRight(op, m)), Value::Numeric(n)) => apply(op, m, n)?,
+                (Some(Frame::Succ), value) => return Err(EvalError::NotNumeric("succ", value)),
+                (Some(Frame::Pred), value) => return Err(EvalError::NotNumeric("pred", value)),
+                (Some(Frame::IsZero), value) => return Err(EvalError::NotNumeric("iszero", value)),
+                (Some(Frame::If(keyword, _, _)), value) => {
+                    return Err(EvalError::NotBoolean(keyword, value))
+                }
+                (Some(Frame::PrimLeft(op, _)), value) | (Some(Frame::PrimRight(op, _)), value) => {
+                    return Err(EvalError::NotNumeric(op.name(), value))
+                }
+            };
+        };
+    }
+}
+
+// the value of a normal form, or `None` for a stuck term and a number too
+// big for a u64
+fn value_of(term: &Term) -> Option<Value> {
+    match term {
+        Term::TmTrue => Some(Value::Boolean(true)),
+        Term::TmFalse => Some(Value::Boolean(false)),
+        Term::TmZero => Some(Value::Numeric(0)),
+        Term::TmNumber(n) => Some(Value::Numeric(*n)),
+        Term::TmSucc(term) => match value_of(term)? {
+            Value::Numeric(number) => succ(number).ok().map(Value::Numeric),
+            Value::Boolean(_) => None,
+        },
+        _ => None,
+    }
+}
+
+// small-step evaluation by `trace::eval1` that gives up after `max_steps`
+// reductions
+pub fn eval_with_fuel(term: &Term, max_steps: usize) -> Outcome<Value, Term> {
+    evaluate(
+        term,
+        max_steps,
+        |term| eval1(term).map(|step| step.term),
+        value_of,
+    )
 }
 
 pub fn eval(input: &str) -> Result<Value> {
-    let term = parse(input)?;
-    // assert has no input string left
-    assert!(term.0.is_empty());
+    let (rest, term) = parse(input)?;
+    if !rest.is_empty() {
+        return Err(ParseError::TrailingInput(rest.to_string()).into());
+    }
 
-    eval_term(&term.1)
+    Ok(eval_term(&term)?)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::language::UntypedArith;
+    use tapl_testkit::assert_evals_to;
 
     #[test]
     fn test_eval() -> Result<()> {
@@ -88,6 +242,91 @@ mod tests {
         assert_eq!(eval("succ(2);")?, Value::Numeric(3));
         assert_eq!(eval("iszero(2);")?, Value::Boolean(false));
         assert_eq!(eval("if false then 10 else 20;")?, Value::Numeric(20));
+        assert_eq!(
+            eval("succ(true);"),
+            Err(Error::Eval(EvalError::NotNumeric(
+                "succ",
+                Value::Boolean(true)
+            )))
+        );
+        assert_eq!(
+            eval("0; 0;"),
+            Err(Error::Parse(ParseError::TrailingInput(" 0;".to_string())))
+        );
         Ok(())
     }
+
+    #[test]
+    fn test_eval_term() {
+        assert_evals_to!(UntypedArith, "pred(succ(0));", "0");
+        assert_evals_to!(UntypedArith, "if iszero(0) then succ(1) else 0;", "2");
+        assert_evals_to!(UntypedArith, "if true then false else true;", "false");
+        assert_evals_to!(UntypedArith, "iszero(pred(succ(0)));", "true");
+        assert_evals_to!(UntypedArith, "plus(times(12, 20), pred(4));", "243");
+        assert_evals_to!(UntypedArith, "leq(times(3, 0), 0);", "true");
+        assert_evals_to!(UntypedArith, "times(16, 16);", "256");
+        assert_evals_to!(
+            UntypedArith,
+            "times(4294967296, 4294967295);",
+            "18446744069414584320"
+        );
+        assert_eq!(
+            eval("times(4294967296, 4294967296);"),
+            Err(Error::Eval(EvalError::NumericOverflow))
+        );
+        assert_eq!(
+            eval("succ(18446744073709551615);"),
+            Err(Error::Eval(EvalError::NumericOverflow))
+        );
+        assert_eq!(
+            eval("plus(0, iszero(0));"),
+            Err(Error::Eval(EvalError::NotNumeric(
+                "plus",
+                Value::Boolean(true)
+            )))
+        );
+        assert_evals_to!(UntypedArith, "and(iszero(0), not(false));", "true");
+        assert_evals_to!(UntypedArith, "or(false, leq(2, 1));", "false");
+        // the error names the operator, not the `if` it stands for
+        assert_eq!(
+            eval("and(0, true);").unwrap_err().to_string(),
+            "and MUST operate with Boolean, found 0"
+        );
+        assert_eq!(
+            eval("if 1 then 0 else 0;").unwrap_err().to_string(),
+            "if condition MUST operate with Boolean, found 1"
+        );
+    }
+
+    #[test]
+    fn test_eval_with_fuel() {
+        let run = |input: &str, max_steps| eval_with_fuel(&parse(input).unwrap().1, max_steps);
+        assert_eq!(
+            run("pred(succ(succ(0)));", 1),
+            Outcome::Value(Value::Numeric(1))
+        );
+        assert_eq!(
+            run("succ(if 0 then true else 0);", 10),
+            Outcome::Stuck(parse("succ(if 0 then true else 0);").unwrap().1)
+        );
+        let outcome = run("iszero(pred(pred(0)));", 2);
+        assert_eq!(outcome.to_string(), "no normal form after 2 steps");
+    }
+
+    #[test]
+    fn test_eval_deep_term() {
+        let mut term = Term::TmZero;
+        for _ in 0..100_000 {
+            term = Term::TmPred(Box::new(Term::TmSucc(Box::new(term))));
+        }
+        assert_eq!(eval_term(&term), Ok(Value::Numeric(0)));
+        for _ in 0..100_000 {
+            term = Term::TmIf(
+                Box::new(Term::TmIsZero(Box::new(Term::TmZero))),
+                Box::new(term),
+                Box::new(Term::TmTrue),
+            );
+        }
+        assert_eq!(eval_term(&term), Ok(Value::Numeric(0)));
+    }
 }
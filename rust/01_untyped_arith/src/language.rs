@@ -0,0 +1,28 @@
+use tapl_testkit::{Failure, Language};
+
+use crate::eval::{eval_term, Value};
+use crate::parser::{parse, Term};
+use crate::trace::eval1;
+
+pub struct UntypedArith;
+
+impl Language for UntypedArith {
+    type Term = Term;
+    type Value = Value;
+
+    fn parse(input: &str) -> Result<Term, Failure> {
+        match parse(input) {
+            Ok(("", term)) => Ok(term),
+            Ok((rest, _)) => Err(Failure::Parse(format!("unexpected `{}`", rest))),
+            Err(error) => Err(Failure::Parse(error.to_string())),
+        }
+    }
+
+    fn eval(term: &Term) -> Result<Value, Failure> {
+        eval_term(term).map_err(|error| Failure::Eval(error.to_string()))
+    }
+
+    fn step(term: &Term) -> Option<Term> {
+        eval1(term).map(|step| step.term)
+    }
+}
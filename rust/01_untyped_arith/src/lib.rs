@@ -1,5 +1,14 @@
+#[cfg(test)]
+mod arbitrary;
+pub mod bigstep;
 pub mod eval;
+#[cfg(test)]
+mod language;
+#[cfg(test)]
+mod metatheory;
 pub mod parser;
+pub mod trace;
+pub mod wrong;
 
 pub use eval::eval;
 pub use parser::parse;
@@ -0,0 +1,161 @@
+// The determinacy and normal form theorems of TAPL section 3.5 as properties
+// of `trace::eval1` on random terms. `steps` is the evaluation relation read
+// off figures 3-1 and 3-2 rule by rule, with no priority between the rules,
+// so an overlap that `eval1` hides by trying one rule first shows up here.
+
+use std::collections::HashSet;
+
+use proptest::prelude::*;
+
+use crate::{
+    parser::Term,
+    trace::{eval1, eval_trace, stuck_at},
+};
+
+fn is_numeric_val(term: &Term) -> bool {
+    match term {
+        Term::TmZero | Term::TmNumber(_) => true,
+        Term::TmSucc(term) => is_numeric_val(term),
+        _ => false,
+    }
+}
+
+fn is_val(term: &Term) -> bool {
+    matches!(term, Term::TmTrue | Term::TmFalse) || is_numeric_val(term)
+}
+
+// every t' with t -> t'
+fn steps(term: &Term) -> Vec<Term> {
+    let boxed = |term: &Term| Box::new(term.clone());
+    let mut steps = vec![];
+    if let Some(conditional) = term.conditional() {
+        match conditional.cond_term {
+            Term::TmTrue => steps.push(conditional.then_term.clone()),
+            Term::TmFalse => steps.push(conditional.else_term.clone()),
+            _ => {}
+        }
+        for cond in self::steps(conditional.cond_term) {
+            steps.push(term.with_condition(boxed(&cond)));
+        }
+        return steps;
+    }
+    match term {
+        Term::TmSucc(term) => {
+            for term in self::steps(term) {
+                steps.push(Term::TmSucc(boxed(&term)));
+            }
+        }
+        Term::TmPred(term) => {
+            match term.as_ref() {
+                Term::TmZero => steps.push(Term::TmZero),
+                Term::TmSucc(number) if is_numeric_val(number) => {
+                    steps.push(number.as_ref().clone())
+                }
+                Term::TmNumber(n) => steps.push(Term::numeral(n - 1)),
+                _ => {}
+            }
+            for term in self::steps(term) {
+                steps.push(Term::TmPred(boxed(&term)));
+            }
+        }
+        Term::TmIsZero(term) => {
+            match term.as_ref() {
+                Term::TmZero => steps.push(Term::TmTrue),
+                Term::TmSucc(number) if is_numeric_val(number) => steps.push(Term::TmFalse),
+                Term::TmNumber(_) => steps.push(Term::TmFalse),
+                _ => {}
+            }
+            for term in self::steps(term) {
+                steps.push(Term::TmIsZero(boxed(&term)));
+            }
+        }
+        Term::TmPrim(op, left, right) => {
+            if let (Some(m), Some(n)) = (left.to_numeral(), right.to_numeral()) {
+                steps.extend(op.apply(m, n));
+            }
+            for left in self::steps(left) {
+                steps.push(Term::TmPrim(*op, boxed(&left), right.clone()));
+            }
+            if is_numeric_val(left) {
+                for right in self::steps(right) {
+                    steps.push(Term::TmPrim(*op, left.clone(), boxed(&right)));
+                }
+            }
+        }
+        Term::TmTrue | Term::TmFalse | Term::TmZero | Term::TmNumber(_) => {}
+        Term::TmIf(..) | Term::TmNot(_) | Term::TmAnd(..) | Term::TmOr(..) => {
+            unreachable!("a conditional steps above")
+        }
+    }
+    steps
+}
+
+// the normal forms reachable from `term` along any path of steps
+fn normal_forms(term: &Term) -> Vec<Term> {
+    let mut seen = HashSet::new();
+    let mut pending = vec![term.clone()];
+    let mut normal_forms = vec![];
+    while let Some(term) = pending.pop() {
+        if !seen.insert(term.to_string()) {
+            continue;
+        }
+        let next = steps(&term);
+        if next.is_empty() {
+            normal_forms.push(term);
+        } else {
+            pending.extend(next);
+        }
+    }
+    normal_forms
+}
+
+fn value() -> BoxedStrategy<Term> {
+    prop_oneof![
+        Just(Term::TmTrue),
+        Just(Term::TmFalse),
+        (0..20usize)
+            .prop_map(|n| { (0..n).fold(Term::TmZero, |term, _| Term::TmSucc(Box::new(term))) }),
+        (1..1000u64).prop_map(Term::TmNumber),
+    ]
+    .boxed()
+}
+
+proptest! {
+    // Theorem 3.5.4: if t -> t' and t -> t'' then t' = t''
+    #[test]
+    fn test_determinacy(term: Term) {
+        let steps = steps(&term);
+        prop_assert!(steps.len() <= 1, "{} steps to all of {:?}", term, steps);
+        prop_assert_eq!(steps.first().cloned(), eval1(&term).map(|step| step.term));
+    }
+
+    // Theorem 3.5.7: every value is in normal form
+    #[test]
+    fn test_values_are_normal_forms(value in value()) {
+        prop_assert!(is_val(&value));
+        prop_assert!(steps(&value).is_empty());
+        prop_assert!(eval1(&value).is_none());
+    }
+
+    // Theorems 3.5.11 and 3.5.12: every term reaches exactly one normal
+    // form, and `eval_trace` ends at it
+    #[test]
+    fn test_unique_normal_form(term: Term) {
+        let normal_forms = normal_forms(&term);
+        prop_assert_eq!(normal_forms.len(), 1, "{} has normal forms {:?}", term, normal_forms);
+        let normal = eval_trace(&term).pop().map_or(term, |step| step.term);
+        prop_assert_eq!(&normal_forms[0], &normal);
+    }
+
+    // Definition 3.5.15: a term is stuck when it is a normal form but not a
+    // value, and `stuck_at` finds the stuck redex in it
+    #[test]
+    fn test_stuck_terms(term: Term) {
+        let stuck = steps(&term).is_empty() && !is_val(&term);
+        let at = stuck_at(&term);
+        prop_assert_eq!(at.is_some(), stuck, "{}", term);
+        if let Some(at) = at {
+            prop_assert!(steps(&at.subterm).is_empty() && !is_val(&at.subterm));
+        }
+    }
+}
@@ -1,28 +1,151 @@
-use nom::{
-    branch::alt,
-    bytes::complete::{tag, tag_no_case},
-    character::complete::one_of,
-    error::{context, VerboseError},
-    multi::many_m_n,
-    sequence::tuple,
-    Err as NomErr,
+use std::fmt::{self, Formatter};
+
+use tapl_frontend::{
+    dot::Label,
+    lexer::{Lexer, SyntaxError, Token},
+    visit::Node,
 };
 
-pub type IResult<I, O> = nom::IResult<I, O, VerboseError<I>>;
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    Syntax(SyntaxError),
+    // text after the terminating `;`
+    TrailingInput(String),
+    // a numeral above 2^64 - 1, numbers are stored in a u64
+    NumberTooLarge(String),
+}
+
+impl From<SyntaxError> for ParseError {
+    fn from(error: SyntaxError) -> Self {
+        ParseError::Syntax(error)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Syntax(error) => write!(f, "{}", error),
+            ParseError::TrailingInput(rest) => write!(f, "unexpected input `{}`", rest),
+            ParseError::NumberTooLarge(number) => write!(f, "number {} is too large", number),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Syntax(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+pub type ParseResult<'a, T> = Result<(&'a str, T), ParseError>;
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Term {
     TmTrue,
     TmFalse,
     TmZero,
     TmSucc(Box<Term>),
+    // a numeral n above 0, the value `succ` applied n times to 0 kept as a
+    // machine integer so that large numbers stay small
+    TmNumber(u64),
     TmPred(Box<Term>),
     TmIsZero(Box<Term>),
     // condition term, then term, else term
     TmIf(Box<Term>, Box<Term>, Box<Term>),
+    // `not(t)`, `and(t1, t2)` and `or(t1, t2)`, derived forms that evaluate
+    // as the `if` of `Term::conditional` but keep the name of the operator
+    TmNot(Box<Term>),
+    TmAnd(Box<Term>, Box<Term>),
+    TmOr(Box<Term>, Box<Term>),
+    // `plus(t1, t2)`, `times(t1, t2)` and `leq(t1, t2)`
+    TmPrim(Op, Box<Term>, Box<Term>),
 }
 
+// The primitive operators on two numbers, evaluated on machine integers
+// instead of by recursion on `succ`
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Op {
+    Plus,
+    Times,
+    Leq,
+}
+
+impl Op {
+    pub const ALL: [Op; 3] = [Op::Plus, Op::Times, Op::Leq];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Op::Plus => "plus",
+            Op::Times => "times",
+            Op::Leq => "leq",
+        }
+    }
+
+    // the value of `op(m, n)`, a numeric value or a boolean, `None` when the
+    // result does not fit in a u64
+    pub fn apply(self, m: u64, n: u64) -> Option<Term> {
+        match self {
+            Op::Plus => m.checked_add(n).map(Term::numeral),
+            Op::Times => m.checked_mul(n).map(Term::numeral),
+            Op::Leq => Some(if m <= n { Term::TmTrue } else { Term::TmFalse }),
+        }
+    }
+}
+
+// An `if` or a boolean operator, with the word it is written with
+pub struct Conditional<'a> {
+    pub keyword: &'static str,
+    pub cond_term: &'a Term,
+    pub then_term: &'a Term,
+    pub else_term: &'a Term,
+}
+
+static TRUE: Term = Term::TmTrue;
+static FALSE: Term = Term::TmFalse;
+
 impl Term {
+    // The conditional an `if` is and a boolean operator stands for (TAPL
+    // 11.3):
+    //
+    //   not(t)      = if t then false else true
+    //   and(t1, t2) = if t1 then t2 else false
+    //   or(t1, t2)  = if t1 then true else t2
+    pub fn conditional(&self) -> Option<Conditional<'_>> {
+        let (keyword, cond_term, then_term, else_term) = match self {
+            Term::TmIf(cond_term, then_term, else_term) => {
+                ("if", &**cond_term, &**then_term, &**else_term)
+            }
+            Term::TmNot(term) => ("not", &**term, &FALSE, &TRUE),
+            Term::TmAnd(left, right) => ("and", &**left, &**right, &FALSE),
+            Term::TmOr(left, right) => ("or", &**left, &TRUE, &**right),
+            _ => return None,
+        };
+        Some(Conditional {
+            keyword,
+            cond_term,
+            then_term,
+            else_term,
+        })
+    }
+
+    // a conditional with `cond_term` as its condition, for E-If
+    pub fn with_condition(&self, cond_term: Box<Term>) -> Term {
+        match self {
+            Term::TmIf(_, then_term, else_term) => {
+                Term::TmIf(cond_term, then_term.clone(), else_term.clone())
+            }
+            Term::TmNot(_) => Term::TmNot(cond_term),
+            Term::TmAnd(_, right) => Term::TmAnd(cond_term, right.clone()),
+            Term::TmOr(_, right) => Term::TmOr(cond_term, right.clone()),
+            _ => unreachable!("{} is not a conditional", self),
+        }
+    }
+
     pub fn is_zero(&self) -> bool {
         self == &Term::TmZero
     }
@@ -30,142 +153,264 @@ impl Term {
     pub fn is_boolean(&self) -> bool {
         self == &Term::TmTrue || self == &Term::TmFalse
     }
-}
 
-impl From<&str> for Term {
-    fn from(i: &str) -> Self {
-        match i.to_lowercase().as_str() {
-            "true" => Term::TmTrue,
-            "false" => Term::TmFalse,
-            "0" => Term::TmZero,
-            _ => unimplemented!("no other value term supported"),
+    // the numeric value n, 0 or a `TmNumber`
+    pub fn numeral(n: u64) -> Term {
+        match n {
+            0 => Term::TmZero,
+            n => Term::TmNumber(n),
         }
     }
-}
 
-fn parse_succ(input: &str) -> IResult<&str, Term> {
-    context(
-        "succ",
-        tuple((tag_no_case("succ"), tag("("), parse_term, tag(")"))),
-    )(input)
-    .map(|(next_input, (_, _, term, _))| (next_input, Term::TmSucc(Box::new(term))))
+    // the n of a numeric value `succ(...succ(0))` or `succ(...succ(m))`,
+    // `None` for other terms and numbers that do not fit in a u64
+    pub fn to_numeral(&self) -> Option<u64> {
+        let mut n: u64 = 0;
+        let mut term = self;
+        while let Term::TmSucc(inner) = term {
+            n = n.checked_add(1)?;
+            term = inner;
+        }
+        match term {
+            Term::TmZero => Some(n),
+            Term::TmNumber(m) => n.checked_add(*m),
+            _ => None,
+        }
+    }
+
+    // the predecessor of a numeric value other than 0, E-PredSucc
+    pub fn predecessor(&self) -> Option<Term> {
+        match self {
+            Term::TmSucc(number) => Some(number.as_ref().clone()),
+            Term::TmNumber(n) => Some(Term::numeral(n - 1)),
+            _ => None,
+        }
+    }
 }
 
-fn parse_pred(input: &str) -> IResult<&str, Term> {
-    context(
-        "pred",
-        tuple((tag_no_case("pred"), tag("("), parse_term, tag(")"))),
-    )(input)
-    .map(|(next_input, (_, _, term, _))| (next_input, Term::TmPred(Box::new(term))))
+// The derived drop recurses into the subterms and overflows the stack on a
+// deep term, so the subterms are moved out onto a heap stack first
+impl Drop for Term {
+    fn drop(&mut self) {
+        let mut stack = vec![];
+        take_subterms(self, &mut stack);
+        while let Some(mut term) = stack.pop() {
+            take_subterms(&mut term, &mut stack);
+        }
+    }
 }
 
-fn parse_iszero(input: &str) -> IResult<&str, Term> {
-    context(
-        "iszero",
-        tuple((tag_no_case("iszero"), tag("("), parse_term, tag(")"))),
-    )(input)
-    .map(|(next_input, (_, _, term, _))| (next_input, Term::TmIsZero(Box::new(term))))
+fn take_subterms(term: &mut Term, stack: &mut Vec<Term>) {
+    match term {
+        Term::TmTrue | Term::TmFalse | Term::TmZero | Term::TmNumber(_) => {}
+        Term::TmSucc(term) | Term::TmPred(term) | Term::TmIsZero(term) | Term::TmNot(term) => {
+            stack.push(std::mem::replace(term, Term::TmZero))
+        }
+        Term::TmIf(cond, then_term, else_term) => {
+            for term in [cond, then_term, else_term] {
+                stack.push(std::mem::replace(term, Term::TmZero));
+            }
+        }
+        Term::TmPrim(_, left, right) | Term::TmAnd(left, right) | Term::TmOr(left, right) => {
+            for term in [left, right] {
+                stack.push(std::mem::replace(term, Term::TmZero));
+            }
+        }
+    }
 }
 
-fn parse_if(input: &str) -> IResult<&str, Term> {
-    context(
-        "if",
-        tuple((
-            tag_no_case("if "),
-            parse_term,
-            tag_no_case(" then "),
-            parse_term,
-            tag_no_case(" else "),
-            parse_term,
-        )),
-    )(input)
-    .map(|(next_input, (_, cond_term, _, then_term, _, else_term))| {
-        (
-            next_input,
-            Term::TmIf(
-                Box::new(cond_term),
-                Box::new(then_term),
-                Box::new(else_term),
-            ),
-        )
-    })
+// there are no binders, every subterm is under 0 variables
+impl Node for Term {
+    fn children(&self) -> Vec<(usize, &Self)> {
+        match self {
+            Term::TmTrue | Term::TmFalse | Term::TmZero | Term::TmNumber(_) => vec![],
+            Term::TmSucc(term) | Term::TmPred(term) | Term::TmIsZero(term) | Term::TmNot(term) => {
+                vec![(0, term)]
+            }
+            Term::TmIf(cond, then_term, else_term) => {
+                vec![(0, cond), (0, then_term), (0, else_term)]
+            }
+            Term::TmPrim(_, left, right) | Term::TmAnd(left, right) | Term::TmOr(left, right) => {
+                vec![(0, left), (0, right)]
+            }
+        }
+    }
 }
 
-fn parse_value(input: &str) -> IResult<&str, Term> {
-    context(
-        "parse_value",
-        alt((tag_no_case("true"), tag_no_case("false"), tag_no_case("0"))),
-    )(input)
-    .map(|(next_input, res)| (next_input, res.into()))
+impl Label for Term {
+    fn label(&self) -> String {
+        match self {
+            Term::TmTrue => "true",
+            Term::TmFalse => "false",
+            Term::TmZero => "0",
+            Term::TmNumber(n) => return n.to_string(),
+            Term::TmSucc(_) => "succ",
+            Term::TmPred(_) => "pred",
+            Term::TmIsZero(_) => "iszero",
+            Term::TmIf(_, _, _) => "if",
+            Term::TmNot(_) => "not",
+            Term::TmAnd(_, _) => "and",
+            Term::TmOr(_, _) => "or",
+            Term::TmPrim(op, _, _) => op.name(),
+        }
+        .to_string()
+    }
 }
 
-fn parse_numeric(input: &str) -> IResult<&str, Term> {
-    fn n_to_m_digits<'a>(n: usize, m: usize) -> impl FnMut(&'a str) -> IResult<&str, String> {
-        move |input| {
-            many_m_n(n, m, one_of("0123456789"))(input)
-                .map(|(next_input, result)| (next_input, result.into_iter().collect()))
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Term::TmTrue => write!(f, "true"),
+            Term::TmFalse => write!(f, "false"),
+            Term::TmZero => write!(f, "0"),
+            Term::TmNumber(n) => write!(f, "{}", n),
+            Term::TmSucc(term) => write!(f, "succ({})", term),
+            Term::TmPred(term) => write!(f, "pred({})", term),
+            Term::TmIsZero(term) => write!(f, "iszero({})", term),
+            Term::TmIf(cond_term, then_term, else_term) => {
+                write!(f, "if {} then {} else {}", cond_term, then_term, else_term)
+            }
+            Term::TmNot(term) => write!(f, "not({})", term),
+            Term::TmAnd(left, right) => write!(f, "and({}, {})", left, right),
+            Term::TmOr(left, right) => write!(f, "or({}, {})", left, right),
+            Term::TmPrim(op, left, right) => write!(f, "{}({}, {})", op.name(), left, right),
         }
     }
+}
 
-    context("numeric", n_to_m_digits(1, 3))(input).and_then(|(next_input, result)| {
-        match result.parse::<u8>() {
-            Ok(n) => {
-                let mut current_term = Term::TmSucc(Box::new(Term::TmZero));
-                for _i in 1..n {
-                    current_term = Term::TmSucc(Box::new(current_term));
-                }
+const SYMBOLS: [&str; 4] = ["(", ")", ",", ";"];
 
-                Ok((next_input, current_term))
-            }
-            Err(_) => Err(NomErr::Error(VerboseError { errors: vec![] })),
+// the operators written `op(t1, ..., tn)`, with their number of arguments
+const OPERATORS: [(&str, usize); 9] = [
+    ("succ", 1),
+    ("pred", 1),
+    ("iszero", 1),
+    ("not", 1),
+    ("and", 2),
+    ("or", 2),
+    ("plus", 2),
+    ("times", 2),
+    ("leq", 2),
+];
+
+// `(t1, ..., tn)` after an operator. Every operator goes through here, so a
+// nested operator takes no more stack than a nested `succ`.
+fn parse_arguments(lexer: &mut Lexer, count: usize) -> Result<Vec<Term>, ParseError> {
+    lexer.expect_symbol("(")?;
+    let mut arguments = Vec::with_capacity(count);
+    for i in 0..count {
+        if i > 0 {
+            lexer.expect_symbol(",")?;
         }
-    })
+        arguments.push(parse_term(lexer)?);
+    }
+    lexer.expect_symbol(")")?;
+    Ok(arguments)
 }
 
-fn parse_term(input: &str) -> IResult<&str, Term> {
-    context(
-        "term",
-        alt((
-            parse_value,
-            parse_succ,
-            parse_pred,
-            parse_iszero,
-            parse_if,
-            parse_numeric,
-        )),
-    )(input)
-    .map(|(next_input, res)| (next_input, res))
+// The boolean operators are derived forms (TAPL 11.3), `Term::conditional`
+// gives the `if` each one stands for
+fn operator(name: &str, arguments: Vec<Term>) -> Term {
+    let mut arguments = arguments.into_iter();
+    let mut argument = || {
+        arguments
+            .next()
+            .expect("the number of arguments of the operator")
+    };
+    match name {
+        "succ" => Term::TmSucc(Box::new(argument())),
+        "pred" => Term::TmPred(Box::new(argument())),
+        "iszero" => Term::TmIsZero(Box::new(argument())),
+        "not" => Term::TmNot(Box::new(argument())),
+        "and" => {
+            let left = argument();
+            Term::TmAnd(Box::new(left), Box::new(argument()))
+        }
+        "or" => {
+            let left = argument();
+            Term::TmOr(Box::new(left), Box::new(argument()))
+        }
+        _ => {
+            let op = Op::ALL.iter().find(|op| op.name() == name);
+            let op = *op.expect("a primitive operator");
+            let left = argument();
+            Term::TmPrim(op, Box::new(left), Box::new(argument()))
+        }
+    }
 }
 
-pub fn parse(input: &str) -> IResult<&str, Term> {
-    context("parse", tuple((parse_term, tag(";"))))(input)
-        .map(|(next_input, (term, _))| (next_input, term))
+// a numeral n stands for succ applied n times to 0
+fn parse_numeric(number: &str) -> Result<Term, ParseError> {
+    let n = number
+        .parse::<u64>()
+        .map_err(|_| ParseError::NumberTooLarge(number.to_string()))?;
+    Ok(Term::numeral(n))
+}
+
+fn parse_term(lexer: &mut Lexer) -> Result<Term, ParseError> {
+    lexer.nested(|lexer| {
+        let (token, span) = lexer.next_token()?;
+        let keyword = |keyword| lexer.is_keyword(token, keyword);
+        let term = if keyword("true") {
+            Term::TmTrue
+        } else if keyword("false") {
+            Term::TmFalse
+        } else if let Some((name, count)) = OPERATORS.iter().find(|(name, _)| keyword(name)) {
+            operator(name, parse_arguments(lexer, *count)?)
+        } else if keyword("if") {
+            let cond_term = parse_term(lexer)?;
+            lexer.expect_keyword("then")?;
+            let then_term = parse_term(lexer)?;
+            lexer.expect_keyword("else")?;
+            let else_term = parse_term(lexer)?;
+            Term::TmIf(
+                Box::new(cond_term),
+                Box::new(then_term),
+                Box::new(else_term),
+            )
+        } else if let Token::Number(number) = token {
+            parse_numeric(number)?
+        } else {
+            return Err(lexer.error_at("a term", &token.to_string(), span).into());
+        };
+        Ok(term)
+    })
+}
+
+// a term terminated by `;`, together with the input after the `;`
+pub fn parse(input: &str) -> ParseResult<'_, Term> {
+    let mut lexer = Lexer::new(input, &SYMBOLS).ignore_case();
+    let term = parse_term(&mut lexer)?;
+    lexer.expect_symbol(";")?;
+    Ok((lexer.rest(), term))
 }
 
 #[cfg(test)]
 mod tests {
+    use tapl_frontend::visit::size;
+
     use super::*;
 
     #[test]
     fn test_term() {
-        assert_eq!(parse_term("true"), Ok(("", Term::TmTrue)));
-        assert_eq!(parse_term("FALSE"), Ok(("", Term::TmFalse)));
-        assert_eq!(parse_term("0"), Ok(("", Term::TmZero)));
+        assert_eq!(parse("true;"), Ok(("", Term::TmTrue)));
+        assert_eq!(parse("FALSE;"), Ok(("", Term::TmFalse)));
+        assert_eq!(parse("0;"), Ok(("", Term::TmZero)));
+        assert_eq!(size(&parse("if iszero(0) then 1 else 0;").unwrap().1), 5);
         assert_eq!(
-            parse_term("succ(0)"),
+            parse("succ(0);"),
             Ok(("", Term::TmSucc(Box::new(Term::TmZero))))
         );
         assert_eq!(
-            parse_term("succ(2)"),
-            Ok((
-                "",
-                Term::TmSucc(Box::new(Term::TmSucc(Box::new(Term::TmSucc(Box::new(
-                    Term::TmZero
-                ))))))
-            ))
+            parse("succ(2);"),
+            Ok(("", Term::TmSucc(Box::new(Term::TmNumber(2)))))
         );
         assert_eq!(
-            parse_term("if false then true else false"),
+            parse("18446744073709551615;"),
+            Ok(("", Term::TmNumber(u64::MAX)))
+        );
+        assert_eq!(
+            parse("if false then true else false;"),
             Ok((
                 "",
                 Term::TmIf(
@@ -175,5 +420,67 @@ mod tests {
                 )
             ))
         );
+        assert_eq!(parse("0; 0;"), Ok((" 0;", Term::TmZero)));
+    }
+
+    #[test]
+    fn test_boolean_operators() {
+        let term = |input| parse(input).unwrap().1;
+        // the `if` an operator stands for, with its operands as written
+        let expanded = |input| {
+            let term = term(input);
+            let conditional = term.conditional().unwrap();
+            format!(
+                "if {} then {} else {}",
+                conditional.cond_term, conditional.then_term, conditional.else_term
+            )
+        };
+        assert_eq!(expanded("not(true);"), "if true then false else true");
+        assert_eq!(
+            expanded("and(iszero(0), false);"),
+            "if iszero(0) then false else false"
+        );
+        assert_eq!(
+            expanded("or(false, not(true));"),
+            "if false then true else not(true)"
+        );
+        assert_eq!(
+            term("or(false, not(true));").to_string(),
+            "or(false, not(true))"
+        );
+        assert!(parse("and(true);").is_err());
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let message = |input| parse(input).unwrap_err().to_string();
+        // keywords end at word boundaries
+        assert_eq!(message("iffy;"), "1:1: expected a term, found `iffy`");
+        assert_eq!(
+            message("if true then 0\nelsex 1;"),
+            "2:1: expected `else`, found `elsex`"
+        );
+        assert_eq!(message("succ(0;"), "1:7: expected `)`, found `;`");
+        assert_eq!(
+            message("18446744073709551616;"),
+            "number 18446744073709551616 is too large"
+        );
+        for open in ["succ(", "not(", "plus(0, "] {
+            let input = open.repeat(1000);
+            let message = parse(&input).unwrap_err().to_string();
+            assert!(message.contains("expected at most 256 nested terms"));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let (_, term) = parse("if iszero(0) then succ(0) else 0;").unwrap();
+        let json = serde_json::to_string(&term).unwrap();
+        assert_eq!(
+            json,
+            r#"{"TmIf":[{"TmIsZero":"TmZero"},{"TmSucc":"TmZero"},"TmZero"]}"#
+        );
+        assert_eq!(serde_json::from_str::<Term>(&json).unwrap(), term);
     }
 }
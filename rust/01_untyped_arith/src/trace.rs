@@ -0,0 +1,297 @@
+use std::fmt::{self, Formatter};
+
+use crate::parser::{Op, Term};
+
+// The evaluation rules of TAPL figures 3-1 and 3-2, and E-Plus, E-Plus1 and
+// E-Plus2 for each primitive operator: the computation on two numeric values
+// and the congruences on the left and then the right operand
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rule {
+    IfTrue,
+    IfFalse,
+    If,
+    Succ,
+    PredZero,
+    PredSucc,
+    Pred,
+    IsZeroZero,
+    IsZeroSucc,
+    IsZero,
+    Prim(Op),
+    Prim1(Op),
+    Prim2(Op),
+}
+
+fn title(op: Op) -> &'static str {
+    match op {
+        Op::Plus => "Plus",
+        Op::Times => "Times",
+        Op::Leq => "Leq",
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Rule::IfTrue => "E-IfTrue",
+            Rule::IfFalse => "E-IfFalse",
+            Rule::If => "E-If",
+            Rule::Succ => "E-Succ",
+            Rule::PredZero => "E-PredZero",
+            Rule::PredSucc => "E-PredSucc",
+            Rule::Pred => "E-Pred",
+            Rule::IsZeroZero => "E-IsZeroZero",
+            Rule::IsZeroSucc => "E-IsZeroSucc",
+            Rule::IsZero => "E-IsZero",
+            Rule::Prim(op) => return write!(f, "E-{}", title(*op)),
+            Rule::Prim1(op) => return write!(f, "E-{}1", title(*op)),
+            Rule::Prim2(op) => return write!(f, "E-{}2", title(*op)),
+        };
+        write!(f, "{}", name)
+    }
+}
+
+fn is_numeric_val(term: &Term) -> bool {
+    match term {
+        Term::TmZero | Term::TmNumber(_) => true,
+        Term::TmSucc(term) => is_numeric_val(term),
+        _ => false,
+    }
+}
+
+// One reduction step: the rules of its derivation from the outermost
+// congruence rule down to the computation rule that contracts the redex, and
+// the resulting term
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub rules: Vec<Rule>,
+    pub term: Term,
+}
+
+impl Step {
+    fn axiom(rule: Rule, term: Term) -> Self {
+        Step {
+            rules: vec![rule],
+            term,
+        }
+    }
+
+    fn congruence(mut self, rule: Rule, f: impl FnOnce(Box<Term>) -> Term) -> Self {
+        self.rules.insert(0, rule);
+        self.term = f(Box::new(self.term));
+        self
+    }
+}
+
+// t -> t', `None` for values and stuck terms. A boolean operator steps by
+// the rules of the `if` it stands for
+pub fn eval1(term: &Term) -> Option<Step> {
+    if let Some(conditional) = term.conditional() {
+        return match conditional.cond_term {
+            Term::TmTrue => Some(Step::axiom(Rule::IfTrue, conditional.then_term.clone())),
+            Term::TmFalse => Some(Step::axiom(Rule::IfFalse, conditional.else_term.clone())),
+            cond_term => eval1(cond_term)
+                .map(|step| step.congruence(Rule::If, |cond_term| term.with_condition(cond_term))),
+        };
+    }
+    match term {
+        Term::TmSucc(term) => eval1(term).map(|step| step.congruence(Rule::Succ, Term::TmSucc)),
+        Term::TmPred(term) => match term.as_ref() {
+            Term::TmZero => Some(Step::axiom(Rule::PredZero, Term::TmZero)),
+            number if is_numeric_val(number) => {
+                Some(Step::axiom(Rule::PredSucc, number.predecessor()?))
+            }
+            _ => eval1(term).map(|step| step.congruence(Rule::Pred, Term::TmPred)),
+        },
+        Term::TmIsZero(term) => match term.as_ref() {
+            Term::TmZero => Some(Step::axiom(Rule::IsZeroZero, Term::TmTrue)),
+            number if is_numeric_val(number) => Some(Step::axiom(Rule::IsZeroSucc, Term::TmFalse)),
+            _ => eval1(term).map(|step| step.congruence(Rule::IsZero, Term::TmIsZero)),
+        },
+        Term::TmPrim(op, left, right) => match (left.to_numeral(), right.to_numeral()) {
+            (Some(m), Some(n)) => Some(Step::axiom(Rule::Prim(*op), op.apply(m, n)?)),
+            (Some(_), None) => eval1(right).map(|step| {
+                step.congruence(Rule::Prim2(*op), |right| {
+                    Term::TmPrim(*op, left.clone(), right)
+                })
+            }),
+            (None, _) => eval1(left).map(|step| {
+                step.congruence(Rule::Prim1(*op), |left| {
+                    Term::TmPrim(*op, left, right.clone())
+                })
+            }),
+        },
+        _ => None,
+    }
+}
+
+// Why no rule applies to a stuck redex: `succ`, `pred`, `iszero` and the
+// primitive operators need a number and the condition of `if` a boolean, but it is a value of the other
+// kind, or the result of a primitive operator does not fit in a u64
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reason {
+    NotNumeric,
+    NotBoolean,
+    Overflow,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::NotNumeric => write!(f, "expected a number"),
+            Reason::NotBoolean => write!(f, "expected a boolean"),
+            Reason::Overflow => write!(f, "numeric overflow"),
+        }
+    }
+}
+
+// The redex a stuck term (TAPL definition 3.5.15) is stuck on, `path` leads
+// to it from the root by the indices of `Node::children`
+#[derive(Clone, Debug, PartialEq)]
+pub struct StuckAt {
+    pub path: Vec<usize>,
+    pub subterm: Term,
+    pub reason: Reason,
+}
+
+impl fmt::Display for StuckAt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "stuck at {}: {}", self.subterm, self.reason)
+    }
+}
+
+// follows the evaluation contexts of `eval1` down to the redex, `None` if
+// `term` is a value or takes a step
+pub fn stuck_at(term: &Term) -> Option<StuckAt> {
+    let mut path = vec![];
+    let mut subterm = term;
+    let reason = loop {
+        // the redex, or the index of the child the evaluation continues in
+        let (index, term) = match subterm {
+            Term::TmTrue | Term::TmFalse | Term::TmZero | Term::TmNumber(_) => return None,
+            Term::TmIf(cond, _, _)
+            | Term::TmNot(cond)
+            | Term::TmAnd(cond, _)
+            | Term::TmOr(cond, _) => match cond.as_ref() {
+                Term::TmTrue | Term::TmFalse => return None,
+                cond if is_numeric_val(cond) => break Reason::NotBoolean,
+                cond => (0, cond),
+            },
+            Term::TmSucc(term) | Term::TmPred(term) | Term::TmIsZero(term) => match term.as_ref() {
+                term if is_numeric_val(term) => return None,
+                Term::TmTrue | Term::TmFalse => break Reason::NotNumeric,
+                term => (0, term),
+            },
+            Term::TmPrim(op, left, right) if is_numeric_val(left) => match right.as_ref() {
+                right if is_numeric_val(right) => match (left.to_numeral(), right.to_numeral()) {
+                    (Some(m), Some(n)) if op.apply(m, n).is_some() => return None,
+                    _ => break Reason::Overflow,
+                },
+                Term::TmTrue | Term::TmFalse => break Reason::NotNumeric,
+                right => (1, right),
+            },
+            Term::TmPrim(_, left, _) => match left.as_ref() {
+                Term::TmTrue | Term::TmFalse => break Reason::NotNumeric,
+                left => (0, left),
+            },
+        };
+        path.push(index);
+        subterm = term;
+    };
+    Some(StuckAt {
+        path,
+        subterm: subterm.clone(),
+        reason,
+    })
+}
+
+// every step of the reduction of `term` to a normal form
+pub fn eval_trace(term: &Term) -> Vec<Step> {
+    let mut trace: Vec<Step> = vec![];
+    while let Some(step) = eval1(trace.last().map_or(term, |step| &step.term)) {
+        trace.push(step);
+    }
+    trace
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::UntypedArith;
+    use crate::parser::parse;
+    use tapl_testkit::assert_step_trace;
+
+    #[test]
+    fn test_eval_trace() {
+        let (_, term) = parse("if iszero(pred(1)) then succ(pred(0)) else 0;").unwrap();
+        let trace = eval_trace(&term);
+        let rules = trace
+            .iter()
+            .map(|step| {
+                let rules = step.rules.iter().map(Rule::to_string);
+                rules.collect::<Vec<_>>().join(" ")
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rules,
+            [
+                "E-If E-IsZero E-PredSucc",
+                "E-If E-IsZeroZero",
+                "E-IfTrue",
+                "E-Succ E-PredZero"
+            ]
+        );
+        assert_eq!(trace[3].term, parse("succ(0);").unwrap().1);
+
+        // a stuck term has no steps
+        let (_, term) = parse("succ(true);").unwrap();
+        assert_eq!(eval_trace(&term), vec![]);
+        assert_step_trace!(
+            UntypedArith,
+            "pred(if false then 0 else 2);",
+            ["pred(2)", "1"]
+        );
+        let (_, term) = parse("leq(plus(1, 1), times(pred(3), 1));").unwrap();
+        let rules = eval_trace(&term)
+            .iter()
+            .map(|step| step.rules.iter().map(Rule::to_string).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rules,
+            [
+                vec!["E-Leq1", "E-Plus"],
+                vec!["E-Leq2", "E-Times1", "E-PredSucc"],
+                vec!["E-Leq2", "E-Times"],
+                vec!["E-Leq"]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stuck_at() {
+        let stuck = |input: &str| stuck_at(&parse(input).unwrap().1);
+        let (_, subterm) = parse("if 0 then true else false;").unwrap();
+        assert_eq!(
+            stuck("succ(pred(if 0 then true else false));"),
+            Some(StuckAt {
+                path: vec![0, 0],
+                subterm,
+                reason: Reason::NotBoolean,
+            })
+        );
+        let at = stuck("if iszero(false) then 0 else 1;").unwrap();
+        assert_eq!(at.path, [0]);
+        assert_eq!(at.to_string(), "stuck at iszero(false): expected a number");
+        assert_eq!(stuck("succ(succ(0));"), None);
+        assert_eq!(stuck("pred(if true then 0 else false);"), None);
+        let at = stuck("succ(plus(1, leq(0, true)));").unwrap();
+        assert_eq!(at.path, [0, 1]);
+        assert_eq!(at.to_string(), "stuck at leq(0, true): expected a number");
+        let at = stuck("iszero(plus(1, 18446744073709551615));").unwrap();
+        assert_eq!(at.path, [0]);
+        assert_eq!(at.reason, Reason::Overflow);
+        let at = stuck("succ(not(and(2, true)));").unwrap();
+        assert_eq!(at.path, [0, 0]);
+        assert_eq!(at.to_string(), "stuck at and(2, true): expected a boolean");
+    }
+}
@@ -0,0 +1,162 @@
+use crate::parser::{self, Op};
+
+// Arithmetic expressions with the extra normal form `wrong` of TAPL exercise
+// 3.5.16: instead of getting stuck, a term whose subterm has the wrong kind
+// of value steps to `wrong`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Term {
+    Wrong,
+    True,
+    False,
+    Zero,
+    Succ(Box<Term>),
+    Number(u64),
+    Pred(Box<Term>),
+    IsZero(Box<Term>),
+    If(Box<Term>, Box<Term>, Box<Term>),
+    Prim(Op, Box<Term>, Box<Term>),
+}
+
+impl From<&parser::Term> for Term {
+    fn from(term: &parser::Term) -> Self {
+        let boxed = |term: &parser::Term| Box::new(Term::from(term));
+        match term {
+            parser::Term::TmTrue => Term::True,
+            parser::Term::TmFalse => Term::False,
+            parser::Term::TmZero => Term::Zero,
+            parser::Term::TmSucc(term) => Term::Succ(boxed(term)),
+            parser::Term::TmNumber(n) => Term::Number(*n),
+            parser::Term::TmPred(term) => Term::Pred(boxed(term)),
+            parser::Term::TmIsZero(term) => Term::IsZero(boxed(term)),
+            parser::Term::TmIf(..)
+            | parser::Term::TmNot(_)
+            | parser::Term::TmAnd(..)
+            | parser::Term::TmOr(..) => {
+                let conditional = term.conditional().unwrap();
+                Term::If(
+                    boxed(conditional.cond_term),
+                    boxed(conditional.then_term),
+                    boxed(conditional.else_term),
+                )
+            }
+            parser::Term::TmPrim(op, left, right) => Term::Prim(*op, boxed(left), boxed(right)),
+        }
+    }
+}
+
+impl Term {
+    pub fn is_numeric_val(&self) -> bool {
+        self.to_numeral().is_some()
+    }
+
+    fn to_numeral(&self) -> Option<u64> {
+        match self {
+            Term::Zero => Some(0),
+            Term::Number(n) => Some(*n),
+            Term::Succ(term) => term.to_numeral()?.checked_add(1),
+            _ => None,
+        }
+    }
+
+    pub fn is_val(&self) -> bool {
+        matches!(self, Term::True | Term::False) || self.is_numeric_val()
+    }
+
+    // badnat: what cannot be an argument of succ, pred, iszero or a
+    // primitive operator
+    fn is_bad_nat(&self) -> bool {
+        matches!(self, Term::Wrong | Term::True | Term::False)
+    }
+
+    // badbool: what cannot be the condition of an if
+    fn is_bad_bool(&self) -> bool {
+        matches!(self, Term::Wrong) || self.is_numeric_val()
+    }
+}
+
+// t -> t' with the rules of figure 3-2 and E-IfWrong, E-SuccWrong,
+// E-PredWrong and E-IsZeroWrong, and the E-Plus rules of `trace` with
+// E-PlusWrong1 and E-PlusWrong2 for each primitive operator, `None` for
+// values and `wrong`
+pub fn eval1(term: &Term) -> Option<Term> {
+    match term {
+        Term::If(cond, then_term, else_term) => match cond.as_ref() {
+            Term::True => Some(then_term.as_ref().clone()),
+            Term::False => Some(else_term.as_ref().clone()),
+            cond if cond.is_bad_bool() => Some(Term::Wrong),
+            cond => eval1(cond)
+                .map(|cond| Term::If(Box::new(cond), then_term.clone(), else_term.clone())),
+        },
+        Term::Succ(term) if term.is_bad_nat() => Some(Term::Wrong),
+        Term::Succ(term) => eval1(term).map(|term| Term::Succ(Box::new(term))),
+        Term::Pred(term) => match term.as_ref() {
+            Term::Zero => Some(Term::Zero),
+            Term::Succ(number) if number.is_numeric_val() => Some(number.as_ref().clone()),
+            Term::Number(n) => Some(Term::from(&parser::Term::numeral(n - 1))),
+            term if term.is_bad_nat() => Some(Term::Wrong),
+            term => eval1(term).map(|term| Term::Pred(Box::new(term))),
+        },
+        Term::IsZero(term) => match term.as_ref() {
+            Term::Zero => Some(Term::True),
+            Term::Succ(number) if number.is_numeric_val() => Some(Term::False),
+            Term::Number(_) => Some(Term::False),
+            term if term.is_bad_nat() => Some(Term::Wrong),
+            term => eval1(term).map(|term| Term::IsZero(Box::new(term))),
+        },
+        Term::Prim(op, left, right) => match (left.to_numeral(), right.to_numeral()) {
+            (Some(m), Some(n)) => Some(Term::from(&op.apply(m, n)?)),
+            _ if left.is_bad_nat() => Some(Term::Wrong),
+            (Some(_), None) if right.is_bad_nat() => Some(Term::Wrong),
+            (Some(_), None) => {
+                eval1(right).map(|right| Term::Prim(*op, left.clone(), Box::new(right)))
+            }
+            (None, _) => eval1(left).map(|left| Term::Prim(*op, Box::new(left), right.clone())),
+        },
+        _ => None,
+    }
+}
+
+// the normal form of `term`, always a value or `wrong`
+pub fn eval(term: &Term) -> Term {
+    let mut term = term.clone();
+    while let Some(next_term) = eval1(&term) {
+        term = next_term;
+    }
+    term
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{parser::parse, trace::eval_trace};
+
+    fn eval_str(input: &str) -> Term {
+        eval(&Term::from(&parse(input).unwrap().1))
+    }
+
+    #[test]
+    fn test_eval() {
+        assert_eq!(eval_str("pred(succ(0));"), Term::Zero);
+        assert_eq!(eval_str("succ(true);"), Term::Wrong);
+        assert_eq!(eval_str("if 0 then true else false;"), Term::Wrong);
+        assert_eq!(eval_str("pred(succ(iszero(false)));"), Term::Wrong);
+        assert_eq!(eval_str("times(2, leq(0, 0));"), Term::Wrong);
+        // only the branch that is taken is evaluated
+        assert_eq!(eval_str("if true then 0 else succ(false);"), Term::Zero);
+    }
+
+    proptest! {
+        // the exercise's claim: a term gets stuck under the original rules
+        // exactly when it evaluates to `wrong`, otherwise both give the same
+        // value
+        #[test]
+        fn test_agrees_with_stuck_terms(term: parser::Term) {
+            let normal = eval_trace(&term).pop().map_or(term.clone(), |step| step.term);
+            let normal = Term::from(&normal);
+            let expected = if normal.is_val() { normal } else { Term::Wrong };
+            prop_assert_eq!(eval(&Term::from(&term)), expected);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use untyped_lambda::arena::TermArena;
+use untyped_lambda::church::{self, apply};
+use untyped_lambda::closure::{self, Env};
+use untyped_lambda::eval::eval_term;
+use untyped_lambda::nameless::{remove_names, NamelessTerm, NamingContext};
+use untyped_lambda::nbe;
+use untyped_lambda::parse;
+
+// c applies the identity c times to the identity, forcing the numeral to be
+// computed even though evaluation never goes under a lambda
+fn church_times(n: usize, m: usize) -> NamelessTerm {
+    let numeral = |n: usize| {
+        let mut body = "x".to_string();
+        for _ in 0..n {
+            body = format!("s ({})", body);
+        }
+        format!("(lambda s.lambda x.{})", body)
+    };
+    let input = format!(
+        "(lambda c.c (lambda p.p) (lambda z.z)) ((lambda m.lambda n.lambda s.m (n s)) {} {});",
+        numeral(n),
+        numeral(m)
+    );
+    let (_, term) = parse(&input).unwrap();
+    remove_names(&mut NamingContext::default(), &term)
+}
+
+fn bench_evaluators(c: &mut Criterion) {
+    let term = church_times(20, 20);
+
+    let mut group = c.benchmark_group("church 20*20");
+    group.bench_function("substitution", |b| b.iter(|| eval_term(&term)));
+    group.bench_function("closure", |b| {
+        b.iter(|| closure::eval(&Env::default(), &term).unwrap().to_term())
+    });
+    group.bench_function("arena", |b| {
+        b.iter(|| {
+            let mut arena = TermArena::new();
+            let id = arena.from_term(&term);
+            let normal = arena.eval(id);
+            arena.to_term(normal)
+        })
+    });
+    group.finish();
+}
+
+// normal order reduction of `times m n` all the way to the numeral m*n
+fn bench_church_normalize(c: &mut Criterion) {
+    for n in [5, 10] {
+        let term = apply(
+            church::times(),
+            vec![church::numeral(n), church::numeral(n)],
+        );
+        c.bench_function(&format!("normalize church {}*{}", n, n), |b| {
+            b.iter(|| church::normalize(&term, 100_000).unwrap())
+        });
+        c.bench_function(&format!("normalize church {}*{} shared", n, n), |b| {
+            b.iter(|| {
+                let mut arena = TermArena::new();
+                let id = arena.from_term(&term);
+                let normal = arena.normalize(id, 100_000).unwrap();
+                arena.to_term(normal)
+            })
+        });
+    }
+}
+
+// normalizing the numeral 2^n by repeated substitution and by evaluation
+fn bench_church_power(c: &mut Criterion) {
+    for n in [4, 6] {
+        let term = apply(
+            church::power(),
+            vec![church::numeral(2), church::numeral(n)],
+        );
+        let mut group = c.benchmark_group(format!("church 2^{}", n));
+        group.bench_function("normal order", |b| {
+            b.iter(|| church::normalize(&term, 1_000_000).unwrap())
+        });
+        group.bench_function("nbe", |b| b.iter(|| nbe::normalize(&term)));
+        group.finish();
+    }
+}
+
+criterion_group!(
+    benches,
+    bench_evaluators,
+    bench_church_normalize,
+    bench_church_power
+);
+criterion_main!(benches);
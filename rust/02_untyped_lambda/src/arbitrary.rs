@@ -0,0 +1,110 @@
+use std::rc::Rc;
+
+use proptest::prelude::*;
+use tapl_frontend::intern::Symbol;
+
+use crate::{nameless::NamelessTerm, parser::Term};
+
+pub(crate) const NAMES: [&str; 3] = ["x", "y", "z"];
+
+// a closed term, every variable is bound by an enclosing abstraction
+fn named_term(scope: Vec<Symbol>, depth: u32) -> BoxedStrategy<Term> {
+    let abs = {
+        let scope = scope.clone();
+        prop::sample::select(&NAMES[..]).prop_flat_map(move |name| {
+            let mut scope = scope.clone();
+            scope.push(name.into());
+            named_term(scope, depth.saturating_sub(1))
+                .prop_map(move |body| Term::TmAbs(name.into(), Rc::new(body)))
+        })
+    };
+    if scope.is_empty() {
+        return abs.boxed();
+    }
+    let var = prop::sample::select(scope.clone()).prop_map(Term::TmVar);
+    if depth == 0 {
+        return var.boxed();
+    }
+    let app = (
+        named_term(scope.clone(), depth - 1),
+        named_term(scope, depth - 1),
+    )
+        .prop_map(|(left, right)| Term::TmApp(Rc::new(left), Rc::new(right)));
+    prop_oneof![var, abs, app].boxed()
+}
+
+impl Arbitrary for Term {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Term>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        named_term(vec![], 4)
+    }
+}
+
+// a term whose free variables are among `NAMES`, binders may shadow them
+pub(crate) fn open_term() -> BoxedStrategy<Term> {
+    named_term(NAMES.iter().map(|&name| name.into()).collect(), 3)
+}
+
+// a term whose free variables are below `binders`
+fn nameless_term(binders: usize, depth: u32) -> BoxedStrategy<NamelessTerm> {
+    let var = (0..binders).prop_map(NamelessTerm::TmVar);
+    if binders > 0 && depth == 0 {
+        return var.boxed();
+    }
+    let abs = nameless_term(binders + 1, depth.saturating_sub(1))
+        .prop_map(|body| NamelessTerm::TmAbs("x".into(), Rc::new(body)));
+    if binders == 0 {
+        return abs.boxed();
+    }
+    let app = (
+        nameless_term(binders, depth - 1),
+        nameless_term(binders, depth - 1),
+    )
+        .prop_map(|(left, right)| NamelessTerm::TmApp(Rc::new(left), Rc::new(right)));
+    prop_oneof![var, abs, app].boxed()
+}
+
+impl Arbitrary for NamelessTerm {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<NamelessTerm>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (0..3usize)
+            .prop_flat_map(|binders| nameless_term(binders, 4))
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        arena::TermArena,
+        eval::{eval1, shift},
+        parser::parse,
+    };
+
+    proptest! {
+        #[test]
+        fn test_display_parses_back(term: Term) {
+            let source = format!("{};", term);
+            prop_assert_eq!(parse(&source), Ok(("", term)));
+        }
+
+        #[test]
+        fn test_shift_up_then_down(term: NamelessTerm, d in 0..3isize, cutoff in 0..3usize) {
+            prop_assert_eq!(shift(&shift(&term, d, cutoff), -d, cutoff), term);
+        }
+
+        #[test]
+        fn test_arena_steps_like_eval1(term: NamelessTerm) {
+            let mut arena = TermArena::new();
+            let id = arena.from_term(&term);
+            prop_assert_eq!(arena.to_term(id), term.clone());
+            let step = arena.eval1(id).map(|id| arena.to_term(id));
+            prop_assert_eq!(step, eval1(&term).ok());
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+
+use tapl_frontend::intern::Symbol;
+
+use crate::{
+    nameless::{NamelessTerm, NamingContext},
+    parser::{parse_with, Build, ParseResult},
+};
+
+// A node of a `TermArena`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TermId(u32);
+
+// A nameless term whose subterms are nodes of the same arena
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ArenaTerm {
+    Var(usize),
+    Abs(Symbol, TermId),
+    App(TermId, TermId),
+}
+
+// Terms as one vector of nodes: a new node is pushed onto it instead of being
+// allocated on its own. Nodes are hash-consed, a node that is already in the
+// arena is not pushed again but shared by its id, so two ids are equal
+// exactly when their terms are, and a term with many copies of a subterm
+// (the numerals inside `2^n`) keeps one of them. Shifts, substitutions and
+// normal forms are memoized on the ids. Nodes are never freed, the arena
+// grows with every step and goes away as a whole.
+#[derive(Clone, Debug, Default)]
+pub struct TermArena {
+    nodes: Vec<ArenaTerm>,
+    shared: HashMap<ArenaTerm, TermId>,
+    shifts: HashMap<(TermId, isize, usize), TermId>,
+    substitutions: HashMap<(TermId, usize, TermId), TermId>,
+    normal_forms: HashMap<TermId, TermId>,
+}
+
+impl TermArena {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn alloc(&mut self, term: ArenaTerm) -> TermId {
+        if let Some(&id) = self.shared.get(&term) {
+            return id;
+        }
+        self.nodes.push(term);
+        let id = TermId(self.nodes.len() as u32 - 1);
+        self.shared.insert(term, id);
+        id
+    }
+
+    pub fn get(&self, id: TermId) -> ArenaTerm {
+        self.nodes[id.0 as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    // parses straight into the arena, the free variables are added to `ctx`
+    // as `remove_names` does
+    pub fn parse<'a>(
+        &mut self,
+        ctx: &mut NamingContext,
+        input: &'a str,
+    ) -> ParseResult<'a, TermId> {
+        let saved = ctx.clone();
+        let parsed = parse_with(input, &mut Builder { arena: self, ctx });
+        if parsed.is_err() {
+            *ctx = saved;
+        }
+        parsed
+    }
+
+    pub fn from_term(&mut self, term: &NamelessTerm) -> TermId {
+        let node = match term {
+            NamelessTerm::TmVar(index) => ArenaTerm::Var(*index),
+            NamelessTerm::TmAbs(hint, body) => ArenaTerm::Abs(*hint, self.from_term(body)),
+            NamelessTerm::TmApp(left, right) => {
+                ArenaTerm::App(self.from_term(left), self.from_term(right))
+            }
+        };
+        self.alloc(node)
+    }
+
+    pub fn to_term(&self, id: TermId) -> NamelessTerm {
+        match self.get(id) {
+            ArenaTerm::Var(index) => NamelessTerm::TmVar(index),
+            ArenaTerm::Abs(hint, body) => NamelessTerm::TmAbs(hint, self.to_term(body).into()),
+            ArenaTerm::App(left, right) => {
+                NamelessTerm::TmApp(self.to_term(left).into(), self.to_term(right).into())
+            }
+        }
+    }
+
+    // ↑d,c (TAPL 6.2.1)
+    pub fn shift(&mut self, id: TermId, d: isize, cutoff: usize) -> TermId {
+        if let Some(&shifted) = self.shifts.get(&(id, d, cutoff)) {
+            return shifted;
+        }
+        let term = match self.get(id) {
+            ArenaTerm::Var(index) if index >= cutoff => {
+                ArenaTerm::Var((index as isize + d) as usize)
+            }
+            ArenaTerm::Var(index) => ArenaTerm::Var(index),
+            ArenaTerm::Abs(hint, body) => ArenaTerm::Abs(hint, self.shift(body, d, cutoff + 1)),
+            ArenaTerm::App(left, right) => {
+                ArenaTerm::App(self.shift(left, d, cutoff), self.shift(right, d, cutoff))
+            }
+        };
+        let shifted = self.alloc(term);
+        self.shifts.insert((id, d, cutoff), shifted);
+        shifted
+    }
+
+    // [j ↦ s] t (TAPL 6.2.4)
+    pub fn substitute(&mut self, id: TermId, j: usize, s: TermId) -> TermId {
+        if let Some(&substituted) = self.substitutions.get(&(id, j, s)) {
+            return substituted;
+        }
+        let term = match self.get(id) {
+            ArenaTerm::Var(index) if index == j => return s,
+            ArenaTerm::Var(index) => ArenaTerm::Var(index),
+            ArenaTerm::Abs(hint, body) => {
+                let s = self.shift(s, 1, 0);
+                ArenaTerm::Abs(hint, self.substitute(body, j + 1, s))
+            }
+            ArenaTerm::App(left, right) => {
+                ArenaTerm::App(self.substitute(left, j, s), self.substitute(right, j, s))
+            }
+        };
+        let substituted = self.alloc(term);
+        self.substitutions.insert((id, j, s), substituted);
+        substituted
+    }
+
+    // the body of a beta reduction: ↑-1 ([0 ↦ ↑1 s] t)
+    pub fn substitute_top(&mut self, s: TermId, body: TermId) -> TermId {
+        let s = self.shift(s, 1, 0);
+        let body = self.substitute(body, 0, s);
+        self.shift(body, -1, 0)
+    }
+
+    // one call-by-value step (TAPL figure 5-3), `None` for a normal form
+    pub fn eval1(&mut self, id: TermId) -> Option<TermId> {
+        let (left, right) = match self.get(id) {
+            ArenaTerm::App(left, right) => (left, right),
+            _ => return None,
+        };
+        let term = match (self.get(left), self.get(right)) {
+            (ArenaTerm::Abs(_, body), ArenaTerm::Abs(_, _)) => {
+                return Some(self.substitute_top(right, body))
+            }
+            (ArenaTerm::Abs(_, _), _) => ArenaTerm::App(left, self.eval1(right)?),
+            _ => ArenaTerm::App(self.eval1(left)?, right),
+        };
+        Some(self.alloc(term))
+    }
+
+    pub fn eval(&mut self, id: TermId) -> TermId {
+        let mut id = id;
+        while let Some(next) = self.eval1(id) {
+            id = next;
+        }
+        id
+    }
+
+    // the normal form of `id` by normal order reduction (TAPL 5.1), or `None`
+    // if there is none within `max_steps` beta reductions
+    pub fn normalize(&mut self, id: TermId, max_steps: usize) -> Option<TermId> {
+        let mut fuel = max_steps;
+        self.normal_form(id, &mut fuel)
+    }
+
+    fn normal_form(&mut self, id: TermId, fuel: &mut usize) -> Option<TermId> {
+        if let Some(&normal) = self.normal_forms.get(&id) {
+            return Some(normal);
+        }
+        let term = match self.get(id) {
+            ArenaTerm::Var(index) => ArenaTerm::Var(index),
+            ArenaTerm::Abs(hint, body) => ArenaTerm::Abs(hint, self.normal_form(body, fuel)?),
+            ArenaTerm::App(_, _) => {
+                let head = self.weak_head_normal_form(id, fuel)?;
+                match self.get(head) {
+                    ArenaTerm::App(left, right) => ArenaTerm::App(
+                        self.normal_form(left, fuel)?,
+                        self.normal_form(right, fuel)?,
+                    ),
+                    _ => {
+                        let normal = self.normal_form(head, fuel)?;
+                        self.normal_forms.insert(id, normal);
+                        return Some(normal);
+                    }
+                }
+            }
+        };
+        let normal = self.alloc(term);
+        self.normal_forms.insert(id, normal);
+        Some(normal)
+    }
+
+    // reduces the leftmost outermost redex until the term is an abstraction
+    // or has a variable at its head
+    fn weak_head_normal_form(&mut self, id: TermId, fuel: &mut usize) -> Option<TermId> {
+        let (left, right) = match self.get(id) {
+            ArenaTerm::App(left, right) => (left, right),
+            _ => return Some(id),
+        };
+        let left = self.weak_head_normal_form(left, fuel)?;
+        match self.get(left) {
+            ArenaTerm::Abs(_, body) => {
+                *fuel = fuel.checked_sub(1)?;
+                let reduct = self.substitute_top(right, body);
+                self.weak_head_normal_form(reduct, fuel)
+            }
+            _ => Some(self.alloc(ArenaTerm::App(left, right))),
+        }
+    }
+}
+
+struct Builder<'a> {
+    arena: &'a mut TermArena,
+    ctx: &'a mut NamingContext,
+}
+
+impl Build for Builder<'_> {
+    type Term = TermId;
+
+    fn var(&mut self, name: &str) -> TermId {
+        let index = self.ctx.index_or_free(name.into());
+        self.arena.alloc(ArenaTerm::Var(index))
+    }
+
+    fn bind(&mut self, name: &str) {
+        self.ctx.push(name.into());
+    }
+
+    fn abs(&mut self, name: &str, body: TermId) -> TermId {
+        self.ctx.pop();
+        self.arena.alloc(ArenaTerm::Abs(name.into(), body))
+    }
+
+    fn app(&mut self, left: TermId, right: TermId) -> TermId {
+        self.arena.alloc(ArenaTerm::App(left, right))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        church::{self, apply},
+        eval::{eval_term, shift},
+        nameless::remove_names,
+        parser::parse,
+    };
+
+    #[test]
+    fn test_arena() {
+        let input = "(lambda x.lambda y.x) ((lambda z.z) (lambda w.w)) free;";
+        let mut ctx = NamingContext::default();
+        let mut arena = TermArena::new();
+        let (_, id) = arena.parse(&mut ctx, input).unwrap();
+        let (_, term) = parse(input).unwrap();
+        let nameless = remove_names(&mut NamingContext::default(), &term);
+        assert_eq!(arena.to_term(id), nameless);
+        assert_eq!(ctx.name_of(0), Some("free".into()));
+
+        let normal = arena.eval(id);
+        assert_eq!(arena.to_term(normal), eval_term(&nameless));
+        // shifting a closed term changes nothing, so it takes no new node
+        let (_, closed) = arena.parse(&mut ctx, "lambda x.x (lambda y.x y);").unwrap();
+        let len = arena.len();
+        assert_eq!(arena.shift(closed, 1, 0), closed);
+        assert_eq!(arena.len(), len);
+        let shifted = arena.shift(id, 1, 0);
+        assert_eq!(arena.to_term(shifted), shift(&nameless, 1, 0));
+    }
+
+    #[test]
+    fn test_hash_consing() {
+        let mut ctx = NamingContext::default();
+        let mut arena = TermArena::new();
+        let (_, id) = arena.parse(&mut ctx, "(lambda x.x) (lambda x.x);").unwrap();
+        assert_eq!(arena.len(), 3);
+        match arena.get(id) {
+            ArenaTerm::App(left, right) => assert_eq!(left, right),
+            term => panic!("{:?} is not an application", term),
+        }
+
+        let term = apply(
+            church::power(),
+            vec![church::numeral(2), church::numeral(8)],
+        );
+        let id = arena.from_term(&term);
+        let normal = arena.normalize(id, 100_000).unwrap();
+        assert_eq!(church::readback_numeral(&arena.to_term(normal)), Some(256));
+        assert_eq!(arena.normalize(id, 0), Some(normal));
+
+        // the normal form of d (d (... (d y))) for d = lambda x.lambda k.k x x
+        // is a tree of 2^30 copies of y, shared it takes a few nodes per level
+        let mut arena = TermArena::new();
+        let dup = "(lambda x.lambda k.k x x)";
+        let input = format!("{}y{};", format!("{} (", dup).repeat(30), ")".repeat(30));
+        let (_, id) = arena.parse(&mut ctx, &input).unwrap();
+        let normal = arena.normalize(id, 1_000).unwrap();
+        assert!(arena.len() < 1_000, "{} nodes", arena.len());
+        assert_eq!(arena.normalize(normal, 0), Some(normal));
+    }
+}
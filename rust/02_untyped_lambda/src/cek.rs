@@ -0,0 +1,320 @@
+use std::rc::Rc;
+
+use tapl_frontend::dot::quote;
+
+use crate::closure::{Closure, Env, FreeVariable};
+use crate::nameless::NamelessTerm;
+
+// The CEK machine: call-by-value evaluation as a transition system over
+// states made of a control term, an environment and a continuation. Each
+// transition does a constant amount of work, and the states are exposed so
+// that a run can be stepped through and inspected.
+
+// what to do with the value of the term under evaluation
+#[derive(Clone, Debug)]
+pub enum Kont<'a> {
+    Done,
+    // evaluate the argument of an application in its environment
+    Arg(&'a NamelessTerm, Env<'a>, Rc<Kont<'a>>),
+    // call the function value once the argument is a value
+    Call(Closure<'a>, Rc<Kont<'a>>),
+}
+
+#[derive(Clone, Debug)]
+pub enum State<'a> {
+    // evaluate the term in the environment
+    Eval(&'a NamelessTerm, Env<'a>, Rc<Kont<'a>>),
+    // pass a value to the continuation
+    Return(Rc<Kont<'a>>, Closure<'a>),
+}
+
+impl<'a> State<'a> {
+    // the initial state for a closed term
+    pub fn new(term: &'a NamelessTerm) -> Self {
+        State::Eval(term, Env::default(), Rc::new(Kont::Done))
+    }
+
+    // the value of a final state
+    pub fn value(&self) -> Option<&Closure<'a>> {
+        match self {
+            State::Return(kont, value) if matches!(kont.as_ref(), Kont::Done) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+// one transition, `None` once the state is final
+pub fn step<'a>(state: &State<'a>) -> Option<Result<State<'a>, FreeVariable>> {
+    let next_state = match state {
+        State::Eval(NamelessTerm::TmVar(index), env, kont) => match env.lookup(*index) {
+            Some(value) => State::Return(kont.clone(), value.clone()),
+            None => return Some(Err(FreeVariable(*index))),
+        },
+        State::Eval(NamelessTerm::TmAbs(hint, body), env, kont) => {
+            State::Return(kont.clone(), Closure::new(env.clone(), *hint, body))
+        }
+        State::Eval(NamelessTerm::TmApp(left, right), env, kont) => State::Eval(
+            left,
+            env.clone(),
+            Rc::new(Kont::Arg(right, env.clone(), kont.clone())),
+        ),
+        State::Return(kont, value) => match kont.as_ref() {
+            Kont::Done => return None,
+            Kont::Arg(argument, env, kont) => State::Eval(
+                argument,
+                env.clone(),
+                Rc::new(Kont::Call(value.clone(), kont.clone())),
+            ),
+            Kont::Call(function, kont) => State::Eval(
+                function.body(),
+                function.env().bind(value.clone()),
+                kont.clone(),
+            ),
+        },
+    };
+    Some(Ok(next_state))
+}
+
+// every state of the run of a term, starting with the initial one
+pub fn states(term: &NamelessTerm) -> impl Iterator<Item = Result<State<'_>, FreeVariable>> {
+    let mut next_state = Some(Ok(State::new(term)));
+    std::iter::from_fn(move || {
+        let state = next_state.take()?;
+        if let Ok(state) = &state {
+            next_state = step(state);
+        }
+        Some(state)
+    })
+}
+
+// The states of a run as a table with a row per state and aligned columns
+// for the control, the environment and the continuation. Terms are nameless,
+// `λ.0` for the identity, and a value is shown by the term its closure
+// reads back to. An environment lists its values from index 0 and a
+// continuation its frames from the innermost one.
+pub fn render_states(states: &[State<'_>]) -> String {
+    let mut rows = vec![[
+        String::new(),
+        "control".to_string(),
+        "environment".to_string(),
+        "continuation".to_string(),
+    ]];
+    for (i, state) in states.iter().enumerate() {
+        let [control, env, kont] = columns(state);
+        rows.push([i.to_string(), control, env, kont]);
+    }
+    let mut widths = [0; 3];
+    for row in &rows {
+        for (width, column) in widths.iter_mut().zip(row) {
+            *width = (*width).max(column.chars().count());
+        }
+    }
+    let mut out = String::new();
+    for row in &rows {
+        for (width, column) in widths.iter().zip(row) {
+            out += &format!("{}{}  ", column, " ".repeat(width - column.chars().count()));
+        }
+        out += &row[3];
+        out += "\n";
+    }
+    out
+}
+
+// The run as a Graphviz DOT chain with a node per state showing its three
+// parts, and an edge per transition labeled with the rule it takes
+pub fn states_to_dot(states: &[State<'_>]) -> String {
+    let mut out = "digraph cek {\n  node [shape=record];\n".to_string();
+    for (i, state) in states.iter().enumerate() {
+        let label = format!("{{{}}}", columns(state).join("|"));
+        out += &format!("  s{} [label={}];\n", i, quote(&label));
+        if i + 1 < states.len() {
+            let rule = quote(transition(state));
+            out += &format!("  s{} -> s{} [label={}];\n", i, i + 1, rule);
+        }
+    }
+    out + "}\n"
+}
+
+// which transition of `step` leaves `state`
+fn transition(state: &State<'_>) -> &'static str {
+    match state {
+        State::Eval(NamelessTerm::TmVar(_), _, _) => "var",
+        State::Eval(NamelessTerm::TmAbs(_, _), _, _) => "abs",
+        State::Eval(NamelessTerm::TmApp(_, _), _, _) => "app",
+        State::Return(kont, _) => match kont.as_ref() {
+            Kont::Done => "done",
+            Kont::Arg(_, _, _) => "arg",
+            Kont::Call(_, _) => "call",
+        },
+    }
+}
+
+fn columns(state: &State<'_>) -> [String; 3] {
+    match state {
+        State::Eval(term, env, kont) => [
+            format!("eval {}", show_term(term)),
+            show_env(env),
+            show_kont(kont),
+        ],
+        State::Return(kont, value) => [
+            format!("return {}", show_term(&value.to_term())),
+            String::new(),
+            show_kont(kont),
+        ],
+    }
+}
+
+fn show_env(env: &Env<'_>) -> String {
+    let values = (0..env.len())
+        .filter_map(|index| env.lookup(index))
+        .map(|value| show_term(&value.to_term()))
+        .collect::<Vec<_>>();
+    format!("[{}]", values.join(", "))
+}
+
+fn show_kont(kont: &Kont<'_>) -> String {
+    let mut frames = vec![];
+    let mut kont = kont;
+    loop {
+        kont = match kont {
+            Kont::Done => break,
+            Kont::Arg(argument, env, kont) => {
+                frames.push(format!("arg({}, {})", show_term(argument), show_env(env)));
+                kont
+            }
+            Kont::Call(function, kont) => {
+                frames.push(format!("call({})", show_term(&function.to_term())));
+                kont
+            }
+        };
+    }
+    frames.push("done".to_string());
+    frames.join(" :: ")
+}
+
+// with the parentheses of `Term::display`
+fn show_term(term: &NamelessTerm) -> String {
+    match term {
+        NamelessTerm::TmVar(index) => index.to_string(),
+        NamelessTerm::TmAbs(_, body) => format!("λ.{}", show_term(body)),
+        NamelessTerm::TmApp(left, right) => {
+            let left = match left.as_ref() {
+                NamelessTerm::TmAbs(_, _) => format!("({})", show_term(left)),
+                _ => show_term(left),
+            };
+            match right.as_ref() {
+                NamelessTerm::TmVar(_) => format!("{} {}", left, show_term(right)),
+                _ => format!("{} ({})", left, show_term(right)),
+            }
+        }
+    }
+}
+
+pub fn eval(term: &NamelessTerm) -> Result<Closure<'_>, FreeVariable> {
+    let mut state = State::new(term);
+    while let Some(next_state) = step(&state) {
+        state = next_state?;
+    }
+    Ok(state.value().cloned().expect("a final state holds a value"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::closure;
+    use crate::nameless::{remove_names, NamingContext};
+    use crate::parser::parse;
+
+    fn nameless(input: &str) -> NamelessTerm {
+        let (_, term) = parse(input).unwrap();
+        remove_names(&mut NamingContext::default(), &term)
+    }
+
+    #[test]
+    fn test_eval() {
+        for input in [
+            "lambda x.x;",
+            "(lambda x.lambda y.x) (lambda z.z);",
+            "(lambda t.lambda f.f) (lambda a.a) (lambda b.b);",
+            "(lambda f.f (f (lambda x.x))) (lambda y.y);",
+        ] {
+            let term = nameless(input);
+            let expected = closure::eval(&Env::default(), &term).unwrap();
+            assert_eq!(
+                eval(&term).unwrap().to_term(),
+                expected.to_term(),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(
+            eval(&nameless("(lambda x.x) y;")).unwrap_err(),
+            FreeVariable(0)
+        );
+    }
+
+    #[test]
+    fn test_states() {
+        let term = nameless("(lambda x.x) (lambda y.y);");
+        let states = states(&term).collect::<Result<Vec<_>, _>>().unwrap();
+        // eval the application, the function, the argument, then the body
+        let controls = states
+            .iter()
+            .map(|state| match state {
+                State::Eval(term, _, _) => format!("eval {:?}", term),
+                State::Return(_, value) => format!("return {:?}", value.to_term()),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(controls.len(), 7);
+        assert!(controls[0].starts_with("eval TmApp"));
+        assert!(controls[1].starts_with("eval TmAbs(\"x\""));
+        assert!(controls[3].starts_with("eval TmAbs(\"y\""));
+        assert_eq!(controls[5], "eval TmVar(0)");
+        assert_eq!(
+            states[6].value().map(Closure::to_term),
+            Some(nameless("lambda y.y;"))
+        );
+        assert!(states[..6].iter().all(|state| state.value().is_none()));
+    }
+
+    #[test]
+    fn test_render_states() {
+        let term = nameless("(lambda x.x) (lambda y.y);");
+        let states = states(&term).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(
+            render_states(&states),
+            "   control           environment  continuation\n\
+             0  eval (λ.0) (λ.0)  []           done\n\
+             1  eval λ.0          []           arg(λ.0, []) :: done\n\
+             2  return λ.0                     arg(λ.0, []) :: done\n\
+             3  eval λ.0          []           call(λ.0) :: done\n\
+             4  return λ.0                     call(λ.0) :: done\n\
+             5  eval 0            [λ.0]        done\n\
+             6  return λ.0                     done\n"
+        );
+        let abs = nameless("lambda x.(lambda y.y x) x;");
+        let states = super::states(&abs).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(
+            render_states(&states).lines().nth(2),
+            Some("1  return λ.(λ.0 1) 0               done")
+        );
+    }
+
+    #[test]
+    fn test_states_to_dot() {
+        let term = nameless("(lambda x.x) (lambda y.y);");
+        let states = states(&term).collect::<Result<Vec<_>, _>>().unwrap();
+        let dot = states_to_dot(&states);
+        assert!(dot.starts_with(
+            "digraph cek {\n  node [shape=record];\n  \
+             s0 [label=\"{eval (λ.0) (λ.0)|[]|done}\"];\n  \
+             s0 -> s1 [label=\"app\"];\n"
+        ));
+        let edges = dot.lines().filter(|line| line.contains("->"));
+        let rules = edges
+            .map(|line| line.rsplit('"').nth(1).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(rules, ["app", "abs", "arg", "abs", "call", "var"]);
+        assert!(dot.contains("  s6 [label=\"{return λ.0||done}\"];\n}\n"));
+    }
+}
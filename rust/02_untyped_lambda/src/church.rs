@@ -0,0 +1,209 @@
+use std::rc::Rc;
+
+use crate::eval::{shift, substitute_top};
+use crate::nameless::NamelessTerm;
+
+// Church encodings of TAPL 5.2 built directly as nameless terms, and readback
+// of the normal forms that encode booleans and numerals
+
+fn var(index: usize) -> NamelessTerm {
+    NamelessTerm::TmVar(index)
+}
+
+fn abs(hint: &str, body: NamelessTerm) -> NamelessTerm {
+    NamelessTerm::TmAbs(hint.into(), Rc::new(body))
+}
+
+// `f a1 ... an`
+pub fn apply(function: NamelessTerm, arguments: Vec<NamelessTerm>) -> NamelessTerm {
+    arguments.into_iter().fold(function, |term, argument| {
+        NamelessTerm::TmApp(Rc::new(term), Rc::new(argument))
+    })
+}
+
+// `lambda t.lambda f.t` and `lambda t.lambda f.f`
+pub fn boolean(value: bool) -> NamelessTerm {
+    abs("t", abs("f", var(if value { 1 } else { 0 })))
+}
+
+// `lambda b.lambda c.lambda d.b c d`
+pub fn test() -> NamelessTerm {
+    abs("b", abs("c", abs("d", apply(var(2), vec![var(1), var(0)]))))
+}
+
+// `lambda s.lambda z.s (s ... (s z))` with `n` applications of `s`
+pub fn numeral(n: u64) -> NamelessTerm {
+    let body = (0..n).fold(var(0), |term, _| apply(var(1), vec![term]));
+    abs("s", abs("z", body))
+}
+
+// `lambda n.lambda s.lambda z.s (n s z)`
+pub fn succ() -> NamelessTerm {
+    let n_s_z = apply(var(2), vec![var(1), var(0)]);
+    abs("n", abs("s", abs("z", apply(var(1), vec![n_s_z]))))
+}
+
+// `lambda m.lambda n.lambda s.lambda z.m s (n s z)`
+pub fn plus() -> NamelessTerm {
+    let n_s_z = apply(var(2), vec![var(1), var(0)]);
+    let body = apply(var(3), vec![var(1), n_s_z]);
+    abs("m", abs("n", abs("s", abs("z", body))))
+}
+
+// `lambda m.lambda n.lambda s.m (n s)`
+pub fn times() -> NamelessTerm {
+    let n_s = apply(var(1), vec![var(0)]);
+    abs("m", abs("n", abs("s", apply(var(2), vec![n_s]))))
+}
+
+// `lambda m.lambda n.n m`, m to the power n
+pub fn power() -> NamelessTerm {
+    abs("m", abs("n", apply(var(0), vec![var(1)])))
+}
+
+// `lambda f.lambda s.lambda b.b f s`
+pub fn pair() -> NamelessTerm {
+    abs("f", abs("s", abs("b", apply(var(0), vec![var(2), var(1)]))))
+}
+
+// `lambda p.p tru`
+pub fn fst() -> NamelessTerm {
+    abs("p", apply(var(0), vec![boolean(true)]))
+}
+
+// `lambda p.p fls`
+pub fn snd() -> NamelessTerm {
+    abs("p", apply(var(0), vec![boolean(false)]))
+}
+
+// A list is its own right fold: `[t1, ..., tn]` is
+// `lambda c.lambda n.c t1 (c t2 ... (c tn n))`
+pub fn list(items: Vec<NamelessTerm>) -> NamelessTerm {
+    let body = items.into_iter().rev().fold(var(0), |tail, item| {
+        apply(var(1), vec![shift(&item, 2, 0), tail])
+    });
+    abs("c", abs("n", body))
+}
+
+// `lambda h.lambda t.lambda c.lambda n.c h (t c n)`
+pub fn cons() -> NamelessTerm {
+    let t_c_n = apply(var(2), vec![var(1), var(0)]);
+    let body = apply(var(1), vec![var(3), t_c_n]);
+    abs("h", abs("t", abs("c", abs("n", body))))
+}
+
+// one step of normal order reduction (TAPL 5.1), which also reduces under
+// abstractions so that arithmetic results reach their normal form
+pub fn eval1_normal(term: &NamelessTerm) -> Option<NamelessTerm> {
+    match term {
+        NamelessTerm::TmVar(_) => None,
+        NamelessTerm::TmAbs(hint, body) => {
+            eval1_normal(body).map(|body| NamelessTerm::TmAbs(*hint, Rc::new(body)))
+        }
+        NamelessTerm::TmApp(left, right) => match left.as_ref() {
+            NamelessTerm::TmAbs(_, body) => Some(substitute_top(right, body)),
+            _ => match eval1_normal(left) {
+                Some(left) => Some(NamelessTerm::TmApp(Rc::new(left), right.clone())),
+                None => eval1_normal(right)
+                    .map(|right| NamelessTerm::TmApp(left.clone(), Rc::new(right))),
+            },
+        },
+    }
+}
+
+// the normal form of `term`, or `None` if there is none within `max_steps`
+pub fn normalize(term: &NamelessTerm, max_steps: usize) -> Option<NamelessTerm> {
+    let mut term = term.clone();
+    for _ in 0..max_steps {
+        match eval1_normal(&term) {
+            Some(next_term) => term = next_term,
+            None => return Some(term),
+        }
+    }
+    None
+}
+
+// recognizes `lambda s.lambda z.s (s ... (s z))`
+pub fn readback_numeral(term: &NamelessTerm) -> Option<u64> {
+    let mut body = match term {
+        NamelessTerm::TmAbs(_, body) => match body.as_ref() {
+            NamelessTerm::TmAbs(_, body) => body.as_ref(),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let mut n = 0;
+    loop {
+        match body {
+            NamelessTerm::TmVar(0) => return Some(n),
+            NamelessTerm::TmApp(left, right) if **left == var(1) => {
+                n += 1;
+                body = right;
+            }
+            _ => return None,
+        }
+    }
+}
+
+// recognizes `lambda t.lambda f.t` and `lambda t.lambda f.f`
+pub fn readback_boolean(term: &NamelessTerm) -> Option<bool> {
+    if *term == boolean(true) {
+        Some(true)
+    } else if *term == boolean(false) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nameless::{remove_names, NamingContext};
+    use crate::parser::parse;
+
+    const MAX_STEPS: usize = 10_000;
+
+    fn run(term: NamelessTerm) -> NamelessTerm {
+        normalize(&term, MAX_STEPS).unwrap()
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let plus_2_3 = apply(plus(), vec![numeral(2), numeral(3)]);
+        assert_eq!(readback_numeral(&run(plus_2_3.clone())), Some(5));
+        let times_2_3 = apply(times(), vec![numeral(2), numeral(3)]);
+        assert_eq!(readback_numeral(&run(times_2_3)), Some(6));
+        let succ_5 = apply(succ(), vec![plus_2_3]);
+        assert_eq!(readback_numeral(&run(succ_5)), Some(6));
+        let power_2_3 = apply(power(), vec![numeral(2), numeral(3)]);
+        assert_eq!(readback_numeral(&run(power_2_3)), Some(8));
+
+        let (_, parsed) = parse("lambda s.lambda z.s (s z);").unwrap();
+        let parsed = remove_names(&mut NamingContext::default(), &parsed);
+        assert_eq!(readback_numeral(&parsed), Some(2));
+        assert_eq!(readback_numeral(&boolean(true)), None);
+        assert_eq!(readback_numeral(&apply(succ(), vec![numeral(0)])), None);
+    }
+
+    #[test]
+    fn test_data() {
+        let tested = apply(test(), vec![boolean(false), numeral(1), numeral(2)]);
+        assert_eq!(readback_numeral(&run(tested)), Some(2));
+        let p = apply(pair(), vec![numeral(1), boolean(true)]);
+        assert_eq!(
+            readback_numeral(&run(apply(fst(), vec![p.clone()]))),
+            Some(1)
+        );
+        assert_eq!(readback_boolean(&run(apply(snd(), vec![p]))), Some(true));
+
+        // summing a list is folding it with plus
+        let items = list(vec![numeral(1), numeral(2)]);
+        let items = apply(cons(), vec![numeral(3), items]);
+        let sum = apply(items, vec![plus(), numeral(0)]);
+        assert_eq!(readback_numeral(&run(sum)), Some(6));
+
+        let omega = abs("x", apply(var(0), vec![var(0)]));
+        assert_eq!(normalize(&apply(omega.clone(), vec![omega]), 100), None);
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    fmt::{self, Formatter},
+    rc::Rc,
+};
+
+use tapl_frontend::intern::Symbol;
+
+use crate::nameless::NamelessTerm;
+
+// Big-step evaluation with environments and closures: a variable is looked
+// up in the environment when it is reached instead of being substituted
+// into the body at every beta reduction.
+
+// Abstraction body together with the environment it was created in, the
+// only kind of value in the pure calculus
+#[derive(Clone, Debug)]
+pub struct Closure<'a> {
+    env: Env<'a>,
+    hint: Symbol,
+    body: &'a NamelessTerm,
+}
+
+// Persistent list of values indexed by de Bruijn index, so that closures
+// can share the tail they were created with
+#[derive(Clone, Debug, Default)]
+pub struct Env<'a>(Option<Rc<(Closure<'a>, Env<'a>)>>);
+
+impl<'a> Env<'a> {
+    pub fn bind(&self, value: Closure<'a>) -> Self {
+        Env(Some(Rc::new((value, self.clone()))))
+    }
+
+    pub fn lookup(&self, index: usize) -> Option<&Closure<'a>> {
+        let mut env = self;
+        let mut index = index;
+        while let Some(node) = &env.0 {
+            if index == 0 {
+                return Some(&node.0);
+            }
+            index -= 1;
+            env = &node.1;
+        }
+        None
+    }
+
+    pub fn len(&self) -> usize {
+        let mut env = self;
+        let mut len = 0;
+        while let Some(node) = &env.0 {
+            len += 1;
+            env = &node.1;
+        }
+        len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+}
+
+// evaluation reached a variable that is bound neither in the term nor in the environment
+#[derive(Clone, Debug, PartialEq)]
+pub struct FreeVariable(pub usize);
+
+impl fmt::Display for FreeVariable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "free variable with index {}", self.0)
+    }
+}
+
+impl std::error::Error for FreeVariable {}
+
+pub fn eval<'a>(env: &Env<'a>, term: &'a NamelessTerm) -> Result<Closure<'a>, FreeVariable> {
+    match term {
+        NamelessTerm::TmVar(index) => env.lookup(*index).cloned().ok_or(FreeVariable(*index)),
+        NamelessTerm::TmAbs(hint, body) => Ok(Closure {
+            env: env.clone(),
+            hint: *hint,
+            body,
+        }),
+        NamelessTerm::TmApp(left, right) => {
+            let function = eval(env, left.as_ref())?;
+            let argument = eval(env, right.as_ref())?;
+            eval(&function.env.bind(argument), function.body)
+        }
+    }
+}
+
+impl<'a> Closure<'a> {
+    pub fn new(env: Env<'a>, hint: Symbol, body: &'a NamelessTerm) -> Self {
+        Closure { env, hint, body }
+    }
+
+    pub fn env(&self) -> &Env<'a> {
+        &self.env
+    }
+
+    pub fn body(&self) -> &'a NamelessTerm {
+        self.body
+    }
+
+    // read the closure back into a term by substituting its environment into the body
+    pub fn to_term(&self) -> NamelessTerm {
+        NamelessTerm::TmAbs(self.hint, Rc::new(close(self.body, &self.env, 1)))
+    }
+}
+
+fn close(term: &NamelessTerm, env: &Env<'_>, depth: usize) -> NamelessTerm {
+    match term {
+        NamelessTerm::TmVar(index) if *index < depth => NamelessTerm::TmVar(*index),
+        // values in the environment are closed, so they need no shifting
+        NamelessTerm::TmVar(index) => match env.lookup(index - depth) {
+            Some(value) => value.to_term(),
+            None => NamelessTerm::TmVar(index - env.len()),
+        },
+        NamelessTerm::TmAbs(hint, body) => {
+            NamelessTerm::TmAbs(*hint, Rc::new(close(body, env, depth + 1)))
+        }
+        NamelessTerm::TmApp(left, right) => NamelessTerm::TmApp(
+            Rc::new(close(left, env, depth)),
+            Rc::new(close(right, env, depth)),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::eval1;
+    use crate::nameless::{remove_names, NamingContext};
+    use crate::parser::parse;
+
+    fn nameless(input: &str) -> NamelessTerm {
+        let (_, term) = parse(input).unwrap();
+        remove_names(&mut NamingContext::default(), &term)
+    }
+
+    // every closed term with `size` nodes under `depth` binders
+    fn closed_terms(size: usize, depth: usize) -> Vec<NamelessTerm> {
+        let mut terms = vec![];
+        if size == 1 {
+            terms.extend((0..depth).map(NamelessTerm::TmVar));
+        }
+        if size >= 2 {
+            for body in closed_terms(size - 1, depth + 1) {
+                terms.push(NamelessTerm::TmAbs("x".into(), Rc::new(body)));
+            }
+        }
+        for left_size in 1..size.saturating_sub(1) {
+            let right_size = size - 1 - left_size;
+            for left in closed_terms(left_size, depth) {
+                for right in closed_terms(right_size, depth) {
+                    terms.push(NamelessTerm::TmApp(Rc::new(left.clone()), Rc::new(right)));
+                }
+            }
+        }
+        terms
+    }
+
+    #[test]
+    fn test_eval() {
+        let term = nameless("(lambda x.lambda y.x) (lambda z.z);");
+        let value = eval(&Env::default(), &term).unwrap();
+        assert_eq!(value.to_term(), nameless("lambda y.lambda z.z;"));
+
+        let term = nameless("(lambda t.lambda f.t) (lambda a.a) (lambda b.b);");
+        let value = eval(&Env::default(), &term).unwrap();
+        assert_eq!(value.to_term(), nameless("lambda a.a;"));
+
+        let term = nameless("(lambda x.x) y;");
+        assert_eq!(eval(&Env::default(), &term).unwrap_err(), FreeVariable(0));
+    }
+
+    // the closure evaluator must agree with the substitution evaluator on
+    // every closed term that normalizes
+    #[test]
+    fn test_agrees_with_substitution() {
+        for size in 1..=8 {
+            for term in closed_terms(size, 0) {
+                let mut normal_form = Some(term.clone());
+                let mut steps = 0;
+                while let Some(Ok(next_term)) = normal_form.as_ref().map(eval1) {
+                    steps += 1;
+                    normal_form = if steps > 100 { None } else { Some(next_term) };
+                }
+                if let Some(normal_form) = normal_form {
+                    let value = eval(&Env::default(), &term).unwrap();
+                    assert_eq!(value.to_term(), normal_form, "{:?}", term);
+                }
+            }
+        }
+    }
+}
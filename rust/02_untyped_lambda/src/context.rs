@@ -0,0 +1,143 @@
+use std::rc::Rc;
+
+use tapl_frontend::intern::Symbol;
+
+use crate::eval::substitute_top;
+use crate::nameless::NamelessTerm;
+
+// The evaluation-context presentation of call-by-value (TAPL 5.3 and
+// Felleisen): a term that can step is E[r] for exactly one context E and
+// redex r, it steps to E[r'] where r' is the contractum of r.
+//
+//   E ::= [] | E t | v E
+
+// One layer of an evaluation context around the hole
+#[derive(Clone, Debug, PartialEq)]
+pub enum Frame {
+    // `[] t`
+    AppLeft(NamelessTerm),
+    // `v []`
+    AppRight(NamelessTerm),
+}
+
+// The frames from the outermost one in
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Context(pub Vec<Frame>);
+
+impl Context {
+    // E[t]
+    pub fn plug(&self, term: NamelessTerm) -> NamelessTerm {
+        self.0.iter().rev().fold(term, |term, frame| match frame {
+            Frame::AppLeft(right) => NamelessTerm::TmApp(Rc::new(term), Rc::new(right.clone())),
+            Frame::AppRight(left) => NamelessTerm::TmApp(Rc::new(left.clone()), Rc::new(term)),
+        })
+    }
+}
+
+// `(lambda x.body) argument` with `argument` a value
+#[derive(Clone, Debug, PartialEq)]
+pub struct Redex {
+    pub hint: Symbol,
+    pub body: NamelessTerm,
+    pub argument: NamelessTerm,
+}
+
+impl Redex {
+    // E-AppAbs
+    pub fn contract(&self) -> NamelessTerm {
+        substitute_top(&self.argument, &self.body)
+    }
+
+    pub fn to_term(&self) -> NamelessTerm {
+        let abs = NamelessTerm::TmAbs(self.hint, Rc::new(self.body.clone()));
+        NamelessTerm::TmApp(Rc::new(abs), Rc::new(self.argument.clone()))
+    }
+}
+
+fn is_val(term: &NamelessTerm) -> bool {
+    matches!(term, NamelessTerm::TmAbs(_, _))
+}
+
+// splits `term` into E[r], `None` for values and stuck terms
+pub fn decompose(term: &NamelessTerm) -> Option<(Context, Redex)> {
+    let mut frames = vec![];
+    let mut term = term;
+    loop {
+        match term {
+            NamelessTerm::TmApp(left, right) => match left.as_ref() {
+                NamelessTerm::TmAbs(hint, body) if is_val(right) => {
+                    let redex = Redex {
+                        hint: *hint,
+                        body: body.as_ref().clone(),
+                        argument: right.as_ref().clone(),
+                    };
+                    return Some((Context(frames), redex));
+                }
+                _ if is_val(left) => {
+                    frames.push(Frame::AppRight(left.as_ref().clone()));
+                    term = right;
+                }
+                _ => {
+                    frames.push(Frame::AppLeft(right.as_ref().clone()));
+                    term = left;
+                }
+            },
+            _ => return None,
+        }
+    }
+}
+
+// E[r] -> E[r']
+pub fn eval1(term: &NamelessTerm) -> Option<NamelessTerm> {
+    let (context, redex) = decompose(term)?;
+    Some(context.plug(redex.contract()))
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{
+        nameless::{remove_names, NamingContext},
+        parser::parse,
+        trace,
+    };
+
+    fn nameless(input: &str) -> NamelessTerm {
+        let (_, term) = parse(input).unwrap();
+        remove_names(&mut NamingContext::default(), &term)
+    }
+
+    #[test]
+    fn test_decompose() {
+        let term = nameless("(lambda x.x) ((lambda y.y) (lambda z.z)) (lambda w.w);");
+        let (context, redex) = decompose(&term).unwrap();
+        assert_eq!(
+            context,
+            Context(vec![
+                Frame::AppLeft(nameless("lambda w.w;")),
+                Frame::AppRight(nameless("lambda x.x;")),
+            ])
+        );
+        assert_eq!(redex.to_term(), nameless("(lambda y.y) (lambda z.z);"));
+        assert_eq!(context.plug(redex.to_term()), term);
+        assert_eq!(
+            eval1(&term),
+            Some(nameless("(lambda x.x) (lambda z.z) (lambda w.w);"))
+        );
+        assert_eq!(decompose(&nameless("lambda x.(lambda y.y) x;")), None);
+    }
+
+    proptest! {
+        // the evaluation-context semantics is the one of figure 5-3
+        #[test]
+        fn test_agrees_with_eval1(term: NamelessTerm) {
+            let step = trace::eval1(&term).ok().map(|step| step.term);
+            prop_assert_eq!(eval1(&term), step);
+            if let Some((context, redex)) = decompose(&term) {
+                prop_assert_eq!(context.plug(redex.to_term()), term);
+            }
+        }
+    }
+}
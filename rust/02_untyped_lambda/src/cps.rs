@@ -0,0 +1,152 @@
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+use tapl_frontend::intern::Symbol;
+
+use crate::{named::fresh_name, parser::Term};
+
+// Call-by-value continuation-passing style (Plotkin 1975, as in Fischer):
+//
+//   [x]     = lambda k.k x
+//   [λx.t]  = lambda k.k (λx.[t])
+//   [t1 t2] = lambda k.[t1] (lambda m.[t2] (lambda n.m n k))
+//
+// `k`, `m` and `n` are renamed apart from every name in the source term, so
+// they can capture nothing.
+
+struct Names {
+    k: Symbol,
+    m: Symbol,
+    n: Symbol,
+}
+
+fn var(name: impl Into<Symbol>) -> Term {
+    Term::TmVar(name.into())
+}
+
+fn abs(name: impl Into<Symbol>, body: Term) -> Term {
+    Term::TmAbs(name.into(), Rc::new(body))
+}
+
+fn app(left: Term, right: Term) -> Term {
+    Term::TmApp(Rc::new(left), Rc::new(right))
+}
+
+fn collect_names(term: &Term, names: &mut BTreeSet<Symbol>) {
+    match term {
+        Term::TmVar(name) => {
+            names.insert(*name);
+        }
+        Term::TmAbs(name, body) => {
+            names.insert(*name);
+            collect_names(body, names);
+        }
+        Term::TmApp(left, right) => {
+            collect_names(left, names);
+            collect_names(right, names);
+        }
+    }
+}
+
+// Ψ, the translation of a term that is a value or a variable
+fn value(term: &Term, names: &Names) -> Term {
+    match term {
+        Term::TmAbs(name, body) => abs(*name, transform(body, names)),
+        _ => term.clone(),
+    }
+}
+
+fn transform(term: &Term, names: &Names) -> Term {
+    let &Names { k, m, n } = names;
+    match term {
+        Term::TmVar(_) | Term::TmAbs(_, _) => abs(k, app(var(k), value(term, names))),
+        Term::TmApp(left, right) => {
+            let call = abs(n, app(app(var(m), var(n)), var(k)));
+            let right = abs(m, app(transform(right, names), call));
+            abs(k, app(transform(left, names), right))
+        }
+    }
+}
+
+// `k`, `m` and `n` renamed apart from every name in `term`
+fn names_for(term: &Term) -> Names {
+    let mut avoid = BTreeSet::new();
+    collect_names(term, &mut avoid);
+    let mut fresh = |hint: &str| {
+        let name = fresh_name(hint.into(), &avoid);
+        avoid.insert(name);
+        name
+    };
+    Names {
+        k: fresh("k"),
+        m: fresh("m"),
+        n: fresh("n"),
+    }
+}
+
+pub fn cps_transform(term: &Term) -> Term {
+    transform(term, &names_for(term))
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{
+        eval::eval_with_fuel,
+        named::alpha_equivalent,
+        nameless::{remove_names, restore_names, NamingContext},
+        parser::parse,
+    };
+
+    fn term(input: &str) -> Term {
+        parse(input).unwrap().1
+    }
+
+    fn run(term: &Term, max_steps: usize) -> Option<Term> {
+        let mut ctx = NamingContext::default();
+        let nameless = remove_names(&mut ctx, term);
+        let value = eval_with_fuel(&nameless, max_steps).value()?;
+        Some(restore_names(&mut ctx, &value))
+    }
+
+    // the CPS'd program run with the identity continuation
+    fn run_cps(term: &Term, max_steps: usize) -> Option<Term> {
+        run(&app(cps_transform(term), term_id()), max_steps)
+    }
+
+    fn term_id() -> Term {
+        abs("x", var("x"))
+    }
+
+    #[test]
+    fn test_cps_transform() {
+        assert_eq!(cps_transform(&term("x;")), term("lambda k.k x;"));
+        assert_eq!(
+            cps_transform(&term("lambda k.k;")),
+            abs(
+                "k'",
+                app(var("k'"), abs("k", abs("k'", app(var("k'"), var("k")))))
+            )
+        );
+        let program = term("(lambda t.lambda f.t) (lambda a.a) (lambda b.b);");
+        assert_eq!(run(&program, 100), Some(term("lambda a.a;")));
+        let identity = term("lambda a.lambda k.k a;");
+        let value = run_cps(&program, 1_000).unwrap();
+        assert!(alpha_equivalent(&value, &identity), "{}", value);
+    }
+
+    proptest! {
+        // direct evaluation ends at a value v, the CPS'd program ends at Ψ(v)
+        #[test]
+        fn test_agrees_with_direct_evaluation(term: Term) {
+            if let Some(direct) = run(&term, 100) {
+                let cps = run_cps(&term, 10_000);
+                prop_assert!(cps.is_some(), "{} ran out of fuel", term);
+                let expected = value(&direct, &names_for(&direct));
+                prop_assert!(alpha_equivalent(cps.as_ref().unwrap(), &expected), "{:?} and {}", cps, expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::rc::Rc;
+
+use tapl_frontend::outcome::{evaluate, Outcome};
+
+use crate::nameless::{remove_names, restore_names, NamelessTerm, NamingContext};
+use crate::parser::{parse, ParseError, Term};
+use crate::trace;
+
+// parsing is the only way `eval` can fail, every term has a normal form or
+// diverges
+pub type Result<T, E = ParseError> = std::result::Result<T, E>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NoRuleApplies;
+
+impl Display for NoRuleApplies {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no rule applies")
+    }
+}
+
+impl std::error::Error for NoRuleApplies {}
+
+// ↑d,c: shift the variables at or above cutoff `c` by `d` (TAPL 6.2.1)
+pub fn shift(term: &NamelessTerm, d: isize, cutoff: usize) -> NamelessTerm {
+    match term {
+        NamelessTerm::TmVar(index) if *index >= cutoff => {
+            NamelessTerm::TmVar((*index as isize + d) as usize)
+        }
+        NamelessTerm::TmVar(index) => NamelessTerm::TmVar(*index),
+        NamelessTerm::TmAbs(hint, body) => {
+            NamelessTerm::TmAbs(*hint, Rc::new(shift(body, d, cutoff + 1)))
+        }
+        NamelessTerm::TmApp(left, right) => NamelessTerm::TmApp(
+            Rc::new(shift(left, d, cutoff)),
+            Rc::new(shift(right, d, cutoff)),
+        ),
+    }
+}
+
+// [j ↦ s] t (TAPL 6.2.4)
+pub fn substitute(term: &NamelessTerm, j: usize, s: &NamelessTerm) -> NamelessTerm {
+    match term {
+        NamelessTerm::TmVar(index) if *index == j => s.clone(),
+        NamelessTerm::TmVar(index) => NamelessTerm::TmVar(*index),
+        NamelessTerm::TmAbs(hint, body) => {
+            NamelessTerm::TmAbs(*hint, Rc::new(substitute(body, j + 1, &shift(s, 1, 0))))
+        }
+        NamelessTerm::TmApp(left, right) => NamelessTerm::TmApp(
+            Rc::new(substitute(left, j, s)),
+            Rc::new(substitute(right, j, s)),
+        ),
+    }
+}
+
+// the body of a beta reduction: ↑-1 ([0 ↦ ↑1 s] t)
+pub fn substitute_top(s: &NamelessTerm, body: &NamelessTerm) -> NamelessTerm {
+    shift(&substitute(body, 0, &shift(s, 1, 0)), -1, 0)
+}
+
+// one step of call-by-value evaluation (TAPL figure 5-3), `trace::eval1`
+// also tells which rules it used
+pub fn eval1(term: &NamelessTerm) -> Result<NamelessTerm, NoRuleApplies> {
+    trace::eval1(term).map(|step| step.term)
+}
+
+// every step is a `trace` event of the `eval` span
+pub fn eval_term(term: &NamelessTerm) -> NamelessTerm {
+    let _span = tracing::debug_span!("eval").entered();
+    let mut term = term.clone();
+    while let Ok(next_term) = eval1(&term) {
+        tracing::trace!(term = ?next_term, "step");
+        term = next_term;
+    }
+    term
+}
+
+// like `eval_term`, but gives up after `max_steps` reductions, so it also
+// returns for a diverging term such as omega. The values are the
+// abstractions, an open term can get stuck on a free variable.
+pub fn eval_with_fuel(
+    term: &NamelessTerm,
+    max_steps: usize,
+) -> Outcome<NamelessTerm, NamelessTerm> {
+    let value = |term: &NamelessTerm| match term {
+        NamelessTerm::TmAbs(_, _) => Some(term.clone()),
+        _ => None,
+    };
+    let _span = tracing::debug_span!("eval", max_steps).entered();
+    let step = |term: &NamelessTerm| {
+        let next_term = eval1(term).ok()?;
+        tracing::trace!(term = ?next_term, "step");
+        Some(next_term)
+    };
+    evaluate(term, max_steps, step, value)
+}
+
+pub fn eval(input: &str) -> Result<Term> {
+    let (rest, term) = parse(input)?;
+    if !rest.is_empty() {
+        return Err(ParseError::TrailingInput(rest.to_string()));
+    }
+    let mut ctx = NamingContext::default();
+    let term = remove_names(&mut ctx, &term);
+
+    Ok(restore_names(&mut ctx, &eval_term(&term)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::UntypedLambda;
+    use tapl_testkit::{assert_evals_to, assert_step_trace, Language};
+
+    fn var(name: &str) -> Rc<Term> {
+        Rc::new(Term::TmVar(name.into()))
+    }
+
+    #[test]
+    fn test_eval() -> Result<()> {
+        assert_eq!(eval("lambda x.x;")?, Term::TmAbs("x".into(), var("x")));
+        assert_eq!(
+            eval("(lambda x.x) (lambda y.y);")?,
+            Term::TmAbs("y".into(), var("y"))
+        );
+        // the argument is substituted under the inner binder and shifted
+        assert_eq!(
+            eval("(lambda x.lambda y.x) (lambda z.z);")?,
+            Term::TmAbs("y".into(), Rc::new(Term::TmAbs("z".into(), var("z"))))
+        );
+        // a free variable is not a value, so this is stuck right away
+        let (_, stuck) = parse("z ((lambda x.x) (lambda y.y));")?;
+        assert_eq!(eval("z ((lambda x.x) (lambda y.y));")?, stuck);
+        assert_eq!(
+            eval("(lambda t.lambda f.t) (lambda a.a) (lambda b.b);")?,
+            Term::TmAbs("a".into(), var("a"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_eval1() {
+        // call by value: the argument is reduced before the outer redex
+        assert_step_trace!(
+            UntypedLambda,
+            "(lambda x.x) ((lambda y.y) (lambda z.z));",
+            ["(lambda x.x) (lambda z.z)", "lambda z.z"]
+        );
+        assert_step_trace!(UntypedLambda, "lambda x.(lambda y.y) x;", []);
+        assert_evals_to!(
+            UntypedLambda,
+            "(lambda t.lambda f.f) (lambda a.a) (lambda b.b);",
+            "lambda b.b"
+        );
+    }
+
+    #[test]
+    fn test_eval_with_fuel() {
+        let term = |input: &str| UntypedLambda::parse(input).unwrap();
+        let omega = term("(lambda x.x x) (lambda x.x x);");
+        assert_eq!(
+            eval_with_fuel(&omega, 100),
+            Outcome::OutOfFuel {
+                steps: 100,
+                term: omega.clone()
+            }
+        );
+        // exactly enough fuel to reach the normal form
+        let identity = term("(lambda x.x) ((lambda y.y) (lambda z.z));");
+        assert_eq!(
+            eval_with_fuel(&identity, 2),
+            Outcome::Value(term("lambda z.z;"))
+        );
+        assert!(eval_with_fuel(&identity, 1).value().is_none());
+        let stuck = term("z (lambda x.x);");
+        assert_eq!(eval_with_fuel(&stuck, 10), Outcome::Stuck(stuck));
+    }
+}
@@ -0,0 +1,28 @@
+use tapl_testkit::{Failure, Language};
+
+use crate::eval::{eval1, eval_term};
+use crate::nameless::{remove_names, NamelessTerm, NamingContext};
+use crate::parser::parse;
+
+pub struct UntypedLambda;
+
+impl Language for UntypedLambda {
+    type Term = NamelessTerm;
+    type Value = NamelessTerm;
+
+    fn parse(input: &str) -> Result<NamelessTerm, Failure> {
+        match parse(input) {
+            Ok(("", term)) => Ok(remove_names(&mut NamingContext::default(), &term)),
+            Ok((rest, _)) => Err(Failure::Parse(format!("unexpected `{}`", rest))),
+            Err(error) => Err(Failure::Parse(error.to_string())),
+        }
+    }
+
+    fn eval(term: &NamelessTerm) -> Result<NamelessTerm, Failure> {
+        Ok(eval_term(term))
+    }
+
+    fn step(term: &NamelessTerm) -> Option<NamelessTerm> {
+        eval1(term).ok()
+    }
+}
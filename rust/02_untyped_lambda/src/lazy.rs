@@ -0,0 +1,181 @@
+use std::{cell::RefCell, rc::Rc};
+
+use tapl_frontend::intern::Symbol;
+
+use crate::{closure::FreeVariable, nameless::NamelessTerm};
+
+// Call-by-need evaluation: an argument is passed as a thunk that is shared by
+// every copy of the variable bound to it, and the first time it is forced its
+// value replaces the suspended term, so each argument is evaluated at most once.
+
+#[derive(Clone, Debug)]
+enum Suspension<'a> {
+    Delayed(Env<'a>, &'a NamelessTerm),
+    Forced(Value<'a>),
+}
+
+type Thunk<'a> = Rc<RefCell<Suspension<'a>>>;
+
+// Abstraction body together with the environment of thunks it was created in
+#[derive(Clone, Debug)]
+pub struct Value<'a> {
+    env: Env<'a>,
+    hint: Symbol,
+    body: &'a NamelessTerm,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Env<'a>(Option<Rc<(Thunk<'a>, Env<'a>)>>);
+
+impl<'a> Env<'a> {
+    fn bind(&self, thunk: Thunk<'a>) -> Self {
+        Env(Some(Rc::new((thunk, self.clone()))))
+    }
+
+    fn lookup(&self, index: usize) -> Option<&Thunk<'a>> {
+        let mut env = self;
+        let mut index = index;
+        while let Some(node) = &env.0 {
+            if index == 0 {
+                return Some(&node.0);
+            }
+            index -= 1;
+            env = &node.1;
+        }
+        None
+    }
+}
+
+pub fn eval(term: &NamelessTerm) -> Result<Value<'_>, FreeVariable> {
+    eval_counting(term).map(|(value, _)| value)
+}
+
+// the value together with the number of beta reductions it took
+pub fn eval_counting(term: &NamelessTerm) -> Result<(Value<'_>, usize), FreeVariable> {
+    let mut reductions = 0;
+    let value = eval_in(&Env::default(), term, &mut reductions)?;
+    Ok((value, reductions))
+}
+
+fn eval_in<'a>(
+    env: &Env<'a>,
+    term: &'a NamelessTerm,
+    reductions: &mut usize,
+) -> Result<Value<'a>, FreeVariable> {
+    match term {
+        NamelessTerm::TmVar(index) => {
+            let thunk = env.lookup(*index).ok_or(FreeVariable(*index))?;
+            force(thunk, reductions)
+        }
+        NamelessTerm::TmAbs(hint, body) => Ok(Value {
+            env: env.clone(),
+            hint: *hint,
+            body,
+        }),
+        NamelessTerm::TmApp(left, right) => {
+            let function = eval_in(env, left.as_ref(), reductions)?;
+            // a variable argument passes its own thunk on, so it stays shared
+            let argument = match right.as_ref() {
+                NamelessTerm::TmVar(index) => env.lookup(*index).cloned(),
+                _ => None,
+            };
+            let argument = argument
+                .unwrap_or_else(|| Rc::new(RefCell::new(Suspension::Delayed(env.clone(), right))));
+            *reductions += 1;
+            eval_in(&function.env.bind(argument), function.body, reductions)
+        }
+    }
+}
+
+fn force<'a>(thunk: &Thunk<'a>, reductions: &mut usize) -> Result<Value<'a>, FreeVariable> {
+    let (env, term) = match &*thunk.borrow() {
+        Suspension::Forced(value) => return Ok(value.clone()),
+        Suspension::Delayed(env, term) => (env.clone(), *term),
+    };
+    let value = eval_in(&env, term, reductions)?;
+    *thunk.borrow_mut() = Suspension::Forced(value.clone());
+    Ok(value)
+}
+
+impl<'a> Value<'a> {
+    // read the value back into a term, forcing the thunks its body mentions
+    pub fn to_term(&self) -> Result<NamelessTerm, FreeVariable> {
+        let body = close(self.body, &self.env, 1)?;
+        Ok(NamelessTerm::TmAbs(self.hint, Rc::new(body)))
+    }
+}
+
+fn close(term: &NamelessTerm, env: &Env<'_>, depth: usize) -> Result<NamelessTerm, FreeVariable> {
+    Ok(match term {
+        NamelessTerm::TmVar(index) if *index < depth => NamelessTerm::TmVar(*index),
+        NamelessTerm::TmVar(index) => {
+            let thunk = env.lookup(index - depth).ok_or(FreeVariable(*index))?;
+            force(thunk, &mut 0)?.to_term()?
+        }
+        NamelessTerm::TmAbs(hint, body) => {
+            NamelessTerm::TmAbs(*hint, Rc::new(close(body, env, depth + 1)?))
+        }
+        NamelessTerm::TmApp(left, right) => NamelessTerm::TmApp(
+            Rc::new(close(left, env, depth)?),
+            Rc::new(close(right, env, depth)?),
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        church::{apply, fst, numeral, pair, snd},
+        eval::eval_with_fuel,
+        nameless::{remove_names, NamingContext},
+        parser::parse,
+    };
+    use tapl_frontend::outcome::Outcome;
+
+    fn nameless(input: &str) -> NamelessTerm {
+        let (_, term) = parse(input).unwrap();
+        remove_names(&mut NamingContext::default(), &term)
+    }
+
+    #[test]
+    fn test_eval() {
+        let term = nameless("(lambda x.lambda y.x) (lambda z.z);");
+        assert_eq!(
+            eval(&term).unwrap().to_term(),
+            Ok(nameless("lambda y.lambda z.z;"))
+        );
+        // the argument is never needed, so the free variable is never reached
+        let term = nameless("(lambda x.lambda y.y) ((lambda w.w) v);");
+        assert!(eval(&term).is_ok());
+        let term = nameless("(lambda x.x) ((lambda w.w) v);");
+        assert_eq!(eval(&term).unwrap_err(), FreeVariable(0));
+
+        // the argument is reduced once for the three uses of `x`: one beta
+        // reduction for the outer application, one to force `x` and two for
+        // applying the identity twice
+        let term = nameless("(lambda x.x x x) ((lambda a.a) (lambda b.b));");
+        let (value, reductions) = eval_counting(&term).unwrap();
+        assert_eq!(value.to_term(), Ok(nameless("lambda b.b;")));
+        assert_eq!(reductions, 4);
+    }
+
+    // `ones = fix (lambda s.pair 1 s)` is an infinite stream, the tail is only
+    // unfolded as far as `fst (snd (snd ones))` asks for it
+    #[test]
+    fn test_infinite_stream() {
+        let fix = nameless("lambda f.(lambda x.f (x x)) (lambda x.f (x x));");
+        let cons_one = NamelessTerm::TmAbs(
+            "s".into(),
+            Rc::new(apply(pair(), vec![numeral(1), NamelessTerm::TmVar(0)])),
+        );
+        let ones = apply(fix, vec![cons_one]);
+        let third = apply(fst(), vec![apply(snd(), vec![apply(snd(), vec![ones])])]);
+        assert_eq!(eval(&third).unwrap().to_term(), Ok(numeral(1)));
+        // under call-by-value `fix` unfolds forever
+        assert!(matches!(
+            eval_with_fuel(&third, 200),
+            Outcome::OutOfFuel { .. }
+        ));
+    }
+}
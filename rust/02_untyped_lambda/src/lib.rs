@@ -1 +1,21 @@
-mod parser;
+#[cfg(test)]
+mod arbitrary;
+pub mod arena;
+pub mod cek;
+pub mod church;
+pub mod closure;
+pub mod context;
+pub mod cps;
+pub mod eval;
+#[cfg(test)]
+mod language;
+pub mod lazy;
+pub mod named;
+pub mod nameless;
+pub mod nbe;
+pub mod parser;
+pub mod toplevel;
+pub mod trace;
+
+pub use eval::eval;
+pub use parser::parse;
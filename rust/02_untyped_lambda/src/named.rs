@@ -0,0 +1,158 @@
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+use tapl_frontend::intern::Symbol;
+
+use crate::parser::Term;
+
+// FV(t) (TAPL 5.3.2)
+pub fn free_variables(term: &Term) -> BTreeSet<Symbol> {
+    match term {
+        Term::TmVar(name) => vec![*name].into_iter().collect(),
+        Term::TmAbs(name, body) => {
+            let mut names = free_variables(body);
+            names.remove(name);
+            names
+        }
+        Term::TmApp(left, right) => {
+            let mut names = free_variables(left);
+            names.extend(free_variables(right));
+            names
+        }
+    }
+}
+
+// a variant of `hint` that is not in `avoid`
+pub fn fresh_name(hint: Symbol, avoid: &BTreeSet<Symbol>) -> Symbol {
+    let mut name = hint;
+    while avoid.contains(&name) {
+        name = Symbol::intern(&format!("{}'", name));
+    }
+    name
+}
+
+// Capture-avoiding [x ↦ s] t on named terms (TAPL 5.3.5). Instead of leaving
+// the substitution undefined when a binder would capture a free variable of
+// `s`, the binder is renamed to a fresh name first (TAPL 5.3.4).
+pub fn substitute(term: &Term, x: Symbol, s: &Term) -> Term {
+    match term {
+        Term::TmVar(name) if *name == x => s.clone(),
+        Term::TmVar(_) => term.clone(),
+        Term::TmAbs(name, _) if *name == x => term.clone(),
+        Term::TmAbs(name, body) => {
+            let free = free_variables(s);
+            if free.contains(name) {
+                let mut avoid = free;
+                avoid.extend(free_variables(body));
+                avoid.insert(x);
+                let fresh = fresh_name(*name, &avoid);
+                let body = substitute(body, *name, &Term::TmVar(fresh));
+                Term::TmAbs(fresh, Rc::new(substitute(&body, x, s)))
+            } else {
+                Term::TmAbs(*name, Rc::new(substitute(body, x, s)))
+            }
+        }
+        Term::TmApp(left, right) => Term::TmApp(
+            Rc::new(substitute(left, x, s)),
+            Rc::new(substitute(right, x, s)),
+        ),
+    }
+}
+
+// Terms that differ only in the names of bound variables (TAPL 5.3.4)
+pub fn alpha_equivalent(left: &Term, right: &Term) -> bool {
+    fn index_of(binders: &[Symbol], name: Symbol) -> Option<usize> {
+        binders.iter().rev().position(|binder| *binder == name)
+    }
+
+    fn walk(
+        left: &Term,
+        right: &Term,
+        left_binders: &mut Vec<Symbol>,
+        right_binders: &mut Vec<Symbol>,
+    ) -> bool {
+        match (left, right) {
+            (Term::TmVar(x), Term::TmVar(y)) => {
+                match (index_of(left_binders, *x), index_of(right_binders, *y)) {
+                    (None, None) => x == y,
+                    (i, j) => i == j,
+                }
+            }
+            (Term::TmAbs(x, left_body), Term::TmAbs(y, right_body)) => {
+                left_binders.push(*x);
+                right_binders.push(*y);
+                let equivalent = walk(left_body, right_body, left_binders, right_binders);
+                left_binders.pop();
+                right_binders.pop();
+                equivalent
+            }
+            (Term::TmApp(left_1, right_1), Term::TmApp(left_2, right_2)) => {
+                walk(left_1, left_2, left_binders, right_binders)
+                    && walk(right_1, right_2, left_binders, right_binders)
+            }
+            _ => false,
+        }
+    }
+
+    walk(left, right, &mut vec![], &mut vec![])
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{
+        arbitrary::{open_term, NAMES},
+        eval,
+        nameless::{remove_names, restore_names, NamingContext},
+        parser::parse,
+    };
+
+    fn term(input: &str) -> Term {
+        parse(input).unwrap().1
+    }
+
+    #[test]
+    fn test_substitute() {
+        let x = Symbol::intern("x");
+        let substituted = substitute(&term("lambda y.x y;"), x, &term("y;"));
+        let y = |name: &str| Rc::new(Term::TmVar(name.into()));
+        let expected = Term::TmAbs("y'".into(), Rc::new(Term::TmApp(y("y"), y("y'"))));
+        assert_eq!(substituted, expected);
+        let substituted = substitute(&term("lambda x.x;"), x, &term("y;"));
+        assert_eq!(substituted, term("lambda x.x;"));
+        assert!(alpha_equivalent(
+            &term("lambda x.lambda y.x y z;"),
+            &term("lambda y.lambda x.y x z;")
+        ));
+        assert!(!alpha_equivalent(
+            &term("lambda x.z;"),
+            &term("lambda z.z;")
+        ));
+    }
+
+    // a context binding the free variables of the generated terms
+    fn context() -> NamingContext {
+        let mut ctx = NamingContext::default();
+        remove_names(&mut ctx, &term(&format!("{};", NAMES.join(" "))));
+        ctx
+    }
+
+    proptest! {
+        #[test]
+        fn test_agrees_with_de_bruijn(
+            t in open_term(),
+            s in open_term(),
+            x in prop::sample::select(&NAMES[..]),
+        ) {
+            let named = substitute(&t, x.into(), &s);
+            let mut ctx = context();
+            let j = ctx.index_of(x.into()).unwrap();
+            let t = remove_names(&mut ctx, &t);
+            let s = remove_names(&mut ctx, &s);
+            let nameless = restore_names(&mut ctx, &eval::substitute(&t, j, &s));
+            prop_assert!(alpha_equivalent(&named, &nameless), "{} and {}", named, nameless);
+        }
+    }
+}
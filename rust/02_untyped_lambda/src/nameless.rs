@@ -0,0 +1,180 @@
+use std::rc::Rc;
+
+use tapl_frontend::{dot::Label, intern::Symbol, visit::Node};
+
+use crate::parser::Term;
+
+// Term in de Bruijn representation (TAPL 6.1), abstractions keep the
+// original binder name as a hint for printing
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NamelessTerm {
+    TmVar(usize),
+    // name hint and body
+    TmAbs(Symbol, Rc<NamelessTerm>),
+    TmApp(Rc<NamelessTerm>, Rc<NamelessTerm>),
+}
+
+impl Node for NamelessTerm {
+    fn children(&self) -> Vec<(usize, &Self)> {
+        match self {
+            NamelessTerm::TmVar(_) => vec![],
+            NamelessTerm::TmAbs(_, body) => vec![(1, body)],
+            NamelessTerm::TmApp(left, right) => vec![(0, left), (0, right)],
+        }
+    }
+}
+
+// a variable shows its index, an abstraction the name hint of its binder
+impl Label for NamelessTerm {
+    fn label(&self) -> String {
+        match self {
+            NamelessTerm::TmVar(index) => index.to_string(),
+            NamelessTerm::TmAbs(hint, _) => format!("lambda {}", hint),
+            NamelessTerm::TmApp(_, _) => "app".to_string(),
+        }
+    }
+}
+
+// Naming context Γ, the innermost binding is at de Bruijn index 0. Names are
+// interned, so the lookups compare symbols.
+#[derive(Clone, Debug, Default)]
+pub struct NamingContext {
+    names: Vec<Symbol>,
+}
+
+impl NamingContext {
+    pub fn index_of(&self, name: Symbol) -> Option<usize> {
+        self.names.iter().rev().position(|n| *n == name)
+    }
+
+    pub fn name_of(&self, index: usize) -> Option<Symbol> {
+        self.names.iter().rev().nth(index).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    // the index of `name`, which is added to the context if it is free
+    pub(crate) fn index_or_free(&mut self, name: Symbol) -> usize {
+        match self.index_of(name) {
+            Some(index) => index,
+            None => {
+                self.names.insert(0, name);
+                self.len() - 1
+            }
+        }
+    }
+
+    pub(crate) fn push(&mut self, name: Symbol) {
+        self.names.push(name);
+    }
+
+    pub(crate) fn pop(&mut self) {
+        self.names.pop();
+    }
+
+    // pick a name for a binder that does not shadow anything already bound
+    fn fresh_name(&self, hint: Symbol) -> Symbol {
+        let mut name = hint;
+        while self.index_of(name).is_some() {
+            name = Symbol::intern(&format!("{}'", name));
+        }
+        name
+    }
+}
+
+// Free variables are added to the outermost end of the context, so indexes
+// that were already handed out stay valid
+pub fn remove_names(ctx: &mut NamingContext, term: &Term) -> NamelessTerm {
+    match term {
+        Term::TmVar(name) => NamelessTerm::TmVar(ctx.index_or_free(*name)),
+        Term::TmAbs(name, body) => {
+            ctx.push(*name);
+            let body = remove_names(ctx, body.as_ref());
+            ctx.pop();
+            NamelessTerm::TmAbs(*name, Rc::new(body))
+        }
+        Term::TmApp(left, right) => {
+            let left = remove_names(ctx, left.as_ref());
+            let right = remove_names(ctx, right.as_ref());
+            NamelessTerm::TmApp(Rc::new(left), Rc::new(right))
+        }
+    }
+}
+
+pub fn restore_names(ctx: &mut NamingContext, term: &NamelessTerm) -> Term {
+    match term {
+        NamelessTerm::TmVar(index) => match ctx.name_of(*index) {
+            Some(name) => Term::TmVar(name),
+            None => unreachable!("index {} is not bound in the naming context", index),
+        },
+        NamelessTerm::TmAbs(hint, body) => {
+            let name = ctx.fresh_name(*hint);
+            ctx.push(name);
+            let body = restore_names(ctx, body.as_ref());
+            ctx.pop();
+            Term::TmAbs(name, Rc::new(body))
+        }
+        NamelessTerm::TmApp(left, right) => {
+            let left = restore_names(ctx, left.as_ref());
+            let right = restore_names(ctx, right.as_ref());
+            Term::TmApp(Rc::new(left), Rc::new(right))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn test_remove_names() {
+        let mut ctx = NamingContext::default();
+        let (_, term) = parse("lambda x.lambda y.x (y z);").unwrap();
+        let nameless = remove_names(&mut ctx, &term);
+        assert_eq!(
+            nameless,
+            NamelessTerm::TmAbs(
+                "x".into(),
+                Rc::new(NamelessTerm::TmAbs(
+                    "y".into(),
+                    Rc::new(NamelessTerm::TmApp(
+                        Rc::new(NamelessTerm::TmVar(1)),
+                        Rc::new(NamelessTerm::TmApp(
+                            Rc::new(NamelessTerm::TmVar(0)),
+                            Rc::new(NamelessTerm::TmVar(2))
+                        ))
+                    ))
+                ))
+            )
+        );
+        assert_eq!(ctx.name_of(0), Some("z".into()));
+        assert_eq!(restore_names(&mut ctx, &nameless), term);
+
+        // a rebound name gets primed instead of capturing the outer one
+        let shadowed = NamelessTerm::TmAbs(
+            "z".into(),
+            Rc::new(NamelessTerm::TmApp(
+                Rc::new(NamelessTerm::TmVar(0)),
+                Rc::new(NamelessTerm::TmVar(1)),
+            )),
+        );
+        assert_eq!(
+            restore_names(&mut ctx, &shadowed),
+            Term::TmAbs(
+                "z'".into(),
+                Rc::new(Term::TmApp(
+                    Rc::new(Term::TmVar("z'".into())),
+                    Rc::new(Term::TmVar("z".into()))
+                ))
+            )
+        );
+    }
+}
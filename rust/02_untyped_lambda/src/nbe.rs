@@ -0,0 +1,140 @@
+use std::rc::Rc;
+
+use tapl_frontend::intern::Symbol;
+
+use crate::nameless::NamelessTerm;
+
+// Normalization by evaluation: the term is evaluated into a semantic domain
+// where abstractions are closures and a variable that is not bound to
+// anything yet is a neutral term, then the value is read back into a normal
+// form by applying every closure to a fresh neutral variable. Beta reduction
+// happens in the host, so there is no substitution and no shifting.
+//
+// Arguments are evaluated before the call, so like call-by-value this
+// diverges on a term whose normal form discards an argument that diverges.
+
+#[derive(Clone, Debug)]
+enum Value<'a> {
+    Closure(Env<'a>, Symbol, &'a NamelessTerm),
+    Neutral(Rc<Neutral<'a>>),
+}
+
+// A variable applied to arguments, variables count binders from the outside
+// (de Bruijn levels) so they need no shifting when going under a binder
+#[derive(Debug)]
+enum Neutral<'a> {
+    Level(usize),
+    App(Rc<Neutral<'a>>, Value<'a>),
+}
+
+#[derive(Clone, Debug, Default)]
+struct Env<'a>(Option<Rc<(Value<'a>, Env<'a>)>>);
+
+impl<'a> Env<'a> {
+    fn bind(&self, value: Value<'a>) -> Self {
+        Env(Some(Rc::new((value, self.clone()))))
+    }
+
+    fn lookup(&self, index: usize) -> &Value<'a> {
+        let mut env = self;
+        let mut index = index;
+        while let Some(node) = &env.0 {
+            if index == 0 {
+                return &node.0;
+            }
+            index -= 1;
+            env = &node.1;
+        }
+        unreachable!("every free variable is bound to a neutral")
+    }
+}
+
+fn eval<'a>(env: &Env<'a>, term: &'a NamelessTerm) -> Value<'a> {
+    match term {
+        NamelessTerm::TmVar(index) => env.lookup(*index).clone(),
+        NamelessTerm::TmAbs(hint, body) => Value::Closure(env.clone(), *hint, body),
+        NamelessTerm::TmApp(left, right) => apply(eval(env, left), eval(env, right)),
+    }
+}
+
+fn apply<'a>(function: Value<'a>, argument: Value<'a>) -> Value<'a> {
+    match function {
+        Value::Closure(env, _, body) => eval(&env.bind(argument), body),
+        Value::Neutral(neutral) => Value::Neutral(Rc::new(Neutral::App(neutral, argument))),
+    }
+}
+
+// `depth` is the number of binders the value is read back under
+fn readback(depth: usize, value: Value<'_>) -> NamelessTerm {
+    match value {
+        Value::Closure(_, hint, _) => {
+            let variable = Value::Neutral(Rc::new(Neutral::Level(depth)));
+            let body = readback(depth + 1, apply(value.clone(), variable));
+            NamelessTerm::TmAbs(hint, Rc::new(body))
+        }
+        Value::Neutral(neutral) => readback_neutral(depth, &neutral),
+    }
+}
+
+fn readback_neutral(depth: usize, neutral: &Neutral<'_>) -> NamelessTerm {
+    match neutral {
+        Neutral::Level(level) => NamelessTerm::TmVar(depth - level - 1),
+        Neutral::App(function, argument) => NamelessTerm::TmApp(
+            Rc::new(readback_neutral(depth, function)),
+            Rc::new(readback(depth, argument.clone())),
+        ),
+    }
+}
+
+// the number of variables free in `term`
+fn free_variables(term: &NamelessTerm, binders: usize) -> usize {
+    match term {
+        NamelessTerm::TmVar(index) => (index + 1).saturating_sub(binders),
+        NamelessTerm::TmAbs(_, body) => free_variables(body, binders + 1),
+        NamelessTerm::TmApp(left, right) => {
+            free_variables(left, binders).max(free_variables(right, binders))
+        }
+    }
+}
+
+// the beta normal form of `term`, free variables stay free
+pub fn normalize(term: &NamelessTerm) -> NamelessTerm {
+    let free = free_variables(term, 0);
+    let env = (0..free).fold(Env::default(), |env, level| {
+        env.bind(Value::Neutral(Rc::new(Neutral::Level(level))))
+    });
+    readback(free, eval(&env, term))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        church::{self, apply, numeral, power, readback_numeral, times},
+        nameless::{remove_names, NamingContext},
+        parser::parse,
+    };
+
+    fn nameless(input: &str) -> NamelessTerm {
+        let (_, term) = parse(input).unwrap();
+        remove_names(&mut NamingContext::default(), &term)
+    }
+
+    #[test]
+    fn test_normalize() {
+        let power_2_10 = apply(power(), vec![numeral(2), numeral(10)]);
+        assert_eq!(readback_numeral(&normalize(&power_2_10)), Some(1024));
+        let times_3_4 = apply(times(), vec![numeral(3), numeral(4)]);
+        assert_eq!(
+            normalize(&times_3_4),
+            church::normalize(&times_3_4, 10_000).unwrap()
+        );
+        // reduces under binders and keeps free variables free
+        let term = nameless("lambda x.(lambda y.y x z) (lambda w.w);");
+        assert_eq!(normalize(&term), nameless("lambda x.x z;"));
+        assert_eq!(
+            normalize(&nameless("a (lambda x.(lambda y.y) b);")),
+            nameless("a (lambda x.b);")
+        );
+    }
+}
@@ -1,86 +1,264 @@
-use nom::{
-    branch::alt,
-    bytes::complete::{tag, tag_no_case},
-    character::complete::{alpha0, alpha1, multispace0, one_of},
-    error::{context, VerboseError},
-    multi::many1,
-    sequence::tuple,
+use std::fmt::{self, Formatter};
+use std::rc::Rc;
+
+use misc::DisplayMode;
+use tapl_frontend::{
+    dot::Label,
+    intern::Symbol,
+    lexer::{Lexer, SyntaxError, Token},
+    visit::Node,
 };
 
-use misc::ALPHABET;
-pub type IResult<I, O> = nom::IResult<I, O, VerboseError<I>>;
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    Syntax(SyntaxError),
+    // text after the terminating `;`
+    TrailingInput(String),
+}
+
+impl From<SyntaxError> for ParseError {
+    fn from(error: SyntaxError) -> Self {
+        ParseError::Syntax(error)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Syntax(error) => write!(f, "{}", error),
+            ParseError::TrailingInput(rest) => write!(f, "unexpected input `{}`", rest),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Syntax(error) => Some(error),
+            ParseError::TrailingInput(_) => None,
+        }
+    }
+}
+
+pub type ParseResult<'a, T> = Result<(&'a str, T), ParseError>;
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Term {
-    TmVar(String),
+    TmVar(Symbol),
     // argument and body
-    TmAbs(String, Box<Term>),
-    TmApp(Box<Term>, Box<Term>),
-}
-
-fn parse_paren_term(input: &str) -> IResult<&str, Term> {
-    //println!("parse_paren_term {:?}", input);
-    context("parse_paren_term", tuple((tag("("), parse_term, tag(")"))))(input)
-        .map(|(next_input, (_, term, _))| (next_input, term))
-}
-
-fn parse_variable(input: &str) -> IResult<&str, Term> {
-    println!("parse_variable {:?}", input);
-    context("parse_ident", tuple((multispace0, one_of(ALPHABET))))(input)
-        .map(|(next_input, (_, res))| (next_input, Term::TmVar(res.to_string())))
-}
-
-fn parse_atom(input: &str) -> IResult<&str, Term> {
-    //println!("parse_atom {:?}", input);
-    context("parse_atom", alt((parse_variable, parse_paren_term)))(input)
-        .map(|(next_input, res)| (next_input, res))
-}
-
-fn parse_abstraction(input: &str) -> IResult<&str, Term> {
-    println!("parse_abstraction: {:?}", input);
-    context(
-        "parse_abstraction",
-        tuple((
-            tag_no_case("lambda "),
-            one_of(ALPHABET),
-            tag("."),
-            parse_term,
-        )),
-    )(input)
-    .map(|(next_input, (_, param, _, body))| {
-        /*
-        println!(
-            "param: {:?}, body: {:?}, next_input: {:?}",
-            param, body, next_input
-        );
-        */
-        (next_input, Term::TmAbs(param.to_string(), Box::new(body)))
+    TmAbs(Symbol, Rc<Term>),
+    TmApp(Rc<Term>, Rc<Term>),
+}
+
+impl Node for Term {
+    fn children(&self) -> Vec<(usize, &Self)> {
+        match self {
+            Term::TmVar(_) => vec![],
+            Term::TmAbs(_, body) => vec![(1, body)],
+            Term::TmApp(left, right) => vec![(0, left), (0, right)],
+        }
+    }
+}
+
+impl Label for Term {
+    fn label(&self) -> String {
+        match self {
+            Term::TmVar(name) => name.to_string(),
+            Term::TmAbs(name, _) => format!("lambda {}", name),
+            Term::TmApp(_, _) => "app".to_string(),
+        }
+    }
+}
+
+impl Term {
+    pub fn display(&self, mode: DisplayMode) -> TermDisplay<'_> {
+        TermDisplay { term: self, mode }
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.display(DisplayMode::Named).fmt(f)
+    }
+}
+
+pub struct TermDisplay<'a> {
+    term: &'a Term,
+    mode: DisplayMode,
+}
+
+impl fmt::Display for TermDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_term(f, self.term, self.mode, &mut vec![])
+    }
+}
+
+fn write_parenthesized(
+    f: &mut Formatter<'_>,
+    term: &Term,
+    mode: DisplayMode,
+    binders: &mut Vec<Symbol>,
+) -> fmt::Result {
+    write!(f, "(")?;
+    write_term(f, term, mode, binders)?;
+    write!(f, ")")
+}
+
+// An abstraction body extends as far right as possible, so abstractions are
+// parenthesized everywhere except at the top and in bodies. `binders` are
+// the names bound around `term`, outermost first; a free variable keeps its
+// name in every mode.
+fn write_term(
+    f: &mut Formatter<'_>,
+    term: &Term,
+    mode: DisplayMode,
+    binders: &mut Vec<Symbol>,
+) -> fmt::Result {
+    match term {
+        Term::TmVar(name) => match (mode, binders.iter().rev().position(|b| b == name)) {
+            (DisplayMode::DeBruijn, Some(index)) => write!(f, "{}", index),
+            (DisplayMode::Both, Some(index)) => write!(f, "{}#{}", name, index),
+            _ => write!(f, "{}", name),
+        },
+        Term::TmAbs(name, body) => {
+            match mode {
+                DisplayMode::DeBruijn => write!(f, "λ.")?,
+                _ => write!(f, "lambda {}.", name)?,
+            }
+            binders.push(*name);
+            let result = write_term(f, body, mode, binders);
+            binders.pop();
+            result
+        }
+        Term::TmApp(left, right) => {
+            match left.as_ref() {
+                Term::TmAbs(_, _) => write_parenthesized(f, left, mode, binders)?,
+                _ => write_term(f, left, mode, binders)?,
+            }
+            write!(f, " ")?;
+            match right.as_ref() {
+                Term::TmVar(_) => write_term(f, right, mode, binders),
+                _ => write_parenthesized(f, right, mode, binders),
+            }
+        }
+    }
+}
+
+const SYMBOLS: [&str; 5] = ["(", ")", ".", ";", "="];
+
+const KEYWORDS: [&str; 1] = ["lambda"];
+
+fn starts_atom(lexer: &mut Lexer) -> Result<bool, ParseError> {
+    let (token, _) = lexer.peek()?;
+    Ok(match token {
+        Token::Word(_) => !lexer.is_keyword(token, "lambda"),
+        Token::Symbol("(") => true,
+        _ => false,
+    })
+}
+
+// What the parser builds from the syntax: a named `Term`, or the nodes of a
+// `TermArena`. `bind` comes before the body of an abstraction is parsed and
+// `abs` after it.
+pub trait Build {
+    type Term;
+
+    fn var(&mut self, name: &str) -> Self::Term;
+    fn bind(&mut self, name: &str);
+    fn abs(&mut self, name: &str, body: Self::Term) -> Self::Term;
+    fn app(&mut self, left: Self::Term, right: Self::Term) -> Self::Term;
+}
+
+struct Named;
+
+impl Build for Named {
+    type Term = Term;
+
+    fn var(&mut self, name: &str) -> Term {
+        Term::TmVar(name.into())
+    }
+
+    fn bind(&mut self, _: &str) {}
+
+    fn abs(&mut self, name: &str, body: Term) -> Term {
+        Term::TmAbs(name.into(), Rc::new(body))
+    }
+
+    fn app(&mut self, left: Term, right: Term) -> Term {
+        Term::TmApp(Rc::new(left), Rc::new(right))
+    }
+}
+
+fn parse_atom<B: Build>(lexer: &mut Lexer, build: &mut B) -> Result<B::Term, ParseError> {
+    lexer.nested(|lexer| {
+        if lexer.eat_symbol("(")?.is_some() {
+            let term = parse_term(lexer, build)?;
+            lexer.expect_symbol(")")?;
+            return Ok(term);
+        }
+        let (name, _) = lexer.expect_word("a term", &KEYWORDS)?;
+        Ok(build.var(name))
     })
 }
 
-fn parse_application(input: &str) -> IResult<&str, Term> {
-    //println!("parse_application {:?}", input);
-    context("parse_application", many1(parse_atom))(input).map(|(next_input, vars)| {
-        //println!("vars: {:?}", vars);
-        let mut lhs = Box::new(vars[0].clone());
-        for i in 1..vars.len() {
-            let rhs = Box::new(vars[i].clone());
-            lhs = Box::new(Term::TmApp(lhs, rhs));
+// application is left associative: `x y z` is `(x y) z`
+fn parse_application<B: Build>(lexer: &mut Lexer, build: &mut B) -> Result<B::Term, ParseError> {
+    let mut term = parse_atom(lexer, build)?;
+    while starts_atom(lexer)? {
+        let right = parse_atom(lexer, build)?;
+        term = build.app(term, right);
+    }
+    Ok(term)
+}
+
+// the body of an abstraction extends as far right as possible
+fn parse_term<B: Build>(lexer: &mut Lexer, build: &mut B) -> Result<B::Term, ParseError> {
+    lexer.nested(|lexer| {
+        if lexer.eat_keyword("lambda")?.is_some() {
+            let (param, _) = lexer.expect_word("a variable", &KEYWORDS)?;
+            lexer.expect_symbol(".")?;
+            build.bind(param);
+            let body = parse_term(lexer, build)?;
+            return Ok(build.abs(param, body));
         }
-        (next_input, lhs.as_ref().clone())
+        parse_application(lexer, build)
     })
 }
 
-fn parse_term(input: &str) -> IResult<&str, Term> {
-    //println!("parse_term: {:?}", input);
-    context("term", alt((parse_abstraction, parse_application)))(input)
-        .map(|(next_input, res)| (next_input, res))
+// a term terminated by `;`, together with the input after the `;`
+pub fn parse(input: &str) -> ParseResult<'_, Term> {
+    let _span = tracing::debug_span!("parse").entered();
+    let (rest, term) = parse_with(input, &mut Named)?;
+    tracing::debug!(%term, "parsed");
+    Ok((rest, term))
 }
 
-pub fn parse(input: &str) -> IResult<&str, Term> {
-    //println!("parse");
-    context("parse", tuple((parse_term, tag(";"))))(input)
-        .map(|(next_input, (term, _))| (next_input, term))
+// like `parse`, with the term made by `build`
+pub fn parse_with<'a, B: Build>(input: &'a str, build: &mut B) -> ParseResult<'a, B::Term> {
+    let mut lexer = Lexer::new(input, &SYMBOLS).ignore_case();
+    let term = parse_term(&mut lexer, build)?;
+    lexer.expect_symbol(";")?;
+    Ok((lexer.rest(), term))
+}
+
+// `name = t;` defines `name` at the top level, any other statement is a
+// term `t;`
+pub fn parse_statement(input: &str) -> ParseResult<'_, (Option<Symbol>, Term)> {
+    let mut lexer = Lexer::new(input, &SYMBOLS).ignore_case();
+    let mut ahead = lexer.clone();
+    let is_definition =
+        matches!(ahead.next_token()?.0, Token::Word(_)) && ahead.eat_symbol("=")?.is_some();
+    let name = if is_definition {
+        let (name, _) = lexer.expect_word("a variable", &KEYWORDS)?;
+        lexer.expect_symbol("=")?;
+        Some(Symbol::intern(name))
+    } else {
+        None
+    };
+    let term = parse_term(&mut lexer, &mut Named)?;
+    lexer.expect_symbol(";")?;
+    Ok((lexer.rest(), (name, term)))
 }
 
 #[cfg(test)]
@@ -93,7 +271,7 @@ mod tests {
             parse("(lambda x.y);"),
             Ok((
                 "",
-                Term::TmAbs("x".to_string(), Box::new(Term::TmVar("y".to_string())))
+                Term::TmAbs("x".into(), Rc::new(Term::TmVar("y".into())))
             ))
         );
         assert_eq!(
@@ -101,16 +279,58 @@ mod tests {
             Ok((
                 "",
                 Term::TmApp(
-                    Box::new(Term::TmAbs(
-                        "x".to_string(),
-                        Box::new(Term::TmAbs(
-                            "y".to_string(),
-                            Box::new(Term::TmVar("y".to_string()))
-                        ))
+                    Rc::new(Term::TmAbs(
+                        "x".into(),
+                        Rc::new(Term::TmAbs("y".into(), Rc::new(Term::TmVar("y".into()))))
                     )),
-                    Box::new(Term::TmVar("z".to_string()))
+                    Rc::new(Term::TmVar("z".into()))
                 )
             ))
         );
+        // names are whole words
+        assert_eq!(
+            parse("lambdax.x;").unwrap_err().to_string(),
+            "1:8: expected `;`, found `.`"
+        );
+        assert_eq!(
+            parse("lambda f.f (lambda x.x;").unwrap_err().to_string(),
+            "1:23: expected `)`, found `;`"
+        );
+        assert_eq!(
+            parse_statement("id = lambda x.x; id;"),
+            Ok((
+                " id;",
+                (
+                    Some("id".into()),
+                    Term::TmAbs("x".into(), Rc::new(Term::TmVar("x".into())))
+                )
+            ))
+        );
+        assert_eq!(
+            parse_statement("lambda = x;").unwrap_err().to_string(),
+            "1:1: expected a variable, found `lambda`"
+        );
+        assert!(parse(&"lambda x.".repeat(1000))
+            .unwrap_err()
+            .to_string()
+            .contains("expected at most 256 nested terms"));
+    }
+
+    #[test]
+    fn test_display_mode() {
+        let (_, term) = parse("lambda x.lambda y.x (lambda x.x y) z;").unwrap();
+        assert_eq!(
+            term.display(DisplayMode::Named).to_string(),
+            "lambda x.lambda y.x (lambda x.x y) z"
+        );
+        // the inner `x` refers to the nearer binder, `z` is free
+        assert_eq!(
+            term.display(DisplayMode::DeBruijn).to_string(),
+            "λ.λ.1 (λ.0 1) z"
+        );
+        assert_eq!(
+            term.display(DisplayMode::Both).to_string(),
+            "lambda x.lambda y.x#1 (lambda x.x#0 y#1) z"
+        );
     }
 }
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use tapl_frontend::intern::Symbol;
+
+use crate::{
+    named::{free_variables, substitute},
+    parser::{parse_statement, ParseError, Term},
+};
+
+// The combinators, booleans, pairs, numerals and lists of TAPL 5.2 as
+// top-level definitions
+pub const PRELUDE: &str = include_str!("../prelude.lam");
+
+// Top-level definitions `name = t;`. A free variable of a later term that is
+// defined here stands for its definition, which is expanded when it is made
+// so it only refers to the definitions before it.
+#[derive(Clone, Debug, Default)]
+pub struct Bindings {
+    terms: HashMap<Symbol, Term>,
+}
+
+impl Bindings {
+    pub fn define(&mut self, name: Symbol, term: &Term) {
+        let term = self.expand(term);
+        self.terms.insert(name, term);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Term> {
+        self.terms.get(&Symbol::get(name)?)
+    }
+
+    // the defined names in alphabetical order
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names = self
+            .terms
+            .keys()
+            .map(|name| name.as_str())
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        names
+    }
+
+    // `term` with its defined free variables replaced by their definitions
+    pub fn expand(&self, term: &Term) -> Term {
+        free_variables(term)
+            .into_iter()
+            .fold(term.clone(), |term, name| match self.terms.get(&name) {
+                Some(definition) => substitute(&term, name, definition),
+                None => term,
+            })
+    }
+
+    // Runs the definitions of `source` in order and returns its other
+    // statements expanded, ready to evaluate
+    pub fn load(&mut self, source: &str) -> Result<Vec<Term>, ParseError> {
+        let mut input = source;
+        let mut terms = vec![];
+        while !input.trim().is_empty() {
+            let (rest, (name, term)) = parse_statement(input)?;
+            match name {
+                Some(name) => self.define(name, &term),
+                None => terms.push(self.expand(&term)),
+            }
+            input = rest;
+        }
+        Ok(terms)
+    }
+
+    pub fn load_prelude(&mut self) {
+        self.load(PRELUDE).expect("the prelude parses");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        church::{self, normalize, readback_boolean, readback_numeral},
+        nameless::{remove_names, NamingContext},
+    };
+
+    fn run(bindings: &mut Bindings, source: &str) -> crate::nameless::NamelessTerm {
+        let terms = bindings.load(source).unwrap();
+        let term = remove_names(&mut NamingContext::default(), &terms[0]);
+        normalize(&term, 10_000).unwrap()
+    }
+
+    #[test]
+    fn test_prelude() {
+        let mut bindings = Bindings::default();
+        bindings.load_prelude();
+        assert_eq!(bindings.names()[..3], ["Y", "and", "c0"]);
+        for name in ["Y", "fix", "and", "prd", "cons"].iter() {
+            assert!(free_variables(bindings.get(name).unwrap()).is_empty());
+        }
+        let plus = remove_names(&mut NamingContext::default(), bindings.get("plus").unwrap());
+        assert_eq!(plus, church::plus());
+        assert_eq!(
+            readback_numeral(&run(&mut bindings, "prd (times c2 c3);")),
+            Some(5)
+        );
+        assert_eq!(
+            readback_boolean(&run(&mut bindings, "and (iszro c0) (not fls);")),
+            Some(true)
+        );
+        assert_eq!(
+            readback_numeral(&run(&mut bindings, "compose scc scc (const c2 Y);")),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn test_load() {
+        let mut bindings = Bindings::default();
+        let terms = bindings.load("k = lambda x.lambda y.x; k y;").unwrap();
+        assert_eq!(terms[0].to_string(), "(lambda x.lambda y.x) y");
+        // a later definition replaces an earlier one, earlier uses keep theirs
+        bindings.load("f = k; k = lambda x.x;").unwrap();
+        assert_eq!(
+            bindings.get("f").unwrap().to_string(),
+            "lambda x.lambda y.x"
+        );
+        assert_eq!(
+            bindings.load("k z;").unwrap()[0].to_string(),
+            "(lambda x.x) z"
+        );
+        assert!(bindings.load("k = ;").is_err());
+    }
+}
@@ -0,0 +1,161 @@
+use std::fmt::{self, Formatter};
+use std::rc::Rc;
+
+use tapl_frontend::dot::Label;
+
+use crate::eval::{substitute_top, NoRuleApplies};
+use crate::nameless::NamelessTerm;
+
+// The call-by-value evaluation rules of TAPL figure 5-3
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rule {
+    App1,
+    App2,
+    AppAbs,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Rule::App1 => "E-App1",
+            Rule::App2 => "E-App2",
+            Rule::AppAbs => "E-AppAbs",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// One reduction step: the rules of its derivation from the outermost
+// congruence rule down to E-AppAbs, and the resulting term
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub rules: Vec<Rule>,
+    pub term: NamelessTerm,
+}
+
+fn is_val(term: &NamelessTerm) -> bool {
+    matches!(term, NamelessTerm::TmAbs(_, _))
+}
+
+pub fn eval1(term: &NamelessTerm) -> Result<Step, NoRuleApplies> {
+    match term {
+        NamelessTerm::TmApp(left, right) => match left.as_ref() {
+            NamelessTerm::TmAbs(_, body) if is_val(right.as_ref()) => Ok(Step {
+                rules: vec![Rule::AppAbs],
+                term: substitute_top(right.as_ref(), body.as_ref()),
+            }),
+            _ if is_val(left.as_ref()) => {
+                let mut step = eval1(right.as_ref())?;
+                step.rules.insert(0, Rule::App2);
+                step.term = NamelessTerm::TmApp(left.clone(), Rc::new(step.term));
+                Ok(step)
+            }
+            _ => {
+                let mut step = eval1(left.as_ref())?;
+                step.rules.insert(0, Rule::App1);
+                step.term = NamelessTerm::TmApp(Rc::new(step.term), right.clone());
+                Ok(step)
+            }
+        },
+        _ => Err(NoRuleApplies),
+    }
+}
+
+// Only an open term gets stuck: call by value never reduces under an
+// abstraction, so the redex it is stuck on is a free variable in the place of
+// a function or an argument. `path` leads to it from the root by the indices
+// of `Node::children`, and with no binder on the way its index is one of the
+// naming context of the root.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StuckAt {
+    pub path: Vec<usize>,
+    pub subterm: NamelessTerm,
+}
+
+impl fmt::Display for StuckAt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "stuck at the free variable {}", self.subterm.label())
+    }
+}
+
+// follows the evaluation contexts of `eval1` down to the redex, `None` if
+// `term` is a value or takes a step
+pub fn stuck_at(term: &NamelessTerm) -> Option<StuckAt> {
+    let mut path = vec![];
+    let mut subterm = term;
+    loop {
+        match subterm {
+            NamelessTerm::TmVar(_) => break,
+            NamelessTerm::TmAbs(_, _) => return None,
+            NamelessTerm::TmApp(left, right) => match (left.as_ref(), right.as_ref()) {
+                (NamelessTerm::TmAbs(_, _), right) if is_val(right) => return None,
+                (left, right) if is_val(left) => {
+                    path.push(1);
+                    subterm = right;
+                }
+                (left, _) => {
+                    path.push(0);
+                    subterm = left;
+                }
+            },
+        }
+    }
+    Some(StuckAt {
+        path,
+        subterm: subterm.clone(),
+    })
+}
+
+// every step of the reduction of `term` to a value or a stuck term
+pub fn eval_trace(term: &NamelessTerm) -> Vec<Step> {
+    let _span = tracing::debug_span!("eval").entered();
+    let mut trace: Vec<Step> = vec![];
+    while let Ok(step) = eval1(trace.last().map_or(term, |step| &step.term)) {
+        tracing::trace!(term = ?step.term, "step");
+        trace.push(step);
+    }
+    trace
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nameless::{remove_names, NamingContext};
+    use crate::parser::parse;
+
+    fn nameless(input: &str) -> NamelessTerm {
+        let (_, term) = parse(input).unwrap();
+        remove_names(&mut NamingContext::default(), &term)
+    }
+
+    #[test]
+    fn test_eval_trace() {
+        let trace = eval_trace(&nameless(
+            "(lambda x.x) ((lambda y.y) (lambda z.z)) (lambda w.w);",
+        ));
+        assert_eq!(
+            trace
+                .iter()
+                .map(|step| step.rules.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                vec![Rule::App1, Rule::App2, Rule::AppAbs],
+                vec![Rule::App1, Rule::AppAbs],
+                vec![Rule::AppAbs],
+            ]
+        );
+        assert_eq!(trace[2].term, nameless("lambda w.w;"));
+        assert_eq!(Rule::AppAbs.to_string(), "E-AppAbs");
+        assert_eq!(eval_trace(&nameless("lambda x.(lambda y.y) x;")), vec![]);
+    }
+
+    #[test]
+    fn test_stuck_at() {
+        let at = stuck_at(&nameless("(lambda x.x) ((lambda y.y) z) (lambda w.w);")).unwrap();
+        assert_eq!(at.path, [0, 1, 1]);
+        assert_eq!(at.subterm, NamelessTerm::TmVar(0));
+        assert_eq!(at.to_string(), "stuck at the free variable 0");
+        assert_eq!(stuck_at(&nameless("(lambda x.x) (lambda y.y);")), None);
+        assert_eq!(stuck_at(&nameless("lambda x.x y;")), None);
+    }
+}
@@ -0,0 +1,31 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use typed_arith::eval::{eval, eval_term};
+use untyped_arith::parse;
+
+// `if true then if true then ... 0 else 0 else 0`, `eval_term` typechecks
+// the whole tower once before it evaluates
+fn if_tower(depth: usize) -> String {
+    format!(
+        "{}0{};",
+        "if true then ".repeat(depth),
+        " else 0".repeat(depth)
+    )
+}
+
+fn bench_eval(c: &mut Criterion) {
+    for depth in [10, 100, 200] {
+        let input = if_tower(depth);
+        let term = parse(&input).unwrap().1;
+
+        let mut group = c.benchmark_group(format!("if tower {}", depth));
+        group.bench_function("eval term", |b| b.iter(|| eval_term(&term).unwrap()));
+        group.bench_function("parse and eval", |b| {
+            b.iter(|| eval(input.as_str()).unwrap())
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_eval);
+criterion_main!(benches);
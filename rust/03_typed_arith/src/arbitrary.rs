@@ -0,0 +1,60 @@
+use proptest::prelude::*;
+use untyped_arith::parser::{Op, Term};
+
+// `Term` belongs to untyped_arith, so this is a strategy rather than an
+// `Arbitrary` instance
+pub(crate) fn term() -> BoxedStrategy<Term> {
+    let leaf = prop_oneof![
+        Just(Term::TmTrue),
+        Just(Term::TmFalse),
+        Just(Term::TmZero),
+        (1..=u64::MAX).prop_map(Term::TmNumber),
+    ];
+    leaf.prop_recursive(4, 32, 3, |inner| {
+        prop_oneof![
+            inner.clone().prop_map(|term| Term::TmSucc(Box::new(term))),
+            inner.clone().prop_map(|term| Term::TmPred(Box::new(term))),
+            inner
+                .clone()
+                .prop_map(|term| Term::TmIsZero(Box::new(term))),
+            (inner.clone(), inner.clone(), inner.clone()).prop_map(
+                |(cond, then_term, else_term)| {
+                    Term::TmIf(Box::new(cond), Box::new(then_term), Box::new(else_term))
+                }
+            ),
+            inner.clone().prop_map(|term| Term::TmNot(Box::new(term))),
+            (inner.clone(), inner.clone())
+                .prop_map(|(left, right)| Term::TmAnd(Box::new(left), Box::new(right))),
+            (inner.clone(), inner.clone())
+                .prop_map(|(left, right)| Term::TmOr(Box::new(left), Box::new(right))),
+            (prop::sample::select(&Op::ALL[..]), inner.clone(), inner)
+                .prop_map(|(op, left, right)| Term::TmPrim(op, Box::new(left), Box::new(right))),
+        ]
+    })
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::{eval_term, type_of_term, Error, EvalError};
+
+    proptest! {
+        #[test]
+        fn test_type_check_never_panics(term in term()) {
+            let _ = type_of_term(&term);
+        }
+
+        // progress (TAPL 8.3.2): a well-typed term evaluates to a value, the
+        // only runtime error is a numeral that does not fit in a u64
+        #[test]
+        fn test_well_typed_terms_evaluate(term in term()) {
+            if type_of_term(&term).is_ok() {
+                match eval_term(&term) {
+                    Ok(_) | Err(Error::Eval(EvalError::NumericOverflow)) => {}
+                    Err(error) => prop_assert!(false, "{} failed with {}", term, error),
+                }
+            }
+        }
+    }
+}
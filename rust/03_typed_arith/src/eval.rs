@@ -1,155 +1,290 @@
 use std::fmt::Display;
 use std::fmt::Formatter;
 
-use untyped_arith::parser::parse;
-use untyped_arith::parser::Term;
+use std::convert::Infallible;
+
+use untyped_arith::eval as untyped;
+use untyped_arith::parser::{Op, ParseError, Term};
+
+use crate::source::TermSource;
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Type {
     Boolean,
     Numeric,
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Value {
     Boolean(bool),
-    Numeric(u8),
+    Numeric(u64),
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Boolean(value) => write!(f, "{}", value),
+            Value::Numeric(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Boolean => write!(f, "Bool"),
+            Type::Numeric => write!(f, "Nat"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeError {
+    // the argument of `succ`, `pred` or `iszero`, or an operand of `plus`,
+    // `times` or `leq`
+    NotNumeric(Term),
+    // the condition of an `if`
+    NotBoolean(Term),
+    // an operand of `not`, `and` or `or`, with the operator name
+    OperandNotBoolean(&'static str, Term),
+    // the types of the then and else branches
+    BranchesMismatch(Type, Type),
 }
 
-#[derive(Clone, Debug)]
-pub struct Error {
-    msg: String,
+impl Display for TypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeError::NotNumeric(term) => write!(f, "term {} MUST be Numeric", term),
+            TypeError::NotBoolean(term) => write!(f, "term {} MUST be Boolean", term),
+            TypeError::OperandNotBoolean(operator, term) => {
+                write!(f, "operand {} of {} MUST be Boolean", term, operator)
+            }
+            TypeError::BranchesMismatch(then_type, else_type) => write!(
+                f,
+                "then term of type {} mismatch with else term of type {}",
+                then_type, else_type
+            ),
+        }
+    }
 }
 
-impl From<nom::Err<nom::error::VerboseError<&str>>> for Error {
-    fn from(error: nom::Err<nom::error::VerboseError<&str>>) -> Self {
-        Error {
-            msg: format!("nom parser error: {}", error.to_string()),
+impl std::error::Error for TypeError {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EvalError {
+    // numbers are stored in a u64, so the successor of 2^64 - 1 is an error
+    NumericOverflow,
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvalError::NumericOverflow => write!(f, "numeric overflow"),
         }
     }
 }
 
+impl std::error::Error for EvalError {}
+
+// Everything `eval` can fail with, from the source text to a value
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    Parse(ParseError),
+    Type(TypeError),
+    Eval(EvalError),
+}
+
+impl From<ParseError> for Error {
+    fn from(error: ParseError) -> Self {
+        Error::Parse(error)
+    }
+}
+
+impl From<Infallible> for Error {
+    fn from(error: Infallible) -> Self {
+        match error {}
+    }
+}
+
+impl From<TypeError> for Error {
+    fn from(error: TypeError) -> Self {
+        Error::Type(error)
+    }
+}
+
+impl From<EvalError> for Error {
+    fn from(error: EvalError) -> Self {
+        Error::Eval(error)
+    }
+}
+
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.msg)?;
-        Ok(())
+        match self {
+            Error::Parse(error) => write!(f, "{}", error),
+            Error::Type(error) => write!(f, "{}", error),
+            Error::Eval(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Parse(error) => Some(error),
+            Error::Type(error) => Some(error),
+            Error::Eval(error) => Some(error),
+        }
     }
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
-fn term_type(term: &Term) -> Result<Type> {
-    let term_type = match term {
-        Term::TmTrue => Type::Boolean,
-        Term::TmFalse => Type::Boolean,
-        Term::TmZero => Type::Numeric,
-        Term::TmSucc(term) => match term_type(term)? {
-            Type::Numeric => Type::Numeric,
-            _ => {
-                return Err(Error {
-                    msg: format!("term {:?} MUST be Numeric", term),
-                });
-            }
-        },
-        Term::TmPred(term) => match term_type(term)? {
-            Type::Numeric => Type::Numeric,
-            _ => {
-                return Err(Error {
-                    msg: format!("term {:?} MUST be Numeric", term),
-                });
-            }
-        },
-        Term::TmIsZero(term) => match term_type(term)? {
-            Type::Numeric => Type::Numeric,
-            _ => {
-                return Err(Error {
-                    msg: format!("term {:?} MUST be Numeric", term),
-                });
+// The typing still to do once the current subterm has a type
+enum Check<'a> {
+    // the argument of `succ`, `pred` or `iszero`, and the type of the result
+    Numeric(&'a Term, Type),
+    // the two operands of a primitive operator and the type of the result
+    Operands(&'a Term, &'a Term, Type),
+    // an operand of a boolean operator, then the next one if there is one
+    Boolean(&'static str, &'a Term, Option<&'a Term>),
+    Condition(&'a Term, &'a Term, &'a Term),
+    ThenBranch(&'a Term),
+    ElseBranch(Type),
+}
+
+// Types a term with the pending checks on a heap stack, so deep terms do not
+// overflow the Rust stack
+pub fn type_of_term(term: &Term) -> Result<Type, TypeError> {
+    let mut checks = vec![];
+    let mut term = term;
+    loop {
+        let mut typ = loop {
+            match term {
+                Term::TmTrue | Term::TmFalse => break Type::Boolean,
+                Term::TmZero | Term::TmNumber(_) => break Type::Numeric,
+                Term::TmSucc(inner) | Term::TmPred(inner) => {
+                    checks.push(Check::Numeric(inner, Type::Numeric));
+                    term = inner;
+                }
+                // T-IsZero: a numeric argument gives a boolean
+                Term::TmIsZero(inner) => {
+                    checks.push(Check::Numeric(inner, Type::Boolean));
+                    term = inner;
+                }
+                Term::TmIf(cond_term, then_term, else_term) => {
+                    checks.push(Check::Condition(cond_term, then_term, else_term));
+                    term = cond_term;
+                }
+                // T-Not, T-And and T-Or: the typing of the `if` each one
+                // stands for (TAPL 11.3), checked as written so an error
+                // names the operator
+                Term::TmNot(inner) => {
+                    checks.push(Check::Boolean("not", inner, None));
+                    term = inner;
+                }
+                Term::TmAnd(left, right) => {
+                    checks.push(Check::Boolean("and", left, Some(right)));
+                    term = left;
+                }
+                Term::TmOr(left, right) => {
+                    checks.push(Check::Boolean("or", left, Some(right)));
+                    term = left;
+                }
+                // T-Plus and T-Times give a number, T-Leq a boolean
+                Term::TmPrim(op, left, right) => {
+                    let result = match op {
+                        Op::Plus | Op::Times => Type::Numeric,
+                        Op::Leq => Type::Boolean,
+                    };
+                    checks.push(Check::Operands(left, right, result));
+                    term = left;
+                }
             }
-        },
-        Term::TmIf(cond_term, then_term, else_term) => {
-            let cond_type = term_type(cond_term.as_ref())?;
-            match cond_type {
-                Type::Boolean => {
-                    let then_type = term_type(then_term.as_ref())?;
-                    let else_type = term_type(else_term.as_ref())?;
+        };
+        term = loop {
+            typ = match (checks.pop(), typ) {
+                (None, typ) => return Ok(typ),
+                (Some(Check::Numeric(_, result)), Type::Numeric) => result,
+                (Some(Check::Numeric(inner, _)), _) => {
+                    return Err(TypeError::NotNumeric(inner.clone()))
+                }
+                (Some(Check::Operands(_, right, result)), Type::Numeric) => {
+                    checks.push(Check::Numeric(right, result));
+                    break right;
+                }
+                (Some(Check::Operands(left, _, _)), _) => {
+                    return Err(TypeError::NotNumeric(left.clone()))
+                }
+                (Some(Check::Boolean(operator, _, Some(right))), Type::Boolean) => {
+                    checks.push(Check::Boolean(operator, right, None));
+                    break right;
+                }
+                (Some(Check::Boolean(_, _, None)), Type::Boolean) => Type::Boolean,
+                (Some(Check::Boolean(operator, operand, _)), _) => {
+                    return Err(TypeError::OperandNotBoolean(operator, operand.clone()))
+                }
+                (Some(Check::Condition(_, then_term, else_term)), Type::Boolean) => {
+                    checks.push(Check::ThenBranch(else_term));
+                    break then_term;
+                }
+                (Some(Check::Condition(cond_term, _, _)), _) => {
+                    return Err(TypeError::NotBoolean(cond_term.clone()))
+                }
+                (Some(Check::ThenBranch(else_term)), then_type) => {
+                    checks.push(Check::ElseBranch(then_type));
+                    break else_term;
+                }
+                (Some(Check::ElseBranch(then_type)), else_type) => {
                     if then_type != else_type {
-                        return Err(Error {
-                            msg: format!(
-                                "then term {:?} mismatch with else term {:?}",
-                                then_term, else_term
-                            ),
-                        });
+                        return Err(TypeError::BranchesMismatch(then_type, else_type));
                     }
-
                     then_type
                 }
-                _ => {
-                    return Err(Error {
-                        msg: format!("term {:?} MUST be Boolean", cond_term),
-                    });
-                }
-            }
-        }
-    };
-    Ok(term_type)
+            };
+        };
+    }
 }
 
-fn check_term_type(term: &Term) -> Result<()> {
-    let _ = term_type(term)?;
-    Ok(())
+impl From<untyped::Value> for Value {
+    fn from(value: untyped::Value) -> Self {
+        match value {
+            untyped::Value::Boolean(value) => Value::Boolean(value),
+            untyped::Value::Numeric(value) => Value::Numeric(value),
+        }
+    }
 }
 
+// Typechecks the term once and runs it with the untyped evaluator, types
+// are erased at runtime and a well-typed term does not get stuck (TAPL 8.3)
 pub fn eval_term(term: &Term) -> Result<Value> {
-    check_term_type(term)?;
-
-    let value = match term {
-        Term::TmTrue => Value::Boolean(true),
-        Term::TmFalse => Value::Boolean(false),
-        Term::TmZero => Value::Numeric(0),
-        Term::TmSucc(term) => {
-            let value = if let Value::Numeric(number) = eval_term(term.as_ref())? {
-                Value::Numeric(number + 1)
-            } else {
-                unreachable!("has check_term_type before");
-            };
-            value
-        }
-        Term::TmPred(term) => {
-            let value = if let Value::Numeric(number) = eval_term(term.as_ref())? {
-                Value::Numeric(number - 1)
-            } else {
-                unreachable!("has check_term_type before");
-            };
-            value
-        }
-        Term::TmIsZero(term) => Value::Boolean(term.is_zero()),
-        Term::TmIf(cond_term, then_term, else_term) => {
-            if let Value::Boolean(cond) = eval_term(cond_term.as_ref())? {
-                if cond {
-                    eval_term(&then_term.as_ref())?
-                } else {
-                    eval_term(&else_term.as_ref())?
-                }
-            } else {
-                unreachable!("has check_term_type before");
-            }
-        }
-    };
-    Ok(value)
+    type_of_term(term)?;
+
+    match untyped::eval_term(term) {
+        Ok(value) => Ok(value.into()),
+        Err(untyped::EvalError::NumericOverflow) => Err(EvalError::NumericOverflow.into()),
+        Err(error) => unreachable!("well-typed term got stuck: {}", error),
+    }
 }
 
-pub fn eval(input: &str) -> Result<Value> {
-    let term = parse(input)?;
-    // assert has no input string left
-    assert!(term.0.is_empty());
+// the type of a program without evaluating it
+pub fn type_of(source: impl TermSource) -> Result<Type> {
+    let term = source.into_term().map_err(Into::into)?;
+    Ok(type_of_term(&term)?)
+}
 
-    eval_term(&term.1)
+pub fn eval(source: impl TermSource) -> Result<Value> {
+    let term = source.into_term().map_err(Into::into)?;
+    eval_term(&term)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::language::TypedArith;
+    use tapl_testkit::{assert_evals_to, assert_type_error};
 
     #[test]
     fn test_eval() -> Result<()> {
@@ -159,11 +294,94 @@ mod tests {
         assert!(eval("iszero(false);").is_err());
         assert_eq!(eval("if false then 10 else 20;")?, Value::Numeric(20));
         assert!(eval("if 9 then 10 else 20;").is_err());
-        assert!(eval("if true then false else 20;").is_err());
+        assert_eq!(
+            eval("if true then false else 20;"),
+            Err(Error::Type(TypeError::BranchesMismatch(
+                Type::Boolean,
+                Type::Numeric
+            )))
+        );
         assert_eq!(
             eval("if false then true else false;")?,
             Value::Boolean(false)
         );
         Ok(())
     }
+
+    #[test]
+    fn test_type_of() -> Result<()> {
+        assert_eq!(type_of("iszero(pred(0));")?, Type::Boolean);
+        assert_eq!(type_of("if true then 0 else succ(0);")?, Type::Numeric);
+        assert_eq!(type_of("leq(plus(1, 2), 3);")?, Type::Boolean);
+        assert_eq!(
+            type_of("times(0, iszero(0));"),
+            Err(Error::Type(TypeError::NotNumeric(Term::TmIsZero(
+                Box::new(Term::TmZero)
+            ))))
+        );
+        assert_eq!(
+            type_of("pred(false);"),
+            Err(Error::Type(TypeError::NotNumeric(Term::TmFalse)))
+        );
+        assert_eq!(
+            type_of("and(true, 0);").unwrap_err().to_string(),
+            "operand 0 of and MUST be Boolean"
+        );
+        assert_eq!(
+            type_of("or(iszero(0), not(succ(0)));")
+                .unwrap_err()
+                .to_string(),
+            "operand succ(0) of not MUST be Boolean"
+        );
+        // typing does not evaluate, so an overflowing numeral is still Nat
+        let term = Term::TmSucc(Box::new(Term::TmNumber(u64::MAX)));
+        assert_eq!(type_of_term(&term), Ok(Type::Numeric));
+        Ok(())
+    }
+
+    #[test]
+    fn test_eval_term() {
+        assert_evals_to!(TypedArith, "pred(succ(succ(0)));", "1");
+        assert_evals_to!(TypedArith, "if false then 0 else succ(3);", "4");
+        assert_type_error!(TypedArith, "succ(true);");
+        assert_type_error!(TypedArith, "if 0 then true else false;");
+        assert_evals_to!(TypedArith, "iszero(pred(succ(0)));", "true");
+        assert_evals_to!(TypedArith, "and(iszero(0), not(iszero(1)));", "true");
+        assert_evals_to!(TypedArith, "or(iszero(1), false);", "false");
+        assert_type_error!(TypedArith, "not(0);");
+        assert_type_error!(TypedArith, "and(true, 0);");
+        assert_type_error!(TypedArith, "or(false, succ(0));");
+        assert_evals_to!(TypedArith, "times(plus(2, 3), times(5, 10));", "250");
+        assert_evals_to!(TypedArith, "times(16, 16);", "256");
+        assert_evals_to!(TypedArith, "if leq(times(9, 9), 80) then 0 else 1;", "1");
+        assert_type_error!(TypedArith, "plus(leq(0, 1), 1);");
+        assert_type_error!(TypedArith, "succ(leq(0, 1));");
+    }
+
+    #[test]
+    fn test_eval_deep_term() {
+        let mut term = Term::TmZero;
+        for _ in 0..100_000 {
+            term = Term::TmPred(Box::new(Term::TmSucc(Box::new(term))));
+        }
+        assert_eq!(eval_term(&term), Ok(Value::Numeric(0)));
+        let mut term = Term::TmIsZero(Box::new(term));
+        for _ in 0..100_000 {
+            term = Term::TmIf(
+                Box::new(term),
+                Box::new(Term::TmFalse),
+                Box::new(Term::TmTrue),
+            );
+        }
+        assert_eq!(eval_term(&term), Ok(Value::Boolean(true)));
+        let mut term = Term::TmZero;
+        for _ in 0..256 {
+            term = Term::TmSucc(Box::new(term));
+        }
+        assert_eq!(eval_term(&term), Ok(Value::Numeric(256)));
+        assert_eq!(
+            eval("plus(18446744073709551615, 1);"),
+            Err(Error::Eval(EvalError::NumericOverflow))
+        );
+    }
 }
@@ -0,0 +1,29 @@
+use tapl_testkit::{Failure, Language};
+use untyped_arith::parser::{parse, Term};
+
+use crate::eval::{eval_term, type_of_term, Value};
+
+pub struct TypedArith;
+
+impl Language for TypedArith {
+    type Term = Term;
+    type Value = Value;
+
+    fn parse(input: &str) -> Result<Term, Failure> {
+        match parse(input) {
+            Ok(("", term)) => Ok(term),
+            Ok((rest, _)) => Err(Failure::Parse(format!("unexpected `{}`", rest))),
+            Err(error) => Err(Failure::Parse(error.to_string())),
+        }
+    }
+
+    fn type_check(term: &Term) -> Result<(), Failure> {
+        type_of_term(term)
+            .map(|_| ())
+            .map_err(|error| Failure::Type(error.to_string()))
+    }
+
+    fn eval(term: &Term) -> Result<Value, Failure> {
+        eval_term(term).map_err(|error| Failure::Eval(error.to_string()))
+    }
+}
@@ -1,3 +1,8 @@
+#[cfg(test)]
+mod arbitrary;
 pub mod eval;
+#[cfg(test)]
+mod language;
+pub mod source;
 
-pub use eval::eval;
+pub use eval::{eval, type_of};
@@ -0,0 +1,89 @@
+use std::convert::Infallible;
+
+use untyped_arith::parser::{parse, ParseError, Term};
+
+use crate::eval::Error;
+
+// Where the type checker gets its terms from. Source text in the syntax of
+// `untyped_arith` is one source, a frontend with its own syntax, tokens or a
+// deserialized AST can feed `type_of` and `eval` by implementing this.
+pub trait TermSource {
+    type Error: Into<Error>;
+
+    fn into_term(self) -> Result<Term, Self::Error>;
+}
+
+// a whole program ending with `;`
+impl TermSource for &str {
+    type Error = ParseError;
+
+    fn into_term(self) -> Result<Term, ParseError> {
+        let (rest, term) = parse(self)?;
+        if !rest.is_empty() {
+            return Err(ParseError::TrailingInput(rest.to_string()));
+        }
+        Ok(term)
+    }
+}
+
+impl TermSource for &String {
+    type Error = ParseError;
+
+    fn into_term(self) -> Result<Term, ParseError> {
+        self.as_str().into_term()
+    }
+}
+
+impl TermSource for String {
+    type Error = ParseError;
+
+    fn into_term(self) -> Result<Term, ParseError> {
+        self.as_str().into_term()
+    }
+}
+
+// a term that was built or parsed elsewhere
+impl TermSource for Term {
+    type Error = Infallible;
+
+    fn into_term(self) -> Result<Term, Infallible> {
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::{eval, type_of, Type, Value};
+
+    // numerals written in decimal, a frontend `untyped_arith` does not have
+    struct Decimal(&'static str);
+
+    impl TermSource for Decimal {
+        type Error = ParseError;
+
+        fn into_term(self) -> Result<Term, ParseError> {
+            let n = self
+                .0
+                .parse::<u64>()
+                .map_err(|_| ParseError::NumberTooLarge(self.0.to_string()))?;
+            Ok(Term::numeral(n))
+        }
+    }
+
+    #[test]
+    fn test_term_source() {
+        assert_eq!(eval("succ(0);"), Ok(Value::Numeric(1)));
+        assert_eq!(type_of(Term::TmTrue), Ok(Type::Boolean));
+        let source = format!("iszero({});", 0);
+        assert_eq!(type_of(&source), Ok(Type::Boolean));
+        assert_eq!(eval(source), Ok(Value::Boolean(true)));
+        assert_eq!(eval(Decimal("42")), Ok(Value::Numeric(42)));
+        assert_eq!(eval(Decimal("256")), Ok(Value::Numeric(256)));
+        let number = "18446744073709551616";
+        assert_eq!(
+            eval(Decimal(number)),
+            Err(Error::Parse(ParseError::NumberTooLarge(number.to_string())))
+        );
+    }
+}
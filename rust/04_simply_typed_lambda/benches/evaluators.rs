@@ -0,0 +1,48 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use simply_typed_lambda::bytecode::Program;
+use simply_typed_lambda::closure::{self, Env};
+use simply_typed_lambda::compile::{self, compile};
+use simply_typed_lambda::eval::eval;
+use simply_typed_lambda::parser::{Parser, Term};
+use simply_typed_lambda::vm;
+
+// `(lambda f:Nat->Nat.f (f (... (f 0)))) (lambda n:Nat.succ(n))` with `depth`
+// nested applications of `f`: every substitution step walks the whole
+// remaining term, the closure evaluator visits each application once, and
+// compiled code no longer looks at the term at all
+fn nested_applications(depth: usize) -> Term {
+    let mut body = "0".to_string();
+    for _ in 0..depth {
+        body = format!("f ({})", body);
+    }
+    let input = format!("(lambda f:Nat->Nat.{}) (lambda n:Nat.succ(n));", body);
+    Parser::new().parse(&input).unwrap()
+}
+
+fn bench_evaluators(c: &mut Criterion) {
+    for depth in [10, 100] {
+        let term = nested_applications(depth);
+
+        let mut group = c.benchmark_group(format!("nested applications {}", depth));
+        group.bench_function("substitution", |b| b.iter(|| eval(&term).unwrap()));
+        group.bench_function("closure", |b| {
+            b.iter(|| closure::eval(&Env::default(), &term).unwrap().to_term())
+        });
+        let code = compile(&term);
+        group.bench_function("compiled", |b| {
+            b.iter(|| code.run(&compile::Env::default()).unwrap().to_term())
+        });
+        group.bench_function("compile and run", |b| {
+            b.iter(|| compile::eval(&term).unwrap().to_term())
+        });
+        let program = Program::compile(&term);
+        group.bench_function("bytecode", |b| {
+            b.iter(|| vm::run(&program).unwrap().to_term())
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_evaluators);
+criterion_main!(benches);
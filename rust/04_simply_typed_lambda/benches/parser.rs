@@ -0,0 +1,32 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use simply_typed_lambda::context::Context;
+use simply_typed_lambda::parser::Parser;
+use simply_typed_lambda::typing::type_of;
+
+// `lambda x:Nat.succ(succ(... x))`, the parser accepts at most 256 nested
+// terms
+fn succ_chain(depth: usize) -> String {
+    format!(
+        "lambda x:Nat.{}x{};",
+        "succ(".repeat(depth),
+        ")".repeat(depth)
+    )
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let input = succ_chain(120);
+    c.bench_function("parse succ chain 120", |b| {
+        b.iter(|| Parser::new().parse(&input).unwrap())
+    });
+    c.bench_function("parse with spans succ chain 120", |b| {
+        b.iter(|| Parser::new().parse_with_spans(&input).unwrap())
+    });
+    let term = Parser::new().parse(&input).unwrap();
+    c.bench_function("type succ chain 120", |b| {
+        b.iter(|| type_of(&mut Context::default(), &term).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
@@ -0,0 +1,370 @@
+use std::rc::Rc;
+
+use proptest::prelude::*;
+use tapl_frontend::intern::Symbol;
+
+use crate::{parser::Term, typing::Type};
+
+const LABELS: [&str; 3] = ["a", "b", "c"];
+
+fn labeled<T>(items: Vec<T>) -> Vec<(Symbol, T)> {
+    LABELS
+        .iter()
+        .map(|&label| Symbol::intern(label))
+        .zip(items)
+        .collect()
+}
+
+impl Arbitrary for Type {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Type>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let leaf = prop_oneof![
+            Just(Type::Boolean),
+            Just(Type::Number),
+            Just(Type::String),
+            Just(Type::Float)
+        ];
+        leaf.prop_recursive(3, 12, 3, |inner| {
+            prop_oneof![
+                (inner.clone(), inner.clone())
+                    .prop_map(|(param, result)| Type::Arrow(Box::new(param), Box::new(result))),
+                prop::collection::vec(inner.clone(), 1..3).prop_map(Type::Product),
+                prop::collection::vec(inner.clone(), 1..3)
+                    .prop_map(|types| Type::Record(labeled(types))),
+                (inner.clone(), inner.clone())
+                    .prop_map(|(inl, inr)| Type::Sum(Box::new(inl), Box::new(inr))),
+                prop::collection::vec(inner.clone(), 1..3)
+                    .prop_map(|types| Type::Variant(labeled(types))),
+                inner.prop_map(|element| Type::List(Box::new(element))),
+            ]
+        })
+        .boxed()
+    }
+}
+
+// Wraps variables around into the binders above them, a variable with no
+// binder in reach becomes `true`
+fn scope(term: &mut Term, binders: usize) {
+    match term {
+        Term::TmVar(_) if binders == 0 => *term = Term::TmTrue,
+        Term::TmVar(index) => *index %= binders,
+        Term::TmAbs(_, _, body) => scope(Rc::make_mut(body), binders + 1),
+        Term::TmCase(term, _, inl_term, _, inr_term) => {
+            scope(Rc::make_mut(term), binders);
+            scope(Rc::make_mut(inl_term), binders + 1);
+            scope(Rc::make_mut(inr_term), binders + 1);
+        }
+        Term::TmVariantCase(term, branches) => {
+            scope(Rc::make_mut(term), binders);
+            for (_, _, branch) in branches {
+                scope(branch, binders + 1);
+            }
+        }
+        Term::TmSucc(term)
+        | Term::TmProj(term, _)
+        | Term::TmRecordProj(term, _)
+        | Term::TmInl(term, _)
+        | Term::TmInr(term, _)
+        | Term::TmTag(_, term, _)
+        | Term::TmIsNil(_, term)
+        | Term::TmHead(_, term)
+        | Term::TmTail(_, term) => scope(Rc::make_mut(term), binders),
+        Term::TmApp(left, right) | Term::TmCons(_, left, right) => {
+            scope(Rc::make_mut(left), binders);
+            scope(Rc::make_mut(right), binders);
+        }
+        Term::TmIf(if_term, then_term, else_term) => {
+            scope(Rc::make_mut(if_term), binders);
+            scope(Rc::make_mut(then_term), binders);
+            scope(Rc::make_mut(else_term), binders);
+        }
+        Term::TmTuple(terms) => terms.iter_mut().for_each(|term| scope(term, binders)),
+        Term::TmRecord(fields) => fields.iter_mut().for_each(|(_, term)| scope(term, binders)),
+        Term::TmTrue
+        | Term::TmFalse
+        | Term::TmZero
+        | Term::TmNumber(_)
+        | Term::TmString(_)
+        | Term::TmFloat(_)
+        | Term::TmNil(_)
+        | Term::TmGlobal(_, _)
+        | Term::TmPrim(_) => {}
+    }
+}
+
+// Well-scoped terms that are mostly ill-typed, the parameter is the number of
+// free variables a term may use
+impl Arbitrary for Term {
+    type Parameters = usize;
+    type Strategy = BoxedStrategy<Term>;
+
+    fn arbitrary_with(free: usize) -> Self::Strategy {
+        let typ = any::<Type>;
+        let boxed = |term: Term| Rc::new(term);
+        let leaf = prop_oneof![
+            Just(Term::TmTrue),
+            Just(Term::TmFalse),
+            Just(Term::TmZero),
+            (1..1000u64).prop_map(Term::TmNumber),
+            "[a-z ]{0,3}".prop_map(Term::TmString),
+            (0..40u32).prop_map(|n| Term::TmFloat(f64::from(n) / 4.0)),
+            (0..4usize).prop_map(Term::TmVar),
+            typ().prop_map(Term::TmNil),
+        ];
+        leaf.prop_recursive(4, 32, 3, move |inner| {
+            prop_oneof![
+                inner
+                    .clone()
+                    .prop_map(move |term| Term::TmSucc(boxed(term))),
+                (typ(), inner.clone())
+                    .prop_map(move |(typ, body)| { Term::TmAbs("x".into(), typ, boxed(body)) }),
+                (inner.clone(), inner.clone())
+                    .prop_map(move |(left, right)| Term::TmApp(boxed(left), boxed(right))),
+                (inner.clone(), inner.clone(), inner.clone())
+                    .prop_map(move |(a, b, c)| { Term::TmIf(boxed(a), boxed(b), boxed(c)) }),
+                prop::collection::vec(inner.clone(), 0..3).prop_map(Term::TmTuple),
+                (inner.clone(), 0..4usize)
+                    .prop_map(move |(term, index)| Term::TmProj(boxed(term), index)),
+                prop::collection::vec(inner.clone(), 1..3)
+                    .prop_map(|terms| Term::TmRecord(labeled(terms))),
+                (inner.clone(), prop::sample::select(&LABELS[..]))
+                    .prop_map(move |(term, label)| Term::TmRecordProj(boxed(term), label.into())),
+                (inner.clone(), typ(), any::<bool>()).prop_map(move |(term, typ, left)| {
+                    if left {
+                        Term::TmInl(boxed(term), typ)
+                    } else {
+                        Term::TmInr(boxed(term), typ)
+                    }
+                }),
+                (inner.clone(), inner.clone(), inner.clone()).prop_map(move |(a, b, c)| {
+                    Term::TmCase(boxed(a), "l".into(), boxed(b), "r".into(), boxed(c))
+                }),
+                (prop::sample::select(&LABELS[..]), inner.clone(), typ()).prop_map(
+                    move |(label, term, typ)| Term::TmTag(label.into(), boxed(term), typ)
+                ),
+                (inner.clone(), prop::collection::vec(inner.clone(), 1..3)).prop_map(
+                    move |(term, branches)| {
+                        let branches = labeled(branches)
+                            .into_iter()
+                            .map(|(label, branch)| (label, "x".into(), branch));
+                        Term::TmVariantCase(boxed(term), branches.collect())
+                    }
+                ),
+                (typ(), inner.clone(), inner.clone()).prop_map(move |(typ, head, tail)| {
+                    Term::TmCons(typ, boxed(head), boxed(tail))
+                }),
+                (typ(), inner, 0..3).prop_map(move |(typ, term, operation)| match operation {
+                    0 => Term::TmIsNil(typ, boxed(term)),
+                    1 => Term::TmHead(typ, boxed(term)),
+                    _ => Term::TmTail(typ, boxed(term)),
+                }),
+            ]
+        })
+        .prop_map(move |mut term| {
+            scope(&mut term, free);
+            term
+        })
+        .boxed()
+    }
+}
+
+// The ways of building a term of a given type, introduction forms follow
+// the type and elimination forms pick the type of the eliminated term
+#[derive(Clone, Debug)]
+enum Form {
+    Var(usize),
+    Intro(usize),
+    If,
+    App(Type),
+    Proj,
+    RecordProj,
+    Case,
+    VariantCase,
+}
+
+fn forms(ctx: &[Type], typ: &Type, depth: u32) -> Vec<Form> {
+    let mut forms = ctx
+        .iter()
+        .rev()
+        .enumerate()
+        .filter(|(_, bound)| *bound == typ)
+        .map(|(index, _)| Form::Var(index))
+        .collect::<Vec<_>>();
+    let intros = match typ {
+        Type::Variant(fields) => fields.len(),
+        Type::Boolean | Type::Sum(_, _) => 2,
+        // 0, a numeral and `succ`
+        Type::Number if depth > 0 => 3,
+        Type::Number | Type::List(_) if depth > 0 => 2,
+        _ => 1,
+    };
+    forms.extend((0..intros).map(Form::Intro));
+    if depth > 0 {
+        forms.extend(vec![
+            Form::If,
+            Form::App(Type::Boolean),
+            Form::App(Type::Number),
+            Form::Proj,
+            Form::RecordProj,
+            Form::Case,
+            Form::VariantCase,
+        ]);
+    }
+    forms
+}
+
+// Terms of type `typ` in a context binding `ctx`, the innermost binding last.
+// `head` and `tail` are left out, they get stuck on an empty list.
+pub(crate) fn typed_term(ctx: Vec<Type>, typ: Type, depth: u32) -> BoxedStrategy<Term> {
+    prop::sample::select(forms(&ctx, &typ, depth))
+        .prop_flat_map(move |form| build(&ctx, &typ, depth, form))
+        .boxed()
+}
+
+fn build(ctx: &[Type], typ: &Type, depth: u32, form: Form) -> BoxedStrategy<Term> {
+    let below = depth.saturating_sub(1);
+    let term = |typ: &Type| typed_term(ctx.to_vec(), typ.clone(), below);
+    let bound = |param: &Type, typ: &Type| {
+        let mut ctx = ctx.to_vec();
+        ctx.push(param.clone());
+        typed_term(ctx, typ.clone(), below)
+    };
+    let boxed = |term: Term| Rc::new(term);
+    let sum = Type::Sum(Box::new(Type::Number), Box::new(Type::Boolean));
+    let variant = Type::Variant(labeled(vec![Type::Number, Type::Boolean]));
+    match (form, typ.clone()) {
+        (Form::Var(index), _) => Just(Term::TmVar(index)).boxed(),
+        (Form::Intro(_), Type::Named(_)) => unreachable!("generated types name no alias"),
+        (Form::Intro(_), Type::Var(_)) => unreachable!("generated types have no type variable"),
+        (Form::Intro(0), Type::Boolean) => Just(Term::TmTrue).boxed(),
+        (Form::Intro(_), Type::Boolean) => Just(Term::TmFalse).boxed(),
+        (Form::Intro(0), Type::Number) => Just(Term::TmZero).boxed(),
+        (Form::Intro(1), Type::Number) => (1..1000u64).prop_map(Term::TmNumber).boxed(),
+        (Form::Intro(_), Type::Number) => term(typ)
+            .prop_map(move |term| Term::TmSucc(boxed(term)))
+            .boxed(),
+        (Form::Intro(_), Type::String) => "[a-z ]{0,3}".prop_map(Term::TmString).boxed(),
+        (Form::Intro(_), Type::Float) => (0..40u32)
+            .prop_map(|n| Term::TmFloat(f64::from(n) / 4.0))
+            .boxed(),
+        (Form::Intro(_), Type::Arrow(param, result)) => bound(&param, &result)
+            .prop_map(move |body| Term::TmAbs("x".into(), param.as_ref().clone(), boxed(body)))
+            .boxed(),
+        (Form::Intro(_), Type::Product(types)) => types
+            .iter()
+            .map(term)
+            .collect::<Vec<_>>()
+            .prop_map(Term::TmTuple)
+            .boxed(),
+        (Form::Intro(_), Type::Record(fields)) => {
+            let labels = fields.iter().map(|(label, _)| *label).collect::<Vec<_>>();
+            fields
+                .iter()
+                .map(|(_, typ)| term(typ))
+                .collect::<Vec<_>>()
+                .prop_map(move |terms| Term::TmRecord(labels.iter().cloned().zip(terms).collect()))
+                .boxed()
+        }
+        (Form::Intro(0), Type::Sum(inl, _)) => {
+            let typ = typ.clone();
+            term(&inl)
+                .prop_map(move |term| Term::TmInl(boxed(term), typ.clone()))
+                .boxed()
+        }
+        (Form::Intro(_), Type::Sum(_, inr)) => {
+            let typ = typ.clone();
+            term(&inr)
+                .prop_map(move |term| Term::TmInr(boxed(term), typ.clone()))
+                .boxed()
+        }
+        (Form::Intro(i), Type::Variant(fields)) => {
+            let label = fields[i].0;
+            let typ = typ.clone();
+            term(&fields[i].1)
+                .prop_map(move |term| Term::TmTag(label, boxed(term), typ.clone()))
+                .boxed()
+        }
+        (Form::Intro(0), Type::List(element)) => Just(Term::TmNil(*element)).boxed(),
+        (Form::Intro(_), Type::List(element)) => (term(&element), term(typ))
+            .prop_map(move |(head, tail)| {
+                Term::TmCons(element.as_ref().clone(), boxed(head), boxed(tail))
+            })
+            .boxed(),
+        (Form::If, _) => (term(&Type::Boolean), term(typ), term(typ))
+            .prop_map(move |(a, b, c)| Term::TmIf(boxed(a), boxed(b), boxed(c)))
+            .boxed(),
+        (Form::App(param), _) => {
+            let function = Type::Arrow(Box::new(param.clone()), Box::new(typ.clone()));
+            (term(&function), term(&param))
+                .prop_map(move |(left, right)| Term::TmApp(boxed(left), boxed(right)))
+                .boxed()
+        }
+        (Form::Proj, _) => term(&Type::Product(vec![typ.clone(), Type::Boolean]))
+            .prop_map(move |tuple| Term::TmProj(boxed(tuple), 1))
+            .boxed(),
+        (Form::RecordProj, _) => term(&Type::Record(labeled(vec![typ.clone()])))
+            .prop_map(move |record| Term::TmRecordProj(boxed(record), "a".into()))
+            .boxed(),
+        (Form::Case, _) => (
+            term(&sum),
+            bound(&Type::Number, typ),
+            bound(&Type::Boolean, typ),
+        )
+            .prop_map(move |(a, b, c)| {
+                Term::TmCase(boxed(a), "n".into(), boxed(b), "b".into(), boxed(c))
+            })
+            .boxed(),
+        // the branches in the other order than the labels of the type
+        (Form::VariantCase, _) => (
+            term(&variant),
+            bound(&Type::Boolean, typ),
+            bound(&Type::Number, typ),
+        )
+            .prop_map(move |(a, b, c)| {
+                let branches = vec![("b".into(), "y".into(), b), ("a".into(), "x".into(), c)];
+                Term::TmVariantCase(boxed(a), branches)
+            })
+            .boxed(),
+    }
+}
+
+// a closed well-typed term together with its type
+pub(crate) fn well_typed() -> BoxedStrategy<(Term, Type)> {
+    any::<Type>()
+        .prop_flat_map(|typ| (typed_term(vec![], typ.clone(), 3), Just(typ)))
+        .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        closure::{self, Env},
+        context::Context,
+        typing::type_of,
+    };
+
+    proptest! {
+        #[test]
+        fn test_type_of_never_panics(term in any_with::<Term>(2)) {
+            let _ = type_of(&mut Context::default(), &term);
+        }
+
+        #[test]
+        fn test_generated_terms_are_well_typed((term, typ) in well_typed()) {
+            prop_assert_eq!(type_of(&mut Context::default(), &term), Ok(typ));
+        }
+
+        // progress and preservation (TAPL 9.3): a well-typed term evaluates to
+        // a value of the same type
+        #[test]
+        fn test_well_typed_terms_evaluate((term, typ) in well_typed()) {
+            let value = closure::eval(&Env::default(), &term);
+            prop_assert!(value.is_ok(), "{} failed with {:?}", term, value);
+            let value = value.unwrap().to_term();
+            prop_assert_eq!(type_of(&mut Context::default(), &value), Ok(typ));
+        }
+    }
+}
@@ -1,148 +1,695 @@
-use nom::{
-    branch::alt,
-    bytes::complete::{tag, tag_no_case},
-    character::complete::{multispace0, one_of},
-    error::{context, VerboseError},
-    multi::many1,
-    sequence::tuple,
+use tapl_frontend::{
+    intern::Symbol,
+    lexer::{Lexer, SyntaxError, Token, MAX_DEPTH},
 };
 
-use misc::ALPHABET;
+use crate::{
+    span::{Span, Spanned},
+    type_parser::{parse_type, parse_type_name},
+    typing::Type,
+};
+
+// every subterm carries the span of source text it was parsed from
+pub type SpannedTerm = Spanned<ASTTerm>;
 
-use crate::{parser::IResult, type_parser::parse_type, typing::Type};
+// what `let p = t1 in t2` binds (TAPL 11.8): a variable, or a tuple or
+// record whose components are matched against patterns in turn
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Pattern {
+    Var(Symbol),
+    Tuple(Vec<Pattern>),
+    // labeled fields in the order they are matched
+    Record(Vec<(Symbol, Pattern)>),
+}
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ASTTerm {
     TmTrue,
     TmFalse,
     TmZero,
-    TmSucc(Box<ASTTerm>),
-    TmVar(String),
-    // argument ident, argument type and body
-    TmAbs(String, Type, Box<ASTTerm>),
-    TmApp(Box<ASTTerm>, Box<ASTTerm>),
+    // a numeral other than `0`, written in decimal
+    TmNumber(u64),
+    TmSucc(Box<SpannedTerm>),
+    TmString(String),
+    TmFloat(f64),
+    TmVar(Symbol),
+    // argument ident, argument type unless it is left to inference and body
+    TmAbs(Symbol, Option<Type>, Box<SpannedTerm>),
+    TmApp(Box<SpannedTerm>, Box<SpannedTerm>),
     // condition term, then term, else term
-    TmIf(Box<ASTTerm>, Box<ASTTerm>, Box<ASTTerm>),
+    TmIf(Box<SpannedTerm>, Box<SpannedTerm>, Box<SpannedTerm>),
+    TmTuple(Vec<SpannedTerm>),
+    // tuple term and 1-based index
+    TmProj(Box<SpannedTerm>, usize),
+    // labeled fields in declaration order
+    TmRecord(Vec<(Symbol, SpannedTerm)>),
+    // record term and field label
+    TmRecordProj(Box<SpannedTerm>, Symbol),
+    // injected term and the sum type it is injected into
+    TmInl(Box<SpannedTerm>, Type),
+    TmInr(Box<SpannedTerm>, Type),
+    // scrutinee, inl binder and branch, inr binder and branch
+    TmCase(
+        Box<SpannedTerm>,
+        Symbol,
+        Box<SpannedTerm>,
+        Symbol,
+        Box<SpannedTerm>,
+    ),
+    // `<l=t> as T`, label, tagged term and the variant type, which only the
+    // tags `elaborate` makes leave to inference
+    TmTag(Symbol, Box<SpannedTerm>, Option<Type>),
+    // scrutinee and the label, binder and branch of every alternative
+    TmVariantCase(Box<SpannedTerm>, Vec<(Symbol, Symbol, SpannedTerm)>),
+    // element type annotation on every list operation
+    TmNil(Type),
+    TmCons(Type, Box<SpannedTerm>, Box<SpannedTerm>),
+    TmIsNil(Type, Box<SpannedTerm>),
+    TmHead(Type, Box<SpannedTerm>),
+    TmTail(Type, Box<SpannedTerm>),
+    // derived forms of the surface syntax, `elaborate` lowers them into the
+    // constructs above
+    // `(t1; t2)`
+    TmSeq(Box<SpannedTerm>, Box<SpannedTerm>),
+    // `let p:T = t1 in t2`, pattern, its optional type, bound term and body
+    TmLet(Pattern, Option<Type>, Box<SpannedTerm>, Box<SpannedTerm>),
+    // `lambda x:S y:T.t`, two or more parameters
+    TmMultiAbs(Vec<(Symbol, Option<Type>)>, Box<SpannedTerm>),
+    // `not(t)`, `and(t1, t2)` and `or(t1, t2)`
+    TmNot(Box<SpannedTerm>),
+    TmAnd(Box<SpannedTerm>, Box<SpannedTerm>),
+    TmOr(Box<SpannedTerm>, Box<SpannedTerm>),
+    // `none[T]` and `some t`, the tags of `Option T`
+    TmNone(Type),
+    TmSome(Box<SpannedTerm>),
 }
 
-impl From<&str> for ASTTerm {
-    fn from(i: &str) -> Self {
-        match i.to_lowercase().as_str() {
-            "true" => ASTTerm::TmTrue,
-            "false" => ASTTerm::TmFalse,
-            "0" => ASTTerm::TmZero,
-            _ => unimplemented!("no other value term supported"),
+impl ASTTerm {
+    // the direct subterms, in source order
+    fn subterms(&self) -> Vec<&SpannedTerm> {
+        match self {
+            ASTTerm::TmTrue
+            | ASTTerm::TmFalse
+            | ASTTerm::TmZero
+            | ASTTerm::TmNumber(_)
+            | ASTTerm::TmString(_)
+            | ASTTerm::TmFloat(_)
+            | ASTTerm::TmVar(_)
+            | ASTTerm::TmNil(_)
+            | ASTTerm::TmNone(_) => vec![],
+            ASTTerm::TmSucc(term)
+            | ASTTerm::TmAbs(_, _, term)
+            | ASTTerm::TmProj(term, _)
+            | ASTTerm::TmRecordProj(term, _)
+            | ASTTerm::TmInl(term, _)
+            | ASTTerm::TmInr(term, _)
+            | ASTTerm::TmTag(_, term, _)
+            | ASTTerm::TmIsNil(_, term)
+            | ASTTerm::TmHead(_, term)
+            | ASTTerm::TmTail(_, term)
+            | ASTTerm::TmMultiAbs(_, term)
+            | ASTTerm::TmNot(term)
+            | ASTTerm::TmSome(term) => vec![term],
+            ASTTerm::TmApp(left, right)
+            | ASTTerm::TmCons(_, left, right)
+            | ASTTerm::TmSeq(left, right)
+            | ASTTerm::TmLet(_, _, left, right)
+            | ASTTerm::TmAnd(left, right)
+            | ASTTerm::TmOr(left, right) => vec![left, right],
+            ASTTerm::TmIf(condition, then_term, else_term) => vec![condition, then_term, else_term],
+            ASTTerm::TmTuple(terms) => terms.iter().collect(),
+            ASTTerm::TmRecord(fields) => fields.iter().map(|(_, term)| term).collect(),
+            ASTTerm::TmCase(term, _, inl_term, _, inr_term) => vec![term, inl_term, inr_term],
+            ASTTerm::TmVariantCase(term, branches) => std::iter::once(term.as_ref())
+                .chain(branches.iter().map(|(_, _, branch)| branch))
+                .collect(),
         }
     }
 }
 
-fn parse_value(input: &str) -> IResult<&str, ASTTerm> {
-    //println!("parse_value {:?}", input);
-    context(
-        "parse_value",
-        alt((tag("true"), tag("false"), tag_no_case("0"))),
-    )(input)
-    .map(|(next_input, res)| (next_input, res.into()))
-}
-
-fn parse_succ(input: &str) -> IResult<&str, ASTTerm> {
-    context(
-        "parse_succ",
-        tuple((tag("succ"), tag("("), parse_term, tag(")"))),
-    )(input)
-    .map(|(next_input, (_, _, term, _))| (next_input, ASTTerm::TmSucc(Box::new(term))))
-}
-
-fn parse_ident(input: &str) -> IResult<&str, ASTTerm> {
-    //println!("parse_ident {:?}", input);
-    context("parse_ident", tuple((multispace0, one_of(ALPHABET))))(input)
-        .map(|(next_input, (_, res))| (next_input, ASTTerm::TmVar(res.to_string())))
-}
-
-fn parse_if(input: &str) -> IResult<&str, ASTTerm> {
-    context(
-        "parse_if",
-        tuple((
-            tag("if "),
-            parse_term,
-            tag(" then "),
-            parse_term,
-            tag(" else "),
-            parse_term,
-        )),
-    )(input)
-    .map(|(next_input, (_, condition, _, then_term, _, else_term))| {
-        (
-            next_input,
-            ASTTerm::TmIf(
-                Box::new(condition),
-                Box::new(then_term),
-                Box::new(else_term),
-            ),
-        )
+pub const KEYWORDS: [&str; 27] = [
+    "true", "false", "succ", "if", "then", "else", "lambda", "inl", "inr", "as", "case", "of",
+    "nil", "cons", "isnil", "head", "tail", "let", "in", "import", "not", "and", "or", "type",
+    "unit", "none", "some",
+];
+
+// keywords that begin an atom, the others only appear inside a construct
+const ATOM_KEYWORDS: [&str; 17] = [
+    "true", "false", "succ", "if", "inl", "inr", "case", "nil", "cons", "isnil", "head", "not",
+    "and", "or", "unit", "none", "some",
+];
+
+type ParseResult = Result<SpannedTerm, SyntaxError>;
+
+fn spanned(node: ASTTerm, start: usize, lexer: &Lexer) -> SpannedTerm {
+    Spanned {
+        node,
+        span: Span::new(start, lexer.offset()),
+    }
+}
+
+fn starts_atom(lexer: &mut Lexer) -> Result<bool, SyntaxError> {
+    let (token, _) = lexer.peek()?;
+    Ok(match token {
+        Token::Word(_) => {
+            !KEYWORDS
+                .iter()
+                .any(|keyword| lexer.is_keyword(token, keyword))
+                || ATOM_KEYWORDS
+                    .iter()
+                    .chain(&["tail"])
+                    .any(|keyword| lexer.is_keyword(token, keyword))
+        }
+        Token::Number(_)
+        | Token::Str(_)
+        | Token::Symbol("(")
+        | Token::Symbol("{")
+        | Token::Symbol("<") => true,
+        _ => false,
     })
 }
 
-fn parse_atom(input: &str) -> IResult<&str, ASTTerm> {
-    //println!("parse_atom {:?}", input);
-    context(
-        "parse_atom",
-        alt((
-            parse_value,
-            parse_succ,
-            parse_ident,
-            parse_if,
-            parse_parent_term,
-        )),
-    )(input)
-    .map(|(next_input, res)| (next_input, res))
-}
-
-fn parse_parent_term(input: &str) -> IResult<&str, ASTTerm> {
-    //println!("parse_paren_term {:?}", input);
-    context("parse_parent_term", tuple((tag("("), parse_term, tag(")"))))(input)
-        .map(|(next_input, (_, term, _))| (next_input, term))
-}
-
-fn parse_abstraction(input: &str) -> IResult<&str, ASTTerm> {
-    println!("parse_abstraction: {:?}", input);
-    context(
-        "parse_abstraction",
-        tuple((
-            tag("lambda "),
-            one_of(ALPHABET),
-            tag_no_case(":"),
-            parse_type,
-            tag("."),
-            parse_term,
-        )),
-    )(input)
-    .map(|(next_input, (_, param, _, typ, _, body))| {
-        println!("param: {:?}, typ: {:?}", param, typ);
-        (
-            next_input,
-            ASTTerm::TmAbs(param.to_string(), typ, Box::new(body)),
-        )
+fn parse_binder(lexer: &mut Lexer) -> Result<Symbol, SyntaxError> {
+    lexer
+        .expect_word("a variable", &KEYWORDS)
+        .map(|(name, _)| Symbol::intern(name))
+}
+
+// `name[T]`, the element type of a list operation or of `none`
+fn parse_element_type(lexer: &mut Lexer) -> Result<Type, SyntaxError> {
+    lexer.expect_symbol("[")?;
+    let typ = parse_type(lexer)?;
+    lexer.expect_symbol("]")?;
+    Ok(typ)
+}
+
+fn parse_if(lexer: &mut Lexer) -> Result<ASTTerm, SyntaxError> {
+    let condition = parse_term(lexer)?;
+    lexer.expect_keyword("then")?;
+    let then_term = parse_term(lexer)?;
+    lexer.expect_keyword("else")?;
+    let else_term = parse_term(lexer)?;
+    Ok(ASTTerm::TmIf(
+        Box::new(condition),
+        Box::new(then_term),
+        Box::new(else_term),
+    ))
+}
+
+fn parse_case(lexer: &mut Lexer) -> Result<ASTTerm, SyntaxError> {
+    let term = parse_term(lexer)?;
+    lexer.expect_keyword("of")?;
+    if lexer.peek()?.0 == Token::Symbol("<") {
+        return parse_variant_branches(lexer, term);
+    }
+    lexer.expect_keyword("inl")?;
+    let inl_name = parse_binder(lexer)?;
+    lexer.expect_symbol("=>")?;
+    let inl_term = parse_term(lexer)?;
+    lexer.expect_symbol("|")?;
+    lexer.expect_keyword("inr")?;
+    let inr_name = parse_binder(lexer)?;
+    lexer.expect_symbol("=>")?;
+    let inr_term = parse_term(lexer)?;
+    Ok(ASTTerm::TmCase(
+        Box::new(term),
+        inl_name,
+        Box::new(inl_term),
+        inr_name,
+        Box::new(inr_term),
+    ))
+}
+
+// `<l=x> => t | ...` after the `of` of a case on a variant
+fn parse_variant_branches(lexer: &mut Lexer, term: SpannedTerm) -> Result<ASTTerm, SyntaxError> {
+    let mut branches = vec![];
+    loop {
+        lexer.expect_symbol("<")?;
+        let (label, _) = lexer.expect_word("a label", &[])?;
+        lexer.expect_symbol("=")?;
+        let name = parse_binder(lexer)?;
+        lexer.expect_symbol(">")?;
+        lexer.expect_symbol("=>")?;
+        branches.push((Symbol::intern(label), name, parse_term(lexer)?));
+        if lexer.eat_symbol("|")?.is_none() {
+            break;
+        }
+    }
+    Ok(ASTTerm::TmVariantCase(Box::new(term), branches))
+}
+
+// `<l=t> as T` after the opening `<`
+fn parse_tag(lexer: &mut Lexer) -> Result<ASTTerm, SyntaxError> {
+    let (label, _) = lexer.expect_word("a label", &[])?;
+    lexer.expect_symbol("=")?;
+    let term = Box::new(parse_term(lexer)?);
+    lexer.expect_symbol(">")?;
+    lexer.expect_keyword("as")?;
+    Ok(ASTTerm::TmTag(
+        Symbol::intern(label),
+        term,
+        Some(parse_type(lexer)?),
+    ))
+}
+
+// `{t, ...}` or `{l=t, ...}`, a record starts with a label and `=`
+fn parse_braces(lexer: &mut Lexer) -> Result<ASTTerm, SyntaxError> {
+    let mut ahead = lexer.clone();
+    let is_record =
+        matches!(ahead.next_token()?.0, Token::Word(_)) && ahead.eat_symbol("=")?.is_some();
+    if is_record {
+        let mut fields = vec![];
+        loop {
+            let (label, _) = lexer.expect_word("a label", &[])?;
+            lexer.expect_symbol("=")?;
+            fields.push((Symbol::intern(label), parse_term(lexer)?));
+            if lexer.eat_symbol(",")?.is_none() {
+                break;
+            }
+        }
+        lexer.expect_symbol("}")?;
+        return Ok(ASTTerm::TmRecord(fields));
+    }
+    let mut terms = vec![];
+    if lexer.eat_symbol("}")?.is_none() {
+        loop {
+            terms.push(parse_term(lexer)?);
+            if lexer.eat_symbol(",")?.is_none() {
+                break;
+            }
+        }
+        lexer.expect_symbol("}")?;
+    }
+    Ok(ASTTerm::TmTuple(terms))
+}
+
+// `succ(t)`, `not(t)`, `and(t1, t2)` and `or(t1, t2)` after the keyword
+fn parse_operator(lexer: &mut Lexer, keyword: &str) -> Result<ASTTerm, SyntaxError> {
+    lexer.expect_symbol("(")?;
+    let first = Box::new(parse_term(lexer)?);
+    let second = if keyword == "and" || keyword == "or" {
+        lexer.expect_symbol(",")?;
+        Some(Box::new(parse_term(lexer)?))
+    } else {
+        None
+    };
+    lexer.expect_symbol(")")?;
+    Ok(match (keyword, second) {
+        ("succ", _) => ASTTerm::TmSucc(first),
+        ("and", Some(second)) => ASTTerm::TmAnd(first, second),
+        (_, Some(second)) => ASTTerm::TmOr(first, second),
+        _ => ASTTerm::TmNot(first),
+    })
+}
+
+// `inl t as T` or `inr t as T` after the keyword
+fn parse_injection(lexer: &mut Lexer, keyword: &str) -> Result<ASTTerm, SyntaxError> {
+    let term = Box::new(parse_term(lexer)?);
+    lexer.expect_keyword("as")?;
+    let typ = parse_type(lexer)?;
+    Ok(if keyword == "inl" {
+        ASTTerm::TmInl(term, typ)
+    } else {
+        ASTTerm::TmInr(term, typ)
     })
 }
 
-fn parse_application(input: &str) -> IResult<&str, ASTTerm> {
-    println!("parse_application {:?}", input);
-    context("parse_application", many1(parse_atom))(input).map(|(next_input, vars)| {
-        //println!("vars: {:?}", vars);
-        let mut lhs = Box::new(vars[0].clone());
-        let mut i = 1;
-        while i < vars.len() {
-            let rhs = Box::new(vars[i].clone());
-            lhs = Box::new(ASTTerm::TmApp(lhs, rhs));
-            i += 1;
+// `cons[T] t1 t2`, `isnil[T] t`, `head[T] t` or `tail[T] t` after the keyword
+fn parse_list_operation(lexer: &mut Lexer, keyword: &str) -> Result<ASTTerm, SyntaxError> {
+    let typ = parse_element_type(lexer)?;
+    let term = Box::new(parse_atom(lexer)?);
+    Ok(match keyword {
+        "cons" => ASTTerm::TmCons(typ, term, Box::new(parse_atom(lexer)?)),
+        "isnil" => ASTTerm::TmIsNil(typ, term),
+        "head" => ASTTerm::TmHead(typ, term),
+        _ => ASTTerm::TmTail(typ, term),
+    })
+}
+
+// Every construct that nests terms goes through its own function, so each
+// level of nesting only takes the stack that construct needs
+fn parse_keyword_atom(lexer: &mut Lexer, keyword: &str) -> Result<ASTTerm, SyntaxError> {
+    match keyword {
+        "true" => Ok(ASTTerm::TmTrue),
+        "false" => Ok(ASTTerm::TmFalse),
+        "succ" | "not" | "and" | "or" => parse_operator(lexer, keyword),
+        "if" => parse_if(lexer),
+        "inl" | "inr" => parse_injection(lexer, keyword),
+        "case" => parse_case(lexer),
+        "nil" => parse_element_type(lexer).map(ASTTerm::TmNil),
+        // the unit value is the empty tuple (TAPL 11.2)
+        "unit" => Ok(ASTTerm::TmTuple(vec![])),
+        "none" => parse_element_type(lexer).map(ASTTerm::TmNone),
+        "some" => parse_atom(lexer).map(|term| ASTTerm::TmSome(Box::new(term))),
+        _ => parse_list_operation(lexer, keyword),
+    }
+}
+
+// `(t1; ...; tn)` after the opening parenthesis, a single term is just
+// grouped
+fn parse_parenthesized(lexer: &mut Lexer) -> Result<ASTTerm, SyntaxError> {
+    let mut terms = vec![parse_term(lexer)?];
+    while lexer.eat_symbol(";")?.is_some() {
+        lexer.deepen()?;
+        terms.push(parse_term(lexer)?);
+    }
+    lexer.expect_symbol(")")?;
+    let last = terms.pop().unwrap();
+    let end = last.span.end;
+    let term = terms.into_iter().rev().fold(last, |rest, term| Spanned {
+        span: Span::new(term.span.start, end),
+        node: ASTTerm::TmSeq(Box::new(term), Box::new(rest)),
+    });
+    Ok(term.node)
+}
+
+// `(t1; ...; tn)`, `{...}` or `<l=t> as T` after the opening symbol, one
+// call in `parse_atom` for the three of them saves it stack
+fn parse_delimited(lexer: &mut Lexer, symbol: &str) -> Result<ASTTerm, SyntaxError> {
+    match symbol {
+        "(" => parse_parenthesized(lexer),
+        "{" => parse_braces(lexer),
+        _ => parse_tag(lexer),
+    }
+}
+
+// whether the number ending at `span` goes on with `.` and digits, as in
+// `2.5`. With a space in between the `.` is a projection.
+fn starts_fraction(lexer: &Lexer, span: Span) -> bool {
+    let mut rest = lexer.source()[span.end..].chars();
+    rest.next() == Some('.') && rest.next().is_some_and(|c| c.is_ascii_digit())
+}
+
+// `2.5`, the digits on either side of the point are separate tokens
+fn parse_float(lexer: &mut Lexer) -> Result<ASTTerm, SyntaxError> {
+    let (whole, span) = lexer.expect_number()?;
+    lexer.expect_symbol(".")?;
+    let (fraction, _) = lexer.expect_number()?;
+    let literal = format!("{}.{}", whole, fraction);
+    let value = literal
+        .parse::<f64>()
+        .map_err(|_| lexer.error_at("a float", &format!("`{}`", literal), span))?;
+    Ok(ASTTerm::TmFloat(value))
+}
+
+// a decimal numeral, `0` stays the constant of the book
+fn parse_number(lexer: &mut Lexer) -> Result<ASTTerm, SyntaxError> {
+    let (digits, span) = lexer.expect_number()?;
+    match digits.parse::<u64>() {
+        Ok(0) => Ok(ASTTerm::TmZero),
+        Ok(n) => Ok(ASTTerm::TmNumber(n)),
+        Err(_) => Err(lexer.error_at("a number below 2^64", &format!("`{}`", digits), span)),
+    }
+}
+
+// any number of projections `.1` or `.label` after the atom `term`
+fn parse_projections(lexer: &mut Lexer, mut term: SpannedTerm) -> ParseResult {
+    let start = term.span.start;
+    while lexer.eat_symbol(".")?.is_some() {
+        lexer.deepen()?;
+        let node = match lexer.peek()? {
+            (Token::Number(index), number_span) => {
+                lexer.next_token()?;
+                let index = index.parse::<usize>().map_err(|_| {
+                    lexer.error_at("a tuple index", &format!("`{}`", index), number_span)
+                })?;
+                ASTTerm::TmProj(Box::new(term), index)
+            }
+            _ => {
+                let (label, _) = lexer.expect_word("an index or a label", &[])?;
+                ASTTerm::TmRecordProj(Box::new(term), Symbol::intern(label))
+            }
+        };
+        term = spanned(node, start, lexer);
+    }
+    Ok(term)
+}
+
+// An atom followed by any number of projections. Nesting goes through this
+// function, so the literals and projections are parsed in functions of their
+// own to keep its stack frame small.
+fn parse_atom(lexer: &mut Lexer) -> ParseResult {
+    lexer.nested(|lexer| {
+        let (token, span) = lexer.peek()?;
+        let keyword = ATOM_KEYWORDS
+            .iter()
+            .chain(&["tail"])
+            .find(|keyword| lexer.is_keyword(token, keyword));
+        let node = match (token, keyword) {
+            (_, Some(keyword)) => {
+                lexer.next_token()?;
+                parse_keyword_atom(lexer, keyword)
+            }
+            (Token::Number(_), _) if starts_fraction(lexer, span) => parse_float(lexer),
+            (Token::Number(_), _) => parse_number(lexer),
+            (Token::Str(text), _) => lexer
+                .next_token()
+                .map(|_| ASTTerm::TmString(text.to_string())),
+            // the delimiters belong to the span of the term
+            (Token::Symbol(symbol), _) if ["(", "{", "<"].contains(&symbol) => {
+                lexer.next_token()?;
+                parse_delimited(lexer, symbol)
+            }
+            // `_` binds a variable nothing can refer to
+            (Token::Word("_"), _) => Err(lexer.error_at("a term", "the wildcard `_`", span)),
+            (Token::Word(_), None) if !KEYWORDS.iter().any(|k| lexer.is_keyword(token, k)) => {
+                parse_binder(lexer).map(ASTTerm::TmVar)
+            }
+            _ => Err(lexer.unexpected("a term")),
+        }?;
+        parse_projections(lexer, spanned(node, span.start, lexer))
+    })
+}
+
+// application is left associative and spans from its function to its last
+// argument
+fn parse_application(lexer: &mut Lexer) -> ParseResult {
+    let mut term = parse_atom(lexer)?;
+    while starts_atom(lexer)? {
+        lexer.deepen()?;
+        let argument = parse_atom(lexer)?;
+        let span = Span::new(term.span.start, argument.span.end);
+        term = Spanned {
+            node: ASTTerm::TmApp(Box::new(term), Box::new(argument)),
+            span,
+        };
+    }
+    Ok(term)
+}
+
+// `x:T`, or just `x` when the type is left to inference
+fn parse_typed_binder(lexer: &mut Lexer) -> Result<(Symbol, Option<Type>), SyntaxError> {
+    let name = parse_binder(lexer)?;
+    if lexer.eat_symbol(":")?.is_none() {
+        return Ok((name, None));
+    }
+    Ok((name, Some(parse_type(lexer)?)))
+}
+
+// the path of a statement `import "path";`, if the next statement is one
+pub fn parse_import(lexer: &mut Lexer) -> Result<Option<String>, SyntaxError> {
+    if lexer.eat_keyword("import")?.is_none() {
+        return Ok(None);
+    }
+    let (path, _) = lexer.expect_string()?;
+    lexer.expect_symbol(";")?;
+    Ok(Some(path.to_string()))
+}
+
+// the name and type of a statement `type Name = T;`, if the next statement
+// is one
+pub fn parse_type_alias(lexer: &mut Lexer) -> Result<Option<(Symbol, Type)>, SyntaxError> {
+    if lexer.eat_keyword("type")?.is_none() {
+        return Ok(None);
+    }
+    let name = parse_type_name(lexer)?;
+    lexer.expect_symbol("=")?;
+    let typ = parse_type(lexer)?;
+    lexer.expect_symbol(";")?;
+    Ok(Some((name, typ)))
+}
+
+// the `name =` that starts a top-level definition `name = t;`, if any
+pub fn parse_definition(lexer: &mut Lexer) -> Result<Option<Symbol>, SyntaxError> {
+    let mut ahead = lexer.clone();
+    let is_definition =
+        matches!(ahead.next_token()?.0, Token::Word(_)) && ahead.eat_symbol("=")?.is_some();
+    if !is_definition {
+        return Ok(None);
+    }
+    let name = parse_binder(lexer)?;
+    lexer.expect_symbol("=")?;
+    Ok(Some(name))
+}
+
+// `x`, `{p, ...}` or `{l=p, ...}`, a record starts with a label and `=`
+fn parse_pattern(lexer: &mut Lexer) -> Result<Pattern, SyntaxError> {
+    lexer.nested(|lexer| {
+        if lexer.eat_symbol("{")?.is_none() {
+            return Ok(Pattern::Var(parse_binder(lexer)?));
         }
-        (next_input, lhs.as_ref().clone())
+        let mut ahead = lexer.clone();
+        let is_record =
+            matches!(ahead.next_token()?.0, Token::Word(_)) && ahead.eat_symbol("=")?.is_some();
+        let pattern = if is_record {
+            let mut fields = vec![];
+            loop {
+                let (label, _) = lexer.expect_word("a label", &[])?;
+                lexer.expect_symbol("=")?;
+                fields.push((Symbol::intern(label), parse_pattern(lexer)?));
+                if lexer.eat_symbol(",")?.is_none() {
+                    break;
+                }
+            }
+            Pattern::Record(fields)
+        } else {
+            let mut patterns = vec![parse_pattern(lexer)?];
+            while lexer.eat_symbol(",")?.is_some() {
+                patterns.push(parse_pattern(lexer)?);
+            }
+            Pattern::Tuple(patterns)
+        };
+        lexer.expect_symbol("}")?;
+        Ok(pattern)
+    })
+}
+
+// `let p:T = t1 in t2` after the keyword
+fn parse_let(lexer: &mut Lexer) -> Result<ASTTerm, SyntaxError> {
+    let pattern = parse_pattern(lexer)?;
+    let typ = match lexer.eat_symbol(":")? {
+        Some(_) => Some(parse_type(lexer)?),
+        None => None,
+    };
+    lexer.expect_symbol("=")?;
+    let bound = parse_term(lexer)?;
+    lexer.expect_keyword("in")?;
+    let body = parse_term(lexer)?;
+    Ok(ASTTerm::TmLet(
+        pattern,
+        typ,
+        Box::new(bound),
+        Box::new(body),
+    ))
+}
+
+// `lambda x:S y:T.t` after the keyword, a type ends before a word
+fn parse_abs(lexer: &mut Lexer) -> Result<ASTTerm, SyntaxError> {
+    let mut params = vec![parse_typed_binder(lexer)?];
+    while let (Token::Word(_), _) = lexer.peek()? {
+        lexer.deepen()?;
+        params.push(parse_typed_binder(lexer)?);
+    }
+    lexer.expect_symbol(".")?;
+    let body = Box::new(parse_term(lexer)?);
+    Ok(if params.len() == 1 {
+        let (param, typ) = params.pop().unwrap();
+        ASTTerm::TmAbs(param, typ, body)
+    } else {
+        ASTTerm::TmMultiAbs(params, body)
     })
 }
 
-pub fn parse_term(input: &str) -> IResult<&str, ASTTerm> {
-    println!("parse_term: {:?}", input);
-    context("term", alt((parse_abstraction, parse_application)))(input)
-        .map(|(next_input, res)| (next_input, res))
+// A term nested deeper than MAX_DEPTH is an error at its start. The parser
+// cannot tell how deep a left-nested chain, such as an application, nests
+// its first terms until it has parsed the whole chain, and elaboration
+// nests the derived forms further, so the finished tree is checked too.
+pub fn check_depth(term: &SpannedTerm, lexer: &Lexer) -> Result<(), SyntaxError> {
+    let mut stack = vec![(term, 1)];
+    while let Some((term, depth)) = stack.pop() {
+        if depth > MAX_DEPTH {
+            let expected = format!("at most {} nested terms", MAX_DEPTH);
+            let found = format!("a term {} deep", depth);
+            return Err(lexer.error_at(&expected, &found, term.span));
+        }
+        stack.extend(
+            term.node
+                .subterms()
+                .into_iter()
+                .map(|term| (term, depth + 1)),
+        );
+    }
+    Ok(())
+}
+
+// the body of an abstraction or a `let` extends as far right as possible
+pub fn parse_term(lexer: &mut Lexer) -> ParseResult {
+    lexer.nested(|lexer| {
+        let (_, span) = lexer.peek()?;
+        let node = if lexer.eat_keyword("let")?.is_some() {
+            parse_let(lexer)?
+        } else if lexer.eat_keyword("lambda")?.is_some() {
+            parse_abs(lexer)?
+        } else {
+            return parse_application(lexer);
+        };
+        Ok(spanned(node, span.start, lexer))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SYMBOLS;
+
+    #[test]
+    fn test_spans() {
+        let source = "(lambda x:Bool.x) {true, 0}.1 ";
+        let term = parse_term(&mut Lexer::new(source, &SYMBOLS)).unwrap();
+        assert_eq!(term.span.text(source), "(lambda x:Bool.x) {true, 0}.1");
+        let (function, argument) = match term.node {
+            ASTTerm::TmApp(function, argument) => (function, argument),
+            _ => panic!("application expected"),
+        };
+        assert_eq!(function.span.text(source), "(lambda x:Bool.x)");
+        assert_eq!(argument.span.text(source), "{true, 0}.1");
+        match argument.node {
+            ASTTerm::TmProj(tuple, 1) => match tuple.node {
+                ASTTerm::TmTuple(terms) => {
+                    assert_eq!(tuple.span.text(source), "{true, 0}");
+                    assert_eq!(terms[1].span, Span::new(25, 26));
+                }
+                _ => panic!("tuple expected"),
+            },
+            _ => panic!("projection expected"),
+        }
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let message = |source: &str| {
+            parse_term(&mut Lexer::new(source, &SYMBOLS))
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            message("if true then 0 | 0"),
+            "1:16: expected `else`, found `|`"
+        );
+        assert_eq!(
+            message("lambda then:Bool.0"),
+            "1:8: expected a variable, found `then`"
+        );
+        assert_eq!(message("{x=0, 1}"), "1:7: expected a label, found `1`");
+        assert_eq!(message("succ(|)"), "1:6: expected a term, found `|`");
+        assert_eq!(
+            message("succ(18446744073709551616)"),
+            "1:6: expected a number below 2^64, found `18446744073709551616`"
+        );
+        assert_eq!(
+            message("let {x, 0} = {0, 0} in x"),
+            "1:9: expected a variable, found `0`"
+        );
+        // nesting is bounded instead of overflowing the stack
+        assert!(message(&"(".repeat(1000)).contains("expected at most 256 nested terms"));
+        let list_type = format!("isnil[{}Nat] 0", "List ".repeat(1000));
+        assert!(message(&list_type).contains("nested terms"));
+        assert!(parse_term(&mut Lexer::new(
+            &format!("{}0{}", "succ(".repeat(100), ")".repeat(100)),
+            &SYMBOLS
+        ))
+        .is_ok());
+    }
 }
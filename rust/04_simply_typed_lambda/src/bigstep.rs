@@ -0,0 +1,142 @@
+use std::rc::Rc;
+
+use crate::{
+    eval::{is_val, EvalError},
+    parser::Term,
+    substitute::substitution,
+};
+
+// Call-by-value natural semantics for the terms of TAPL chapters 9 and 11,
+// t ⇓ v. A term with no derivation fails with `NoRuleApplies`, like the small
+// step evaluator does on the stuck term it reaches.
+
+pub fn eval_big_step(term: &Term) -> Result<Term, EvalError> {
+    let eval = |term: &Term| eval_big_step(term).map(Rc::new);
+    match term {
+        _ if is_val(term) => Ok(term.clone()),
+        Term::TmIf(if_term, then_term, else_term) => match eval_big_step(if_term)? {
+            Term::TmTrue => eval_big_step(then_term),
+            Term::TmFalse => eval_big_step(else_term),
+            _ => Err(EvalError::NoRuleApplies),
+        },
+        Term::TmSucc(term) => Ok(Term::TmSucc(eval(term)?)),
+        Term::TmApp(left, right) => {
+            let left = eval_big_step(left)?;
+            let right = eval_big_step(right)?;
+            match &left {
+                Term::TmAbs(_, _, body) => {
+                    let mut body = body.as_ref().clone();
+                    substitution(right, &mut body);
+                    eval_big_step(&body)
+                }
+                Term::TmPrim(primitive) => {
+                    let result = primitive
+                        .apply(&right)
+                        .ok_or_else(|| EvalError::PrimitiveFailed(primitive.name().to_string()))?;
+                    eval_big_step(&result)
+                }
+                _ => Err(EvalError::NoRuleApplies),
+            }
+        }
+        Term::TmTuple(terms) => Ok(Term::TmTuple(
+            terms.iter().map(eval_big_step).collect::<Result<_, _>>()?,
+        )),
+        Term::TmProj(tuple, index) => match &eval_big_step(tuple)? {
+            Term::TmTuple(terms) => index
+                .checked_sub(1)
+                .and_then(|i| terms.get(i).cloned())
+                .ok_or(EvalError::NoRuleApplies),
+            _ => Err(EvalError::NoRuleApplies),
+        },
+        Term::TmRecord(fields) => Ok(Term::TmRecord(
+            fields
+                .iter()
+                .map(|(label, term)| Ok((*label, eval_big_step(term)?)))
+                .collect::<Result<_, EvalError>>()?,
+        )),
+        Term::TmRecordProj(record, label) => match &eval_big_step(record)? {
+            Term::TmRecord(fields) => fields
+                .iter()
+                .find(|(field, _)| field == label)
+                .map(|(_, term)| term.clone())
+                .ok_or(EvalError::NoRuleApplies),
+            _ => Err(EvalError::NoRuleApplies),
+        },
+        Term::TmInl(term, typ) => Ok(Term::TmInl(eval(term)?, typ.clone())),
+        Term::TmInr(term, typ) => Ok(Term::TmInr(eval(term)?, typ.clone())),
+        Term::TmCase(term, _, inl_term, _, inr_term) => {
+            let (value, mut branch) = match &eval_big_step(term)? {
+                Term::TmInl(value, _) => (value.as_ref().clone(), inl_term.as_ref().clone()),
+                Term::TmInr(value, _) => (value.as_ref().clone(), inr_term.as_ref().clone()),
+                _ => return Err(EvalError::NoRuleApplies),
+            };
+            substitution(value, &mut branch);
+            eval_big_step(&branch)
+        }
+        Term::TmTag(label, term, typ) => Ok(Term::TmTag(*label, eval(term)?, typ.clone())),
+        Term::TmVariantCase(term, branches) => match &eval_big_step(term)? {
+            Term::TmTag(label, value, _) => {
+                let mut branch = branches
+                    .iter()
+                    .find(|(other, _, _)| other == label)
+                    .map(|(_, _, branch)| branch.clone())
+                    .ok_or(EvalError::NoRuleApplies)?;
+                substitution(value.as_ref().clone(), &mut branch);
+                eval_big_step(&branch)
+            }
+            _ => Err(EvalError::NoRuleApplies),
+        },
+        Term::TmCons(typ, head, tail) => Ok(Term::TmCons(typ.clone(), eval(head)?, eval(tail)?)),
+        Term::TmIsNil(_, term) => match eval_big_step(term)? {
+            Term::TmNil(_) => Ok(Term::TmTrue),
+            Term::TmCons(_, _, _) => Ok(Term::TmFalse),
+            _ => Err(EvalError::NoRuleApplies),
+        },
+        Term::TmHead(_, term) => match &eval_big_step(term)? {
+            Term::TmCons(_, head, _) => Ok(head.as_ref().clone()),
+            _ => Err(EvalError::NoRuleApplies),
+        },
+        Term::TmTail(_, term) => match &eval_big_step(term)? {
+            Term::TmCons(_, _, tail) => Ok(tail.as_ref().clone()),
+            _ => Err(EvalError::NoRuleApplies),
+        },
+        _ => Err(EvalError::NoRuleApplies),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{arbitrary::well_typed, eval::eval, parser::Parser};
+
+    fn big_step(input: &str) -> Result<String, EvalError> {
+        let term = Parser::new().parse(input).unwrap();
+        eval_big_step(&term).map(|value| value.to_string())
+    }
+
+    #[test]
+    fn test_eval_big_step() {
+        assert_eq!(
+            big_step("(lambda x:Nat.{x, succ(x)}) (if true then 0 else succ(0));"),
+            Ok("{0, succ(0)}".into())
+        );
+        assert_eq!(
+            big_step("case inr true as Nat+Bool of inl n => false | inr b => b;"),
+            Ok("true".into())
+        );
+        assert_eq!(
+            big_step("head[Nat] nil[Nat];"),
+            Err(EvalError::NoRuleApplies)
+        );
+    }
+
+    proptest! {
+        // both semantics reach the same value (exercise 3.5.17 for STLC)
+        #[test]
+        fn test_agrees_with_small_step((term, _) in well_typed()) {
+            prop_assert_eq!(eval_big_step(&term), eval(&term));
+        }
+    }
+}
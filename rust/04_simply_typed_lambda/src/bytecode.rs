@@ -0,0 +1,328 @@
+use std::fmt::{self, Formatter};
+
+use tapl_frontend::intern::Symbol;
+
+use crate::{parser::Term, primitive::Primitive, typing::Type};
+
+// A bytecode for the stack machine of `vm`. Every abstraction body is a
+// chunk of its own, ended by `Return`, and the program is chunk 0. An
+// instruction pops its operands from the value stack and pushes its result,
+// variables are read from the environment by de Bruijn index, and jump
+// targets are positions in the current chunk.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Const {
+    Boolean(bool),
+    Number(u64),
+    String(String),
+    Float(f64),
+    Nil(Type),
+    Prim(Primitive),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Instr {
+    Const(Const),
+    // pushes the variable at the de Bruijn index
+    Access(usize),
+    // pushes a closure of the chunk over the current environment
+    Closure(usize),
+    // pops the argument and then the function
+    Apply,
+    Return,
+    JumpIfFalse(usize),
+    Jump(usize),
+    Succ,
+    // pops that many values, the last one pushed is the last component
+    Tuple(usize),
+    Proj(usize),
+    Record(Vec<Symbol>),
+    RecordProj(Symbol),
+    Inl(Type),
+    Inr(Type),
+    // pops a sum and binds its value, jumps to the target for `inr`
+    CaseSum(usize),
+    Tag(Symbol, Type),
+    // pops a variant, binds its value and jumps to the branch of its label
+    CaseTag(Vec<(Symbol, usize)>),
+    // drops the innermost binding once a branch is done
+    Unbind,
+    Cons(Type),
+    IsNil,
+    Head,
+    Tail,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Program {
+    pub chunks: Vec<Vec<Instr>>,
+}
+
+impl Program {
+    // The instructions of `term`, which is evaluated once the program runs
+    pub fn compile(term: &Term) -> Self {
+        let mut program = Program {
+            chunks: vec![vec![]],
+        };
+        program.emit_term(0, term);
+        program.emit(0, Instr::Return);
+        program
+    }
+
+    fn emit(&mut self, chunk: usize, instr: Instr) -> usize {
+        self.chunks[chunk].push(instr);
+        self.chunks[chunk].len() - 1
+    }
+
+    fn here(&self, chunk: usize) -> usize {
+        self.chunks[chunk].len()
+    }
+
+    // points the jump at `at` to the next instruction
+    fn patch(&mut self, chunk: usize, at: usize) {
+        let target = self.here(chunk);
+        match &mut self.chunks[chunk][at] {
+            Instr::JumpIfFalse(to) | Instr::Jump(to) | Instr::CaseSum(to) => *to = target,
+            instr => unreachable!("{} is not a jump", instr),
+        }
+    }
+
+    // the body of a case branch, which runs with the matched value bound
+    fn emit_branch(&mut self, chunk: usize, branch: &Term) -> usize {
+        self.emit_term(chunk, branch);
+        self.emit(chunk, Instr::Unbind);
+        self.emit(chunk, Instr::Jump(0))
+    }
+
+    fn emit_term(&mut self, chunk: usize, term: &Term) {
+        let instr = match term {
+            Term::TmTrue => Instr::Const(Const::Boolean(true)),
+            Term::TmFalse => Instr::Const(Const::Boolean(false)),
+            Term::TmZero => Instr::Const(Const::Number(0)),
+            Term::TmNumber(n) => Instr::Const(Const::Number(*n)),
+            Term::TmSucc(term) => {
+                self.emit_term(chunk, term);
+                Instr::Succ
+            }
+            Term::TmString(text) => Instr::Const(Const::String(text.clone())),
+            Term::TmFloat(value) => Instr::Const(Const::Float(*value)),
+            Term::TmVar(index) => Instr::Access(*index),
+            Term::TmAbs(_, _, body) => {
+                self.chunks.push(vec![]);
+                let body_chunk = self.chunks.len() - 1;
+                self.emit_term(body_chunk, body);
+                self.emit(body_chunk, Instr::Return);
+                Instr::Closure(body_chunk)
+            }
+            Term::TmApp(left, right) => {
+                self.emit_term(chunk, left);
+                self.emit_term(chunk, right);
+                Instr::Apply
+            }
+            Term::TmIf(if_term, then_term, else_term) => {
+                self.emit_term(chunk, if_term);
+                let jump_else = self.emit(chunk, Instr::JumpIfFalse(0));
+                self.emit_term(chunk, then_term);
+                let jump_end = self.emit(chunk, Instr::Jump(0));
+                self.patch(chunk, jump_else);
+                self.emit_term(chunk, else_term);
+                self.patch(chunk, jump_end);
+                return;
+            }
+            Term::TmTuple(terms) => {
+                for term in terms {
+                    self.emit_term(chunk, term);
+                }
+                Instr::Tuple(terms.len())
+            }
+            Term::TmProj(term, index) => {
+                self.emit_term(chunk, term);
+                Instr::Proj(*index)
+            }
+            Term::TmRecord(fields) => {
+                for (_, term) in fields {
+                    self.emit_term(chunk, term);
+                }
+                Instr::Record(fields.iter().map(|(label, _)| *label).collect())
+            }
+            Term::TmRecordProj(term, label) => {
+                self.emit_term(chunk, term);
+                Instr::RecordProj(*label)
+            }
+            Term::TmInl(term, typ) => {
+                self.emit_term(chunk, term);
+                Instr::Inl(typ.clone())
+            }
+            Term::TmInr(term, typ) => {
+                self.emit_term(chunk, term);
+                Instr::Inr(typ.clone())
+            }
+            Term::TmCase(term, _, inl_term, _, inr_term) => {
+                self.emit_term(chunk, term);
+                let case = self.emit(chunk, Instr::CaseSum(0));
+                let jump_end = self.emit_branch(chunk, inl_term);
+                self.patch(chunk, case);
+                self.emit_term(chunk, inr_term);
+                self.emit(chunk, Instr::Unbind);
+                self.patch(chunk, jump_end);
+                return;
+            }
+            Term::TmTag(label, term, typ) => {
+                self.emit_term(chunk, term);
+                Instr::Tag(*label, typ.clone())
+            }
+            Term::TmVariantCase(term, branches) => {
+                self.emit_term(chunk, term);
+                let case = self.emit(chunk, Instr::CaseTag(vec![]));
+                let mut targets = vec![];
+                let mut jumps = vec![];
+                for (label, _, branch) in branches {
+                    targets.push((*label, self.here(chunk)));
+                    jumps.push(self.emit_branch(chunk, branch));
+                }
+                self.chunks[chunk][case] = Instr::CaseTag(targets);
+                for jump in jumps {
+                    self.patch(chunk, jump);
+                }
+                return;
+            }
+            Term::TmNil(typ) => Instr::Const(Const::Nil(typ.clone())),
+            Term::TmCons(typ, head, tail) => {
+                self.emit_term(chunk, head);
+                self.emit_term(chunk, tail);
+                Instr::Cons(typ.clone())
+            }
+            Term::TmIsNil(_, term) => {
+                self.emit_term(chunk, term);
+                Instr::IsNil
+            }
+            Term::TmHead(_, term) => {
+                self.emit_term(chunk, term);
+                Instr::Head
+            }
+            Term::TmTail(_, term) => {
+                self.emit_term(chunk, term);
+                Instr::Tail
+            }
+            Term::TmGlobal(_, term) => return self.emit_term(chunk, term),
+            Term::TmPrim(primitive) => Instr::Const(Const::Prim(primitive.clone())),
+        };
+        self.emit(chunk, instr);
+    }
+}
+
+impl fmt::Display for Const {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Const::Boolean(value) => write!(f, "{}", value),
+            Const::Number(n) => write!(f, "{}", n),
+            Const::String(text) => write!(f, "{:?}", text),
+            Const::Float(value) => write!(f, "{:?}", value),
+            Const::Nil(typ) => write!(f, "nil[{}]", typ),
+            Const::Prim(primitive) => write!(f, "{}", primitive.name()),
+        }
+    }
+}
+
+impl fmt::Display for Instr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Instr::Const(value) => write!(f, "const {}", value),
+            Instr::Access(index) => write!(f, "access {}", index),
+            Instr::Closure(chunk) => write!(f, "closure {}", chunk),
+            Instr::Apply => write!(f, "apply"),
+            Instr::Return => write!(f, "return"),
+            Instr::JumpIfFalse(target) => write!(f, "jump_if_false {}", target),
+            Instr::Jump(target) => write!(f, "jump {}", target),
+            Instr::Succ => write!(f, "succ"),
+            Instr::Tuple(len) => write!(f, "tuple {}", len),
+            Instr::Proj(index) => write!(f, "proj {}", index),
+            Instr::Record(labels) => {
+                write!(f, "record")?;
+                for label in labels {
+                    write!(f, " {}", label)?;
+                }
+                Ok(())
+            }
+            Instr::RecordProj(label) => write!(f, "record_proj {}", label),
+            Instr::Inl(typ) => write!(f, "inl {}", typ),
+            Instr::Inr(typ) => write!(f, "inr {}", typ),
+            Instr::CaseSum(target) => write!(f, "case_sum {}", target),
+            Instr::Tag(label, typ) => write!(f, "tag {} {}", label, typ),
+            Instr::CaseTag(targets) => {
+                write!(f, "case_tag")?;
+                for (label, target) in targets {
+                    write!(f, " {}:{}", label, target)?;
+                }
+                Ok(())
+            }
+            Instr::Unbind => write!(f, "unbind"),
+            Instr::Cons(typ) => write!(f, "cons {}", typ),
+            Instr::IsNil => write!(f, "is_nil"),
+            Instr::Head => write!(f, "head"),
+            Instr::Tail => write!(f, "tail"),
+        }
+    }
+}
+
+// the listing of every chunk, one numbered instruction per line
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, chunk) in self.chunks.iter().enumerate() {
+            writeln!(f, "chunk {}:", i)?;
+            for (pc, instr) in chunk.iter().enumerate() {
+                writeln!(f, "  {:>3} {}", pc, instr)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn listing(input: &str) -> String {
+        Program::compile(&Parser::new().parse(input).unwrap()).to_string()
+    }
+
+    #[test]
+    fn test_compile() {
+        assert_eq!(
+            listing("(lambda x:Bool.if x then 0 else succ(0)) true;"),
+            "chunk 0:\n\
+             \x20   0 closure 1\n\
+             \x20   1 const true\n\
+             \x20   2 apply\n\
+             \x20   3 return\n\
+             chunk 1:\n\
+             \x20   0 access 0\n\
+             \x20   1 jump_if_false 4\n\
+             \x20   2 const 0\n\
+             \x20   3 jump 6\n\
+             \x20   4 const 0\n\
+             \x20   5 succ\n\
+             \x20   6 return\n"
+        );
+        // every branch unbinds its variable before joining the others
+        assert_eq!(
+            listing("case <b=true> as <a:Nat, b:Bool> of <a=n> => {n, true} | <b=x> => {0, x};"),
+            "chunk 0:\n\
+             \x20   0 const true\n\
+             \x20   1 tag b <a:Nat, b:Bool>\n\
+             \x20   2 case_tag a:3 b:8\n\
+             \x20   3 access 0\n\
+             \x20   4 const true\n\
+             \x20   5 tuple 2\n\
+             \x20   6 unbind\n\
+             \x20   7 jump 13\n\
+             \x20   8 const 0\n\
+             \x20   9 access 0\n\
+             \x20  10 tuple 2\n\
+             \x20  11 unbind\n\
+             \x20  12 jump 13\n\
+             \x20  13 return\n"
+        );
+    }
+}
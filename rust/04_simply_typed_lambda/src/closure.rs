@@ -0,0 +1,377 @@
+use std::rc::Rc;
+
+use tapl_frontend::intern::Symbol;
+
+use crate::{eval::EvalError, parser::Term, primitive::Primitive, typing::Type};
+
+// Big-step evaluation with environments and closures: a variable is looked
+// up in the environment when it is reached instead of being substituted into
+// the body at every beta reduction, so an application costs the same however
+// large the argument is.
+
+// Abstraction body together with the environment it was created in
+#[derive(Clone, Debug)]
+pub struct Closure<'a> {
+    env: Env<'a>,
+    name: Symbol,
+    typ: &'a Type,
+    body: &'a Term,
+}
+
+#[derive(Clone, Debug)]
+pub enum Value<'a> {
+    Boolean(bool),
+    Number(u64),
+    String(String),
+    Float(f64),
+    Tuple(Vec<Value<'a>>),
+    Record(Vec<(Symbol, Value<'a>)>),
+    Inl(Box<Value<'a>>, Type),
+    Inr(Box<Value<'a>>, Type),
+    Tag(Symbol, Box<Value<'a>>, Type),
+    Nil(Type),
+    Cons(Type, Box<Value<'a>>, Box<Value<'a>>),
+    Closure(Closure<'a>),
+    Prim(Primitive),
+}
+
+// Persistent list of values indexed by de Bruijn index, so that closures can
+// share the tail they were created with
+#[derive(Clone, Debug, Default)]
+pub struct Env<'a>(Option<Rc<(Value<'a>, Env<'a>)>>);
+
+impl<'a> Env<'a> {
+    pub fn bind(&self, value: Value<'a>) -> Self {
+        Env(Some(Rc::new((value, self.clone()))))
+    }
+
+    pub fn lookup(&self, index: usize) -> Option<&Value<'a>> {
+        let mut env = self;
+        let mut index = index;
+        while let Some(node) = &env.0 {
+            if index == 0 {
+                return Some(&node.0);
+            }
+            index -= 1;
+            env = &node.1;
+        }
+        None
+    }
+}
+
+// Evaluates a well-typed term to a value. A term the substitution evaluator
+// gets stuck on, like `head[T] nil[T]`, is `NoRuleApplies` here. A primitive
+// must return a first-order value, the closure evaluator cannot run an
+// abstraction that has no environment.
+pub fn eval<'a>(env: &Env<'a>, term: &'a Term) -> Result<Value<'a>, EvalError> {
+    let eval_all = |terms: &'a [Term]| {
+        terms
+            .iter()
+            .map(|term| eval(env, term))
+            .collect::<Result<Vec<_>, _>>()
+    };
+    match term {
+        Term::TmTrue => Ok(Value::Boolean(true)),
+        Term::TmFalse => Ok(Value::Boolean(false)),
+        Term::TmZero => Ok(Value::Number(0)),
+        Term::TmNumber(n) => Ok(Value::Number(*n)),
+        Term::TmSucc(term) => match eval(env, term)? {
+            Value::Number(n) => n
+                .checked_add(1)
+                .map(Value::Number)
+                .ok_or(EvalError::NoRuleApplies),
+            _ => Err(EvalError::NoRuleApplies),
+        },
+        Term::TmString(text) => Ok(Value::String(text.clone())),
+        Term::TmFloat(value) => Ok(Value::Float(*value)),
+        Term::TmVar(index) => env.lookup(*index).cloned().ok_or(EvalError::NoRuleApplies),
+        Term::TmAbs(name, typ, body) => Ok(Value::Closure(Closure {
+            env: env.clone(),
+            name: *name,
+            typ,
+            body,
+        })),
+        Term::TmApp(left, right) => {
+            let function = eval(env, left)?;
+            let argument = eval(env, right)?;
+            match function {
+                Value::Closure(closure) => eval(&closure.env.bind(argument), closure.body),
+                Value::Prim(primitive) => primitive
+                    .apply(&argument.to_term())
+                    .ok_or_else(|| EvalError::PrimitiveFailed(primitive.name().to_string()))
+                    .and_then(|result| Value::from_term(&result)),
+                _ => Err(EvalError::NoRuleApplies),
+            }
+        }
+        Term::TmIf(if_term, then_term, else_term) => match eval(env, if_term)? {
+            Value::Boolean(true) => eval(env, then_term),
+            Value::Boolean(false) => eval(env, else_term),
+            _ => Err(EvalError::NoRuleApplies),
+        },
+        Term::TmTuple(terms) => Ok(Value::Tuple(eval_all(terms)?)),
+        Term::TmProj(tuple, index) => match eval(env, tuple)? {
+            Value::Tuple(mut values) if (1..=values.len()).contains(index) => {
+                Ok(values.swap_remove(index - 1))
+            }
+            _ => Err(EvalError::NoRuleApplies),
+        },
+        Term::TmRecord(fields) => Ok(Value::Record(
+            fields
+                .iter()
+                .map(|(label, term)| Ok((*label, eval(env, term)?)))
+                .collect::<Result<Vec<_>, EvalError>>()?,
+        )),
+        Term::TmRecordProj(record, label) => match eval(env, record)? {
+            Value::Record(fields) => fields
+                .into_iter()
+                .find(|(field, _)| field == label)
+                .map(|(_, value)| value)
+                .ok_or(EvalError::NoRuleApplies),
+            _ => Err(EvalError::NoRuleApplies),
+        },
+        Term::TmInl(term, typ) => Ok(Value::Inl(Box::new(eval(env, term)?), typ.clone())),
+        Term::TmInr(term, typ) => Ok(Value::Inr(Box::new(eval(env, term)?), typ.clone())),
+        Term::TmCase(term, _, inl_term, _, inr_term) => match eval(env, term)? {
+            Value::Inl(value, _) => eval(&env.bind(*value), inl_term),
+            Value::Inr(value, _) => eval(&env.bind(*value), inr_term),
+            _ => Err(EvalError::NoRuleApplies),
+        },
+        Term::TmTag(label, term, typ) => {
+            Ok(Value::Tag(*label, Box::new(eval(env, term)?), typ.clone()))
+        }
+        Term::TmVariantCase(term, branches) => match eval(env, term)? {
+            Value::Tag(label, value, _) => branches
+                .iter()
+                .find(|(other, _, _)| *other == label)
+                .ok_or(EvalError::NoRuleApplies)
+                .and_then(|(_, _, branch)| eval(&env.bind(*value), branch)),
+            _ => Err(EvalError::NoRuleApplies),
+        },
+        Term::TmNil(typ) => Ok(Value::Nil(typ.clone())),
+        Term::TmCons(typ, head, tail) => {
+            let head = eval(env, head)?;
+            let tail = eval(env, tail)?;
+            Ok(Value::Cons(typ.clone(), Box::new(head), Box::new(tail)))
+        }
+        Term::TmIsNil(_, term) => match eval(env, term)? {
+            Value::Nil(_) => Ok(Value::Boolean(true)),
+            Value::Cons(_, _, _) => Ok(Value::Boolean(false)),
+            _ => Err(EvalError::NoRuleApplies),
+        },
+        Term::TmHead(_, term) => match eval(env, term)? {
+            Value::Cons(_, head, _) => Ok(*head),
+            _ => Err(EvalError::NoRuleApplies),
+        },
+        Term::TmTail(_, term) => match eval(env, term)? {
+            Value::Cons(_, _, tail) => Ok(*tail),
+            _ => Err(EvalError::NoRuleApplies),
+        },
+        // the definition is closed, it runs in an empty environment
+        Term::TmGlobal(_, term) => eval(&Env::default(), term),
+        Term::TmPrim(primitive) => Ok(Value::Prim(primitive.clone())),
+    }
+}
+
+impl<'a> Value<'a> {
+    // a first-order value term, as returned by a primitive
+    fn from_term(term: &Term) -> Result<Self, EvalError> {
+        let from_terms = |terms: &[Term]| {
+            terms
+                .iter()
+                .map(Value::from_term)
+                .collect::<Result<Vec<_>, _>>()
+        };
+        match term {
+            Term::TmTrue => Ok(Value::Boolean(true)),
+            Term::TmFalse => Ok(Value::Boolean(false)),
+            Term::TmZero => Ok(Value::Number(0)),
+            Term::TmNumber(n) => Ok(Value::Number(*n)),
+            Term::TmSucc(term) => match Value::from_term(term)? {
+                Value::Number(n) => n
+                    .checked_add(1)
+                    .map(Value::Number)
+                    .ok_or(EvalError::NoRuleApplies),
+                _ => Err(EvalError::NoRuleApplies),
+            },
+            Term::TmString(text) => Ok(Value::String(text.clone())),
+            Term::TmFloat(value) => Ok(Value::Float(*value)),
+            Term::TmTuple(terms) => Ok(Value::Tuple(from_terms(terms)?)),
+            Term::TmRecord(fields) => Ok(Value::Record(
+                fields
+                    .iter()
+                    .map(|(label, term)| Ok((*label, Value::from_term(term)?)))
+                    .collect::<Result<Vec<_>, EvalError>>()?,
+            )),
+            Term::TmInl(term, typ) => {
+                Ok(Value::Inl(Box::new(Value::from_term(term)?), typ.clone()))
+            }
+            Term::TmInr(term, typ) => {
+                Ok(Value::Inr(Box::new(Value::from_term(term)?), typ.clone()))
+            }
+            Term::TmTag(label, term, typ) => Ok(Value::Tag(
+                *label,
+                Box::new(Value::from_term(term)?),
+                typ.clone(),
+            )),
+            Term::TmNil(typ) => Ok(Value::Nil(typ.clone())),
+            Term::TmCons(typ, head, tail) => Ok(Value::Cons(
+                typ.clone(),
+                Box::new(Value::from_term(head)?),
+                Box::new(Value::from_term(tail)?),
+            )),
+            Term::TmPrim(primitive) => Ok(Value::Prim(primitive.clone())),
+            _ => Err(EvalError::NoRuleApplies),
+        }
+    }
+
+    // read the value back into a term, closures get their environment
+    // substituted into the body
+    pub fn to_term(&self) -> Term {
+        let boxed = |value: &Value<'_>| Rc::new(value.to_term());
+        match self {
+            Value::Boolean(true) => Term::TmTrue,
+            Value::Boolean(false) => Term::TmFalse,
+            Value::Number(n) => Term::number(*n),
+            Value::String(text) => Term::TmString(text.clone()),
+            Value::Float(value) => Term::TmFloat(*value),
+            Value::Tuple(values) => Term::TmTuple(values.iter().map(Value::to_term).collect()),
+            Value::Record(fields) => Term::TmRecord(
+                fields
+                    .iter()
+                    .map(|(label, value)| (*label, value.to_term()))
+                    .collect(),
+            ),
+            Value::Inl(value, typ) => Term::TmInl(boxed(value), typ.clone()),
+            Value::Inr(value, typ) => Term::TmInr(boxed(value), typ.clone()),
+            Value::Tag(label, value, typ) => Term::TmTag(*label, boxed(value), typ.clone()),
+            Value::Nil(typ) => Term::TmNil(typ.clone()),
+            Value::Cons(typ, head, tail) => Term::TmCons(typ.clone(), boxed(head), boxed(tail)),
+            Value::Closure(closure) => Term::TmAbs(
+                closure.name,
+                closure.typ.clone(),
+                Rc::new(close(closure.body, &closure.env, 1)),
+            ),
+            Value::Prim(primitive) => Term::TmPrim(primitive.clone()),
+        }
+    }
+}
+
+// replaces the variables bound in `env` by their values, `depth` binders
+// have been entered since
+fn close(term: &Term, env: &Env<'_>, depth: usize) -> Term {
+    let boxed = |term: &Term| Rc::new(close(term, env, depth));
+    let under_binder = |term: &Term| Rc::new(close(term, env, depth + 1));
+    match term {
+        Term::TmVar(index) if *index < depth => Term::TmVar(*index),
+        // values are closed, so they need no shifting
+        Term::TmVar(index) => match env.lookup(index - depth) {
+            Some(value) => value.to_term(),
+            None => Term::TmVar(*index),
+        },
+        Term::TmTrue
+        | Term::TmFalse
+        | Term::TmZero
+        | Term::TmNumber(_)
+        | Term::TmString(_)
+        | Term::TmFloat(_)
+        | Term::TmNil(_)
+        | Term::TmGlobal(_, _)
+        | Term::TmPrim(_) => term.clone(),
+        Term::TmSucc(term) => Term::TmSucc(boxed(term)),
+        Term::TmAbs(name, typ, body) => Term::TmAbs(*name, typ.clone(), under_binder(body)),
+        Term::TmApp(left, right) => Term::TmApp(boxed(left), boxed(right)),
+        Term::TmIf(if_term, then_term, else_term) => {
+            Term::TmIf(boxed(if_term), boxed(then_term), boxed(else_term))
+        }
+        Term::TmTuple(terms) => {
+            Term::TmTuple(terms.iter().map(|term| close(term, env, depth)).collect())
+        }
+        Term::TmProj(term, index) => Term::TmProj(boxed(term), *index),
+        Term::TmRecord(fields) => Term::TmRecord(
+            fields
+                .iter()
+                .map(|(label, term)| (*label, close(term, env, depth)))
+                .collect(),
+        ),
+        Term::TmRecordProj(term, label) => Term::TmRecordProj(boxed(term), *label),
+        Term::TmInl(term, typ) => Term::TmInl(boxed(term), typ.clone()),
+        Term::TmInr(term, typ) => Term::TmInr(boxed(term), typ.clone()),
+        Term::TmCase(term, inl_name, inl_term, inr_name, inr_term) => Term::TmCase(
+            boxed(term),
+            *inl_name,
+            under_binder(inl_term),
+            *inr_name,
+            under_binder(inr_term),
+        ),
+        Term::TmTag(label, term, typ) => Term::TmTag(*label, boxed(term), typ.clone()),
+        Term::TmVariantCase(term, branches) => Term::TmVariantCase(
+            boxed(term),
+            branches
+                .iter()
+                .map(|(label, name, branch)| (*label, *name, close(branch, env, depth + 1)))
+                .collect(),
+        ),
+        Term::TmCons(typ, head, tail) => Term::TmCons(typ.clone(), boxed(head), boxed(tail)),
+        Term::TmIsNil(typ, term) => Term::TmIsNil(typ.clone(), boxed(term)),
+        Term::TmHead(typ, term) => Term::TmHead(typ.clone(), boxed(term)),
+        Term::TmTail(typ, term) => Term::TmTail(typ.clone(), boxed(term)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{eval, parser::Parser};
+
+    fn parse(input: &str) -> Term {
+        Parser::new().parse(input).unwrap()
+    }
+
+    fn run(input: &str) -> Result<Term, EvalError> {
+        let term = parse(input);
+        eval(&Env::default(), &term).map(|value| value.to_term())
+    }
+
+    #[test]
+    fn test_eval() {
+        assert_eq!(
+            run("(lambda f:Nat->Nat.lambda x:Nat.f (f x)) (lambda n:Nat.succ(n)) 0;"),
+            Ok(parse("2;"))
+        );
+        // the closure is read back with its environment substituted in
+        assert_eq!(
+            run("(lambda x:Nat.lambda y:Bool.if y then x else 0) succ(0);"),
+            Ok(parse("lambda y:Bool.if y then 1 else 0;"))
+        );
+        assert_eq!(
+            run("case inr {0, true} as Bool+Nat*Bool of inl b => 0 | inr p => succ(p.1);"),
+            Ok(parse("1;"))
+        );
+        assert_eq!(run("head[Nat] nil[Nat];"), Err(EvalError::NoRuleApplies));
+    }
+
+    // the closure evaluator must agree with the substitution evaluator
+    #[test]
+    fn test_agrees_with_substitution() {
+        for input in [
+            "if false then true else false;",
+            "{if true then false else true, succ(0)};",
+            "(lambda p:Bool*Nat.p.2) {false, succ(0)};",
+            "(lambda r:{a:Nat, b:Bool}.r.b) {a=0, b=true};",
+            "case inl succ(0) as Nat+Bool of inl n => {n, n} | inr b => {0, 0};",
+            "head[Nat] (tail[Nat] (cons[Nat] 0 (cons[Nat] succ(0) nil[Nat])));",
+            "isnil[Nat] (cons[Nat] 0 nil[Nat]);",
+            "(lambda f:Nat->Nat.f (f 0)) (lambda n:Nat.succ(n));",
+        ] {
+            // numbers are read back as numerals, the substitution evaluator
+            // keeps the chains of `succ` of the program
+            let value = |term: Term| eval::Value::from_term(&term);
+            assert_eq!(
+                run(input).map(value),
+                eval::eval(&parse(input)).map(value),
+                "{}",
+                input
+            );
+        }
+    }
+}
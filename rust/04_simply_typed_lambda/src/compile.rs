@@ -0,0 +1,428 @@
+use std::{
+    fmt::{self, Formatter},
+    rc::Rc,
+};
+
+use tapl_frontend::intern::Symbol;
+
+use crate::{eval::EvalError, parser::Term, primitive::Primitive, typing::Type};
+
+// Compilation to Rust closures: the term is walked once, ahead of running
+// it, into a tree of closures that each take the runtime environment. An
+// abstraction becomes a Rust function from values to values (higher-order
+// abstract syntax), so applying it calls straight into its compiled body,
+// with no term to match on or substitute into.
+
+pub type Function = Rc<dyn Fn(Value) -> Result<Value, EvalError>>;
+
+#[derive(Clone)]
+pub enum Value {
+    Boolean(bool),
+    Number(u64),
+    String(String),
+    Float(f64),
+    Tuple(Vec<Value>),
+    Record(Vec<(Symbol, Value)>),
+    Inl(Box<Value>, Type),
+    Inr(Box<Value>, Type),
+    Tag(Symbol, Box<Value>, Type),
+    Nil(Type),
+    Cons(Type, Box<Value>, Box<Value>),
+    Function(Function),
+    Prim(Primitive),
+}
+
+// a compiled function is opaque, only its presence is shown
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.to_term() {
+            Some(term) => write!(f, "{}", term),
+            None => write!(f, "<function>"),
+        }
+    }
+}
+
+// Persistent list of values indexed by de Bruijn index, so that functions
+// can share the tail they were created with
+#[derive(Clone, Default)]
+pub struct Env(Option<Rc<(Value, Env)>>);
+
+impl Env {
+    pub fn bind(&self, value: Value) -> Self {
+        Env(Some(Rc::new((value, self.clone()))))
+    }
+
+    pub fn lookup(&self, index: usize) -> Option<&Value> {
+        let mut env = self;
+        let mut index = index;
+        while let Some(node) = &env.0 {
+            if index == 0 {
+                return Some(&node.0);
+            }
+            index -= 1;
+            env = &node.1;
+        }
+        None
+    }
+}
+
+type CodeFn = dyn Fn(&Env) -> Result<Value, EvalError>;
+
+// A compiled term, run it with the values of its free variables
+#[derive(Clone)]
+pub struct Code(Rc<CodeFn>);
+
+impl Code {
+    fn new(f: impl Fn(&Env) -> Result<Value, EvalError> + 'static) -> Self {
+        Code(Rc::new(f))
+    }
+
+    pub fn run(&self, env: &Env) -> Result<Value, EvalError> {
+        (self.0)(env)
+    }
+}
+
+// Compiles a well-typed term. A term the substitution evaluator gets stuck
+// on, like `head[T] nil[T]`, is `NoRuleApplies` when its code runs.
+pub fn compile(term: &Term) -> Code {
+    let compile_all = |terms: &[Term]| terms.iter().map(compile).collect::<Vec<_>>();
+    match term {
+        Term::TmTrue => Code::new(|_| Ok(Value::Boolean(true))),
+        Term::TmFalse => Code::new(|_| Ok(Value::Boolean(false))),
+        Term::TmZero => Code::new(|_| Ok(Value::Number(0))),
+        Term::TmNumber(n) => {
+            let n = *n;
+            Code::new(move |_| Ok(Value::Number(n)))
+        }
+        Term::TmSucc(term) => {
+            let term = compile(term);
+            Code::new(move |env| match term.run(env)? {
+                Value::Number(n) => n
+                    .checked_add(1)
+                    .map(Value::Number)
+                    .ok_or(EvalError::NoRuleApplies),
+                _ => Err(EvalError::NoRuleApplies),
+            })
+        }
+        Term::TmString(text) => {
+            let text = text.clone();
+            Code::new(move |_| Ok(Value::String(text.clone())))
+        }
+        Term::TmFloat(value) => {
+            let value = *value;
+            Code::new(move |_| Ok(Value::Float(value)))
+        }
+        Term::TmVar(index) => {
+            let index = *index;
+            Code::new(move |env| env.lookup(index).cloned().ok_or(EvalError::NoRuleApplies))
+        }
+        Term::TmAbs(_, _, body) => {
+            let body = compile(body);
+            Code::new(move |env| {
+                let (env, body) = (env.clone(), body.clone());
+                Ok(Value::Function(Rc::new(move |argument| {
+                    body.run(&env.bind(argument))
+                })))
+            })
+        }
+        Term::TmApp(left, right) => {
+            let (left, right) = (compile(left), compile(right));
+            Code::new(move |env| {
+                let function = left.run(env)?;
+                let argument = right.run(env)?;
+                apply(function, argument)
+            })
+        }
+        Term::TmIf(if_term, then_term, else_term) => {
+            let (if_term, then_term, else_term) =
+                (compile(if_term), compile(then_term), compile(else_term));
+            Code::new(move |env| match if_term.run(env)? {
+                Value::Boolean(true) => then_term.run(env),
+                Value::Boolean(false) => else_term.run(env),
+                _ => Err(EvalError::NoRuleApplies),
+            })
+        }
+        Term::TmTuple(terms) => {
+            let terms = compile_all(terms);
+            Code::new(move |env| {
+                let values = terms.iter().map(|term| term.run(env));
+                Ok(Value::Tuple(values.collect::<Result<_, _>>()?))
+            })
+        }
+        Term::TmProj(tuple, index) => {
+            let (tuple, index) = (compile(tuple), *index);
+            Code::new(move |env| match tuple.run(env)? {
+                Value::Tuple(mut values) if (1..=values.len()).contains(&index) => {
+                    Ok(values.swap_remove(index - 1))
+                }
+                _ => Err(EvalError::NoRuleApplies),
+            })
+        }
+        Term::TmRecord(fields) => {
+            let fields = fields
+                .iter()
+                .map(|(label, term)| (*label, compile(term)))
+                .collect::<Vec<_>>();
+            Code::new(move |env| {
+                let values = fields
+                    .iter()
+                    .map(|(label, term)| Ok((*label, term.run(env)?)));
+                Ok(Value::Record(values.collect::<Result<_, EvalError>>()?))
+            })
+        }
+        Term::TmRecordProj(record, label) => {
+            let (record, label) = (compile(record), *label);
+            Code::new(move |env| match record.run(env)? {
+                Value::Record(fields) => fields
+                    .into_iter()
+                    .find(|(field, _)| *field == label)
+                    .map(|(_, value)| value)
+                    .ok_or(EvalError::NoRuleApplies),
+                _ => Err(EvalError::NoRuleApplies),
+            })
+        }
+        Term::TmInl(term, typ) => {
+            let (term, typ) = (compile(term), typ.clone());
+            Code::new(move |env| Ok(Value::Inl(Box::new(term.run(env)?), typ.clone())))
+        }
+        Term::TmInr(term, typ) => {
+            let (term, typ) = (compile(term), typ.clone());
+            Code::new(move |env| Ok(Value::Inr(Box::new(term.run(env)?), typ.clone())))
+        }
+        Term::TmCase(term, _, inl_term, _, inr_term) => {
+            let (term, inl_term, inr_term) = (compile(term), compile(inl_term), compile(inr_term));
+            Code::new(move |env| match term.run(env)? {
+                Value::Inl(value, _) => inl_term.run(&env.bind(*value)),
+                Value::Inr(value, _) => inr_term.run(&env.bind(*value)),
+                _ => Err(EvalError::NoRuleApplies),
+            })
+        }
+        Term::TmTag(label, term, typ) => {
+            let (label, term, typ) = (*label, compile(term), typ.clone());
+            Code::new(move |env| Ok(Value::Tag(label, Box::new(term.run(env)?), typ.clone())))
+        }
+        Term::TmVariantCase(term, branches) => {
+            let term = compile(term);
+            let branches = branches
+                .iter()
+                .map(|(label, _, branch)| (*label, compile(branch)))
+                .collect::<Vec<_>>();
+            Code::new(move |env| match term.run(env)? {
+                Value::Tag(label, value, _) => branches
+                    .iter()
+                    .find(|(other, _)| *other == label)
+                    .ok_or(EvalError::NoRuleApplies)
+                    .and_then(|(_, branch)| branch.run(&env.bind(*value))),
+                _ => Err(EvalError::NoRuleApplies),
+            })
+        }
+        Term::TmNil(typ) => {
+            let typ = typ.clone();
+            Code::new(move |_| Ok(Value::Nil(typ.clone())))
+        }
+        Term::TmCons(typ, head, tail) => {
+            let (typ, head, tail) = (typ.clone(), compile(head), compile(tail));
+            Code::new(move |env| {
+                let head = head.run(env)?;
+                let tail = tail.run(env)?;
+                Ok(Value::Cons(typ.clone(), Box::new(head), Box::new(tail)))
+            })
+        }
+        Term::TmIsNil(_, term) => {
+            let term = compile(term);
+            Code::new(move |env| match term.run(env)? {
+                Value::Nil(_) => Ok(Value::Boolean(true)),
+                Value::Cons(_, _, _) => Ok(Value::Boolean(false)),
+                _ => Err(EvalError::NoRuleApplies),
+            })
+        }
+        Term::TmHead(_, term) => {
+            let term = compile(term);
+            Code::new(move |env| match term.run(env)? {
+                Value::Cons(_, head, _) => Ok(*head),
+                _ => Err(EvalError::NoRuleApplies),
+            })
+        }
+        Term::TmTail(_, term) => {
+            let term = compile(term);
+            Code::new(move |env| match term.run(env)? {
+                Value::Cons(_, _, tail) => Ok(*tail),
+                _ => Err(EvalError::NoRuleApplies),
+            })
+        }
+        // the definition is closed, its code runs in an empty environment
+        Term::TmGlobal(_, term) => {
+            let term = compile(term);
+            Code::new(move |_| term.run(&Env::default()))
+        }
+        Term::TmPrim(primitive) => {
+            let primitive = primitive.clone();
+            Code::new(move |_| Ok(Value::Prim(primitive.clone())))
+        }
+    }
+}
+
+// A primitive is called on the term of its argument, so it can only be
+// passed first-order values
+fn apply(function: Value, argument: Value) -> Result<Value, EvalError> {
+    match function {
+        Value::Function(function) => function(argument),
+        Value::Prim(primitive) => argument
+            .to_term()
+            .and_then(|argument| primitive.apply(&argument))
+            .and_then(|result| Value::from_term(&result))
+            .ok_or_else(|| EvalError::PrimitiveFailed(primitive.name().to_string())),
+        _ => Err(EvalError::NoRuleApplies),
+    }
+}
+
+// compiles and runs a closed term
+pub fn eval(term: &Term) -> Result<Value, EvalError> {
+    compile(term).run(&Env::default())
+}
+
+impl Value {
+    // a first-order value term, as returned by a primitive
+    fn from_term(term: &Term) -> Option<Self> {
+        let from_terms = |terms: &[Term]| terms.iter().map(Value::from_term).collect::<Option<_>>();
+        match term {
+            Term::TmTrue => Some(Value::Boolean(true)),
+            Term::TmFalse => Some(Value::Boolean(false)),
+            Term::TmZero => Some(Value::Number(0)),
+            Term::TmNumber(n) => Some(Value::Number(*n)),
+            Term::TmSucc(term) => match Value::from_term(term)? {
+                Value::Number(n) => n.checked_add(1).map(Value::Number),
+                _ => None,
+            },
+            Term::TmString(text) => Some(Value::String(text.clone())),
+            Term::TmFloat(value) => Some(Value::Float(*value)),
+            Term::TmTuple(terms) => Some(Value::Tuple(from_terms(terms)?)),
+            Term::TmRecord(fields) => Some(Value::Record(
+                fields
+                    .iter()
+                    .map(|(label, term)| Some((*label, Value::from_term(term)?)))
+                    .collect::<Option<_>>()?,
+            )),
+            Term::TmInl(term, typ) => {
+                Some(Value::Inl(Box::new(Value::from_term(term)?), typ.clone()))
+            }
+            Term::TmInr(term, typ) => {
+                Some(Value::Inr(Box::new(Value::from_term(term)?), typ.clone()))
+            }
+            Term::TmTag(label, term, typ) => Some(Value::Tag(
+                *label,
+                Box::new(Value::from_term(term)?),
+                typ.clone(),
+            )),
+            Term::TmNil(typ) => Some(Value::Nil(typ.clone())),
+            Term::TmCons(typ, head, tail) => Some(Value::Cons(
+                typ.clone(),
+                Box::new(Value::from_term(head)?),
+                Box::new(Value::from_term(tail)?),
+            )),
+            Term::TmPrim(primitive) => Some(Value::Prim(primitive.clone())),
+            _ => None,
+        }
+    }
+
+    // reads the value back into a term, `None` if it contains a compiled
+    // function, which no longer has a body to show
+    pub fn to_term(&self) -> Option<Term> {
+        let boxed = |value: &Value| value.to_term().map(Rc::new);
+        match self {
+            Value::Boolean(true) => Some(Term::TmTrue),
+            Value::Boolean(false) => Some(Term::TmFalse),
+            Value::Number(n) => Some(Term::number(*n)),
+            Value::String(text) => Some(Term::TmString(text.clone())),
+            Value::Float(value) => Some(Term::TmFloat(*value)),
+            Value::Tuple(values) => Some(Term::TmTuple(
+                values.iter().map(Value::to_term).collect::<Option<_>>()?,
+            )),
+            Value::Record(fields) => Some(Term::TmRecord(
+                fields
+                    .iter()
+                    .map(|(label, value)| Some((*label, value.to_term()?)))
+                    .collect::<Option<_>>()?,
+            )),
+            Value::Inl(value, typ) => Some(Term::TmInl(boxed(value)?, typ.clone())),
+            Value::Inr(value, typ) => Some(Term::TmInr(boxed(value)?, typ.clone())),
+            Value::Tag(label, value, typ) => Some(Term::TmTag(*label, boxed(value)?, typ.clone())),
+            Value::Nil(typ) => Some(Term::TmNil(typ.clone())),
+            Value::Cons(typ, head, tail) => {
+                Some(Term::TmCons(typ.clone(), boxed(head)?, boxed(tail)?))
+            }
+            Value::Function(_) => None,
+            Value::Prim(primitive) => Some(Term::TmPrim(primitive.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{eval, parser::Parser};
+
+    fn parse(input: &str) -> Term {
+        Parser::new().parse(input).unwrap()
+    }
+
+    fn run(input: &str) -> Result<Option<Term>, EvalError> {
+        super::eval(&parse(input)).map(|value| value.to_term())
+    }
+
+    #[test]
+    fn test_compile() {
+        assert_eq!(
+            run("(lambda f:Nat->Nat.lambda x:Nat.f (f x)) (lambda n:Nat.succ(n)) 0;"),
+            Ok(Some(parse("2;")))
+        );
+        // a function cannot be read back
+        assert_eq!(run("{0, lambda x:Nat.x};"), Ok(None));
+        assert_eq!(run("head[Nat] nil[Nat];"), Err(EvalError::NoRuleApplies));
+
+        // the code is built once and can run in any environment
+        let code = compile(&parse("lambda f:Nat->Nat.f (f 0);"));
+        let twice = |argument| match code.run(&Env::default())? {
+            Value::Function(function) => function(argument),
+            _ => Err(EvalError::NoRuleApplies),
+        };
+        let succ = Value::Function(Rc::new(|value| match value {
+            Value::Number(n) => n
+                .checked_add(1)
+                .map(Value::Number)
+                .ok_or(EvalError::NoRuleApplies),
+            _ => Err(EvalError::NoRuleApplies),
+        }));
+        assert_eq!(
+            twice(succ).map(|value| value.to_term()),
+            Ok(Some(parse("2;")))
+        );
+    }
+
+    // compiled code must agree with the substitution evaluator
+    #[test]
+    fn test_agrees_with_substitution() {
+        for input in [
+            "if false then true else false;",
+            "{if true then false else true, succ(0)};",
+            "(lambda p:Bool*Nat.p.2) {false, succ(0)};",
+            "(lambda r:{a:Nat, b:Bool}.r.b) {a=0, b=true};",
+            "case inl succ(0) as Nat+Bool of inl n => {n, n} | inr b => {0, 0};",
+            "case <b=true> as <a:Nat, b:Bool> of <a=n> => n | <b=x> => if x then succ(0) else 0;",
+            "head[Nat] (tail[Nat] (cons[Nat] 0 (cons[Nat] succ(0) nil[Nat])));",
+            "isnil[Nat] (cons[Nat] 0 nil[Nat]);",
+            "(lambda f:Nat->Nat.f (f 0)) (lambda n:Nat.succ(n));",
+            "(lambda x:Nat.lambda y:Nat.{y, x}) 0 succ(0);",
+        ] {
+            // numbers are read back as numerals, the substitution evaluator
+            // keeps the chains of `succ` of the program
+            let value = |term: Option<Term>| eval::Value::from_term(&term?);
+            assert_eq!(
+                run(input).map(value),
+                eval::eval(&parse(input)).map(Some).map(value),
+                "{}",
+                input
+            );
+        }
+    }
+}
@@ -1 +1,32 @@
-pub struct Context {}
+use tapl_frontend::intern::Symbol;
+
+use crate::typing::Type;
+
+// Typing context, the innermost binding is at de Bruijn index 0
+#[derive(Clone, Debug, Default)]
+pub struct Context {
+    bindings: Vec<(Symbol, Type)>,
+}
+
+impl Context {
+    pub fn push(&mut self, name: Symbol, typ: Type) {
+        self.bindings.push((name, typ));
+    }
+
+    pub fn pop(&mut self) {
+        self.bindings.pop();
+    }
+
+    // the bindings from the outermost in
+    pub fn bindings(&self) -> &[(Symbol, Type)] {
+        &self.bindings
+    }
+
+    pub fn get_type(&self, index: usize) -> Option<Type> {
+        self.bindings
+            .iter()
+            .rev()
+            .nth(index)
+            .map(|(_, typ)| typ.clone())
+    }
+}
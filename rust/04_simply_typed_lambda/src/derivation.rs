@@ -0,0 +1,181 @@
+use tapl_frontend::{intern::Symbol, latex::texttt, visit::Node};
+
+use crate::{
+    context::Context,
+    parser::Term,
+    printer::Named,
+    typing::{type_of, Type, TypeError},
+};
+
+// A typing derivation (TAPL 9.2): the rule that concludes Γ ⊢ t : T, with
+// the derivations of its premises in the order of the subterms
+#[derive(Clone, Debug, PartialEq)]
+pub struct Derivation {
+    pub rule: &'static str,
+    // Γ from the outermost binding in
+    pub context: Vec<(Symbol, Type)>,
+    pub term: Term,
+    pub typ: Type,
+    pub premises: Vec<Derivation>,
+}
+
+fn rule(term: &Term) -> &'static str {
+    match term {
+        Term::TmTrue => "T-True",
+        Term::TmFalse => "T-False",
+        Term::TmZero => "T-Zero",
+        Term::TmNumber(_) => "T-Number",
+        Term::TmSucc(_) => "T-Succ",
+        Term::TmString(_) => "T-String",
+        Term::TmFloat(_) => "T-Float",
+        Term::TmVar(_) => "T-Var",
+        Term::TmAbs(_, _, _) => "T-Abs",
+        Term::TmApp(_, _) => "T-App",
+        Term::TmIf(_, _, _) => "T-If",
+        Term::TmTuple(_) => "T-Tuple",
+        Term::TmProj(_, _) => "T-Proj",
+        Term::TmRecord(_) => "T-Rcd",
+        Term::TmRecordProj(_, _) => "T-RcdProj",
+        Term::TmInl(_, _) => "T-Inl",
+        Term::TmInr(_, _) => "T-Inr",
+        Term::TmCase(_, _, _, _, _) => "T-Case",
+        Term::TmTag(_, _, _) => "T-Variant",
+        Term::TmVariantCase(_, _) => "T-Case",
+        Term::TmNil(_) => "T-Nil",
+        Term::TmCons(_, _, _) => "T-Cons",
+        Term::TmIsNil(_, _) => "T-IsNil",
+        Term::TmHead(_, _) => "T-Head",
+        Term::TmTail(_, _) => "T-Tail",
+        Term::TmGlobal(_, _) => "T-Global",
+        Term::TmPrim(_) => "T-Prim",
+    }
+}
+
+// the derivation `type_of` finds for `term`, or the error it fails with
+pub fn derive(ctx: &mut Context, term: &Term) -> Result<Derivation, TypeError> {
+    let typ = type_of(ctx, term)?;
+    // the variable each premise is typed under, in `children` order
+    let binders = match term {
+        Term::TmAbs(name, typ, _) => vec![Some((name, typ.clone()))],
+        Term::TmCase(scrutinee, inl_name, _, inr_name, _) => match type_of(ctx, scrutinee)? {
+            Type::Sum(inl, inr) => vec![None, Some((inl_name, *inl)), Some((inr_name, *inr))],
+            found => return Err(TypeError::SumTypeExpected(found)),
+        },
+        Term::TmVariantCase(scrutinee, branches) => match type_of(ctx, scrutinee)? {
+            Type::Variant(fields) => std::iter::once(None)
+                .chain(branches.iter().map(|(label, name, _)| {
+                    let field = fields.iter().find(|(field, _)| field == label);
+                    field.map(|(_, typ)| (name, typ.clone()))
+                }))
+                .collect(),
+            found => return Err(TypeError::VariantTypeExpected(found)),
+        },
+        _ => vec![],
+    };
+    let mut premises = vec![];
+    for (i, (_, child)) in term.children().into_iter().enumerate() {
+        let binder = binders.get(i).cloned().flatten();
+        if let Some((name, typ)) = &binder {
+            ctx.push(**name, typ.clone());
+        }
+        let premise = derive(ctx, child);
+        if binder.is_some() {
+            ctx.pop();
+        }
+        premises.push(premise?);
+    }
+    Ok(Derivation {
+        rule: rule(term),
+        context: ctx.bindings().to_vec(),
+        term: term.clone(),
+        typ,
+        premises,
+    })
+}
+
+impl Derivation {
+    // Γ ⊢ t : T, with the variables of t named after Γ
+    fn judgement(&self) -> String {
+        let names = self
+            .context
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>();
+        let context = self
+            .context
+            .iter()
+            .map(|(name, typ)| texttt(&format!("{}:{}", name, typ)))
+            .collect::<Vec<_>>();
+        let term = Named {
+            term: &self.term,
+            names: &names,
+        };
+        format!(
+            "{} \\vdash {} : {}",
+            context.join(", "),
+            texttt(&term.to_string()),
+            texttt(&self.typ.to_string())
+        )
+        .trim_start()
+        .to_string()
+    }
+
+    // The derivation tree as nested `\inferrule*` of the mathpartir package,
+    // to be set inside a `mathpar` environment or math mode
+    pub fn to_latex(&self) -> String {
+        let mut out = String::new();
+        self.write_latex(&mut out, 0);
+        out
+    }
+
+    fn write_latex(&self, out: &mut String, indent: usize) {
+        let pad = "  ".repeat(indent);
+        *out += &format!(
+            "{}\\inferrule*[right=\\textsc{{{}}}]\n{}  {{",
+            pad, self.rule, pad
+        );
+        for (i, premise) in self.premises.iter().enumerate() {
+            *out += if i == 0 { "\n" } else { " \\and\n" };
+            premise.write_latex(out, indent + 2);
+        }
+        if !self.premises.is_empty() {
+            *out += &format!("\n{}  ", pad);
+        }
+        *out += &format!("}}\n{}  {{{}}}", pad, self.judgement());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn derive_str(input: &str) -> Result<Derivation, TypeError> {
+        let term = Parser::new().parse(input).unwrap();
+        derive(&mut Context::default(), &term)
+    }
+
+    #[test]
+    fn test_derive() {
+        let derivation = derive_str("(lambda x:Bool.x) true;").unwrap();
+        let rules = |derivation: &Derivation| {
+            let rules = derivation.premises.iter().map(|premise| premise.rule);
+            rules.collect::<Vec<_>>()
+        };
+        assert_eq!(derivation.rule, "T-App");
+        assert_eq!(rules(&derivation), ["T-Abs", "T-True"]);
+        let body = &derivation.premises[0].premises[0];
+        assert_eq!(body.context, [("x".into(), Type::Boolean)]);
+        assert_eq!(
+            derive_str("lambda x:Bool.x;").unwrap().to_latex(),
+            "\\inferrule*[right=\\textsc{T-Abs}]\n  {\n    \
+             \\inferrule*[right=\\textsc{T-Var}]\n      {}\n      \
+             {\\texttt{x:Bool} \\vdash \\texttt{x} : \\texttt{Bool}}\n  }\n  \
+             {\\vdash \\texttt{lambda x:Bool.x} : \\texttt{Bool->Bool}}"
+        );
+        assert_eq!(
+            derive_str("if 0 then true else false;"),
+            Err(TypeError::IfConditionNotBoolean(Type::Number))
+        );
+    }
+}
@@ -0,0 +1,549 @@
+use tapl_frontend::{intern::Symbol, visit::Node};
+
+use crate::{
+    context::Context,
+    eval::EvalError,
+    located::{as_written, culprit, Located},
+    parser::{ParseError, Parser, Term},
+    reconstruct::{has_vars, solve},
+    span::{Span, SpanTree},
+    typing::{type_of, Type, TypeError},
+};
+
+// Text underlined in the code frame, the primary label marks the culprit and
+// secondary labels give context such as the type of a function
+#[derive(Clone, Debug, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    pub primary: bool,
+}
+
+// An error message with labeled source spans and an optional suggestion,
+// rendered the way rustc prints errors
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    fn new(message: String) -> Self {
+        Diagnostic {
+            message,
+            labels: vec![],
+            help: None,
+        }
+    }
+
+    fn primary(mut self, span: Span, message: String) -> Self {
+        self.labels.push(Label {
+            span,
+            message,
+            primary: true,
+        });
+        self
+    }
+
+    fn secondary(mut self, span: Span, message: String) -> Self {
+        self.labels.push(Label {
+            span,
+            message,
+            primary: false,
+        });
+        self
+    }
+
+    fn help(mut self, help: impl ToString) -> Self {
+        self.help = Some(help.to_string());
+        self
+    }
+
+    // `color` wraps the header, the underlines and the gutter in ANSI escapes
+    pub fn render(&self, source: &str, color: bool) -> String {
+        let paint = |text: &str, code: &str| {
+            if color {
+                format!("\x1b[{}m{}\x1b[0m", code, text)
+            } else {
+                text.to_string()
+            }
+        };
+        let mut labels = self.labels.iter().collect::<Vec<_>>();
+        labels.sort_by_key(|label| (label.span.start, !label.primary));
+        let lines = source.lines().collect::<Vec<_>>();
+        let width = labels
+            .iter()
+            .map(|label| label.span.line_col(source).0)
+            .max()
+            .unwrap_or(1)
+            .to_string()
+            .len();
+        let gutter = paint(&format!("{} |", " ".repeat(width)), "1;34");
+
+        let mut out = format!("{}: {}\n", paint("error", "1;31"), self.message);
+        if let Some(label) = labels.first() {
+            let (line, column) = label.span.line_col(source);
+            out += &format!(
+                "{}{} {}:{}\n",
+                " ".repeat(width),
+                paint("-->", "1;34"),
+                line,
+                column
+            );
+        }
+        out += &format!("{}\n", gutter);
+        let mut previous_line = None;
+        for label in labels {
+            let (line, column) = label.span.line_col(source);
+            let text = lines.get(line - 1).copied().unwrap_or("");
+            if previous_line != Some(line) {
+                let number = paint(&format!("{:>width$} |", line, width = width), "1;34");
+                out += &format!("{} {}\n", number, text);
+                previous_line = Some(line);
+            }
+            // a span reaching past its first line is underlined up to the line end
+            let length = label
+                .span
+                .text(source)
+                .lines()
+                .next()
+                .map_or(0, |first| first.chars().count())
+                .max(1);
+            let (marker, code) = if label.primary {
+                ("^", "1;31")
+            } else {
+                ("-", "1;34")
+            };
+            let underline = paint(&marker.repeat(length), code);
+            out += &format!(
+                "{} {}{} {}\n",
+                gutter,
+                " ".repeat(column - 1),
+                underline,
+                paint(&label.message, code)
+            );
+        }
+        if let Some(help) = &self.help {
+            out += &format!("{} {}: {}\n", gutter, paint("help", "1"), help);
+        }
+        out
+    }
+}
+
+// evaluation errors only know where the program, or the failing primitive, is
+impl From<Located<EvalError>> for Diagnostic {
+    fn from(located: Located<EvalError>) -> Self {
+        let label = match &located.error {
+            EvalError::PrimitiveFailed(_) => "this primitive has no result for its argument",
+            _ => "while evaluating this",
+        };
+        Diagnostic::new(located.error.to_string()).primary(located.span, label.to_string())
+    }
+}
+
+impl From<Located<ParseError>> for Diagnostic {
+    fn from(located: Located<ParseError>) -> Self {
+        match &located.error {
+            ParseError::Syntax(error) => Diagnostic::new(format!(
+                "expected {}, found {}",
+                error.expected, error.found
+            ))
+            .primary(located.span, format!("expected {}", error.expected)),
+            error => Diagnostic::new(error.to_string())
+                .primary(located.span, "in this statement".to_string()),
+        }
+    }
+}
+
+// The parse and type errors of every statement of a program in one pass,
+// statements are typed independently of each other
+pub fn check_program(parser: &mut Parser, source: &str) -> Vec<Diagnostic> {
+    parser
+        .parse_program(source)
+        .into_iter()
+        .filter_map(|statement| match statement {
+            Ok(statement) => {
+                diagnose(&mut Context::default(), &statement.term, &statement.spans).err()
+            }
+            Err(error) => Some(error.into()),
+        })
+        .collect()
+}
+
+// the abstraction whose parameter `name` still has a type variable, the
+// first one as reconstruction finds it
+fn unsolved_binder(term: &Term, spans: &SpanTree, name: Symbol) -> Option<Span> {
+    match term {
+        Term::TmAbs(param, typ, _) if *param == name && has_vars(typ) => Some(spans.span),
+        _ => term
+            .children()
+            .into_iter()
+            .enumerate()
+            .find_map(|(i, (_, child))| unsolved_binder(child, spans.child(i), name)),
+    }
+}
+
+// Types `term` and explains a failure with expected and found types at the
+// innermost offending subterm
+pub fn diagnose(ctx: &mut Context, term: &Term, spans: &SpanTree) -> Result<Type, Diagnostic> {
+    let reconstructed;
+    let term = match solve(ctx, term) {
+        Ok((term, None)) => {
+            reconstructed = term;
+            &reconstructed
+        }
+        Ok((solved, Some(name))) => {
+            let span = unsolved_binder(&solved, spans, name).unwrap_or(spans.span);
+            let error = TypeError::AnnotationRequired(name);
+            return Err(Diagnostic::new(error.to_string())
+                .primary(span, format!("the type of {} is not determined here", name)));
+        }
+        // the error of the term as written, which `type_of` reports as well
+        Err(_) => term,
+    };
+    if let Ok(typ) = type_of(ctx, term) {
+        return Ok(typ);
+    }
+    let mut found = culprit(ctx, term, spans);
+    // the culprit's own error, its children are well typed
+    let error = match type_of(&mut found.context, found.term) {
+        Ok(_) => unreachable!("the culprit of a type error is ill-typed"),
+        Err(error) => as_written(error, &found),
+    };
+    let mut ctx = found.context;
+    let spans = found.spans;
+    let mismatch = |expected: &dyn ToString, found: &Type| {
+        format!("expected {}, found {}", expected.to_string(), found)
+    };
+
+    let diagnostic = match (&error, found.term) {
+        (TypeError::IfConditionNotBoolean(found), _) => {
+            Diagnostic::new(format!("condition of if must be Bool, found {}", found))
+                .primary(spans.child(0).span, mismatch(&Type::Boolean, found))
+                .help("branch on a boolean, only true and false can be tested")
+        }
+        (TypeError::OperatorArgumentNotBoolean(name, found), culprit) => {
+            // the operand of that type, the constants the operator adds
+            // have its span
+            let operand = culprit
+                .children()
+                .into_iter()
+                .enumerate()
+                .find(|(i, (_, child))| {
+                    spans.child(*i).span != spans.span
+                        && type_of(&mut ctx, child).as_ref() == Ok(found)
+                })
+                .map_or(spans.span, |(i, _)| spans.child(i).span);
+            Diagnostic::new(format!(
+                "argument of {} must be Bool, found {}",
+                name, found
+            ))
+            .primary(operand, mismatch(&Type::Boolean, found))
+        }
+        (TypeError::IfBranchesMismatch(then_type, else_type), _) => Diagnostic::new(format!(
+            "branches of if have different types {} and {}",
+            then_type, else_type
+        ))
+        .secondary(spans.child(1).span, format!("this is {}", then_type))
+        .primary(spans.child(2).span, mismatch(then_type, else_type))
+        .help("both branches must have the same type"),
+        (TypeError::SuccArgumentNotNumber(found), _) => {
+            Diagnostic::new(format!("argument of succ must be Nat, found {}", found))
+                .primary(spans.child(0).span, mismatch(&Type::Number, found))
+        }
+        (TypeError::ParameterTypeMismatch(expected, found), Term::TmApp(function, _)) => {
+            let function_type = type_of(&mut ctx, function)
+                .map(|typ| typ.to_string())
+                .unwrap_or_default();
+            Diagnostic::new(format!(
+                "function expects an argument of type {}, found {}",
+                expected, found
+            ))
+            .secondary(
+                spans.child(0).span,
+                format!("this function has type {}", function_type),
+            )
+            .primary(spans.child(1).span, mismatch(expected, found))
+        }
+        (TypeError::ArrowTypeExpected(found), _) => {
+            Diagnostic::new(format!("only functions can be applied, found {}", found))
+                .primary(spans.child(0).span, mismatch(&"a function type", found))
+                .help("remove the argument, or parenthesize the application you meant")
+        }
+        (TypeError::ProductTypeExpected(found), Term::TmProj(_, index)) => {
+            let diagnostic =
+                Diagnostic::new(format!("cannot take component {} of {}", index, found))
+                    .primary(spans.child(0).span, mismatch(&"a tuple", found));
+            match found {
+                Type::Record(_) => diagnostic.help("project a record field by its label"),
+                _ => diagnostic,
+            }
+        }
+        (TypeError::TupleIndexOutOfRange(index), Term::TmProj(term, _)) => {
+            let size = match type_of(&mut ctx, term) {
+                Ok(Type::Product(types)) => types.len(),
+                _ => 0,
+            };
+            Diagnostic::new(format!("tuple index {} out of range", index))
+                .primary(spans.span, format!("the tuple has {} components", size))
+                .help(format!("tuple components are numbered from 1 to {}", size))
+        }
+        (TypeError::RecordTypeExpected(found), Term::TmRecordProj(_, label)) => {
+            let diagnostic = Diagnostic::new(format!("cannot take field {} of {}", label, found))
+                .primary(spans.child(0).span, mismatch(&"a record", found));
+            match found {
+                Type::Product(_) => diagnostic.help("project a tuple component by its index"),
+                _ => diagnostic,
+            }
+        }
+        (TypeError::LabelNotFound(label), Term::TmRecordProj(term, _)) => {
+            let record_type = type_of(&mut ctx, term);
+            let labels = match &record_type {
+                Ok(Type::Record(fields)) => fields
+                    .iter()
+                    .map(|(label, _)| label.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                _ => String::new(),
+            };
+            let record_type = record_type.map(|typ| typ.to_string()).unwrap_or_default();
+            Diagnostic::new(format!("no field {} on {}", label, record_type))
+                .primary(spans.span, "unknown field".to_string())
+                .help(format!("the record has the fields {}", labels))
+        }
+        (TypeError::SumTypeExpected(found), Term::TmCase(_, _, _, _, _)) => {
+            Diagnostic::new(format!("case needs a value of sum type, found {}", found))
+                .primary(spans.child(0).span, mismatch(&"a sum type", found))
+        }
+        (TypeError::SumTypeExpected(found), _) => {
+            Diagnostic::new(format!("injection is annotated with {}", found))
+                .primary(spans.span, mismatch(&"a sum type", found))
+                .help("annotate the injection with a sum type such as `as Nat+Bool`")
+        }
+        (TypeError::InjectionTypeMismatch(expected, found), _) => Diagnostic::new(format!(
+            "injected term must be {}, found {}",
+            expected, found
+        ))
+        .primary(spans.child(0).span, mismatch(expected, found)),
+        (TypeError::CaseBranchesMismatch(first, other), Term::TmVariantCase(term, branches)) => {
+            let fields = match type_of(&mut ctx, term) {
+                Ok(Type::Variant(fields)) => fields,
+                _ => vec![],
+            };
+            // the first branch of another type than the first branch
+            let position = branches
+                .iter()
+                .position(|(label, name, branch)| {
+                    let field = fields.iter().find(|(field, _)| field == label);
+                    let binder = field.map_or(Type::Boolean, |(_, typ)| typ.clone());
+                    ctx.push(*name, binder);
+                    let typ = type_of(&mut ctx, branch);
+                    ctx.pop();
+                    typ.as_ref() == Ok(other)
+                })
+                .unwrap_or(1);
+            Diagnostic::new(format!(
+                "branches of case have different types {} and {}",
+                first, other
+            ))
+            .secondary(spans.child(1).span, format!("this is {}", first))
+            .primary(spans.child(position + 1).span, mismatch(first, other))
+            .help("all branches must have the same type")
+        }
+        (TypeError::CaseBranchesMismatch(inl_type, inr_type), _) => Diagnostic::new(format!(
+            "branches of case have different types {} and {}",
+            inl_type, inr_type
+        ))
+        .secondary(spans.child(1).span, format!("this is {}", inl_type))
+        .primary(spans.child(2).span, mismatch(inl_type, inr_type))
+        .help("both branches must have the same type"),
+        (TypeError::VariantTypeExpected(found), Term::TmVariantCase(_, _)) => Diagnostic::new(
+            format!("case needs a value of variant type, found {}", found),
+        )
+        .primary(spans.child(0).span, mismatch(&"a variant type", found)),
+        (TypeError::VariantTypeExpected(found), _) => {
+            Diagnostic::new(format!("tag is annotated with {}", found))
+                .primary(spans.span, mismatch(&"a variant type", found))
+                .help("annotate the tag with a variant type such as `as <none:Bool, some:Nat>`")
+        }
+        (TypeError::LabelNotFound(label), Term::TmTag(_, _, typ)) => {
+            let labels = match typ {
+                Type::Variant(fields) => fields
+                    .iter()
+                    .map(|(label, _)| label.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                _ => String::new(),
+            };
+            Diagnostic::new(format!("no label {} in {}", label, typ))
+                .primary(spans.span, "unknown label".to_string())
+                .help(format!("the variant has the labels {}", labels))
+        }
+        (TypeError::MissingBranches(fields), _) => {
+            let uncovered = fields
+                .iter()
+                .map(|(label, typ)| format!("<{}:{}>", label, typ))
+                .collect::<Vec<_>>();
+            let branches = fields
+                .iter()
+                .map(|(label, _)| format!("<{}=_> => ...", label))
+                .collect::<Vec<_>>();
+            Diagnostic::new(format!("case does not cover {}", uncovered.join(", ")))
+                .primary(spans.span, format!("missing {}", uncovered.join(", ")))
+                .help(format!("add the branches `| {}`", branches.join(" | ")))
+        }
+        (TypeError::UnreachableBranch(label), Term::TmVariantCase(term, branches)) => {
+            let typ = type_of(&mut ctx, term);
+            let labels = match &typ {
+                Ok(Type::Variant(fields)) => fields.iter().map(|(label, _)| *label).collect(),
+                _ => vec![],
+            };
+            // the first branch for a label the type lacks or has handled already
+            let position = (0..branches.len())
+                .find(|&i| {
+                    let (other, _, _) = &branches[i];
+                    other == label
+                        && (!labels.contains(label)
+                            || branches[..i].iter().any(|(other, _, _)| other == label))
+                })
+                .unwrap_or(0);
+            let diagnostic = Diagnostic::new(format!("unreachable branch for label {}", label))
+                .primary(
+                    spans.child(position + 1).span,
+                    "unreachable branch".to_string(),
+                );
+            match branches.iter().position(|(other, _, _)| other == label) {
+                Some(first) if first < position => diagnostic
+                    .secondary(
+                        spans.child(first + 1).span,
+                        format!("{} is handled here first", label),
+                    )
+                    .help("remove the later branch"),
+                _ => diagnostic.help(format!(
+                    "the scrutinee has type {}, which has no label {}",
+                    typ.map(|typ| typ.to_string()).unwrap_or_default(),
+                    label
+                )),
+            }
+        }
+        (TypeError::ListTypeMismatch(expected, found), Term::TmCons(typ, _, _)) => {
+            // the head is checked against the element type before the tail
+            let (part, child) = if expected == typ {
+                ("head", 0)
+            } else {
+                ("tail", 1)
+            };
+            Diagnostic::new(format!("{} of cons must be {}", part, expected))
+                .primary(spans.child(child).span, mismatch(expected, found))
+        }
+        (TypeError::ListTypeMismatch(expected, found), _) => {
+            let diagnostic = Diagnostic::new(format!("expected a {}, found {}", expected, found))
+                .primary(spans.child(0).span, mismatch(expected, found));
+            match found {
+                Type::List(element) => {
+                    diagnostic.help(format!("annotate the operation with [{}]", element))
+                }
+                _ => diagnostic,
+            }
+        }
+        _ => Diagnostic::new(error.to_string()).primary(spans.span, "here".to_string()),
+    };
+    Err(diagnostic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(source: &str) -> String {
+        let (term, spans) = Parser::new().parse_with_spans(source).unwrap();
+        diagnose(&mut Context::default(), &term, &spans)
+            .unwrap_err()
+            .render(source, false)
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            render("lambda x:Nat.\n  if x then 0 else succ(0);"),
+            "error: condition of if must be Bool, found Nat\n \
+             --> 2:6\n  \
+               |\n\
+             2 |   if x then 0 else succ(0);\n  \
+               |      ^ expected Bool, found Nat\n  \
+               | help: branch on a boolean, only true and false can be tested\n"
+        );
+        assert_eq!(
+            render("(lambda f:Nat->Nat.f) true;"),
+            "error: function expects an argument of type Nat->Nat, found Bool\n \
+             --> 1:1\n  \
+               |\n\
+             1 | (lambda f:Nat->Nat.f) true;\n  \
+               | --------------------- this function has type (Nat->Nat)->Nat->Nat\n  \
+               |                       ^^^^ expected Nat->Nat, found Bool\n"
+        );
+        assert!(render("{x=0}.y;").contains("help: the record has the fields x"));
+        assert_eq!(
+            render("or(true, 0);"),
+            "error: argument of or must be Bool, found Nat\n \
+             --> 1:10\n  \
+               |\n\
+             1 | or(true, 0);\n  \
+               |          ^ expected Bool, found Nat\n"
+        );
+        // the abstraction of a parameter applied to itself
+        assert_eq!(
+            render("{0, lambda x. x x};"),
+            "error: annotation required: the type of x cannot be inferred, write lambda x:T\n \
+             --> 1:5\n  \
+               |\n\
+             1 | {0, lambda x. x x};\n  \
+               |     ^^^^^^^^^^^^^ the type of x is not determined here\n"
+        );
+    }
+
+    #[test]
+    fn test_case_coverage() {
+        assert_eq!(
+            render("lambda o:<none:Bool, some:Nat, many:Nat*Nat>.\n  case o of <some=n> => n;"),
+            "error: case does not cover <none:Bool>, <many:Nat*Nat>\n \
+             --> 2:3\n  \
+               |\n\
+             2 |   case o of <some=n> => n;\n  \
+               |   ^^^^^^^^^^^^^^^^^^^^^^^ missing <none:Bool>, <many:Nat*Nat>\n  \
+               | help: add the branches `| <none=_> => ... | <many=_> => ...`\n"
+        );
+        assert_eq!(
+            render("lambda o:<none:Bool, some:Nat>.case o of <none=b> => 0 | <some=n> => n | <none=c> => 0;"),
+            "error: unreachable branch for label none\n \
+             --> 1:54\n  \
+               |\n\
+             1 | lambda o:<none:Bool, some:Nat>.case o of <none=b> => 0 | <some=n> => n | <none=c> => 0;\n  \
+               |                                                      - none is handled here first\n  \
+               |                                                                                      ^ unreachable branch\n  \
+               | help: remove the later branch\n"
+        );
+        assert!(render("case <a=0> as <a:Nat> of <a=n> => n | <b=m> => m;")
+            .contains("help: the scrutinee has type <a:Nat>, which has no label b"));
+    }
+
+    #[test]
+    fn test_check_program() {
+        let source = "succ(true);\nlambda x:Bool.;\ntrue;\nif 0 then y else 0;\n0 #;\nz;";
+        let messages = check_program(&mut Parser::new(), source)
+            .iter()
+            .map(|diagnostic| diagnostic.message.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                "argument of succ must be Nat, found Bool",
+                "expected a term, found `;`",
+                "unbound variable y",
+                "expected a token, found `#`",
+                "unbound variable z",
+            ]
+        );
+    }
+}
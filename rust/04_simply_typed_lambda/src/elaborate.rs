@@ -0,0 +1,329 @@
+use tapl_frontend::intern::Symbol;
+
+use crate::{
+    ast_parser::{ASTTerm, Pattern, SpannedTerm},
+    parser::Term,
+    span::{SpanTree, Spanned},
+    type_parser::option_type,
+    typing::Type,
+};
+
+// Elaboration lowers the derived forms of the surface syntax into the core
+// calculus (TAPL 11.3 and 11.5), so typechecking and evaluation only ever see
+// the constructs of `Term`. Every node a derived form expands to keeps the
+// span of the derived form.
+//
+//   (t1; t2)              = (lambda _:Unit.t2) t1
+//   let x:T = t1 in t2    = (lambda x:T.t2) t1
+//   let {x, y} = t1 in t2 = let p' = t1 in let x = p'.1 in let y = p'.2 in t2
+//   lambda x:S y:T.t      = lambda x:S.lambda y:T.t
+//   not(t)                = if t then false else true
+//   and(t1, t2)           = if t1 then t2 else false
+//   or(t1, t2)            = if t1 then true else t2
+//   none[T]               = <none=unit> as Option T
+//   some t                = <some=t> as Option T
+//
+// `some t` leaves `T` to the parser, which takes the variant type the
+// enclosing term expects, or else `Option` of the type of `t`. Sequencing
+// evaluates `t1` first for its effect, so a `t1` that is not of type Unit is
+// a type error (TAPL 11.3). A wildcard `_` binder needs no lowering, the
+// parser never lets a variable refer to it. A record
+// pattern projects its labels the same way, and a nested pattern projects
+// further along the path to each variable. The matched value is bound once,
+// to `p'`, which no variable in the source can be named, so the projections
+// are never captured by the variables of the pattern or the body.
+pub fn elaborate(term: &SpannedTerm) -> SpannedTerm {
+    let span = term.span;
+    let spanned = |node: ASTTerm| Spanned { node, span };
+    let boxed = |term: &SpannedTerm| Box::new(elaborate(term));
+    let node = match &term.node {
+        ASTTerm::TmSeq(first, second) => {
+            let unit = Some(Type::Product(vec![]));
+            let_in(
+                Symbol::intern("_"),
+                unit,
+                elaborate(first),
+                elaborate(second),
+            )
+        }
+        ASTTerm::TmLet(Pattern::Var(name), typ, bound, body) => {
+            let_in(*name, typ.clone(), elaborate(bound), elaborate(body))
+        }
+        ASTTerm::TmLet(pattern, typ, bound, body) => {
+            let matched = Symbol::intern("p'");
+            let value = spanned(ASTTerm::TmVar(matched));
+            let body = match_pattern(pattern, value, elaborate(body));
+            let_in(matched, typ.clone(), elaborate(bound), body)
+        }
+        ASTTerm::TmMultiAbs(params, body) => {
+            let (last, outer) = params.split_last().expect("at least two parameters");
+            let abs = ASTTerm::TmAbs(last.0, last.1.clone(), boxed(body));
+            outer.iter().rev().fold(abs, |abs, (name, typ)| {
+                ASTTerm::TmAbs(*name, typ.clone(), Box::new(spanned(abs)))
+            })
+        }
+        ASTTerm::TmNot(term) => ASTTerm::TmIf(
+            boxed(term),
+            Box::new(spanned(ASTTerm::TmFalse)),
+            Box::new(spanned(ASTTerm::TmTrue)),
+        ),
+        ASTTerm::TmAnd(left, right) => ASTTerm::TmIf(
+            boxed(left),
+            boxed(right),
+            Box::new(spanned(ASTTerm::TmFalse)),
+        ),
+        ASTTerm::TmOr(left, right) => ASTTerm::TmIf(
+            boxed(left),
+            Box::new(spanned(ASTTerm::TmTrue)),
+            boxed(right),
+        ),
+        ASTTerm::TmNone(typ) => ASTTerm::TmTag(
+            Symbol::intern("none"),
+            Box::new(spanned(ASTTerm::TmTuple(vec![]))),
+            Some(option_type(typ.clone())),
+        ),
+        ASTTerm::TmSome(term) => ASTTerm::TmTag(Symbol::intern("some"), boxed(term), None),
+        ASTTerm::TmTrue => ASTTerm::TmTrue,
+        ASTTerm::TmFalse => ASTTerm::TmFalse,
+        ASTTerm::TmZero => ASTTerm::TmZero,
+        ASTTerm::TmNumber(n) => ASTTerm::TmNumber(*n),
+        ASTTerm::TmString(text) => ASTTerm::TmString(text.clone()),
+        ASTTerm::TmFloat(value) => ASTTerm::TmFloat(*value),
+        ASTTerm::TmSucc(term) => ASTTerm::TmSucc(boxed(term)),
+        ASTTerm::TmVar(name) => ASTTerm::TmVar(*name),
+        ASTTerm::TmAbs(name, typ, body) => ASTTerm::TmAbs(*name, typ.clone(), boxed(body)),
+        ASTTerm::TmApp(left, right) => ASTTerm::TmApp(boxed(left), boxed(right)),
+        ASTTerm::TmIf(if_term, then_term, else_term) => {
+            ASTTerm::TmIf(boxed(if_term), boxed(then_term), boxed(else_term))
+        }
+        ASTTerm::TmTuple(terms) => ASTTerm::TmTuple(terms.iter().map(elaborate).collect()),
+        ASTTerm::TmProj(term, index) => ASTTerm::TmProj(boxed(term), *index),
+        ASTTerm::TmRecord(fields) => ASTTerm::TmRecord(
+            fields
+                .iter()
+                .map(|(label, term)| (*label, elaborate(term)))
+                .collect(),
+        ),
+        ASTTerm::TmRecordProj(term, label) => ASTTerm::TmRecordProj(boxed(term), *label),
+        ASTTerm::TmInl(term, typ) => ASTTerm::TmInl(boxed(term), typ.clone()),
+        ASTTerm::TmInr(term, typ) => ASTTerm::TmInr(boxed(term), typ.clone()),
+        ASTTerm::TmCase(term, inl_name, inl_term, inr_name, inr_term) => ASTTerm::TmCase(
+            boxed(term),
+            *inl_name,
+            boxed(inl_term),
+            *inr_name,
+            boxed(inr_term),
+        ),
+        ASTTerm::TmTag(label, term, typ) => ASTTerm::TmTag(*label, boxed(term), typ.clone()),
+        ASTTerm::TmVariantCase(term, branches) => ASTTerm::TmVariantCase(
+            boxed(term),
+            branches
+                .iter()
+                .map(|(label, name, branch)| (*label, *name, elaborate(branch)))
+                .collect(),
+        ),
+        ASTTerm::TmNil(typ) => ASTTerm::TmNil(typ.clone()),
+        ASTTerm::TmCons(typ, head, tail) => ASTTerm::TmCons(typ.clone(), boxed(head), boxed(tail)),
+        ASTTerm::TmIsNil(typ, term) => ASTTerm::TmIsNil(typ.clone(), boxed(term)),
+        ASTTerm::TmHead(typ, term) => ASTTerm::TmHead(typ.clone(), boxed(term)),
+        ASTTerm::TmTail(typ, term) => ASTTerm::TmTail(typ.clone(), boxed(term)),
+    };
+    spanned(node)
+}
+
+// `(lambda x:T.t2) t1`, the parameter type is inferred from `t1` if missing
+// The boolean operator an `if` of the core calculus was elaborated from. The
+// constants it adds have the span of the whole operator, unlike those of an
+// `if` the program wrote.
+pub fn derived_operator(term: &Term, spans: &SpanTree) -> Option<Symbol> {
+    let derived = |i: usize| spans.child(i).span == spans.span;
+    let name = match term {
+        Term::TmIf(_, then_term, else_term) => match (then_term.as_ref(), else_term.as_ref()) {
+            (Term::TmFalse, Term::TmTrue) if derived(1) && derived(2) => "not",
+            (_, Term::TmFalse) if derived(2) => "and",
+            (Term::TmTrue, _) if derived(1) => "or",
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(Symbol::intern(name))
+}
+
+fn let_in(name: Symbol, typ: Option<Type>, bound: SpannedTerm, body: SpannedTerm) -> ASTTerm {
+    let span = body.span;
+    let abs = Spanned {
+        node: ASTTerm::TmAbs(name, typ, Box::new(body)),
+        span,
+    };
+    ASTTerm::TmApp(Box::new(abs), Box::new(bound))
+}
+
+// binds the variables of `pattern` to the parts of `value` around `body`,
+// `value` is a path of projections out of the matched value
+fn match_pattern(pattern: &Pattern, value: SpannedTerm, body: SpannedTerm) -> SpannedTerm {
+    let span = body.span;
+    let spanned = |node: ASTTerm| Spanned { node, span };
+    match pattern {
+        Pattern::Var(name) => spanned(let_in(*name, None, value, body)),
+        Pattern::Tuple(patterns) => {
+            patterns
+                .iter()
+                .enumerate()
+                .rev()
+                .fold(body, |body, (i, pattern)| {
+                    let part = spanned(ASTTerm::TmProj(Box::new(value.clone()), i + 1));
+                    match_pattern(pattern, part, body)
+                })
+        }
+        Pattern::Record(fields) => fields.iter().rev().fold(body, |body, (label, pattern)| {
+            let part = spanned(ASTTerm::TmRecordProj(Box::new(value.clone()), *label));
+            match_pattern(pattern, part, body)
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        context::Context,
+        parser::{Parser, Term},
+        reconstruct::reconstruct,
+        Stlc,
+    };
+    use tapl_testkit::{assert_evals_to, assert_type_error};
+
+    // the core term of `source` with its omitted types reconstructed
+    fn core(source: &str) -> Term {
+        let term = Parser::new().parse(source).unwrap();
+        reconstruct(&mut Context::default(), &term).unwrap()
+    }
+
+    fn same_core(surface: &str, core_source: &str) {
+        assert_eq!(core(surface), core(core_source));
+    }
+
+    #[test]
+    fn test_elaborate() {
+        same_core(
+            "let x:Nat = succ(0) in {x, x};",
+            "(lambda x:Nat.{x, x}) succ(0);",
+        );
+        same_core("(unit; 0);", "(lambda _:Unit.0) unit;");
+        same_core(
+            "(unit; {}; 0);",
+            "(lambda _:Unit.(lambda _:Unit.0) {}) unit;",
+        );
+        same_core(
+            "lambda x:Bool y:Nat z:Bool.{x, y, z};",
+            "lambda x:Bool.lambda y:Nat.lambda z:Bool.{x, y, z};",
+        );
+        same_core(
+            "lambda x:Bool.and(x, not(x));",
+            "lambda x:Bool.if x then if x then false else true else false;",
+        );
+        same_core("or(false, true);", "if false then true else true;");
+        assert!(Parser::new().parse("lambda _:Bool._;").is_err());
+        assert_evals_to!(
+            Stlc,
+            "let f:Nat->Nat = lambda n:Nat.succ(n) in let _:Bool = true in f (f 0);",
+            "succ(succ(0))"
+        );
+        assert_evals_to!(Stlc, "(lambda b:Bool.or(not(b), b)) false;", "true");
+        assert_type_error!(Stlc, "and(true, 0);");
+        assert_type_error!(Stlc, "not(lambda x:Bool.x);");
+        // the error names the operator the program wrote
+        let error = |source: &str| Stlc::type_of(source).unwrap_err().to_string();
+        assert_eq!(
+            error("and(true, 0);"),
+            "argument of and is not a boolean: found Nat at 0..12"
+        );
+        assert_eq!(
+            error("lambda x:Nat.or(x, false);"),
+            "argument of or is not a boolean: found Nat at 13..25"
+        );
+        assert_eq!(
+            error("not(lambda x:Bool.x);"),
+            "argument of not is not a boolean: found Bool->Bool at 0..20"
+        );
+        assert_eq!(
+            error("if true then false else 0;"),
+            "arms of conditional have different types Bool and Nat at 0..25"
+        );
+        // only a term of type Unit is sequenced, the `_` it is bound to
+        // shifts the variables of the rest
+        assert_type_error!(Stlc, "(true; 0);");
+        assert_evals_to!(Stlc, "(lambda n:Nat.(unit; succ(n))) 0;", "succ(0)");
+    }
+
+    #[test]
+    fn test_let_pattern() {
+        assert_eq!(
+            core("let {x, y} = {0, true} in {y, x};").to_string(),
+            "(lambda p':Nat*Bool.(lambda x:Nat.(lambda y:Bool.{y, x}) p'.2) p'.1) {0, true}"
+        );
+        assert_evals_to!(
+            Stlc,
+            "let {f={x, y}, g=z} = {f={0, succ(0)}, g=true} in {y, z, x};",
+            "{succ(0), true, 0}"
+        );
+        // the bound term may mention the variables the pattern rebinds
+        assert_evals_to!(
+            Stlc,
+            "(lambda x:Nat y:Bool.let {x, y} = {y, x} in {x, y}) 0 true;",
+            "{true, 0}"
+        );
+        assert_evals_to!(
+            Stlc,
+            "let {_, n}:Bool*(Nat->Nat) = {true, lambda n:Nat.succ(n)} in n 0;",
+            "succ(0)"
+        );
+        // a pattern that does not fit the bound term is blamed on the
+        // projection, not on the variable it binds
+        assert_type_error!(Stlc, "let {x, y} = {0} in x;", "tuple index 2 out of range");
+        assert_type_error!(Stlc, "let {l=x} = {m=0} in x;", "label l not found");
+        assert_type_error!(
+            Stlc,
+            "let {x, y} = 0 in succ(x);",
+            "product type expected, found Nat"
+        );
+    }
+
+    #[test]
+    fn test_option() {
+        same_core("none[Nat];", "<none={}> as <none:Unit, some:Nat>;");
+        same_core("some succ(0);", "<some=succ(0)> as Option Nat;");
+        // a failure is a value of the result type, which `case` inspects
+        let first = "let first = lambda l:List Nat.\
+                    if isnil[Nat] l then none[Nat] else some (head[Nat] l) in ";
+        assert_evals_to!(
+            Stlc,
+            &format!(
+                "{}case first cons[Nat] succ(0) nil[Nat] of <none=u> => 0 | <some=n> => n;",
+                first
+            ),
+            "succ(0)"
+        );
+        assert_evals_to!(
+            Stlc,
+            &format!("{}first nil[Nat];", first),
+            "<none={}> as Option Nat"
+        );
+        assert_evals_to!(
+            Stlc,
+            "case <err=\"empty\"> as Result Nat String of <ok=n> => \"found\" | <err=e> => e;",
+            "\"empty\""
+        );
+        // the payload takes its type from the variant type the list expects
+        assert_evals_to!(
+            Stlc,
+            "case head[Option (Nat->Nat)] \
+             cons[Option (Nat->Nat)] (some (lambda n.succ(n))) nil[Option (Nat->Nat)] \
+             of <none=u> => 0 | <some=f> => f 0;",
+            "succ(0)"
+        );
+        assert_type_error!(
+            Stlc,
+            "case some 0 of <some=b> => if b then 0 else 0 | <none=u> => 0;"
+        );
+    }
+}
@@ -1,49 +1,336 @@
+use std::fmt::{self, Formatter};
+use std::rc::Rc;
+
+use tapl_frontend::{
+    intern::Symbol,
+    outcome::{evaluate, Outcome},
+};
+
 use crate::{
-    context::Context,
     parser::Term,
+    primitive::Primitive,
+    trace,
     typing::{Type, TypeError},
 };
 
-use nom::error::VerboseError;
-
+#[derive(Clone, Debug, PartialEq)]
 pub enum EvalError {
-    VerboseError(String),
-    TypeError(String),
+    // the program was typechecked before it ran
+    TypeError(TypeError),
     NoRuleApplies,
+    // the named primitive returned no result for its argument
+    PrimitiveFailed(String),
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::TypeError(error) => write!(f, "{}", error),
+            EvalError::NoRuleApplies => write!(f, "no rule applies"),
+            EvalError::PrimitiveFailed(name) => {
+                write!(f, "primitive {} has no result for its argument", name)
+            }
+        }
+    }
 }
 
-impl From<nom::Err<VerboseError<&str>>> for EvalError {
-    fn from(i: nom::Err<VerboseError<&str>>) -> Self {
-        EvalError::VerboseError(i.to_string())
+impl std::error::Error for EvalError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EvalError::TypeError(error) => Some(error),
+            _ => None,
+        }
     }
 }
 
 impl From<TypeError> for EvalError {
     fn from(i: TypeError) -> Self {
-        EvalError::TypeError(i.to_string())
+        EvalError::TypeError(i)
+    }
+}
+
+// The normal forms of closed well-typed terms (TAPL 9.3.4), abstractions
+// keep their body unevaluated
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Value {
+    Boolean(bool),
+    Number(u64),
+    String(String),
+    Float(f64),
+    Abs(Symbol, Type, Rc<Term>),
+    Tuple(Vec<Value>),
+    Record(Vec<(Symbol, Value)>),
+    Inl(Box<Value>, Type),
+    Inr(Box<Value>, Type),
+    Tag(Symbol, Box<Value>, Type),
+    Nil(Type),
+    Cons(Type, Box<Value>, Box<Value>),
+    // wraps a Rust function, like `Term::TmPrim` it is left out of
+    // serialized values
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Prim(Primitive),
+}
+
+impl Value {
+    // `None` for a term that is not a value
+    pub fn from_term(term: &Term) -> Option<Value> {
+        let boxed = |term: &Term| Value::from_term(term).map(Box::new);
+        let value = match term {
+            Term::TmTrue => Value::Boolean(true),
+            Term::TmFalse => Value::Boolean(false),
+            Term::TmZero | Term::TmNumber(_) | Term::TmSucc(_) if is_numeric_val(term) => {
+                let mut n = 0;
+                let mut term = term;
+                while let Term::TmSucc(inner) = term {
+                    n += 1;
+                    term = inner;
+                }
+                match term {
+                    Term::TmNumber(base) => Value::Number(base.checked_add(n)?),
+                    _ => Value::Number(n),
+                }
+            }
+            Term::TmString(text) => Value::String(text.clone()),
+            Term::TmFloat(value) => Value::Float(*value),
+            Term::TmAbs(name, typ, body) => Value::Abs(*name, typ.clone(), body.clone()),
+            Term::TmTuple(terms) => Value::Tuple(
+                terms
+                    .iter()
+                    .map(Value::from_term)
+                    .collect::<Option<Vec<_>>>()?,
+            ),
+            Term::TmRecord(fields) => Value::Record(
+                fields
+                    .iter()
+                    .map(|(label, term)| Some((*label, Value::from_term(term)?)))
+                    .collect::<Option<Vec<_>>>()?,
+            ),
+            Term::TmInl(term, typ) => Value::Inl(boxed(term)?, typ.clone()),
+            Term::TmInr(term, typ) => Value::Inr(boxed(term)?, typ.clone()),
+            Term::TmTag(label, term, typ) => Value::Tag(*label, boxed(term)?, typ.clone()),
+            Term::TmNil(typ) => Value::Nil(typ.clone()),
+            Term::TmCons(typ, head, tail) => Value::Cons(typ.clone(), boxed(head)?, boxed(tail)?),
+            Term::TmPrim(primitive) => Value::Prim(primitive.clone()),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    pub fn to_term(&self) -> Term {
+        let boxed = |value: &Value| Rc::new(value.to_term());
+        match self {
+            Value::Boolean(true) => Term::TmTrue,
+            Value::Boolean(false) => Term::TmFalse,
+            Value::Number(n) => Term::number(*n),
+            Value::String(text) => Term::TmString(text.clone()),
+            Value::Float(value) => Term::TmFloat(*value),
+            Value::Abs(name, typ, body) => Term::TmAbs(*name, typ.clone(), body.clone()),
+            Value::Tuple(values) => Term::TmTuple(values.iter().map(Value::to_term).collect()),
+            Value::Record(fields) => Term::TmRecord(
+                fields
+                    .iter()
+                    .map(|(label, value)| (*label, value.to_term()))
+                    .collect(),
+            ),
+            Value::Inl(value, typ) => Term::TmInl(boxed(value), typ.clone()),
+            Value::Inr(value, typ) => Term::TmInr(boxed(value), typ.clone()),
+            Value::Tag(label, value, typ) => Term::TmTag(*label, boxed(value), typ.clone()),
+            Value::Nil(typ) => Term::TmNil(typ.clone()),
+            Value::Cons(typ, head, tail) => Term::TmCons(typ.clone(), boxed(head), boxed(tail)),
+            Value::Prim(primitive) => Term::TmPrim(primitive.clone()),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.to_term().fmt(f)
     }
 }
 
-fn eval1(ctx: &mut Context, term: &Term) -> Result<Term, EvalError> {
+pub fn is_numeric_val(mut term: &Term) -> bool {
+    while let Term::TmSucc(inner) = term {
+        term = inner;
+    }
+    matches!(term, Term::TmZero | Term::TmNumber(_))
+}
+
+pub fn is_val(term: &Term) -> bool {
     match term {
-        Term::TmIf(if_term, then_term, else_term) => match *if_term.as_ref() {
-            Term::TmTrue => Ok(then_term.as_ref().clone()),
-            Term::TmFalse => Ok(else_term.as_ref().clone()),
-            _ => {
-                let if_term = eval(ctx, if_term.as_ref())?;
-                Ok(Term::TmIf(
-                    Box::new(if_term),
-                    then_term.clone(),
-                    else_term.clone(),
-                ))
+        Term::TmTrue
+        | Term::TmFalse
+        | Term::TmString(_)
+        | Term::TmFloat(_)
+        | Term::TmAbs(_, _, _)
+        | Term::TmPrim(_) => true,
+        Term::TmTuple(terms) => terms.iter().all(is_val),
+        Term::TmRecord(fields) => fields.iter().all(|(_, term)| is_val(term)),
+        Term::TmInl(term, _) | Term::TmInr(term, _) | Term::TmTag(_, term, _) => is_val(term),
+        Term::TmNil(_) => true,
+        Term::TmCons(_, head, tail) => is_val(head) && is_val(tail),
+        _ => is_numeric_val(term),
+    }
+}
+
+// one step of call-by-value evaluation, `trace::eval1` also tells which rules
+// it used
+pub fn eval1(term: &Term) -> Result<Term, EvalError> {
+    trace::eval1(term).map(|step| step.term)
+}
+
+// evaluates until the term is a value, a term that gets stuck before is an
+// error. Every step is a `trace` event of the `eval` span.
+pub fn eval(term: &Term) -> Result<Term, EvalError> {
+    let _span = tracing::debug_span!("eval").entered();
+    #[cfg(feature = "metatheory-checks")]
+    let typ = crate::typing::type_of(&mut crate::context::Context::default(), term).ok();
+    let mut term = term.clone();
+    loop {
+        match eval1(&term) {
+            Ok(next_term) => {
+                #[cfg(feature = "metatheory-checks")]
+                crate::metatheory::preservation(typ.as_ref(), &term, &next_term);
+                tracing::trace!(term = %next_term, "step");
+                term = next_term
             }
-        },
-        Term::TmApp(left, right) => if left.as_ref() == &Term::TmAbs(name, typ, body) {},
-        _ => Err(EvalError::NoRuleApplies),
+            Err(EvalError::NoRuleApplies) if is_val(&term) => return Ok(term),
+            Err(err) => {
+                #[cfg(feature = "metatheory-checks")]
+                crate::metatheory::progress(typ.as_ref(), &term, &err);
+                return Err(err);
+            }
+        }
     }
 }
 
-pub fn eval(ctx: &mut Context, term: &Term) -> Result<Term, EvalError> {
-    let term = eval1(ctx, term)?;
-    Ok(term)
+pub fn eval_to_value(term: &Term) -> Result<Value, EvalError> {
+    let value = eval(term)?;
+    Ok(Value::from_term(&value).expect("evaluation stops at a value"))
+}
+
+// gives up after `max_steps` reductions, a primitive without a result for its
+// argument leaves the term stuck like a missing rule does
+pub fn eval_with_fuel(term: &Term, max_steps: usize) -> Outcome<Value, Term> {
+    evaluate(term, max_steps, |term| eval1(term).ok(), Value::from_term)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::Parser, Stlc};
+    use tapl_testkit::{assert_evals_to, assert_step_trace, assert_type_error};
+
+    #[test]
+    fn test_eval() {
+        assert_evals_to!(Stlc, "true;", "true");
+        assert_evals_to!(Stlc, "if false then true else false;", "false");
+        assert_evals_to!(Stlc, "(lambda x:Bool.x) true;", "true");
+        assert_evals_to!(Stlc, "(lambda x:Bool.lambda y:Bool.x) true false;", "true");
+        assert_evals_to!(
+            Stlc,
+            "(lambda x:Bool.lambda y:Bool.{y, x}) true;",
+            "lambda y:Bool.{y, true}"
+        );
+        assert_evals_to!(
+            Stlc,
+            "{if true then false else true, succ(0)};",
+            "{false, succ(0)}"
+        );
+        assert_evals_to!(Stlc, "{true, 0}.2;", "0");
+        assert_evals_to!(Stlc, "(lambda p:Bool*Nat.p.1) {false, 0};", "false");
+        assert_evals_to!(Stlc, "{{true, 0}, false}.1.2;", "0");
+        assert_type_error!(Stlc, "{true, 0}.1 0;");
+        assert_evals_to!(
+            Stlc,
+            "{x=if true then 0 else succ(0), y=false};",
+            "{x=0, y=false}"
+        );
+        assert_evals_to!(
+            Stlc,
+            "(lambda r:{a:Nat, b:Bool}.r.b) {a=0, b=true};",
+            "true"
+        );
+        assert_evals_to!(Stlc, "{p={x=0}, q=true}.p.x;", "0");
+        assert_evals_to!(
+            Stlc,
+            "inl if true then 0 else succ(0) as Nat+Bool;",
+            "inl 0 as Nat+Bool"
+        );
+        assert_evals_to!(
+            Stlc,
+            "case inl succ(0) as Nat+Bool of inl n => {n, n} | inr b => {0, 0};",
+            "{succ(0), succ(0)}"
+        );
+        assert_evals_to!(
+            Stlc,
+            "case inr true as Nat+Bool of inl n => false | inr b => if b then false else true;",
+            "false"
+        );
+        assert_evals_to!(
+            Stlc,
+            "case <some=succ(0)> as <none:Bool, some:Nat> of <none=b> => 0 | <some=n> => succ(n);",
+            "succ(succ(0))"
+        );
+        assert_evals_to!(
+            Stlc,
+            "cons[Nat] (if true then 0 else succ(0)) nil[Nat];",
+            "cons[Nat] 0 nil[Nat]"
+        );
+        assert_evals_to!(Stlc, "isnil[Nat] nil[Nat];", "true");
+        assert_evals_to!(Stlc, "isnil[Nat] (cons[Nat] 0 nil[Nat]);", "false");
+        assert_evals_to!(
+            Stlc,
+            "head[Nat] (tail[Nat] (cons[Nat] 0 (cons[Nat] succ(0) nil[Nat])));",
+            "succ(0)"
+        );
+        // head of an empty list is stuck, it is not a value
+        let stuck = Parser::new().parse("head[Nat] nil[Nat];").unwrap();
+        assert_eq!(eval(&stuck), Err(EvalError::NoRuleApplies));
+    }
+
+    #[test]
+    fn test_eval_to_value() {
+        let term = Parser::new()
+            .parse("{(lambda x:Nat.succ(x)) succ(0), inl true as Bool+Nat, lambda x:Bool.x};")
+            .unwrap();
+        let value = eval_to_value(&term).unwrap();
+        assert_eq!(
+            value,
+            Value::Tuple(vec![
+                Value::Number(2),
+                Value::Inl(
+                    Box::new(Value::Boolean(true)),
+                    Type::Sum(Box::new(Type::Boolean), Box::new(Type::Number))
+                ),
+                Value::Abs("x".into(), Type::Boolean, Rc::new(Term::TmVar(0))),
+            ])
+        );
+        assert_eq!(
+            value.to_string(),
+            "{2, inl true as Bool+Nat, lambda x:Bool.x}"
+        );
+        assert_eq!(Value::from_term(&Term::TmVar(0)), None);
+        assert_eq!(eval_with_fuel(&term, 10), Outcome::Value(value));
+        let stuck = Parser::new().parse("head[Nat] nil[Nat];").unwrap();
+        assert_eq!(eval_with_fuel(&stuck, 10), Outcome::Stuck(stuck.clone()));
+        assert!(matches!(
+            eval_with_fuel(&term, 0),
+            Outcome::OutOfFuel { steps: 0, .. }
+        ));
+    }
+
+    #[test]
+    fn test_eval1() {
+        assert_step_trace!(
+            Stlc,
+            "(lambda x:Bool.if x then false else true) (if true then true else false);",
+            [
+                "(lambda x:Bool.if x then false else true) true",
+                "if true then false else true",
+                "false"
+            ]
+        );
+        assert_step_trace!(Stlc, "{true, 0};", []);
+    }
 }
@@ -0,0 +1,42 @@
+use tapl_testkit::{Failure, Language};
+
+use crate::{
+    context::Context,
+    eval::{eval, eval1, EvalError},
+    parser::{Parser, Term},
+    reconstruct::reconstruct,
+    typing::type_of,
+    Stlc,
+};
+
+impl Language for Stlc {
+    type Term = Term;
+    type Value = Term;
+
+    fn parse(input: &str) -> Result<Term, Failure> {
+        Parser::new()
+            .parse(input)
+            .map_err(|error| Failure::Parse(format!("{:?}", error)))
+    }
+
+    fn type_check(term: &Term) -> Result<(), Failure> {
+        type_of(&mut Context::default(), term)
+            .map(|_| ())
+            .map_err(|error| Failure::Type(error.to_string()))
+    }
+
+    // the value of the term with its omitted parameter types reconstructed
+    fn eval(term: &Term) -> Result<Term, Failure> {
+        let term = reconstruct(&mut Context::default(), term)
+            .map_err(|error| Failure::Type(error.to_string()))?;
+        eval(&term).map_err(|error| Failure::Eval(format!("{:?}", error)))
+    }
+
+    fn step(term: &Term) -> Option<Term> {
+        match eval1(term) {
+            Ok(term) => Some(term),
+            Err(EvalError::NoRuleApplies) => None,
+            Err(error) => panic!("evaluation failed: {:?}", error),
+        }
+    }
+}
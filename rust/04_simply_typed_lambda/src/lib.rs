@@ -1,7 +1,32 @@
-mod ast_parser;
-mod context;
-mod eval;
-mod parser;
-mod substitute;
-mod type_parser;
-mod typing;
+#[cfg(test)]
+mod arbitrary;
+pub mod ast_parser;
+pub mod bigstep;
+pub mod bytecode;
+pub mod closure;
+pub mod compile;
+pub mod context;
+pub mod derivation;
+pub mod diagnostic;
+pub mod elaborate;
+pub mod eval;
+#[cfg(test)]
+mod language;
+pub mod lint;
+pub mod located;
+#[cfg(feature = "metatheory-checks")]
+mod metatheory;
+pub mod parser;
+pub mod primitive;
+pub mod printer;
+pub mod reconstruct;
+pub mod span;
+pub mod stlc;
+pub mod substitute;
+pub mod trace;
+pub mod type_parser;
+pub mod typing;
+pub mod vm;
+pub mod wat;
+
+pub use stlc::Stlc;
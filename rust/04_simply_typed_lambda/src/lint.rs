@@ -0,0 +1,175 @@
+use std::fmt::{self, Formatter};
+
+use tapl_frontend::{
+    intern::Symbol,
+    visit::{any, Node},
+};
+
+use crate::parser::Term;
+
+// Idioms from the untyped calculus that can never be given a simple type,
+// reported before typechecking with an explanation instead of a bare TypeError
+#[derive(Clone, Debug, PartialEq)]
+pub enum Lint {
+    // `x x` for the bound variable `x`
+    SelfApplication(String),
+    // `(lambda x. x x) (lambda x. x x)`
+    Omega,
+    // `lambda f. (lambda x. f (x x)) (lambda x. f (x x))` and its variants
+    FixedPointCombinator(String),
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Lint::SelfApplication(name) => write!(
+                f,
+                "`{0} {0}` applies {0} to itself: {0} would need a type T with T = T->S, \
+                 and no simple type is equal to an arrow containing itself (TAPL 9.3)",
+                name
+            ),
+            Lint::Omega => write!(
+                f,
+                "this is omega, which reduces to itself forever: every well-typed STLC term \
+                 terminates (normalization, TAPL 12), so a diverging term cannot be typed"
+            ),
+            Lint::FixedPointCombinator(name) => write!(
+                f,
+                "`lambda {}. ...` is a fixed-point combinator: general recursion is not \
+                 definable in STLC because well-typed terms are normalizing, it needs a \
+                 primitive fix (TAPL 11.11)",
+                name
+            ),
+        }
+    }
+}
+
+pub fn lint(term: &Term) -> Vec<Lint> {
+    let mut lints = vec![];
+    walk(term, &mut vec![], &mut lints);
+    lints
+}
+
+fn walk(term: &Term, names: &mut Vec<Symbol>, lints: &mut Vec<Lint>) {
+    match term {
+        Term::TmAbs(name, _, body) => {
+            if let Some(self_body) = omega_body(body) {
+                // the binder of the outer abstraction is used from inside both copies
+                if mentions(self_body, 1) {
+                    lints.push(Lint::FixedPointCombinator(name.to_string()));
+                    return;
+                }
+            }
+            names.push(*name);
+            walk(body, names, lints);
+            names.pop();
+        }
+        Term::TmApp(left, right) => {
+            if omega_body(term).is_some() {
+                lints.push(Lint::Omega);
+                return;
+            }
+            match (left.as_ref(), right.as_ref()) {
+                (Term::TmVar(i), Term::TmVar(j)) if i == j => {
+                    let name = names
+                        .iter()
+                        .rev()
+                        .nth(*i)
+                        .map(|name| name.to_string())
+                        .unwrap_or_else(|| i.to_string());
+                    lints.push(Lint::SelfApplication(name));
+                }
+                _ => {
+                    walk(left, names, lints);
+                    walk(right, names, lints);
+                }
+            }
+        }
+        Term::TmCase(term, inl_name, inl_term, inr_name, inr_term) => {
+            walk(term, names, lints);
+            for (name, branch) in [(inl_name, inl_term), (inr_name, inr_term)] {
+                names.push(*name);
+                walk(branch, names, lints);
+                names.pop();
+            }
+        }
+        Term::TmVariantCase(term, branches) => {
+            walk(term, names, lints);
+            for (_, name, branch) in branches {
+                names.push(*name);
+                walk(branch, names, lints);
+                names.pop();
+            }
+        }
+        _ => term
+            .children()
+            .into_iter()
+            .for_each(|(_, term)| walk(term, names, lints)),
+    }
+}
+
+// `(lambda x. b) (lambda x. b)` where `b` applies `x` to itself, returns `b`
+fn omega_body(term: &Term) -> Option<&Term> {
+    match term {
+        Term::TmApp(left, right) => match (left.as_ref(), right.as_ref()) {
+            (Term::TmAbs(_, _, left_body), Term::TmAbs(_, _, right_body))
+                if left_body == right_body && applies_to_itself(left_body, 0) =>
+            {
+                Some(left_body)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn applies_to_itself(term: &Term, index: usize) -> bool {
+    any(term, 0, &mut |term, depth| match term {
+        Term::TmApp(left, right) => matches!(
+            (left.as_ref(), right.as_ref()),
+            (Term::TmVar(i), Term::TmVar(j)) if *i == index + depth && *j == index + depth
+        ),
+        _ => false,
+    })
+}
+
+fn mentions(term: &Term, index: usize) -> bool {
+    any(
+        term,
+        0,
+        &mut |term, depth| matches!(term, Term::TmVar(i) if *i == index + depth),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn lint_str(input: &str) -> Vec<Lint> {
+        lint(&Parser::new().parse(input).unwrap())
+    }
+
+    #[test]
+    fn test_lint() {
+        assert_eq!(lint_str("lambda x:Bool.x;"), vec![]);
+        assert_eq!(lint_str("lambda f:Bool->Bool.f true;"), vec![]);
+        assert_eq!(
+            lint_str("lambda x:Bool->Bool.x x;"),
+            vec![Lint::SelfApplication("x".into())]
+        );
+        assert_eq!(
+            lint_str("lambda x:Bool.lambda y:Bool.{y y, x};"),
+            vec![Lint::SelfApplication("y".into())]
+        );
+        assert_eq!(
+            lint_str("(lambda x:Bool.x x) (lambda x:Bool.x x);"),
+            vec![Lint::Omega]
+        );
+        assert_eq!(
+            lint_str("lambda f:Bool->Bool.(lambda x:Bool.f (x x)) (lambda x:Bool.f (x x));"),
+            vec![Lint::FixedPointCombinator("f".into())]
+        );
+        assert!(Lint::Omega.to_string().contains("normalization"));
+    }
+}
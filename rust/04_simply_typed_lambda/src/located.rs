@@ -0,0 +1,252 @@
+use std::fmt::{self, Formatter};
+
+use tapl_frontend::visit::Node;
+
+use crate::{
+    context::Context,
+    elaborate::derived_operator,
+    eval::{eval, EvalError},
+    parser::Term,
+    reconstruct::reconstruct,
+    span::{Span, SpanTree},
+    typing::{type_of, Type, TypeError},
+};
+
+// An error together with the span of the source text that caused it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Located<E> {
+    pub error: E,
+    pub span: Span,
+}
+
+impl<E: fmt::Display> Located<E> {
+    // `line:column: error` followed by the offending text
+    pub fn describe(&self, source: &str) -> String {
+        let (line, column) = self.span.line_col(source);
+        format!(
+            "{}:{}: {} in `{}`",
+            line,
+            column,
+            self.error,
+            self.span.text(source)
+        )
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for Located<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.error, self.span)
+    }
+}
+
+// Types `term` and on failure blames the smallest subterm that is ill-typed
+// on its own, so `if 0 then ...` points at the whole `if` but `succ(true)`
+// inside a well-formed abstraction points at `succ(true)`
+pub fn type_of_located(
+    ctx: &mut Context,
+    term: &Term,
+    spans: &SpanTree,
+) -> Result<Type, Located<TypeError>> {
+    type_of(ctx, term).map_err(|error| {
+        let found = culprit(ctx, term, spans);
+        Located {
+            error: as_written(error, &found),
+            span: found.spans.span,
+        }
+    })
+}
+
+// Reconstructs the parameter types `term` leaves out, a failure is blamed
+// like a type error
+pub fn reconstruct_located(
+    ctx: &mut Context,
+    term: &Term,
+    spans: &SpanTree,
+) -> Result<Term, Located<TypeError>> {
+    reconstruct(ctx, term).map_err(|error| {
+        let found = culprit(ctx, term, spans);
+        Located {
+            error: as_written(error, &found),
+            span: found.spans.span,
+        }
+    })
+}
+
+// the error of the culprit in terms of the source, an operand of `and`, `or`
+// or `not` that is not a boolean is blamed on the operator rather than on
+// the `if` it elaborates to
+pub(crate) fn as_written(error: TypeError, culprit: &Culprit) -> TypeError {
+    let name = match derived_operator(culprit.term, culprit.spans) {
+        Some(name) => name,
+        None => return error,
+    };
+    match error {
+        TypeError::IfConditionNotBoolean(found) => {
+            TypeError::OperatorArgumentNotBoolean(name, found)
+        }
+        // the other operand is the branch that is not the added constant
+        TypeError::IfBranchesMismatch(then_type, else_type) => {
+            let found = if then_type == Type::Boolean {
+                else_type
+            } else {
+                then_type
+            };
+            TypeError::OperatorArgumentNotBoolean(name, found)
+        }
+        error => error,
+    }
+}
+
+// The innermost ill-typed subterm whose children all type, with the context
+// it is typed in
+pub(crate) struct Culprit<'a> {
+    pub context: Context,
+    pub term: &'a Term,
+    pub spans: &'a SpanTree,
+}
+
+pub(crate) fn culprit<'a>(ctx: &mut Context, term: &'a Term, spans: &'a SpanTree) -> Culprit<'a> {
+    let this = |ctx: &mut Context| Culprit {
+        context: ctx.clone(),
+        term,
+        spans,
+    };
+    // the variable each child is typed under, in `children` order
+    let binders = match term {
+        Term::TmAbs(name, typ, _) => vec![Some((name, typ.clone()))],
+        Term::TmCase(scrutinee, inl_name, _, inr_name, _) => match type_of(ctx, scrutinee) {
+            Ok(Type::Sum(inl, inr)) => vec![None, Some((inl_name, *inl)), Some((inr_name, *inr))],
+            // the branches cannot be typed without the components of the sum
+            Ok(_) => return this(ctx),
+            Err(_) => vec![],
+        },
+        Term::TmVariantCase(scrutinee, branches) => match type_of(ctx, scrutinee) {
+            // the branches are typed once they cover the labels one to one,
+            // a missing or unreachable branch is the fault of the case
+            Ok(Type::Variant(fields))
+                if fields.len() == branches.len()
+                    && fields
+                        .iter()
+                        .all(|(field, _)| branches.iter().any(|(label, _, _)| label == field)) =>
+            {
+                std::iter::once(None)
+                    .chain(branches.iter().map(|(label, name, _)| {
+                        let field = fields.iter().find(|(field, _)| field == label);
+                        field.map(|(_, typ)| (name, typ.clone()))
+                    }))
+                    .collect()
+            }
+            Ok(_) => return this(ctx),
+            Err(_) => vec![],
+        },
+        _ => vec![],
+    };
+    for (i, (_, child)) in term.children().into_iter().enumerate() {
+        let binder = binders.get(i).cloned().flatten();
+        if let Some((name, typ)) = &binder {
+            ctx.push(**name, typ.clone());
+        }
+        let found = match type_of(ctx, child) {
+            Ok(_) => None,
+            Err(_) => Some(culprit(ctx, child, spans.child(i))),
+        };
+        if binder.is_some() {
+            ctx.pop();
+        }
+        if let Some(found) = found {
+            return found;
+        }
+    }
+    this(ctx)
+}
+
+// Evaluates `term`, a failing primitive is blamed on the place it is named in
+// the source, the other runtime errors on the whole program
+pub fn eval_located(term: &Term, spans: &SpanTree) -> Result<Term, Located<EvalError>> {
+    eval(term).map_err(|error| {
+        let span = match &error {
+            EvalError::PrimitiveFailed(name) => find_primitive(term, spans, name),
+            _ => None,
+        };
+        Located {
+            span: span.unwrap_or(spans.span),
+            error,
+        }
+    })
+}
+
+fn find_primitive(term: &Term, spans: &SpanTree, name: &str) -> Option<Span> {
+    match term {
+        Term::TmPrim(primitive) if primitive.name() == name => Some(spans.span),
+        // a definition has no spans of its own, the name it is used by is
+        // blamed instead
+        Term::TmGlobal(_, term) if names(term, name) => Some(spans.span),
+        _ => term
+            .children()
+            .into_iter()
+            .enumerate()
+            .find_map(|(i, (_, child))| find_primitive(child, spans.child(i), name)),
+    }
+}
+
+fn names(term: &Term, name: &str) -> bool {
+    match term {
+        Term::TmPrim(primitive) => primitive.name() == name,
+        Term::TmGlobal(_, term) => names(term, name),
+        _ => term
+            .children()
+            .into_iter()
+            .any(|(_, child)| names(child, name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::Parser, primitive::Primitives};
+    use tapl_frontend::intern::Symbol;
+
+    fn type_error(source: &str) -> String {
+        let (term, spans) = Parser::new().parse_with_spans(source).unwrap();
+        type_of_located(&mut Context::default(), &term, &spans)
+            .unwrap_err()
+            .describe(source)
+    }
+
+    #[test]
+    fn test_type_of_located() {
+        assert_eq!(
+            type_error("lambda x:Bool.\n  {x, succ(x)};"),
+            "2:7: argument of succ is not a number: found Bool in `succ(x)`"
+        );
+        assert_eq!(
+            type_error("{0, (lambda f:Nat->Nat.f 0) true};"),
+            "1:5: parameter type mismatch: expected Nat->Nat, found Bool \
+             in `(lambda f:Nat->Nat.f 0) true`"
+        );
+    }
+
+    #[test]
+    fn test_eval_located() {
+        let nat_to_nat = Type::Arrow(Box::new(Type::Number), Box::new(Type::Number));
+        let mut primitives = Primitives::new();
+        primitives.register("pred", nat_to_nat, |term| match term {
+            Term::TmSucc(term) => Some(term.as_ref().clone()),
+            _ => None,
+        });
+        let mut parser = Parser::with_primitives(primitives);
+        let source = "succ(succ(pred 0));";
+        let (term, spans) = parser.parse_with_spans(source).unwrap();
+        let error = eval_located(&term, &spans).unwrap_err();
+        assert_eq!(error.error, EvalError::PrimitiveFailed("pred".into()));
+        assert_eq!(error.span.text(source), "pred");
+
+        // a primitive in a definition is blamed on the name of the definition
+        let body = parser.parse("lambda n:Nat.pred n;").unwrap();
+        parser.define(Symbol::intern("down"), body);
+        let source = "succ(down 0);";
+        let (term, spans) = parser.parse_with_spans(source).unwrap();
+        let error = eval_located(&term, &spans).unwrap_err();
+        assert_eq!(error.span.text(source), "down");
+    }
+}
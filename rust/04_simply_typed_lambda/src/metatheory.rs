@@ -0,0 +1,71 @@
+// Progress and preservation (TAPL 9.3.5 and 9.3.9) checked while `eval`
+// runs, with the `metatheory-checks` feature. Only closed well-typed terms
+// are checked, `typ` is the type of the term evaluation started from.
+
+use tapl_frontend::visit::any;
+
+use crate::{
+    context::Context,
+    eval::EvalError,
+    parser::Term,
+    typing::{type_of, Type},
+};
+
+// t -> t' keeps the type of t
+pub(crate) fn preservation(typ: Option<&Type>, term: &Term, next_term: &Term) {
+    if let Some(typ) = typ {
+        let next_type = type_of(&mut Context::default(), next_term);
+        assert!(
+            next_type.as_ref() == Ok(typ),
+            "preservation: `{}` of type {} stepped to `{}` of type {:?}",
+            term,
+            typ,
+            next_term,
+            next_type
+        );
+    }
+}
+
+// a well-typed term that is not a value can step. `head` and `tail` of nil
+// are the exception, the list operations are partial (TAPL 11.12)
+pub(crate) fn progress(typ: Option<&Type>, term: &Term, error: &EvalError) {
+    let empty_list_operation = |term: &Term, _| match term {
+        Term::TmHead(_, list) | Term::TmTail(_, list) => matches!(**list, Term::TmNil(_)),
+        _ => false,
+    };
+    if let (Some(typ), EvalError::NoRuleApplies) = (typ, error) {
+        assert!(
+            any(term, 0, &mut |term, depth| empty_list_operation(
+                term, depth
+            )),
+            "progress: `{}` of type {} is stuck",
+            term,
+            typ
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{arbitrary::well_typed, eval::eval, parser::Parser};
+
+    #[test]
+    #[should_panic(expected = "preservation")]
+    fn test_preservation() {
+        let term = Parser::new()
+            .parse("if true then false else true;")
+            .unwrap();
+        preservation(Some(&Type::Boolean), &term, &Term::TmZero);
+    }
+
+    proptest! {
+        // every step of a well-typed term runs through both checks
+        #[test]
+        fn test_checked_eval((term, _) in well_typed()) {
+            prop_assert!(eval(&term).is_ok());
+        }
+    }
+}
@@ -1,142 +1,892 @@
-use std::{collections::VecDeque, println};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::{self, Formatter},
+    fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
-use nom::{
-    bytes::complete::tag,
-    error::{context, VerboseError},
-    sequence::tuple,
+use tapl_frontend::{
+    dot::Label,
+    intern::Symbol,
+    lexer::{Lexer, SyntaxError, Token},
+    visit::Node,
 };
 
 use crate::{
-    ast_parser::{parse_term, ASTTerm},
+    ast_parser::{
+        check_depth, parse_definition, parse_import, parse_term, parse_type_alias, ASTTerm,
+        SpannedTerm,
+    },
+    elaborate::elaborate,
+    located::Located,
+    primitive::{Primitive, Primitives},
+    span::{Span, SpanTree},
+    type_parser::option_type,
     typing::Type,
 };
 
-pub type IResult<I, O> = nom::IResult<I, O, VerboseError<I>>;
+// punctuation of terms and types, longer symbols win over their prefixes
+pub const SYMBOLS: [&str; 18] = [
+    "->", "=>", "(", ")", "{", "}", "[", "]", "<", ">", ",", ".", ":", ";", "=", "|", "*", "+",
+];
 
+// binder names are interned, so looking a variable up compares symbols
 #[derive(Clone, Debug, Default)]
 pub struct DeBruijnIndexer {
-    inner: VecDeque<String>,
+    // `None` for a wildcard, which takes an index but has no name
+    inner: VecDeque<Option<Symbol>>,
 }
 
 impl DeBruijnIndexer {
-    pub fn push(&mut self, hint: String) -> usize {
-        if self.inner.contains(&hint) {
-            self.push(hint)
-        } else {
-            let idx = self.inner.len();
-            self.inner.push_front(hint);
-            idx
-        }
+    // a name that is already bound is shadowed, `lookup` finds the innermost
+    // binding first. The wildcard `_` binds nothing a variable can refer to
+    // (TAPL 11.3).
+    pub fn push(&mut self, hint: Symbol) -> usize {
+        let symbol = if hint == "_" { None } else { Some(hint) };
+        let idx = self.inner.len();
+        self.inner.push_front(symbol);
+        idx
     }
 
     pub fn pop(&mut self) {
         self.inner.pop_front();
     }
 
-    pub fn lookup(&self, key: &str) -> Option<usize> {
-        for (idx, s) in self.inner.iter().enumerate() {
-            if key == s {
-                return Some(idx);
-            }
-        }
-        None
+    pub fn lookup(&self, key: Symbol) -> Option<usize> {
+        self.inner.iter().position(|s| *s == Some(key))
     }
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Term {
     TmTrue,
     TmFalse,
     TmZero,
-    TmSucc(Box<Term>),
+    // A numeral other than 0 written in decimal, `Term::number` builds it.
+    // `plus` and `times` compute on machine integers and return one, so a
+    // large result neither takes a term per unit nor nests as deep.
+    TmNumber(u64),
+    TmSucc(Rc<Term>),
+    // the text of a string literal, without its quotes
+    TmString(String),
+    TmFloat(f64),
     // var DeBrujin index
     TmVar(usize),
     // argument name, type and body
-    TmAbs(String, Type, Box<Term>),
-    TmApp(Box<Term>, Box<Term>),
-    TmIf(Box<Term>, Box<Term>, Box<Term>),
+    TmAbs(Symbol, Type, Rc<Term>),
+    TmApp(Rc<Term>, Rc<Term>),
+    TmIf(Rc<Term>, Rc<Term>, Rc<Term>),
+    TmTuple(Vec<Term>),
+    // tuple term and 1-based index
+    TmProj(Rc<Term>, usize),
+    // labeled fields in declaration order
+    TmRecord(Vec<(Symbol, Term)>),
+    // record term and field label
+    TmRecordProj(Rc<Term>, Symbol),
+    // The sum type annotation keeps types unique: without it `inl 0` would
+    // have type Nat+T for every T (TAPL 11.9)
+    TmInl(Rc<Term>, Type),
+    TmInr(Rc<Term>, Type),
+    // scrutinee, inl binder name and branch, inr binder name and branch
+    TmCase(Rc<Term>, Symbol, Rc<Term>, Symbol, Rc<Term>),
+    // label, tagged term and the variant type annotation (TAPL 11.10)
+    TmTag(Symbol, Rc<Term>, Type),
+    // scrutinee and the label, binder name and branch of every alternative
+    TmVariantCase(Rc<Term>, Vec<(Symbol, Symbol, Term)>),
+    // element type annotation on every list operation
+    TmNil(Type),
+    TmCons(Type, Rc<Term>, Rc<Term>),
+    TmIsNil(Type, Rc<Term>),
+    TmHead(Type, Rc<Term>),
+    TmTail(Type, Rc<Term>),
+    // the name of a top-level definition and the closed term it stands for,
+    // which evaluation unfolds it to
+    TmGlobal(Symbol, Definition),
+    // constant implemented by the host program, it wraps a Rust function and
+    // is left out of serialized terms
+    #[cfg_attr(feature = "serde", serde(skip))]
+    TmPrim(Primitive),
+}
+
+// The derived drop recurses into the subterms and overflows the stack on a
+// deep term, so the subterms are moved out onto a heap stack first. A shared
+// subterm is left to its other owners.
+// the closed term a top-level definition stands for, its debug form leaves
+// the term out so that a use of the definition shows only the name
+#[derive(Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Definition(pub Rc<Term>);
+
+impl Deref for Definition {
+    type Target = Term;
+
+    fn deref(&self) -> &Term {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Definition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "..")
+    }
+}
+
+impl Term {
+    // the numeral `n`, the constant `0` for zero
+    pub fn number(n: u64) -> Term {
+        match n {
+            0 => Term::TmZero,
+            n => Term::TmNumber(n),
+        }
+    }
+}
+
+impl Drop for Term {
+    fn drop(&mut self) {
+        let mut stack = vec![];
+        take_subterms(self, &mut stack);
+        while let Some(mut term) = stack.pop() {
+            take_subterms(&mut term, &mut stack);
+        }
+    }
+}
+
+fn take_subterms(term: &mut Term, stack: &mut Vec<Term>) {
+    let mut take = |term: &mut Rc<Term>| {
+        if let Some(term) = Rc::get_mut(term) {
+            stack.push(std::mem::replace(term, Term::TmZero))
+        }
+    };
+    match term {
+        Term::TmTrue
+        | Term::TmFalse
+        | Term::TmZero
+        | Term::TmNumber(_)
+        | Term::TmString(_)
+        | Term::TmFloat(_)
+        | Term::TmVar(_)
+        | Term::TmNil(_)
+        | Term::TmGlobal(_, _)
+        | Term::TmPrim(_) => {}
+        Term::TmSucc(term)
+        | Term::TmAbs(_, _, term)
+        | Term::TmProj(term, _)
+        | Term::TmRecordProj(term, _)
+        | Term::TmInl(term, _)
+        | Term::TmInr(term, _)
+        | Term::TmTag(_, term, _)
+        | Term::TmIsNil(_, term)
+        | Term::TmHead(_, term)
+        | Term::TmTail(_, term) => take(term),
+        Term::TmApp(left, right) | Term::TmCons(_, left, right) => {
+            take(left);
+            take(right);
+        }
+        Term::TmIf(a, b, c) | Term::TmCase(a, _, b, _, c) => {
+            take(a);
+            take(b);
+            take(c);
+        }
+        Term::TmTuple(terms) => stack.append(terms),
+        Term::TmRecord(fields) => stack.extend(fields.drain(..).map(|(_, term)| term)),
+        Term::TmVariantCase(term, branches) => {
+            take(term);
+            stack.extend(branches.drain(..).map(|(_, _, branch)| branch));
+        }
+    }
+}
+
+// direct subterms together with the number of variables bound around each
+impl Node for Term {
+    fn children(&self) -> Vec<(usize, &Self)> {
+        match self {
+            Term::TmTrue
+            | Term::TmFalse
+            | Term::TmZero
+            | Term::TmNumber(_)
+            | Term::TmString(_)
+            | Term::TmFloat(_)
+            | Term::TmVar(_)
+            | Term::TmNil(_)
+            | Term::TmGlobal(_, _)
+            | Term::TmPrim(_) => vec![],
+            Term::TmAbs(_, _, body) => vec![(1, body)],
+            Term::TmSucc(term)
+            | Term::TmProj(term, _)
+            | Term::TmRecordProj(term, _)
+            | Term::TmInl(term, _)
+            | Term::TmInr(term, _)
+            | Term::TmTag(_, term, _)
+            | Term::TmIsNil(_, term)
+            | Term::TmHead(_, term)
+            | Term::TmTail(_, term) => vec![(0, term)],
+            Term::TmApp(left, right) | Term::TmCons(_, left, right) => vec![(0, left), (0, right)],
+            Term::TmIf(if_term, then_term, else_term) => {
+                vec![(0, if_term), (0, then_term), (0, else_term)]
+            }
+            Term::TmTuple(terms) => terms.iter().map(|term| (0, term)).collect(),
+            Term::TmRecord(fields) => fields.iter().map(|(_, term)| (0, term)).collect(),
+            Term::TmCase(term, _, inl_term, _, inr_term) => {
+                vec![(0, term), (1, inl_term), (1, inr_term)]
+            }
+            Term::TmVariantCase(term, branches) => std::iter::once((0, term.as_ref()))
+                .chain(branches.iter().map(|(_, _, branch)| (1, branch)))
+                .collect(),
+        }
+    }
+}
+
+// the annotations and binders of a construct, a variable shows its index
+impl Label for Term {
+    fn label(&self) -> String {
+        match self {
+            Term::TmTrue => "true".to_string(),
+            Term::TmFalse => "false".to_string(),
+            Term::TmZero => "0".to_string(),
+            Term::TmNumber(n) => n.to_string(),
+            Term::TmSucc(_) => "succ".to_string(),
+            Term::TmString(text) => format!("\"{}\"", text),
+            Term::TmFloat(_) => self.to_string(),
+            Term::TmVar(index) => index.to_string(),
+            Term::TmAbs(name, typ, _) => format!("lambda {}:{}", name, typ),
+            Term::TmApp(_, _) => "app".to_string(),
+            Term::TmIf(_, _, _) => "if".to_string(),
+            Term::TmTuple(_) => "tuple".to_string(),
+            Term::TmProj(_, index) => format!(".{}", index),
+            Term::TmRecord(fields) => {
+                let labels = fields.iter().map(|(label, _)| label.as_str());
+                format!("{{{}}}", labels.collect::<Vec<_>>().join(", "))
+            }
+            Term::TmRecordProj(_, label) => format!(".{}", label),
+            Term::TmInl(_, typ) => format!("inl as {}", typ),
+            Term::TmInr(_, typ) => format!("inr as {}", typ),
+            Term::TmCase(_, inl_name, _, inr_name, _) => {
+                format!("case inl {} | inr {}", inl_name, inr_name)
+            }
+            Term::TmTag(label, _, typ) => format!("<{}> as {}", label, typ),
+            Term::TmVariantCase(_, branches) => {
+                let branches = branches
+                    .iter()
+                    .map(|(label, name, _)| format!("<{}={}>", label, name));
+                format!("case {}", branches.collect::<Vec<_>>().join(" | "))
+            }
+            Term::TmNil(typ) => format!("nil[{}]", typ),
+            Term::TmCons(typ, _, _) => format!("cons[{}]", typ),
+            Term::TmIsNil(typ, _) => format!("isnil[{}]", typ),
+            Term::TmHead(typ, _) => format!("head[{}]", typ),
+            Term::TmTail(typ, _) => format!("tail[{}]", typ),
+            Term::TmGlobal(name, _) => name.to_string(),
+            Term::TmPrim(primitive) => primitive.name().to_string(),
+        }
+    }
 }
 
 pub struct Parser {
     context: DeBruijnIndexer,
+    // the number of type variables given to types left to inference
+    vars: usize,
+    // the closed terms defined by earlier `name = t;` statements
+    globals: HashMap<Symbol, Rc<Term>>,
+    primitives: Primitives,
+    // the types declared by earlier `type Name = T;` statements, with the
+    // aliases in them already expanded
+    aliases: HashMap<Symbol, Type>,
+    // the files being parsed, innermost import last
+    files: Vec<PathBuf>,
 }
 
-#[derive(Clone, Debug, PartialEq, PartialOrd)]
+// A `;`-terminated statement of a program, `name = t;` defines `name` for
+// the statements after it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Statement {
+    pub name: Option<Symbol>,
+    pub term: Term,
+    pub spans: SpanTree,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum ParseError {
-    VerboseError(String),
+    Syntax(SyntaxError),
+    // text after the `;` that ends the program
+    TrailingInput(String),
     UnboundVariable(String),
+    // the path of an import and why its file could not be read or parsed
+    Import(String, String),
+    // an import of a file that is already being parsed
+    ImportCycle(String),
+    // a capitalized name in a type that no `type Name = T;` declares
+    UnknownType(String),
+    // `type Name = T;` with `Name` in `T`
+    RecursiveAlias(String),
+    // `type Name = T;` for a `Name` that is already declared
+    DuplicateAlias(String),
+}
+
+impl From<SyntaxError> for ParseError {
+    fn from(error: SyntaxError) -> Self {
+        ParseError::Syntax(error)
+    }
 }
 
-impl From<nom::Err<VerboseError<&str>>> for ParseError {
-    fn from(i: nom::Err<VerboseError<&str>>) -> Self {
-        ParseError::VerboseError(i.to_string())
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Syntax(error) => write!(f, "{}", error),
+            ParseError::TrailingInput(rest) => write!(f, "unexpected input after `;`: {}", rest),
+            ParseError::UnboundVariable(name) => write!(f, "unbound variable {}", name),
+            ParseError::Import(path, reason) => write!(f, "cannot import \"{}\": {}", path, reason),
+            ParseError::ImportCycle(path) => {
+                write!(f, "import cycle: \"{}\" is already being imported", path)
+            }
+            ParseError::UnknownType(name) => write!(f, "unknown type {}", name),
+            ParseError::RecursiveAlias(name) => write!(
+                f,
+                "type alias {} refers to itself, an alias cannot be recursive",
+                name
+            ),
+            ParseError::DuplicateAlias(name) => {
+                write!(f, "type alias {} is already declared", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Syntax(error) => Some(error),
+            _ => None,
+        }
     }
 }
 
 pub type ParseResult = Result<Term, ParseError>;
 
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Parser {
+    // names that are not bound resolve to `Primitives::builtin`
     pub fn new() -> Self {
+        Self::with_primitives(Primitives::builtin())
+    }
+
+    // names that are not bound by an enclosing binder resolve to these
+    pub fn with_primitives(primitives: Primitives) -> Self {
         Self {
             context: DeBruijnIndexer::default(),
+            vars: 0,
+            globals: HashMap::new(),
+            primitives,
+            aliases: HashMap::new(),
+            files: vec![],
         }
     }
 
+    // A name that is not bound by an enclosing binder refers to `term` from
+    // now on, ahead of a primitive of the same name. `term` must be closed,
+    // the parsed terms keep the name and evaluation unfolds it.
+    pub fn define(&mut self, name: Symbol, term: Term) {
+        self.globals.insert(name, Rc::new(term));
+    }
+
+    // `name` stands for `typ` in the types of the statements from now on.
+    // Expanding an alias never ends if it contains itself, that needs the
+    // recursive types of TAPL chapter 20. A name is declared once, the types
+    // already expanded from it would not follow a new declaration.
+    pub fn declare_type(&mut self, name: Symbol, typ: &Type) -> Result<(), ParseError> {
+        if self.aliases.contains_key(&name) {
+            return Err(ParseError::DuplicateAlias(name.to_string()));
+        }
+        let typ = expand_aliases(typ, &self.aliases, Some(name))?;
+        self.aliases.insert(name, typ);
+        Ok(())
+    }
+
+    // `typ` with the aliases it names replaced by the types they stand for
+    fn expand(&self, typ: &Type) -> Result<Type, ParseError> {
+        expand_aliases(typ, &self.aliases, None)
+    }
+
     pub fn parse(self: &mut Parser, input: &str) -> ParseResult {
-        //println!("parse");
-        let (output, term) = context("parse", tuple((parse_term, tag(";"))))(input)
-            .map(|(next_input, (term, _))| (next_input, term))?;
+        self.parse_with_spans(input).map(|(term, _)| term)
+    }
 
-        assert!(output.is_empty());
+    // the term together with the source span of every subterm
+    pub fn parse_with_spans(
+        self: &mut Parser,
+        input: &str,
+    ) -> Result<(Term, SpanTree), ParseError> {
+        let _span = tracing::debug_span!("parse").entered();
+        let mut lexer = Lexer::new(input, &SYMBOLS);
+        let term = parse_term(&mut lexer)?;
+        lexer.expect_symbol(";")?;
+        if !lexer.rest().trim().is_empty() {
+            return Err(ParseError::TrailingInput(lexer.rest().trim().to_string()));
+        }
 
-        self.from_ast_term(&term)
+        check_depth(&term, &lexer)?;
+        let term = elaborate(&term);
+        check_depth(&term, &lexer)?;
+        let (term, spans) = self.from_ast_term(&term)?;
+        tracing::debug!(%term, "parsed");
+        Ok((term, spans))
     }
 
-    fn from_ast_term(self: &mut Parser, ast_term: &ASTTerm) -> ParseResult {
-        let term = match ast_term {
-            ASTTerm::TmTrue => Term::TmTrue,
-            ASTTerm::TmFalse => Term::TmFalse,
-            ASTTerm::TmZero => Term::TmZero,
-            ASTTerm::TmSucc(number) => {
-                let term = self.from_ast_term(number.as_ref())?;
-                Term::TmSucc(Box::new(term))
-            }
-            ASTTerm::TmVar(id) => match self.context.lookup(&id) {
-                Some(index) => Term::TmVar(index),
-                None => {
-                    return Err(ParseError::UnboundVariable(id.to_string()));
+    // Every `;`-terminated statement of `input` in order, a definition is
+    // visible in the statements after it. `import "path";` brings in the
+    // definitions of another file, relative to the current directory outside
+    // of `parse_file`. A syntax error skips to the next `;`, so one bad
+    // statement does not hide the errors in the ones after it.
+    pub fn parse_program(
+        self: &mut Parser,
+        input: &str,
+    ) -> Vec<Result<Statement, Located<ParseError>>> {
+        let _span = tracing::debug_span!("parse").entered();
+        let mut lexer = Lexer::new(input, &SYMBOLS);
+        let mut statements = vec![];
+        let syntax = |error: SyntaxError| Located {
+            span: error.span,
+            error: ParseError::Syntax(error),
+        };
+        loop {
+            let start = match lexer.peek() {
+                Ok((Token::Eof, _)) => return statements,
+                Ok((_, span)) => span.start,
+                Err(error) => {
+                    statements.push(Err(syntax(error)));
+                    lexer.skip_past(";");
+                    continue;
+                }
+            };
+            match parse_type_alias(&mut lexer) {
+                Ok(Some((name, typ))) => {
+                    if let Err(error) = self.declare_type(name, &typ) {
+                        let span = Span::new(start, lexer.offset());
+                        statements.push(Err(Located { error, span }));
+                    }
+                    continue;
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    statements.push(Err(syntax(error)));
+                    lexer.skip_past(";");
+                    continue;
                 }
-            },
-            ASTTerm::TmAbs(arg, typ, body) => {
-                // Bind variable into a new context before parsing the body
-                self.context.push(arg.to_string());
-                let body_term = self.from_ast_term(body.as_ref())?;
-                // Return to previous context
-                self.context.pop();
-                Term::TmAbs(arg.clone(), typ.clone(), Box::new(body_term))
             }
-            ASTTerm::TmApp(left, right) => {
-                let left = self.from_ast_term(&left.as_ref())?;
-                let right = self.from_ast_term(&right.as_ref())?;
-                Term::TmApp(Box::new(left), Box::new(right))
+            match parse_import(&mut lexer) {
+                Ok(Some(path)) => {
+                    if let Err(error) = self.import(&path) {
+                        let span = Span::new(start, lexer.offset());
+                        statements.push(Err(Located { error, span }));
+                    }
+                    continue;
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    statements.push(Err(syntax(error)));
+                    lexer.skip_past(";");
+                    continue;
+                }
             }
-            ASTTerm::TmIf(if_term, then_term, else_them) => {
-                let if_term = self.from_ast_term(&if_term.as_ref())?;
-                let then_term = self.from_ast_term(&then_term.as_ref())?;
-                let else_them = self.from_ast_term(&else_them.as_ref())?;
-                Term::TmIf(Box::new(if_term), Box::new(then_term), Box::new(else_them))
+            let statement = parse_definition(&mut lexer).and_then(|name| {
+                // a statement nested too deeply skips to its own `;`
+                let term = parse_term(&mut lexer)?;
+                check_depth(&term, &lexer)?;
+                let ast_term = elaborate(&term);
+                check_depth(&ast_term, &lexer)?;
+                lexer.expect_symbol(";")?;
+                Ok((name, ast_term))
+            });
+            match statement {
+                Ok((name, ast_term)) => {
+                    // an unbound variable leaves its enclosing binders behind
+                    self.context = DeBruijnIndexer::default();
+                    let statement = match self.from_ast_term(&ast_term) {
+                        Ok((term, spans)) => {
+                            tracing::debug!(?name, %term, "parsed");
+                            if let Some(name) = name {
+                                self.define(name, term.clone());
+                            }
+                            Ok(Statement { name, term, spans })
+                        }
+                        Err(error) => Err(Located {
+                            error,
+                            span: ast_term.span,
+                        }),
+                    };
+                    statements.push(statement);
+                }
+                Err(error) => {
+                    statements.push(Err(syntax(error)));
+                    lexer.skip_past(";");
+                }
             }
+        }
+    }
+
+    // the names defined by the statements parsed so far
+    pub fn definitions(&self) -> impl Iterator<Item = &str> {
+        self.globals.keys().map(|name| name.as_str())
+    }
+
+    // like `parse_program`, for `source` read from `path`, which its imports
+    // are relative to
+    pub fn parse_file(
+        self: &mut Parser,
+        path: &Path,
+        source: &str,
+    ) -> Vec<Result<Statement, Located<ParseError>>> {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.files.push(path);
+        let statements = self.parse_program(source);
+        self.files.pop();
+        statements
+    }
+
+    // the definitions of the file at `path`, its other statements are left
+    // out and its first error fails the import
+    fn import(self: &mut Parser, path: &str) -> Result<(), ParseError> {
+        let failed = |reason: String| ParseError::Import(path.to_string(), reason);
+        let file = match self.files.last().and_then(|file| file.parent()) {
+            Some(dir) => dir.join(path),
+            None => PathBuf::from(path),
         };
+        let file = file
+            .canonicalize()
+            .map_err(|error| failed(error.to_string()))?;
+        if self.files.contains(&file) {
+            return Err(ParseError::ImportCycle(path.to_string()));
+        }
+        let source = fs::read_to_string(&file).map_err(|error| failed(error.to_string()))?;
+        match self
+            .parse_file(&file, &source)
+            .into_iter()
+            .find_map(Result::err)
+        {
+            Some(located) => Err(failed(located.describe(&source))),
+            None => Ok(()),
+        }
+    }
 
+    fn from_ast_child(
+        self: &mut Parser,
+        ast_term: &SpannedTerm,
+        children: &mut Vec<SpanTree>,
+    ) -> Result<Term, ParseError> {
+        let (term, tree) = self.from_ast_term(ast_term)?;
+        children.push(tree);
         Ok(term)
     }
+
+    // a type variable of its own for a type that is left to inference
+    fn fresh_type(self: &mut Parser) -> Type {
+        self.vars += 1;
+        Type::Var(self.vars - 1)
+    }
+
+    // `lambda x:T.t`, or `lambda x.t` whose parameter type the typechecker
+    // reconstructs
+    fn from_ast_abs(
+        self: &mut Parser,
+        arg: Symbol,
+        typ: &Option<Type>,
+        body: &SpannedTerm,
+        children: &mut Vec<SpanTree>,
+    ) -> Result<Term, ParseError> {
+        let typ = match typ {
+            Some(typ) => self.expand(typ)?,
+            None => self.fresh_type(),
+        };
+        // Bind variable into a new context before parsing the body
+        self.context.push(arg);
+        let body_term = self.from_ast_child(body, children);
+        // Return to previous context
+        self.context.pop();
+        Ok(Term::TmAbs(arg, typ, Rc::new(body_term?)))
+    }
+
+    fn from_ast_app(
+        self: &mut Parser,
+        left: &SpannedTerm,
+        right: &SpannedTerm,
+        children: &mut Vec<SpanTree>,
+    ) -> Result<Term, ParseError> {
+        let left = self.from_ast_child(left, children)?;
+        let right = self.from_ast_child(right, children)?;
+        Ok(Term::TmApp(Rc::new(left), Rc::new(right)))
+    }
+
+    fn from_ast_if(
+        self: &mut Parser,
+        [if_term, then_term, else_term]: [&SpannedTerm; 3],
+        children: &mut Vec<SpanTree>,
+    ) -> Result<Term, ParseError> {
+        let if_term = self.from_ast_child(if_term, children)?;
+        let then_term = self.from_ast_child(then_term, children)?;
+        let else_term = self.from_ast_child(else_term, children)?;
+        Ok(Term::TmIf(
+            Rc::new(if_term),
+            Rc::new(then_term),
+            Rc::new(else_term),
+        ))
+    }
+
+    fn from_ast_tuple(
+        self: &mut Parser,
+        terms: &[SpannedTerm],
+        children: &mut Vec<SpanTree>,
+    ) -> Result<Term, ParseError> {
+        let terms = terms
+            .iter()
+            .map(|term| self.from_ast_child(term, children))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Term::TmTuple(terms))
+    }
+
+    fn from_ast_record(
+        self: &mut Parser,
+        fields: &[(Symbol, SpannedTerm)],
+        children: &mut Vec<SpanTree>,
+    ) -> Result<Term, ParseError> {
+        let fields = fields
+            .iter()
+            .map(|(label, term)| Ok((*label, self.from_ast_child(term, children)?)))
+            .collect::<Result<Vec<_>, ParseError>>()?;
+        Ok(Term::TmRecord(fields))
+    }
+
+    // `inl t as T` or `inr t as T`
+    fn from_ast_injection(
+        self: &mut Parser,
+        ast_term: &ASTTerm,
+        children: &mut Vec<SpanTree>,
+    ) -> Result<Term, ParseError> {
+        let (term, typ) = match ast_term {
+            ASTTerm::TmInl(term, typ) | ASTTerm::TmInr(term, typ) => (term, self.expand(typ)?),
+            _ => unreachable!("not an injection"),
+        };
+        let term = Rc::new(self.from_ast_child(term, children)?);
+        Ok(match ast_term {
+            ASTTerm::TmInl(_, _) => Term::TmInl(term, typ),
+            _ => Term::TmInr(term, typ),
+        })
+    }
+
+    // the body of a branch, under the binder of the matched value
+    fn from_ast_branch(
+        self: &mut Parser,
+        name: Symbol,
+        branch: &SpannedTerm,
+        children: &mut Vec<SpanTree>,
+    ) -> Result<Term, ParseError> {
+        self.context.push(name);
+        let branch = self.from_ast_child(branch, children);
+        self.context.pop();
+        branch
+    }
+
+    fn from_ast_case(
+        self: &mut Parser,
+        term: &SpannedTerm,
+        (inl_name, inl_term): (Symbol, &SpannedTerm),
+        (inr_name, inr_term): (Symbol, &SpannedTerm),
+        children: &mut Vec<SpanTree>,
+    ) -> Result<Term, ParseError> {
+        let term = self.from_ast_child(term, children)?;
+        let inl_term = self.from_ast_branch(inl_name, inl_term, children)?;
+        let inr_term = self.from_ast_branch(inr_name, inr_term, children)?;
+        Ok(Term::TmCase(
+            Rc::new(term),
+            inl_name,
+            Rc::new(inl_term),
+            inr_name,
+            Rc::new(inr_term),
+        ))
+    }
+
+    fn from_ast_variant_case(
+        self: &mut Parser,
+        term: &SpannedTerm,
+        branches: &[(Symbol, Symbol, SpannedTerm)],
+        children: &mut Vec<SpanTree>,
+    ) -> Result<Term, ParseError> {
+        let term = self.from_ast_child(term, children)?;
+        let branches = branches
+            .iter()
+            .map(|(label, name, branch)| {
+                Ok((
+                    *label,
+                    *name,
+                    self.from_ast_branch(*name, branch, children)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, ParseError>>()?;
+        Ok(Term::TmVariantCase(Rc::new(term), branches))
+    }
+
+    // `<l=t> as T`, or `some t`, whose `Option` type the typechecker
+    // reconstructs from the type of `t`
+    fn from_ast_tag(
+        self: &mut Parser,
+        label: Symbol,
+        term: &SpannedTerm,
+        typ: &Option<Type>,
+        children: &mut Vec<SpanTree>,
+    ) -> Result<Term, ParseError> {
+        let typ = match typ {
+            Some(typ) => self.expand(typ)?,
+            None => option_type(self.fresh_type()),
+        };
+        let term = self.from_ast_child(term, children)?;
+        Ok(Term::TmTag(label, Rc::new(term), typ))
+    }
+
+    // `cons[T] t1 t2`, `isnil[T] t`, `head[T] t` or `tail[T] t`
+    fn from_ast_list(
+        self: &mut Parser,
+        ast_term: &ASTTerm,
+        children: &mut Vec<SpanTree>,
+    ) -> Result<Term, ParseError> {
+        let (typ, term) = match ast_term {
+            ASTTerm::TmCons(typ, term, _)
+            | ASTTerm::TmIsNil(typ, term)
+            | ASTTerm::TmHead(typ, term)
+            | ASTTerm::TmTail(typ, term) => (self.expand(typ)?, term),
+            _ => unreachable!("not a list operation"),
+        };
+        let term = Rc::new(self.from_ast_child(term, children)?);
+        Ok(match ast_term {
+            ASTTerm::TmCons(_, _, tail) => {
+                let tail = self.from_ast_child(tail, children)?;
+                Term::TmCons(typ, term, Rc::new(tail))
+            }
+            ASTTerm::TmIsNil(_, _) => Term::TmIsNil(typ, term),
+            ASTTerm::TmHead(_, _) => Term::TmHead(typ, term),
+            _ => Term::TmTail(typ, term),
+        })
+    }
+
+    // A parameter without a type gets a type variable, which the
+    // typechecker solves. Each construct that nests terms is converted in a
+    // method of its own, so each level of nesting only takes the stack that
+    // construct needs.
+    fn from_ast_term(
+        self: &mut Parser,
+        ast_term: &SpannedTerm,
+    ) -> Result<(Term, SpanTree), ParseError> {
+        let mut children = vec![];
+        let term = match &ast_term.node {
+            ASTTerm::TmTrue => Ok(Term::TmTrue),
+            ASTTerm::TmFalse => Ok(Term::TmFalse),
+            ASTTerm::TmZero => Ok(Term::TmZero),
+            ASTTerm::TmNumber(n) => Ok(Term::TmNumber(*n)),
+            ASTTerm::TmString(text) => Ok(Term::TmString(text.clone())),
+            ASTTerm::TmFloat(value) => Ok(Term::TmFloat(*value)),
+            ASTTerm::TmVar(id) => match self.context.lookup(*id) {
+                Some(index) => Ok(Term::TmVar(index)),
+                None => match (self.globals.get(id), self.primitives.get(id.as_str())) {
+                    (Some(term), _) => Ok(Term::TmGlobal(*id, Definition(term.clone()))),
+                    (None, Some(primitive)) => Ok(Term::TmPrim(primitive.clone())),
+                    (None, None) => Err(ParseError::UnboundVariable(id.to_string())),
+                },
+            },
+            ASTTerm::TmSucc(term) => self
+                .from_ast_child(term, &mut children)
+                .map(|term| Term::TmSucc(Rc::new(term))),
+            ASTTerm::TmProj(term, index) => self
+                .from_ast_child(term, &mut children)
+                .map(|term| Term::TmProj(Rc::new(term), *index)),
+            ASTTerm::TmRecordProj(term, label) => self
+                .from_ast_child(term, &mut children)
+                .map(|term| Term::TmRecordProj(Rc::new(term), *label)),
+            ASTTerm::TmAbs(arg, typ, body) => self.from_ast_abs(*arg, typ, body, &mut children),
+            ASTTerm::TmApp(left, right) => self.from_ast_app(left, right, &mut children),
+            ASTTerm::TmIf(if_term, then_term, else_term) => {
+                self.from_ast_if([if_term, then_term, else_term], &mut children)
+            }
+            ASTTerm::TmTuple(terms) => self.from_ast_tuple(terms, &mut children),
+            ASTTerm::TmRecord(fields) => self.from_ast_record(fields, &mut children),
+            ASTTerm::TmInl(_, _) | ASTTerm::TmInr(_, _) => {
+                self.from_ast_injection(&ast_term.node, &mut children)
+            }
+            ASTTerm::TmCase(term, inl_name, inl_term, inr_name, inr_term) => self.from_ast_case(
+                term,
+                (*inl_name, inl_term),
+                (*inr_name, inr_term),
+                &mut children,
+            ),
+            ASTTerm::TmTag(label, term, typ) => self.from_ast_tag(*label, term, typ, &mut children),
+            ASTTerm::TmVariantCase(term, branches) => {
+                self.from_ast_variant_case(term, branches, &mut children)
+            }
+            ASTTerm::TmNil(typ) => self.expand(typ).map(Term::TmNil),
+            ASTTerm::TmCons(_, _, _)
+            | ASTTerm::TmIsNil(_, _)
+            | ASTTerm::TmHead(_, _)
+            | ASTTerm::TmTail(_, _) => self.from_ast_list(&ast_term.node, &mut children),
+            ASTTerm::TmSeq(_, _)
+            | ASTTerm::TmLet(_, _, _, _)
+            | ASTTerm::TmMultiAbs(_, _)
+            | ASTTerm::TmNot(_)
+            | ASTTerm::TmAnd(_, _)
+            | ASTTerm::TmOr(_, _)
+            | ASTTerm::TmNone(_)
+            | ASTTerm::TmSome(_) => {
+                unreachable!("derived forms are elaborated before conversion")
+            }
+        }?;
+
+        let tree = SpanTree {
+            span: ast_term.span,
+            children,
+        };
+        Ok((term, tree))
+    }
+}
+
+// `declaring` is the alias `typ` is being declared as, which it must not name
+fn expand_aliases(
+    typ: &Type,
+    aliases: &HashMap<Symbol, Type>,
+    declaring: Option<Symbol>,
+) -> Result<Type, ParseError> {
+    let expand = |typ: &Type| expand_aliases(typ, aliases, declaring);
+    let boxed = |typ: &Type| expand(typ).map(Box::new);
+    let fields = |fields: &[(Symbol, Type)]| {
+        fields
+            .iter()
+            .map(|(label, typ)| Ok((*label, expand(typ)?)))
+            .collect::<Result<Vec<_>, ParseError>>()
+    };
+    Ok(match typ {
+        Type::Named(name) if Some(*name) == declaring => {
+            return Err(ParseError::RecursiveAlias(name.to_string()))
+        }
+        Type::Named(name) => aliases
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ParseError::UnknownType(name.to_string()))?,
+        Type::Boolean | Type::Number | Type::String | Type::Float | Type::Var(_) => typ.clone(),
+        Type::Arrow(param, result) => Type::Arrow(boxed(param)?, boxed(result)?),
+        Type::Product(types) => Type::Product(types.iter().map(expand).collect::<Result<_, _>>()?),
+        Type::Record(types) => Type::Record(fields(types)?),
+        Type::Sum(left, right) => Type::Sum(boxed(left)?, boxed(right)?),
+        Type::Variant(types) => Type::Variant(fields(types)?),
+        Type::List(element) => Type::List(boxed(element)?),
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use std::println;
-
     use super::*;
+    use crate::{context::Context, typing::type_of};
 
     #[test]
     fn test_term() {
@@ -148,19 +898,302 @@ mod tests {
             let mut parser = Parser::new();
             assert_eq!(parser.parse("0;"), Ok(Term::TmZero));
         }
+        {
+            let mut parser = Parser::new();
+            assert_eq!(
+                parser.parse("{true, 0}.1;"),
+                Ok(Term::TmProj(
+                    Rc::new(Term::TmTuple(vec![Term::TmTrue, Term::TmZero])),
+                    1
+                ))
+            );
+        }
+        {
+            let mut parser = Parser::new();
+            assert_eq!(
+                parser.parse("{x=true, y=0}.y;"),
+                Ok(Term::TmRecordProj(
+                    Rc::new(Term::TmRecord(vec![
+                        ("x".into(), Term::TmTrue),
+                        ("y".into(), Term::TmZero)
+                    ])),
+                    "y".into()
+                ))
+            );
+        }
         {
             let mut parser = Parser::new();
             let input = "(lambda x:Bool.x);";
-            let term = parser.parse(input);
-            println!("term: {:?}", term);
-            //assert_eq!(term, Ok(Term::TmZero));
+            assert_eq!(
+                parser.parse(input),
+                Ok(Term::TmAbs(
+                    "x".into(),
+                    Type::Boolean,
+                    Rc::new(Term::TmVar(0))
+                ))
+            );
         }
         {
             let mut parser = Parser::new();
             let input = "lambda x:bool.if x then false else true;";
-            let term = parser.parse(input);
-            println!("term: {:?}", term);
-            //assert_eq!(term, Ok(Term::TmZero));
+            assert!(parser.parse(input).is_err());
         }
     }
+
+    #[test]
+    fn test_wildcard() {
+        let mut indexer = DeBruijnIndexer::default();
+        indexer.push("x".into());
+        indexer.push("_".into());
+        assert_eq!(indexer.lookup("x".into()), Some(1));
+        assert_eq!(indexer.lookup("_".into()), None);
+        let term = Parser::new().parse("lambda _:Nat x:Bool _:Nat.x;").unwrap();
+        assert_eq!(term.to_string(), "lambda _:Nat x:Bool _:Nat.x");
+        // the wildcards still take their de Bruijn index in the typing context
+        let typ = crate::typing::type_of(&mut Default::default(), &term).unwrap();
+        assert_eq!(typ.to_string(), "Nat->Bool->Nat->Bool");
+        let error = Parser::new()
+            .parse("lambda _:Bool.if _ then 0 else 0;")
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "1:18: expected a term, found the wildcard `_`"
+        );
+    }
+
+    #[test]
+    fn test_import() {
+        let dir = std::env::temp_dir().join(format!("stlc-import-{}", std::process::id()));
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        let write = |name: &str, source: &str| fs::write(dir.join(name), source).unwrap();
+        write(
+            "lib/bool.stlc",
+            "neg = lambda b:Bool.if b then false else true; neg;",
+        );
+        write(
+            "lib/twice.stlc",
+            "import \"bool.stlc\"; same = lambda b:Bool.neg (neg b);",
+        );
+        write("main.stlc", "import \"lib/twice.stlc\";\nsame true;");
+        write("a.stlc", "import \"b.stlc\";");
+        write("b.stlc", "import \"a.stlc\";");
+
+        // imports are relative to the importing file, only definitions come in
+        let mut parser = Parser::new();
+        let source = fs::read_to_string(dir.join("main.stlc")).unwrap();
+        let statements = parser.parse_file(&dir.join("main.stlc"), &source);
+        assert_eq!(statements.len(), 1);
+        let term = &statements[0].as_ref().unwrap().term;
+        assert_eq!(crate::eval::eval(term), Ok(Term::TmTrue));
+        // the definitions of nested imports stay visible to later statements
+        assert!(parser.parse("neg true;").is_ok());
+        let statements = Parser::new().parse_file(&dir.join("a.stlc"), "import \"b.stlc\";");
+        let error = statements[0].as_ref().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "cannot import \"b.stlc\": 1:1: import cycle: \"a.stlc\" is already being imported in `import \"a.stlc\";` at 0..16"
+        );
+        let error = Parser::new().parse_program("import \"missing.stlc\";")[0]
+            .clone()
+            .unwrap_err();
+        assert!(matches!(error.error, ParseError::Import(path, _) if path == "missing.stlc"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_global() {
+        let mut parser = Parser::new();
+        let statements = parser.parse_program("id = lambda x:Bool.x; id true;");
+        let id = statements[0].as_ref().unwrap().term.clone();
+        let term = &statements[1].as_ref().unwrap().term;
+        // a use of a definition keeps its name, evaluation unfolds it
+        assert_eq!(
+            term,
+            &Term::TmApp(
+                Rc::new(Term::TmGlobal("id".into(), Definition(Rc::new(id)))),
+                Rc::new(Term::TmTrue)
+            )
+        );
+        assert_eq!(term.to_string(), "id true");
+        assert_eq!(crate::eval::eval(term), Ok(Term::TmTrue));
+    }
+
+    #[test]
+    fn test_type_alias() {
+        let mut parser = Parser::new();
+        let statements = parser.parse_program(
+            "type NatPair = Nat * Nat;\n\
+             type Choice = NatPair + Bool;\n\
+             swap = lambda p:NatPair.{p.2, p.1};\n\
+             inr true as Choice;",
+        );
+        let terms = statements
+            .iter()
+            .map(|statement| statement.as_ref().unwrap().term.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            terms,
+            ["lambda p:Nat*Nat.{p.2, p.1}", "inr true as Nat*Nat+Bool"]
+        );
+        // an alias is declared once and the aliases expanded from it stay
+        assert_eq!(
+            parser.parse_program("type NatPair = Bool;")[0]
+                .clone()
+                .unwrap_err()
+                .error,
+            ParseError::DuplicateAlias("NatPair".into())
+        );
+        assert_eq!(
+            parser.parse("inl {0, 0} as Choice;").unwrap().to_string(),
+            "inl {0, 0} as Nat*Nat+Bool"
+        );
+        let error = |source: &str| {
+            let statements = Parser::new().parse_program(source);
+            statements.last().unwrap().clone().unwrap_err().error
+        };
+        assert_eq!(
+            error("type Tree = Nat * List Tree;"),
+            ParseError::RecursiveAlias("Tree".into())
+        );
+        assert_eq!(
+            error("type Flag = Bool;\ntype Flag = Nat;").to_string(),
+            "type alias Flag is already declared"
+        );
+        assert_eq!(
+            error("lambda p:Pair.p;"),
+            ParseError::UnknownType("Pair".into())
+        );
+        assert_eq!(
+            error("type nat = Nat;").to_string(),
+            "1:6: expected a capitalized type name, found `nat`"
+        );
+    }
+
+    #[test]
+    fn test_shadowing() {
+        let abs = |body: Term| Term::TmAbs("x".into(), Type::Boolean, Rc::new(body));
+        let mut parser = Parser::new();
+        assert_eq!(
+            parser.parse("lambda x:Bool.lambda x:Bool.x;"),
+            Ok(abs(abs(Term::TmVar(0))))
+        );
+        // the outer `x` is visible again after the inner binder
+        assert_eq!(
+            parser.parse("lambda x:Bool.{lambda x:Bool.x, x};"),
+            Ok(abs(Term::TmTuple(vec![
+                abs(Term::TmVar(0)),
+                Term::TmVar(0)
+            ])))
+        );
+        let sum = Type::Sum(Box::new(Type::Number), Box::new(Type::Boolean));
+        let case = Term::TmCase(
+            Rc::new(Term::TmVar(0)),
+            "x".into(),
+            Rc::new(Term::TmVar(0)),
+            "x".into(),
+            Rc::new(Term::TmZero),
+        );
+        assert_eq!(
+            parser.parse("lambda x:Nat+Bool.case x of inl x => x | inr x => 0;"),
+            Ok(Term::TmAbs("x".into(), sum, Rc::new(case)))
+        );
+    }
+
+    #[test]
+    fn test_omitted_type() {
+        // each parameter without a type gets a type variable of its own
+        let abs = |var: usize, body: Term| Term::TmAbs("x".into(), Type::Var(var), Rc::new(body));
+        let mut parser = Parser::new();
+        assert_eq!(
+            parser.parse("lambda x.lambda x.x;"),
+            Ok(abs(0, abs(1, Term::TmVar(0))))
+        );
+        assert_eq!(parser.parse("lambda x.x;"), Ok(abs(2, Term::TmVar(0))));
+    }
+
+    // the type and value of `source`, every pass goes down a deeply nested
+    // term without overflowing the stack of a test thread
+    fn run_deep(source: &str) -> Result<(Type, Term), String> {
+        let term = Parser::new()
+            .parse(source)
+            .map_err(|error| error.to_string())?;
+        let typ = type_of(&mut Context::default(), &term).map_err(|error| error.to_string())?;
+        Ok((typ, crate::eval::eval(&term).unwrap()))
+    }
+
+    const TOO_DEEP: &str = "expected at most 256 nested terms";
+
+    #[test]
+    fn test_deep_application() {
+        // 121 arguments, each application nests the ones before it
+        let function = format!("lambda x:Nat. {}x", "lambda y:Nat. ".repeat(120));
+        let source = format!("({}) {};", function, "0 ".repeat(121));
+        assert_eq!(run_deep(&source), Ok((Type::Number, Term::TmZero)));
+        assert!(run_deep(&format!("x {};", "0 ".repeat(300)))
+            .unwrap_err()
+            .contains(TOO_DEEP));
+        // a deep first argument under a long chain of applications
+        let deep = format!("{}0{}", "succ(".repeat(60), ")".repeat(60));
+        let source = format!("(lambda x:Nat. x) {} {};", deep, "0 ".repeat(200));
+        assert!(run_deep(&source).unwrap_err().contains(TOO_DEEP));
+    }
+
+    #[test]
+    fn test_deep_sequence() {
+        let sequence = |n| format!("({}0);", "unit; ".repeat(n));
+        assert_eq!(run_deep(&sequence(120)), Ok((Type::Number, Term::TmZero)));
+        // the derived form nests deeper than its source once elaborated
+        assert!(run_deep(&sequence(200)).unwrap_err().contains(TOO_DEEP));
+        assert!(run_deep(&sequence(300)).unwrap_err().contains(TOO_DEEP));
+    }
+
+    #[test]
+    fn test_deep_projection() {
+        let tuple = format!("{}0{}", "{".repeat(100), "}".repeat(100));
+        let source = format!("{}{};", tuple, ".1".repeat(100));
+        assert_eq!(run_deep(&source), Ok((Type::Number, Term::TmZero)));
+        let source = format!("{{a=0}}{};", ".a".repeat(300));
+        assert!(run_deep(&source).unwrap_err().contains(TOO_DEEP));
+    }
+
+    #[test]
+    fn test_deep_list() {
+        let list = |n| format!("{}nil[Nat];", "cons[Nat] 0 ".repeat(n));
+        let (typ, _) = run_deep(&list(250)).unwrap();
+        assert_eq!(typ, Type::List(Box::new(Type::Number)));
+        assert!(run_deep(&list(300)).unwrap_err().contains(TOO_DEEP));
+    }
+
+    #[test]
+    fn test_deep_type() {
+        let identity = |n| format!("lambda x:{}Nat{}. x;", "(".repeat(n), ")".repeat(n));
+        let (typ, _) = run_deep(&identity(250)).unwrap();
+        assert_eq!(
+            typ,
+            Type::Arrow(Box::new(Type::Number), Box::new(Type::Number))
+        );
+        assert!(run_deep(&identity(300)).unwrap_err().contains(TOO_DEEP));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let (term, spans) = Parser::new()
+            .parse_with_spans("(lambda x:Nat+Bool.{x, nil[Nat]}) inr true as Nat+Bool;")
+            .unwrap();
+        let json = serde_json::to_string(&term).unwrap();
+        assert_eq!(serde_json::from_str::<Term>(&json).unwrap(), term);
+        let json = serde_json::to_string(&spans).unwrap();
+        assert_eq!(serde_json::from_str::<SpanTree>(&json).unwrap(), spans);
+        let value = crate::eval::eval_to_value(&term).unwrap();
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(
+            serde_json::from_str::<crate::eval::Value>(&json).unwrap(),
+            value
+        );
+        assert_eq!(
+            serde_json::to_string(&Type::List(Box::new(Type::Number))).unwrap(),
+            r#"{"List":"Number"}"#
+        );
+    }
 }
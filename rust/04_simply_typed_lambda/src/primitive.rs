@@ -0,0 +1,362 @@
+use std::{
+    cmp::Ordering,
+    fmt::{self, Formatter},
+    rc::Rc,
+};
+
+use crate::{eval::Value, parser::Term, typing::Type};
+
+type PrimitiveFn = dyn Fn(&Term) -> Option<Term>;
+
+// A constant of arrow type implemented by the host program. Applying it to a
+// value calls the Rust function, which returns a value of the result type or
+// `None` when it has no answer for that argument. Several arguments are
+// passed as one tuple.
+#[derive(Clone)]
+pub struct Primitive {
+    name: String,
+    typ: Type,
+    func: Rc<PrimitiveFn>,
+}
+
+impl Primitive {
+    pub fn new<F>(name: &str, typ: Type, func: F) -> Self
+    where
+        F: Fn(&Term) -> Option<Term> + 'static,
+    {
+        Primitive {
+            name: name.to_string(),
+            typ,
+            func: Rc::new(func),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn typ(&self) -> &Type {
+        &self.typ
+    }
+
+    pub fn apply(&self, argument: &Term) -> Option<Term> {
+        (self.func)(argument)
+    }
+}
+
+// primitives are identified by name, the function itself cannot be compared
+impl PartialEq for Primitive {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.typ == other.typ
+    }
+}
+
+impl PartialOrd for Primitive {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match self.name.partial_cmp(&other.name) {
+            Some(Ordering::Equal) => self.typ.partial_cmp(&other.typ),
+            ordering => ordering,
+        }
+    }
+}
+
+impl fmt::Debug for Primitive {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Primitive({}: {})", self.name, self.typ)
+    }
+}
+
+// Primitives a parser resolves names against, registered by the host
+#[derive(Clone, Debug, Default)]
+pub struct Primitives {
+    entries: Vec<Primitive>,
+}
+
+impl Primitives {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // a later registration under the same name replaces the earlier one
+    pub fn register<F>(&mut self, name: &str, typ: Type, func: F) -> &mut Self
+    where
+        F: Fn(&Term) -> Option<Term> + 'static,
+    {
+        self.entries.retain(|primitive| primitive.name != name);
+        self.entries.push(Primitive::new(name, typ, func));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Primitive> {
+        self.entries.iter().find(|primitive| primitive.name == name)
+    }
+
+    // `pred` and `iszero` on a number and `plus`, `times` and `leq` on a
+    // pair of numbers, computed on machine integers rather than by recursion
+    // on `succ`, `concat` on a pair of strings and `timesfloat` on a pair of
+    // floats. `Parser::new` resolves names against these.
+    pub fn builtin() -> Self {
+        let nat = || Box::new(Type::Number);
+        let nat_pair = || Box::new(Type::Product(vec![Type::Number, Type::Number]));
+        let mut primitives = Primitives::new();
+        primitives
+            // `pred 0` is 0 as in the book
+            .register("pred", Type::Arrow(nat(), nat()), |term| {
+                number(term).map(|n| Term::number(n.saturating_sub(1)))
+            })
+            .register(
+                "iszero",
+                Type::Arrow(nat(), Box::new(Type::Boolean)),
+                |term| number(term).map(|n| Value::Boolean(n == 0).to_term()),
+            )
+            .register(
+                "plus",
+                Type::Arrow(nat_pair(), Box::new(Type::Number)),
+                |term| {
+                    numbers(term)
+                        .and_then(|(m, n)| m.checked_add(n))
+                        .map(Term::number)
+                },
+            )
+            .register(
+                "times",
+                Type::Arrow(nat_pair(), Box::new(Type::Number)),
+                |term| {
+                    numbers(term)
+                        .and_then(|(m, n)| m.checked_mul(n))
+                        .map(Term::number)
+                },
+            )
+            .register(
+                "leq",
+                Type::Arrow(nat_pair(), Box::new(Type::Boolean)),
+                |term| numbers(term).map(|(m, n)| Value::Boolean(m <= n).to_term()),
+            )
+            .register(
+                "concat",
+                Type::Arrow(
+                    Box::new(Type::Product(vec![Type::String, Type::String])),
+                    Box::new(Type::String),
+                ),
+                |term| match term {
+                    Term::TmTuple(terms) => match terms.as_slice() {
+                        [Term::TmString(left), Term::TmString(right)] => {
+                            Some(Term::TmString(format!("{}{}", left, right)))
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                },
+            )
+            .register(
+                "timesfloat",
+                Type::Arrow(
+                    Box::new(Type::Product(vec![Type::Float, Type::Float])),
+                    Box::new(Type::Float),
+                ),
+                |term| match term {
+                    Term::TmTuple(terms) => match terms.as_slice() {
+                        [Term::TmFloat(left), Term::TmFloat(right)] => {
+                            Some(Term::TmFloat(left * right))
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                },
+            );
+        primitives
+    }
+}
+
+fn number(term: &Term) -> Option<u64> {
+    match Value::from_term(term)? {
+        Value::Number(n) => Some(n),
+        _ => None,
+    }
+}
+
+// the two numbers of a pair `{m, n}`
+fn numbers(term: &Term) -> Option<(u64, u64)> {
+    match term {
+        Term::TmTuple(terms) => match terms.as_slice() {
+            [m, n] => Some((number(m)?, number(n)?)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::Context,
+        eval::{eval, EvalError},
+        parser::{ParseError, Parser},
+        typing::{type_of, TypeError},
+        Stlc,
+    };
+    use tapl_testkit::{assert_evals_to, assert_type_error};
+
+    fn nat_to_number(term: &Term) -> Option<u32> {
+        match term {
+            Term::TmZero => Some(0),
+            Term::TmSucc(term) => nat_to_number(term).map(|n| n + 1),
+            _ => None,
+        }
+    }
+
+    fn number_to_nat(n: u32) -> Term {
+        (0..n).fold(Term::TmZero, |term, _| Term::TmSucc(Rc::new(term)))
+    }
+
+    fn primitives() -> Primitives {
+        let nat_to_nat = Type::Arrow(Box::new(Type::Number), Box::new(Type::Number));
+        let nat_pair_to_nat = Type::Arrow(
+            Box::new(Type::Product(vec![Type::Number, Type::Number])),
+            Box::new(Type::Number),
+        );
+        let mut primitives = Primitives::new();
+        primitives
+            .register("double", nat_to_nat.clone(), |term| {
+                nat_to_number(term).map(|n| number_to_nat(n * 2))
+            })
+            .register("pred", nat_to_nat, |term| match term {
+                Term::TmSucc(term) => Some(term.as_ref().clone()),
+                _ => None,
+            })
+            .register("plus", nat_pair_to_nat, |term| match term {
+                Term::TmTuple(terms) => {
+                    let sum = nat_to_number(&terms[0])? + nat_to_number(&terms[1])?;
+                    Some(number_to_nat(sum))
+                }
+                _ => None,
+            });
+        primitives
+    }
+
+    fn run_with(primitives: Primitives, input: &str) -> Result<Term, EvalError> {
+        let term = Parser::with_primitives(primitives).parse(input).unwrap();
+        type_of(&mut Context::default(), &term)?;
+        eval(&term)
+    }
+
+    fn run(input: &str) -> Result<Term, EvalError> {
+        run_with(primitives(), input)
+    }
+
+    fn run_builtin(input: &str) -> Result<Term, EvalError> {
+        run_with(Primitives::builtin(), input)
+    }
+
+    #[test]
+    fn test_primitive() {
+        assert_eq!(run("double succ(succ(0));"), Ok(number_to_nat(4)));
+        assert_eq!(
+            run("(lambda f:Nat->Nat.f (f succ(0))) double;"),
+            Ok(number_to_nat(4))
+        );
+        assert_eq!(run("plus {double succ(0), succ(0)};"), Ok(number_to_nat(3)));
+        assert_eq!(
+            run("double true;"),
+            Err(EvalError::TypeError(TypeError::ParameterTypeMismatch(
+                Type::Number,
+                Type::Boolean
+            )))
+        );
+        assert_eq!(
+            run("pred 0;"),
+            Err(EvalError::PrimitiveFailed("pred".into()))
+        );
+        assert_eq!(
+            Parser::new().parse("double 0;"),
+            Err(ParseError::UnboundVariable("double".into()))
+        );
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_evals_to!(
+            Stlc,
+            "times {plus {succ(0), succ(succ(0))}, succ(succ(0))};",
+            "6"
+        );
+        assert_evals_to!(
+            Stlc,
+            "(lambda n:Nat.leq {times {n, n}, n}) succ(0);",
+            "true"
+        );
+        assert_evals_to!(Stlc, "leq {succ(0), 0};", "false");
+        assert_type_error!(Stlc, "plus {0, true};");
+        assert_type_error!(Stlc, "leq 0;");
+        // a binder shadows the primitive of the same name
+        assert_evals_to!(Stlc, "(lambda plus:Bool.plus) true;", "true");
+    }
+
+    #[test]
+    fn test_numerals() {
+        assert_evals_to!(Stlc, "plus {40, 2};", "42");
+        assert_evals_to!(Stlc, "pred 1;", "0");
+        assert_evals_to!(Stlc, "pred 0;", "0");
+        assert_evals_to!(Stlc, "pred succ(succ(41));", "42");
+        assert_evals_to!(Stlc, "(lambda n:Nat.succ(n)) 41;", "succ(41)");
+        assert_evals_to!(Stlc, "iszero (pred 1);", "true");
+        assert_evals_to!(Stlc, "iszero succ(0);", "false");
+        assert_evals_to!(Stlc, "leq {times {4294967296, 2}, 8589934592};", "true");
+        // a numeral is a value like a chain of `succ`, and can end one
+        let term = Parser::new().parse("succ(succ(40));").unwrap();
+        assert_eq!(Value::from_term(&term), Some(Value::Number(42)));
+        assert_eq!(term.to_string(), "succ(succ(40))");
+        assert_type_error!(Stlc, "pred true;");
+        assert_type_error!(Stlc, "if 1 then 0 else 0;");
+        assert_eq!(
+            run_builtin("plus {18446744073709551615, 1};"),
+            Err(EvalError::PrimitiveFailed("plus".into()))
+        );
+        assert!(Parser::new().parse("18446744073709551616;").is_err());
+    }
+
+    #[test]
+    fn test_large_numbers() {
+        // results far deeper than the Rust stack could recurse down
+        let eval_program = |program: &str| {
+            let input = format!(
+                "let ten = {} in let hundred = times {{ten, ten}} in {};",
+                number_to_nat(10),
+                program
+            );
+            let term = Parser::new().parse(&input).unwrap();
+            assert_eq!(type_of(&mut Context::default(), &term), Ok(Type::Number));
+            eval(&term).unwrap()
+        };
+        let value =
+            eval_program("plus {times {times {hundred, hundred}, ten}, times {hundred, hundred}}");
+        assert_eq!(value, Term::TmNumber(110_000));
+        assert_eq!(value.to_string(), "110000");
+        let value = eval_program(
+            "let n = times {hundred, hundred} in {leq {n, succ(n)}, (lambda m:Nat.succ(m)) n}.2",
+        );
+        assert_eq!(Value::from_term(&value), Some(Value::Number(10_001)));
+    }
+
+    #[test]
+    fn test_concat() {
+        assert_evals_to!(
+            Stlc,
+            r#"(lambda s:String.concat {concat {s, ", "}, s}) "tapl";"#,
+            r#""tapl, tapl""#
+        );
+        assert_evals_to!(Stlc, r#"concat {"", ""};"#, r#""""#);
+        assert_type_error!(Stlc, r#"concat {"a", 0};"#);
+        assert_type_error!(Stlc, r#"succ("a");"#);
+    }
+
+    #[test]
+    fn test_timesfloat() {
+        assert_evals_to!(Stlc, "timesfloat {2.5, 4.0};", "10.0");
+        assert_evals_to!(Stlc, "(lambda x:Float.timesfloat {x, x}) 1.5;", "2.25");
+        assert_evals_to!(Stlc, "{2.5, 0.0}.1;", "2.5");
+        assert_type_error!(Stlc, "timesfloat {2.5, 0};");
+        assert_type_error!(Stlc, "plus {2.5, 0};");
+    }
+}
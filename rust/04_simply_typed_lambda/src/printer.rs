@@ -0,0 +1,318 @@
+use std::fmt::{self, Formatter};
+
+use misc::DisplayMode;
+use tapl_frontend::intern::Symbol;
+
+use crate::{eval::is_numeric_val, parser::Term};
+
+impl Term {
+    pub fn display(&self, mode: DisplayMode) -> TermDisplay<'_> {
+        TermDisplay { term: self, mode }
+    }
+}
+
+// Prints terms in the syntax the parser accepts, variables get the name of
+// their binder back
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.display(DisplayMode::Named).fmt(f)
+    }
+}
+
+// A variable as `#1` in the nameless representation, which the numeral `0`
+// could not be told apart from otherwise, and as `x#1` with both. Nameless
+// binders drop their names, and only the named output parses back.
+pub struct TermDisplay<'a> {
+    term: &'a Term,
+    mode: DisplayMode,
+}
+
+impl fmt::Display for TermDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_term(f, self.term, &mut vec![], self.mode)
+    }
+}
+
+// A subterm printed with the names of the binders around it, outermost
+// first, the way it shows inside the whole term
+pub struct Named<'a> {
+    pub term: &'a Term,
+    pub names: &'a [Symbol],
+}
+
+impl fmt::Display for Named<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_term(f, self.term, &mut self.names.to_vec(), DisplayMode::Named)
+    }
+}
+
+// terms that never need parentheses as an argument or projection base
+fn is_atomic(term: &Term) -> bool {
+    matches!(
+        term,
+        Term::TmTrue
+            | Term::TmFalse
+            | Term::TmZero
+            | Term::TmString(_)
+            | Term::TmFloat(_)
+            | Term::TmSucc(_)
+            | Term::TmVar(_)
+            | Term::TmTuple(_)
+            | Term::TmProj(_, _)
+            | Term::TmRecord(_)
+            | Term::TmRecordProj(_, _)
+            | Term::TmNil(_)
+            | Term::TmGlobal(_, _)
+            | Term::TmPrim(_)
+    )
+}
+
+fn write_atom(
+    f: &mut Formatter<'_>,
+    term: &Term,
+    names: &mut Vec<Symbol>,
+    mode: DisplayMode,
+) -> fmt::Result {
+    if is_atomic(term) {
+        write_term(f, term, names, mode)
+    } else {
+        write!(f, "(")?;
+        write_term(f, term, names, mode)?;
+        write!(f, ")")
+    }
+}
+
+// the name a case branch binds, after its `inl` or `inr`
+fn binder(name: Symbol, mode: DisplayMode) -> String {
+    match mode {
+        DisplayMode::DeBruijn => String::new(),
+        _ => format!(" {}", name),
+    }
+}
+
+fn write_under_binder(
+    f: &mut Formatter<'_>,
+    name: Symbol,
+    term: &Term,
+    names: &mut Vec<Symbol>,
+    mode: DisplayMode,
+) -> fmt::Result {
+    names.push(name);
+    let result = write_term(f, term, names, mode);
+    names.pop();
+    result
+}
+
+fn write_term(
+    f: &mut Formatter<'_>,
+    term: &Term,
+    names: &mut Vec<Symbol>,
+    mode: DisplayMode,
+) -> fmt::Result {
+    match term {
+        Term::TmTrue => write!(f, "true"),
+        Term::TmFalse => write!(f, "false"),
+        Term::TmZero => write!(f, "0"),
+        Term::TmNumber(n) => write!(f, "{}", n),
+        Term::TmString(text) => write!(f, "\"{}\"", text),
+        // a whole number keeps its `.0`, the parser reads no other numerals
+        Term::TmFloat(value) if value.fract() == 0.0 => write!(f, "{:.1}", value),
+        Term::TmFloat(value) => write!(f, "{}", value),
+        // a chain of `succ` on a numeral in a loop, it may be long
+        Term::TmSucc(_) if is_numeric_val(term) => {
+            let mut n = 0;
+            let mut inner = term;
+            while let Term::TmSucc(next) = inner {
+                n += 1;
+                inner = next;
+            }
+            write!(f, "{}", "succ(".repeat(n))?;
+            write_term(f, inner, names, mode)?;
+            write!(f, "{}", ")".repeat(n))
+        }
+        Term::TmSucc(term) => {
+            write!(f, "succ(")?;
+            write_term(f, term, names, mode)?;
+            write!(f, ")")
+        }
+        Term::TmVar(index) => match (names.iter().rev().nth(*index), mode) {
+            (Some(name), DisplayMode::Named) => write!(f, "{}", name),
+            (Some(name), DisplayMode::Both) => write!(f, "{}#{}", name, index),
+            // not bound in the printed term
+            _ => write!(f, "#{}", index),
+        },
+        // every nameless binder is an abstraction of its own
+        Term::TmAbs(name, typ, body) if mode == DisplayMode::DeBruijn => {
+            write!(f, "λ:{}.", typ)?;
+            write_under_binder(f, *name, body, names, mode)
+        }
+        // nested abstractions print as one with several parameters
+        Term::TmAbs(_, _, _) => {
+            write!(f, "lambda")?;
+            let mut term = term;
+            let mut bound = 0;
+            while let Term::TmAbs(name, typ, body) = term {
+                write!(f, " {}:{}", name, typ)?;
+                names.push(*name);
+                bound += 1;
+                term = body;
+            }
+            write!(f, ".")?;
+            let result = write_term(f, term, names, mode);
+            names.truncate(names.len() - bound);
+            result
+        }
+        Term::TmApp(left, right) => {
+            match left.as_ref() {
+                Term::TmApp(_, _) => write_term(f, left, names, mode)?,
+                _ => write_atom(f, left, names, mode)?,
+            }
+            write!(f, " ")?;
+            write_atom(f, right, names, mode)
+        }
+        Term::TmIf(if_term, then_term, else_term) => {
+            write!(f, "if ")?;
+            write_term(f, if_term, names, mode)?;
+            write!(f, " then ")?;
+            write_term(f, then_term, names, mode)?;
+            write!(f, " else ")?;
+            write_term(f, else_term, names, mode)
+        }
+        Term::TmTuple(terms) => {
+            write!(f, "{{")?;
+            for (i, term) in terms.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write_term(f, term, names, mode)?;
+            }
+            write!(f, "}}")
+        }
+        Term::TmProj(term, index) => {
+            write_atom(f, term, names, mode)?;
+            write!(f, ".{}", index)
+        }
+        Term::TmRecord(fields) => {
+            write!(f, "{{")?;
+            for (i, (label, term)) in fields.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}=", label)?;
+                write_term(f, term, names, mode)?;
+            }
+            write!(f, "}}")
+        }
+        Term::TmRecordProj(term, label) => {
+            write_atom(f, term, names, mode)?;
+            write!(f, ".{}", label)
+        }
+        Term::TmInl(term, typ) => {
+            write!(f, "inl ")?;
+            write_term(f, term, names, mode)?;
+            write!(f, " as {}", typ)
+        }
+        Term::TmInr(term, typ) => {
+            write!(f, "inr ")?;
+            write_term(f, term, names, mode)?;
+            write!(f, " as {}", typ)
+        }
+        Term::TmCase(term, inl_name, inl_term, inr_name, inr_term) => {
+            write!(f, "case ")?;
+            write_term(f, term, names, mode)?;
+            write!(f, " of inl{} => ", binder(*inl_name, mode))?;
+            write_under_binder(f, *inl_name, inl_term, names, mode)?;
+            write!(f, " | inr{} => ", binder(*inr_name, mode))?;
+            write_under_binder(f, *inr_name, inr_term, names, mode)
+        }
+        Term::TmTag(label, term, typ) => {
+            write!(f, "<{}=", label)?;
+            write_term(f, term, names, mode)?;
+            write!(f, "> as {}", typ)
+        }
+        Term::TmVariantCase(term, branches) => {
+            write!(f, "case ")?;
+            write_term(f, term, names, mode)?;
+            write!(f, " of ")?;
+            for (i, (label, name, branch)) in branches.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " | ")?;
+                }
+                match mode {
+                    DisplayMode::DeBruijn => write!(f, "<{}> => ", label)?,
+                    _ => write!(f, "<{}={}> => ", label, name)?,
+                }
+                write_under_binder(f, *name, branch, names, mode)?;
+            }
+            Ok(())
+        }
+        Term::TmNil(typ) => write!(f, "nil[{}]", typ),
+        Term::TmCons(typ, head, tail) => {
+            write!(f, "cons[{}] ", typ)?;
+            write_atom(f, head, names, mode)?;
+            write!(f, " ")?;
+            write_atom(f, tail, names, mode)
+        }
+        Term::TmIsNil(typ, term) => {
+            write!(f, "isnil[{}] ", typ)?;
+            write_atom(f, term, names, mode)
+        }
+        Term::TmHead(typ, term) => {
+            write!(f, "head[{}] ", typ)?;
+            write_atom(f, term, names, mode)
+        }
+        Term::TmTail(typ, term) => {
+            write!(f, "tail[{}] ", typ)?;
+            write_atom(f, term, names, mode)
+        }
+        Term::TmGlobal(name, _) => write!(f, "{}", name),
+        Term::TmPrim(primitive) => write!(f, "{}", primitive.name()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use misc::DisplayMode;
+
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_display() {
+        for input in [
+            "lambda x:Bool y:Nat->Nat.y (if x then 0 else succ(0))",
+            "lambda f:Bool->Bool.{f, lambda b:Bool c:List Nat.f b}",
+            "(lambda f:Bool->Bool.f) (lambda b:Bool.b) true",
+            "{0, {x=true, y={false}.1}.y}.2",
+            "case inl 0 as Nat+Bool of inl n => succ(n) | inr b => 0",
+            "case <b=true> as <n:Nat, b:Bool> of <n=x> => succ(x) | <b=y> => 0",
+            "head[Nat] (cons[Nat] succ(0) nil[Nat])",
+            "(lambda s:String.concat {s, \"!\"}) \"hello, world\"",
+        ] {
+            let term = Parser::new().parse(&format!("{};", input)).unwrap();
+            assert_eq!(term.to_string(), input);
+        }
+    }
+
+    #[test]
+    fn test_display_mode() {
+        let parse = |input: &str| Parser::new().parse(input).unwrap();
+        let term = parse("lambda x:Nat y:Nat.x;");
+        assert_eq!(
+            term.display(DisplayMode::DeBruijn).to_string(),
+            "λ:Nat.λ:Nat.#1"
+        );
+        assert_eq!(
+            term.display(DisplayMode::Both).to_string(),
+            "lambda x:Nat y:Nat.x#1"
+        );
+        let term = parse(
+            "lambda s:Nat+Bool.case s of inl n => {n, s} | inr b => \
+             case <l=b> as <l:Bool> of <l=c> => {c, b, 0};",
+        );
+        assert_eq!(
+            term.display(DisplayMode::DeBruijn).to_string(),
+            "λ:Nat+Bool.case #0 of inl => {#0, #1} | inr => \
+             case <l=#0> as <l:Bool> of <l> => {#0, #1, 0}"
+        );
+    }
+}
@@ -0,0 +1,498 @@
+use std::rc::Rc;
+
+use reconstruction::unify::{free_vars, unify, Constraint, Substitution};
+use tapl_frontend::{intern::Symbol, visit::Node};
+
+use crate::{
+    context::Context,
+    eval::is_numeric_val,
+    parser::{Definition, Term},
+    typing::{annotation, type_in, type_of, Type, TypeError},
+};
+
+// Reconstruction of the parameter types a program leaves out (TAPL 22.3 and
+// 22.4). The parser gives each of them a type variable, constraint typing
+// follows the rules of `type_of` and every constraint is solved as soon as
+// it is generated, so a projection or a case sees the type of its subterm
+// so far. A constraint without a solution is dropped, the ill-typed term is
+// reported by `type_of` with the error of its own rule.
+
+// whether an annotation of `term` has a type variable, the definitions it
+// refers to are reconstructed on their own
+pub fn has_type_vars(term: &Term) -> bool {
+    !type_vars(term).is_empty()
+}
+
+// the type variables in the annotations of `term`, a heap stack walks it so
+// a long numeral does not overflow the Rust stack
+fn type_vars(term: &Term) -> Vec<usize> {
+    let mut vars = vec![];
+    let mut stack = vec![term];
+    while let Some(term) = stack.pop() {
+        if let Some(typ) = annotation(term) {
+            free_vars(typ, &mut vars);
+        }
+        stack.extend(term.children().into_iter().map(|(_, child)| child));
+    }
+    vars
+}
+
+// `term` with its type variables solved, or the first parameter whose type
+// is left undetermined
+pub fn reconstruct(ctx: &mut Context, term: &Term) -> Result<Term, TypeError> {
+    match solve(ctx, term)? {
+        (term, None) => Ok(term),
+        (_, Some(name)) => Err(TypeError::AnnotationRequired(name)),
+    }
+}
+
+// `term` with the type variables reconstruction solves, and the first
+// parameter it leaves undetermined
+pub(crate) fn solve(ctx: &mut Context, term: &Term) -> Result<(Term, Option<Symbol>), TypeError> {
+    let mut vars = type_vars(term);
+    for (_, typ) in ctx.bindings() {
+        free_vars(typ, &mut vars);
+    }
+    let mut reconstruction = Reconstruction {
+        substitution: Substitution::default(),
+        next: vars.into_iter().max().map_or(0, |var| var + 1),
+        ill_typed: false,
+        unsolved: None,
+    };
+    reconstruction.infer(ctx, term);
+    let mut term = term.clone();
+    reconstruction.annotate(&mut term)?;
+    let name = match reconstruction.unsolved {
+        Some(name) => name,
+        None => return Ok((term, None)),
+    };
+    // an ill-typed term leaves types unsolved as well, its own error is the
+    // one to report unless it is about a type only an annotation can give,
+    // such as the parameter of `lambda x. x x`
+    if reconstruction.ill_typed {
+        match type_in(ctx, &term) {
+            Err(error) if !mentions_type_var(&error) => return Err(error),
+            _ => {}
+        }
+    }
+    Ok((term, Some(name)))
+}
+
+fn mentions_type_var(error: &TypeError) -> bool {
+    let types = match error {
+        TypeError::ParameterTypeMismatch(expected, found)
+        | TypeError::IfBranchesMismatch(expected, found)
+        | TypeError::InjectionTypeMismatch(expected, found)
+        | TypeError::CaseBranchesMismatch(expected, found)
+        | TypeError::ListTypeMismatch(expected, found) => vec![expected, found],
+        TypeError::ArrowTypeExpected(found)
+        | TypeError::SuccArgumentNotNumber(found)
+        | TypeError::IfConditionNotBoolean(found)
+        | TypeError::OperatorArgumentNotBoolean(_, found)
+        | TypeError::ProductTypeExpected(found)
+        | TypeError::RecordTypeExpected(found)
+        | TypeError::SumTypeExpected(found)
+        | TypeError::VariantTypeExpected(found) => vec![found],
+        TypeError::MissingBranches(fields) => fields.iter().map(|(_, typ)| typ).collect(),
+        TypeError::UnboundVariable(_)
+        | TypeError::TupleIndexOutOfRange(_)
+        | TypeError::LabelNotFound(_)
+        | TypeError::DuplicateLabel(_)
+        | TypeError::UnreachableBranch(_)
+        | TypeError::AnnotationRequired(_) => vec![],
+    };
+    types.into_iter().any(has_vars)
+}
+
+struct Reconstruction {
+    substitution: Substitution<Type>,
+    // the first type variable no annotation has
+    next: usize,
+    // whether a constraint had no solution or a typing rule did not apply
+    ill_typed: bool,
+    // the first parameter whose type is left with a type variable
+    unsolved: Option<Symbol>,
+}
+
+impl Reconstruction {
+    fn fresh(&mut self) -> Type {
+        self.next += 1;
+        Type::Var(self.next - 1)
+    }
+
+    fn resolve(&self, typ: &Type) -> Type {
+        self.substitution.apply(typ)
+    }
+
+    fn equate(&mut self, left: &Type, right: &Type) {
+        let constraint = Constraint::new(self.resolve(left), self.resolve(right), ());
+        match unify(vec![constraint]) {
+            Ok(solution) => self.substitution.extend(solution),
+            Err(_) => self.ill_typed = true,
+        }
+    }
+
+    // the type of `term` as far as constraint typing finds it
+    fn infer(&mut self, ctx: &mut Context, term: &Term) -> Type {
+        match term {
+            Term::TmTrue | Term::TmFalse => Type::Boolean,
+            Term::TmZero | Term::TmNumber(_) => Type::Number,
+            Term::TmString(_) => Type::String,
+            Term::TmFloat(_) => Type::Float,
+            Term::TmPrim(primitive) => primitive.typ().clone(),
+            Term::TmGlobal(_, term) => match type_of(&mut Context::default(), term) {
+                Ok(typ) => typ,
+                Err(_) => self.ill_typed(),
+            },
+            Term::TmSucc(_) if is_numeric_val(term) => Type::Number,
+            Term::TmSucc(term) => {
+                let typ = self.infer(ctx, term);
+                self.equate(&typ, &Type::Number);
+                Type::Number
+            }
+            Term::TmVar(index) => match ctx.get_type(*index) {
+                Some(typ) => typ,
+                None => self.ill_typed(),
+            },
+            Term::TmAbs(name, typ, body) => {
+                ctx.push(*name, typ.clone());
+                let body_type = self.infer(ctx, body);
+                ctx.pop();
+                Type::Arrow(Box::new(typ.clone()), Box::new(body_type))
+            }
+            Term::TmApp(left, right) => {
+                // the argument goes first, as it does in `type_of`. An
+                // abstraction applied directly, as a `let` is, gets its
+                // parameter type before its body, whose projections of the
+                // parameter need it.
+                let right_type = self.infer(ctx, right);
+                if let Term::TmAbs(_, typ, _) = left.as_ref() {
+                    self.equate(typ, &right_type);
+                }
+                let left_type = self.infer(ctx, left);
+                let result = self.fresh();
+                let arrow = Type::Arrow(Box::new(right_type), Box::new(result.clone()));
+                self.equate(&left_type, &arrow);
+                result
+            }
+            Term::TmIf(if_term, then_term, else_term) => {
+                let if_type = self.infer(ctx, if_term);
+                self.equate(&if_type, &Type::Boolean);
+                let then_type = self.infer(ctx, then_term);
+                let else_type = self.infer(ctx, else_term);
+                self.equate(&then_type, &else_type);
+                then_type
+            }
+            Term::TmTuple(terms) => {
+                Type::Product(terms.iter().map(|term| self.infer(ctx, term)).collect())
+            }
+            Term::TmRecord(fields) => Type::Record(
+                fields
+                    .iter()
+                    .map(|(label, term)| (*label, self.infer(ctx, term)))
+                    .collect(),
+            ),
+            Term::TmProj(term, index) => {
+                let typ = self.infer(ctx, term);
+                let typ = self.resolve(&typ);
+                let found = match &typ {
+                    Type::Product(types) => {
+                        index.checked_sub(1).and_then(|i| types.get(i).cloned())
+                    }
+                    _ => None,
+                };
+                self.found(&typ, found)
+            }
+            Term::TmRecordProj(term, label) => {
+                let typ = self.infer(ctx, term);
+                let typ = self.resolve(&typ);
+                let found = match &typ {
+                    Type::Record(fields) => lookup(fields, *label),
+                    _ => None,
+                };
+                self.found(&typ, found)
+            }
+            Term::TmInl(term, typ) => {
+                let found = self.infer(ctx, term);
+                match typ {
+                    Type::Sum(left, _) => self.equate(&found, left),
+                    _ => self.ill_typed = true,
+                }
+                typ.clone()
+            }
+            Term::TmInr(term, typ) => {
+                let found = self.infer(ctx, term);
+                match typ {
+                    Type::Sum(_, right) => self.equate(&found, right),
+                    _ => self.ill_typed = true,
+                }
+                typ.clone()
+            }
+            Term::TmCase(term, inl_name, inl_term, inr_name, inr_term) => {
+                let typ = self.infer(ctx, term);
+                let (left, right) = (self.fresh(), self.fresh());
+                let sum = Type::Sum(Box::new(left.clone()), Box::new(right.clone()));
+                self.equate(&typ, &sum);
+                let inl_type = self.infer_branch(ctx, (*inl_name, left), inl_term);
+                let inr_type = self.infer_branch(ctx, (*inr_name, right), inr_term);
+                self.equate(&inl_type, &inr_type);
+                inl_type
+            }
+            Term::TmTag(label, term, typ) => {
+                let found = self.infer(ctx, term);
+                match typ {
+                    Type::Variant(fields) => match lookup(fields, *label) {
+                        Some(expected) => self.equate(&found, &expected),
+                        None => self.ill_typed = true,
+                    },
+                    _ => self.ill_typed = true,
+                }
+                typ.clone()
+            }
+            Term::TmVariantCase(term, branches) => {
+                let typ = self.infer(ctx, term);
+                let typ = self.resolve(&typ);
+                let fields = match &typ {
+                    Type::Variant(fields) => fields.clone(),
+                    _ => vec![],
+                };
+                let mut result = None;
+                for (label, name, branch) in branches {
+                    let found = lookup(&fields, *label);
+                    let typ = self.found(&typ, found);
+                    let branch_type = self.infer_branch(ctx, (*name, typ), branch);
+                    match &result {
+                        Some(first) => self.equate(first, &branch_type),
+                        None => result = Some(branch_type),
+                    }
+                }
+                result.unwrap_or_else(|| self.fresh())
+            }
+            Term::TmNil(typ) => Type::List(Box::new(typ.clone())),
+            Term::TmCons(typ, head, tail) => {
+                let list = Type::List(Box::new(typ.clone()));
+                let head_type = self.infer(ctx, head);
+                self.equate(&head_type, typ);
+                let tail_type = self.infer(ctx, tail);
+                self.equate(&tail_type, &list);
+                list
+            }
+            Term::TmIsNil(typ, term) => {
+                self.infer_list(ctx, typ, term);
+                Type::Boolean
+            }
+            Term::TmHead(typ, term) => {
+                self.infer_list(ctx, typ, term);
+                typ.clone()
+            }
+            Term::TmTail(typ, term) => self.infer_list(ctx, typ, term),
+        }
+    }
+
+    // the argument of `isnil[T]`, `head[T]` or `tail[T]` is a `List T`,
+    // which is returned
+    fn infer_list(&mut self, ctx: &mut Context, typ: &Type, term: &Term) -> Type {
+        let list = Type::List(Box::new(typ.clone()));
+        let found = self.infer(ctx, term);
+        self.equate(&found, &list);
+        list
+    }
+
+    // The type a typing rule looks up in `typ`, if it is there. A type
+    // variable may still turn out to have it, any other type is ill-typed.
+    fn found(&mut self, typ: &Type, found: Option<Type>) -> Type {
+        match (found, typ) {
+            (Some(found), _) => found,
+            (None, Type::Var(_)) => self.fresh(),
+            (None, _) => self.ill_typed(),
+        }
+    }
+
+    // a type for a term no typing rule applies to
+    fn ill_typed(&mut self) -> Type {
+        self.ill_typed = true;
+        self.fresh()
+    }
+
+    // the type of a case branch, with its binder of type `typ`
+    fn infer_branch(
+        &mut self,
+        ctx: &mut Context,
+        (name, typ): (Symbol, Type),
+        branch: &Term,
+    ) -> Type {
+        ctx.push(name, typ);
+        let typ = self.infer(ctx, branch);
+        ctx.pop();
+        typ
+    }
+
+    // solves the annotations of `term`, a parameter type that still has a
+    // type variable needs an annotation
+    fn annotate(&mut self, term: &mut Term) -> Result<(), TypeError> {
+        if let Term::TmAbs(name, typ, _) = term {
+            *typ = self.resolve(typ);
+            if has_vars(typ) && self.unsolved.is_none() {
+                self.unsolved = Some(*name);
+            }
+        }
+        if let Some(typ) = annotation_mut(term) {
+            *typ = self.resolve(typ);
+        }
+        let mut annotate = |term: &mut Rc<Term>| self.annotate(Rc::make_mut(term));
+        match term {
+            Term::TmTrue
+            | Term::TmFalse
+            | Term::TmZero
+            | Term::TmNumber(_)
+            | Term::TmString(_)
+            | Term::TmFloat(_)
+            | Term::TmVar(_)
+            | Term::TmNil(_)
+            | Term::TmPrim(_) => Ok(()),
+            Term::TmSucc(_) if is_numeric_val(term) => Ok(()),
+            // a definition parsed before its own statement was typechecked
+            Term::TmGlobal(_, definition) if has_type_vars(definition) => {
+                let term = reconstruct(&mut Context::default(), definition)?;
+                *definition = Definition(Rc::new(term));
+                Ok(())
+            }
+            Term::TmGlobal(_, _) => Ok(()),
+            Term::TmSucc(term)
+            | Term::TmAbs(_, _, term)
+            | Term::TmProj(term, _)
+            | Term::TmRecordProj(term, _)
+            | Term::TmInl(term, _)
+            | Term::TmInr(term, _)
+            | Term::TmTag(_, term, _)
+            | Term::TmIsNil(_, term)
+            | Term::TmHead(_, term)
+            | Term::TmTail(_, term) => annotate(term),
+            Term::TmApp(left, right) | Term::TmCons(_, left, right) => {
+                annotate(left)?;
+                annotate(right)
+            }
+            Term::TmIf(a, b, c) | Term::TmCase(a, _, b, _, c) => {
+                annotate(a)?;
+                annotate(b)?;
+                annotate(c)
+            }
+            Term::TmTuple(terms) => terms.iter_mut().try_for_each(|term| self.annotate(term)),
+            Term::TmRecord(fields) => fields
+                .iter_mut()
+                .try_for_each(|(_, term)| self.annotate(term)),
+            Term::TmVariantCase(term, branches) => {
+                annotate(term)?;
+                branches
+                    .iter_mut()
+                    .try_for_each(|(_, _, branch)| self.annotate(branch))
+            }
+        }
+    }
+}
+
+pub(crate) fn has_vars(typ: &Type) -> bool {
+    let mut vars = vec![];
+    free_vars(typ, &mut vars);
+    !vars.is_empty()
+}
+
+fn lookup(fields: &[(Symbol, Type)], label: Symbol) -> Option<Type> {
+    fields
+        .iter()
+        .find(|(field, _)| *field == label)
+        .map(|(_, typ)| typ.clone())
+}
+
+fn annotation_mut(term: &mut Term) -> Option<&mut Type> {
+    match term {
+        Term::TmAbs(_, typ, _)
+        | Term::TmInl(_, typ)
+        | Term::TmInr(_, typ)
+        | Term::TmTag(_, _, typ)
+        | Term::TmNil(typ)
+        | Term::TmCons(typ, _, _)
+        | Term::TmIsNil(typ, _)
+        | Term::TmHead(typ, _)
+        | Term::TmTail(typ, _) => Some(typ),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tapl_testkit::{assert_evals_to, assert_type_error};
+
+    use super::*;
+    use crate::{parser::Parser, Stlc};
+
+    fn reconstructed(input: &str) -> Result<String, TypeError> {
+        let term = Parser::new().parse(input).unwrap();
+        let term = reconstruct(&mut Context::default(), &term)?;
+        assert!(type_of(&mut Context::default(), &term).is_ok(), "{}", input);
+        Ok(term.to_string())
+    }
+
+    #[test]
+    fn test_reconstruct() {
+        let inferred = [
+            ("lambda x. succ(x);", "lambda x:Nat.succ(x)"),
+            (
+                "let f = lambda x. succ(x) in f 0;",
+                "(lambda f:Nat->Nat.f 0) (lambda x:Nat.succ(x))",
+            ),
+            (
+                "lambda x. if x then 0 else 0;",
+                "lambda x:Bool.if x then 0 else 0",
+            ),
+            (
+                "let x = succ(0) in succ(x);",
+                "(lambda x:Nat.succ(x)) succ(0)",
+            ),
+            (
+                "(lambda f:Nat->Bool.f 0) (lambda n.isnil[Nat] nil[Nat]);",
+                "(lambda f:Nat->Bool.f 0) (lambda n:Nat.isnil[Nat] nil[Nat])",
+            ),
+            (
+                "cons[Nat->Nat] (lambda n.succ(n)) nil[Nat->Nat];",
+                "cons[Nat->Nat] (lambda n:Nat.succ(n)) nil[Nat->Nat]",
+            ),
+            // the use of a parameter after its binder determines its type
+            (
+                "lambda f. lambda x. succ(f (succ(x)));",
+                "lambda f:Nat->Nat x:Nat.succ(f succ(x))",
+            ),
+        ];
+        for (input, expected) in inferred {
+            assert_eq!(reconstructed(input), Ok(expected.to_string()), "{}", input);
+        }
+        // a parameter applied to itself would need an infinite type
+        for (input, name) in [
+            ("lambda x.x;", "x"),
+            ("lambda x y:Nat.y;", "x"),
+            ("lambda x. x x;", "x"),
+        ] {
+            assert_eq!(
+                reconstructed(input),
+                Err(TypeError::AnnotationRequired(name.into()))
+            );
+        }
+        assert_eq!(
+            TypeError::AnnotationRequired("x".into()).to_string(),
+            "annotation required: the type of x cannot be inferred, write lambda x:T"
+        );
+    }
+
+    #[test]
+    fn test_reconstruct_eval() {
+        assert_evals_to!(Stlc, "let f = lambda x. succ(x) in f 0;", "succ(0)");
+        assert_evals_to!(
+            Stlc,
+            "(lambda x. if x then 0 else succ(0)) false;",
+            "succ(0)"
+        );
+        // a constraint that cannot be solved is the type error of the term
+        assert_type_error!(Stlc, "lambda x. if x then x else succ(x);");
+        assert_type_error!(Stlc, "let f = lambda x. succ(x) in f true;");
+    }
+}
@@ -0,0 +1 @@
+pub use tapl_frontend::span::{Span, SpanTree, Spanned};
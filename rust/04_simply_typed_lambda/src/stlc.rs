@@ -0,0 +1,202 @@
+use std::fmt::{self, Formatter};
+
+use tapl_frontend::intern::Symbol;
+
+use crate::{
+    context::Context,
+    diagnostic::{diagnose, Diagnostic},
+    eval::{eval_to_value, EvalError, Value},
+    located::{reconstruct_located, type_of_located, Located},
+    parser::{ParseError, Parser, Statement, Term},
+    span::SpanTree,
+    typing::{Type, TypeError},
+};
+
+// Everything running a program can fail with, type errors point at the
+// subterm to blame
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    Parse(ParseError),
+    Type(Located<TypeError>),
+    Eval(EvalError),
+}
+
+impl From<ParseError> for Error {
+    fn from(error: ParseError) -> Self {
+        Error::Parse(error)
+    }
+}
+
+impl From<Located<TypeError>> for Error {
+    fn from(error: Located<TypeError>) -> Self {
+        Error::Type(error)
+    }
+}
+
+impl From<EvalError> for Error {
+    fn from(error: EvalError) -> Self {
+        Error::Eval(error)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(error) => write!(f, "{}", error),
+            Error::Type(error) => write!(f, "{}", error),
+            Error::Eval(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Parse(error) => Some(error),
+            Error::Type(error) => Some(&error.error),
+            Error::Eval(error) => Some(error),
+        }
+    }
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+// A term run to a value, along with the type it was checked to have
+#[derive(Clone, Debug, PartialEq)]
+pub struct Evaluated {
+    pub value: Value,
+    pub typ: Type,
+}
+
+// What running one statement of a program gave
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    Defined(Symbol, Type),
+    Evaluated(Evaluated),
+}
+
+// The parser, typechecker and evaluator wired together for whole programs,
+// like the `eval(input)` functions of the arith crates:
+//
+//     Stlc::eval("(lambda x:Bool.{x, 0}) true;")
+//     Stlc::type_of("lambda x:Nat.succ(x);")
+pub struct Stlc;
+
+impl Stlc {
+    // the term together with the source span of every subterm
+    pub fn parse_str(input: &str) -> Result<(Term, SpanTree)> {
+        Ok(Parser::new().parse_with_spans(input)?)
+    }
+
+    pub fn type_of(input: &str) -> Result<Type> {
+        let (term, spans) = Stlc::parse_str(input)?;
+        Ok(type_of_located(&mut Context::default(), &term, &spans)?)
+    }
+
+    // typechecks the program and evaluates it to a value
+    pub fn eval(input: &str) -> Result<Evaluated> {
+        let (term, spans) = Stlc::parse_str(input)?;
+        let term = reconstruct_located(&mut Context::default(), &term, &spans)?;
+        let typ = type_of_located(&mut Context::default(), &term, &spans)?;
+        Ok(Evaluated {
+            value: eval_to_value(&term)?,
+            typ,
+        })
+    }
+
+    // Runs the statements of `input` in order and stops at the first error,
+    // a definition `name = t;` is typechecked and then usable by later
+    // statements:
+    //
+    //     Stlc::run("id = lambda x:Bool.x; id true;")
+    pub fn run(input: &str) -> Result<Vec<Outcome>> {
+        Stlc::run_with(&mut Parser::new(), input)
+    }
+
+    // like `run`, with the definitions and primitives of `parser`, which
+    // keeps the definitions of `input` for the next call
+    pub fn run_with(parser: &mut Parser, input: &str) -> Result<Vec<Outcome>> {
+        let mut outcomes = vec![];
+        for statement in parser.parse_program(input) {
+            let Statement { name, term, spans } = statement.map_err(|located| located.error)?;
+            let term = reconstruct_located(&mut Context::default(), &term, &spans)?;
+            let typ = type_of_located(&mut Context::default(), &term, &spans)?;
+            outcomes.push(match name {
+                Some(name) => Outcome::Defined(name, typ),
+                None => Outcome::Evaluated(Evaluated {
+                    value: eval_to_value(&term)?,
+                    typ,
+                }),
+            });
+        }
+        Ok(outcomes)
+    }
+
+    // a rustc-style explanation of why `input` does not typecheck
+    pub fn diagnose(input: &str) -> Result<Option<Diagnostic>> {
+        let (term, spans) = Stlc::parse_str(input)?;
+        Ok(diagnose(&mut Context::default(), &term, &spans).err())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::span::Span;
+
+    #[test]
+    fn test_stlc() -> Result<()> {
+        let evaluated = Stlc::eval("(lambda x:Bool.{x, 0}) true;")?;
+        assert_eq!(evaluated.value.to_string(), "{true, 0}");
+        assert_eq!(evaluated.typ.to_string(), "Bool*Nat");
+        assert_eq!(
+            Stlc::type_of("lambda x:Nat.succ(x);")?.to_string(),
+            "Nat->Nat"
+        );
+
+        let source = "lambda x:Bool.succ(x);";
+        match Stlc::type_of(source) {
+            Err(Error::Type(located)) => assert_eq!(located.span.text(source), "succ(x)"),
+            result => panic!("expected a type error, got {:?}", result),
+        }
+        assert!(Stlc::diagnose(source)?.is_some());
+        assert_eq!(
+            Stlc::eval("y;"),
+            Err(Error::Parse(ParseError::UnboundVariable("y".into())))
+        );
+        assert_eq!(Stlc::parse_str("true;")?.1.span, Span::new(0, 4));
+        Ok(())
+    }
+
+    #[test]
+    fn test_run() -> Result<()> {
+        let outcomes = Stlc::run(
+            "id = lambda x:Bool.x;\nneg = lambda b:Bool.if b then false else true;\nneg (id true);",
+        )?;
+        let typ = |outcome: &Outcome| match outcome {
+            Outcome::Defined(_, typ) => typ.to_string(),
+            Outcome::Evaluated(evaluated) => evaluated.typ.to_string(),
+        };
+        assert_eq!(typ(&outcomes[0]), "Bool->Bool");
+        match &outcomes[2] {
+            Outcome::Evaluated(evaluated) => assert_eq!(evaluated.value.to_string(), "false"),
+            outcome => panic!("expected a value, got {:?}", outcome),
+        }
+        // a definition is only visible after it, a binder shadows it
+        assert_eq!(
+            Stlc::run("id true; id = lambda x:Bool.x;"),
+            Err(Error::Parse(ParseError::UnboundVariable("id".into())))
+        );
+        let mut parser = Parser::new();
+        Stlc::run_with(&mut parser, "id = lambda x:Bool.x;")?;
+        let outcomes = Stlc::run_with(&mut parser, "(lambda id:Nat.id) 0;")?;
+        assert_eq!(typ(&outcomes[0]), "Nat");
+        // a type error in a statement is blamed on its own text
+        let source = "f = lambda x:Bool.x; f 0;";
+        match Stlc::run(source) {
+            Err(Error::Type(located)) => assert_eq!(located.span.text(source), "f 0"),
+            result => panic!("expected a type error, got {:?}", result),
+        }
+        Ok(())
+    }
+}
@@ -1,128 +1,226 @@
-use crate::{parser::Term, typing::Type};
+use std::rc::Rc;
 
-trait MutVisitor: Sized {
-    fn visit_var(&mut self, var: &mut Term) {}
-
-    fn visit_const(&mut self, t: &mut Term) {}
-
-    fn visit_succ(&mut self, t: &mut Term) {
-        self.visit_term(t);
-    }
-
-    fn visit_abs(&mut self, body: &mut Term) {
-        self.visit_term(body);
-    }
-
-    fn visit_app(&mut self, t1: &mut Term, t2: &mut Term) {
-        self.visit_term(t1);
-        self.visit_term(t2);
-    }
+use crate::parser::Term;
 
-    fn visit_if(&mut self, guard: &mut Term, csq: &mut Term, alt: &mut Term) {
-        self.visit_term(guard);
-        self.visit_term(csq);
-        self.visit_term(alt);
-    }
+trait MutVisitor: Sized {
+    // `depth` is the number of binders between the variable and the root
+    fn visit_var(&mut self, var: &mut Term, depth: usize);
 
     fn visit_term(&mut self, term: &mut Term) {
         walk_mut_term(self, term);
     }
 }
 
-fn walk_mut_term<V: MutVisitor>(visitor: &mut V, var: &mut Term) {
-    match var {
-        Term::TmTrue | Term::TmFalse | Term::TmZero => visitor.visit_const(var),
-        Term::TmSucc(t) => visitor.visit_succ(t),
-        Term::TmVar(_) => visitor.visit_var(var),
-        Term::TmAbs(_, _ty, body) => visitor.visit_abs(body),
-        Term::TmApp(t1, t2) => visitor.visit_app(t1, t2),
-        Term::TmIf(a, b, c) => visitor.visit_if(a, b, c),
+// Visits the variables of `term` with the subterms still to visit kept on a
+// heap stack, so deep terms do not overflow the Rust stack. A subterm shared
+// with another term is copied before it changes.
+fn walk_mut_term<V: MutVisitor>(visitor: &mut V, term: &mut Term) {
+    let mut stack = vec![(term, 0)];
+    while let Some((term, depth)) = stack.pop() {
+        match term {
+            Term::TmVar(_) => visitor.visit_var(term, depth),
+            Term::TmTrue
+            | Term::TmFalse
+            | Term::TmZero
+            | Term::TmNumber(_)
+            | Term::TmString(_)
+            | Term::TmFloat(_)
+            | Term::TmNil(_)
+            | Term::TmGlobal(_, _)
+            | Term::TmPrim(_) => {}
+            Term::TmAbs(_, _, body) => stack.push((Rc::make_mut(body), depth + 1)),
+            Term::TmSucc(t)
+            | Term::TmProj(t, _)
+            | Term::TmRecordProj(t, _)
+            | Term::TmInl(t, _)
+            | Term::TmInr(t, _)
+            | Term::TmTag(_, t, _)
+            | Term::TmIsNil(_, t)
+            | Term::TmHead(_, t)
+            | Term::TmTail(_, t) => stack.push((Rc::make_mut(t), depth)),
+            Term::TmApp(t1, t2) | Term::TmCons(_, t1, t2) => {
+                stack.push((Rc::make_mut(t2), depth));
+                stack.push((Rc::make_mut(t1), depth));
+            }
+            Term::TmIf(guard, csq, alt) => {
+                stack.push((Rc::make_mut(alt), depth));
+                stack.push((Rc::make_mut(csq), depth));
+                stack.push((Rc::make_mut(guard), depth));
+            }
+            Term::TmTuple(terms) => stack.extend(terms.iter_mut().map(|t| (t, depth))),
+            Term::TmRecord(fields) => stack.extend(fields.iter_mut().map(|(_, t)| (t, depth))),
+            // each branch of a case binds one variable, like an abstraction body
+            Term::TmCase(t, _, inl_branch, _, inr_branch) => {
+                stack.push((Rc::make_mut(inr_branch), depth + 1));
+                stack.push((Rc::make_mut(inl_branch), depth + 1));
+                stack.push((Rc::make_mut(t), depth));
+            }
+            Term::TmVariantCase(t, branches) => {
+                let branches = branches.iter_mut().rev();
+                stack.extend(branches.map(|(_, _, branch)| (branch, depth + 1)));
+                stack.push((Rc::make_mut(t), depth));
+            }
+        }
     }
 }
 
-#[derive(Copy, Clone, Debug)]
-enum Direction {
-    Up,
-    Down,
-}
-
 #[derive(Copy, Clone, Debug)]
 struct Shifting {
+    d: isize,
     cutoff: usize,
-    direction: Direction,
-}
-
-impl Default for Shifting {
-    fn default() -> Self {
-        Shifting {
-            cutoff: 0,
-            direction: Direction::Up,
-        }
-    }
-}
-
-impl Shifting {
-    pub fn new(direction: Direction) -> Self {
-        Shifting {
-            cutoff: 0,
-            direction,
-        }
-    }
 }
 
 impl MutVisitor for Shifting {
-    fn visit_var(&mut self, var: &mut Term) {
+    fn visit_var(&mut self, var: &mut Term, depth: usize) {
         let n = match var {
             Term::TmVar(n) => n,
             _ => unreachable!(),
         };
 
-        if *n >= self.cutoff {
-            match self.direction {
-                Direction::Up => *n += 1,
-                Direction::Down => *n -= 1,
-            }
+        if *n >= self.cutoff + depth {
+            *n = (*n as isize + self.d) as usize;
         }
     }
+}
 
-    fn visit_abs(&mut self, body: &mut Term) {
-        self.cutoff += 1;
-        self.visit_term(body);
-        self.cutoff -= 1;
-    }
+// ↑d,c: shift the variables at or above cutoff `c` by `d` (TAPL 6.2.1), a
+// negative `d` must not take a variable below 0
+pub fn shift(term: &mut Term, d: isize, cutoff: usize) {
+    Shifting { d, cutoff }.visit_term(term);
 }
 
+// [j ↦ s] (TAPL 6.2.4): under `d` binders the variable j is j + d and the
+// substituted term is shifted up by d
 #[derive(Debug)]
 struct Substitution {
-    cutoff: usize,
+    index: usize,
     term: Term,
 }
 
 impl Substitution {
-    pub fn new(term: Term) -> Substitution {
-        Substitution { cutoff: 0, term }
+    pub fn new(index: usize, term: Term) -> Substitution {
+        Substitution { index, term }
     }
 }
 
 impl MutVisitor for Substitution {
-    fn visit_var(&mut self, var: &mut Term) {
+    fn visit_var(&mut self, var: &mut Term, depth: usize) {
         match var {
-            Term::TmVar(n) if *n >= self.cutoff => {
+            Term::TmVar(n) if *n == self.index + depth => {
                 *var = self.term.clone();
+                shift(var, depth as isize, 0);
             }
+            Term::TmVar(_) => {}
             _ => unreachable!(),
         }
     }
-
-    fn visit_abs(&mut self, body: &mut Term) {
-        self.cutoff += 1;
-        walk_mut_term(self, body);
-        self.cutoff -= 1;
-    }
 }
 
+// the body of a beta reduction: ↑-1 ([0 ↦ ↑1 val] body)
 pub fn substitution(mut val: Term, body: &mut Term) {
-    Shifting::new(Direction::Up).visit_term(&mut val);
-    Substitution::new(val).visit_term(body);
-    Shifting::new(Direction::Down).visit_term(body);
+    shift(&mut val, 1, 0);
+    Substitution::new(0, val).visit_term(body);
+    shift(body, -1, 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::typing::Type;
+
+    proptest! {
+        #[test]
+        fn test_shift_up_then_down(term in any_with::<Term>(3)) {
+            let mut shifted = term.clone();
+            shift(&mut shifted, 3, 1);
+            shift(&mut shifted, -3, 1);
+            prop_assert_eq!(shifted, term);
+        }
+    }
+
+    fn abs(body: Term) -> Term {
+        Term::TmAbs("x".into(), Type::Boolean, Rc::new(body))
+    }
+
+    fn app(left: Term, right: Term) -> Term {
+        Term::TmApp(Rc::new(left), Rc::new(right))
+    }
+
+    fn substitute(val: Term, mut body: Term) -> Term {
+        substitution(val, &mut body);
+        body
+    }
+
+    #[test]
+    fn test_shift() {
+        use Term::TmVar as Var;
+
+        // TAPL exercise 6.2.2: ↑2 (λ.λ. 1 (0 2))
+        let mut term = abs(abs(app(Var(1), app(Var(0), Var(2)))));
+        shift(&mut term, 2, 0);
+        assert_eq!(term, abs(abs(app(Var(1), app(Var(0), Var(4))))));
+        // the cutoff leaves the variables below it alone
+        let mut term = app(Var(0), abs(app(Var(2), Var(0))));
+        shift(&mut term, 3, 1);
+        assert_eq!(term, app(Var(0), abs(app(Var(5), Var(0)))));
+        shift(&mut term, -2, 1);
+        assert_eq!(term, app(Var(0), abs(app(Var(3), Var(0)))));
+    }
+
+    #[test]
+    fn test_substitution() {
+        use Term::TmVar as Var;
+
+        assert_eq!(substitute(Term::TmTrue, Var(0)), Term::TmTrue);
+        // the other free variables move down into the place of the binder
+        assert_eq!(
+            substitute(Term::TmTrue, app(Var(1), Var(0))),
+            app(Var(0), Term::TmTrue)
+        );
+        // bound variables are left alone
+        assert_eq!(
+            substitute(Term::TmTrue, abs(app(Var(0), Var(1)))),
+            abs(app(Var(0), Term::TmTrue))
+        );
+        // a free variable of the substituted term is shifted under binders
+        // and still refers to the same binding (TAPL 6.2.4)
+        assert_eq!(
+            substitute(Var(0), abs(abs(app(Var(2), Var(0))))),
+            abs(abs(app(Var(2), Var(0))))
+        );
+        assert_eq!(
+            substitute(Var(3), abs(app(Var(1), Var(2)))),
+            abs(app(Var(4), Var(1)))
+        );
+        // both branches of a case bind a variable
+        let case = |term: Term, inl: Term, inr: Term| {
+            Term::TmCase(
+                Rc::new(term),
+                "l".into(),
+                Rc::new(inl),
+                "r".into(),
+                Rc::new(inr),
+            )
+        };
+        assert_eq!(
+            substitute(Var(5), case(Var(0), Var(1), Var(0))),
+            case(Var(5), Var(6), Var(0))
+        );
+    }
+
+    #[test]
+    fn test_substitute_deep_term() {
+        let mut body = Term::TmVar(0);
+        for _ in 0..100_000 {
+            body = Term::TmSucc(Rc::new(body));
+        }
+        substitution(Term::TmZero, &mut body);
+        let mut term = &body;
+        while let Term::TmSucc(inner) = term {
+            term = inner;
+        }
+        assert_eq!(term, &Term::TmZero);
+    }
 }
@@ -0,0 +1,331 @@
+use std::fmt::{self, Formatter};
+use std::rc::Rc;
+
+use crate::{
+    eval::{is_numeric_val, is_val, EvalError},
+    parser::Term,
+    substitute::substitution,
+};
+
+// The evaluation rules of TAPL chapters 9 and 11, with E-AppPrim for the
+// host primitives
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rule {
+    IfTrue,
+    IfFalse,
+    If,
+    Succ,
+    AppAbs,
+    AppPrim,
+    App1,
+    App2,
+    Tuple,
+    ProjTuple,
+    ProjRcd,
+    Proj,
+    Rcd,
+    Inl,
+    Inr,
+    CaseInl,
+    CaseInr,
+    Case,
+    Variant,
+    CaseVariant,
+    Cons1,
+    Cons2,
+    IsNilNil,
+    IsNilCons,
+    IsNil,
+    HeadCons,
+    Head,
+    TailCons,
+    Tail,
+    Global,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Rule::IfTrue => "E-IfTrue",
+            Rule::IfFalse => "E-IfFalse",
+            Rule::If => "E-If",
+            Rule::Succ => "E-Succ",
+            Rule::AppAbs => "E-AppAbs",
+            Rule::AppPrim => "E-AppPrim",
+            Rule::App1 => "E-App1",
+            Rule::App2 => "E-App2",
+            Rule::Tuple => "E-Tuple",
+            Rule::ProjTuple => "E-ProjTuple",
+            Rule::ProjRcd => "E-ProjRcd",
+            Rule::Proj => "E-Proj",
+            Rule::Rcd => "E-Rcd",
+            Rule::Inl => "E-Inl",
+            Rule::Inr => "E-Inr",
+            Rule::CaseInl => "E-CaseInl",
+            Rule::CaseInr => "E-CaseInr",
+            Rule::Case => "E-Case",
+            Rule::Variant => "E-Variant",
+            Rule::CaseVariant => "E-CaseVariant",
+            Rule::Cons1 => "E-Cons1",
+            Rule::Cons2 => "E-Cons2",
+            Rule::IsNilNil => "E-IsNilNil",
+            Rule::IsNilCons => "E-IsNilCons",
+            Rule::IsNil => "E-IsNil",
+            Rule::HeadCons => "E-HeadCons",
+            Rule::Head => "E-Head",
+            Rule::TailCons => "E-TailCons",
+            Rule::Tail => "E-Tail",
+            Rule::Global => "E-Global",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// One reduction step: the rules of its derivation from the outermost
+// congruence rule down to the computation rule that contracts the redex, and
+// the resulting term
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub rules: Vec<Rule>,
+    pub term: Term,
+}
+
+impl Step {
+    fn axiom(rule: Rule, term: Term) -> Result<Self, EvalError> {
+        Ok(Step {
+            rules: vec![rule],
+            term,
+        })
+    }
+
+    // a step of the subterm `term` by `rule`, `f` puts the result back into
+    // the enclosing term. Each congruence rule recurses through its own
+    // instance of this function, which keeps the stack frame of `eval1`
+    // small.
+    fn congruence(
+        term: &Term,
+        rule: Rule,
+        f: impl FnOnce(Term) -> Term,
+    ) -> Result<Self, EvalError> {
+        let mut step = eval1(term)?;
+        step.rules.insert(0, rule);
+        step.term = f(step.term);
+        Ok(step)
+    }
+}
+
+pub fn eval1(term: &Term) -> Result<Step, EvalError> {
+    match term {
+        // a name defined by an earlier statement unfolds to its definition
+        Term::TmGlobal(_, term) => Step::axiom(Rule::Global, Term::clone(term)),
+        Term::TmIf(if_term, then_term, else_term) => match if_term.as_ref() {
+            Term::TmTrue => Step::axiom(Rule::IfTrue, then_term.as_ref().clone()),
+            Term::TmFalse => Step::axiom(Rule::IfFalse, else_term.as_ref().clone()),
+            _ => Step::congruence(if_term, Rule::If, |if_term| {
+                Term::TmIf(Rc::new(if_term), then_term.clone(), else_term.clone())
+            }),
+        },
+        // a numeral is a value however many `succ` it has, finding that out
+        // does not recurse down the chain
+        Term::TmSucc(_) if is_numeric_val(term) => Err(EvalError::NoRuleApplies),
+        Term::TmSucc(term) => {
+            Step::congruence(term, Rule::Succ, |term| Term::TmSucc(Rc::new(term)))
+        }
+        Term::TmApp(left, right) => match left.as_ref() {
+            Term::TmAbs(_, _, body) if is_val(right) => {
+                let mut body = body.as_ref().clone();
+                substitution(right.as_ref().clone(), &mut body);
+                Step::axiom(Rule::AppAbs, body)
+            }
+            Term::TmPrim(primitive) if is_val(right) => {
+                let result = primitive
+                    .apply(right)
+                    .ok_or_else(|| EvalError::PrimitiveFailed(primitive.name().to_string()))?;
+                Step::axiom(Rule::AppPrim, result)
+            }
+            _ if is_val(left) => Step::congruence(right, Rule::App2, |right| {
+                Term::TmApp(left.clone(), Rc::new(right))
+            }),
+            _ => Step::congruence(left, Rule::App1, |left| {
+                Term::TmApp(Rc::new(left), right.clone())
+            }),
+        },
+        Term::TmTuple(terms) => match terms.iter().position(|term| !is_val(term)) {
+            // evaluate the fields from left to right
+            Some(i) => Step::congruence(&terms[i], Rule::Tuple, |term| {
+                let mut terms = terms.clone();
+                terms[i] = term;
+                Term::TmTuple(terms)
+            }),
+            None => Err(EvalError::NoRuleApplies),
+        },
+        Term::TmProj(tuple, index) => match tuple.as_ref() {
+            Term::TmTuple(terms) if is_val(tuple) => index
+                .checked_sub(1)
+                .and_then(|i| terms.get(i).cloned())
+                .ok_or(EvalError::NoRuleApplies)
+                .and_then(|term| Step::axiom(Rule::ProjTuple, term)),
+            _ => Step::congruence(tuple, Rule::Proj, |tuple| {
+                Term::TmProj(Rc::new(tuple), *index)
+            }),
+        },
+        Term::TmRecord(fields) => match fields.iter().position(|(_, term)| !is_val(term)) {
+            // evaluate the fields from left to right
+            Some(i) => Step::congruence(&fields[i].1, Rule::Rcd, |term| {
+                let mut fields = fields.clone();
+                fields[i].1 = term;
+                Term::TmRecord(fields)
+            }),
+            None => Err(EvalError::NoRuleApplies),
+        },
+        Term::TmRecordProj(record, label) => match record.as_ref() {
+            Term::TmRecord(fields) if is_val(record) => fields
+                .iter()
+                .find(|(field, _)| field == label)
+                .map(|(_, term)| term.clone())
+                .ok_or(EvalError::NoRuleApplies)
+                .and_then(|term| Step::axiom(Rule::ProjRcd, term)),
+            _ => Step::congruence(record, Rule::Proj, |record| {
+                Term::TmRecordProj(Rc::new(record), *label)
+            }),
+        },
+        Term::TmInl(term, typ) => Step::congruence(term, Rule::Inl, |term| {
+            Term::TmInl(Rc::new(term), typ.clone())
+        }),
+        Term::TmInr(term, typ) => Step::congruence(term, Rule::Inr, |term| {
+            Term::TmInr(Rc::new(term), typ.clone())
+        }),
+        Term::TmCase(term, inl_name, inl_term, inr_name, inr_term) => match term.as_ref() {
+            Term::TmInl(value, _) if is_val(value) => {
+                let mut inl_term = inl_term.as_ref().clone();
+                substitution(value.as_ref().clone(), &mut inl_term);
+                Step::axiom(Rule::CaseInl, inl_term)
+            }
+            Term::TmInr(value, _) if is_val(value) => {
+                let mut inr_term = inr_term.as_ref().clone();
+                substitution(value.as_ref().clone(), &mut inr_term);
+                Step::axiom(Rule::CaseInr, inr_term)
+            }
+            _ => Step::congruence(term, Rule::Case, |term| {
+                Term::TmCase(
+                    Rc::new(term),
+                    *inl_name,
+                    inl_term.clone(),
+                    *inr_name,
+                    inr_term.clone(),
+                )
+            }),
+        },
+        Term::TmTag(label, term, typ) => Step::congruence(term, Rule::Variant, |term| {
+            Term::TmTag(*label, Rc::new(term), typ.clone())
+        }),
+        Term::TmVariantCase(term, branches) => match term.as_ref() {
+            Term::TmTag(label, value, _) if is_val(value) => {
+                let mut branch = branches
+                    .iter()
+                    .find(|(other, _, _)| other == label)
+                    .map(|(_, _, branch)| branch.clone())
+                    .ok_or(EvalError::NoRuleApplies)?;
+                substitution(value.as_ref().clone(), &mut branch);
+                Step::axiom(Rule::CaseVariant, branch)
+            }
+            _ => Step::congruence(term, Rule::Case, |term| {
+                Term::TmVariantCase(Rc::new(term), branches.clone())
+            }),
+        },
+        Term::TmCons(typ, head, tail) => {
+            if is_val(head) {
+                Step::congruence(tail, Rule::Cons2, |tail| {
+                    Term::TmCons(typ.clone(), head.clone(), Rc::new(tail))
+                })
+            } else {
+                Step::congruence(head, Rule::Cons1, |head| {
+                    Term::TmCons(typ.clone(), Rc::new(head), tail.clone())
+                })
+            }
+        }
+        Term::TmIsNil(typ, term) => match term.as_ref() {
+            Term::TmNil(_) => Step::axiom(Rule::IsNilNil, Term::TmTrue),
+            Term::TmCons(_, _, _) if is_val(term) => Step::axiom(Rule::IsNilCons, Term::TmFalse),
+            _ => Step::congruence(term, Rule::IsNil, |term| {
+                Term::TmIsNil(typ.clone(), Rc::new(term))
+            }),
+        },
+        Term::TmHead(typ, term) => match term.as_ref() {
+            Term::TmCons(_, head, _) if is_val(term) => {
+                Step::axiom(Rule::HeadCons, head.as_ref().clone())
+            }
+            _ => Step::congruence(term, Rule::Head, |term| {
+                Term::TmHead(typ.clone(), Rc::new(term))
+            }),
+        },
+        Term::TmTail(typ, term) => match term.as_ref() {
+            Term::TmCons(_, _, tail) if is_val(term) => {
+                Step::axiom(Rule::TailCons, tail.as_ref().clone())
+            }
+            _ => Step::congruence(term, Rule::Tail, |term| {
+                Term::TmTail(typ.clone(), Rc::new(term))
+            }),
+        },
+        _ => Err(EvalError::NoRuleApplies),
+    }
+}
+
+// every step of the reduction of `term` to a value or a stuck term, and the
+// error that ended it early, if any
+pub fn eval_trace(term: &Term) -> (Vec<Step>, Option<EvalError>) {
+    let _span = tracing::debug_span!("eval").entered();
+    let mut trace: Vec<Step> = vec![];
+    loop {
+        match eval1(trace.last().map_or(term, |step| &step.term)) {
+            Ok(step) => {
+                tracing::trace!(term = %step.term, "step");
+                trace.push(step)
+            }
+            Err(EvalError::NoRuleApplies) => return (trace, None),
+            Err(error) => return (trace, Some(error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn rules(input: &str) -> Vec<String> {
+        let (trace, error) = eval_trace(&Parser::new().parse(input).unwrap());
+        assert_eq!(error, None);
+        trace
+            .iter()
+            .map(|step| {
+                let rules = step.rules.iter().map(Rule::to_string);
+                rules.collect::<Vec<_>>().join(" ")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_eval_trace() {
+        assert_eq!(
+            rules("(lambda x:Bool.if x then 0 else succ(0)) (if true then false else true);"),
+            ["E-App2 E-IfTrue", "E-AppAbs", "E-IfFalse"]
+        );
+        assert_eq!(
+            rules("{x={true, 0}.2, y=head[Nat] (cons[Nat] 0 nil[Nat])}.y;"),
+            [
+                "E-Proj E-Rcd E-ProjTuple",
+                "E-Proj E-Rcd E-HeadCons",
+                "E-ProjRcd"
+            ]
+        );
+        assert_eq!(
+            rules("case inl {true}.1 as Bool+Nat of inl b => b | inr n => false;"),
+            ["E-Case E-Inl E-ProjTuple", "E-CaseInl"]
+        );
+        assert_eq!(
+            rules("case <some={0, true}.2> as <none:Bool, some:Bool> of <none=x> => x | <some=y> => y;"),
+            ["E-Case E-Variant E-ProjTuple", "E-CaseVariant"]
+        );
+    }
+}
@@ -1,47 +1,329 @@
-use nom::{
-    branch::alt,
-    bytes::complete::{tag, tag_no_case},
-    error::context,
-    multi::many0,
-    sequence::tuple,
+use tapl_frontend::{
+    intern::Symbol,
+    lexer::{Lexer, SyntaxError, Token},
 };
 
-use crate::{parser::IResult, typing::Type};
+use crate::typing::Type;
 
-fn parse_boolean_type(input: &str) -> IResult<&str, Type> {
-    context("parse_boolean_type", tag("Bool"))(input)
-        .map(|(next_input, _res)| (next_input, Type::Boolean))
+// the names of the base types, of `List` and of the derived types, an alias
+// cannot take them
+pub const TYPE_KEYWORDS: [&str; 8] = [
+    "Bool", "Nat", "String", "Float", "List", "Unit", "Option", "Result",
+];
+
+// Derived types (TAPL 11.2 and 11.10), which the parser expands:
+//
+//   Unit       = the empty product, the type of `unit`
+//   Option T   = <none:Unit, some:T>
+//   Result T E = <ok:T, err:E>
+pub fn option_type(typ: Type) -> Type {
+    Type::Variant(vec![
+        (Symbol::intern("none"), Type::Product(vec![])),
+        (Symbol::intern("some"), typ),
+    ])
+}
+
+// a type alias is a capitalized word, lowercase words name variables
+fn is_type_name(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_uppercase())
 }
 
-fn parse_number_type(input: &str) -> IResult<&str, Type> {
-    context("parse_number_type", tag("Nat"))(input)
-        .map(|(next_input, _res)| (next_input, Type::Number))
+fn parse_field_type(lexer: &mut Lexer) -> Result<(Symbol, Type), SyntaxError> {
+    let (label, _) = lexer.expect_word("a label", &[])?;
+    lexer.expect_symbol(":")?;
+    Ok((Symbol::intern(label), parse_type(lexer)?))
 }
 
-fn parse_atom_type(input: &str) -> IResult<&str, Type> {
-    context(
-        "parse_atom_type",
-        alt((parse_boolean_type, parse_number_type)),
-    )(input)
-    .map(|(next_input, res)| (next_input, res))
+// `{l:T, ...}` or `<l:T, ...>` after the opening symbol
+fn parse_field_types(lexer: &mut Lexer, open: Token) -> Result<Type, SyntaxError> {
+    let mut fields = vec![parse_field_type(lexer)?];
+    while lexer.eat_symbol(",")?.is_some() {
+        fields.push(parse_field_type(lexer)?);
+    }
+    if open == Token::Symbol("{") {
+        lexer.expect_symbol("}")?;
+        Ok(Type::Record(fields))
+    } else {
+        lexer.expect_symbol(">")?;
+        Ok(Type::Variant(fields))
+    }
 }
 
-fn parse_arrow_type(input: &str) -> IResult<&str, Type> {
-    context(
-        "parse_arrow_type",
-        tuple((tag_no_case("->"), parse_atom_type)),
-    )(input)
-    .map(|(next_input, (_, res))| (next_input, res))
+// `List T`, `Option T` or `Result T E` after the keyword, or a base type
+fn parse_keyword_type(lexer: &mut Lexer, keyword: &str) -> Result<Type, SyntaxError> {
+    match keyword {
+        "Bool" => Ok(Type::Boolean),
+        "Nat" => Ok(Type::Number),
+        "String" => Ok(Type::String),
+        "Float" => Ok(Type::Float),
+        "List" => parse_atom_type(lexer).map(|typ| Type::List(Box::new(typ))),
+        "Unit" => Ok(Type::Product(vec![])),
+        "Option" => parse_atom_type(lexer).map(option_type),
+        _ => {
+            let ok = parse_atom_type(lexer)?;
+            Ok(Type::Variant(vec![
+                (Symbol::intern("ok"), ok),
+                (Symbol::intern("err"), parse_atom_type(lexer)?),
+            ]))
+        }
+    }
 }
 
-pub fn parse_type(input: &str) -> IResult<&str, Type> {
-    context(
-        "parse_type",
-        tuple((parse_atom_type, many0(parse_arrow_type))),
-    )(input)
-    .map(|(next_input, (typ, types))| {
-        let mut lhs = typ;
-        types.into_iter().map(|typ| lhs = typ);
-        (next_input, lhs)
+// `(T)` after the opening parenthesis
+fn parse_parenthesized_type(lexer: &mut Lexer) -> Result<Type, SyntaxError> {
+    let typ = parse_type(lexer)?;
+    lexer.expect_symbol(")")?;
+    Ok(typ)
+}
+
+// Nesting goes through this function, so the constructs that nest types are
+// parsed in functions of their own to keep its stack frame small
+fn parse_atom_type(lexer: &mut Lexer) -> Result<Type, SyntaxError> {
+    lexer.nested(|lexer| {
+        let (token, _) = lexer.peek()?;
+        let keyword = TYPE_KEYWORDS
+            .iter()
+            .find(|keyword| lexer.is_keyword(token, keyword));
+        if let Some(keyword) = keyword {
+            lexer.next_token()?;
+            return parse_keyword_type(lexer, keyword);
+        }
+        match token {
+            Token::Symbol("(") => {
+                lexer.next_token()?;
+                parse_parenthesized_type(lexer)
+            }
+            Token::Symbol("{") | Token::Symbol("<") => {
+                lexer.next_token()?;
+                parse_field_types(lexer, token)
+            }
+            Token::Word(word) if is_type_name(word) => lexer
+                .expect_word("a type", &TYPE_KEYWORDS)
+                .map(|(name, _)| Type::Named(Symbol::intern(name))),
+            _ => Err(lexer.unexpected("a type")),
+        }
     })
 }
+
+// left and right binding power of a type operator: `*` binds tighter than
+// `+` and `+` tighter than `->`, the latter two are right associative
+fn binding_power(token: Token) -> Option<(u8, u8)> {
+    match token {
+        Token::Symbol("->") => Some((1, 1)),
+        Token::Symbol("+") => Some((3, 3)),
+        Token::Symbol("*") => Some((5, 6)),
+        _ => None,
+    }
+}
+
+// the operator `token` applied to `left` and the operands after it
+fn parse_operator_type(
+    lexer: &mut Lexer,
+    token: Token,
+    left: Type,
+    right_power: u8,
+) -> Result<Type, SyntaxError> {
+    let right = parse_type_bp(lexer, right_power)?;
+    Ok(match token {
+        // `A*B*C` is one product of three components
+        Token::Symbol("*") => {
+            let mut types = vec![left, right];
+            while lexer.eat_symbol("*")?.is_some() {
+                types.push(parse_type_bp(lexer, right_power)?);
+            }
+            Type::Product(types)
+        }
+        Token::Symbol("+") => Type::Sum(Box::new(left), Box::new(right)),
+        _ => Type::Arrow(Box::new(left), Box::new(right)),
+    })
+}
+
+// Pratt parser over the type operators
+fn parse_type_bp(lexer: &mut Lexer, min_power: u8) -> Result<Type, SyntaxError> {
+    let mut typ = parse_atom_type(lexer)?;
+    loop {
+        let (token, _) = lexer.peek()?;
+        let right_power = match binding_power(token) {
+            Some((left_power, right_power)) if left_power >= min_power => right_power,
+            _ => return Ok(typ),
+        };
+        lexer.next_token()?;
+        // the right operand nests in the operator, `A->B->C` is as deep as
+        // `A->(B->C)`
+        typ = lexer.nested(|lexer| parse_operator_type(lexer, token, typ, right_power))?;
+    }
+}
+
+pub fn parse_type(lexer: &mut Lexer) -> Result<Type, SyntaxError> {
+    parse_type_bp(lexer, 0)
+}
+
+// the name of `type Name = T;`, after the keyword
+pub fn parse_type_name(lexer: &mut Lexer) -> Result<Symbol, SyntaxError> {
+    match lexer.peek()?.0 {
+        Token::Word(word) if is_type_name(word) => {
+            let (name, _) = lexer.expect_word("a type name", &TYPE_KEYWORDS)?;
+            Ok(Symbol::intern(name))
+        }
+        _ => Err(lexer.unexpected("a capitalized type name")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SYMBOLS;
+
+    fn parse(input: &str) -> Result<(&str, Type), String> {
+        let mut lexer = Lexer::new(input, &SYMBOLS);
+        parse_type(&mut lexer)
+            .map(|typ| (lexer.rest(), typ))
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn test_type() {
+        assert_eq!(parse("Bool"), Ok(("", Type::Boolean)));
+        assert_eq!(
+            parse("List String"),
+            Ok(("", Type::List(Box::new(Type::String))))
+        );
+        assert_eq!(
+            parse("Bool->Nat->Bool"),
+            Ok((
+                "",
+                Type::Arrow(
+                    Box::new(Type::Boolean),
+                    Box::new(Type::Arrow(Box::new(Type::Number), Box::new(Type::Boolean)))
+                )
+            ))
+        );
+        assert_eq!(
+            parse("(Bool->Nat)->Bool"),
+            Ok((
+                "",
+                Type::Arrow(
+                    Box::new(Type::Arrow(Box::new(Type::Boolean), Box::new(Type::Number))),
+                    Box::new(Type::Boolean)
+                )
+            ))
+        );
+        assert_eq!(
+            parse("Bool*Nat*Bool->Nat"),
+            Ok((
+                "",
+                Type::Arrow(
+                    Box::new(Type::Product(vec![
+                        Type::Boolean,
+                        Type::Number,
+                        Type::Boolean
+                    ])),
+                    Box::new(Type::Number)
+                )
+            ))
+        );
+        assert_eq!(
+            parse("(Bool*Nat)*Bool"),
+            Ok((
+                "",
+                Type::Product(vec![
+                    Type::Product(vec![Type::Boolean, Type::Number]),
+                    Type::Boolean
+                ])
+            ))
+        );
+        assert_eq!(
+            parse("{x:Bool, y:Nat->Nat}"),
+            Ok((
+                "",
+                Type::Record(vec![
+                    ("x".into(), Type::Boolean),
+                    (
+                        "y".into(),
+                        Type::Arrow(Box::new(Type::Number), Box::new(Type::Number))
+                    )
+                ])
+            ))
+        );
+        assert_eq!(
+            parse("Nat+Bool*Nat->List Nat"),
+            Ok((
+                "",
+                Type::Arrow(
+                    Box::new(Type::Sum(
+                        Box::new(Type::Number),
+                        Box::new(Type::Product(vec![Type::Boolean, Type::Number]))
+                    )),
+                    Box::new(Type::List(Box::new(Type::Number)))
+                )
+            ))
+        );
+        assert_eq!(
+            parse("<none:Bool, some:Nat->Nat>"),
+            Ok((
+                "",
+                Type::Variant(vec![
+                    ("none".into(), Type::Boolean),
+                    (
+                        "some".into(),
+                        Type::Arrow(Box::new(Type::Number), Box::new(Type::Number))
+                    )
+                ])
+            ))
+        );
+        assert_eq!(
+            parse("Option Nat->Result Nat String"),
+            Ok((
+                "",
+                Type::Arrow(
+                    Box::new(option_type(Type::Number)),
+                    Box::new(Type::Variant(vec![
+                        ("ok".into(), Type::Number),
+                        ("err".into(), Type::String)
+                    ]))
+                )
+            ))
+        );
+        assert_eq!(
+            parse("Option Unit"),
+            Ok(("", option_type(Type::Product(vec![]))))
+        );
+        // a capitalized word names a type alias
+        assert_eq!(
+            parse("Bool->Natural"),
+            Ok((
+                "",
+                Type::Arrow(
+                    Box::new(Type::Boolean),
+                    Box::new(Type::Named("Natural".into()))
+                )
+            ))
+        );
+        assert_eq!(
+            parse("Bool->natural"),
+            Err("1:7: expected a type, found `natural`".into())
+        );
+    }
+
+    #[test]
+    fn test_deep_arrow() {
+        // runs on a thread with the 2 MB stack of a test thread whatever
+        // RUST_MIN_STACK says, an arrow chain that is not counted overflows
+        // it after about a thousand arrows
+        let chain = |n: usize| format!("{}Nat", "Nat->".repeat(n));
+        let parse_chain = move |n| {
+            std::thread::Builder::new()
+                .stack_size(2048 * 1024)
+                .spawn(move || parse(&chain(n)).map(|(rest, _)| rest.len()))
+                .unwrap()
+                .join()
+                .unwrap()
+        };
+        assert_eq!(parse_chain(200), Ok(0));
+        for n in [300, 20_000] {
+            assert!(parse_chain(n)
+                .unwrap_err()
+                .contains("expected at most 256 nested terms"));
+        }
+    }
+}
@@ -1,18 +1,923 @@
 use std::fmt::{self, Formatter};
 
-#[derive(Debug)]
+use misc::Notation;
+use tapl_frontend::intern::Symbol;
+
+use reconstruction::unify::Unifiable;
+
+use crate::{
+    context::Context,
+    eval::is_numeric_val,
+    parser::Term,
+    reconstruct::{has_type_vars, reconstruct},
+};
+
+// Mismatches carry the expected type first and the type that was found second
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub enum TypeError {
-    ParameterTypeMismatch,
+    ParameterTypeMismatch(Type, Type),
+    // the type of the term in function position
+    ArrowTypeExpected(Type),
+    SuccArgumentNotNumber(Type),
+    IfConditionNotBoolean(Type),
+    // an operand of `and`, `or` or `not`, which elaborate to an `if`
+    OperatorArgumentNotBoolean(Symbol, Type),
+    // then and else types
+    IfBranchesMismatch(Type, Type),
+    UnboundVariable(usize),
+    ProductTypeExpected(Type),
+    TupleIndexOutOfRange(usize),
+    RecordTypeExpected(Type),
+    LabelNotFound(Symbol),
+    // a label given twice in a record, a record type or a variant type
+    DuplicateLabel(Symbol),
+    // the scrutinee or the injection annotation
+    SumTypeExpected(Type),
+    InjectionTypeMismatch(Type, Type),
+    // the types of the first branch and of a later one
+    CaseBranchesMismatch(Type, Type),
+    // the scrutinee or the tag annotation
+    VariantTypeExpected(Type),
+    // the labels of the variant type a case has no branch for, with their types
+    MissingBranches(Vec<(Symbol, Type)>),
+    // a branch for a label the variant type lacks or an earlier branch handles
+    UnreachableBranch(Symbol),
+    ListTypeMismatch(Type, Type),
+    // a parameter whose type the rest of the term does not determine
+    AnnotationRequired(Symbol),
 }
 
 impl fmt::Display for TypeError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        Ok(())
+        match self {
+            TypeError::ParameterTypeMismatch(expected, found) => write!(
+                f,
+                "parameter type mismatch: expected {}, found {}",
+                expected, found
+            ),
+            TypeError::ArrowTypeExpected(found) => {
+                write!(f, "arrow type expected, found {}", found)
+            }
+            TypeError::SuccArgumentNotNumber(found) => {
+                write!(f, "argument of succ is not a number: found {}", found)
+            }
+            TypeError::IfConditionNotBoolean(found) => {
+                write!(f, "guard of conditional not a boolean: found {}", found)
+            }
+            TypeError::OperatorArgumentNotBoolean(name, found) => {
+                write!(f, "argument of {} is not a boolean: found {}", name, found)
+            }
+            TypeError::IfBranchesMismatch(then_type, else_type) => write!(
+                f,
+                "arms of conditional have different types {} and {}",
+                then_type, else_type
+            ),
+            TypeError::UnboundVariable(index) => write!(f, "unbound variable index {}", index),
+            TypeError::ProductTypeExpected(found) => {
+                write!(f, "product type expected, found {}", found)
+            }
+            TypeError::TupleIndexOutOfRange(index) => {
+                write!(f, "tuple index {} out of range", index)
+            }
+            TypeError::RecordTypeExpected(found) => {
+                write!(f, "record type expected, found {}", found)
+            }
+            TypeError::LabelNotFound(label) => write!(f, "label {} not found", label),
+            TypeError::DuplicateLabel(label) => write!(f, "duplicate label {}", label),
+            TypeError::SumTypeExpected(found) => write!(f, "sum type expected, found {}", found),
+            TypeError::InjectionTypeMismatch(expected, found) => write!(
+                f,
+                "injected term does not match the annotated type: expected {}, found {}",
+                expected, found
+            ),
+            TypeError::CaseBranchesMismatch(first, other) => write!(
+                f,
+                "branches of case have different types {} and {}",
+                first, other
+            ),
+            TypeError::VariantTypeExpected(found) => {
+                write!(f, "variant type expected, found {}", found)
+            }
+            TypeError::MissingBranches(fields) => {
+                let labels = fields.iter().map(|(label, _)| label.as_str());
+                write!(
+                    f,
+                    "case has no branch for {}",
+                    labels.collect::<Vec<_>>().join(", ")
+                )
+            }
+            TypeError::UnreachableBranch(label) => {
+                write!(f, "branch for label {} is unreachable", label)
+            }
+            TypeError::ListTypeMismatch(expected, found) => write!(
+                f,
+                "list does not match the annotated element type: expected {}, found {}",
+                expected, found
+            ),
+            TypeError::AnnotationRequired(name) => write!(
+                f,
+                "annotation required: the type of {} cannot be inferred, write lambda {}:T",
+                name, name
+            ),
+        }
     }
 }
 
+impl std::error::Error for TypeError {}
+
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Type {
     Boolean,
     Number,
+    String,
+    Float,
+    // parameter type and return type
+    Arrow(Box<Type>, Box<Type>),
+    // component types of a tuple
+    Product(Vec<Type>),
+    // labeled field types in declaration order
+    Record(Vec<(Symbol, Type)>),
+    // left and right summand
+    Sum(Box<Type>, Box<Type>),
+    // labeled alternatives in declaration order (TAPL 11.10)
+    Variant(Vec<(Symbol, Type)>),
+    // element type
+    List(Box<Type>),
+    // a type alias as written, the parser expands it before typechecking
+    Named(Symbol),
+    // the type of a parameter written without one, to be solved by
+    // unification before typechecking (TAPL 22.3)
+    Var(usize),
+}
+
+impl Type {
+    pub fn display(&self, notation: Notation) -> TypeDisplay<'_> {
+        TypeDisplay {
+            typ: self,
+            notation,
+        }
+    }
+}
+
+impl Unifiable for Type {
+    fn var(&self) -> Option<usize> {
+        match self {
+            Type::Var(var) => Some(*var),
+            _ => None,
+        }
+    }
+
+    // records and variants agree on their labels in order, as they have to
+    // for `type_of` to find them equal
+    fn decompose(&self, other: &Self) -> Option<Vec<(Self, Self)>> {
+        let pair = |s: &Type, t: &Type| (s.clone(), t.clone());
+        let fields = |xs: &[(Symbol, Type)], ys: &[(Symbol, Type)]| {
+            let same_labels =
+                xs.len() == ys.len() && xs.iter().zip(ys).all(|((x, _), (y, _))| x == y);
+            same_labels.then(|| {
+                xs.iter()
+                    .zip(ys)
+                    .map(|((_, s), (_, t))| pair(s, t))
+                    .collect()
+            })
+        };
+        match (self, other) {
+            (Type::Boolean, Type::Boolean)
+            | (Type::Number, Type::Number)
+            | (Type::String, Type::String)
+            | (Type::Float, Type::Float) => Some(vec![]),
+            (Type::Named(x), Type::Named(y)) if x == y => Some(vec![]),
+            (Type::Arrow(s1, s2), Type::Arrow(t1, t2)) | (Type::Sum(s1, s2), Type::Sum(t1, t2)) => {
+                Some(vec![pair(s1, t1), pair(s2, t2)])
+            }
+            (Type::List(s), Type::List(t)) => Some(vec![pair(s, t)]),
+            (Type::Product(xs), Type::Product(ys)) if xs.len() == ys.len() => {
+                Some(xs.iter().zip(ys).map(|(s, t)| pair(s, t)).collect())
+            }
+            (Type::Record(xs), Type::Record(ys)) | (Type::Variant(xs), Type::Variant(ys)) => {
+                fields(xs, ys)
+            }
+            _ => None,
+        }
+    }
+
+    fn components(&self) -> Vec<&Self> {
+        match self {
+            Type::Arrow(left, right) | Type::Sum(left, right) => vec![left, right],
+            Type::List(typ) => vec![typ],
+            Type::Product(types) => types.iter().collect(),
+            Type::Record(fields) | Type::Variant(fields) => {
+                fields.iter().map(|(_, typ)| typ).collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    fn map_components(&self, mut f: impl FnMut(&Self) -> Self) -> Self {
+        match self {
+            Type::Arrow(left, right) => {
+                let left = f(left);
+                Type::Arrow(Box::new(left), Box::new(f(right)))
+            }
+            Type::Sum(left, right) => {
+                let left = f(left);
+                Type::Sum(Box::new(left), Box::new(f(right)))
+            }
+            Type::List(typ) => Type::List(Box::new(f(typ))),
+            Type::Product(types) => Type::Product(types.iter().map(f).collect()),
+            Type::Record(fields) => {
+                Type::Record(fields.iter().map(|(label, typ)| (*label, f(typ))).collect())
+            }
+            Type::Variant(fields) => {
+                Type::Variant(fields.iter().map(|(label, typ)| (*label, f(typ))).collect())
+            }
+            _ => self.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.display(Notation::Ascii).fmt(f)
+    }
+}
+
+pub struct TypeDisplay<'a> {
+    typ: &'a Type,
+    notation: Notation,
+}
+
+impl fmt::Display for TypeDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_type(f, self.typ, self.notation, Precedence::Arrow)
+    }
+}
+
+// Binding strength of the context a type is printed in, parentheses are
+// only emitted when the type binds looser than its context
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+enum Precedence {
+    Arrow,
+    Sum,
+    Product,
+    Atom,
+}
+
+fn write_type(
+    f: &mut Formatter<'_>,
+    typ: &Type,
+    notation: Notation,
+    precedence: Precedence,
+) -> fmt::Result {
+    match typ {
+        Type::Boolean => write!(f, "Bool"),
+        Type::Number => write!(f, "Nat"),
+        Type::String => write!(f, "String"),
+        Type::Float => write!(f, "Float"),
+        Type::Named(name) => write!(f, "{}", name),
+        Type::Var(var) => write!(f, "X{}", var),
+        Type::List(typ) => {
+            write!(f, "List ")?;
+            write_type(f, typ, notation, Precedence::Atom)
+        }
+        Type::Arrow(param_type, return_type) => {
+            if precedence > Precedence::Arrow {
+                write!(f, "(")?;
+            }
+            // arrows associate to the right, so only the left side needs parentheses
+            write_type(f, param_type, notation, Precedence::Sum)?;
+            write!(f, "{}", notation.arrow())?;
+            write_type(f, return_type, notation, Precedence::Arrow)?;
+            if precedence > Precedence::Arrow {
+                write!(f, ")")?;
+            }
+            Ok(())
+        }
+        Type::Product(types) if types.is_empty() => write!(f, "Unit"),
+        Type::Product(types) => {
+            if precedence > Precedence::Product {
+                write!(f, "(")?;
+            }
+            for (i, typ) in types.iter().enumerate() {
+                if i > 0 {
+                    write!(f, "{}", notation.product())?;
+                }
+                write_type(f, typ, notation, Precedence::Atom)?;
+            }
+            if precedence > Precedence::Product {
+                write!(f, ")")?;
+            }
+            Ok(())
+        }
+        Type::Sum(left, right) => {
+            if precedence > Precedence::Sum {
+                write!(f, "(")?;
+            }
+            // sums associate to the right as well
+            write_type(f, left, notation, Precedence::Product)?;
+            write!(f, "+")?;
+            write_type(f, right, notation, Precedence::Sum)?;
+            if precedence > Precedence::Sum {
+                write!(f, ")")?;
+            }
+            Ok(())
+        }
+        Type::Record(fields) => {
+            write!(f, "{{")?;
+            for (i, (label, typ)) in fields.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}:", label)?;
+                write_type(f, typ, notation, Precedence::Arrow)?;
+            }
+            write!(f, "}}")
+        }
+        Type::Variant(fields) => {
+            write!(f, "<")?;
+            for (i, (label, typ)) in fields.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}:", label)?;
+                write_type(f, typ, notation, Precedence::Arrow)?;
+            }
+            write!(f, ">")
+        }
+    }
+}
+
+// the type of `term` in `ctx` (TAPL figures 9-1 and 11-*), in a `typecheck`
+// span. The parameter types `term` leaves out are reconstructed first.
+pub fn type_of(ctx: &mut Context, term: &Term) -> Result<Type, TypeError> {
+    let _span = tracing::debug_span!("typecheck").entered();
+    let typ = if has_type_vars(term) {
+        reconstruct(ctx, term).and_then(|term| type_in(ctx, &term))
+    } else {
+        type_in(ctx, term)
+    };
+    tracing::debug!(?typ, "checked");
+    typ
+}
+
+// Each construct that nests terms is typed in a function of its own, so each
+// level of nesting only takes the stack that construct needs
+pub(crate) fn type_in(ctx: &mut Context, term: &Term) -> Result<Type, TypeError> {
+    if let Some(typ) = annotation(term) {
+        check_labels(typ)?;
+    }
+    match term {
+        Term::TmTrue | Term::TmFalse => Ok(Type::Boolean),
+        Term::TmPrim(primitive) => Ok(primitive.typ().clone()),
+        // the definition is closed, it is typed on its own
+        Term::TmGlobal(_, term) => type_of(&mut Context::default(), term),
+        Term::TmZero | Term::TmNumber(_) => Ok(Type::Number),
+        Term::TmString(_) => Ok(Type::String),
+        Term::TmFloat(_) => Ok(Type::Float),
+        // a numeral may be written as a long chain of `succ`
+        Term::TmSucc(_) if is_numeric_val(term) => Ok(Type::Number),
+        Term::TmSucc(term) => match type_in(ctx, term.as_ref())? {
+            Type::Number => Ok(Type::Number),
+            found => Err(TypeError::SuccArgumentNotNumber(found)),
+        },
+        Term::TmVar(index) => ctx
+            .get_type(*index)
+            .ok_or(TypeError::UnboundVariable(*index)),
+        Term::TmAbs(name, typ, body) => {
+            ctx.push(*name, typ.clone());
+            let body_type = type_in(ctx, body.as_ref());
+            ctx.pop();
+            Ok(Type::Arrow(Box::new(typ.clone()), Box::new(body_type?)))
+        }
+        Term::TmApp(left, right) => type_app(ctx, left, right),
+        Term::TmIf(if_term, then_term, else_term) => type_if(ctx, if_term, then_term, else_term),
+        Term::TmTuple(terms) => terms
+            .iter()
+            .map(|term| type_in(ctx, term))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Type::Product),
+        Term::TmRecord(fields) => type_record(ctx, fields),
+        Term::TmProj(_, _) | Term::TmRecordProj(_, _) => type_projection(ctx, term),
+        Term::TmInl(term, typ) => type_injection(ctx, term, typ, |typ| match typ {
+            Type::Sum(left, _) => Some(left),
+            _ => None,
+        }),
+        Term::TmInr(term, typ) => type_injection(ctx, term, typ, |typ| match typ {
+            Type::Sum(_, right) => Some(right),
+            _ => None,
+        }),
+        Term::TmNil(typ) => Ok(Type::List(Box::new(typ.clone()))),
+        Term::TmCons(typ, head, tail) => type_cons(ctx, typ, head, tail),
+        Term::TmIsNil(typ, term) => check_list_type(ctx, typ, term).map(|_| Type::Boolean),
+        Term::TmHead(typ, term) => check_list_type(ctx, typ, term).map(|_| typ.clone()),
+        Term::TmTail(typ, term) => {
+            check_list_type(ctx, typ, term).map(|_| Type::List(Box::new(typ.clone())))
+        }
+        Term::TmCase(term, inl_name, inl_term, inr_name, inr_term) => {
+            type_case(ctx, term, (*inl_name, inl_term), (*inr_name, inr_term))
+        }
+        Term::TmTag(label, term, typ) => type_tag(ctx, *label, term, typ),
+        Term::TmVariantCase(term, branches) => match type_in(ctx, term.as_ref())? {
+            Type::Variant(fields) => type_variant_case(ctx, &fields, branches),
+            found => Err(TypeError::VariantTypeExpected(found)),
+        },
+    }
+}
+
+fn type_app(ctx: &mut Context, left: &Term, right: &Term) -> Result<Type, TypeError> {
+    // the argument goes first, a `let` whose bound term is ill-typed
+    // gives its binder a placeholder type and the error to report is
+    // the one in the bound term
+    let right_type = type_in(ctx, right)?;
+    let left_type = type_in(ctx, left)?;
+    match left_type {
+        Type::Arrow(param_type, return_type) => {
+            if *param_type == right_type {
+                Ok(*return_type)
+            } else {
+                Err(TypeError::ParameterTypeMismatch(*param_type, right_type))
+            }
+        }
+        found => Err(TypeError::ArrowTypeExpected(found)),
+    }
+}
+
+fn type_if(
+    ctx: &mut Context,
+    if_term: &Term,
+    then_term: &Term,
+    else_term: &Term,
+) -> Result<Type, TypeError> {
+    let if_type = type_in(ctx, if_term)?;
+    if if_type != Type::Boolean {
+        return Err(TypeError::IfConditionNotBoolean(if_type));
+    }
+    let then_type = type_in(ctx, then_term)?;
+    let else_type = type_in(ctx, else_term)?;
+    if then_type != else_type {
+        return Err(TypeError::IfBranchesMismatch(then_type, else_type));
+    }
+    Ok(then_type)
+}
+
+fn type_record(ctx: &mut Context, fields: &[(Symbol, Term)]) -> Result<Type, TypeError> {
+    distinct(fields.iter().map(|(label, _)| label))?;
+    let fields = fields
+        .iter()
+        .map(|(label, term)| Ok((*label, type_in(ctx, term)?)))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Type::Record(fields))
+}
+
+// `t.i` of a tuple or `t.l` of a record
+fn type_projection(ctx: &mut Context, term: &Term) -> Result<Type, TypeError> {
+    match term {
+        Term::TmProj(term, index) => match type_in(ctx, term.as_ref())? {
+            Type::Product(types) => index
+                .checked_sub(1)
+                .and_then(|i| types.get(i).cloned())
+                .ok_or(TypeError::TupleIndexOutOfRange(*index)),
+            found => Err(TypeError::ProductTypeExpected(found)),
+        },
+        Term::TmRecordProj(term, label) => match type_in(ctx, term.as_ref())? {
+            Type::Record(fields) => fields
+                .into_iter()
+                .find(|(field, _)| field == label)
+                .map(|(_, typ)| typ)
+                .ok_or(TypeError::LabelNotFound(*label)),
+            found => Err(TypeError::RecordTypeExpected(found)),
+        },
+        _ => unreachable!("not a projection"),
+    }
+}
+
+// `inl t as T` or `inr t as T`, `summand` picks the side of `T` that `t`
+// goes into
+fn type_injection(
+    ctx: &mut Context,
+    term: &Term,
+    typ: &Type,
+    summand: fn(&Type) -> Option<&Type>,
+) -> Result<Type, TypeError> {
+    let expected = summand(typ).ok_or_else(|| TypeError::SumTypeExpected(typ.clone()))?;
+    let found = type_in(ctx, term)?;
+    if found == *expected {
+        Ok(typ.clone())
+    } else {
+        Err(TypeError::InjectionTypeMismatch(expected.clone(), found))
+    }
+}
+
+fn type_cons(ctx: &mut Context, typ: &Type, head: &Term, tail: &Term) -> Result<Type, TypeError> {
+    let list_type = Type::List(Box::new(typ.clone()));
+    let head_type = type_in(ctx, head)?;
+    if head_type != *typ {
+        return Err(TypeError::ListTypeMismatch(typ.clone(), head_type));
+    }
+    let tail_type = type_in(ctx, tail)?;
+    if tail_type != list_type {
+        return Err(TypeError::ListTypeMismatch(list_type, tail_type));
+    }
+    Ok(list_type)
+}
+
+fn type_case(
+    ctx: &mut Context,
+    term: &Term,
+    (inl_name, inl_term): (Symbol, &Term),
+    (inr_name, inr_term): (Symbol, &Term),
+) -> Result<Type, TypeError> {
+    let (left, right) = match type_in(ctx, term)? {
+        Type::Sum(left, right) => (left, right),
+        found => return Err(TypeError::SumTypeExpected(found)),
+    };
+    ctx.push(inl_name, *left);
+    let inl_type = type_in(ctx, inl_term);
+    ctx.pop();
+    ctx.push(inr_name, *right);
+    let inr_type = type_in(ctx, inr_term);
+    ctx.pop();
+    let (inl_type, inr_type) = (inl_type?, inr_type?);
+    if inl_type == inr_type {
+        Ok(inl_type)
+    } else {
+        Err(TypeError::CaseBranchesMismatch(inl_type, inr_type))
+    }
+}
+
+fn type_tag(ctx: &mut Context, label: Symbol, term: &Term, typ: &Type) -> Result<Type, TypeError> {
+    let fields = match typ {
+        Type::Variant(fields) => fields,
+        _ => return Err(TypeError::VariantTypeExpected(typ.clone())),
+    };
+    let expected = fields
+        .iter()
+        .find(|(field, _)| *field == label)
+        .map(|(_, typ)| typ)
+        .ok_or(TypeError::LabelNotFound(label))?;
+    let found = type_in(ctx, term)?;
+    if found == *expected {
+        Ok(typ.clone())
+    } else {
+        Err(TypeError::InjectionTypeMismatch(expected.clone(), found))
+    }
+}
+
+// T-Case for variants: every label of the variant type has exactly one
+// branch, in any order, and the branches agree on their type
+fn type_variant_case(
+    ctx: &mut Context,
+    fields: &[(Symbol, Type)],
+    branches: &[(Symbol, Symbol, Term)],
+) -> Result<Type, TypeError> {
+    let mut binder_types = vec![];
+    for (i, (label, _, _)) in branches.iter().enumerate() {
+        let field = fields.iter().find(|(field, _)| field == label);
+        match field {
+            Some((_, typ)) if !branches[..i].iter().any(|(other, _, _)| other == label) => {
+                binder_types.push(typ.clone())
+            }
+            _ => return Err(TypeError::UnreachableBranch(*label)),
+        }
+    }
+    let missing = fields
+        .iter()
+        .filter(|(field, _)| !branches.iter().any(|(label, _, _)| label == field))
+        .cloned()
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        return Err(TypeError::MissingBranches(missing));
+    }
+    let mut result: Option<Type> = None;
+    for ((_, name, branch), typ) in branches.iter().zip(binder_types) {
+        ctx.push(*name, typ);
+        let branch_type = type_in(ctx, branch);
+        ctx.pop();
+        let branch_type = branch_type?;
+        match &result {
+            Some(first) if *first != branch_type => {
+                return Err(TypeError::CaseBranchesMismatch(first.clone(), branch_type))
+            }
+            _ => result = Some(branch_type),
+        }
+    }
+    // the syntax has no empty variant type `<>`, a case on one would have
+    // no branch to take its type from
+    result.ok_or(TypeError::VariantTypeExpected(Type::Variant(vec![])))
+}
+
+pub(crate) fn annotation(term: &Term) -> Option<&Type> {
+    match term {
+        Term::TmAbs(_, typ, _)
+        | Term::TmInl(_, typ)
+        | Term::TmInr(_, typ)
+        | Term::TmTag(_, _, typ)
+        | Term::TmNil(typ)
+        | Term::TmCons(typ, _, _)
+        | Term::TmIsNil(typ, _)
+        | Term::TmHead(typ, _)
+        | Term::TmTail(typ, _) => Some(typ),
+        _ => None,
+    }
+}
+
+fn distinct<'a>(labels: impl Iterator<Item = &'a Symbol>) -> Result<(), TypeError> {
+    let mut seen = vec![];
+    for label in labels {
+        if seen.contains(&label) {
+            return Err(TypeError::DuplicateLabel(*label));
+        }
+        seen.push(label);
+    }
+    Ok(())
+}
+
+// an annotation names every field of a record type and every alternative
+// of a variant type once (TAPL 11.8, 11.10)
+fn check_labels(typ: &Type) -> Result<(), TypeError> {
+    match typ {
+        Type::Boolean
+        | Type::Number
+        | Type::String
+        | Type::Float
+        | Type::Named(_)
+        | Type::Var(_) => Ok(()),
+        Type::Arrow(left, right) | Type::Sum(left, right) => {
+            check_labels(left)?;
+            check_labels(right)
+        }
+        Type::List(typ) => check_labels(typ),
+        Type::Product(types) => types.iter().try_for_each(check_labels),
+        Type::Record(fields) | Type::Variant(fields) => {
+            distinct(fields.iter().map(|(label, _)| label))?;
+            fields.iter().try_for_each(|(_, typ)| check_labels(typ))
+        }
+    }
+}
+
+fn check_list_type(ctx: &mut Context, typ: &Type, term: &Term) -> Result<(), TypeError> {
+    let list_type = Type::List(Box::new(typ.clone()));
+    let found = type_in(ctx, term)?;
+    if found == list_type {
+        Ok(())
+    } else {
+        Err(TypeError::ListTypeMismatch(list_type, found))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tapl_frontend::lexer::Lexer;
+
+    use crate::{
+        parser::{Parser, SYMBOLS},
+        type_parser::parse_type,
+    };
+
+    fn type_of_str(input: &str) -> Result<Type, TypeError> {
+        let term = Parser::new().parse(input).unwrap();
+        type_of(&mut Context::default(), &term)
+    }
+
+    #[test]
+    fn test_type_of() {
+        assert_eq!(type_of_str("true;"), Ok(Type::Boolean));
+        assert_eq!(
+            type_of_str("{a=0, a=true};"),
+            Err(TypeError::DuplicateLabel("a".into()))
+        );
+        assert_eq!(
+            type_of_str("lambda r:Nat->{a:Nat, b:Bool, a:Nat}. r;"),
+            Err(TypeError::DuplicateLabel("a".into()))
+        );
+        assert_eq!(
+            type_of_str("lambda x:Bool.x;"),
+            Ok(Type::Arrow(
+                Box::new(Type::Boolean),
+                Box::new(Type::Boolean)
+            ))
+        );
+        assert_eq!(type_of_str("(lambda x:Nat.x) 0;"), Ok(Type::Number));
+        assert_eq!(
+            type_of_str("(lambda x:Nat.x) true;"),
+            Err(TypeError::ParameterTypeMismatch(
+                Type::Number,
+                Type::Boolean
+            ))
+        );
+        assert_eq!(
+            type_of_str("{true, 0};"),
+            Ok(Type::Product(vec![Type::Boolean, Type::Number]))
+        );
+        assert_eq!(type_of_str("{true, succ(0)}.2;"), Ok(Type::Number));
+        assert_eq!(
+            type_of_str("{true, 0}.3;"),
+            Err(TypeError::TupleIndexOutOfRange(3))
+        );
+        assert_eq!(
+            type_of_str("true.1;"),
+            Err(TypeError::ProductTypeExpected(Type::Boolean))
+        );
+        assert_eq!(
+            type_of_str("lambda p:Bool*Nat.p.1;"),
+            Ok(Type::Arrow(
+                Box::new(Type::Product(vec![Type::Boolean, Type::Number])),
+                Box::new(Type::Boolean)
+            ))
+        );
+        assert_eq!(
+            type_of_str("{x=true, y=0};"),
+            Ok(Type::Record(vec![
+                ("x".into(), Type::Boolean),
+                ("y".into(), Type::Number)
+            ]))
+        );
+        assert_eq!(
+            type_of_str("(lambda r:{x:Bool, y:Nat}.r.y) {x=false, y=succ(0)};"),
+            Ok(Type::Number)
+        );
+        assert_eq!(
+            type_of_str("{x=true}.z;"),
+            Err(TypeError::LabelNotFound("z".into()))
+        );
+        assert_eq!(
+            type_of_str("{true}.x;"),
+            Err(TypeError::RecordTypeExpected(Type::Product(vec![
+                Type::Boolean
+            ])))
+        );
+        assert_eq!(
+            type_of_str("inl 0 as Nat+Bool;"),
+            Ok(Type::Sum(Box::new(Type::Number), Box::new(Type::Boolean)))
+        );
+        assert_eq!(
+            type_of_str("inr 0 as Nat+Bool;"),
+            Err(TypeError::InjectionTypeMismatch(
+                Type::Boolean,
+                Type::Number
+            ))
+        );
+        assert_eq!(
+            type_of_str("inl 0 as Nat;"),
+            Err(TypeError::SumTypeExpected(Type::Number))
+        );
+        assert_eq!(
+            type_of_str("case inr true as Nat+Bool of inl n => succ(n) | inr b => 0;"),
+            Ok(Type::Number)
+        );
+        assert_eq!(
+            type_of_str("case inl 0 as Nat+Bool of inl n => n | inr b => b;"),
+            Err(TypeError::CaseBranchesMismatch(Type::Number, Type::Boolean))
+        );
+        assert_eq!(
+            type_of_str("case true of inl n => n | inr b => b;"),
+            Err(TypeError::SumTypeExpected(Type::Boolean))
+        );
+        let nat_list = Type::List(Box::new(Type::Number));
+        assert_eq!(type_of_str("nil[Nat];"), Ok(nat_list.clone()));
+        assert_eq!(
+            type_of_str("cons[Nat] 0 (cons[Nat] succ(0) nil[Nat]);"),
+            Ok(nat_list.clone())
+        );
+        assert_eq!(
+            type_of_str("cons[Nat] true nil[Nat];"),
+            Err(TypeError::ListTypeMismatch(Type::Number, Type::Boolean))
+        );
+        assert_eq!(
+            type_of_str("cons[Nat] 0 nil[Bool];"),
+            Err(TypeError::ListTypeMismatch(
+                nat_list.clone(),
+                Type::List(Box::new(Type::Boolean))
+            ))
+        );
+        assert_eq!(type_of_str("isnil[Nat] nil[Nat];"), Ok(Type::Boolean));
+        assert_eq!(type_of_str("head[Nat] nil[Nat];"), Ok(Type::Number));
+        assert_eq!(type_of_str("tail[Nat] nil[Nat];"), Ok(nat_list.clone()));
+        assert_eq!(
+            type_of_str("head[Bool] nil[Nat];"),
+            Err(TypeError::ListTypeMismatch(
+                Type::List(Box::new(Type::Boolean)),
+                nat_list
+            ))
+        );
+    }
+
+    #[test]
+    fn test_variant_case() {
+        let option = "<none:Bool, some:Nat>";
+        let case =
+            |branches: &str| type_of_str(&format!("lambda o:{}.case o of {};", option, branches));
+        let typ = |input: &str| parse_type(&mut Lexer::new(input, &SYMBOLS)).unwrap();
+        assert_eq!(
+            type_of_str(&format!("<some=0> as {};", option)),
+            Ok(typ(option))
+        );
+        assert_eq!(
+            type_of_str("<a=0> as <a:Nat, a:Bool>;"),
+            Err(TypeError::DuplicateLabel("a".into()))
+        );
+        assert_eq!(
+            type_of_str("lambda v:<a:Nat, b:<c:Bool, c:Nat>>. v;"),
+            Err(TypeError::DuplicateLabel("c".into()))
+        );
+        // the branches may come in any order
+        assert_eq!(
+            case("<some=n> => succ(n) | <none=b> => 0"),
+            Ok(typ(&format!("{}->Nat", option)))
+        );
+        assert_eq!(
+            case("<some=n> => n"),
+            Err(TypeError::MissingBranches(vec![(
+                "none".into(),
+                Type::Boolean
+            )]))
+        );
+        assert_eq!(
+            case("<none=b> => 0 | <some=n> => n | <other=x> => 0"),
+            Err(TypeError::UnreachableBranch("other".into()))
+        );
+        assert_eq!(
+            case("<none=b> => 0 | <some=n> => n | <none=c> => 0"),
+            Err(TypeError::UnreachableBranch("none".into()))
+        );
+        assert_eq!(
+            case("<none=b> => b | <some=n> => n"),
+            Err(TypeError::CaseBranchesMismatch(Type::Boolean, Type::Number))
+        );
+        assert_eq!(
+            type_of_str(&format!("<any=0> as {};", option)),
+            Err(TypeError::LabelNotFound("any".into()))
+        );
+        assert_eq!(
+            type_of_str(&format!("<some=true> as {};", option)),
+            Err(TypeError::InjectionTypeMismatch(
+                Type::Number,
+                Type::Boolean
+            ))
+        );
+        assert_eq!(
+            type_of_str("case 0 of <some=n> => n;"),
+            Err(TypeError::VariantTypeExpected(Type::Number))
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let display = |input: &str, notation: Notation| {
+            let typ = parse_type(&mut Lexer::new(input, &SYMBOLS)).unwrap();
+            typ.display(notation).to_string()
+        };
+
+        assert_eq!(
+            display("Bool->Nat->Bool", Notation::Ascii),
+            "Bool->Nat->Bool"
+        );
+        assert_eq!(
+            display("(Bool->Nat)->Bool", Notation::Ascii),
+            "(Bool->Nat)->Bool"
+        );
+        assert_eq!(
+            display("(Bool->Nat)->Bool", Notation::Unicode),
+            "(Bool→Nat)→Bool"
+        );
+        assert_eq!(
+            display("(Bool*Nat)->Bool", Notation::Ascii),
+            "Bool*Nat->Bool"
+        );
+        assert_eq!(
+            display("(Bool*Nat)*Bool", Notation::Unicode),
+            "(Bool×Nat)×Bool"
+        );
+        assert_eq!(
+            display("Bool*(Nat->Nat)", Notation::Ascii),
+            "Bool*(Nat->Nat)"
+        );
+        assert_eq!(
+            display("{x:Bool, f:Nat->(Nat->Nat)}", Notation::Unicode),
+            "{x:Bool, f:Nat→Nat→Nat}"
+        );
+        assert_eq!(display("Nat+Bool+Nat", Notation::Ascii), "Nat+Bool+Nat");
+        assert_eq!(display("(Nat+Bool)+Nat", Notation::Ascii), "(Nat+Bool)+Nat");
+        assert_eq!(
+            display("Nat*Nat+Bool->Nat", Notation::Ascii),
+            "Nat*Nat+Bool->Nat"
+        );
+        assert_eq!(display("Nat*(Nat+Bool)", Notation::Ascii), "Nat*(Nat+Bool)");
+        assert_eq!(display("List Nat*Bool", Notation::Ascii), "List Nat*Bool");
+        assert_eq!(
+            display("List (Nat->Bool)", Notation::Unicode),
+            "List (Nat→Bool)"
+        );
+        assert_eq!(display("List List Nat", Notation::Ascii), "List List Nat");
+        assert_eq!(
+            display("<none:Bool, some:Nat->Nat>", Notation::Unicode),
+            "<none:Bool, some:Nat→Nat>"
+        );
+        assert_eq!(
+            Type::Arrow(Box::new(Type::Boolean), Box::new(Type::Number)).to_string(),
+            "Bool->Nat"
+        );
+    }
 }
@@ -0,0 +1,407 @@
+use std::{
+    fmt::{self, Formatter},
+    rc::Rc,
+};
+
+use tapl_frontend::intern::Symbol;
+
+use crate::{
+    bytecode::{Const, Instr, Program},
+    eval::EvalError,
+    parser::Term,
+    primitive::Primitive,
+    typing::Type,
+};
+
+// A stack machine for the bytecode of `bytecode`. Calls push a frame on a
+// stack of their own instead of recursing in Rust, so how deep calls nest
+// while a program runs is only bounded by memory. A compiled program can be run any number of
+// times, without the term being looked at again.
+
+#[derive(Clone, Debug)]
+pub struct Closure {
+    chunk: usize,
+    env: Env,
+}
+
+#[derive(Clone, Debug)]
+pub enum Value {
+    Boolean(bool),
+    Number(u64),
+    String(String),
+    Float(f64),
+    Tuple(Vec<Value>),
+    Record(Vec<(Symbol, Value)>),
+    Inl(Box<Value>, Type),
+    Inr(Box<Value>, Type),
+    Tag(Symbol, Box<Value>, Type),
+    Nil(Type),
+    Cons(Type, Box<Value>, Box<Value>),
+    Closure(Closure),
+    Prim(Primitive),
+}
+
+// Persistent list of values indexed by de Bruijn index, so that closures can
+// share the tail they were created with
+#[derive(Clone, Debug, Default)]
+pub struct Env(Option<Rc<(Value, Env)>>);
+
+impl Env {
+    pub fn bind(&self, value: Value) -> Self {
+        Env(Some(Rc::new((value, self.clone()))))
+    }
+
+    pub fn lookup(&self, index: usize) -> Option<&Value> {
+        let mut env = self;
+        let mut index = index;
+        while let Some(node) = &env.0 {
+            if index == 0 {
+                return Some(&node.0);
+            }
+            index -= 1;
+            env = &node.1;
+        }
+        None
+    }
+
+    fn unbind(&self) -> Option<Env> {
+        self.0.as_ref().map(|node| node.1.clone())
+    }
+}
+
+// where to continue once the called closure returns
+struct Frame {
+    chunk: usize,
+    pc: usize,
+    env: Env,
+}
+
+#[derive(Default)]
+struct Machine {
+    stack: Vec<Value>,
+    frames: Vec<Frame>,
+}
+
+impl Machine {
+    fn pop(&mut self) -> Result<Value, EvalError> {
+        self.stack.pop().ok_or(EvalError::NoRuleApplies)
+    }
+
+    fn pop_many(&mut self, len: usize) -> Result<Vec<Value>, EvalError> {
+        let start = self
+            .stack
+            .len()
+            .checked_sub(len)
+            .ok_or(EvalError::NoRuleApplies)?;
+        Ok(self.stack.split_off(start))
+    }
+}
+
+// Runs a program compiled from a well-typed term. A term the substitution
+// evaluator gets stuck on, like `head[T] nil[T]`, is `NoRuleApplies` here.
+pub fn run(program: &Program) -> Result<Value, EvalError> {
+    let mut machine = Machine::default();
+    let (mut chunk, mut pc, mut env) = (0, 0, Env::default());
+    loop {
+        let instr = program.chunks[chunk]
+            .get(pc)
+            .ok_or(EvalError::NoRuleApplies)?;
+        pc += 1;
+        let value = match instr {
+            Instr::Const(value) => Value::from_const(value),
+            Instr::Access(index) => env
+                .lookup(*index)
+                .cloned()
+                .ok_or(EvalError::NoRuleApplies)?,
+            Instr::Closure(body) => Value::Closure(Closure {
+                chunk: *body,
+                env: env.clone(),
+            }),
+            Instr::Apply => {
+                let argument = machine.pop()?;
+                match machine.pop()? {
+                    Value::Closure(closure) => {
+                        machine.frames.push(Frame { chunk, pc, env });
+                        chunk = closure.chunk;
+                        pc = 0;
+                        env = closure.env.bind(argument);
+                        continue;
+                    }
+                    Value::Prim(primitive) => apply_prim(&primitive, &argument)?,
+                    _ => return Err(EvalError::NoRuleApplies),
+                }
+            }
+            Instr::Return => match machine.frames.pop() {
+                Some(frame) => {
+                    chunk = frame.chunk;
+                    pc = frame.pc;
+                    env = frame.env;
+                    continue;
+                }
+                None => return machine.pop(),
+            },
+            Instr::JumpIfFalse(target) => {
+                match machine.pop()? {
+                    Value::Boolean(true) => {}
+                    Value::Boolean(false) => pc = *target,
+                    _ => return Err(EvalError::NoRuleApplies),
+                }
+                continue;
+            }
+            Instr::Jump(target) => {
+                pc = *target;
+                continue;
+            }
+            Instr::Succ => match machine.pop()? {
+                Value::Number(n) => {
+                    Value::Number(n.checked_add(1).ok_or(EvalError::NoRuleApplies)?)
+                }
+                _ => return Err(EvalError::NoRuleApplies),
+            },
+            Instr::Tuple(len) => Value::Tuple(machine.pop_many(*len)?),
+            Instr::Proj(index) => match machine.pop()? {
+                Value::Tuple(mut values) if (1..=values.len()).contains(index) => {
+                    values.swap_remove(index - 1)
+                }
+                _ => return Err(EvalError::NoRuleApplies),
+            },
+            Instr::Record(labels) => {
+                let values = machine.pop_many(labels.len())?;
+                Value::Record(labels.iter().copied().zip(values).collect())
+            }
+            Instr::RecordProj(label) => match machine.pop()? {
+                Value::Record(fields) => fields
+                    .into_iter()
+                    .find(|(field, _)| field == label)
+                    .map(|(_, value)| value)
+                    .ok_or(EvalError::NoRuleApplies)?,
+                _ => return Err(EvalError::NoRuleApplies),
+            },
+            Instr::Inl(typ) => Value::Inl(Box::new(machine.pop()?), typ.clone()),
+            Instr::Inr(typ) => Value::Inr(Box::new(machine.pop()?), typ.clone()),
+            Instr::CaseSum(target) => {
+                match machine.pop()? {
+                    Value::Inl(value, _) => env = env.bind(*value),
+                    Value::Inr(value, _) => {
+                        env = env.bind(*value);
+                        pc = *target;
+                    }
+                    _ => return Err(EvalError::NoRuleApplies),
+                }
+                continue;
+            }
+            Instr::Tag(label, typ) => Value::Tag(*label, Box::new(machine.pop()?), typ.clone()),
+            Instr::CaseTag(targets) => match machine.pop()? {
+                Value::Tag(label, value, _) => {
+                    pc = targets
+                        .iter()
+                        .find(|(other, _)| *other == label)
+                        .map(|(_, target)| *target)
+                        .ok_or(EvalError::NoRuleApplies)?;
+                    env = env.bind(*value);
+                    continue;
+                }
+                _ => return Err(EvalError::NoRuleApplies),
+            },
+            Instr::Unbind => {
+                env = env.unbind().ok_or(EvalError::NoRuleApplies)?;
+                continue;
+            }
+            Instr::Cons(typ) => {
+                let tail = machine.pop()?;
+                let head = machine.pop()?;
+                Value::Cons(typ.clone(), Box::new(head), Box::new(tail))
+            }
+            Instr::IsNil => match machine.pop()? {
+                Value::Nil(_) => Value::Boolean(true),
+                Value::Cons(_, _, _) => Value::Boolean(false),
+                _ => return Err(EvalError::NoRuleApplies),
+            },
+            Instr::Head => match machine.pop()? {
+                Value::Cons(_, head, _) => *head,
+                _ => return Err(EvalError::NoRuleApplies),
+            },
+            Instr::Tail => match machine.pop()? {
+                Value::Cons(_, _, tail) => *tail,
+                _ => return Err(EvalError::NoRuleApplies),
+            },
+        };
+        machine.stack.push(value);
+    }
+}
+
+// compiles and runs a closed term
+pub fn eval(term: &Term) -> Result<Value, EvalError> {
+    run(&Program::compile(term))
+}
+
+// A primitive is called on the term of its argument, so it can only be
+// passed first-order values
+fn apply_prim(primitive: &Primitive, argument: &Value) -> Result<Value, EvalError> {
+    argument
+        .to_term()
+        .and_then(|argument| primitive.apply(&argument))
+        .and_then(|result| Value::from_term(&result))
+        .ok_or_else(|| EvalError::PrimitiveFailed(primitive.name().to_string()))
+}
+
+impl Value {
+    fn from_const(value: &Const) -> Self {
+        match value {
+            Const::Boolean(value) => Value::Boolean(*value),
+            Const::Number(n) => Value::Number(*n),
+            Const::String(text) => Value::String(text.clone()),
+            Const::Float(value) => Value::Float(*value),
+            Const::Nil(typ) => Value::Nil(typ.clone()),
+            Const::Prim(primitive) => Value::Prim(primitive.clone()),
+        }
+    }
+
+    // a first-order value term, as returned by a primitive
+    fn from_term(term: &Term) -> Option<Self> {
+        Some(match term {
+            Term::TmTrue => Value::Boolean(true),
+            Term::TmFalse => Value::Boolean(false),
+            Term::TmZero => Value::Number(0),
+            Term::TmNumber(n) => Value::Number(*n),
+            Term::TmSucc(term) => match Value::from_term(term)? {
+                Value::Number(n) => Value::Number(n.checked_add(1)?),
+                _ => return None,
+            },
+            Term::TmString(text) => Value::String(text.clone()),
+            Term::TmFloat(value) => Value::Float(*value),
+            Term::TmTuple(terms) => {
+                Value::Tuple(terms.iter().map(Value::from_term).collect::<Option<_>>()?)
+            }
+            Term::TmRecord(fields) => Value::Record(
+                fields
+                    .iter()
+                    .map(|(label, term)| Some((*label, Value::from_term(term)?)))
+                    .collect::<Option<_>>()?,
+            ),
+            Term::TmInl(term, typ) => Value::Inl(Box::new(Value::from_term(term)?), typ.clone()),
+            Term::TmInr(term, typ) => Value::Inr(Box::new(Value::from_term(term)?), typ.clone()),
+            Term::TmTag(label, term, typ) => {
+                Value::Tag(*label, Box::new(Value::from_term(term)?), typ.clone())
+            }
+            Term::TmNil(typ) => Value::Nil(typ.clone()),
+            Term::TmCons(typ, head, tail) => Value::Cons(
+                typ.clone(),
+                Box::new(Value::from_term(head)?),
+                Box::new(Value::from_term(tail)?),
+            ),
+            Term::TmPrim(primitive) => Value::Prim(primitive.clone()),
+            _ => return None,
+        })
+    }
+
+    // reads the value back into a term, `None` if it contains a closure,
+    // whose body is bytecode now
+    pub fn to_term(&self) -> Option<Term> {
+        let boxed = |value: &Value| value.to_term().map(Rc::new);
+        Some(match self {
+            Value::Boolean(true) => Term::TmTrue,
+            Value::Boolean(false) => Term::TmFalse,
+            Value::Number(n) => Term::number(*n),
+            Value::String(text) => Term::TmString(text.clone()),
+            Value::Float(value) => Term::TmFloat(*value),
+            Value::Tuple(values) => {
+                Term::TmTuple(values.iter().map(Value::to_term).collect::<Option<_>>()?)
+            }
+            Value::Record(fields) => Term::TmRecord(
+                fields
+                    .iter()
+                    .map(|(label, value)| Some((*label, value.to_term()?)))
+                    .collect::<Option<_>>()?,
+            ),
+            Value::Inl(value, typ) => Term::TmInl(boxed(value)?, typ.clone()),
+            Value::Inr(value, typ) => Term::TmInr(boxed(value)?, typ.clone()),
+            Value::Tag(label, value, typ) => Term::TmTag(*label, boxed(value)?, typ.clone()),
+            Value::Nil(typ) => Term::TmNil(typ.clone()),
+            Value::Cons(typ, head, tail) => Term::TmCons(typ.clone(), boxed(head)?, boxed(tail)?),
+            Value::Closure(_) => return None,
+            Value::Prim(primitive) => Term::TmPrim(primitive.clone()),
+        })
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.to_term() {
+            Some(term) => write!(f, "{}", term),
+            None => write!(f, "<closure>"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{eval, parser::Parser};
+
+    fn parse(input: &str) -> Term {
+        Parser::new().parse(input).unwrap()
+    }
+
+    fn run_str(input: &str) -> Result<Option<Term>, EvalError> {
+        super::eval(&parse(input)).map(|value| value.to_term())
+    }
+
+    #[test]
+    fn test_run() {
+        // the program is compiled once and can be run again
+        let program = Program::compile(&parse(
+            "(lambda f:Nat->Nat.lambda x:Nat.f (f x)) (lambda n:Nat.succ(n)) 0;",
+        ));
+        for _ in 0..2 {
+            assert_eq!(
+                run(&program).map(|value| value.to_term()),
+                Ok(Some(parse("2;")))
+            );
+        }
+        assert_eq!(
+            run_str("{0, lambda x:Nat.x};").map(|term| term.is_none()),
+            Ok(true)
+        );
+        assert_eq!(
+            run_str("head[Nat] nil[Nat];"),
+            Err(EvalError::NoRuleApplies)
+        );
+        // twice applied to itself twice applies succ sixteen times
+        assert_eq!(
+            run_str(
+                "(lambda t:(Nat->Nat)->Nat->Nat.t t t (lambda n:Nat.succ(n)) 0) \
+                 (lambda f:Nat->Nat.lambda x:Nat.f (f x));"
+            ),
+            Ok(Some(parse("16;")))
+        );
+    }
+
+    // the machine must agree with the substitution evaluator
+    #[test]
+    fn test_agrees_with_substitution() {
+        for input in [
+            "if false then true else false;",
+            "{if true then false else true, succ(0)};",
+            "(lambda p:Bool*Nat.p.2) {false, succ(0)};",
+            "(lambda r:{a:Nat, b:Bool}.r.b) {a=0, b=true};",
+            "case inl succ(0) as Nat+Bool of inl n => {n, n} | inr b => {0, 0};",
+            "(lambda x:Nat.case inr true as Nat+Bool of inl n => n | inr b => x) succ(0);",
+            "case <b=true> as <a:Nat, b:Bool> of <a=n> => n | <b=x> => if x then succ(0) else 0;",
+            "head[Nat] (tail[Nat] (cons[Nat] 0 (cons[Nat] succ(0) nil[Nat])));",
+            "isnil[Nat] (cons[Nat] 0 nil[Nat]);",
+            "(lambda f:Nat->Nat.f (f 0)) (lambda n:Nat.succ(n));",
+            "(lambda x:Nat.lambda y:Nat.{y, x}) 0 succ(0);",
+        ] {
+            // numbers are read back as numerals, the substitution evaluator
+            // keeps the chains of `succ` of the program
+            let value = |term: Option<Term>| eval::Value::from_term(&term?);
+            assert_eq!(
+                run_str(input).map(value),
+                eval::eval(&parse(input)).map(Some).map(value),
+                "{}",
+                input
+            );
+        }
+    }
+}
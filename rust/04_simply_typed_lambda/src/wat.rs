@@ -0,0 +1,421 @@
+use std::fmt::{self, Formatter};
+
+use crate::{parser::Term, typing::Type};
+
+// An experimental backend emitting the WebAssembly text format for the
+// first-order fragment: booleans, numbers, conditionals, `plus`, `times` and
+// `leq`, and abstractions. Every value is an i64, a boolean is 0 or 1 and a
+// function is the address of a closure record in linear memory, holding the
+// table index of its code followed by the values it captured. The code of an
+// abstraction takes the record and the argument, and the closed program is
+// the exported `main`.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum WatError {
+    // the kind of term, which has no representation as an i64
+    Unsupported(String),
+}
+
+impl fmt::Display for WatError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WatError::Unsupported(kind) => write!(f, "{} cannot be compiled to wasm", kind),
+        }
+    }
+}
+
+impl std::error::Error for WatError {}
+
+// The code of one wasm function, `main` or an abstraction
+struct Function {
+    is_abstraction: bool,
+    // the variables of the enclosing function this one captured, by their
+    // index there, in the order of the record
+    captures: Vec<usize>,
+    locals: Vec<&'static str>,
+    code: Vec<String>,
+    depth: usize,
+}
+
+impl Function {
+    fn new(is_abstraction: bool) -> Self {
+        Function {
+            is_abstraction,
+            captures: vec![],
+            locals: vec![],
+            code: vec![],
+            depth: 0,
+        }
+    }
+
+    fn emit(&mut self, instr: impl Into<String>) {
+        self.code
+            .push(format!("{}{}", "  ".repeat(self.depth), instr.into()));
+    }
+
+    fn local(&mut self, typ: &'static str) -> usize {
+        self.locals.push(typ);
+        self.locals.len() - 1
+    }
+
+    // the slot in the closure record of the enclosing variable
+    fn capture(&mut self, index: usize) -> usize {
+        match self.captures.iter().position(|captured| *captured == index) {
+            Some(slot) => slot,
+            None => {
+                self.captures.push(index);
+                self.captures.len() - 1
+            }
+        }
+    }
+
+    fn write(&self, f: &mut Formatter<'_>, header: &str) -> fmt::Result {
+        writeln!(f, "  (func {}", header)?;
+        for (i, typ) in self.locals.iter().enumerate() {
+            writeln!(f, "    (local $t{} {})", i, typ)?;
+        }
+        for instr in &self.code {
+            writeln!(f, "    {}", instr)?;
+        }
+        writeln!(f, "  )")
+    }
+}
+
+// A wasm module, `to_string` gives its text
+pub struct Module {
+    // the abstractions, by their index in the table
+    functions: Vec<Function>,
+    main: Function,
+}
+
+impl Module {
+    pub fn compile(term: &Term) -> Result<Self, WatError> {
+        let mut module = Module {
+            functions: vec![],
+            main: Function::new(false),
+        };
+        let mut main = Function::new(false);
+        module.emit_term(&mut main, term)?;
+        module.main = main;
+        Ok(module)
+    }
+
+    fn emit_variable(&mut self, function: &mut Function, index: usize) -> Result<(), WatError> {
+        if !function.is_abstraction {
+            return Err(WatError::Unsupported("a free variable".to_string()));
+        }
+        if index == 0 {
+            function.emit("local.get $arg");
+        } else {
+            let slot = function.capture(index - 1);
+            function.emit("local.get $env");
+            function.emit("i32.wrap_i64");
+            function.emit(format!("i64.load offset={}", 8 + 8 * slot));
+        }
+        Ok(())
+    }
+
+    // allocates the record and fills it with the captured values
+    fn emit_closure(&mut self, function: &mut Function, body: &Term) -> Result<(), WatError> {
+        let mut code = Function::new(true);
+        self.emit_term(&mut code, body)?;
+        let captures = code.captures.clone();
+        let table_index = self.functions.len();
+        self.functions.push(code);
+
+        let record = function.local("i32");
+        function.emit(format!("i32.const {}", 8 + 8 * captures.len()));
+        function.emit("call $alloc");
+        function.emit(format!("local.set $t{}", record));
+        function.emit(format!("local.get $t{}", record));
+        function.emit(format!("i32.const {}", table_index));
+        function.emit("i32.store");
+        for (slot, index) in captures.into_iter().enumerate() {
+            function.emit(format!("local.get $t{}", record));
+            self.emit_variable(function, index)?;
+            function.emit(format!("i64.store offset={}", 8 + 8 * slot));
+        }
+        function.emit(format!("local.get $t{}", record));
+        function.emit("i64.extend_i32_u");
+        Ok(())
+    }
+
+    // `plus`, `times` and `leq` applied to a pair become wasm arithmetic,
+    // which wraps around where the primitives fail on overflow
+    fn emit_arithmetic(
+        &mut self,
+        function: &mut Function,
+        name: &str,
+        argument: &Term,
+    ) -> Result<(), WatError> {
+        let instrs: &[&str] = match name {
+            "plus" => &["i64.add"],
+            "times" => &["i64.mul"],
+            "leq" => &["i64.le_u", "i64.extend_i32_u"],
+            _ => return Err(WatError::Unsupported(format!("primitive {}", name))),
+        };
+        match argument {
+            Term::TmTuple(terms) if terms.len() == 2 => {
+                self.emit_term(function, &terms[0])?;
+                self.emit_term(function, &terms[1])?;
+            }
+            _ => {
+                return Err(WatError::Unsupported(format!(
+                    "primitive {} on anything but a pair",
+                    name
+                )))
+            }
+        }
+        for instr in instrs {
+            function.emit(*instr);
+        }
+        Ok(())
+    }
+
+    fn emit_term(&mut self, function: &mut Function, term: &Term) -> Result<(), WatError> {
+        let unsupported = |kind: &str| Err(WatError::Unsupported(kind.to_string()));
+        match term {
+            Term::TmTrue => function.emit("i64.const 1"),
+            Term::TmFalse | Term::TmZero => function.emit("i64.const 0"),
+            Term::TmNumber(n) => function.emit(format!("i64.const {}", n)),
+            Term::TmSucc(term) => {
+                self.emit_term(function, term)?;
+                function.emit("i64.const 1");
+                function.emit("i64.add");
+            }
+            Term::TmVar(index) => self.emit_variable(function, *index)?,
+            Term::TmAbs(_, _, body) => self.emit_closure(function, body)?,
+            Term::TmApp(left, right) => match left.as_ref() {
+                Term::TmPrim(primitive) => {
+                    self.emit_arithmetic(function, primitive.name(), right)?
+                }
+                // the record is both the function to call and its first
+                // argument
+                _ => {
+                    let closure = function.local("i64");
+                    self.emit_term(function, left)?;
+                    function.emit(format!("local.set $t{}", closure));
+                    function.emit(format!("local.get $t{}", closure));
+                    self.emit_term(function, right)?;
+                    function.emit(format!("local.get $t{}", closure));
+                    function.emit("i32.wrap_i64");
+                    function.emit("i32.load");
+                    function.emit("call_indirect (type $closure)");
+                }
+            },
+            Term::TmIf(if_term, then_term, else_term) => {
+                self.emit_term(function, if_term)?;
+                function.emit("i32.wrap_i64");
+                function.emit("if (result i64)");
+                function.depth += 1;
+                self.emit_term(function, then_term)?;
+                function.depth -= 1;
+                function.emit("else");
+                function.depth += 1;
+                self.emit_term(function, else_term)?;
+                function.depth -= 1;
+                function.emit("end");
+            }
+            Term::TmString(_) => return unsupported("a string"),
+            Term::TmFloat(_) => return unsupported("a float"),
+            Term::TmTuple(_) | Term::TmProj(_, _) => return unsupported("a tuple"),
+            Term::TmRecord(_) | Term::TmRecordProj(_, _) => return unsupported("a record"),
+            Term::TmInl(_, _) | Term::TmInr(_, _) | Term::TmCase(_, _, _, _, _) => {
+                return unsupported("a sum")
+            }
+            Term::TmTag(_, _, _) | Term::TmVariantCase(_, _) => return unsupported("a variant"),
+            Term::TmNil(_)
+            | Term::TmCons(_, _, _)
+            | Term::TmIsNil(_, _)
+            | Term::TmHead(_, _)
+            | Term::TmTail(_, _) => return unsupported("a list"),
+            // the definition is closed, so it compiles the same anywhere
+            Term::TmGlobal(_, term) => self.emit_term(function, term)?,
+            Term::TmPrim(primitive) => {
+                return Err(WatError::Unsupported(format!(
+                    "primitive {} as a value",
+                    primitive.name()
+                )))
+            }
+        }
+        Ok(())
+    }
+}
+
+// The records are allocated from a bump pointer and never freed, memory
+// grows by a page whenever the next record would not fit
+impl fmt::Display for Module {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "(module")?;
+        writeln!(f, "  (type $closure (func (param i64 i64) (result i64)))")?;
+        writeln!(f, "  (memory (export \"memory\") 1)")?;
+        writeln!(f, "  (global $heap (mut i32) (i32.const 0))")?;
+        writeln!(f, "  (table {} funcref)", self.functions.len())?;
+        if !self.functions.is_empty() {
+            write!(f, "  (elem (i32.const 0)")?;
+            for i in 0..self.functions.len() {
+                write!(f, " $f{}", i)?;
+            }
+            writeln!(f, ")")?;
+        }
+        writeln!(f, "  (func $alloc (param $size i32) (result i32)")?;
+        for instr in &[
+            "global.get $heap",
+            "local.get $size",
+            "i32.add",
+            "memory.size",
+            "i32.const 16",
+            "i32.shl",
+            "i32.gt_u",
+            "if",
+            "  i32.const 1",
+            "  memory.grow",
+            "  drop",
+            "end",
+            "global.get $heap",
+            "global.get $heap",
+            "local.get $size",
+            "i32.add",
+            "global.set $heap",
+        ] {
+            writeln!(f, "    {}", instr)?;
+        }
+        writeln!(f, "  )")?;
+        for (i, function) in self.functions.iter().enumerate() {
+            function.write(
+                f,
+                &format!("$f{} (param $env i64) (param $arg i64) (result i64)", i),
+            )?;
+        }
+        self.main.write(f, "(export \"main\") (result i64)")?;
+        writeln!(f, ")")
+    }
+}
+
+// the text of the module for a closed term
+pub fn compile(term: &Term) -> Result<String, WatError> {
+    Module::compile(term).map(|module| module.to_string())
+}
+
+// reads the result of `main` back into a term of the program's type
+pub fn to_term(value: u64, typ: &Type) -> Option<Term> {
+    match (typ, value) {
+        (Type::Boolean, 0) => Some(Term::TmFalse),
+        (Type::Boolean, 1) => Some(Term::TmTrue),
+        (Type::Number, n) => Some(Term::number(n)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::Context,
+        eval::{eval_to_value, Value},
+        parser::Parser,
+        typing::type_of,
+    };
+
+    fn wat(input: &str) -> Result<String, WatError> {
+        compile(&Parser::new().parse(input).unwrap())
+    }
+
+    // assembles the module, runs its `main` and reads the result back
+    fn run(term: &Term) -> Term {
+        let wasm = wat::parse_str(compile(term).unwrap()).unwrap();
+        let engine = wasmi::Engine::default();
+        let module = wasmi::Module::new(&engine, &wasm[..]).unwrap();
+        let mut store = wasmi::Store::new(&engine, ());
+        let instance = wasmi::Linker::<()>::new(&engine)
+            .instantiate(&mut store, &module)
+            .unwrap()
+            .start(&mut store)
+            .unwrap();
+        let main = instance.get_typed_func::<(), i64>(&store, "main").unwrap();
+        let value = main.call(&mut store, ()).unwrap() as u64;
+        let typ = type_of(&mut Context::default(), term).unwrap();
+        to_term(value, &typ).unwrap()
+    }
+
+    #[test]
+    fn test_run() {
+        for input in [
+            "true;",
+            "succ(succ(0));",
+            "if leq {3, 2} then 0 else times {6, 7};",
+            "(lambda x:Nat.lambda y:Nat.if leq {x, y} then y else x) 0 succ(0);",
+            "(lambda f:Nat->Nat.f (f 3)) (lambda n:Nat.plus {n, n});",
+            "(lambda x:Nat.lambda b:Bool.if b then x else 0) 5 false;",
+            "(lambda compose:(Nat->Nat)->(Nat->Nat)->Nat->Nat.\
+             compose (lambda n:Nat.succ(n)) (lambda n:Nat.times {n, n}) 4)\
+             (lambda f:Nat->Nat.lambda g:Nat->Nat.lambda x:Nat.f (g x));",
+        ] {
+            let term = Parser::new().parse(input).unwrap();
+            // `to_term` gives a numeral where `eval` may stop at `succ`s
+            let value = Value::from_term(&run(&term));
+            assert_eq!(value, eval_to_value(&term).ok(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_compile() {
+        let text =
+            wat("(lambda x:Nat.lambda y:Nat.if leq {x, y} then y else x) 0 succ(0);").unwrap();
+        // the inner abstraction captures x, the outer one nothing
+        assert!(text.contains(
+            "  (func $f0 (param $env i64) (param $arg i64) (result i64)\n\
+             \x20   local.get $env\n\
+             \x20   i32.wrap_i64\n\
+             \x20   i64.load offset=8\n\
+             \x20   local.get $arg\n\
+             \x20   i64.le_u\n\
+             \x20   i64.extend_i32_u\n\
+             \x20   i32.wrap_i64\n\
+             \x20   if (result i64)\n\
+             \x20     local.get $arg\n\
+             \x20   else\n\
+             \x20     local.get $env\n\
+             \x20     i32.wrap_i64\n\
+             \x20     i64.load offset=8\n\
+             \x20   end\n\
+             \x20 )\n"
+        ));
+        assert!(text.contains(
+            "  (func $f1 (param $env i64) (param $arg i64) (result i64)\n\
+             \x20   (local $t0 i32)\n\
+             \x20   i32.const 16\n\
+             \x20   call $alloc\n\
+             \x20   local.set $t0\n\
+             \x20   local.get $t0\n\
+             \x20   i32.const 0\n\
+             \x20   i32.store\n\
+             \x20   local.get $t0\n\
+             \x20   local.get $arg\n\
+             \x20   i64.store offset=8\n\
+             \x20   local.get $t0\n\
+             \x20   i64.extend_i32_u\n\
+             \x20 )\n"
+        ));
+        assert!(text.contains("  (table 2 funcref)\n  (elem (i32.const 0) $f0 $f1)\n"));
+        assert_eq!(
+            to_term(2, &Type::Number),
+            Some(Parser::new().parse("2;").unwrap())
+        );
+        assert_eq!(to_term(1, &Type::Boolean), Some(Term::TmTrue));
+    }
+
+    #[test]
+    fn test_unsupported() {
+        assert_eq!(
+            wat("{0, true}.1;"),
+            Err(WatError::Unsupported("a tuple".to_string()))
+        );
+        assert_eq!(
+            wat("(lambda f:Nat * Nat->Nat.0) plus;"),
+            Err(WatError::Unsupported(
+                "primitive plus as a value".to_string()
+            ))
+        );
+    }
+}
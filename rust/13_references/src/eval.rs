@@ -0,0 +1,257 @@
+use std::fmt::{self, Formatter};
+
+use crate::{parser::Term, store::Store, substitute::substitute_top};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EvalError {
+    NoRuleApplies,
+    // a location that was never allocated, impossible for well-typed terms
+    InvalidLocation(usize),
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::NoRuleApplies => write!(f, "no rule applies"),
+            EvalError::InvalidLocation(location) => {
+                write!(f, "location {} was never allocated", location)
+            }
+        }
+    }
+}
+
+fn is_numeric_val(term: &Term) -> bool {
+    match term {
+        Term::TmZero => true,
+        Term::TmSucc(term) => is_numeric_val(term),
+        _ => false,
+    }
+}
+
+pub fn is_val(term: &Term) -> bool {
+    match term {
+        Term::TmUnit | Term::TmTrue | Term::TmFalse | Term::TmAbs(_, _, _) | Term::TmLoc(_) => true,
+        _ => is_numeric_val(term),
+    }
+}
+
+// t | μ -> t' | μ' (TAPL figure 13-1)
+pub fn eval1(term: &Term, store: &mut Store) -> Result<Term, EvalError> {
+    let boxed = |term: Result<Term, EvalError>| term.map(Box::new);
+    match term {
+        Term::TmSucc(term) => Ok(Term::TmSucc(boxed(eval1(term, store))?)),
+        Term::TmPred(term) => match term.as_ref() {
+            Term::TmZero => Ok(Term::TmZero),
+            Term::TmSucc(number) if is_numeric_val(number) => Ok(number.as_ref().clone()),
+            _ => Ok(Term::TmPred(boxed(eval1(term, store))?)),
+        },
+        Term::TmIsZero(term) => match term.as_ref() {
+            Term::TmZero => Ok(Term::TmTrue),
+            Term::TmSucc(number) if is_numeric_val(number) => Ok(Term::TmFalse),
+            _ => Ok(Term::TmIsZero(boxed(eval1(term, store))?)),
+        },
+        Term::TmApp(left, right) => match left.as_ref() {
+            Term::TmAbs(_, _, body) if is_val(right) => Ok(substitute_top(right, body)),
+            _ if is_val(left) => Ok(Term::TmApp(left.clone(), boxed(eval1(right, store))?)),
+            _ => Ok(Term::TmApp(boxed(eval1(left, store))?, right.clone())),
+        },
+        Term::TmIf(if_term, then_term, else_term) => match if_term.as_ref() {
+            Term::TmTrue => Ok(then_term.as_ref().clone()),
+            Term::TmFalse => Ok(else_term.as_ref().clone()),
+            _ => Ok(Term::TmIf(
+                boxed(eval1(if_term, store))?,
+                then_term.clone(),
+                else_term.clone(),
+            )),
+        },
+        Term::TmLet(name, bound, body) => {
+            if is_val(bound) {
+                Ok(substitute_top(bound, body))
+            } else {
+                Ok(Term::TmLet(
+                    name.clone(),
+                    boxed(eval1(bound, store))?,
+                    body.clone(),
+                ))
+            }
+        }
+        // E-RefV allocates a fresh location
+        Term::TmRef(term) if is_val(term) => Ok(Term::TmLoc(store.alloc(term.as_ref().clone()))),
+        Term::TmRef(term) => Ok(Term::TmRef(boxed(eval1(term, store))?)),
+        Term::TmDeref(term) => match term.as_ref() {
+            Term::TmLoc(location) => store
+                .get(*location)
+                .cloned()
+                .ok_or(EvalError::InvalidLocation(*location)),
+            _ => Ok(Term::TmDeref(boxed(eval1(term, store))?)),
+        },
+        Term::TmAssign(left, right) => match left.as_ref() {
+            Term::TmLoc(location) if is_val(right) => {
+                if store.update(*location, right.as_ref().clone()) {
+                    Ok(Term::TmUnit)
+                } else {
+                    Err(EvalError::InvalidLocation(*location))
+                }
+            }
+            _ if is_val(left) => Ok(Term::TmAssign(left.clone(), boxed(eval1(right, store))?)),
+            _ => Ok(Term::TmAssign(boxed(eval1(left, store))?, right.clone())),
+        },
+        _ => Err(EvalError::NoRuleApplies),
+    }
+}
+
+pub fn eval(term: &Term, store: &mut Store) -> Result<Term, EvalError> {
+    #[cfg(feature = "metatheory-checks")]
+    let typ = crate::metatheory::start(store, term);
+    let mut term = term.clone();
+    loop {
+        match eval1(&term, store) {
+            Ok(next_term) => {
+                #[cfg(feature = "metatheory-checks")]
+                crate::metatheory::preservation(typ.as_ref(), &term, &next_term, store);
+                term = next_term
+            }
+            Err(EvalError::NoRuleApplies) => return Ok(term),
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+// `eval` that collects the store whenever its live cells have doubled, the
+// current term being the only root of a substitution-based evaluator. A
+// last collection leaves just the cells the value reaches.
+pub fn eval_collecting(term: &Term, store: &mut Store) -> Result<Term, EvalError> {
+    #[cfg(feature = "metatheory-checks")]
+    let typ = crate::metatheory::start(store, term);
+    let mut term = term.clone();
+    loop {
+        match eval1(&term, store) {
+            Ok(next_term) => {
+                #[cfg(feature = "metatheory-checks")]
+                crate::metatheory::preservation(typ.as_ref(), &term, &next_term, store);
+                term = next_term
+            }
+            Err(EvalError::NoRuleApplies) => {
+                store.collect(&[&term]);
+                return Ok(term);
+            }
+            Err(err) => return Err(err),
+        }
+        if store.needs_collection() {
+            store.collect(&[&term]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{context::Context, language::References, parser::Parser, typing::type_of};
+    use tapl_testkit::{assert_evals_to, assert_type_error};
+
+    fn nat(n: usize) -> Term {
+        (0..n).fold(Term::TmZero, |term, _| Term::TmSucc(Box::new(term)))
+    }
+
+    #[test]
+    fn test_eval() {
+        assert_evals_to!(References, "!(ref succ(0));", "succ(0)");
+        assert_evals_to!(References, "let r = ref 0 in r := succ(!r); !r;", "succ(0)");
+        // two names for one location see each other's updates
+        assert_evals_to!(
+            References,
+            "let r = ref 0 in let s = r in s := succ(succ(0)); !r;",
+            "succ(succ(0))"
+        );
+        // `ref` allocates a new location every time it is evaluated
+        assert_evals_to!(
+            References,
+            "let r = ref 0 in let s = ref !r in s := succ(0); !r;",
+            "0"
+        );
+        assert_evals_to!(
+            References,
+            "let c = ref 0 in \
+             let i = lambda u:Unit.c := succ(!c) in \
+             i unit; i unit; i unit; !c;",
+            "succ(succ(succ(0)))"
+        );
+        assert_evals_to!(
+            References,
+            "let r = ref 0 in (if iszero(!r) then r := succ(0) else unit); !r;",
+            "succ(0)"
+        );
+        assert_evals_to!(
+            References,
+            "let f = ref (lambda n:Nat.n) in f := (lambda n:Nat.succ(n)); !f 0;",
+            "succ(0)"
+        );
+        assert_type_error!(References, "let r = ref 0 in r := true;", "reference type");
+    }
+
+    #[test]
+    fn test_store() {
+        let term = Parser::new()
+            .parse("let r = ref 0 in let s = ref true in r := succ(!r); s;")
+            .unwrap();
+        let mut store = Store::new();
+        assert_eq!(eval(&term, &mut store), Ok(Term::TmLoc(1)));
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.get(0), Some(&nat(1)));
+        assert_eq!(store.get(1), Some(&Term::TmTrue));
+
+        // evaluation continues in the store left by an earlier program
+        let assign = Term::TmAssign(Box::new(Term::TmLoc(0)), Box::new(nat(3)));
+        assert_eq!(eval(&assign, &mut store), Ok(Term::TmUnit));
+        assert_eq!(store.get(0), Some(&nat(3)));
+        let deref = Term::TmDeref(Box::new(Term::TmLoc(0)));
+        assert_eq!(eval(&deref, &mut store), Ok(nat(3)));
+        assert_eq!(
+            eval(&Term::TmDeref(Box::new(Term::TmLoc(2))), &mut store),
+            Err(EvalError::InvalidLocation(2))
+        );
+
+        // preservation: the result is typed by the store typing of the final store
+        let store_typing = [crate::typing::Type::Number, crate::typing::Type::Boolean];
+        assert_eq!(
+            type_of(&mut Context::default(), &store_typing, &Term::TmLoc(1)),
+            type_of(&mut Context::default(), &[], &term)
+        );
+    }
+
+    #[test]
+    fn test_collect() {
+        // every iteration allocates a cell that is garbage right after it
+        let input = format!(
+            "let c = ref 0 in {}!c;",
+            "(let r = ref !c in c := succ(!r)); ".repeat(40)
+        );
+        let term = Parser::new().parse(&input).unwrap();
+        let mut store = Store::new();
+        assert_eq!(eval_collecting(&term, &mut store), Ok(nat(40)));
+        let stats = store.stats();
+        assert_eq!(stats.allocated, 41);
+        assert_eq!(stats.live, 0);
+        assert!(stats.peak < 41 && stats.collections > 1);
+        assert_eq!(eval(&term, &mut Store::new()), Ok(nat(40)));
+
+        // the cells of the value and those they reach survive
+        let term = Parser::new()
+            .parse("let r = ref 0 in let s = ref true in ref r;")
+            .unwrap();
+        let mut store = Store::new();
+        let value = eval_collecting(&term, &mut store).unwrap();
+        assert_eq!(store.len(), 2);
+        let inner = match value {
+            Term::TmLoc(location) => store.get(location).cloned(),
+            _ => None,
+        };
+        assert_eq!(
+            inner.and_then(|inner| match inner {
+                Term::TmLoc(location) => store.get(location).cloned(),
+                _ => None,
+            }),
+            Some(Term::TmZero)
+        );
+    }
+}
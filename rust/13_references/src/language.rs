@@ -0,0 +1,33 @@
+use tapl_testkit::{Failure, Language};
+
+use crate::{
+    context::Context,
+    eval::eval,
+    parser::{Parser, Term},
+    store::Store,
+    typing::type_of,
+};
+
+pub struct References;
+
+impl Language for References {
+    type Term = Term;
+    type Value = Term;
+
+    fn parse(input: &str) -> Result<Term, Failure> {
+        Parser::new()
+            .parse(input)
+            .map_err(|error| Failure::Parse(format!("{:?}", error)))
+    }
+
+    fn type_check(term: &Term) -> Result<(), Failure> {
+        type_of(&mut Context::default(), &[], term)
+            .map(|_| ())
+            .map_err(|error| Failure::Type(error.to_string()))
+    }
+
+    // every program starts with an empty store
+    fn eval(term: &Term) -> Result<Term, Failure> {
+        eval(term, &mut Store::new()).map_err(|error| Failure::Eval(format!("{:?}", error)))
+    }
+}
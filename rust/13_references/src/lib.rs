@@ -0,0 +1,13 @@
+pub mod ast_parser;
+pub mod context;
+pub mod eval;
+#[cfg(test)]
+mod language;
+#[cfg(feature = "metatheory-checks")]
+mod metatheory;
+pub mod parser;
+pub mod printer;
+pub mod store;
+pub mod substitute;
+pub mod type_parser;
+pub mod typing;
@@ -0,0 +1,133 @@
+// Preservation with a store (TAPL 13.5.3) checked while `eval` runs, with
+// the `metatheory-checks` feature. The store extends its store typing Σ on
+// every allocation, and after a step t | μ -> t' | μ' the store has to be
+// well typed under Σ (TAPL 13.5.1), t' and the cells may only mention
+// locations Σ has a type for, and t' has to keep the type of t. Only
+// well-typed terms are checked, `typ` is the type evaluation started from.
+
+use crate::{
+    context::Context,
+    parser::Term,
+    store::{locations, Store},
+    typing::{type_of, Type},
+};
+
+// Σ as `type_of` takes it. A free location gets a placeholder, which no
+// check relies on since mentioning a location without a type fails first.
+fn dense(store_typing: &[Option<Type>]) -> Vec<Type> {
+    store_typing
+        .iter()
+        .map(|typ| typ.clone().unwrap_or(Type::Unit))
+        .collect()
+}
+
+pub(crate) fn type_of_value(store_typing: &[Option<Type>], value: &Term) -> Option<Type> {
+    type_of(&mut Context::default(), &dense(store_typing), value).ok()
+}
+
+// the type of the term evaluation starts from, `None` when it is ill typed
+pub(crate) fn start(store: &Store, term: &Term) -> Option<Type> {
+    type_of_value(store.store_typing(), term)
+}
+
+// t | μ -> t' | μ' keeps the type of t, and μ' is well typed under Σ
+pub(crate) fn preservation(typ: Option<&Type>, term: &Term, next_term: &Term, store: &Store) {
+    let typ = match typ {
+        Some(typ) => typ,
+        None => return,
+    };
+    let store_typing = store.store_typing();
+    let mut mentioned = vec![];
+    locations(next_term, &mut mentioned);
+    for (_, value) in store.cells() {
+        locations(value, &mut mentioned);
+    }
+    for location in mentioned {
+        assert!(
+            matches!(store_typing.get(location), Some(Some(_))),
+            "location invariant: stepping `{}` left location {} without a store typing",
+            term,
+            location
+        );
+    }
+
+    for (location, value) in store.cells() {
+        if let Some(expected) = &store_typing[location] {
+            let actual = type_of_value(store_typing, value);
+            assert!(
+                actual.as_ref() == Some(expected),
+                "store typing: stepping `{}` left `{}` of type {:?} at location {} of type {}",
+                term,
+                value,
+                actual,
+                location,
+                expected
+            );
+        }
+    }
+
+    let next_type = type_of_value(store_typing, next_term);
+    assert!(
+        next_type.as_ref() == Some(typ),
+        "preservation: `{}` of type {} stepped to `{}` of type {:?}",
+        term,
+        typ,
+        next_term,
+        next_type
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        eval::{eval, eval_collecting},
+        parser::Parser,
+    };
+
+    #[test]
+    #[should_panic(expected = "store typing")]
+    fn test_store_typing() {
+        // an evaluator that assigned a boolean to a cell of numbers
+        let mut store = Store::new();
+        let location = store.alloc(Term::TmZero);
+        store.update(location, Term::TmTrue);
+        let term = Term::TmDeref(Box::new(Term::TmLoc(location)));
+        preservation(Some(&Type::Number), &term, &Term::TmZero, &store);
+    }
+
+    #[test]
+    #[should_panic(expected = "location invariant")]
+    fn test_location_invariant() {
+        let mut store = Store::new();
+        let location = store.alloc(Term::TmZero);
+        let term = Term::TmRef(Box::new(Term::TmZero));
+        // a collection that freed a location the term still holds
+        store.collect(&[]);
+        preservation(
+            Some(&Type::Ref(Box::new(Type::Number))),
+            &term,
+            &Term::TmLoc(location),
+            &store,
+        );
+    }
+
+    #[test]
+    fn test_checked_eval() {
+        // collected locations are reused for cells of another type
+        let input = format!(
+            "let c = ref 0 in {}!c;",
+            "(let b = ref true in let r = ref !c in c := succ(!r)); ".repeat(20)
+        );
+        let term = Parser::new().parse(&input).unwrap();
+        assert!(eval_collecting(&term, &mut Store::new()).is_ok());
+        let term = Parser::new()
+            .parse(
+                "let f = ref (lambda n:Nat.n) in \
+                 let g = ref f in \
+                 (!g) := (lambda n:Nat.succ(n)); !(!g) 0;",
+            )
+            .unwrap();
+        assert!(eval(&term, &mut Store::new()).is_ok());
+    }
+}
@@ -0,0 +1,225 @@
+use std::fmt::{self, Formatter};
+
+use tapl_frontend::lexer::{Lexer, SyntaxError};
+
+use crate::{
+    ast_parser::{parse_term, ASTTerm},
+    typing::Type,
+};
+
+// punctuation of terms and types, `:=` wins over `:`
+pub const SYMBOLS: [&str; 9] = ["->", ":=", "(", ")", ".", ":", ";", "=", "!"];
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum Term {
+    TmUnit,
+    TmTrue,
+    TmFalse,
+    TmZero,
+    TmSucc(Box<Term>),
+    TmPred(Box<Term>),
+    TmIsZero(Box<Term>),
+    // var DeBrujin index
+    TmVar(usize),
+    // argument name, type and body
+    TmAbs(String, Type, Box<Term>),
+    TmApp(Box<Term>, Box<Term>),
+    TmIf(Box<Term>, Box<Term>, Box<Term>),
+    // bound name, bound term and body
+    TmLet(String, Box<Term>, Box<Term>),
+    TmRef(Box<Term>),
+    TmDeref(Box<Term>),
+    TmAssign(Box<Term>, Box<Term>),
+    // store location, only produced by evaluating `ref`
+    TmLoc(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    Syntax(SyntaxError),
+    UnboundVariable(String),
+    TrailingInput(String),
+}
+
+impl From<SyntaxError> for ParseError {
+    fn from(error: SyntaxError) -> Self {
+        ParseError::Syntax(error)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Syntax(error) => write!(f, "{}", error),
+            ParseError::UnboundVariable(name) => write!(f, "unbound variable {}", name),
+            ParseError::TrailingInput(input) => write!(f, "unexpected input `{}`", input),
+        }
+    }
+}
+
+pub type ParseResult = Result<Term, ParseError>;
+
+// Names of the enclosing binders, the innermost is at de Bruijn index 0
+#[derive(Default)]
+pub struct Parser {
+    names: Vec<String>,
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(&mut self, input: &str) -> ParseResult {
+        let mut lexer = Lexer::new(input, &SYMBOLS);
+        let term = parse_term(&mut lexer)?;
+        lexer.expect_symbol(";")?;
+        if !lexer.rest().trim().is_empty() {
+            return Err(ParseError::TrailingInput(lexer.rest().trim().to_string()));
+        }
+
+        self.from_ast_term(&term)
+    }
+
+    fn with_binding<T>(&mut self, name: &str, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.names.push(name.to_string());
+        let result = f(self);
+        self.names.pop();
+        result
+    }
+
+    fn from_ast_term(self: &mut Parser, ast_term: &ASTTerm) -> ParseResult {
+        let term = match ast_term {
+            ASTTerm::TmUnit => Term::TmUnit,
+            ASTTerm::TmTrue => Term::TmTrue,
+            ASTTerm::TmFalse => Term::TmFalse,
+            ASTTerm::TmZero => Term::TmZero,
+            ASTTerm::TmSucc(term) => Term::TmSucc(Box::new(self.from_ast_term(term)?)),
+            ASTTerm::TmPred(term) => Term::TmPred(Box::new(self.from_ast_term(term)?)),
+            ASTTerm::TmIsZero(term) => Term::TmIsZero(Box::new(self.from_ast_term(term)?)),
+            ASTTerm::TmVar(name) => match self.names.iter().rev().position(|n| n == name) {
+                Some(index) => Term::TmVar(index),
+                None => return Err(ParseError::UnboundVariable(name.clone())),
+            },
+            ASTTerm::TmAbs(name, typ, body) => {
+                let body = self.with_binding(name, |parser| parser.from_ast_term(body))?;
+                Term::TmAbs(name.clone(), typ.clone(), Box::new(body))
+            }
+            ASTTerm::TmApp(left, right) => Term::TmApp(
+                Box::new(self.from_ast_term(left)?),
+                Box::new(self.from_ast_term(right)?),
+            ),
+            ASTTerm::TmIf(if_term, then_term, else_term) => Term::TmIf(
+                Box::new(self.from_ast_term(if_term)?),
+                Box::new(self.from_ast_term(then_term)?),
+                Box::new(self.from_ast_term(else_term)?),
+            ),
+            ASTTerm::TmLet(name, bound, body) => {
+                let bound = self.from_ast_term(bound)?;
+                let body = self.with_binding(name, |parser| parser.from_ast_term(body))?;
+                Term::TmLet(name.clone(), Box::new(bound), Box::new(body))
+            }
+            // derived form `(lambda _:Unit.t2) t1` (TAPL 11.3), `_` can never
+            // be referred to
+            ASTTerm::TmSeq(first, rest) => {
+                let first = self.from_ast_term(first)?;
+                let rest = self.with_binding("_", |parser| parser.from_ast_term(rest))?;
+                Term::TmApp(
+                    Box::new(Term::TmAbs("_".to_string(), Type::Unit, Box::new(rest))),
+                    Box::new(first),
+                )
+            }
+            ASTTerm::TmRef(term) => Term::TmRef(Box::new(self.from_ast_term(term)?)),
+            ASTTerm::TmDeref(term) => Term::TmDeref(Box::new(self.from_ast_term(term)?)),
+            ASTTerm::TmAssign(left, right) => Term::TmAssign(
+                Box::new(self.from_ast_term(left)?),
+                Box::new(self.from_ast_term(right)?),
+            ),
+        };
+
+        Ok(term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Parser::new().parse("lambda r:Ref Nat.!r;"),
+            Ok(Term::TmAbs(
+                "r".to_string(),
+                Type::Ref(Box::new(Type::Number)),
+                Box::new(Term::TmDeref(Box::new(Term::TmVar(0))))
+            ))
+        );
+        // the sequenced term sees the same variables
+        assert_eq!(
+            Parser::new().parse("lambda r:Ref Nat.r := 0; !r;"),
+            Ok(Term::TmAbs(
+                "r".to_string(),
+                Type::Ref(Box::new(Type::Number)),
+                Box::new(Term::TmApp(
+                    Box::new(Term::TmAbs(
+                        "_".to_string(),
+                        Type::Unit,
+                        Box::new(Term::TmDeref(Box::new(Term::TmVar(1))))
+                    )),
+                    Box::new(Term::TmAssign(
+                        Box::new(Term::TmVar(0)),
+                        Box::new(Term::TmZero)
+                    ))
+                ))
+            ))
+        );
+        assert_eq!(
+            Parser::new().parse("!x;"),
+            Err(ParseError::UnboundVariable("x".to_string()))
+        );
+    }
+    #[test]
+    fn test_words() {
+        let parse = |input: &str| {
+            Parser::new()
+                .parse(input)
+                .map_err(|error| error.to_string())
+        };
+        // names are whole words of any length, keywords are not split off them
+        assert_eq!(
+            parse("let counter = ref 0 in !counter;"),
+            Ok(Term::TmLet(
+                "counter".to_string(),
+                Box::new(Term::TmRef(Box::new(Term::TmZero))),
+                Box::new(Term::TmDeref(Box::new(Term::TmVar(0))))
+            ))
+        );
+        assert_eq!(
+            parse("lambda refs:Unit.refs;"),
+            Ok(Term::TmAbs(
+                "refs".to_string(),
+                Type::Unit,
+                Box::new(Term::TmVar(0))
+            ))
+        );
+        assert_eq!(
+            parse("lambda ref:Unit.ref;"),
+            Err("1:8: expected a variable, found `ref`".to_string())
+        );
+        assert_eq!(
+            parse("unit; 0"),
+            Err("1:8: expected `;`, found end of input".to_string())
+        );
+        assert_eq!(parse("0; 0;;"), Err("unexpected input `;`".to_string()));
+        // nesting is bounded instead of overflowing the stack
+        let deep = format!("{}0;", "!".repeat(300));
+        assert!(parse(&deep)
+            .unwrap_err()
+            .contains("at most 256 nested terms"));
+        let sequence = format!("{}unit;", "unit; ".repeat(300));
+        assert!(parse(&sequence)
+            .unwrap_err()
+            .contains("at most 256 nested terms"));
+    }
+}
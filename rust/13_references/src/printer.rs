@@ -0,0 +1,177 @@
+use std::fmt::{self, Formatter};
+
+use crate::{parser::Term, typing::Type};
+
+// Prints terms in the syntax the parser accepts, variables get the name of
+// their binder back and `(lambda _:Unit.t2) t1` is shown as `t1; t2`
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_term(f, self, &mut vec![])
+    }
+}
+
+// `t1; t2`, the derived form built by the parser
+fn as_sequence(term: &Term) -> Option<(&Term, &Term)> {
+    match term {
+        Term::TmApp(left, first) => match left.as_ref() {
+            Term::TmAbs(name, Type::Unit, rest) if name == "_" => Some((first, rest)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_atomic(term: &Term) -> bool {
+    matches!(
+        term,
+        Term::TmUnit
+            | Term::TmTrue
+            | Term::TmFalse
+            | Term::TmZero
+            | Term::TmSucc(_)
+            | Term::TmPred(_)
+            | Term::TmIsZero(_)
+            | Term::TmVar(_)
+            | Term::TmRef(_)
+            | Term::TmDeref(_)
+            | Term::TmLoc(_)
+    )
+}
+
+// whether the printed term would swallow a following `;` or `:=`
+fn extends_right(term: &Term) -> bool {
+    match term {
+        Term::TmAbs(_, _, _) | Term::TmLet(_, _, _) | Term::TmIf(_, _, _) => true,
+        Term::TmAssign(_, right) => extends_right(right),
+        _ => as_sequence(term).is_some(),
+    }
+}
+
+fn write_parenthesized(
+    f: &mut Formatter<'_>,
+    term: &Term,
+    names: &mut Vec<String>,
+    parenthesize: bool,
+) -> fmt::Result {
+    if parenthesize {
+        write!(f, "(")?;
+        write_term(f, term, names)?;
+        write!(f, ")")
+    } else {
+        write_term(f, term, names)
+    }
+}
+
+fn write_atom(f: &mut Formatter<'_>, term: &Term, names: &mut Vec<String>) -> fmt::Result {
+    write_parenthesized(f, term, names, !is_atomic(term))
+}
+
+fn write_under_binder(
+    f: &mut Formatter<'_>,
+    name: &str,
+    term: &Term,
+    names: &mut Vec<String>,
+) -> fmt::Result {
+    names.push(name.to_string());
+    let result = write_term(f, term, names);
+    names.pop();
+    result
+}
+
+fn write_term(f: &mut Formatter<'_>, term: &Term, names: &mut Vec<String>) -> fmt::Result {
+    if let Some((first, rest)) = as_sequence(term) {
+        write_parenthesized(f, first, names, extends_right(first))?;
+        write!(f, "; ")?;
+        return write_under_binder(f, "_", rest, names);
+    }
+    match term {
+        Term::TmUnit => write!(f, "unit"),
+        Term::TmTrue => write!(f, "true"),
+        Term::TmFalse => write!(f, "false"),
+        Term::TmZero => write!(f, "0"),
+        Term::TmSucc(term) => {
+            write!(f, "succ(")?;
+            write_term(f, term, names)?;
+            write!(f, ")")
+        }
+        Term::TmPred(term) => {
+            write!(f, "pred(")?;
+            write_term(f, term, names)?;
+            write!(f, ")")
+        }
+        Term::TmIsZero(term) => {
+            write!(f, "iszero(")?;
+            write_term(f, term, names)?;
+            write!(f, ")")
+        }
+        Term::TmVar(index) => match names.iter().rev().nth(*index) {
+            Some(name) => write!(f, "{}", name),
+            // not bound in the printed term
+            None => write!(f, "#{}", index),
+        },
+        Term::TmAbs(name, typ, body) => {
+            write!(f, "lambda {}:{}.", name, typ)?;
+            write_under_binder(f, name, body, names)
+        }
+        Term::TmApp(left, right) => {
+            let left_is_application = matches!(left.as_ref(), Term::TmApp(_, _));
+            write_parenthesized(
+                f,
+                left,
+                names,
+                !(is_atomic(left) || left_is_application && as_sequence(left).is_none()),
+            )?;
+            write!(f, " ")?;
+            write_atom(f, right, names)
+        }
+        Term::TmIf(if_term, then_term, else_term) => {
+            write!(f, "if ")?;
+            write_term(f, if_term, names)?;
+            write!(f, " then ")?;
+            write_term(f, then_term, names)?;
+            write!(f, " else ")?;
+            write_term(f, else_term, names)
+        }
+        Term::TmLet(name, bound, body) => {
+            write!(f, "let {} = ", name)?;
+            write_term(f, bound, names)?;
+            write!(f, " in ")?;
+            write_under_binder(f, name, body, names)
+        }
+        Term::TmRef(term) => {
+            write!(f, "ref ")?;
+            write_atom(f, term, names)
+        }
+        Term::TmDeref(term) => {
+            write!(f, "!")?;
+            write_atom(f, term, names)
+        }
+        Term::TmAssign(left, right) => {
+            write_parenthesized(f, left, names, extends_right(left) || is_assign(left))?;
+            write!(f, " := ")?;
+            write_parenthesized(f, right, names, as_sequence(right).is_some())
+        }
+        Term::TmLoc(location) => write!(f, "<loc {}>", location),
+    }
+}
+
+fn is_assign(term: &Term) -> bool {
+    matches!(term, Term::TmAssign(_, _))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_display() {
+        for input in [
+            "let r = ref 0 in r := succ(!r); !r",
+            "lambda c:Ref Nat.(if iszero(!c) then c := 0 else unit); c := pred(!c)",
+            "(lambda f:Ref (Nat->Nat).!f 0) ref (lambda n:Nat.n)",
+        ] {
+            let term = Parser::new().parse(&format!("{};", input)).unwrap();
+            assert_eq!(term.to_string(), input);
+        }
+    }
+}
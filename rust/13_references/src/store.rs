@@ -0,0 +1,278 @@
+use std::fmt::{self, Formatter};
+
+use crate::parser::Term;
+
+// the fewest live cells that make `needs_collection` true
+const MIN_COLLECTION: usize = 16;
+
+// How the store grew, counted since it was created
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stats {
+    // every `ref` evaluated
+    pub allocated: usize,
+    pub live: usize,
+    // the most cells live at once
+    pub peak: usize,
+    pub collections: usize,
+    pub freed: usize,
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "allocated {}, live {}, peak {}, collections {}, freed {}",
+            self.allocated, self.live, self.peak, self.collections, self.freed
+        )
+    }
+}
+
+// What a cell holds, the terms of a calculus with references. Later
+// chapters reuse the store for their own terms.
+pub trait StoreValue: Clone {
+    // appends the locations `self` mentions to `out`
+    fn locations(&self, out: &mut Vec<usize>);
+
+    // the type of the value under Σ, for the store typing that preservation
+    // is checked against; only this chapter's terms have one
+    #[cfg(feature = "metatheory-checks")]
+    fn store_type(
+        &self,
+        _store_typing: &[Option<crate::typing::Type>],
+    ) -> Option<crate::typing::Type> {
+        None
+    }
+}
+
+impl StoreValue for Term {
+    fn locations(&self, out: &mut Vec<usize>) {
+        locations(self, out)
+    }
+
+    #[cfg(feature = "metatheory-checks")]
+    fn store_type(
+        &self,
+        store_typing: &[Option<crate::typing::Type>],
+    ) -> Option<crate::typing::Type> {
+        crate::metatheory::type_of_value(store_typing, self)
+    }
+}
+
+// The store μ, a location is the index of its cell (TAPL 13.3). A cell no
+// longer reachable from the running term can be collected (TAPL 13.2), and
+// `alloc` reuses its location.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Store<T = Term> {
+    cells: Vec<Option<T>>,
+    free: Vec<usize>,
+    stats: Stats,
+    // the live cells the last collection left
+    survivors: usize,
+    // Σ, the type of the value each live cell was allocated with
+    #[cfg(feature = "metatheory-checks")]
+    store_typing: Vec<Option<crate::typing::Type>>,
+}
+
+// not derived, which would require `T: Default`
+impl<T> Default for Store<T> {
+    fn default() -> Self {
+        Self {
+            cells: vec![],
+            free: vec![],
+            stats: Stats::default(),
+            survivors: 0,
+            #[cfg(feature = "metatheory-checks")]
+            store_typing: vec![],
+        }
+    }
+}
+
+impl<T: StoreValue> Store<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // a fresh location holding `value`
+    pub fn alloc(&mut self, value: T) -> usize {
+        #[cfg(feature = "metatheory-checks")]
+        let typ = value.store_type(&self.store_typing);
+        let location = match self.free.pop() {
+            Some(location) => {
+                self.cells[location] = Some(value);
+                location
+            }
+            None => {
+                self.cells.push(Some(value));
+                self.cells.len() - 1
+            }
+        };
+        self.stats.allocated += 1;
+        self.stats.live += 1;
+        self.stats.peak = self.stats.peak.max(self.stats.live);
+        #[cfg(feature = "metatheory-checks")]
+        {
+            self.store_typing.resize(self.cells.len(), None);
+            self.store_typing[location] = typ;
+        }
+        location
+    }
+
+    pub fn get(&self, location: usize) -> Option<&T> {
+        self.cells.get(location).and_then(Option::as_ref)
+    }
+
+    // replaces the value at `location`, `false` when it is not allocated
+    pub fn update(&mut self, location: usize, value: T) -> bool {
+        match self.cells.get_mut(location) {
+            Some(Some(cell)) => {
+                *cell = value;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // the number of live cells
+    pub fn len(&self) -> usize {
+        self.stats.live
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stats.live == 0
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    // whether the live cells have doubled since the last collection
+    pub fn needs_collection(&self) -> bool {
+        self.stats.live >= MIN_COLLECTION.max(2 * self.survivors)
+    }
+
+    // Mark and sweep: frees every cell that no location in `roots` reaches,
+    // directly or through the values of other cells, and returns how many
+    // were freed
+    pub fn collect(&mut self, roots: &[&T]) -> usize {
+        let mut marked = vec![false; self.cells.len()];
+        let mut pending = vec![];
+        for root in roots {
+            root.locations(&mut pending);
+        }
+        while let Some(location) = pending.pop() {
+            if let Some(Some(value)) = self.cells.get(location) {
+                if !marked[location] {
+                    marked[location] = true;
+                    value.locations(&mut pending);
+                }
+            }
+        }
+
+        let mut freed = 0;
+        for (location, cell) in self.cells.iter_mut().enumerate() {
+            if cell.is_some() && !marked[location] {
+                *cell = None;
+                #[cfg(feature = "metatheory-checks")]
+                {
+                    self.store_typing[location] = None;
+                }
+                self.free.push(location);
+                freed += 1;
+            }
+        }
+        self.stats.live -= freed;
+        self.stats.freed += freed;
+        self.stats.collections += 1;
+        self.survivors = self.stats.live;
+        freed
+    }
+
+    // Σ for every location, `None` for a free one or a value that did not
+    // typecheck
+    #[cfg(feature = "metatheory-checks")]
+    pub(crate) fn store_typing(&self) -> &[Option<crate::typing::Type>] {
+        &self.store_typing
+    }
+
+    // the live locations and their values
+    #[cfg(feature = "metatheory-checks")]
+    pub(crate) fn cells(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter_map(|(location, cell)| cell.as_ref().map(|value| (location, value)))
+    }
+}
+
+// the locations `term` mentions
+pub(crate) fn locations(term: &Term, out: &mut Vec<usize>) {
+    match term {
+        Term::TmLoc(location) => out.push(*location),
+        Term::TmUnit | Term::TmTrue | Term::TmFalse | Term::TmZero | Term::TmVar(_) => {}
+        Term::TmSucc(term)
+        | Term::TmPred(term)
+        | Term::TmIsZero(term)
+        | Term::TmAbs(_, _, term)
+        | Term::TmRef(term)
+        | Term::TmDeref(term) => locations(term, out),
+        Term::TmApp(left, right) | Term::TmLet(_, left, right) | Term::TmAssign(left, right) => {
+            locations(left, out);
+            locations(right, out);
+        }
+        Term::TmIf(if_term, then_term, else_term) => {
+            locations(if_term, out);
+            locations(then_term, out);
+            locations(else_term, out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loc(location: usize) -> Term {
+        Term::TmLoc(location)
+    }
+
+    #[test]
+    fn test_collect() {
+        let mut store = Store::new();
+        let counter = store.alloc(Term::TmZero);
+        // a cell holding a function that closes over the counter
+        let inc = store.alloc(Term::TmAbs(
+            "u".to_string(),
+            crate::typing::Type::Unit,
+            Box::new(Term::TmAssign(
+                Box::new(loc(counter)),
+                Box::new(Term::TmZero),
+            )),
+        ));
+        let garbage = store.alloc(Term::TmTrue);
+        let cycle = store.alloc(Term::TmUnit);
+        assert!(store.update(cycle, loc(cycle)));
+
+        // the counter is reached through the cell of `inc`
+        assert_eq!(store.collect(&[&Term::TmDeref(Box::new(loc(inc)))]), 2);
+        assert_eq!(store.get(counter), Some(&Term::TmZero));
+        assert_eq!(store.get(garbage), None);
+        assert_eq!(store.get(cycle), None);
+        assert!(!store.update(garbage, Term::TmFalse));
+
+        // freed locations are handed out again
+        let reused = store.alloc(Term::TmFalse);
+        assert!(reused == garbage || reused == cycle);
+        assert_eq!(
+            store.stats(),
+            Stats {
+                allocated: 5,
+                live: 3,
+                peak: 4,
+                collections: 1,
+                freed: 2,
+            }
+        );
+        assert_eq!(store.collect(&[]), 3);
+        assert!(store.is_empty());
+    }
+}
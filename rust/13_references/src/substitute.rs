@@ -0,0 +1,58 @@
+use crate::parser::Term;
+
+// Rebuilds `term` replacing every variable by `on_var(c, index)`, where `c`
+// is the number of binders entered so far (TAPL 7.2 `tmmap`)
+fn map_vars<F>(term: &Term, c: usize, on_var: &F) -> Term
+where
+    F: Fn(usize, usize) -> Term,
+{
+    let map = |term: &Term| Box::new(map_vars(term, c, on_var));
+    let map_under_binder = |term: &Term| Box::new(map_vars(term, c + 1, on_var));
+    match term {
+        Term::TmVar(index) => on_var(c, *index),
+        Term::TmUnit | Term::TmTrue | Term::TmFalse | Term::TmZero | Term::TmLoc(_) => term.clone(),
+        Term::TmSucc(term) => Term::TmSucc(map(term)),
+        Term::TmPred(term) => Term::TmPred(map(term)),
+        Term::TmIsZero(term) => Term::TmIsZero(map(term)),
+        Term::TmAbs(name, typ, body) => {
+            Term::TmAbs(name.clone(), typ.clone(), map_under_binder(body))
+        }
+        Term::TmApp(left, right) => Term::TmApp(map(left), map(right)),
+        Term::TmIf(if_term, then_term, else_term) => {
+            Term::TmIf(map(if_term), map(then_term), map(else_term))
+        }
+        Term::TmLet(name, bound, body) => {
+            Term::TmLet(name.clone(), map(bound), map_under_binder(body))
+        }
+        Term::TmRef(term) => Term::TmRef(map(term)),
+        Term::TmDeref(term) => Term::TmDeref(map(term)),
+        Term::TmAssign(left, right) => Term::TmAssign(map(left), map(right)),
+    }
+}
+
+// ↑d_c: adds `d` to every variable at or above the cutoff
+pub fn shift(term: &Term, d: isize, cutoff: usize) -> Term {
+    map_vars(term, cutoff, &|c, index| {
+        if index >= c {
+            Term::TmVar((index as isize + d) as usize)
+        } else {
+            Term::TmVar(index)
+        }
+    })
+}
+
+// [j ↦ s]term
+pub fn substitute(term: &Term, j: usize, s: &Term) -> Term {
+    map_vars(term, 0, &|c, index| {
+        if index == j + c {
+            shift(s, c as isize, 0)
+        } else {
+            Term::TmVar(index)
+        }
+    })
+}
+
+// the body of a beta reduction: ↑-1([0 ↦ ↑1(s)]body)
+pub fn substitute_top(s: &Term, body: &Term) -> Term {
+    shift(&substitute(body, 0, &shift(s, 1, 0)), -1, 0)
+}
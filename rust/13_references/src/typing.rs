@@ -0,0 +1,194 @@
+use std::fmt::{self, Formatter};
+
+use crate::{context::Context, parser::Term};
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum TypeError {
+    ParameterTypeMismatch,
+    ArrowTypeExpected,
+    NumberExpected,
+    IfConditionNotBoolean,
+    IfBranchesMismatch,
+    UnboundVariable(usize),
+    RefTypeExpected,
+    AssignTypeMismatch,
+    UnknownLocation(usize),
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeError::ParameterTypeMismatch => write!(f, "parameter type mismatch"),
+            TypeError::ArrowTypeExpected => write!(f, "arrow type expected"),
+            TypeError::NumberExpected => write!(f, "argument is not a number"),
+            TypeError::IfConditionNotBoolean => write!(f, "guard of conditional not a boolean"),
+            TypeError::IfBranchesMismatch => {
+                write!(f, "arms of conditional have different types")
+            }
+            TypeError::UnboundVariable(index) => write!(f, "unbound variable index {}", index),
+            TypeError::RefTypeExpected => write!(f, "reference type expected"),
+            TypeError::AssignTypeMismatch => {
+                write!(f, "assigned value does not match the reference type")
+            }
+            TypeError::UnknownLocation(location) => {
+                write!(f, "location {} is not in the store typing", location)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum Type {
+    Unit,
+    Boolean,
+    Number,
+    Arrow(Box<Type>, Box<Type>),
+    Ref(Box<Type>),
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Unit => write!(f, "Unit"),
+            Type::Boolean => write!(f, "Bool"),
+            Type::Number => write!(f, "Nat"),
+            Type::Arrow(param, ret) => match param.as_ref() {
+                Type::Arrow(_, _) => write!(f, "({})->{}", param, ret),
+                _ => write!(f, "{}->{}", param, ret),
+            },
+            Type::Ref(typ) => match typ.as_ref() {
+                Type::Arrow(_, _) | Type::Ref(_) => write!(f, "Ref ({})", typ),
+                _ => write!(f, "Ref {}", typ),
+            },
+        }
+    }
+}
+
+// Γ | Σ ⊢ t : T (TAPL 13.4), the store typing Σ gives the type of the
+// value at each location
+pub fn type_of(ctx: &mut Context, store_typing: &[Type], term: &Term) -> Result<Type, TypeError> {
+    match term {
+        Term::TmUnit => Ok(Type::Unit),
+        Term::TmTrue | Term::TmFalse => Ok(Type::Boolean),
+        Term::TmZero => Ok(Type::Number),
+        Term::TmSucc(term) | Term::TmPred(term) => match type_of(ctx, store_typing, term)? {
+            Type::Number => Ok(Type::Number),
+            _ => Err(TypeError::NumberExpected),
+        },
+        Term::TmIsZero(term) => match type_of(ctx, store_typing, term)? {
+            Type::Number => Ok(Type::Boolean),
+            _ => Err(TypeError::NumberExpected),
+        },
+        Term::TmVar(index) => ctx
+            .get_type(*index)
+            .ok_or(TypeError::UnboundVariable(*index)),
+        Term::TmAbs(name, typ, body) => {
+            ctx.push(name.clone(), typ.clone());
+            let body_type = type_of(ctx, store_typing, body);
+            ctx.pop();
+            Ok(Type::Arrow(Box::new(typ.clone()), Box::new(body_type?)))
+        }
+        Term::TmApp(left, right) => {
+            let left_type = type_of(ctx, store_typing, left)?;
+            let right_type = type_of(ctx, store_typing, right)?;
+            match left_type {
+                Type::Arrow(param_type, return_type) if *param_type == right_type => {
+                    Ok(*return_type)
+                }
+                Type::Arrow(_, _) => Err(TypeError::ParameterTypeMismatch),
+                _ => Err(TypeError::ArrowTypeExpected),
+            }
+        }
+        Term::TmIf(if_term, then_term, else_term) => {
+            if type_of(ctx, store_typing, if_term)? != Type::Boolean {
+                return Err(TypeError::IfConditionNotBoolean);
+            }
+            let then_type = type_of(ctx, store_typing, then_term)?;
+            if type_of(ctx, store_typing, else_term)? != then_type {
+                return Err(TypeError::IfBranchesMismatch);
+            }
+            Ok(then_type)
+        }
+        Term::TmLet(name, bound, body) => {
+            let bound_type = type_of(ctx, store_typing, bound)?;
+            ctx.push(name.clone(), bound_type);
+            let body_type = type_of(ctx, store_typing, body);
+            ctx.pop();
+            body_type
+        }
+        Term::TmRef(term) => Ok(Type::Ref(Box::new(type_of(ctx, store_typing, term)?))),
+        Term::TmDeref(term) => match type_of(ctx, store_typing, term)? {
+            Type::Ref(typ) => Ok(*typ),
+            _ => Err(TypeError::RefTypeExpected),
+        },
+        Term::TmAssign(left, right) => match type_of(ctx, store_typing, left)? {
+            Type::Ref(typ) if *typ == type_of(ctx, store_typing, right)? => Ok(Type::Unit),
+            Type::Ref(_) => Err(TypeError::AssignTypeMismatch),
+            _ => Err(TypeError::RefTypeExpected),
+        },
+        Term::TmLoc(location) => store_typing
+            .get(*location)
+            .map(|typ| Type::Ref(Box::new(typ.clone())))
+            .ok_or(TypeError::UnknownLocation(*location)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn type_str(input: &str) -> Result<Type, TypeError> {
+        let term = Parser::new().parse(input).unwrap();
+        type_of(&mut Context::default(), &[], &term)
+    }
+
+    #[test]
+    fn test_type_of() {
+        let nat_ref = Type::Ref(Box::new(Type::Number));
+        assert_eq!(type_str("ref 0;"), Ok(nat_ref.clone()));
+        assert_eq!(type_str("!(ref true);"), Ok(Type::Boolean));
+        assert_eq!(type_str("(ref 0) := succ(0);"), Ok(Type::Unit));
+        assert_eq!(
+            type_str("lambda r:Ref Nat.r := succ(!r); !r;"),
+            Ok(Type::Arrow(
+                Box::new(nat_ref.clone()),
+                Box::new(Type::Number)
+            ))
+        );
+        assert_eq!(
+            type_str("(ref 0) := true;"),
+            Err(TypeError::AssignTypeMismatch)
+        );
+        assert_eq!(type_str("!0;"), Err(TypeError::RefTypeExpected));
+        assert_eq!(type_str("0; true;"), Err(TypeError::ParameterTypeMismatch));
+
+        // locations are typed by the store typing
+        let store_typing = [Type::Number, Type::Boolean];
+        let mut ctx = Context::default();
+        assert_eq!(
+            type_of(&mut ctx, &store_typing, &Term::TmLoc(0)),
+            Ok(nat_ref)
+        );
+        assert_eq!(
+            type_of(
+                &mut ctx,
+                &store_typing,
+                &Term::TmDeref(Box::new(Term::TmLoc(1)))
+            ),
+            Ok(Type::Boolean)
+        );
+        assert_eq!(
+            type_of(&mut ctx, &store_typing, &Term::TmLoc(2)),
+            Err(TypeError::UnknownLocation(2))
+        );
+        assert_eq!(
+            Type::Ref(Box::new(Type::Arrow(
+                Box::new(Type::Unit),
+                Box::new(Type::Number)
+            )))
+            .to_string(),
+            "Ref (Unit->Nat)"
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use tapl_frontend::lexer::{Lexer, Token};
+
+use crate::{
+    parser::ParseError,
+    type_parser::{parse_type, TYPE_KEYWORDS},
+    typing::Type,
+};
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum ASTTerm {
+    TmTrue,
+    TmFalse,
+    TmZero,
+    TmSucc(Box<ASTTerm>),
+    TmPred(Box<ASTTerm>),
+    TmIsZero(Box<ASTTerm>),
+    TmVar(String),
+    // argument ident, argument type and body
+    TmAbs(String, Type, Box<ASTTerm>),
+    TmApp(Box<ASTTerm>, Box<ASTTerm>),
+    // condition term, then term, else term
+    TmIf(Box<ASTTerm>, Box<ASTTerm>, Box<ASTTerm>),
+    // bound ident, bound term and body
+    TmLet(String, Box<ASTTerm>, Box<ASTTerm>),
+    TmError,
+    // `try t1 with t2`
+    TmTry(Box<ASTTerm>, Box<ASTTerm>),
+    // `raise t`
+    TmRaise(Box<ASTTerm>),
+    // `try t1 catch t2`
+    TmCatch(Box<ASTTerm>, Box<ASTTerm>),
+    // `try t1 handle x => t2`
+    TmHandle(Box<ASTTerm>, String, Box<ASTTerm>),
+    // `<l=t> as T`, the exception type without `as T`
+    TmTag(String, Box<ASTTerm>, Option<Type>),
+    // `case t of <l=x> => t1 | ...`
+    TmCase(Box<ASTTerm>, Vec<(String, String, ASTTerm)>),
+}
+
+// the words a variable cannot be named
+pub const KEYWORDS: [&str; 21] = [
+    "lambda",
+    "let",
+    "in",
+    "if",
+    "then",
+    "else",
+    "true",
+    "false",
+    "succ",
+    "pred",
+    "iszero",
+    "error",
+    "raise",
+    "try",
+    "with",
+    "catch",
+    "handle",
+    "case",
+    "of",
+    "as",
+    "exception",
+];
+
+// the keywords an atom starts with
+const ATOM_KEYWORDS: [&str; 7] = ["true", "false", "succ", "pred", "iszero", "error", "raise"];
+
+fn is_reserved(word: &str) -> bool {
+    KEYWORDS.contains(&word) || TYPE_KEYWORDS.contains(&word)
+}
+
+fn variable<'a>(lexer: &mut Lexer<'a>) -> Result<&'a str, ParseError> {
+    match lexer.peek()? {
+        (Token::Word(word), _) if !is_reserved(word) => {
+            lexer.next_token()?;
+            Ok(word)
+        }
+        _ => Err(lexer.unexpected("a variable").into()),
+    }
+}
+
+fn starts_atom(lexer: &mut Lexer) -> Result<bool, ParseError> {
+    Ok(match lexer.peek()?.0 {
+        Token::Word(word) => !is_reserved(word) || ATOM_KEYWORDS.contains(&word),
+        Token::Number(_) | Token::Symbol("(") | Token::Symbol("<") => true,
+        _ => false,
+    })
+}
+
+// `(t)` after `succ`, `pred` or `iszero`
+fn parse_argument(lexer: &mut Lexer) -> Result<Box<ASTTerm>, ParseError> {
+    lexer.expect_symbol("(")?;
+    let term = parse_term(lexer)?;
+    lexer.expect_symbol(")")?;
+    Ok(Box::new(term))
+}
+
+// `l=t> as T` after the `<`, the exception type without `as T`
+fn parse_tag(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let (label, _) = lexer.expect_word("a label", &[])?;
+    lexer.expect_symbol("=")?;
+    let term = parse_term(lexer)?;
+    lexer.expect_symbol(">")?;
+    let typ = match lexer.eat_keyword("as")? {
+        Some(_) => Some(parse_type(lexer)?),
+        None => None,
+    };
+    Ok(ASTTerm::TmTag(label.to_string(), Box::new(term), typ))
+}
+
+fn parse_primary(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let (token, span) = lexer.next_token()?;
+    let term = match token {
+        Token::Word("true") => ASTTerm::TmTrue,
+        Token::Word("false") => ASTTerm::TmFalse,
+        Token::Word("error") => ASTTerm::TmError,
+        Token::Word("succ") => ASTTerm::TmSucc(parse_argument(lexer)?),
+        Token::Word("pred") => ASTTerm::TmPred(parse_argument(lexer)?),
+        Token::Word("iszero") => ASTTerm::TmIsZero(parse_argument(lexer)?),
+        Token::Word("raise") => ASTTerm::TmRaise(Box::new(parse_atom(lexer)?)),
+        Token::Word(word) if !is_reserved(word) => ASTTerm::TmVar(word.to_string()),
+        Token::Number("0") => ASTTerm::TmZero,
+        Token::Symbol("<") => parse_tag(lexer)?,
+        Token::Symbol("(") => {
+            let term = parse_term(lexer)?;
+            lexer.expect_symbol(")")?;
+            term
+        }
+        _ => return Err(lexer.error_at("a term", &token.to_string(), span).into()),
+    };
+    Ok(term)
+}
+
+fn parse_atom(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    lexer.nested(parse_primary)
+}
+
+// application is left associative: `f x y` is `(f x) y`, and every argument
+// nests the application one level deeper
+fn parse_application(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let mut term = parse_atom(lexer)?;
+    while starts_atom(lexer)? {
+        lexer.deepen()?;
+        let right = parse_atom(lexer)?;
+        term = ASTTerm::TmApp(Box::new(term), Box::new(right));
+    }
+    Ok(term)
+}
+
+// `x:T.t` after `lambda`
+fn parse_abstraction(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let param = variable(lexer)?.to_string();
+    lexer.expect_symbol(":")?;
+    let typ = parse_type(lexer)?;
+    lexer.expect_symbol(".")?;
+    Ok(ASTTerm::TmAbs(param, typ, Box::new(parse_term(lexer)?)))
+}
+
+fn parse_if(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let condition = parse_term(lexer)?;
+    lexer.expect_keyword("then")?;
+    let then_term = parse_term(lexer)?;
+    lexer.expect_keyword("else")?;
+    let else_term = parse_term(lexer)?;
+    Ok(ASTTerm::TmIf(
+        Box::new(condition),
+        Box::new(then_term),
+        Box::new(else_term),
+    ))
+}
+
+fn parse_let(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let name = variable(lexer)?.to_string();
+    lexer.expect_symbol("=")?;
+    let bound = parse_term(lexer)?;
+    lexer.expect_keyword("in")?;
+    let body = parse_term(lexer)?;
+    Ok(ASTTerm::TmLet(name, Box::new(bound), Box::new(body)))
+}
+
+// `try t1 with t2` handles `error`, `try t1 catch t2` and
+// `try t1 handle x => t2` handle `raise`
+fn parse_try(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let term = Box::new(parse_term(lexer)?);
+    if lexer.eat_keyword("handle")?.is_some() {
+        let name = variable(lexer)?.to_string();
+        lexer.expect_symbol("=>")?;
+        Ok(ASTTerm::TmHandle(term, name, Box::new(parse_term(lexer)?)))
+    } else if lexer.eat_keyword("catch")?.is_some() {
+        Ok(ASTTerm::TmCatch(term, Box::new(parse_term(lexer)?)))
+    } else if lexer.eat_keyword("with")?.is_some() {
+        Ok(ASTTerm::TmTry(term, Box::new(parse_term(lexer)?)))
+    } else {
+        Err(lexer.unexpected("`with`, `catch` or `handle`").into())
+    }
+}
+
+// `t of <l=x> => t1 | <m=y> => t2` after `case`
+fn parse_case(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let term = parse_term(lexer)?;
+    lexer.expect_keyword("of")?;
+    let mut branches = vec![];
+    loop {
+        lexer.expect_symbol("<")?;
+        let (label, _) = lexer.expect_word("a label", &[])?;
+        lexer.expect_symbol("=")?;
+        let name = variable(lexer)?.to_string();
+        lexer.expect_symbol(">")?;
+        lexer.expect_symbol("=>")?;
+        branches.push((label.to_string(), name, parse_term(lexer)?));
+        if lexer.eat_symbol("|")?.is_none() {
+            break;
+        }
+    }
+    Ok(ASTTerm::TmCase(Box::new(term), branches))
+}
+
+// the bodies of abstractions, lets and handlers extend as far to the right
+// as possible
+pub fn parse_term(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    lexer.nested(|lexer| {
+        if lexer.eat_keyword("lambda")?.is_some() {
+            parse_abstraction(lexer)
+        } else if lexer.eat_keyword("let")?.is_some() {
+            parse_let(lexer)
+        } else if lexer.eat_keyword("if")?.is_some() {
+            parse_if(lexer)
+        } else if lexer.eat_keyword("try")?.is_some() {
+            parse_try(lexer)
+        } else if lexer.eat_keyword("case")?.is_some() {
+            parse_case(lexer)
+        } else {
+            parse_application(lexer)
+        }
+    })
+}
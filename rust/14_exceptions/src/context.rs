@@ -0,0 +1,4 @@
+use crate::typing::Type;
+
+// the shared typing context with the types of this language
+pub type Context = tapl_frontend::context::Context<Type>;
@@ -0,0 +1,268 @@
+use std::fmt::{self, Formatter};
+
+use crate::{parser::Term, substitute::substitute_top};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EvalError {
+    NoRuleApplies,
+    // the program evaluated to `error`
+    UncaughtError,
+    // the program evaluated to `raise v`, with the raised value
+    UncaughtException(Term),
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::NoRuleApplies => write!(f, "no rule applies"),
+            EvalError::UncaughtError => write!(f, "uncaught error"),
+            EvalError::UncaughtException(value) => write!(f, "uncaught exception {:?}", value),
+        }
+    }
+}
+
+fn is_numeric_val(term: &Term) -> bool {
+    match term {
+        Term::TmZero => true,
+        Term::TmSucc(term) => is_numeric_val(term),
+        _ => false,
+    }
+}
+
+pub fn is_val(term: &Term) -> bool {
+    match term {
+        Term::TmTrue | Term::TmFalse | Term::TmAbs(_, _, _) => true,
+        Term::TmTag(_, term, _) => is_val(term),
+        _ => is_numeric_val(term),
+    }
+}
+
+// `error` or `raise v`, the other normal forms of a well-typed term
+pub fn is_exception(term: &Term) -> bool {
+    match term {
+        Term::TmError => true,
+        Term::TmRaise(term, _) => is_val(term),
+        _ => false,
+    }
+}
+
+// Steps the subterm in evaluation position, an exception there replaces the
+// whole enclosing term (E-AppErr1, E-AppRaise1 and the other propagation rules)
+fn eval_subterm(term: &Term, rebuild: impl FnOnce(Box<Term>) -> Term) -> Result<Term, EvalError> {
+    if is_exception(term) {
+        Ok(term.clone())
+    } else {
+        Ok(rebuild(Box::new(eval1(term)?)))
+    }
+}
+
+pub fn eval1(term: &Term) -> Result<Term, EvalError> {
+    match term {
+        Term::TmSucc(term) => eval_subterm(term, Term::TmSucc),
+        Term::TmPred(term) => match term.as_ref() {
+            Term::TmZero => Ok(Term::TmZero),
+            Term::TmSucc(number) if is_numeric_val(number) => Ok(number.as_ref().clone()),
+            _ => eval_subterm(term, Term::TmPred),
+        },
+        Term::TmIsZero(term) => match term.as_ref() {
+            Term::TmZero => Ok(Term::TmTrue),
+            Term::TmSucc(number) if is_numeric_val(number) => Ok(Term::TmFalse),
+            _ => eval_subterm(term, Term::TmIsZero),
+        },
+        Term::TmApp(left, right) => match left.as_ref() {
+            Term::TmAbs(_, _, body) if is_val(right) => Ok(substitute_top(right, body)),
+            _ if is_val(left) => eval_subterm(right, |right| Term::TmApp(left.clone(), right)),
+            _ => eval_subterm(left, |left| Term::TmApp(left, right.clone())),
+        },
+        Term::TmIf(if_term, then_term, else_term) => match if_term.as_ref() {
+            Term::TmTrue => Ok(then_term.as_ref().clone()),
+            Term::TmFalse => Ok(else_term.as_ref().clone()),
+            _ => eval_subterm(if_term, |if_term| {
+                Term::TmIf(if_term, then_term.clone(), else_term.clone())
+            }),
+        },
+        Term::TmLet(name, bound, body) => {
+            if is_val(bound) {
+                Ok(substitute_top(bound, body))
+            } else {
+                eval_subterm(bound, |bound| {
+                    Term::TmLet(name.clone(), bound, body.clone())
+                })
+            }
+        }
+        Term::TmRaise(term, typ) if !is_val(term) => {
+            eval_subterm(term, |term| Term::TmRaise(term, typ.clone()))
+        }
+        Term::TmTry(term, handler) => match term.as_ref() {
+            // E-TryV
+            _ if is_val(term) => Ok(term.as_ref().clone()),
+            // E-TryError
+            Term::TmError => Ok(handler.as_ref().clone()),
+            // a raised value is only caught by `try ... catch`
+            _ if is_exception(term) => Ok(term.as_ref().clone()),
+            _ => Ok(Term::TmTry(Box::new(eval1(term)?), handler.clone())),
+        },
+        Term::TmCatch(term, handler, typ) => match term.as_ref() {
+            _ if is_val(term) => Ok(term.as_ref().clone()),
+            // E-TryRaise
+            Term::TmRaise(value, _) if is_val(value) => {
+                Ok(Term::TmApp(handler.clone(), value.clone()))
+            }
+            _ if is_exception(term) => Ok(term.as_ref().clone()),
+            _ => Ok(Term::TmCatch(
+                Box::new(eval1(term)?),
+                handler.clone(),
+                typ.clone(),
+            )),
+        },
+        Term::TmHandle(term, name, typ, handler) => match term.as_ref() {
+            _ if is_val(term) => Ok(term.as_ref().clone()),
+            // E-TryRaise, with the raised value substituted for x
+            Term::TmRaise(value, _) if is_val(value) => Ok(substitute_top(value, handler)),
+            _ if is_exception(term) => Ok(term.as_ref().clone()),
+            _ => Ok(Term::TmHandle(
+                Box::new(eval1(term)?),
+                name.clone(),
+                typ.clone(),
+                handler.clone(),
+            )),
+        },
+        Term::TmTag(label, term, typ) => {
+            eval_subterm(term, |term| Term::TmTag(label.clone(), term, typ.clone()))
+        }
+        // E-CaseVariant
+        Term::TmCase(term, branches) => match term.as_ref() {
+            Term::TmTag(label, value, _) if is_val(value) => branches
+                .iter()
+                .find(|(name, _, _)| name == label)
+                .map(|(_, _, branch)| substitute_top(value, branch))
+                .ok_or(EvalError::NoRuleApplies),
+            _ => eval_subterm(term, |term| Term::TmCase(term, branches.clone())),
+        },
+        _ => Err(EvalError::NoRuleApplies),
+    }
+}
+
+pub fn eval(term: &Term) -> Result<Term, EvalError> {
+    let mut term = term.clone();
+    loop {
+        match eval1(&term) {
+            Ok(next_term) => term = next_term,
+            Err(EvalError::NoRuleApplies) => break,
+            Err(err) => return Err(err),
+        }
+    }
+    match term {
+        Term::TmError => Err(EvalError::UncaughtError),
+        Term::TmRaise(value, _) if is_val(&value) => Err(EvalError::UncaughtException(*value)),
+        _ => Ok(term),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{language::Exceptions, parser::Parser};
+    use tapl_testkit::{assert_evals_to, assert_step_trace};
+
+    fn eval_str(input: &str) -> Result<Term, EvalError> {
+        eval(&Parser::new().parse(input).unwrap())
+    }
+
+    #[test]
+    fn test_eval() {
+        assert_evals_to!(Exceptions, "try error with 0;", "0");
+        assert_evals_to!(Exceptions, "try succ(0) with 0;", "succ(0)");
+        assert_evals_to!(
+            Exceptions,
+            "let f = lambda x:Nat.if iszero(x) then error else pred(x) in \
+             try succ(f 0) with 0;",
+            "0"
+        );
+        assert_evals_to!(Exceptions, "try (try error with error) with true;", "true");
+        assert_evals_to!(
+            Exceptions,
+            "try succ(raise succ(succ(0))) catch lambda e:Nat.pred(e);",
+            "succ(0)"
+        );
+        // each kind of exception passes through the other kind of handler
+        assert_evals_to!(
+            Exceptions,
+            "try (try raise 0 with succ(0)) catch lambda e:Nat.succ(succ(e));",
+            "succ(succ(0))"
+        );
+        assert_evals_to!(
+            Exceptions,
+            "try (try error catch lambda e:Nat.e) with 0;",
+            "0"
+        );
+        assert_eq!(eval_str("succ(error);"), Err(EvalError::UncaughtError));
+        assert_eq!(
+            eval_str("(lambda x:Nat.raise x) succ(0);"),
+            Err(EvalError::UncaughtException(Term::TmSucc(Box::new(
+                Term::TmZero
+            ))))
+        );
+    }
+
+    #[test]
+    fn test_exception_type() {
+        // TAPL 14.3: a variant of the ways a computation can fail
+        let program = |body: &str| {
+            format!(
+                "exception <divideByZero:Nat, notFound:Bool>; \
+                 let d = lambda x:Nat.lambda y:Nat.\
+                 if iszero(y) then raise <divideByZero=x> else x in {}",
+                body
+            )
+        };
+        assert_evals_to!(
+            Exceptions,
+            &program(
+                "try succ(d succ(0) 0) handle e => case e of \
+                 <divideByZero=n> => pred(n) | <notFound=b> => succ(0);"
+            ),
+            "0"
+        );
+        assert_evals_to!(
+            Exceptions,
+            &program("try d 0 succ(0) handle e => succ(succ(0));"),
+            "0"
+        );
+        // a raised tag is a value, and passes `try ... with`
+        assert_evals_to!(
+            Exceptions,
+            &program(
+                "try (try raise <notFound=true> with 0) handle e => \
+                 case e of <divideByZero=n> => n | <notFound=b> => if b then succ(0) else 0;"
+            ),
+            "succ(0)"
+        );
+        assert_eq!(
+            eval_str(&program("d succ(0) 0;")),
+            Err(EvalError::UncaughtException(Term::TmTag(
+                "divideByZero".to_string(),
+                Box::new(Term::TmSucc(Box::new(Term::TmZero))),
+                crate::typing::Type::Variant(vec![
+                    ("divideByZero".to_string(), crate::typing::Type::Number),
+                    ("notFound".to_string(), crate::typing::Type::Boolean),
+                ])
+            )))
+        );
+    }
+
+    #[test]
+    fn test_eval1() {
+        // the error discards the pending application and the argument
+        assert_step_trace!(
+            Exceptions,
+            "(lambda x:Bool.x) (iszero(error));",
+            ["(lambda x:Bool.x) error", "error"]
+        );
+        assert_step_trace!(
+            Exceptions,
+            "raise (raise pred(0));",
+            ["raise (raise 0)", "raise 0"]
+        );
+    }
+}
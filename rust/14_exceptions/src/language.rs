@@ -0,0 +1,35 @@
+use tapl_testkit::{Failure, Language};
+
+use crate::{
+    context::Context,
+    eval::{eval, eval1},
+    parser::{Parser, Term},
+    typing::type_of,
+};
+
+pub struct Exceptions;
+
+impl Language for Exceptions {
+    type Term = Term;
+    type Value = Term;
+
+    fn parse(input: &str) -> Result<Term, Failure> {
+        Parser::new()
+            .parse(input)
+            .map_err(|error| Failure::Parse(error.to_string()))
+    }
+
+    fn type_check(term: &Term) -> Result<(), Failure> {
+        type_of(&mut Context::default(), term)
+            .map(|_| ())
+            .map_err(|error| Failure::Type(error.to_string()))
+    }
+
+    fn eval(term: &Term) -> Result<Term, Failure> {
+        eval(term).map_err(|error| Failure::Eval(error.to_string()))
+    }
+
+    fn step(term: &Term) -> Option<Term> {
+        eval1(term).ok()
+    }
+}
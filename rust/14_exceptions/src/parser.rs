@@ -0,0 +1,301 @@
+use std::fmt::{self, Formatter};
+
+use tapl_frontend::lexer::{Lexer, SyntaxError};
+
+use crate::{
+    ast_parser::{parse_term, ASTTerm},
+    type_parser::parse_type,
+    typing::Type,
+};
+
+// punctuation of terms and types, `->` and `=>` win over `>` and `=`
+pub const SYMBOLS: [&str; 12] = ["->", "=>", "(", ")", "<", ">", ",", ".", ":", ";", "=", "|"];
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum Term {
+    TmTrue,
+    TmFalse,
+    TmZero,
+    TmSucc(Box<Term>),
+    TmPred(Box<Term>),
+    TmIsZero(Box<Term>),
+    // var DeBrujin index
+    TmVar(usize),
+    // argument name, type and body
+    TmAbs(String, Type, Box<Term>),
+    TmApp(Box<Term>, Box<Term>),
+    TmIf(Box<Term>, Box<Term>, Box<Term>),
+    // bound name, bound term and body
+    TmLet(String, Box<Term>, Box<Term>),
+    // aborts the whole program unless a `try ... with` is around it (TAPL 14.1)
+    TmError,
+    // guarded term and the term that replaces it on `error`
+    TmTry(Box<Term>, Box<Term>),
+    // raises a value of the exception type as exception (TAPL 14.3)
+    TmRaise(Box<Term>, Type),
+    // guarded term, the handler applied to a raised value and the
+    // exception type
+    TmCatch(Box<Term>, Box<Term>, Type),
+    // guarded term, the name the raised value is bound to in the handler,
+    // the exception type and the handler
+    TmHandle(Box<Term>, String, Type, Box<Term>),
+    // label, tagged term and the variant type
+    TmTag(String, Box<Term>, Type),
+    // the term cased on and a label, bound name and term per branch
+    TmCase(Box<Term>, Vec<(String, String, Term)>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    Syntax(SyntaxError),
+    UnboundVariable(String),
+    TrailingInput(String),
+}
+
+impl From<SyntaxError> for ParseError {
+    fn from(error: SyntaxError) -> Self {
+        ParseError::Syntax(error)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Syntax(error) => write!(f, "{}", error),
+            ParseError::UnboundVariable(name) => write!(f, "unbound variable {}", name),
+            ParseError::TrailingInput(input) => write!(f, "unexpected input `{}`", input),
+        }
+    }
+}
+
+pub type ParseResult = Result<Term, ParseError>;
+
+// Names of the enclosing binders, the innermost is at de Bruijn index 0,
+// and the exception type of the program
+#[derive(Default)]
+pub struct Parser {
+    names: Vec<String>,
+    exception_type: Option<Type>,
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A program may start with `exception T;`, which fixes T_exn for every
+    // `raise` and handler in it, and T_exn is Nat otherwise
+    pub fn parse(&mut self, input: &str) -> ParseResult {
+        let mut lexer = Lexer::new(input, &SYMBOLS);
+        let exception_type = match lexer.eat_keyword("exception")? {
+            Some(_) => {
+                let typ = parse_type(&mut lexer)?;
+                lexer.expect_symbol(";")?;
+                Some(typ)
+            }
+            None => None,
+        };
+        let term = parse_term(&mut lexer)?;
+        lexer.expect_symbol(";")?;
+        if !lexer.rest().trim().is_empty() {
+            return Err(ParseError::TrailingInput(lexer.rest().trim().to_string()));
+        }
+
+        self.exception_type = exception_type;
+        self.from_ast_term(&term)
+    }
+
+    fn exception_type(&self) -> Type {
+        self.exception_type.clone().unwrap_or(Type::Number)
+    }
+
+    fn with_binding<T>(&mut self, name: &str, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.names.push(name.to_string());
+        let result = f(self);
+        self.names.pop();
+        result
+    }
+
+    fn from_ast_term(self: &mut Parser, ast_term: &ASTTerm) -> ParseResult {
+        let term = match ast_term {
+            ASTTerm::TmTrue => Term::TmTrue,
+            ASTTerm::TmFalse => Term::TmFalse,
+            ASTTerm::TmZero => Term::TmZero,
+            ASTTerm::TmSucc(term) => Term::TmSucc(Box::new(self.from_ast_term(term)?)),
+            ASTTerm::TmPred(term) => Term::TmPred(Box::new(self.from_ast_term(term)?)),
+            ASTTerm::TmIsZero(term) => Term::TmIsZero(Box::new(self.from_ast_term(term)?)),
+            ASTTerm::TmVar(name) => match self.names.iter().rev().position(|n| n == name) {
+                Some(index) => Term::TmVar(index),
+                None => return Err(ParseError::UnboundVariable(name.clone())),
+            },
+            ASTTerm::TmAbs(name, typ, body) => {
+                let body = self.with_binding(name, |parser| parser.from_ast_term(body))?;
+                Term::TmAbs(name.clone(), typ.clone(), Box::new(body))
+            }
+            ASTTerm::TmApp(left, right) => Term::TmApp(
+                Box::new(self.from_ast_term(left)?),
+                Box::new(self.from_ast_term(right)?),
+            ),
+            ASTTerm::TmIf(if_term, then_term, else_term) => Term::TmIf(
+                Box::new(self.from_ast_term(if_term)?),
+                Box::new(self.from_ast_term(then_term)?),
+                Box::new(self.from_ast_term(else_term)?),
+            ),
+            ASTTerm::TmLet(name, bound, body) => {
+                let bound = self.from_ast_term(bound)?;
+                let body = self.with_binding(name, |parser| parser.from_ast_term(body))?;
+                Term::TmLet(name.clone(), Box::new(bound), Box::new(body))
+            }
+            ASTTerm::TmError => Term::TmError,
+            ASTTerm::TmTry(term, handler) => Term::TmTry(
+                Box::new(self.from_ast_term(term)?),
+                Box::new(self.from_ast_term(handler)?),
+            ),
+            ASTTerm::TmRaise(term) => {
+                Term::TmRaise(Box::new(self.from_ast_term(term)?), self.exception_type())
+            }
+            ASTTerm::TmCatch(term, handler) => Term::TmCatch(
+                Box::new(self.from_ast_term(term)?),
+                Box::new(self.from_ast_term(handler)?),
+                self.exception_type(),
+            ),
+            ASTTerm::TmHandle(term, name, handler) => {
+                let term = self.from_ast_term(term)?;
+                let handler = self.with_binding(name, |parser| parser.from_ast_term(handler))?;
+                Term::TmHandle(
+                    Box::new(term),
+                    name.clone(),
+                    self.exception_type(),
+                    Box::new(handler),
+                )
+            }
+            // a tag without a type is a value of the exception type
+            ASTTerm::TmTag(label, term, typ) => Term::TmTag(
+                label.clone(),
+                Box::new(self.from_ast_term(term)?),
+                typ.clone().unwrap_or_else(|| self.exception_type()),
+            ),
+            ASTTerm::TmCase(term, branches) => Term::TmCase(
+                Box::new(self.from_ast_term(term)?),
+                branches
+                    .iter()
+                    .map(|(label, name, branch)| {
+                        let branch =
+                            self.with_binding(name, |parser| parser.from_ast_term(branch))?;
+                        Ok((label.clone(), name.clone(), branch))
+                    })
+                    .collect::<Result<_, ParseError>>()?,
+            ),
+        };
+
+        Ok(term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Parser::new().parse("try (lambda x:Nat.error) 0 with succ(0);"),
+            Ok(Term::TmTry(
+                Box::new(Term::TmApp(
+                    Box::new(Term::TmAbs(
+                        "x".to_string(),
+                        Type::Number,
+                        Box::new(Term::TmError)
+                    )),
+                    Box::new(Term::TmZero)
+                )),
+                Box::new(Term::TmSucc(Box::new(Term::TmZero)))
+            ))
+        );
+        assert_eq!(
+            Parser::new().parse("try raise succ(0) catch lambda e:Nat.pred(e);"),
+            Ok(Term::TmCatch(
+                Box::new(Term::TmRaise(
+                    Box::new(Term::TmSucc(Box::new(Term::TmZero))),
+                    Type::Number
+                )),
+                Box::new(Term::TmAbs(
+                    "e".to_string(),
+                    Type::Number,
+                    Box::new(Term::TmPred(Box::new(Term::TmVar(0))))
+                )),
+                Type::Number
+            ))
+        );
+        // the declared exception type annotates every raise, handler and
+        // untyped tag
+        let exn = Type::Variant(vec![
+            ("overflow".to_string(), Type::Number),
+            ("negative".to_string(), Type::Boolean),
+        ]);
+        assert_eq!(
+            Parser::new().parse(
+                "exception <overflow:Nat, negative:Bool>; \
+                 try raise <negative=true> handle e => case e of \
+                 <overflow=n> => n | <negative=b> => 0;"
+            ),
+            Ok(Term::TmHandle(
+                Box::new(Term::TmRaise(
+                    Box::new(Term::TmTag(
+                        "negative".to_string(),
+                        Box::new(Term::TmTrue),
+                        exn.clone()
+                    )),
+                    exn.clone()
+                )),
+                "e".to_string(),
+                exn,
+                Box::new(Term::TmCase(
+                    Box::new(Term::TmVar(0)),
+                    vec![
+                        ("overflow".to_string(), "n".to_string(), Term::TmVar(0)),
+                        ("negative".to_string(), "b".to_string(), Term::TmZero),
+                    ]
+                ))
+            ))
+        );
+    }
+    #[test]
+    fn test_words() {
+        let parse = |input: &str| {
+            Parser::new()
+                .parse(input)
+                .map_err(|error| error.to_string())
+        };
+        // names are whole words of any length, keywords are not split off them
+        assert_eq!(
+            parse("lambda errors:Nat.errors;"),
+            Ok(Term::TmAbs(
+                "errors".to_string(),
+                Type::Number,
+                Box::new(Term::TmVar(0))
+            ))
+        );
+        assert_eq!(
+            parse("lambda try:Nat.0;"),
+            Err("1:8: expected a variable, found `try`".to_string())
+        );
+        assert_eq!(
+            parse("try error cath 0;"),
+            Err("1:17: expected `with`, `catch` or `handle`, found `;`".to_string())
+        );
+        assert_eq!(
+            parse("try error;"),
+            Err("1:10: expected `with`, `catch` or `handle`, found `;`".to_string())
+        );
+        assert_eq!(
+            parse("case <a=0> as <a:Nat> of <a=x> x;"),
+            Err("1:32: expected `=>`, found `x`".to_string())
+        );
+        // nesting is bounded instead of overflowing the stack
+        let deep = format!("{}0;", "raise ".repeat(300));
+        assert!(parse(&deep)
+            .unwrap_err()
+            .contains("at most 256 nested terms"));
+    }
+}
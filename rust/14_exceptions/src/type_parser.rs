@@ -0,0 +1,49 @@
+use tapl_frontend::lexer::{Lexer, SyntaxError, Token};
+
+use crate::typing::Type;
+
+pub const TYPE_KEYWORDS: [&str; 3] = ["Bool", "Nat", "Bot"];
+
+// `l:T, m:U>` after the `<`
+fn parse_variant_type(lexer: &mut Lexer) -> Result<Type, SyntaxError> {
+    let mut fields = vec![];
+    loop {
+        let (label, _) = lexer.expect_word("a label", &[])?;
+        lexer.expect_symbol(":")?;
+        fields.push((label.to_string(), parse_type(lexer)?));
+        if lexer.eat_symbol(",")?.is_none() {
+            break;
+        }
+    }
+    lexer.expect_symbol(">")?;
+    Ok(Type::Variant(fields))
+}
+
+fn parse_atom_type(lexer: &mut Lexer) -> Result<Type, SyntaxError> {
+    lexer.nested(|lexer| {
+        let (token, span) = lexer.next_token()?;
+        match token {
+            Token::Word("Bool") => Ok(Type::Boolean),
+            Token::Word("Nat") => Ok(Type::Number),
+            Token::Word("Bot") => Ok(Type::Bot),
+            Token::Symbol("<") => parse_variant_type(lexer),
+            Token::Symbol("(") => {
+                let typ = parse_type(lexer)?;
+                lexer.expect_symbol(")")?;
+                Ok(typ)
+            }
+            _ => Err(lexer.error_at("a type", &token.to_string(), span)),
+        }
+    })
+}
+
+// arrow types are right associative: `A->B->C` is `A->(B->C)`
+pub fn parse_type(lexer: &mut Lexer) -> Result<Type, SyntaxError> {
+    lexer.nested(|lexer| {
+        let typ = parse_atom_type(lexer)?;
+        match lexer.eat_symbol("->")? {
+            Some(_) => Ok(Type::Arrow(Box::new(typ), Box::new(parse_type(lexer)?))),
+            None => Ok(typ),
+        }
+    })
+}
@@ -0,0 +1,342 @@
+use std::fmt::{self, Formatter};
+
+use crate::{context::Context, parser::Term};
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum TypeError {
+    ParameterTypeMismatch,
+    ArrowTypeExpected,
+    NumberExpected,
+    IfConditionNotBoolean,
+    IfBranchesMismatch,
+    UnboundVariable(usize),
+    HandlerTypeMismatch,
+    ExceptionTypeMismatch,
+    VariantTypeExpected,
+    LabelNotFound(String),
+    TagTypeMismatch(String),
+    MissingBranch(String),
+    CaseBranchesMismatch,
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeError::ParameterTypeMismatch => write!(f, "parameter type mismatch"),
+            TypeError::ArrowTypeExpected => write!(f, "arrow type expected"),
+            TypeError::NumberExpected => write!(f, "argument is not a number"),
+            TypeError::IfConditionNotBoolean => write!(f, "guard of conditional not a boolean"),
+            TypeError::IfBranchesMismatch => {
+                write!(f, "arms of conditional have different types")
+            }
+            TypeError::UnboundVariable(index) => write!(f, "unbound variable index {}", index),
+            TypeError::HandlerTypeMismatch => {
+                write!(f, "handler does not match the type of the guarded term")
+            }
+            TypeError::ExceptionTypeMismatch => {
+                write!(f, "raised value is not of the exception type")
+            }
+            TypeError::VariantTypeExpected => write!(f, "variant type expected"),
+            TypeError::LabelNotFound(label) => write!(f, "label {} not found", label),
+            TypeError::TagTypeMismatch(label) => {
+                write!(f, "value tagged {} does not match the variant type", label)
+            }
+            TypeError::MissingBranch(label) => write!(f, "case has no branch for {}", label),
+            TypeError::CaseBranchesMismatch => write!(f, "branches of case have different types"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum Type {
+    Boolean,
+    Number,
+    Arrow(Box<Type>, Box<Type>),
+    // labeled alternatives in declaration order
+    Variant(Vec<(String, Type)>),
+    // the type of `error` and `raise t`, a subtype of every type, so an
+    // exception can stand where any type is expected (TAPL 14.1)
+    Bot,
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Boolean => write!(f, "Bool"),
+            Type::Number => write!(f, "Nat"),
+            Type::Arrow(param, ret) => match param.as_ref() {
+                Type::Arrow(_, _) => write!(f, "({})->{}", param, ret),
+                _ => write!(f, "{}->{}", param, ret),
+            },
+            Type::Variant(fields) => {
+                write!(f, "<")?;
+                for (i, (label, typ)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}:{}", label, typ)?;
+                }
+                write!(f, ">")
+            }
+            Type::Bot => write!(f, "Bot"),
+        }
+    }
+}
+
+// S <: T, arrows are contravariant in the parameter
+pub fn is_subtype(s: &Type, t: &Type) -> bool {
+    match (s, t) {
+        (Type::Bot, _) => true,
+        (Type::Arrow(s1, s2), Type::Arrow(t1, t2)) => is_subtype(t1, s1) && is_subtype(s2, t2),
+        _ => s == t,
+    }
+}
+
+// the least common supertype, used for the arms of conditionals and handlers
+pub fn join(s: &Type, t: &Type) -> Option<Type> {
+    match (s, t) {
+        (Type::Bot, t) => Some(t.clone()),
+        (s, Type::Bot) => Some(s.clone()),
+        (Type::Arrow(s1, s2), Type::Arrow(t1, t2)) => Some(Type::Arrow(
+            Box::new(meet(s1, t1)?),
+            Box::new(join(s2, t2)?),
+        )),
+        _ if s == t => Some(s.clone()),
+        _ => None,
+    }
+}
+
+// the greatest common subtype
+pub fn meet(s: &Type, t: &Type) -> Option<Type> {
+    match (s, t) {
+        (Type::Bot, _) | (_, Type::Bot) => Some(Type::Bot),
+        (Type::Arrow(s1, s2), Type::Arrow(t1, t2)) => Some(Type::Arrow(
+            Box::new(join(s1, t1)?),
+            Box::new(meet(s2, t2)?),
+        )),
+        _ if s == t => Some(s.clone()),
+        _ => None,
+    }
+}
+
+fn expect_number(typ: Type) -> Result<(), TypeError> {
+    if is_subtype(&typ, &Type::Number) {
+        Ok(())
+    } else {
+        Err(TypeError::NumberExpected)
+    }
+}
+
+pub fn type_of(ctx: &mut Context, term: &Term) -> Result<Type, TypeError> {
+    match term {
+        Term::TmTrue | Term::TmFalse => Ok(Type::Boolean),
+        Term::TmZero => Ok(Type::Number),
+        Term::TmSucc(term) | Term::TmPred(term) => {
+            expect_number(type_of(ctx, term)?)?;
+            Ok(Type::Number)
+        }
+        Term::TmIsZero(term) => {
+            expect_number(type_of(ctx, term)?)?;
+            Ok(Type::Boolean)
+        }
+        Term::TmVar(index) => ctx
+            .get_type(*index)
+            .ok_or(TypeError::UnboundVariable(*index)),
+        Term::TmAbs(name, typ, body) => {
+            ctx.push(name.clone(), typ.clone());
+            let body_type = type_of(ctx, body);
+            ctx.pop();
+            Ok(Type::Arrow(Box::new(typ.clone()), Box::new(body_type?)))
+        }
+        Term::TmApp(left, right) => {
+            let left_type = type_of(ctx, left)?;
+            let right_type = type_of(ctx, right)?;
+            match left_type {
+                // applying an exception never returns
+                Type::Bot => Ok(Type::Bot),
+                Type::Arrow(param_type, return_type) if is_subtype(&right_type, &param_type) => {
+                    Ok(*return_type)
+                }
+                Type::Arrow(_, _) => Err(TypeError::ParameterTypeMismatch),
+                _ => Err(TypeError::ArrowTypeExpected),
+            }
+        }
+        Term::TmIf(if_term, then_term, else_term) => {
+            if !is_subtype(&type_of(ctx, if_term)?, &Type::Boolean) {
+                return Err(TypeError::IfConditionNotBoolean);
+            }
+            let then_type = type_of(ctx, then_term)?;
+            let else_type = type_of(ctx, else_term)?;
+            join(&then_type, &else_type).ok_or(TypeError::IfBranchesMismatch)
+        }
+        Term::TmLet(name, bound, body) => {
+            let bound_type = type_of(ctx, bound)?;
+            ctx.push(name.clone(), bound_type);
+            let body_type = type_of(ctx, body);
+            ctx.pop();
+            body_type
+        }
+        Term::TmError => Ok(Type::Bot),
+        // T-Try: the handler replaces the guarded term, so they share a type
+        Term::TmTry(term, handler) => {
+            let term_type = type_of(ctx, term)?;
+            let handler_type = type_of(ctx, handler)?;
+            join(&term_type, &handler_type).ok_or(TypeError::HandlerTypeMismatch)
+        }
+        // T-Exn of TAPL 14.3: the raised value has the exception type T_exn
+        Term::TmRaise(term, exception_type) => {
+            if !is_subtype(&type_of(ctx, term)?, exception_type) {
+                return Err(TypeError::ExceptionTypeMismatch);
+            }
+            Ok(Type::Bot)
+        }
+        // T-Try of TAPL 14.3: the handler is a function from T_exn
+        Term::TmCatch(term, handler, exception_type) => {
+            let term_type = type_of(ctx, term)?;
+            let handler_type = match type_of(ctx, handler)? {
+                Type::Bot => Type::Bot,
+                Type::Arrow(param_type, return_type) if is_subtype(exception_type, &param_type) => {
+                    *return_type
+                }
+                _ => return Err(TypeError::HandlerTypeMismatch),
+            };
+            join(&term_type, &handler_type).ok_or(TypeError::HandlerTypeMismatch)
+        }
+        // T-Try with the raised value bound to x : T_exn in the handler
+        Term::TmHandle(term, name, exception_type, handler) => {
+            let term_type = type_of(ctx, term)?;
+            ctx.push(name.clone(), exception_type.clone());
+            let handler_type = type_of(ctx, handler);
+            ctx.pop();
+            join(&term_type, &handler_type?).ok_or(TypeError::HandlerTypeMismatch)
+        }
+        // T-Variant (TAPL 11.10)
+        Term::TmTag(label, term, typ) => {
+            let fields = match typ {
+                Type::Variant(fields) => fields,
+                _ => return Err(TypeError::VariantTypeExpected),
+            };
+            let field_type = fields
+                .iter()
+                .find(|(name, _)| name == label)
+                .map(|(_, typ)| typ)
+                .ok_or_else(|| TypeError::LabelNotFound(label.clone()))?;
+            if !is_subtype(&type_of(ctx, term)?, field_type) {
+                return Err(TypeError::TagTypeMismatch(label.clone()));
+            }
+            Ok(typ.clone())
+        }
+        // T-Case: a branch for every label, and the branches join
+        Term::TmCase(term, branches) => {
+            let fields = match type_of(ctx, term)? {
+                Type::Variant(fields) => fields,
+                _ => return Err(TypeError::VariantTypeExpected),
+            };
+            if let Some((label, _)) = fields
+                .iter()
+                .find(|(label, _)| !branches.iter().any(|(name, _, _)| name == label))
+            {
+                return Err(TypeError::MissingBranch(label.clone()));
+            }
+            let mut case_type = Type::Bot;
+            for (label, name, branch) in branches {
+                let field_type = fields
+                    .iter()
+                    .find(|(field, _)| field == label)
+                    .map(|(_, typ)| typ.clone())
+                    .ok_or_else(|| TypeError::LabelNotFound(label.clone()))?;
+                ctx.push(name.clone(), field_type);
+                let branch_type = type_of(ctx, branch);
+                ctx.pop();
+                case_type =
+                    join(&case_type, &branch_type?).ok_or(TypeError::CaseBranchesMismatch)?;
+            }
+            Ok(case_type)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn type_str(input: &str) -> Result<Type, TypeError> {
+        let term = Parser::new().parse(input).unwrap();
+        type_of(&mut Context::default(), &term)
+    }
+
+    #[test]
+    fn test_type_of() {
+        let nat_to_nat = Type::Arrow(Box::new(Type::Number), Box::new(Type::Number));
+        assert_eq!(type_str("error;"), Ok(Type::Bot));
+        // `error` fits wherever a term of any type is expected
+        assert_eq!(type_str("succ(error);"), Ok(Type::Number));
+        assert_eq!(type_str("if error then 0 else error;"), Ok(Type::Number));
+        assert_eq!(type_str("(lambda f:Nat->Nat.f 0) error;"), Ok(Type::Number));
+        assert_eq!(
+            type_str("if true then lambda x:Nat.x else error;"),
+            Ok(nat_to_nat)
+        );
+        assert_eq!(type_str("error true;"), Ok(Type::Bot));
+        assert_eq!(type_str("try error with true;"), Ok(Type::Boolean));
+        assert_eq!(
+            type_str("try 0 with true;"),
+            Err(TypeError::HandlerTypeMismatch)
+        );
+        assert_eq!(
+            type_str("raise iszero(0);"),
+            Err(TypeError::ExceptionTypeMismatch)
+        );
+        assert_eq!(
+            type_str("try raise 0 catch lambda e:Nat.iszero(e);"),
+            Ok(Type::Boolean)
+        );
+        assert_eq!(
+            type_str("try 0 catch lambda e:Bool.0;"),
+            Err(TypeError::HandlerTypeMismatch)
+        );
+    }
+
+    #[test]
+    fn test_exception_type() {
+        let exn = "exception <divideByZero:Nat, notFound:Bool>; ";
+        let typ = |input: &str| type_str(&format!("{}{}", exn, input));
+        assert_eq!(
+            typ("lambda x:Nat.if iszero(x) then raise <divideByZero=0> else x;"),
+            Ok(Type::Arrow(Box::new(Type::Number), Box::new(Type::Number)))
+        );
+        // the handler sees the raised value at the exception type
+        assert_eq!(
+            typ("try raise <notFound=true> handle e => \
+                 case e of <divideByZero=n> => iszero(n) | <notFound=b> => b;"),
+            Ok(Type::Boolean)
+        );
+        assert_eq!(typ("raise 0;"), Err(TypeError::ExceptionTypeMismatch));
+        assert_eq!(
+            typ("raise <notFound=0>;"),
+            Err(TypeError::TagTypeMismatch("notFound".to_string()))
+        );
+        assert_eq!(
+            typ("raise <overflow=0>;"),
+            Err(TypeError::LabelNotFound("overflow".to_string()))
+        );
+        assert_eq!(
+            typ("try 0 handle e => case e of <divideByZero=n> => n;"),
+            Err(TypeError::MissingBranch("notFound".to_string()))
+        );
+        assert_eq!(
+            typ("try 0 handle e => case e of <divideByZero=n> => n | <notFound=b> => b;"),
+            Err(TypeError::CaseBranchesMismatch)
+        );
+        assert_eq!(
+            typ("try 0 handle e => e;"),
+            Err(TypeError::HandlerTypeMismatch)
+        );
+        // without a declaration exceptions carry numbers
+        assert_eq!(
+            type_str("try raise succ(0) handle e => pred(e);"),
+            Ok(Type::Number)
+        );
+    }
+}
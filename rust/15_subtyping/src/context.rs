@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+
+use crate::typing::Type;
+
+// Typing context, the innermost binding is at de Bruijn index 0. It also
+// remembers the subtype judgments decided so far, they do not depend on the
+// bindings, so a context reused for several terms checks a pair once.
+#[derive(Clone, Debug, Default)]
+pub struct Context {
+    bindings: Vec<(String, Type)>,
+    // keyed by S and then by T, so a lookup borrows both instead of
+    // building a pair
+    subtypes: HashMap<Type, HashMap<Type, bool>>,
+}
+
+impl Context {
+    pub fn push(&mut self, name: String, typ: Type) {
+        self.bindings.push((name, typ));
+    }
+
+    pub fn pop(&mut self) {
+        self.bindings.pop();
+    }
+
+    pub fn get_type(&self, index: usize) -> Option<Type> {
+        self.bindings
+            .iter()
+            .rev()
+            .nth(index)
+            .map(|(_, typ)| typ.clone())
+    }
+
+    // whether S <: T, if it was decided before
+    pub fn known_subtype(&self, s: &Type, t: &Type) -> Option<bool> {
+        self.subtypes.get(s)?.get(t).copied()
+    }
+
+    pub fn record_subtype(&mut self, s: &Type, t: &Type, holds: bool) {
+        match self.subtypes.get_mut(s) {
+            Some(supertypes) => {
+                supertypes.insert(t.clone(), holds);
+            }
+            None => {
+                self.subtypes
+                    .insert(s.clone(), HashMap::from([(t.clone(), holds)]));
+            }
+        }
+    }
+
+    // the number of subtype judgments remembered
+    pub fn known_subtypes(&self) -> usize {
+        self.subtypes.values().map(HashMap::len).sum()
+    }
+}
@@ -0,0 +1,137 @@
+use tapl_frontend::outcome::Outcome;
+
+use crate::{parser::Term, substitute::substitute_top};
+
+fn is_numeric_val(term: &Term) -> bool {
+    match term {
+        Term::TmZero => true,
+        Term::TmSucc(term) => is_numeric_val(term),
+        _ => false,
+    }
+}
+
+pub fn is_val(term: &Term) -> bool {
+    match term {
+        Term::TmTrue | Term::TmFalse | Term::TmAbs(_, _, _) => true,
+        Term::TmRecord(fields) => fields.iter().all(|(_, term)| is_val(term)),
+        _ => is_numeric_val(term),
+    }
+}
+
+// subtyping does not change evaluation, a record passed where fewer fields
+// are expected keeps all of them at runtime
+pub fn eval1(term: &Term) -> Option<Term> {
+    let boxed = |term: Option<Term>| term.map(Box::new);
+    match term {
+        Term::TmSucc(term) => Some(Term::TmSucc(boxed(eval1(term))?)),
+        Term::TmPred(term) => match term.as_ref() {
+            Term::TmZero => Some(Term::TmZero),
+            Term::TmSucc(number) if is_numeric_val(number) => Some(number.as_ref().clone()),
+            _ => Some(Term::TmPred(boxed(eval1(term))?)),
+        },
+        Term::TmIsZero(term) => match term.as_ref() {
+            Term::TmZero => Some(Term::TmTrue),
+            Term::TmSucc(number) if is_numeric_val(number) => Some(Term::TmFalse),
+            _ => Some(Term::TmIsZero(boxed(eval1(term))?)),
+        },
+        Term::TmApp(left, right) => match left.as_ref() {
+            Term::TmAbs(_, _, body) if is_val(right) => Some(substitute_top(right, body)),
+            _ if is_val(left) => Some(Term::TmApp(left.clone(), boxed(eval1(right))?)),
+            _ => Some(Term::TmApp(boxed(eval1(left))?, right.clone())),
+        },
+        Term::TmIf(if_term, then_term, else_term) => match if_term.as_ref() {
+            Term::TmTrue => Some(then_term.as_ref().clone()),
+            Term::TmFalse => Some(else_term.as_ref().clone()),
+            _ => Some(Term::TmIf(
+                boxed(eval1(if_term))?,
+                then_term.clone(),
+                else_term.clone(),
+            )),
+        },
+        Term::TmLet(name, bound, body) => {
+            if is_val(bound) {
+                Some(substitute_top(bound, body))
+            } else {
+                Some(Term::TmLet(
+                    name.clone(),
+                    boxed(eval1(bound))?,
+                    body.clone(),
+                ))
+            }
+        }
+        Term::TmRecord(fields) => match fields.iter().position(|(_, term)| !is_val(term)) {
+            // evaluate the fields from left to right
+            Some(i) => {
+                let mut fields = fields.clone();
+                fields[i].1 = eval1(&fields[i].1)?;
+                Some(Term::TmRecord(fields))
+            }
+            None => None,
+        },
+        Term::TmProj(term, label) => match term.as_ref() {
+            Term::TmRecord(fields) if is_val(term) => fields
+                .iter()
+                .find(|(name, _)| name == label)
+                .map(|(_, term)| term.clone()),
+            _ => Some(Term::TmProj(boxed(eval1(term))?, label.clone())),
+        },
+        _ => None,
+    }
+}
+
+// takes small steps until no rule applies, a normal form that is not a
+// value is stuck
+pub fn eval(term: &Term) -> Outcome<Term, Term> {
+    let mut term = term.clone();
+    while let Some(next_term) = eval1(&term) {
+        term = next_term;
+    }
+    if is_val(&term) {
+        Outcome::Value(term)
+    } else {
+        Outcome::Stuck(term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::Subtyping;
+    use crate::parser::Parser;
+    use tapl_testkit::{assert_evals_to, assert_type_error};
+
+    #[test]
+    fn test_eval() {
+        assert_evals_to!(
+            Subtyping,
+            "(lambda r:{x:Nat}.r.x) {y=true, x=succ(0)};",
+            "succ(0)"
+        );
+        assert_evals_to!(
+            Subtyping,
+            "let f = lambda r:{x:Nat}.r in f {x=0, y=if true then false else true};",
+            "{x=0, y=false}"
+        );
+        assert_evals_to!(
+            Subtyping,
+            "(if false then {x=0, y=true} else {x=succ(0)}).x;",
+            "succ(0)"
+        );
+        assert_type_error!(
+            Subtyping,
+            "(if false then {x=0, y=true} else {x=succ(0)}).y;",
+            "label y"
+        );
+    }
+    #[test]
+    fn test_eval_outcome() {
+        let term = |input: &str| Parser::new().parse(input).unwrap();
+        assert_eq!(
+            eval(&term("iszero(succ(0));")),
+            Outcome::Value(Term::TmFalse)
+        );
+        // an ill-typed term stops at a normal form that is not a value
+        let stuck = term("if 0 then true else false;");
+        assert_eq!(eval(&stuck), Outcome::Stuck(stuck));
+    }
+}
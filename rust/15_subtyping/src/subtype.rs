@@ -0,0 +1,265 @@
+use crate::{context::Context, typing::Type};
+
+pub fn field<'a, T>(fields: &'a [(String, T)], label: &str) -> Option<&'a T> {
+    fields
+        .iter()
+        .find(|(name, _)| name == label)
+        .map(|(_, typ)| typ)
+}
+
+// What the structural subtyping rules see of a type, so that a later
+// calculus with types of its own (chapter 18 adds Unit and Ref) checks
+// S <: T with the same rules
+pub enum Shape<'a, T> {
+    Top,
+    Bot,
+    Arrow(&'a T, &'a T),
+    Record(&'a [(String, T)]),
+    // S-Ref: a reference is read and written, so it is invariant (TAPL 15.5)
+    Ref(&'a T),
+    // a subtype of Top and of itself only
+    Base,
+}
+
+pub trait Subtype: PartialEq + Sized {
+    fn shape(&self) -> Shape<'_, Self>;
+}
+
+impl Subtype for Type {
+    fn shape(&self) -> Shape<'_, Type> {
+        match self {
+            Type::Top => Shape::Top,
+            Type::Bot => Shape::Bot,
+            Type::Arrow(param, ret) => Shape::Arrow(param, ret),
+            Type::Record(fields) => Shape::Record(fields),
+            Type::Boolean | Type::Number => Shape::Base,
+        }
+    }
+}
+
+// Algorithmic subtyping S <: T (TAPL 16.1): reflexivity and transitivity
+// are built in, so only the structural rules remain. `sub` decides the
+// premises, which lets the caller remember judgments.
+pub fn subtype_with<T: Subtype>(s: &T, t: &T, mut sub: impl FnMut(&T, &T) -> bool) -> bool {
+    match (s.shape(), t.shape()) {
+        (_, Shape::Top) => true,
+        // S-Bot
+        (Shape::Bot, _) => true,
+        // S-Arrow: contravariant in the parameter, covariant in the result
+        (Shape::Arrow(s1, s2), Shape::Arrow(t1, t2)) => sub(t1, s1) && sub(s2, t2),
+        // S-Rcd: width, depth and permutation at once, every field of T must
+        // be in S with a subtype
+        (Shape::Record(s_fields), Shape::Record(t_fields)) => {
+            t_fields.iter().all(|(label, t_field)| {
+                field(s_fields, label).is_some_and(|s_field| sub(s_field, t_field))
+            })
+        }
+        (Shape::Ref(s), Shape::Ref(t)) => sub(s, t) && sub(t, s),
+        _ => s == t,
+    }
+}
+
+// S <: T for the types of this chapter, every judgment on the way is
+// remembered in `ctx` and looked up the next time it comes up
+pub fn subtype(ctx: &mut Context, s: &Type, t: &Type) -> bool {
+    if let Some(holds) = ctx.known_subtype(s, t) {
+        return holds;
+    }
+    let holds = subtype_with(s, t, |s, t| subtype(ctx, s, t));
+    ctx.record_subtype(s, t, holds);
+    holds
+}
+
+// S ∨ T, the least common supertype, which always exists thanks to Top
+// (TAPL 16.3)
+pub fn join(ctx: &mut Context, s: &Type, t: &Type) -> Type {
+    match (s, t) {
+        _ if subtype(ctx, s, t) => t.clone(),
+        _ if subtype(ctx, t, s) => s.clone(),
+        (Type::Arrow(s1, s2), Type::Arrow(t1, t2)) => {
+            let param = meet(ctx, s1, t1);
+            Type::Arrow(Box::new(param), Box::new(join(ctx, s2, t2)))
+        }
+        // the labels both records have, in the order of the first one
+        (Type::Record(s_fields), Type::Record(t_fields)) => Type::Record(
+            s_fields
+                .iter()
+                .filter_map(|(label, s_field)| {
+                    field(t_fields, label)
+                        .map(|t_field| (label.clone(), join(ctx, s_field, t_field)))
+                })
+                .collect(),
+        ),
+        _ => Type::Top,
+    }
+}
+
+// S ∧ T, the greatest common subtype, Bot for types with nothing else in
+// common such as Bool and Nat (TAPL 16.4)
+pub fn meet(ctx: &mut Context, s: &Type, t: &Type) -> Type {
+    match (s, t) {
+        _ if subtype(ctx, s, t) => s.clone(),
+        _ if subtype(ctx, t, s) => t.clone(),
+        (Type::Arrow(s1, s2), Type::Arrow(t1, t2)) => {
+            Type::Arrow(Box::new(join(ctx, s1, t1)), Box::new(meet(ctx, s2, t2)))
+        }
+        // every label of either record, common labels meet
+        (Type::Record(s_fields), Type::Record(t_fields)) => {
+            let mut fields = vec![];
+            for (label, s_field) in s_fields {
+                match field(t_fields, label) {
+                    Some(t_field) => fields.push((label.clone(), meet(ctx, s_field, t_field))),
+                    None => fields.push((label.clone(), s_field.clone())),
+                }
+            }
+            for (label, t_field) in t_fields {
+                if field(s_fields, label).is_none() {
+                    fields.push((label.clone(), t_field.clone()));
+                }
+            }
+            Type::Record(fields)
+        }
+        _ => Type::Bot,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tapl_frontend::lexer::Lexer;
+
+    use crate::{parser::SYMBOLS, type_parser::parse_type};
+
+    fn typ(input: &str) -> Type {
+        let mut lexer = Lexer::new(input, &SYMBOLS);
+        let typ = parse_type(&mut lexer).unwrap();
+        assert_eq!(lexer.rest(), "");
+        typ
+    }
+
+    #[test]
+    fn test_subtype() {
+        let mut ctx = Context::default();
+        // width
+        assert!(subtype(&mut ctx, &typ("{x:Nat, y:Bool}"), &typ("{x:Nat}")));
+        assert!(!subtype(&mut ctx, &typ("{x:Nat}"), &typ("{x:Nat, y:Bool}")));
+        // depth
+        assert!(subtype(
+            &mut ctx,
+            &typ("{a:{x:Nat, y:Nat}}"),
+            &typ("{a:{x:Nat}}")
+        ));
+        // permutation
+        assert!(subtype(
+            &mut ctx,
+            &typ("{x:Nat, y:Bool}"),
+            &typ("{y:Bool, x:Nat}")
+        ));
+        assert!(subtype(
+            &mut ctx,
+            &typ("{x:Nat}->Nat"),
+            &typ("{x:Nat, y:Nat}->Top")
+        ));
+        assert!(!subtype(
+            &mut ctx,
+            &typ("{x:Nat, y:Nat}->Nat"),
+            &typ("{x:Nat}->Nat")
+        ));
+        assert!(subtype(&mut ctx, &typ("Bool"), &typ("Top")));
+        assert!(!subtype(&mut ctx, &typ("Top"), &typ("Bool")));
+
+        assert_eq!(
+            join(
+                &mut ctx,
+                &typ("{x:Nat, y:Bool}"),
+                &typ("{y:Nat, x:Nat, z:Nat}")
+            ),
+            typ("{x:Nat, y:Top}")
+        );
+        assert_eq!(join(&mut ctx, &typ("Bool"), &typ("Nat")), Type::Top);
+        assert_eq!(
+            join(&mut ctx, &typ("{x:Nat}->Bool"), &typ("{y:Nat}->Bool")),
+            typ("{x:Nat, y:Nat}->Bool")
+        );
+        assert_eq!(
+            meet(&mut ctx, &typ("{x:Nat, y:Top}"), &typ("{y:Bool, z:Nat}")),
+            typ("{x:Nat, y:Bool, z:Nat}")
+        );
+        assert_eq!(
+            meet(&mut ctx, &typ("{x:Bool}"), &typ("{x:Nat}")),
+            typ("{x:Bot}")
+        );
+        assert_eq!(meet(&mut ctx, &typ("Bool"), &typ("Nat")), Type::Bot);
+        assert!(subtype(&mut ctx, &typ("Bot"), &typ("{x:Nat}->Bool")));
+        assert!(!subtype(&mut ctx, &typ("Top"), &typ("Bot")));
+        // the parameter types always meet now, if only at Bot
+        assert_eq!(
+            join(&mut ctx, &typ("Bool->Nat"), &typ("Nat->Nat")),
+            typ("Bot->Nat")
+        );
+    }
+
+    // TAPL 16.3.2: S ∨ T is an upper bound of S and T below every other
+    // one, and dually for S ∧ T, checked over every pair of a sample of types
+    #[test]
+    fn test_join_and_meet_are_bounds() {
+        let sample = [
+            "Top",
+            "Bot",
+            "Bool",
+            "Nat",
+            "{}",
+            "{x:Nat}",
+            "{x:Top}",
+            "{y:Bool}",
+            "{x:Nat, y:Bool}",
+            "{x:Bool, y:Nat}",
+            "Nat->Bool",
+            "{x:Nat}->Nat",
+            "{y:Bool}->{x:Nat}",
+            "{}->{}",
+            "Top->Bot",
+        ]
+        .map(typ);
+        let mut ctx = Context::default();
+        for s in &sample {
+            for t in &sample {
+                let join = join(&mut ctx, s, t);
+                let meet = meet(&mut ctx, s, t);
+                for (bound, upper) in [(&join, true), (&meet, false)] {
+                    let below = |ctx: &mut Context, u: &Type, v: &Type| {
+                        if upper {
+                            subtype(ctx, u, v)
+                        } else {
+                            subtype(ctx, v, u)
+                        }
+                    };
+                    assert!(below(&mut ctx, s, bound), "{} and {}: {}", s, t, bound);
+                    assert!(below(&mut ctx, t, bound), "{} and {}: {}", s, t, bound);
+                    for u in &sample {
+                        if below(&mut ctx, s, u) && below(&mut ctx, t, u) {
+                            assert!(below(&mut ctx, bound, u), "{} and {}: {}", s, t, u);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_known_subtypes() {
+        let mut ctx = Context::default();
+        let s = typ("{x:Nat}->{a:Nat, b:Nat}");
+        let t = typ("{x:Nat, y:Bool}->{a:Top}");
+        assert!(subtype(&mut ctx, &s, &t));
+        // the arrows, both records and Nat <: Nat and Nat <: Top
+        assert_eq!(ctx.known_subtypes(), 5);
+        assert_eq!(
+            ctx.known_subtype(&typ("{x:Nat, y:Bool}"), &typ("{x:Nat}")),
+            Some(true)
+        );
+        assert!(subtype(&mut ctx, &s, &t));
+        assert!(!subtype(&mut ctx, &t, &s));
+        assert_eq!(ctx.known_subtype(&t, &s), Some(false));
+    }
+}
@@ -0,0 +1,97 @@
+use tapl_frontend::lexer::{Lexer, SyntaxError, Token};
+
+use crate::typing::Type;
+
+pub const TYPE_KEYWORDS: [&str; 4] = ["Top", "Bot", "Bool", "Nat"];
+
+// `{x:Nat, y:Bool}` after the `{`, the empty record type `{}` is a supertype
+// of every record
+fn parse_record_type(lexer: &mut Lexer) -> Result<Type, SyntaxError> {
+    let mut fields = vec![];
+    if lexer.eat_symbol("}")?.is_none() {
+        loop {
+            let (label, _) = lexer.expect_word("a label", &[])?;
+            lexer.expect_symbol(":")?;
+            fields.push((label.to_string(), parse_type(lexer)?));
+            if lexer.eat_symbol(",")?.is_none() {
+                break;
+            }
+        }
+        lexer.expect_symbol("}")?;
+    }
+    Ok(Type::Record(fields))
+}
+
+fn parse_atom_type(lexer: &mut Lexer) -> Result<Type, SyntaxError> {
+    lexer.nested(|lexer| {
+        let (token, span) = lexer.next_token()?;
+        match token {
+            Token::Word("Top") => Ok(Type::Top),
+            Token::Word("Bot") => Ok(Type::Bot),
+            Token::Word("Bool") => Ok(Type::Boolean),
+            Token::Word("Nat") => Ok(Type::Number),
+            Token::Symbol("{") => parse_record_type(lexer),
+            Token::Symbol("(") => {
+                let typ = parse_type(lexer)?;
+                lexer.expect_symbol(")")?;
+                Ok(typ)
+            }
+            _ => Err(lexer.error_at("a type", &token.to_string(), span)),
+        }
+    })
+}
+
+// arrow types are right associative: `A->B->C` is `A->(B->C)`
+pub fn parse_type(lexer: &mut Lexer) -> Result<Type, SyntaxError> {
+    lexer.nested(|lexer| {
+        let typ = parse_atom_type(lexer)?;
+        match lexer.eat_symbol("->")? {
+            Some(_) => Ok(Type::Arrow(Box::new(typ), Box::new(parse_type(lexer)?))),
+            None => Ok(typ),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SYMBOLS;
+
+    fn parse(input: &str) -> Result<(&str, Type), String> {
+        let mut lexer = Lexer::new(input, &SYMBOLS);
+        parse_type(&mut lexer)
+            .map(|typ| (lexer.rest(), typ))
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn test_type() {
+        assert_eq!(
+            parse("{x:Nat, y:Bot}->Top->Bool"),
+            Ok((
+                "",
+                Type::Arrow(
+                    Box::new(Type::Record(vec![
+                        ("x".to_string(), Type::Number),
+                        ("y".to_string(), Type::Bot)
+                    ])),
+                    Box::new(Type::Arrow(Box::new(Type::Top), Box::new(Type::Boolean)))
+                )
+            ))
+        );
+        assert_eq!(parse("{}"), Ok(("", Type::Record(vec![]))));
+        // type names are whole words
+        assert_eq!(
+            parse("Topmost").unwrap_err(),
+            "1:1: expected a type, found `Topmost`"
+        );
+        assert_eq!(
+            parse("{x:Nat y:Nat}").unwrap_err(),
+            "1:8: expected `}`, found `y`"
+        );
+        let deep = format!("{}Nat", "Nat->".repeat(300));
+        assert!(parse(&deep)
+            .unwrap_err()
+            .contains("at most 256 nested terms"));
+    }
+}
@@ -0,0 +1,261 @@
+use std::fmt::{self, Formatter};
+
+use crate::{
+    context::Context,
+    parser::Term,
+    subtype::{join, subtype},
+};
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum TypeError {
+    ParameterTypeMismatch,
+    ArrowTypeExpected,
+    NumberExpected,
+    IfConditionNotBoolean,
+    UnboundVariable(usize),
+    RecordTypeExpected,
+    LabelNotFound(String),
+    // a record term with two fields of the same label
+    DuplicateLabel(String),
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeError::ParameterTypeMismatch => {
+                write!(f, "argument is not a subtype of the parameter type")
+            }
+            TypeError::ArrowTypeExpected => write!(f, "arrow type expected"),
+            TypeError::NumberExpected => write!(f, "argument is not a number"),
+            TypeError::IfConditionNotBoolean => write!(f, "guard of conditional not a boolean"),
+            TypeError::UnboundVariable(index) => write!(f, "unbound variable index {}", index),
+            TypeError::RecordTypeExpected => write!(f, "record type expected"),
+            TypeError::LabelNotFound(label) => write!(f, "label {} not found", label),
+            TypeError::DuplicateLabel(label) => write!(f, "duplicate label {}", label),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd)]
+pub enum Type {
+    Top,
+    // the empty type, a subtype of every type (TAPL 15.4)
+    Bot,
+    Boolean,
+    Number,
+    Arrow(Box<Type>, Box<Type>),
+    // labeled fields in declaration order
+    Record(Vec<(String, Type)>),
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Top => write!(f, "Top"),
+            Type::Bot => write!(f, "Bot"),
+            Type::Boolean => write!(f, "Bool"),
+            Type::Number => write!(f, "Nat"),
+            Type::Arrow(param, ret) => match param.as_ref() {
+                Type::Arrow(_, _) => write!(f, "({})->{}", param, ret),
+                _ => write!(f, "{}->{}", param, ret),
+            },
+            Type::Record(fields) => {
+                write!(f, "{{")?;
+                for (i, (label, typ)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}:{}", label, typ)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+// Algorithmic typing (TAPL 16.2): subsumption is only used where a term
+// meets an expected type, at arguments, and the arms of a conditional join
+pub fn type_of(ctx: &mut Context, term: &Term) -> Result<Type, TypeError> {
+    match term {
+        Term::TmTrue | Term::TmFalse => Ok(Type::Boolean),
+        Term::TmZero => Ok(Type::Number),
+        Term::TmSucc(term) | Term::TmPred(term) => match type_of(ctx, term)? {
+            typ if subtype(ctx, &typ, &Type::Number) => Ok(Type::Number),
+            _ => Err(TypeError::NumberExpected),
+        },
+        Term::TmIsZero(term) => match type_of(ctx, term)? {
+            typ if subtype(ctx, &typ, &Type::Number) => Ok(Type::Boolean),
+            _ => Err(TypeError::NumberExpected),
+        },
+        Term::TmVar(index) => ctx
+            .get_type(*index)
+            .ok_or(TypeError::UnboundVariable(*index)),
+        Term::TmAbs(name, typ, body) => {
+            ctx.push(name.clone(), typ.clone());
+            let body_type = type_of(ctx, body);
+            ctx.pop();
+            Ok(Type::Arrow(Box::new(typ.clone()), Box::new(body_type?)))
+        }
+        // TA-App
+        Term::TmApp(left, right) => {
+            let left_type = type_of(ctx, left)?;
+            let right_type = type_of(ctx, right)?;
+            match left_type {
+                Type::Arrow(param_type, return_type) if subtype(ctx, &right_type, &param_type) => {
+                    Ok(*return_type)
+                }
+                Type::Arrow(_, _) => Err(TypeError::ParameterTypeMismatch),
+                // TA-AppBot: a function of type Bot never returns, so any
+                // argument will do
+                Type::Bot => Ok(Type::Bot),
+                _ => Err(TypeError::ArrowTypeExpected),
+            }
+        }
+        // TA-If
+        Term::TmIf(if_term, then_term, else_term) => {
+            // a guard of type Bot is accepted as well, it never yields a value
+            let if_type = type_of(ctx, if_term)?;
+            if !subtype(ctx, &if_type, &Type::Boolean) {
+                return Err(TypeError::IfConditionNotBoolean);
+            }
+            let then_type = type_of(ctx, then_term)?;
+            let else_type = type_of(ctx, else_term)?;
+            Ok(join(ctx, &then_type, &else_type))
+        }
+        Term::TmLet(name, bound, body) => {
+            let bound_type = type_of(ctx, bound)?;
+            ctx.push(name.clone(), bound_type);
+            let body_type = type_of(ctx, body);
+            ctx.pop();
+            body_type
+        }
+        // T-Rcd, the labels of a record are distinct
+        Term::TmRecord(fields) => {
+            for (i, (label, _)) in fields.iter().enumerate() {
+                if fields[..i].iter().any(|(other, _)| other == label) {
+                    return Err(TypeError::DuplicateLabel(label.clone()));
+                }
+            }
+            Ok(Type::Record(
+                fields
+                    .iter()
+                    .map(|(label, term)| Ok((label.clone(), type_of(ctx, term)?)))
+                    .collect::<Result<Vec<_>, TypeError>>()?,
+            ))
+        }
+        // TA-Proj
+        Term::TmProj(term, label) => match type_of(ctx, term)? {
+            Type::Record(fields) => fields
+                .into_iter()
+                .find(|(name, _)| name == label)
+                .map(|(_, typ)| typ)
+                .ok_or_else(|| TypeError::LabelNotFound(label.clone())),
+            // TA-ProjBot
+            Type::Bot => Ok(Type::Bot),
+            _ => Err(TypeError::RecordTypeExpected),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tapl_frontend::lexer::Lexer;
+
+    use crate::{
+        parser::{Parser, SYMBOLS},
+        type_parser::parse_type,
+    };
+
+    fn type_str(input: &str) -> Result<Type, TypeError> {
+        let term = Parser::new().parse(input).unwrap();
+        type_of(&mut Context::default(), &term)
+    }
+
+    fn typ(input: &str) -> Type {
+        parse_type(&mut Lexer::new(input, &SYMBOLS)).unwrap()
+    }
+
+    #[test]
+    fn test_type_of() {
+        // subsumption at the argument
+        assert_eq!(
+            type_str("(lambda r:{x:Nat}.r.x) {x=0, y=true};"),
+            Ok(Type::Number)
+        );
+        assert_eq!(
+            type_str("(lambda r:{x:Nat, y:Bool}.r.y) {x=0};"),
+            Err(TypeError::ParameterTypeMismatch)
+        );
+        assert_eq!(
+            type_str("(lambda f:{x:Nat, y:Nat}->Top.f {x=0, y=0}) (lambda r:{x:Nat}.r.x);"),
+            Ok(Type::Top)
+        );
+        assert_eq!(
+            type_str("if true then {x=0, y=true} else {y=false, x=succ(0), z=0};"),
+            Ok(typ("{x:Nat, y:Bool}"))
+        );
+        assert_eq!(type_str("if true then true else 0;"), Ok(Type::Top));
+        assert_eq!(
+            type_str("{b=0}.a;"),
+            Err(TypeError::LabelNotFound("a".to_string()))
+        );
+        assert_eq!(
+            type_str("{a=0, b=true, a=false};"),
+            Err(TypeError::DuplicateLabel("a".to_string()))
+        );
+        assert_eq!(
+            type_str("(lambda r:{a:Nat}.r.a) {a=0, a=true};"),
+            Err(TypeError::DuplicateLabel("a".to_string()))
+        );
+        // the static type forgets the field
+        assert_eq!(
+            type_str("((lambda t:Top.t) {a=0}).a;"),
+            Err(TypeError::RecordTypeExpected)
+        );
+    }
+
+    // Minimal typing (TAPL 16.2.5, 16.4): the algorithmic type of a term is a
+    // subtype of every type the declarative rules with T-Sub give it, so each
+    // case lists its minimal type and some of the others
+    #[test]
+    fn test_minimal_types() {
+        let cases = [
+            (
+                "lambda x:Bot.x 0;",
+                "Bot->Bot",
+                &["Bot->Nat", "Bot->{a:Nat}->Top"][..],
+            ),
+            ("lambda x:Bot.x.l;", "Bot->Bot", &["Bot->Bool"]),
+            ("lambda x:Bot.(x 0) x;", "Bot->Bot", &["Bot->Nat->Bool"]),
+            (
+                "lambda x:Bot.if x then 0 else succ(0);",
+                "Bot->Nat",
+                &["Bot->Top"],
+            ),
+            ("lambda x:Bot.succ(x);", "Bot->Nat", &["Top"]),
+            (
+                "if true then lambda r:{x:Nat}.r else lambda r:{y:Nat}.r;",
+                "{x:Nat, y:Nat}->{}",
+                &["{x:Nat, y:Nat, z:Bool}->{}", "Top"],
+            ),
+            (
+                "if true then {a=true, b=0} else {a=false};",
+                "{a:Bool}",
+                &["{}", "Top"],
+            ),
+        ];
+        let mut ctx = Context::default();
+        for (input, minimal, others) in cases {
+            assert_eq!(type_str(input), Ok(typ(minimal)), "{}", input);
+            for other in others {
+                assert!(subtype(&mut ctx, &typ(minimal), &typ(other)), "{}", other);
+                assert!(!subtype(&mut ctx, &typ(other), &typ(minimal)), "{}", other);
+            }
+        }
+        assert_eq!(
+            type_str("lambda x:Top.x 0;"),
+            Err(TypeError::ArrowTypeExpected)
+        );
+    }
+}
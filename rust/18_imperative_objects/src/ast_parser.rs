@@ -0,0 +1,304 @@
+use tapl_frontend::lexer::{Lexer, Token};
+
+use crate::{
+    parser::ParseError,
+    type_parser::{parse_type, TYPE_KEYWORDS},
+    typing::Type,
+};
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum ASTTerm {
+    TmUnit,
+    TmTrue,
+    TmFalse,
+    TmZero,
+    TmSucc(Box<ASTTerm>),
+    TmPred(Box<ASTTerm>),
+    TmIsZero(Box<ASTTerm>),
+    TmVar(String),
+    // argument ident, argument type and body
+    TmAbs(String, Type, Box<ASTTerm>),
+    TmApp(Box<ASTTerm>, Box<ASTTerm>),
+    // condition term, then term, else term
+    TmIf(Box<ASTTerm>, Box<ASTTerm>, Box<ASTTerm>),
+    // bound ident, bound term and body
+    TmLet(String, Box<ASTTerm>, Box<ASTTerm>),
+    // `t1; t2`, the second term runs after the first one of type Unit
+    TmSeq(Box<ASTTerm>, Box<ASTTerm>),
+    TmRef(Box<ASTTerm>),
+    TmDeref(Box<ASTTerm>),
+    TmAssign(Box<ASTTerm>, Box<ASTTerm>),
+    // labeled fields in declaration order
+    TmRecord(Vec<(String, ASTTerm)>),
+    // record term and field label
+    TmProj(Box<ASTTerm>, String),
+    // `fix t`
+    TmFix(Box<ASTTerm>),
+}
+
+// the words a variable cannot be named
+pub const KEYWORDS: [&str; 14] = [
+    "lambda", "let", "in", "if", "then", "else", "unit", "true", "false", "succ", "pred", "iszero",
+    "ref", "fix",
+];
+
+// the keywords an atom starts with
+const ATOM_KEYWORDS: [&str; 8] = [
+    "unit", "true", "false", "succ", "pred", "iszero", "ref", "fix",
+];
+
+fn is_reserved(word: &str) -> bool {
+    KEYWORDS.contains(&word) || TYPE_KEYWORDS.contains(&word)
+}
+
+fn variable<'a>(lexer: &mut Lexer<'a>) -> Result<&'a str, ParseError> {
+    match lexer.peek()? {
+        (Token::Word(word), _) if !is_reserved(word) => {
+            lexer.next_token()?;
+            Ok(word)
+        }
+        _ => Err(lexer.unexpected("a variable").into()),
+    }
+}
+
+fn starts_atom(lexer: &mut Lexer) -> Result<bool, ParseError> {
+    Ok(match lexer.peek()?.0 {
+        Token::Word(word) => !is_reserved(word) || ATOM_KEYWORDS.contains(&word),
+        Token::Number(_) | Token::Symbol("(") | Token::Symbol("{") | Token::Symbol("!") => true,
+        _ => false,
+    })
+}
+
+fn starts_term(lexer: &mut Lexer) -> Result<bool, ParseError> {
+    let starts_compound = matches!(lexer.peek()?.0, Token::Word("lambda" | "let" | "if"));
+    Ok(starts_compound || starts_atom(lexer)?)
+}
+
+// `(t)` after `succ`, `pred` or `iszero`
+fn parse_argument(lexer: &mut Lexer) -> Result<Box<ASTTerm>, ParseError> {
+    lexer.expect_symbol("(")?;
+    let term = parse_term(lexer)?;
+    lexer.expect_symbol(")")?;
+    Ok(Box::new(term))
+}
+
+// `{l = t, ...}` after the `{`
+fn parse_record(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let mut fields = vec![];
+    if lexer.eat_symbol("}")?.is_none() {
+        loop {
+            let (label, _) = lexer.expect_word("a label", &[])?;
+            lexer.expect_symbol("=")?;
+            fields.push((label.to_string(), parse_term(lexer)?));
+            if lexer.eat_symbol(",")?.is_none() {
+                break;
+            }
+        }
+        lexer.expect_symbol("}")?;
+    }
+    Ok(ASTTerm::TmRecord(fields))
+}
+
+fn parse_primary(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let (token, span) = lexer.next_token()?;
+    let term = match token {
+        Token::Word("unit") => ASTTerm::TmUnit,
+        Token::Word("true") => ASTTerm::TmTrue,
+        Token::Word("false") => ASTTerm::TmFalse,
+        Token::Word("succ") => ASTTerm::TmSucc(parse_argument(lexer)?),
+        Token::Word("pred") => ASTTerm::TmPred(parse_argument(lexer)?),
+        Token::Word("iszero") => ASTTerm::TmIsZero(parse_argument(lexer)?),
+        Token::Word("ref") => ASTTerm::TmRef(Box::new(parse_atom(lexer)?)),
+        Token::Word("fix") => ASTTerm::TmFix(Box::new(parse_atom(lexer)?)),
+        Token::Word(word) if !is_reserved(word) => ASTTerm::TmVar(word.to_string()),
+        Token::Number("0") => ASTTerm::TmZero,
+        Token::Symbol("!") => ASTTerm::TmDeref(Box::new(parse_atom(lexer)?)),
+        Token::Symbol("{") => parse_record(lexer)?,
+        Token::Symbol("(") => {
+            let term = parse_term(lexer)?;
+            lexer.expect_symbol(")")?;
+            term
+        }
+        _ => return Err(lexer.error_at("a term", &token.to_string(), span).into()),
+    };
+    Ok(term)
+}
+
+// a method is selected by projecting it out of the object, `!r.x` reads the
+// instance variable `x` of the representation `r`
+fn parse_atom(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    lexer.nested(|lexer| {
+        let mut term = parse_primary(lexer)?;
+        while lexer.eat_symbol(".")?.is_some() {
+            lexer.deepen()?;
+            let (label, _) = lexer.expect_word("a label", &[])?;
+            term = ASTTerm::TmProj(Box::new(term), label.to_string());
+        }
+        Ok(term)
+    })
+}
+
+// application is left associative: `f x y` is `(f x) y`, and every argument
+// nests the application one level deeper
+fn parse_application(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let mut term = parse_atom(lexer)?;
+    while starts_atom(lexer)? {
+        lexer.deepen()?;
+        let right = parse_atom(lexer)?;
+        term = ASTTerm::TmApp(Box::new(term), Box::new(right));
+    }
+    Ok(term)
+}
+
+// `x:T.t` after `lambda`
+fn parse_abstraction(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let param = variable(lexer)?.to_string();
+    lexer.expect_symbol(":")?;
+    let typ = parse_type(lexer)?;
+    lexer.expect_symbol(".")?;
+    Ok(ASTTerm::TmAbs(param, typ, Box::new(parse_term(lexer)?)))
+}
+
+fn parse_if(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let condition = parse_term(lexer)?;
+    lexer.expect_keyword("then")?;
+    let then_term = parse_term(lexer)?;
+    lexer.expect_keyword("else")?;
+    let else_term = parse_term(lexer)?;
+    Ok(ASTTerm::TmIf(
+        Box::new(condition),
+        Box::new(then_term),
+        Box::new(else_term),
+    ))
+}
+
+fn parse_let(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let name = variable(lexer)?.to_string();
+    lexer.expect_symbol("=")?;
+    let bound = parse_term(lexer)?;
+    lexer.expect_keyword("in")?;
+    let body = parse_term(lexer)?;
+    Ok(ASTTerm::TmLet(name, Box::new(bound), Box::new(body)))
+}
+
+// `:=` binds looser than application and is right associative
+fn parse_assign(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    lexer.nested(|lexer| {
+        let left = if lexer.eat_keyword("lambda")?.is_some() {
+            parse_abstraction(lexer)?
+        } else if lexer.eat_keyword("let")?.is_some() {
+            parse_let(lexer)?
+        } else if lexer.eat_keyword("if")?.is_some() {
+            parse_if(lexer)?
+        } else {
+            parse_application(lexer)?
+        };
+        match lexer.eat_symbol(":=")? {
+            Some(_) => Ok(ASTTerm::TmAssign(
+                Box::new(left),
+                Box::new(parse_assign(lexer)?),
+            )),
+            None => Ok(left),
+        }
+    })
+}
+
+// Sequencing binds loosest, so bodies of abstractions and lets extend as
+// far to the right as possible. A `;` that no term follows ends the
+// program and is left to the caller.
+pub fn parse_term(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    lexer.nested(|lexer| {
+        let mut terms = vec![parse_assign(lexer)?];
+        loop {
+            let mut ahead = lexer.clone();
+            if ahead.eat_symbol(";")?.is_none() || !starts_term(&mut ahead)? {
+                break;
+            }
+            lexer.expect_symbol(";")?;
+            lexer.deepen()?;
+            terms.push(parse_assign(lexer)?);
+        }
+        let mut terms = terms.into_iter().rev();
+        let last = terms.next().unwrap();
+        Ok(terms.fold(last, |rest, term| {
+            ASTTerm::TmSeq(Box::new(term), Box::new(rest))
+        }))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SYMBOLS;
+
+    fn parse(input: &str) -> Result<(&str, ASTTerm), ParseError> {
+        let mut lexer = Lexer::new(input, &SYMBOLS);
+        parse_term(&mut lexer).map(|term| (lexer.rest(), term))
+    }
+
+    fn var(name: &str) -> Box<ASTTerm> {
+        Box::new(ASTTerm::TmVar(name.to_string()))
+    }
+
+    #[test]
+    fn test_parse_term() {
+        assert_eq!(
+            parse("r.x := succ(!r.x); !r.x;"),
+            Ok((
+                ";",
+                ASTTerm::TmSeq(
+                    Box::new(ASTTerm::TmAssign(
+                        Box::new(ASTTerm::TmProj(var("r"), "x".to_string())),
+                        Box::new(ASTTerm::TmSucc(Box::new(ASTTerm::TmDeref(Box::new(
+                            ASTTerm::TmProj(var("r"), "x".to_string())
+                        )))))
+                    )),
+                    Box::new(ASTTerm::TmDeref(Box::new(ASTTerm::TmProj(
+                        var("r"),
+                        "x".to_string()
+                    ))))
+                )
+            ))
+        );
+        // a sequence in a method body ends at the next field
+        assert_eq!(
+            parse("{inc = lambda u:Unit.c.inc u; c.get u, get = c.get}"),
+            Ok((
+                "",
+                ASTTerm::TmRecord(vec![
+                    (
+                        "inc".to_string(),
+                        ASTTerm::TmAbs(
+                            "u".to_string(),
+                            Type::Unit,
+                            Box::new(ASTTerm::TmSeq(
+                                Box::new(ASTTerm::TmApp(
+                                    Box::new(ASTTerm::TmProj(var("c"), "inc".to_string())),
+                                    var("u")
+                                )),
+                                Box::new(ASTTerm::TmApp(
+                                    Box::new(ASTTerm::TmProj(var("c"), "get".to_string())),
+                                    var("u")
+                                ))
+                            ))
+                        )
+                    ),
+                    (
+                        "get".to_string(),
+                        ASTTerm::TmProj(var("c"), "get".to_string())
+                    )
+                ])
+            ))
+        );
+        assert_eq!(
+            parse("fix (c r) unit"),
+            Ok((
+                "",
+                ASTTerm::TmApp(
+                    Box::new(ASTTerm::TmFix(Box::new(ASTTerm::TmApp(var("c"), var("r"))))),
+                    Box::new(ASTTerm::TmUnit)
+                )
+            ))
+        );
+    }
+}
@@ -0,0 +1,364 @@
+use std::fmt::{self, Formatter};
+
+use references::store::{Store, StoreValue};
+
+use crate::{parser::Term, substitute::substitute_top};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EvalError {
+    NoRuleApplies,
+    // a location that was never allocated, impossible for well-typed terms
+    InvalidLocation(usize),
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::NoRuleApplies => write!(f, "no rule applies"),
+            EvalError::InvalidLocation(location) => {
+                write!(f, "location {} was never allocated", location)
+            }
+        }
+    }
+}
+
+fn is_numeric_val(term: &Term) -> bool {
+    match term {
+        Term::TmZero => true,
+        Term::TmSucc(term) => is_numeric_val(term),
+        _ => false,
+    }
+}
+
+pub fn is_val(term: &Term) -> bool {
+    match term {
+        Term::TmUnit | Term::TmTrue | Term::TmFalse | Term::TmAbs(_, _, _) | Term::TmLoc(_) => true,
+        Term::TmRecord(fields) => fields.iter().all(|(_, term)| is_val(term)),
+        _ => is_numeric_val(term),
+    }
+}
+
+// the store of chapter 13, holding the terms of this chapter
+impl StoreValue for Term {
+    fn locations(&self, out: &mut Vec<usize>) {
+        match self {
+            Term::TmLoc(location) => out.push(*location),
+            Term::TmUnit | Term::TmTrue | Term::TmFalse | Term::TmZero | Term::TmVar(_) => {}
+            Term::TmSucc(term)
+            | Term::TmPred(term)
+            | Term::TmIsZero(term)
+            | Term::TmAbs(_, _, term)
+            | Term::TmRef(term)
+            | Term::TmDeref(term)
+            | Term::TmProj(term, _)
+            | Term::TmFix(term) => term.locations(out),
+            Term::TmApp(left, right)
+            | Term::TmLet(_, left, right)
+            | Term::TmAssign(left, right) => {
+                left.locations(out);
+                right.locations(out);
+            }
+            Term::TmIf(if_term, then_term, else_term) => {
+                if_term.locations(out);
+                then_term.locations(out);
+                else_term.locations(out);
+            }
+            Term::TmRecord(fields) => fields.iter().for_each(|(_, term)| term.locations(out)),
+        }
+    }
+}
+
+// t | μ -> t' | μ' (TAPL figure 13-1), with records and fix. Subtyping does
+// not change evaluation, an object passed where fewer methods are expected
+// keeps all of them at runtime.
+pub fn eval1(term: &Term, store: &mut Store<Term>) -> Result<Term, EvalError> {
+    let boxed = |term: Result<Term, EvalError>| term.map(Box::new);
+    match term {
+        Term::TmSucc(term) => Ok(Term::TmSucc(boxed(eval1(term, store))?)),
+        Term::TmPred(term) => match term.as_ref() {
+            Term::TmZero => Ok(Term::TmZero),
+            Term::TmSucc(number) if is_numeric_val(number) => Ok(number.as_ref().clone()),
+            _ => Ok(Term::TmPred(boxed(eval1(term, store))?)),
+        },
+        Term::TmIsZero(term) => match term.as_ref() {
+            Term::TmZero => Ok(Term::TmTrue),
+            Term::TmSucc(number) if is_numeric_val(number) => Ok(Term::TmFalse),
+            _ => Ok(Term::TmIsZero(boxed(eval1(term, store))?)),
+        },
+        Term::TmApp(left, right) => match left.as_ref() {
+            Term::TmAbs(_, _, body) if is_val(right) => Ok(substitute_top(right, body)),
+            _ if is_val(left) => Ok(Term::TmApp(left.clone(), boxed(eval1(right, store))?)),
+            _ => Ok(Term::TmApp(boxed(eval1(left, store))?, right.clone())),
+        },
+        Term::TmIf(if_term, then_term, else_term) => match if_term.as_ref() {
+            Term::TmTrue => Ok(then_term.as_ref().clone()),
+            Term::TmFalse => Ok(else_term.as_ref().clone()),
+            _ => Ok(Term::TmIf(
+                boxed(eval1(if_term, store))?,
+                then_term.clone(),
+                else_term.clone(),
+            )),
+        },
+        Term::TmLet(name, bound, body) => {
+            if is_val(bound) {
+                Ok(substitute_top(bound, body))
+            } else {
+                Ok(Term::TmLet(
+                    name.clone(),
+                    boxed(eval1(bound, store))?,
+                    body.clone(),
+                ))
+            }
+        }
+        // E-RefV allocates a fresh location
+        Term::TmRef(term) if is_val(term) => Ok(Term::TmLoc(store.alloc(term.as_ref().clone()))),
+        Term::TmRef(term) => Ok(Term::TmRef(boxed(eval1(term, store))?)),
+        Term::TmDeref(term) => match term.as_ref() {
+            Term::TmLoc(location) => store
+                .get(*location)
+                .cloned()
+                .ok_or(EvalError::InvalidLocation(*location)),
+            _ => Ok(Term::TmDeref(boxed(eval1(term, store))?)),
+        },
+        Term::TmAssign(left, right) => match left.as_ref() {
+            Term::TmLoc(location) if is_val(right) => {
+                if store.update(*location, right.as_ref().clone()) {
+                    Ok(Term::TmUnit)
+                } else {
+                    Err(EvalError::InvalidLocation(*location))
+                }
+            }
+            _ if is_val(left) => Ok(Term::TmAssign(left.clone(), boxed(eval1(right, store))?)),
+            _ => Ok(Term::TmAssign(boxed(eval1(left, store))?, right.clone())),
+        },
+        Term::TmRecord(fields) => match fields.iter().position(|(_, term)| !is_val(term)) {
+            // evaluate the fields from left to right
+            Some(i) => {
+                let mut fields = fields.clone();
+                fields[i].1 = eval1(&fields[i].1, store)?;
+                Ok(Term::TmRecord(fields))
+            }
+            None => Err(EvalError::NoRuleApplies),
+        },
+        Term::TmProj(term, label) => match term.as_ref() {
+            Term::TmRecord(fields) if is_val(term) => fields
+                .iter()
+                .find(|(name, _)| name == label)
+                .map(|(_, term)| term.clone())
+                .ok_or(EvalError::NoRuleApplies),
+            _ => Ok(Term::TmProj(boxed(eval1(term, store))?, label.clone())),
+        },
+        Term::TmFix(term) => match term.as_ref() {
+            // E-FixBeta
+            Term::TmAbs(_, _, body) => Ok(substitute_top(&Term::TmFix(term.clone()), body)),
+            _ => Ok(Term::TmFix(boxed(eval1(term, store))?)),
+        },
+        _ => Err(EvalError::NoRuleApplies),
+    }
+}
+
+pub fn eval(term: &Term, store: &mut Store<Term>) -> Result<Term, EvalError> {
+    let mut term = term.clone();
+    loop {
+        match eval1(&term, store) {
+            Ok(next_term) => term = next_term,
+            Err(EvalError::NoRuleApplies) => return Ok(term),
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{language::ImperativeObjects, parser::Parser, typing::Type};
+    use tapl_testkit::{assert_evals_to, assert_type_error};
+
+    // newCounter and inc3 (TAPL 18.2, 18.3): a counter keeps its count in a
+    // reference only its methods can reach
+    const COUNTER: &str = "let n = lambda u:Unit.let x = ref succ(0) in \
+                           {get = lambda u:Unit.!x, inc = lambda u:Unit.x := succ(!x)} in \
+                           let i = lambda c:{get:Unit->Nat, inc:Unit->Unit}.\
+                           c.inc unit; c.inc unit; c.inc unit in ";
+
+    // counterClass, resetCounterClass and backupCounterClass (TAPL 18.6,
+    // 18.7), classes abstract the methods over the record of instance
+    // variables, a subclass reuses the methods of its superclass `s`
+    const CLASSES: &str = "let k = lambda r:{x:Ref Nat}.\
+                           {get = lambda u:Unit.!r.x, inc = lambda u:Unit.r.x := succ(!r.x)} in \
+                           let l = lambda r:{x:Ref Nat}.let s = k r in \
+                           {get = s.get, inc = s.inc, reset = lambda u:Unit.r.x := succ(0)} in \
+                           let m = lambda r:{x:Ref Nat, b:Ref Nat}.let s = l r in \
+                           {get = s.get, inc = s.inc, reset = lambda u:Unit.r.x := !r.b, \
+                           backup = lambda u:Unit.r.b := !r.x} in ";
+
+    const SET_COUNTER: &str = "{get:Unit->Nat, set:Nat->Unit, inc:Unit->Unit}";
+
+    const INSTR_COUNTER: &str =
+        "{get:Unit->Nat, set:Nat->Unit, inc:Unit->Unit, accesses:Unit->Nat}";
+
+    #[test]
+    fn test_objects() {
+        assert_evals_to!(
+            ImperativeObjects,
+            "let x = ref succ(0) in \
+             let c = {get = lambda u:Unit.!x, inc = lambda u:Unit.x := succ(!x)} in \
+             c.inc unit; c.inc unit; c.get unit;",
+            "succ(succ(succ(0)))"
+        );
+        // every counter has a reference of its own
+        assert_evals_to!(
+            ImperativeObjects,
+            &format!(
+                "{}let a = n unit in let b = n unit in i a; {{a = a.get unit, b = b.get unit}};",
+                COUNTER
+            ),
+            "{a=succ(succ(succ(succ(0)))), b=succ(0)}"
+        );
+        // a reset counter can be used wherever a counter is expected (TAPL 18.4)
+        assert_evals_to!(
+            ImperativeObjects,
+            &format!(
+                "{}let r = lambda u:Unit.let x = ref succ(0) in \
+                 {{get = lambda u:Unit.!x, inc = lambda u:Unit.x := succ(!x), \
+                 reset = lambda u:Unit.x := succ(0)}} in \
+                 let c = r unit in i c; c.reset unit; i c; c.get unit;",
+                COUNTER
+            ),
+            "succ(succ(succ(succ(0))))"
+        );
+        assert_type_error!(
+            ImperativeObjects,
+            &format!("{}i {{get = lambda u:Unit.0}};", COUNTER),
+            "not a subtype"
+        );
+    }
+
+    #[test]
+    fn test_classes() {
+        let backup_counter = "let n = lambda u:Unit.m {x=ref succ(0), b=ref succ(0)} in ";
+        assert_evals_to!(
+            ImperativeObjects,
+            &format!(
+                "{}{}let c = n unit in \
+                 c.inc unit; c.backup unit; c.inc unit; c.inc unit; c.reset unit; c.get unit;",
+                CLASSES, backup_counter
+            ),
+            "succ(succ(0))"
+        );
+        // calling the superclass from an overriding method (TAPL 18.8), inc
+        // backs up the count before incrementing it
+        assert_evals_to!(
+            ImperativeObjects,
+            &format!(
+                "{}let f = lambda r:{{x:Ref Nat, b:Ref Nat}}.let s = m r in \
+                 {{get = s.get, inc = lambda u:Unit.s.backup u; s.inc u, \
+                 reset = s.reset, backup = s.backup}} in \
+                 let c = f {{x=ref succ(0), b=ref 0}} in \
+                 c.inc unit; c.inc unit; c.reset unit; c.get unit;",
+                CLASSES
+            ),
+            "succ(succ(0))"
+        );
+        // a class needs every instance variable it uses
+        assert_type_error!(
+            ImperativeObjects,
+            &format!("{}m {{x=ref 0}};", CLASSES),
+            "not a subtype"
+        );
+    }
+
+    #[test]
+    fn test_self() {
+        // a class that calls its own methods through `self`, the fixed point
+        // is taken once the class is instantiated (TAPL 18.9, 18.10)
+        let set_counter_class = format!(
+            "let k = lambda r:{{x:Ref Nat}}.lambda s:{}.\
+             {{get = lambda u:Unit.!r.x, set = lambda i:Nat.r.x := i, \
+             inc = lambda u:Unit.s.set succ(s.get unit)}} in ",
+            SET_COUNTER
+        );
+        assert_evals_to!(
+            ImperativeObjects,
+            &format!(
+                "{}let c = fix (k {{x=ref succ(0)}}) in c.inc unit; c.inc unit; c.get unit;",
+                set_counter_class
+            ),
+            "succ(succ(succ(0)))"
+        );
+        // the subclass needs `self` to build its superclass, so the fixed
+        // point unrolls forever before an object exists
+        let instr_counter = format!(
+            "{}let m = lambda r:{{x:Ref Nat, a:Ref Nat}}.lambda s:{}.let p = k r s in \
+             {{get = p.get, set = lambda i:Nat.r.a := succ(!r.a); p.set i, inc = p.inc, \
+             accesses = lambda u:Unit.!r.a}} in fix (m {{x=ref succ(0), a=ref 0}});",
+            set_counter_class, INSTR_COUNTER
+        );
+        let mut term = Parser::new().parse(&instr_counter).unwrap();
+        let mut store = Store::new();
+        for _ in 0..100 {
+            term = eval1(&term, &mut store).unwrap();
+        }
+        assert!(!is_val(&term));
+
+        // delaying `self` behind a Unit argument lets the subclass override
+        // the methods its superclass calls (TAPL 18.11)
+        let delayed = format!(
+            "let k = lambda r:{{x:Ref Nat}}.lambda s:Unit->{}.lambda u:Unit.\
+             {{get = lambda u:Unit.!r.x, set = lambda i:Nat.r.x := i, \
+             inc = lambda u:Unit.(s unit).set succ((s unit).get unit)}} in \
+             let m = lambda r:{{x:Ref Nat, a:Ref Nat}}.lambda s:Unit->{}.lambda u:Unit.\
+             let p = k r s u in \
+             {{get = p.get, set = lambda i:Nat.r.a := succ(!r.a); p.set i, inc = p.inc, \
+             accesses = lambda u:Unit.!r.a}} in \
+             let n = lambda u:Unit.fix (m {{x=ref succ(0), a=ref 0}}) unit in ",
+            SET_COUNTER, INSTR_COUNTER
+        );
+        assert_evals_to!(
+            ImperativeObjects,
+            &format!(
+                "{}let c = n unit in c.set succ(succ(0)); c.inc unit; \
+                 {{x = c.get unit, a = c.accesses unit}};",
+                delayed
+            ),
+            "{x=succ(succ(succ(0))), a=succ(succ(0))}"
+        );
+
+        // open recursion needs no state: the superclass's even calls the odd
+        // of the subclass, which counts 0 as odd as well. The subclass builds
+        // its superclass inside the methods, like the delayed `self` above.
+        let parity = "let e = lambda s:{even:Nat->Bool, odd:Nat->Bool}.\
+                      {even = lambda n:Nat.if iszero(n) then true else s.odd pred(n), \
+                      odd = lambda n:Nat.if iszero(n) then false else s.even pred(n)} in \
+                      let o = lambda s:{even:Nat->Bool, odd:Nat->Bool}.\
+                      {even = lambda n:Nat.(e s).even n, \
+                      odd = lambda n:Nat.if iszero(n) then true else (e s).odd n} in ";
+        assert_evals_to!(
+            ImperativeObjects,
+            &format!(
+                "{}{{e = (fix e).even succ(0), o = (fix o).even succ(0)}};",
+                parity
+            ),
+            "{e=false, o=true}"
+        );
+    }
+
+    #[test]
+    fn test_collect() {
+        let mut store = Store::new();
+        let x = store.alloc(Term::TmZero);
+        let garbage = store.alloc(Term::TmTrue);
+        // the instance variables of an object are reached through its methods
+        let object = Term::TmRecord(vec![(
+            "get".to_string(),
+            Term::TmAbs(
+                "u".to_string(),
+                Type::Unit,
+                Box::new(Term::TmDeref(Box::new(Term::TmLoc(x)))),
+            ),
+        )]);
+        assert_eq!(store.collect(&[&Term::TmFix(Box::new(object))]), 1);
+        assert_eq!(store.get(x), Some(&Term::TmZero));
+        assert_eq!(store.get(garbage), None);
+    }
+}
@@ -0,0 +1,250 @@
+use std::fmt::{self, Formatter};
+
+use tapl_frontend::lexer::{Lexer, SyntaxError};
+
+use crate::{
+    ast_parser::{parse_term, ASTTerm},
+    typing::Type,
+};
+
+// punctuation of terms and types, `:=` wins over `:`
+pub const SYMBOLS: [&str; 12] = ["->", ":=", "(", ")", "{", "}", ".", ",", ":", ";", "=", "!"];
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum Term {
+    TmUnit,
+    TmTrue,
+    TmFalse,
+    TmZero,
+    TmSucc(Box<Term>),
+    TmPred(Box<Term>),
+    TmIsZero(Box<Term>),
+    // var DeBrujin index
+    TmVar(usize),
+    // argument name, type and body
+    TmAbs(String, Type, Box<Term>),
+    TmApp(Box<Term>, Box<Term>),
+    TmIf(Box<Term>, Box<Term>, Box<Term>),
+    // bound name, bound term and body
+    TmLet(String, Box<Term>, Box<Term>),
+    TmRef(Box<Term>),
+    TmDeref(Box<Term>),
+    TmAssign(Box<Term>, Box<Term>),
+    // store location, only produced by evaluating `ref`
+    TmLoc(usize),
+    // labeled fields in declaration order
+    TmRecord(Vec<(String, Term)>),
+    // record term and field label
+    TmProj(Box<Term>, String),
+    // general recursion, fix (lambda x:T.t) unrolls to t with x bound to itself
+    TmFix(Box<Term>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    Syntax(SyntaxError),
+    UnboundVariable(String),
+    TrailingInput(String),
+}
+
+impl From<SyntaxError> for ParseError {
+    fn from(error: SyntaxError) -> Self {
+        ParseError::Syntax(error)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Syntax(error) => write!(f, "{}", error),
+            ParseError::UnboundVariable(name) => write!(f, "unbound variable {}", name),
+            ParseError::TrailingInput(input) => write!(f, "unexpected input `{}`", input),
+        }
+    }
+}
+
+pub type ParseResult = Result<Term, ParseError>;
+
+// Names of the enclosing binders, the innermost is at de Bruijn index 0
+#[derive(Default)]
+pub struct Parser {
+    names: Vec<String>,
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(&mut self, input: &str) -> ParseResult {
+        let mut lexer = Lexer::new(input, &SYMBOLS);
+        let term = parse_term(&mut lexer)?;
+        lexer.expect_symbol(";")?;
+        if !lexer.rest().trim().is_empty() {
+            return Err(ParseError::TrailingInput(lexer.rest().trim().to_string()));
+        }
+
+        self.from_ast_term(&term)
+    }
+
+    fn with_binding<T>(&mut self, name: &str, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.names.push(name.to_string());
+        let result = f(self);
+        self.names.pop();
+        result
+    }
+
+    fn from_ast_term(self: &mut Parser, ast_term: &ASTTerm) -> ParseResult {
+        let term = match ast_term {
+            ASTTerm::TmUnit => Term::TmUnit,
+            ASTTerm::TmTrue => Term::TmTrue,
+            ASTTerm::TmFalse => Term::TmFalse,
+            ASTTerm::TmZero => Term::TmZero,
+            ASTTerm::TmSucc(term) => Term::TmSucc(Box::new(self.from_ast_term(term)?)),
+            ASTTerm::TmPred(term) => Term::TmPred(Box::new(self.from_ast_term(term)?)),
+            ASTTerm::TmIsZero(term) => Term::TmIsZero(Box::new(self.from_ast_term(term)?)),
+            ASTTerm::TmVar(name) => match self.names.iter().rev().position(|n| n == name) {
+                Some(index) => Term::TmVar(index),
+                None => return Err(ParseError::UnboundVariable(name.clone())),
+            },
+            ASTTerm::TmAbs(name, typ, body) => {
+                let body = self.with_binding(name, |parser| parser.from_ast_term(body))?;
+                Term::TmAbs(name.clone(), typ.clone(), Box::new(body))
+            }
+            ASTTerm::TmApp(left, right) => Term::TmApp(
+                Box::new(self.from_ast_term(left)?),
+                Box::new(self.from_ast_term(right)?),
+            ),
+            ASTTerm::TmIf(if_term, then_term, else_term) => Term::TmIf(
+                Box::new(self.from_ast_term(if_term)?),
+                Box::new(self.from_ast_term(then_term)?),
+                Box::new(self.from_ast_term(else_term)?),
+            ),
+            ASTTerm::TmLet(name, bound, body) => {
+                let bound = self.from_ast_term(bound)?;
+                let body = self.with_binding(name, |parser| parser.from_ast_term(body))?;
+                Term::TmLet(name.clone(), Box::new(bound), Box::new(body))
+            }
+            // derived form `(lambda _:Unit.t2) t1` (TAPL 11.3), `_` can never
+            // be referred to
+            ASTTerm::TmSeq(first, rest) => {
+                let first = self.from_ast_term(first)?;
+                let rest = self.with_binding("_", |parser| parser.from_ast_term(rest))?;
+                Term::TmApp(
+                    Box::new(Term::TmAbs("_".to_string(), Type::Unit, Box::new(rest))),
+                    Box::new(first),
+                )
+            }
+            ASTTerm::TmRef(term) => Term::TmRef(Box::new(self.from_ast_term(term)?)),
+            ASTTerm::TmDeref(term) => Term::TmDeref(Box::new(self.from_ast_term(term)?)),
+            ASTTerm::TmAssign(left, right) => Term::TmAssign(
+                Box::new(self.from_ast_term(left)?),
+                Box::new(self.from_ast_term(right)?),
+            ),
+            ASTTerm::TmRecord(fields) => Term::TmRecord(
+                fields
+                    .iter()
+                    .map(|(label, term)| Ok((label.clone(), self.from_ast_term(term)?)))
+                    .collect::<Result<Vec<_>, ParseError>>()?,
+            ),
+            ASTTerm::TmProj(term, label) => {
+                Term::TmProj(Box::new(self.from_ast_term(term)?), label.clone())
+            }
+            ASTTerm::TmFix(term) => Term::TmFix(Box::new(self.from_ast_term(term)?)),
+        };
+
+        Ok(term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        // the methods of an object share the representation bound around them
+        assert_eq!(
+            Parser::new().parse("lambda r:{x:Ref Nat}.{get = lambda u:Unit.!r.x};"),
+            Ok(Term::TmAbs(
+                "r".to_string(),
+                Type::Record(vec![("x".to_string(), Type::Ref(Box::new(Type::Number)))]),
+                Box::new(Term::TmRecord(vec![(
+                    "get".to_string(),
+                    Term::TmAbs(
+                        "u".to_string(),
+                        Type::Unit,
+                        Box::new(Term::TmDeref(Box::new(Term::TmProj(
+                            Box::new(Term::TmVar(1)),
+                            "x".to_string()
+                        ))))
+                    )
+                )]))
+            ))
+        );
+        assert_eq!(
+            Parser::new().parse("fix (lambda s:{}.s.get);"),
+            Ok(Term::TmFix(Box::new(Term::TmAbs(
+                "s".to_string(),
+                Type::Record(vec![]),
+                Box::new(Term::TmProj(Box::new(Term::TmVar(0)), "get".to_string()))
+            ))))
+        );
+        assert_eq!(
+            Parser::new().parse("{get = lambda u:Unit.!x};"),
+            Err(ParseError::UnboundVariable("x".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_words() {
+        let parse = |input: &str| {
+            Parser::new()
+                .parse(input)
+                .map_err(|error| error.to_string())
+        };
+        // names are whole words of any length, keywords are not split off them
+        assert_eq!(
+            parse("let counter = ref 0 in !counter;"),
+            Ok(Term::TmLet(
+                "counter".to_string(),
+                Box::new(Term::TmRef(Box::new(Term::TmZero))),
+                Box::new(Term::TmDeref(Box::new(Term::TmVar(0))))
+            ))
+        );
+        assert_eq!(
+            parse("lambda refs:Unit.refs;"),
+            Ok(Term::TmAbs(
+                "refs".to_string(),
+                Type::Unit,
+                Box::new(Term::TmVar(0))
+            ))
+        );
+        assert_eq!(
+            parse("lambda fix:Unit.fix;"),
+            Err("1:8: expected a variable, found `fix`".to_string())
+        );
+        assert_eq!(
+            parse("{get = 0 = 0};"),
+            Err("1:10: expected `}`, found `=`".to_string())
+        );
+        assert_eq!(
+            parse("unit; 0"),
+            Err("1:8: expected `;`, found end of input".to_string())
+        );
+        assert_eq!(parse("0; 0;;"), Err("unexpected input `;`".to_string()));
+        // nesting is bounded instead of overflowing the stack
+        let deep = format!("{}0{};", "(".repeat(300), ")".repeat(300));
+        assert!(parse(&deep)
+            .unwrap_err()
+            .contains("at most 256 nested terms"));
+        let methods = format!("lambda r:{{}}.r{};", ".get".repeat(300));
+        assert!(parse(&methods)
+            .unwrap_err()
+            .contains("at most 256 nested terms"));
+        let sequence = format!("{}unit;", "unit; ".repeat(300));
+        assert!(parse(&sequence)
+            .unwrap_err()
+            .contains("at most 256 nested terms"));
+    }
+}
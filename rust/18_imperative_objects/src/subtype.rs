@@ -0,0 +1,93 @@
+use subtyping::subtype::{field, subtype_with, Shape, Subtype};
+
+use crate::typing::Type;
+
+impl Subtype for Type {
+    fn shape(&self) -> Shape<'_, Type> {
+        match self {
+            Type::Top => Shape::Top,
+            Type::Arrow(param, ret) => Shape::Arrow(param, ret),
+            Type::Record(fields) => Shape::Record(fields),
+            Type::Ref(typ) => Shape::Ref(typ),
+            Type::Unit | Type::Boolean | Type::Number => Shape::Base,
+        }
+    }
+}
+
+// S <: T with the rules of chapter 15 plus S-Ref: an object type with more
+// methods is a subtype of one with fewer, which is all the chapter 18
+// encodings need for inheritance
+pub fn subtype(s: &Type, t: &Type) -> bool {
+    subtype_with(s, t, subtype)
+}
+
+// S ∨ T, the least common supertype of the arms of a conditional. Without a
+// Bot there are no meets, so arrows only join when their parameters agree,
+// and Top stands in for the rest.
+pub fn join(s: &Type, t: &Type) -> Type {
+    match (s, t) {
+        _ if subtype(s, t) => t.clone(),
+        _ if subtype(t, s) => s.clone(),
+        (Type::Arrow(s1, s2), Type::Arrow(t1, t2)) if subtype(s1, t1) && subtype(t1, s1) => {
+            Type::Arrow(s1.clone(), Box::new(join(s2, t2)))
+        }
+        // the labels both records have, in the order of the first one
+        (Type::Record(s_fields), Type::Record(t_fields)) => Type::Record(
+            s_fields
+                .iter()
+                .filter_map(|(label, s_field)| {
+                    field(t_fields, label).map(|t_field| (label.clone(), join(s_field, t_field)))
+                })
+                .collect(),
+        ),
+        _ => Type::Top,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tapl_frontend::lexer::Lexer;
+
+    use crate::{parser::SYMBOLS, type_parser::parse_type};
+
+    fn typ(input: &str) -> Type {
+        let mut lexer = Lexer::new(input, &SYMBOLS);
+        let typ = parse_type(&mut lexer).unwrap();
+        assert_eq!(lexer.rest(), "");
+        typ
+    }
+
+    #[test]
+    fn test_subtype() {
+        let counter = typ("{get:Unit->Nat, inc:Unit->Unit}");
+        let reset_counter = typ("{get:Unit->Nat, inc:Unit->Unit, reset:Unit->Unit}");
+        assert!(subtype(&reset_counter, &counter));
+        assert!(!subtype(&counter, &reset_counter));
+        // a class over a smaller representation works on a bigger one
+        assert!(subtype(
+            &typ("{x:Ref Nat}->{get:Unit->Nat}"),
+            &typ("{x:Ref Nat, b:Ref Nat}->{}")
+        ));
+        assert!(!subtype(&typ("Ref {x:Nat, y:Nat}"), &typ("Ref {x:Nat}")));
+        assert!(subtype(
+            &typ("Ref {x:Nat, y:Nat}"),
+            &typ("Ref {y:Nat, x:Nat}")
+        ));
+        assert!(subtype(&typ("Ref Nat"), &typ("Top")));
+
+        assert_eq!(
+            join(&reset_counter, &typ("{inc:Unit->Unit, x:Nat}")),
+            typ("{inc:Unit->Unit}")
+        );
+        assert_eq!(
+            join(&typ("Unit->{a:Nat}"), &typ("Unit->{a:Nat, b:Nat}")),
+            typ("Unit->{a:Nat}")
+        );
+        assert_eq!(
+            join(&typ("Ref {a:Nat}"), &typ("Ref {a:Nat, b:Nat}")),
+            Type::Top
+        );
+        assert_eq!(join(&typ("{a:Nat}->Nat"), &typ("{b:Nat}->Nat")), Type::Top);
+    }
+}
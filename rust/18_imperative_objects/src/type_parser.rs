@@ -0,0 +1,104 @@
+use tapl_frontend::lexer::{Lexer, SyntaxError, Token};
+
+use crate::typing::Type;
+
+pub const TYPE_KEYWORDS: [&str; 5] = ["Top", "Unit", "Bool", "Nat", "Ref"];
+
+// `{get:Unit->Nat, inc:Unit->Unit}` after the `{`, the type of an object is
+// the record of its methods
+fn parse_record_type(lexer: &mut Lexer) -> Result<Type, SyntaxError> {
+    let mut fields = vec![];
+    if lexer.eat_symbol("}")?.is_none() {
+        loop {
+            let (label, _) = lexer.expect_word("a label", &[])?;
+            lexer.expect_symbol(":")?;
+            fields.push((label.to_string(), parse_type(lexer)?));
+            if lexer.eat_symbol(",")?.is_none() {
+                break;
+            }
+        }
+        lexer.expect_symbol("}")?;
+    }
+    Ok(Type::Record(fields))
+}
+
+fn parse_atom_type(lexer: &mut Lexer) -> Result<Type, SyntaxError> {
+    lexer.nested(|lexer| {
+        let (token, span) = lexer.next_token()?;
+        match token {
+            Token::Word("Ref") => Ok(Type::Ref(Box::new(parse_atom_type(lexer)?))),
+            Token::Word("Top") => Ok(Type::Top),
+            Token::Word("Unit") => Ok(Type::Unit),
+            Token::Word("Bool") => Ok(Type::Boolean),
+            Token::Word("Nat") => Ok(Type::Number),
+            Token::Symbol("{") => parse_record_type(lexer),
+            Token::Symbol("(") => {
+                let typ = parse_type(lexer)?;
+                lexer.expect_symbol(")")?;
+                Ok(typ)
+            }
+            _ => Err(lexer.error_at("a type", &token.to_string(), span)),
+        }
+    })
+}
+
+// arrow types are right associative: `A->B->C` is `A->(B->C)`
+pub fn parse_type(lexer: &mut Lexer) -> Result<Type, SyntaxError> {
+    lexer.nested(|lexer| {
+        let typ = parse_atom_type(lexer)?;
+        match lexer.eat_symbol("->")? {
+            Some(_) => Ok(Type::Arrow(Box::new(typ), Box::new(parse_type(lexer)?))),
+            None => Ok(typ),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SYMBOLS;
+
+    fn parse(input: &str) -> Result<(&str, Type), String> {
+        let mut lexer = Lexer::new(input, &SYMBOLS);
+        parse_type(&mut lexer)
+            .map(|typ| (lexer.rest(), typ))
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn test_type() {
+        assert_eq!(
+            parse("{x:Ref Nat}->Unit->{get:Unit->Nat}"),
+            Ok((
+                "",
+                Type::Arrow(
+                    Box::new(Type::Record(vec![(
+                        "x".to_string(),
+                        Type::Ref(Box::new(Type::Number))
+                    )])),
+                    Box::new(Type::Arrow(
+                        Box::new(Type::Unit),
+                        Box::new(Type::Record(vec![(
+                            "get".to_string(),
+                            Type::Arrow(Box::new(Type::Unit), Box::new(Type::Number))
+                        )]))
+                    ))
+                )
+            ))
+        );
+        assert_eq!(parse("{}"), Ok(("", Type::Record(vec![]))));
+        assert_eq!(
+            Type::Ref(Box::new(Type::Record(vec![]))).to_string(),
+            "Ref {}"
+        );
+        // type names are whole words
+        assert_eq!(
+            parse("RefNat").unwrap_err(),
+            "1:1: expected a type, found `RefNat`"
+        );
+        assert_eq!(
+            parse("{x:Nat y:Nat}").unwrap_err(),
+            "1:8: expected `}`, found `y`"
+        );
+    }
+}
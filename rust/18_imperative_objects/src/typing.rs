@@ -0,0 +1,247 @@
+use std::fmt::{self, Formatter};
+
+use crate::{
+    context::Context,
+    parser::Term,
+    subtype::{join, subtype},
+};
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum TypeError {
+    ParameterTypeMismatch,
+    ArrowTypeExpected,
+    NumberExpected,
+    IfConditionNotBoolean,
+    UnboundVariable(usize),
+    RecordTypeExpected,
+    LabelNotFound(String),
+    RefTypeExpected,
+    AssignTypeMismatch,
+    UnknownLocation(usize),
+    FixTypeMismatch,
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeError::ParameterTypeMismatch => {
+                write!(f, "argument is not a subtype of the parameter type")
+            }
+            TypeError::ArrowTypeExpected => write!(f, "arrow type expected"),
+            TypeError::NumberExpected => write!(f, "argument is not a number"),
+            TypeError::IfConditionNotBoolean => write!(f, "guard of conditional not a boolean"),
+            TypeError::UnboundVariable(index) => write!(f, "unbound variable index {}", index),
+            TypeError::RecordTypeExpected => write!(f, "record type expected"),
+            TypeError::LabelNotFound(label) => write!(f, "label {} not found", label),
+            TypeError::RefTypeExpected => write!(f, "reference type expected"),
+            TypeError::AssignTypeMismatch => {
+                write!(f, "assigned value is not a subtype of the reference type")
+            }
+            TypeError::UnknownLocation(location) => {
+                write!(f, "location {} is not in the store typing", location)
+            }
+            TypeError::FixTypeMismatch => {
+                write!(
+                    f,
+                    "fix expects a function returning a subtype of its parameter"
+                )
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum Type {
+    Top,
+    Unit,
+    Boolean,
+    Number,
+    Arrow(Box<Type>, Box<Type>),
+    // labeled fields in declaration order
+    Record(Vec<(String, Type)>),
+    Ref(Box<Type>),
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Top => write!(f, "Top"),
+            Type::Unit => write!(f, "Unit"),
+            Type::Boolean => write!(f, "Bool"),
+            Type::Number => write!(f, "Nat"),
+            Type::Arrow(param, ret) => match param.as_ref() {
+                Type::Arrow(_, _) => write!(f, "({})->{}", param, ret),
+                _ => write!(f, "{}->{}", param, ret),
+            },
+            Type::Record(fields) => {
+                write!(f, "{{")?;
+                for (i, (label, typ)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}:{}", label, typ)?;
+                }
+                write!(f, "}}")
+            }
+            Type::Ref(typ) => match typ.as_ref() {
+                Type::Arrow(_, _) | Type::Ref(_) => write!(f, "Ref ({})", typ),
+                _ => write!(f, "Ref {}", typ),
+            },
+        }
+    }
+}
+
+// Γ | Σ ⊢ t : T with algorithmic subtyping (TAPL 13.4, 16.2): subsumption
+// is used where a term meets an expected type, at arguments, assignments and
+// fixed points, and the arms of a conditional join
+pub fn type_of(ctx: &mut Context, store_typing: &[Type], term: &Term) -> Result<Type, TypeError> {
+    match term {
+        Term::TmUnit => Ok(Type::Unit),
+        Term::TmTrue | Term::TmFalse => Ok(Type::Boolean),
+        Term::TmZero => Ok(Type::Number),
+        Term::TmSucc(term) | Term::TmPred(term) => match type_of(ctx, store_typing, term)? {
+            Type::Number => Ok(Type::Number),
+            _ => Err(TypeError::NumberExpected),
+        },
+        Term::TmIsZero(term) => match type_of(ctx, store_typing, term)? {
+            Type::Number => Ok(Type::Boolean),
+            _ => Err(TypeError::NumberExpected),
+        },
+        Term::TmVar(index) => ctx
+            .get_type(*index)
+            .ok_or(TypeError::UnboundVariable(*index)),
+        Term::TmAbs(name, typ, body) => {
+            ctx.push(name.clone(), typ.clone());
+            let body_type = type_of(ctx, store_typing, body);
+            ctx.pop();
+            Ok(Type::Arrow(Box::new(typ.clone()), Box::new(body_type?)))
+        }
+        // TA-App
+        Term::TmApp(left, right) => {
+            let left_type = type_of(ctx, store_typing, left)?;
+            let right_type = type_of(ctx, store_typing, right)?;
+            match left_type {
+                Type::Arrow(param_type, return_type) if subtype(&right_type, &param_type) => {
+                    Ok(*return_type)
+                }
+                Type::Arrow(_, _) => Err(TypeError::ParameterTypeMismatch),
+                _ => Err(TypeError::ArrowTypeExpected),
+            }
+        }
+        // TA-If
+        Term::TmIf(if_term, then_term, else_term) => {
+            if type_of(ctx, store_typing, if_term)? != Type::Boolean {
+                return Err(TypeError::IfConditionNotBoolean);
+            }
+            let then_type = type_of(ctx, store_typing, then_term)?;
+            let else_type = type_of(ctx, store_typing, else_term)?;
+            Ok(join(&then_type, &else_type))
+        }
+        Term::TmLet(name, bound, body) => {
+            let bound_type = type_of(ctx, store_typing, bound)?;
+            ctx.push(name.clone(), bound_type);
+            let body_type = type_of(ctx, store_typing, body);
+            ctx.pop();
+            body_type
+        }
+        Term::TmRecord(fields) => Ok(Type::Record(
+            fields
+                .iter()
+                .map(|(label, term)| Ok((label.clone(), type_of(ctx, store_typing, term)?)))
+                .collect::<Result<Vec<_>, TypeError>>()?,
+        )),
+        // TA-Proj
+        Term::TmProj(term, label) => match type_of(ctx, store_typing, term)? {
+            Type::Record(fields) => fields
+                .into_iter()
+                .find(|(name, _)| name == label)
+                .map(|(_, typ)| typ)
+                .ok_or_else(|| TypeError::LabelNotFound(label.clone())),
+            _ => Err(TypeError::RecordTypeExpected),
+        },
+        Term::TmRef(term) => Ok(Type::Ref(Box::new(type_of(ctx, store_typing, term)?))),
+        Term::TmDeref(term) => match type_of(ctx, store_typing, term)? {
+            Type::Ref(typ) => Ok(*typ),
+            _ => Err(TypeError::RefTypeExpected),
+        },
+        Term::TmAssign(left, right) => match type_of(ctx, store_typing, left)? {
+            Type::Ref(typ) if subtype(&type_of(ctx, store_typing, right)?, &typ) => Ok(Type::Unit),
+            Type::Ref(_) => Err(TypeError::AssignTypeMismatch),
+            _ => Err(TypeError::RefTypeExpected),
+        },
+        Term::TmLoc(location) => store_typing
+            .get(*location)
+            .map(|typ| Type::Ref(Box::new(typ.clone())))
+            .ok_or(TypeError::UnknownLocation(*location)),
+        // fix f unrolls to f (fix f), so the result of f has to fit its
+        // parameter, and fix f has the smaller of the two types
+        Term::TmFix(term) => match type_of(ctx, store_typing, term)? {
+            Type::Arrow(param_type, return_type) if subtype(&return_type, &param_type) => {
+                Ok(*return_type)
+            }
+            _ => Err(TypeError::FixTypeMismatch),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tapl_frontend::lexer::Lexer;
+
+    use crate::{
+        parser::{Parser, SYMBOLS},
+        type_parser::parse_type,
+    };
+
+    fn type_str(input: &str) -> Result<Type, TypeError> {
+        let term = Parser::new().parse(input).unwrap();
+        type_of(&mut Context::default(), &[], &term)
+    }
+
+    fn typ(input: &str) -> Type {
+        parse_type(&mut Lexer::new(input, &SYMBOLS)).unwrap()
+    }
+
+    #[test]
+    fn test_type_of() {
+        // an object is a record of closures over its instance variables
+        assert_eq!(
+            type_str(
+                "let x = ref succ(0) in \
+                 {get = lambda u:Unit.!x, inc = lambda u:Unit.x := succ(!x)};"
+            ),
+            Ok(typ("{get:Unit->Nat, inc:Unit->Unit}"))
+        );
+        // a record with more fields can be stored in a reference to fewer
+        assert_eq!(
+            type_str("lambda r:Ref {x:Nat}.r := {x=0, y=true};"),
+            Ok(typ("(Ref {x:Nat})->Unit"))
+        );
+        // but references are invariant
+        assert_eq!(
+            type_str("(lambda r:Ref {x:Nat}.!r) (ref {x=0, y=true});"),
+            Err(TypeError::ParameterTypeMismatch)
+        );
+        assert_eq!(
+            type_str("fix (lambda s:{a:Nat}.{a=0, b=true});"),
+            Ok(typ("{a:Nat, b:Bool}"))
+        );
+        assert_eq!(
+            type_str("fix (lambda s:{a:Nat, b:Bool}.{a=0});"),
+            Err(TypeError::FixTypeMismatch)
+        );
+        assert_eq!(
+            type_str("(ref 0) := true;"),
+            Err(TypeError::AssignTypeMismatch)
+        );
+        assert_eq!(
+            type_str("{get=0}.set;"),
+            Err(TypeError::LabelNotFound("set".to_string()))
+        );
+        assert_eq!(
+            type_of(&mut Context::default(), &[Type::Number], &Term::TmLoc(0)),
+            Ok(typ("Ref Nat"))
+        );
+    }
+}
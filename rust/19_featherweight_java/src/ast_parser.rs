@@ -0,0 +1,252 @@
+use tapl_frontend::lexer::{Lexer, Token};
+
+use crate::parser::{Class, Constructor, Method, ParseError, Term};
+
+const KEYWORDS: [&str; 5] = ["class", "extends", "new", "return", "super"];
+
+// a class, field, method or variable name
+fn ident<'a>(lexer: &mut Lexer<'a>) -> Result<String, ParseError> {
+    let (name, _) = lexer.expect_word("a name", &KEYWORDS)?;
+    Ok(name.to_string())
+}
+
+// what follows the `(` of an argument or parameter list, up to the `)`
+fn parse_list<'a, T>(
+    lexer: &mut Lexer<'a>,
+    mut item: impl FnMut(&mut Lexer<'a>) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    let mut items = vec![];
+    if lexer.eat_symbol(")")?.is_none() {
+        loop {
+            items.push(item(lexer)?);
+            if lexer.eat_symbol(",")?.is_none() {
+                break;
+            }
+        }
+        lexer.expect_symbol(")")?;
+    }
+    Ok(items)
+}
+
+fn parse_arguments(lexer: &mut Lexer) -> Result<Vec<Term>, ParseError> {
+    lexer.expect_symbol("(")?;
+    parse_list(lexer, parse_term)
+}
+
+// `(C)` followed by a term is a cast, any other `(` a parenthesized term
+fn is_cast(lexer: &Lexer) -> Result<bool, ParseError> {
+    let mut ahead = lexer.clone();
+    if ident(&mut ahead).is_err() || ahead.eat_symbol(")")?.is_none() {
+        return Ok(false);
+    }
+    Ok(match ahead.peek()?.0 {
+        Token::Word(word) => word == "new" || !KEYWORDS.contains(&word),
+        Token::Symbol("(") => true,
+        _ => false,
+    })
+}
+
+fn parse_atom(lexer: &mut Lexer) -> Result<Term, ParseError> {
+    lexer.nested(|lexer| {
+        if lexer.eat_keyword("new")?.is_some() {
+            // `new C(t1, ..., tn)`
+            let class = ident(lexer)?;
+            return Ok(Term::New(class, parse_arguments(lexer)?));
+        }
+        if lexer.eat_symbol("(")?.is_none() {
+            return Ok(Term::Var(ident(lexer)?));
+        }
+        if is_cast(lexer)? {
+            // `(C) t`, the cast covers field accesses and invocations of `t`
+            let class = ident(lexer)?;
+            lexer.expect_symbol(")")?;
+            return Ok(Term::Cast(class, Box::new(parse_postfix(lexer)?)));
+        }
+        let term = parse_term(lexer)?;
+        lexer.expect_symbol(")")?;
+        Ok(term)
+    })
+}
+
+// `t.f` and `t.m(t1, ..., tn)`, left associative, and every selector nests
+// the term one level deeper
+fn parse_postfix(lexer: &mut Lexer) -> Result<Term, ParseError> {
+    lexer.nested(|lexer| {
+        let mut term = parse_atom(lexer)?;
+        while lexer.eat_symbol(".")?.is_some() {
+            lexer.deepen()?;
+            let name = ident(lexer)?;
+            term = match lexer.eat_symbol("(")? {
+                Some(_) => Term::Invoke(Box::new(term), name, parse_list(lexer, parse_term)?),
+                None => Term::Field(Box::new(term), name),
+            };
+        }
+        Ok(term)
+    })
+}
+
+pub fn parse_term(lexer: &mut Lexer) -> Result<Term, ParseError> {
+    parse_postfix(lexer)
+}
+
+// `C f`, a field declaration or a parameter
+fn parse_typed_name(lexer: &mut Lexer) -> Result<(String, String), ParseError> {
+    let typ = ident(lexer)?;
+    Ok((typ, ident(lexer)?))
+}
+
+// `(C1 f1, ...) { super(g1, ...); this.f1 = f1; ... }` after the class name
+fn parse_constructor(lexer: &mut Lexer, class: String) -> Result<Constructor, ParseError> {
+    lexer.expect_symbol("(")?;
+    let parameters = parse_list(lexer, parse_typed_name)?;
+    lexer.expect_symbol("{")?;
+    lexer.expect_keyword("super")?;
+    lexer.expect_symbol("(")?;
+    let super_arguments = parse_list(lexer, ident)?;
+    lexer.expect_symbol(";")?;
+    let mut assignments = vec![];
+    while lexer.eat_symbol("}")?.is_none() {
+        lexer.expect_keyword("this")?;
+        lexer.expect_symbol(".")?;
+        let field = ident(lexer)?;
+        lexer.expect_symbol("=")?;
+        let name = ident(lexer)?;
+        lexer.expect_symbol(";")?;
+        assignments.push((field, name));
+    }
+    Ok(Constructor {
+        class,
+        parameters,
+        super_arguments,
+        assignments,
+    })
+}
+
+// `C m(C1 x1, ...) { return t; }`
+fn parse_method(lexer: &mut Lexer) -> Result<Method, ParseError> {
+    let (return_type, name) = parse_typed_name(lexer)?;
+    lexer.expect_symbol("(")?;
+    let parameters = parse_list(lexer, parse_typed_name)?;
+    lexer.expect_symbol("{")?;
+    lexer.expect_keyword("return")?;
+    let body = parse_term(lexer)?;
+    lexer.expect_symbol(";")?;
+    lexer.expect_symbol("}")?;
+    Ok(Method {
+        return_type,
+        name,
+        parameters,
+        body,
+    })
+}
+
+// `class C extends D { fields constructor methods }`
+pub fn parse_class(lexer: &mut Lexer) -> Result<Class, ParseError> {
+    lexer.expect_keyword("class")?;
+    let name = ident(lexer)?;
+    lexer.expect_keyword("extends")?;
+    let superclass = ident(lexer)?;
+    lexer.expect_symbol("{")?;
+    // the fields `C f;` come first, a name followed by `(` starts the
+    // constructor
+    let mut fields = vec![];
+    let constructor = loop {
+        let typ = ident(lexer)?;
+        if matches!(lexer.peek()?.0, Token::Symbol("(")) {
+            break parse_constructor(lexer, typ)?;
+        }
+        let field = ident(lexer)?;
+        lexer.expect_symbol(";")?;
+        fields.push((typ, field));
+    };
+    let mut methods = vec![];
+    while lexer.eat_symbol("}")?.is_none() {
+        methods.push(parse_method(lexer)?);
+    }
+    Ok(Class {
+        name,
+        superclass,
+        fields,
+        constructor,
+        methods,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SYMBOLS;
+
+    fn parse<'a, T>(
+        parse: impl FnOnce(&mut Lexer<'a>) -> Result<T, ParseError>,
+        input: &'a str,
+    ) -> Result<(&'a str, T), ParseError> {
+        let mut lexer = Lexer::new(input, &SYMBOLS);
+        parse(&mut lexer).map(|result| (lexer.rest(), result))
+    }
+
+    #[test]
+    fn test_parse_term() {
+        let var = |name: &str| Term::Var(name.to_string());
+        assert_eq!(
+            parse(parse_term, "new Pair(new A(), x).setfst(y).fst"),
+            Ok((
+                "",
+                Term::Field(
+                    Box::new(Term::Invoke(
+                        Box::new(Term::New(
+                            "Pair".to_string(),
+                            vec![Term::New("A".to_string(), vec![]), var("x")]
+                        )),
+                        "setfst".to_string(),
+                        vec![var("y")]
+                    )),
+                    "fst".to_string()
+                )
+            ))
+        );
+        assert_eq!(
+            parse(parse_term, "((Pair) p.fst).snd"),
+            Ok((
+                "",
+                Term::Field(
+                    Box::new(Term::Cast(
+                        "Pair".to_string(),
+                        Box::new(Term::Field(Box::new(var("p")), "fst".to_string()))
+                    )),
+                    "snd".to_string()
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_class() {
+        let (rest, class) = parse(
+            parse_class,
+            "class Pair extends Object {
+                Object fst;
+                Object snd;
+                Pair(Object fst, Object snd) { super(); this.fst = fst; this.snd = snd; }
+                Pair setfst(Object newfst) { return new Pair(newfst, this.snd); }
+            }",
+        )
+        .unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(class.name, "Pair");
+        assert_eq!(class.superclass, "Object");
+        assert_eq!(class.fields.len(), 2);
+        assert_eq!(
+            class.constructor.assignments,
+            vec![
+                ("fst".to_string(), "fst".to_string()),
+                ("snd".to_string(), "snd".to_string())
+            ]
+        );
+        assert_eq!(class.methods[0].name, "setfst");
+        assert_eq!(
+            class.methods[0].parameters,
+            vec![("Object".to_string(), "newfst".to_string())]
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use crate::parser::{Class, Method, Term};
+
+pub const OBJECT: &str = "Object";
+
+// The class declarations of a program with the auxiliary definitions of
+// TAPL 19.2: field lookup, method type and body lookup and subtyping. `Object`
+// is built in and has no fields and no methods.
+#[derive(Clone, Debug, Default)]
+pub struct ClassTable {
+    classes: Vec<Class>,
+}
+
+impl ClassTable {
+    pub fn new(classes: Vec<Class>) -> Self {
+        ClassTable { classes }
+    }
+
+    pub fn classes(&self) -> &[Class] {
+        &self.classes
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Class> {
+        self.classes.iter().find(|class| class.name == name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        name == OBJECT || self.get(name).is_some()
+    }
+
+    // the class and its superclasses up to `Object`, stops at an undeclared
+    // class or when the hierarchy loops
+    pub fn ancestors<'a>(&'a self, name: &'a str) -> Vec<&'a str> {
+        let mut ancestors = vec![];
+        let mut name = name;
+        while !ancestors.contains(&name) {
+            ancestors.push(name);
+            match self.get(name) {
+                Some(class) => name = &class.superclass,
+                None => break,
+            }
+        }
+        ancestors
+    }
+
+    // C <: D, the reflexive and transitive closure of `extends`
+    pub fn subtype(&self, sub: &str, sup: &str) -> bool {
+        self.ancestors(sub).contains(&sup)
+    }
+
+    // fields(C), inherited fields come first
+    pub fn fields(&self, name: &str) -> Option<Vec<(String, String)>> {
+        let mut classes = vec![];
+        for ancestor in self.ancestors(name) {
+            match self.get(ancestor) {
+                Some(class) => classes.push(class),
+                None if ancestor == OBJECT => break,
+                None => return None,
+            }
+        }
+        let fields = classes.iter().rev().flat_map(|class| class.fields.clone());
+        Some(fields.collect())
+    }
+
+    fn lookup_method(&self, method: &str, name: &str) -> Option<&Method> {
+        self.ancestors(name)
+            .into_iter()
+            .filter_map(|ancestor| self.get(ancestor))
+            .find_map(|class| class.methods.iter().find(|m| m.name == method))
+    }
+
+    // mtype(m, C), the parameter types and the return type
+    pub fn mtype(&self, method: &str, name: &str) -> Option<(Vec<String>, String)> {
+        self.lookup_method(method, name).map(|method| {
+            let parameter_types = method.parameters.iter().map(|(typ, _)| typ.clone());
+            (parameter_types.collect(), method.return_type.clone())
+        })
+    }
+
+    // mbody(m, C), the parameter names and the body
+    pub fn mbody(&self, method: &str, name: &str) -> Option<(Vec<String>, Term)> {
+        self.lookup_method(method, name).map(|method| {
+            let parameter_names = method.parameters.iter().map(|(_, name)| name.clone());
+            (parameter_names.collect(), method.body.clone())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn test_lookup() {
+        let program = parse(
+            "class A extends Object { Object a; A(Object a) { super(); this.a = a; }
+                 Object get() { return this.a; } }
+             class B extends A { Object b; B(Object a, Object b) { super(a); this.b = b; } }
+             new B(x, y);",
+        )
+        .unwrap();
+        let table = ClassTable::new(program.classes);
+        assert!(table.subtype("B", "A"));
+        assert!(table.subtype("B", OBJECT));
+        assert!(!table.subtype("A", "B"));
+        assert_eq!(
+            table.fields("B"),
+            Some(vec![
+                ("Object".to_string(), "a".to_string()),
+                ("Object".to_string(), "b".to_string())
+            ])
+        );
+        assert_eq!(table.fields("C"), None);
+        assert_eq!(
+            table.mtype("get", "B"),
+            Some((vec![], "Object".to_string()))
+        );
+        assert_eq!(table.mbody("set", "B"), None);
+    }
+}
@@ -0,0 +1,20 @@
+// Typing context of a method body: its parameters and `this`, mapped to their
+// classes
+#[derive(Clone, Debug, Default)]
+pub struct Context {
+    bindings: Vec<(String, String)>,
+}
+
+impl Context {
+    pub fn push(&mut self, name: String, class: String) {
+        self.bindings.push((name, class));
+    }
+
+    pub fn get_type(&self, name: &str) -> Option<&str> {
+        self.bindings
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, class)| class.as_str())
+    }
+}
@@ -0,0 +1,172 @@
+use std::fmt::{self, Formatter};
+
+use crate::{class_table::ClassTable, parser::Term, substitute::substitute};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EvalError {
+    NoRuleApplies,
+    // the class of the object and the target class
+    CastFailed(String, String),
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::NoRuleApplies => write!(f, "no rule applies"),
+            EvalError::CastFailed(class, target) => {
+                write!(f, "cannot cast an object of class {} to {}", class, target)
+            }
+        }
+    }
+}
+
+// objects `new C(v1, ..., vn)` are the only values
+pub fn is_val(term: &Term) -> bool {
+    match term {
+        Term::New(_, arguments) => arguments.iter().all(is_val),
+        _ => false,
+    }
+}
+
+// evaluates the leftmost argument that is not a value yet
+fn eval_arguments(table: &ClassTable, arguments: &[Term]) -> Result<Vec<Term>, EvalError> {
+    let mut arguments = arguments.to_vec();
+    let argument = arguments
+        .iter_mut()
+        .find(|term| !is_val(term))
+        .ok_or(EvalError::NoRuleApplies)?;
+    *argument = eval1(table, argument)?;
+    Ok(arguments)
+}
+
+// t -> t', the rules E-* of TAPL 19.3
+pub fn eval1(table: &ClassTable, term: &Term) -> Result<Term, EvalError> {
+    let boxed = |term: Result<Term, EvalError>| term.map(Box::new);
+    match term {
+        Term::Field(object, field) => match object.as_ref() {
+            // E-ProjNew
+            Term::New(class, arguments) if is_val(object) => table
+                .fields(class)
+                .and_then(|fields| fields.iter().position(|(_, name)| name == field))
+                .and_then(|i| arguments.get(i).cloned())
+                .ok_or(EvalError::NoRuleApplies),
+            _ => Ok(Term::Field(boxed(eval1(table, object))?, field.clone())),
+        },
+        Term::Invoke(object, method, arguments) => match object.as_ref() {
+            _ if !is_val(object) => Ok(Term::Invoke(
+                boxed(eval1(table, object))?,
+                method.clone(),
+                arguments.clone(),
+            )),
+            // E-InvkNew
+            Term::New(class, _) if arguments.iter().all(is_val) => {
+                let (parameters, body) =
+                    table.mbody(method, class).ok_or(EvalError::NoRuleApplies)?;
+                let mut substitution = parameters
+                    .into_iter()
+                    .zip(arguments.iter().cloned())
+                    .collect::<Vec<_>>();
+                substitution.push(("this".to_string(), object.as_ref().clone()));
+                Ok(substitute(&body, &substitution))
+            }
+            _ => Ok(Term::Invoke(
+                object.clone(),
+                method.clone(),
+                eval_arguments(table, arguments)?,
+            )),
+        },
+        Term::New(class, arguments) => {
+            Ok(Term::New(class.clone(), eval_arguments(table, arguments)?))
+        }
+        Term::Cast(target, object) => match object.as_ref() {
+            // E-CastNew
+            Term::New(class, _) if is_val(object) => {
+                if table.subtype(class, target) {
+                    Ok(object.as_ref().clone())
+                } else {
+                    Err(EvalError::CastFailed(class.clone(), target.clone()))
+                }
+            }
+            _ => Ok(Term::Cast(target.clone(), boxed(eval1(table, object))?)),
+        },
+        Term::Var(_) => Err(EvalError::NoRuleApplies),
+    }
+}
+
+pub fn eval(table: &ClassTable, term: &Term) -> Result<Term, EvalError> {
+    let mut term = term.clone();
+    loop {
+        match eval1(table, &term) {
+            Ok(next_term) => term = next_term,
+            Err(EvalError::NoRuleApplies) => return Ok(term),
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::language::FeatherweightJava;
+    use tapl_testkit::{assert_evals_to, assert_step_trace};
+
+    #[test]
+    fn test_eval() {
+        assert_evals_to!(
+            FeatherweightJava,
+            "new Pair(new A(), new B()).setfst(new B());",
+            "new Pair(new B(), new B())"
+        );
+        assert_evals_to!(
+            FeatherweightJava,
+            "((Pair) new Pair(new Pair(new A(), new B()), new A()).fst).snd;",
+            "new B()"
+        );
+        assert_step_trace!(
+            FeatherweightJava,
+            "((Pair) new Pair(new Pair(new A(), new B()), new A()).fst).snd;",
+            [
+                "((Pair) new Pair(new A(), new B())).snd",
+                "new Pair(new A(), new B()).snd",
+                "new B()",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cast_failed() {
+        use super::*;
+        use crate::{parser::parse, typing::check_program};
+
+        // a stupid cast typechecks but gets stuck at runtime
+        let program = parse(
+            "class A extends Object { A() { super(); } }
+             class B extends Object { B() { super(); } }
+             (A) new B();",
+        )
+        .unwrap();
+        assert_eq!(check_program(&program), Ok("A".to_string()));
+        assert_eq!(
+            eval(&ClassTable::new(program.classes), &program.term),
+            Err(EvalError::CastFailed("B".to_string(), "A".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_missing_argument() {
+        use super::*;
+        use crate::parser::parse;
+
+        // an ill-typed object with fewer arguments than fields is stuck on
+        // a field it lacks instead of panicking
+        let program = parse(
+            "class P extends Object {
+                 Object fst; Object snd;
+                 P(Object fst, Object snd) { super(); this.fst = fst; this.snd = snd; }
+             }
+             new P(new Object()).snd;",
+        )
+        .unwrap();
+        let table = ClassTable::new(program.classes);
+        assert_eq!(eval1(&table, &program.term), Err(EvalError::NoRuleApplies));
+    }
+}
@@ -0,0 +1,54 @@
+use tapl_testkit::{Failure, Language};
+
+use crate::{
+    class_table::ClassTable,
+    eval::{eval, eval1},
+    parser::{parse, Program},
+    typing::check_program,
+};
+
+// The classes of TAPL 19.2, declared before every test program
+const CLASSES: &str = "
+    class A extends Object { A() { super(); } }
+    class B extends Object { B() { super(); } }
+    class Pair extends Object {
+        Object fst;
+        Object snd;
+        Pair(Object fst, Object snd) { super(); this.fst = fst; this.snd = snd; }
+        Pair setfst(Object newfst) { return new Pair(newfst, this.snd); }
+    }
+";
+
+pub struct FeatherweightJava;
+
+impl Language for FeatherweightJava {
+    type Term = Program;
+    type Value = Program;
+
+    fn parse(input: &str) -> Result<Program, Failure> {
+        parse(&format!("{}{}", CLASSES, input)).map_err(|error| Failure::Parse(error.to_string()))
+    }
+
+    fn type_check(program: &Program) -> Result<(), Failure> {
+        check_program(program)
+            .map(|_| ())
+            .map_err(|error| Failure::Type(error.to_string()))
+    }
+
+    fn eval(program: &Program) -> Result<Program, Failure> {
+        let term = eval(&ClassTable::new(program.classes.clone()), &program.term)
+            .map_err(|error| Failure::Eval(error.to_string()))?;
+        Ok(Program {
+            classes: program.classes.clone(),
+            term,
+        })
+    }
+
+    fn step(program: &Program) -> Option<Program> {
+        let term = eval1(&ClassTable::new(program.classes.clone()), &program.term).ok()?;
+        Some(Program {
+            classes: program.classes.clone(),
+            term,
+        })
+    }
+}
@@ -0,0 +1,9 @@
+pub mod ast_parser;
+pub mod class_table;
+pub mod context;
+pub mod eval;
+#[cfg(test)]
+mod language;
+pub mod parser;
+pub mod substitute;
+pub mod typing;
@@ -0,0 +1,191 @@
+use std::fmt::{self, Formatter};
+
+use tapl_frontend::lexer::{Lexer, SyntaxError, Token};
+
+use crate::ast_parser::{parse_class, parse_term};
+
+pub const SYMBOLS: [&str; 8] = ["(", ")", "{", "}", ".", ",", ";", "="];
+
+// FJ terms have no binders besides method parameters, so variables keep their
+// names (TAPL 19.2)
+#[derive(Clone, Debug, PartialEq)]
+pub enum Term {
+    Var(String),
+    // object and field name
+    Field(Box<Term>, String),
+    // receiver, method name and arguments
+    Invoke(Box<Term>, String, Vec<Term>),
+    // class name and constructor arguments
+    New(String, Vec<Term>),
+    // target class and the cast term
+    Cast(String, Box<Term>),
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let write_arguments = |f: &mut Formatter<'_>, arguments: &[Term]| {
+            write!(f, "(")?;
+            for (i, argument) in arguments.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}", argument)?;
+            }
+            write!(f, ")")
+        };
+        let write_receiver = |f: &mut Formatter<'_>, term: &Term| match term {
+            Term::Cast(_, _) => write!(f, "({})", term),
+            _ => write!(f, "{}", term),
+        };
+        match self {
+            Term::Var(name) => write!(f, "{}", name),
+            Term::Field(term, field) => {
+                write_receiver(f, term)?;
+                write!(f, ".{}", field)
+            }
+            Term::Invoke(term, method, arguments) => {
+                write_receiver(f, term)?;
+                write!(f, ".{}", method)?;
+                write_arguments(f, arguments)
+            }
+            Term::New(class, arguments) => {
+                write!(f, "new {}", class)?;
+                write_arguments(f, arguments)
+            }
+            Term::Cast(class, term) => write!(f, "({}) {}", class, term),
+        }
+    }
+}
+
+// `C(C1 f1, ...) { super(g1, ...); this.f1 = f1; ... }`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Constructor {
+    pub class: String,
+    // parameter types and names
+    pub parameters: Vec<(String, String)>,
+    pub super_arguments: Vec<String>,
+    // assigned field and the parameter assigned to it
+    pub assignments: Vec<(String, String)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Method {
+    pub return_type: String,
+    pub name: String,
+    // parameter types and names
+    pub parameters: Vec<(String, String)>,
+    pub body: Term,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Class {
+    pub name: String,
+    pub superclass: String,
+    // field types and names, inherited fields are not repeated
+    pub fields: Vec<(String, String)>,
+    pub constructor: Constructor,
+    pub methods: Vec<Method>,
+}
+
+// A class table followed by the term to evaluate
+#[derive(Clone, Debug, PartialEq)]
+pub struct Program {
+    pub classes: Vec<Class>,
+    pub term: Term,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    Syntax(SyntaxError),
+    TrailingInput(String),
+}
+
+impl From<SyntaxError> for ParseError {
+    fn from(error: SyntaxError) -> Self {
+        ParseError::Syntax(error)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Syntax(error) => write!(f, "{}", error),
+            ParseError::TrailingInput(input) => write!(f, "unexpected input `{}`", input),
+        }
+    }
+}
+
+pub type ParseResult = Result<Program, ParseError>;
+
+pub fn parse(input: &str) -> ParseResult {
+    let mut lexer = Lexer::new(input, &SYMBOLS);
+    let mut classes = vec![];
+    while matches!(lexer.peek()?.0, Token::Word("class")) {
+        classes.push(parse_class(&mut lexer)?);
+    }
+    let term = parse_term(&mut lexer)?;
+    lexer.expect_symbol(";")?;
+    if !lexer.rest().trim().is_empty() {
+        return Err(ParseError::TrailingInput(lexer.rest().trim().to_string()));
+    }
+
+    Ok(Program { classes, term })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let program = parse(
+            "class A extends Object { A() { super(); } }
+             (Object) new A();",
+        )
+        .unwrap();
+        assert_eq!(program.classes.len(), 1);
+        assert_eq!(
+            program.term,
+            Term::Cast(
+                "Object".to_string(),
+                Box::new(Term::New("A".to_string(), vec![]))
+            )
+        );
+        for input in [
+            "new Pair(new A(), new B()).setfst(new B())",
+            "((Pair) p.fst).snd",
+            "(Object) x.m(y, z).f",
+        ] {
+            assert_eq!(
+                parse(&format!("{};", input)).unwrap().term.to_string(),
+                input
+            );
+        }
+    }
+    #[test]
+    fn test_syntax_error() {
+        let message = |input: &str| parse(input).unwrap_err().to_string();
+        assert_eq!(
+            message("new class();"),
+            "1:5: expected a name, found `class`"
+        );
+        assert_eq!(
+            message("class A extends Object { A() { super() } } new A();"),
+            "1:40: expected `;`, found `}`"
+        );
+        assert_eq!(
+            message("class A extends Object { Object f A() { super(); } } new A();"),
+            "1:35: expected `;`, found `A`"
+        );
+        assert_eq!(message("x.f(y;"), "1:6: expected `)`, found `;`");
+        assert_eq!(message("x; y;"), "unexpected input `y;`");
+        // nesting is bounded instead of overflowing the stack
+        for input in [
+            format!("{}x{};", "(".repeat(300), ")".repeat(300)),
+            format!("x{};", ".f".repeat(300)),
+            format!("{}x;", "(A) ".repeat(300)),
+        ] {
+            assert!(message(&input).contains("at most 256 nested terms"));
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use crate::parser::Term;
+
+// [x1 ↦ t1, ..., xn ↦ tn]term, simultaneous since FJ terms bind no names
+pub fn substitute(term: &Term, substitution: &[(String, Term)]) -> Term {
+    let substitute_all = |terms: &[Term]| {
+        terms
+            .iter()
+            .map(|term| substitute(term, substitution))
+            .collect()
+    };
+    match term {
+        Term::Var(name) => substitution
+            .iter()
+            .find(|(n, _)| n == name)
+            .map_or_else(|| term.clone(), |(_, term)| term.clone()),
+        Term::Field(term, field) => {
+            Term::Field(Box::new(substitute(term, substitution)), field.clone())
+        }
+        Term::Invoke(term, method, arguments) => Term::Invoke(
+            Box::new(substitute(term, substitution)),
+            method.clone(),
+            substitute_all(arguments),
+        ),
+        Term::New(class, arguments) => Term::New(class.clone(), substitute_all(arguments)),
+        Term::Cast(class, term) => {
+            Term::Cast(class.clone(), Box::new(substitute(term, substitution)))
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use std::fmt::{self, Formatter};
+
+use crate::{
+    class_table::{ClassTable, OBJECT},
+    context::Context,
+    parser::{Class, Method, Program, Term},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeError {
+    UnknownClass(String),
+    DuplicateClass(String),
+    CyclicInheritance(String),
+    UnboundVariable(String),
+    // class and field name
+    FieldNotFound(String, String),
+    // class and method name
+    MethodNotFound(String, String),
+    // the constructor or method called with the wrong number of arguments
+    ArgumentCountMismatch(String),
+    // the found class and the expected one
+    NotSubclass(String, String),
+    BadConstructor(String),
+    // class and method name
+    BadOverride(String, String),
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeError::UnknownClass(class) => write!(f, "unknown class {}", class),
+            TypeError::DuplicateClass(class) => write!(f, "class {} is declared twice", class),
+            TypeError::CyclicInheritance(class) => {
+                write!(f, "class {} inherits from itself", class)
+            }
+            TypeError::UnboundVariable(name) => write!(f, "unbound variable {}", name),
+            TypeError::FieldNotFound(class, field) => {
+                write!(f, "class {} has no field {}", class, field)
+            }
+            TypeError::MethodNotFound(class, method) => {
+                write!(f, "class {} has no method {}", class, method)
+            }
+            TypeError::ArgumentCountMismatch(name) => {
+                write!(f, "wrong number of arguments to {}", name)
+            }
+            TypeError::NotSubclass(found, expected) => {
+                write!(f, "{} is not a subclass of {}", found, expected)
+            }
+            TypeError::BadConstructor(class) => write!(
+                f,
+                "constructor of {} must take the inherited and declared fields and \
+                 initialize them",
+                class
+            ),
+            TypeError::BadOverride(class, method) => write!(
+                f,
+                "method {} of {} overrides one with a different type",
+                method, class
+            ),
+        }
+    }
+}
+
+fn check_class_exists(table: &ClassTable, class: &str) -> Result<(), TypeError> {
+    if table.contains(class) {
+        Ok(())
+    } else {
+        Err(TypeError::UnknownClass(class.to_string()))
+    }
+}
+
+fn check_subclass(table: &ClassTable, found: &str, expected: &str) -> Result<(), TypeError> {
+    if table.subtype(found, expected) {
+        Ok(())
+    } else {
+        Err(TypeError::NotSubclass(
+            found.to_string(),
+            expected.to_string(),
+        ))
+    }
+}
+
+fn check_arguments(
+    table: &ClassTable,
+    ctx: &Context,
+    name: &str,
+    arguments: &[Term],
+    parameter_types: &[String],
+) -> Result<(), TypeError> {
+    if arguments.len() != parameter_types.len() {
+        return Err(TypeError::ArgumentCountMismatch(name.to_string()));
+    }
+    for (argument, parameter_type) in arguments.iter().zip(parameter_types) {
+        check_subclass(table, &type_of(table, ctx, argument)?, parameter_type)?;
+    }
+    Ok(())
+}
+
+// Γ ⊢ t : C, the rules T-* of TAPL 19.4
+pub fn type_of(table: &ClassTable, ctx: &Context, term: &Term) -> Result<String, TypeError> {
+    match term {
+        Term::Var(name) => ctx
+            .get_type(name)
+            .map(str::to_string)
+            .ok_or_else(|| TypeError::UnboundVariable(name.clone())),
+        // T-Field
+        Term::Field(term, field) => {
+            let class = type_of(table, ctx, term)?;
+            table
+                .fields(&class)
+                .ok_or_else(|| TypeError::UnknownClass(class.clone()))?
+                .into_iter()
+                .find(|(_, name)| name == field)
+                .map(|(typ, _)| typ)
+                .ok_or(TypeError::FieldNotFound(class, field.clone()))
+        }
+        // T-Invk
+        Term::Invoke(term, method, arguments) => {
+            let class = type_of(table, ctx, term)?;
+            let (parameter_types, return_type) = table
+                .mtype(method, &class)
+                .ok_or_else(|| TypeError::MethodNotFound(class, method.clone()))?;
+            check_arguments(table, ctx, method, arguments, &parameter_types)?;
+            Ok(return_type)
+        }
+        // T-New
+        Term::New(class, arguments) => {
+            let field_types = table
+                .fields(class)
+                .ok_or_else(|| TypeError::UnknownClass(class.clone()))?
+                .into_iter()
+                .map(|(typ, _)| typ)
+                .collect::<Vec<_>>();
+            check_arguments(table, ctx, class, arguments, &field_types)?;
+            Ok(class.clone())
+        }
+        // T-UCast, T-DCast and T-SCast: an unrelated "stupid" cast is allowed
+        // too, it is needed for subject reduction (TAPL 19.4) and fails at
+        // runtime
+        Term::Cast(class, term) => {
+            type_of(table, ctx, term)?;
+            check_class_exists(table, class)?;
+            Ok(class.clone())
+        }
+    }
+}
+
+// override(m, D, C̄ -> C0): a redefined method keeps the inherited type
+fn check_override(table: &ClassTable, class: &Class, method: &Method) -> Result<(), TypeError> {
+    let parameter_types = method.parameters.iter().map(|(typ, _)| typ.clone());
+    match table.mtype(&method.name, &class.superclass) {
+        Some(inherited) if inherited != (parameter_types.collect(), method.return_type.clone()) => {
+            Err(TypeError::BadOverride(
+                class.name.clone(),
+                method.name.clone(),
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+// M OK in C
+fn check_method(table: &ClassTable, class: &Class, method: &Method) -> Result<(), TypeError> {
+    let mut ctx = Context::default();
+    ctx.push("this".to_string(), class.name.clone());
+    for (typ, name) in &method.parameters {
+        check_class_exists(table, typ)?;
+        ctx.push(name.clone(), typ.clone());
+    }
+    check_class_exists(table, &method.return_type)?;
+    check_subclass(
+        table,
+        &type_of(table, &ctx, &method.body)?,
+        &method.return_type,
+    )?;
+    check_override(table, class, method)
+}
+
+// C OK: the constructor takes the inherited fields followed by the declared
+// ones, passes the former to super and assigns the latter
+fn check_class(table: &ClassTable, class: &Class) -> Result<(), TypeError> {
+    for (typ, _) in &class.fields {
+        check_class_exists(table, typ)?;
+    }
+    let inherited = table
+        .fields(&class.superclass)
+        .ok_or_else(|| TypeError::UnknownClass(class.superclass.clone()))?;
+    let constructor = &class.constructor;
+    let bad_constructor = constructor.class != class.name
+        || constructor.parameters != [&inherited[..], &class.fields[..]].concat()
+        || constructor
+            .super_arguments
+            .iter()
+            .ne(inherited.iter().map(|(_, name)| name))
+        || constructor
+            .assignments
+            .iter()
+            .map(|(field, parameter)| (field, parameter))
+            .ne(class.fields.iter().map(|(_, name)| (name, name)));
+    if bad_constructor {
+        return Err(TypeError::BadConstructor(class.name.clone()));
+    }
+    for method in &class.methods {
+        check_method(table, class, method)?;
+    }
+    Ok(())
+}
+
+pub fn check_class_table(table: &ClassTable) -> Result<(), TypeError> {
+    for (i, class) in table.classes().iter().enumerate() {
+        if class.name == OBJECT || table.classes()[..i].iter().any(|c| c.name == class.name) {
+            return Err(TypeError::DuplicateClass(class.name.clone()));
+        }
+        // the walk up the hierarchy either reaches Object, an undeclared
+        // class, or a class it has already visited
+        let ancestors = table.ancestors(&class.name);
+        let last = ancestors[ancestors.len() - 1];
+        if table.get(last).is_some() {
+            return Err(TypeError::CyclicInheritance(class.name.clone()));
+        } else if last != OBJECT {
+            return Err(TypeError::UnknownClass(last.to_string()));
+        }
+    }
+    for class in table.classes() {
+        check_class(table, class)?;
+    }
+    Ok(())
+}
+
+// checks the class table, then types the program's term in it
+pub fn check_program(program: &Program) -> Result<String, TypeError> {
+    let table = ClassTable::new(program.classes.clone());
+    check_class_table(&table)?;
+    type_of(&table, &Context::default(), &program.term)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{language::FeatherweightJava, parser::parse};
+    use tapl_testkit::assert_type_error;
+
+    fn check_str(input: &str) -> Result<String, TypeError> {
+        check_program(&parse(input).unwrap())
+    }
+
+    #[test]
+    fn test_type_of() {
+        assert_eq!(
+            check_str("new Pair(new A(), new B()).setfst(new B());"),
+            Err(TypeError::UnknownClass("Pair".to_string()))
+        );
+        assert_eq!(
+            check_str("class A extends Object { A() { super(); } } (Object) new A();"),
+            Ok("Object".to_string())
+        );
+        assert_type_error!(
+            FeatherweightJava,
+            "new Pair(new A(), new B()).setfst(new B()).fst.snd;",
+            "class Object has no field snd"
+        );
+        assert_type_error!(
+            FeatherweightJava,
+            "new Pair(new A()).fst;",
+            "wrong number of arguments to Pair"
+        );
+        assert_type_error!(
+            FeatherweightJava,
+            "new A().setfst(new B());",
+            "no method setfst"
+        );
+        assert_type_error!(FeatherweightJava, "x;", "unbound variable x");
+    }
+
+    #[test]
+    fn test_class_table() {
+        assert_eq!(
+            check_str("class A extends B { A() { super(); } } class B extends A { B() { super(); } } new A();"),
+            Err(TypeError::CyclicInheritance("A".to_string()))
+        );
+        assert_eq!(
+            check_str("class A extends C { A() { super(); } } new A();"),
+            Err(TypeError::UnknownClass("C".to_string()))
+        );
+        assert_eq!(
+            check_str("class A extends Object { Object a; A(Object b) { super(); this.a = b; } } new A(new A(x));"),
+            Err(TypeError::BadConstructor("A".to_string()))
+        );
+        assert_eq!(
+            check_str(
+                "class A extends Object { A() { super(); } A m() { return new B(); } } new A();"
+            ),
+            Err(TypeError::UnknownClass("B".to_string()))
+        );
+        assert_type_error!(
+            FeatherweightJava,
+            "class C extends Pair { C(Object fst, Object snd) { super(fst, snd); }
+                 Object setfst(Object x) { return x; } }
+             new C(new A(), new B());",
+            "overrides one with a different type"
+        );
+        assert_type_error!(
+            FeatherweightJava,
+            "class C extends Object { C() { super(); } A m(Object x) { return x; } } new C();",
+            "Object is not a subclass of A"
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use tapl_frontend::lexer::{Lexer, Token};
+
+use crate::{
+    parser::ParseError,
+    type_parser::{parse_type, TYPE_KEYWORDS},
+    typing::Type,
+};
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum ASTTerm {
+    TmUnit,
+    TmTrue,
+    TmFalse,
+    TmZero,
+    TmSucc(Box<ASTTerm>),
+    TmPred(Box<ASTTerm>),
+    TmIsZero(Box<ASTTerm>),
+    TmVar(String),
+    // argument ident, argument type and body
+    TmAbs(String, Type, Box<ASTTerm>),
+    TmApp(Box<ASTTerm>, Box<ASTTerm>),
+    // condition term, then term, else term
+    TmIf(Box<ASTTerm>, Box<ASTTerm>, Box<ASTTerm>),
+    // bound ident, bound term and body
+    TmLet(String, Box<ASTTerm>, Box<ASTTerm>),
+    // `{t1, t2}`
+    TmPair(Box<ASTTerm>, Box<ASTTerm>),
+    // `t.1` or `t.2`
+    TmProj(Box<ASTTerm>, usize),
+    // `fold [T] t`
+    TmFold(Type, Box<ASTTerm>),
+    // `unfold [T] t`
+    TmUnfold(Type, Box<ASTTerm>),
+    // `fix t`
+    TmFix(Box<ASTTerm>),
+    // `letrec f:T = t1 and g:U = t2 in t`, the functions and the body
+    TmLetRec(Vec<(String, Type, ASTTerm)>, Box<ASTTerm>),
+}
+
+// the words a variable cannot be named
+pub const KEYWORDS: [&str; 17] = [
+    "lambda", "let", "letrec", "and", "in", "if", "then", "else", "unit", "true", "false", "succ",
+    "pred", "iszero", "fold", "unfold", "fix",
+];
+
+// the keywords an atom starts with
+const ATOM_KEYWORDS: [&str; 9] = [
+    "unit", "true", "false", "succ", "pred", "iszero", "fold", "unfold", "fix",
+];
+
+fn is_reserved(word: &str) -> bool {
+    KEYWORDS.contains(&word) || TYPE_KEYWORDS.contains(&word)
+}
+
+fn variable<'a>(lexer: &mut Lexer<'a>) -> Result<&'a str, ParseError> {
+    match lexer.peek()? {
+        (Token::Word(word), _) if !is_reserved(word) => {
+            lexer.next_token()?;
+            Ok(word)
+        }
+        _ => Err(lexer.unexpected("a variable").into()),
+    }
+}
+
+fn starts_atom(lexer: &mut Lexer) -> Result<bool, ParseError> {
+    Ok(match lexer.peek()?.0 {
+        Token::Word(word) => !is_reserved(word) || ATOM_KEYWORDS.contains(&word),
+        Token::Number(_) | Token::Symbol("(") | Token::Symbol("{") => true,
+        _ => false,
+    })
+}
+
+// `(t)` after `succ`, `pred` or `iszero`
+fn parse_argument(lexer: &mut Lexer) -> Result<Box<ASTTerm>, ParseError> {
+    lexer.expect_symbol("(")?;
+    let term = parse_term(lexer)?;
+    lexer.expect_symbol(")")?;
+    Ok(Box::new(term))
+}
+
+// `[T] t` after `fold` or `unfold`, the term is an atom like an application
+// argument
+fn parse_fold(lexer: &mut Lexer, unfold: bool) -> Result<ASTTerm, ParseError> {
+    lexer.expect_symbol("[")?;
+    let typ = parse_type(lexer)?;
+    lexer.expect_symbol("]")?;
+    let term = Box::new(parse_atom(lexer)?);
+    Ok(if unfold {
+        ASTTerm::TmUnfold(typ, term)
+    } else {
+        ASTTerm::TmFold(typ, term)
+    })
+}
+
+// `t1, t2}` after `{`
+fn parse_pair(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let left = parse_term(lexer)?;
+    lexer.expect_symbol(",")?;
+    let right = parse_term(lexer)?;
+    lexer.expect_symbol("}")?;
+    Ok(ASTTerm::TmPair(Box::new(left), Box::new(right)))
+}
+
+fn parse_primary(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let (token, span) = lexer.next_token()?;
+    let term = match token {
+        Token::Word("unit") => ASTTerm::TmUnit,
+        Token::Word("true") => ASTTerm::TmTrue,
+        Token::Word("false") => ASTTerm::TmFalse,
+        Token::Word("succ") => ASTTerm::TmSucc(parse_argument(lexer)?),
+        Token::Word("pred") => ASTTerm::TmPred(parse_argument(lexer)?),
+        Token::Word("iszero") => ASTTerm::TmIsZero(parse_argument(lexer)?),
+        Token::Word("fold") => parse_fold(lexer, false)?,
+        Token::Word("unfold") => parse_fold(lexer, true)?,
+        Token::Word("fix") => ASTTerm::TmFix(Box::new(parse_atom(lexer)?)),
+        Token::Word(word) if !is_reserved(word) => ASTTerm::TmVar(word.to_string()),
+        Token::Number("0") => ASTTerm::TmZero,
+        Token::Symbol("{") => parse_pair(lexer)?,
+        Token::Symbol("(") => {
+            let term = parse_term(lexer)?;
+            lexer.expect_symbol(")")?;
+            term
+        }
+        _ => return Err(lexer.error_at("a term", &token.to_string(), span).into()),
+    };
+    Ok(term)
+}
+
+// `t.1` and `t.2` project out of a pair
+fn parse_atom(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    lexer.nested(|lexer| {
+        let mut term = parse_primary(lexer)?;
+        while lexer.eat_symbol(".")?.is_some() {
+            lexer.deepen()?;
+            let index = match lexer.next_token()? {
+                (Token::Number("1"), _) => 1,
+                (Token::Number("2"), _) => 2,
+                (token, span) => {
+                    return Err(lexer
+                        .error_at("`1` or `2`", &token.to_string(), span)
+                        .into())
+                }
+            };
+            term = ASTTerm::TmProj(Box::new(term), index);
+        }
+        Ok(term)
+    })
+}
+
+// application is left associative: `f x y` is `(f x) y`, and every argument
+// nests the application one level deeper
+fn parse_application(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let mut term = parse_atom(lexer)?;
+    while starts_atom(lexer)? {
+        lexer.deepen()?;
+        let right = parse_atom(lexer)?;
+        term = ASTTerm::TmApp(Box::new(term), Box::new(right));
+    }
+    Ok(term)
+}
+
+// `x:T.t` after `lambda`
+fn parse_abstraction(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let param = variable(lexer)?.to_string();
+    lexer.expect_symbol(":")?;
+    let typ = parse_type(lexer)?;
+    lexer.expect_symbol(".")?;
+    Ok(ASTTerm::TmAbs(param, typ, Box::new(parse_term(lexer)?)))
+}
+
+fn parse_if(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let condition = parse_term(lexer)?;
+    lexer.expect_keyword("then")?;
+    let then_term = parse_term(lexer)?;
+    lexer.expect_keyword("else")?;
+    let else_term = parse_term(lexer)?;
+    Ok(ASTTerm::TmIf(
+        Box::new(condition),
+        Box::new(then_term),
+        Box::new(else_term),
+    ))
+}
+
+fn parse_let(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let name = variable(lexer)?.to_string();
+    lexer.expect_symbol("=")?;
+    let bound = parse_term(lexer)?;
+    lexer.expect_keyword("in")?;
+    let body = parse_term(lexer)?;
+    Ok(ASTTerm::TmLet(name, Box::new(bound), Box::new(body)))
+}
+
+// `x:T = t1 in t2` after `letrec`, any number of mutually recursive
+// bindings are separated by `and`
+fn parse_letrec(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let mut bindings = vec![];
+    loop {
+        let name = variable(lexer)?.to_string();
+        lexer.expect_symbol(":")?;
+        let typ = parse_type(lexer)?;
+        lexer.expect_symbol("=")?;
+        bindings.push((name, typ, parse_term(lexer)?));
+        if lexer.eat_keyword("and")?.is_none() {
+            break;
+        }
+    }
+    lexer.expect_keyword("in")?;
+    Ok(ASTTerm::TmLetRec(bindings, Box::new(parse_term(lexer)?)))
+}
+
+// the bodies of abstractions and lets extend as far to the right as possible
+pub fn parse_term(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    lexer.nested(|lexer| {
+        if lexer.eat_keyword("lambda")?.is_some() {
+            parse_abstraction(lexer)
+        } else if lexer.eat_keyword("letrec")?.is_some() {
+            parse_letrec(lexer)
+        } else if lexer.eat_keyword("let")?.is_some() {
+            parse_let(lexer)
+        } else if lexer.eat_keyword("if")?.is_some() {
+            parse_if(lexer)
+        } else {
+            parse_application(lexer)
+        }
+    })
+}
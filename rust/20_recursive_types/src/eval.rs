@@ -0,0 +1,200 @@
+use tapl_frontend::outcome::Outcome;
+
+use crate::{parser::Term, substitute::substitute_top};
+
+fn is_numeric_val(term: &Term) -> bool {
+    match term {
+        Term::TmZero => true,
+        Term::TmSucc(term) => is_numeric_val(term),
+        _ => false,
+    }
+}
+
+pub fn is_val(term: &Term) -> bool {
+    match term {
+        Term::TmUnit | Term::TmTrue | Term::TmFalse | Term::TmAbs(_, _, _) => true,
+        Term::TmPair(left, right) => is_val(left) && is_val(right),
+        // V-Fold
+        Term::TmFold(_, term) => is_val(term),
+        _ => is_numeric_val(term),
+    }
+}
+
+pub fn eval1(term: &Term) -> Option<Term> {
+    let step = |term: &Term| eval1(term).map(Box::new);
+    match term {
+        Term::TmSucc(term) => Some(Term::TmSucc(step(term)?)),
+        Term::TmPred(term) => match term.as_ref() {
+            Term::TmZero => Some(Term::TmZero),
+            Term::TmSucc(number) if is_numeric_val(number) => Some(number.as_ref().clone()),
+            _ => Some(Term::TmPred(step(term)?)),
+        },
+        Term::TmIsZero(term) => match term.as_ref() {
+            Term::TmZero => Some(Term::TmTrue),
+            Term::TmSucc(number) if is_numeric_val(number) => Some(Term::TmFalse),
+            _ => Some(Term::TmIsZero(step(term)?)),
+        },
+        Term::TmApp(left, right) => match left.as_ref() {
+            Term::TmAbs(_, _, body) if is_val(right) => Some(substitute_top(right, body)),
+            _ if is_val(left) => Some(Term::TmApp(left.clone(), step(right)?)),
+            _ => Some(Term::TmApp(step(left)?, right.clone())),
+        },
+        Term::TmIf(if_term, then_term, else_term) => match if_term.as_ref() {
+            Term::TmTrue => Some(then_term.as_ref().clone()),
+            Term::TmFalse => Some(else_term.as_ref().clone()),
+            _ => Some(Term::TmIf(
+                step(if_term)?,
+                then_term.clone(),
+                else_term.clone(),
+            )),
+        },
+        Term::TmLet(name, bound, body) => {
+            if is_val(bound) {
+                Some(substitute_top(bound, body))
+            } else {
+                Some(Term::TmLet(name.clone(), step(bound)?, body.clone()))
+            }
+        }
+        Term::TmPair(left, right) if !is_val(left) => {
+            Some(Term::TmPair(step(left)?, right.clone()))
+        }
+        Term::TmPair(left, right) if !is_val(right) => {
+            Some(Term::TmPair(left.clone(), step(right)?))
+        }
+        Term::TmProj(term, index) => match term.as_ref() {
+            Term::TmPair(left, _) if *index == 1 && is_val(term) => Some(left.as_ref().clone()),
+            Term::TmPair(_, right) if *index == 2 && is_val(term) => Some(right.as_ref().clone()),
+            _ => Some(Term::TmProj(step(term)?, *index)),
+        },
+        // E-Fld
+        Term::TmFold(typ, term) if !is_val(term) => Some(Term::TmFold(typ.clone(), step(term)?)),
+        Term::TmUnfold(typ, term) => match term.as_ref() {
+            // E-UnfldFld
+            Term::TmFold(_, value) if is_val(value) => Some(value.as_ref().clone()),
+            // E-Unfld
+            _ => Some(Term::TmUnfold(typ.clone(), step(term)?)),
+        },
+        Term::TmFix(term) => match term.as_ref() {
+            // E-FixBeta
+            Term::TmAbs(_, _, body) => Some(substitute_top(&Term::TmFix(term.clone()), body)),
+            _ => Some(Term::TmFix(step(term)?)),
+        },
+        _ => None,
+    }
+}
+
+// takes small steps until no rule applies, a normal form that is not a
+// value is stuck
+pub fn eval(term: &Term) -> Outcome<Term, Term> {
+    let mut term = term.clone();
+    while let Some(next_term) = eval1(&term) {
+        term = next_term;
+    }
+    if is_val(&term) {
+        Outcome::Value(term)
+    } else {
+        Outcome::Stuck(term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::RecursiveTypes;
+    use crate::parser::Parser;
+    use tapl_testkit::{assert_evals_to, assert_step_trace};
+
+    // Hungry = Rec A.Nat->A accepts any number of arguments (TAPL 20.1)
+    const HUNGRY: &str = "let f = fix (lambda f:Nat->Rec A.Nat->A.\
+                          lambda n:Nat.fold [Rec A.Nat->A] f) in ";
+
+    // Stream = Rec A.Unit->Nat*A yields a number and the rest of the stream
+    // each time it is forced
+    const STREAM: &str = "let u = fix (lambda f:Nat->Rec A.Unit->Nat*A.\
+                          lambda n:Nat.fold [Rec A.Unit->Nat*A] (lambda x:Unit.{n, f succ(n)})) in \
+                          let h = lambda s:Rec A.Unit->Nat*A.(unfold [Rec A.Unit->Nat*A] s unit).1 in \
+                          let t = lambda s:Rec A.Unit->Nat*A.(unfold [Rec A.Unit->Nat*A] s unit).2 in ";
+
+    #[test]
+    fn test_eval() {
+        assert_evals_to!(
+            RecursiveTypes,
+            &format!("{}unfold [Rec A.Nat->A] (unfold [Rec A.Nat->A] (f 0) 0) 0;", HUNGRY),
+            "fold [Rec A.Nat->A] (lambda n:Nat.fold [Rec A.Nat->A] (fix (lambda f:Nat->Rec A.Nat->A.\
+             lambda n:Nat.fold [Rec A.Nat->A] f)))"
+        );
+        assert_evals_to!(
+            RecursiveTypes,
+            &format!("{}h (t (t (u 0)));", STREAM),
+            "succ(succ(0))"
+        );
+        assert_evals_to!(
+            RecursiveTypes,
+            &format!("{}h (u succ(0));", STREAM),
+            "succ(0)"
+        );
+    }
+
+    // even and odd through each other, on primitive naturals
+    const PARITY: &str = "letrec e:Nat->Bool = lambda n:Nat.if iszero(n) then true else o pred(n) \
+                          and o:Nat->Bool = lambda n:Nat.if iszero(n) then false else e pred(n) in ";
+
+    #[test]
+    fn test_letrec() {
+        assert_evals_to!(
+            RecursiveTypes,
+            &format!(
+                "{}{{e succ(succ(succ(0))), o succ(succ(succ(0)))}};",
+                PARITY
+            ),
+            "{false, true}"
+        );
+        assert_evals_to!(RecursiveTypes, &format!("{}e 0;", PARITY), "true");
+        // a Church numeral counts applications of its first argument
+        assert_evals_to!(
+            RecursiveTypes,
+            &format!(
+                "{}let c = lambda s:Nat->Nat.lambda z:Nat.s (s z) in o (c (lambda n:Nat.succ(n)) 0);",
+                PARITY
+            ),
+            "false"
+        );
+        // every binding sees all the others, whichever comes first
+        assert_evals_to!(
+            RecursiveTypes,
+            "letrec a:Nat->Nat = lambda n:Nat.b n \
+             and b:Nat->Nat = lambda n:Nat.if iszero(n) then 0 else succ(c pred(n)) \
+             and c:Nat->Nat = lambda n:Nat.succ(a n) in a succ(succ(0));",
+            "succ(succ(succ(succ(0))))"
+        );
+    }
+
+    #[test]
+    fn test_eval1() {
+        // fold [T] v is a value, unfold cancels it
+        assert_step_trace!(
+            RecursiveTypes,
+            "unfold [Rec A.Nat->A] (fold [Rec A.Nat->A] (lambda x:Nat.{x, x}.2));",
+            ["lambda x:Nat.{x, x}.2"]
+        );
+        assert_step_trace!(
+            RecursiveTypes,
+            "unfold [Rec A.Bool*Unit] fold [Rec A.Bool*Unit] {iszero(0), unit};",
+            [
+                "unfold [Rec A.Bool*Unit] fold [Rec A.Bool*Unit] {true, unit}",
+                "{true, unit}"
+            ]
+        );
+    }
+    #[test]
+    fn test_eval_outcome() {
+        let term = |input: &str| Parser::new().parse(input).unwrap();
+        assert_eq!(
+            eval(&term("iszero(succ(0));")),
+            Outcome::Value(Term::TmFalse)
+        );
+        // an ill-typed term stops at a normal form that is not a value
+        let stuck = term("if 0 then true else false;");
+        assert_eq!(eval(&stuck), Outcome::Stuck(stuck));
+    }
+}
@@ -0,0 +1,326 @@
+use std::fmt::{self, Formatter};
+
+use tapl_frontend::lexer::{Lexer, SyntaxError};
+
+use crate::{
+    ast_parser::{parse_term, ASTTerm},
+    typing::Type,
+};
+
+// punctuation of terms and types
+pub const SYMBOLS: [&str; 13] = [
+    "->", "(", ")", "{", "}", "[", "]", ".", ",", ":", ";", "=", "*",
+];
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum Term {
+    TmUnit,
+    TmTrue,
+    TmFalse,
+    TmZero,
+    TmSucc(Box<Term>),
+    TmPred(Box<Term>),
+    TmIsZero(Box<Term>),
+    // var DeBrujin index
+    TmVar(usize),
+    // argument name, type and body
+    TmAbs(String, Type, Box<Term>),
+    TmApp(Box<Term>, Box<Term>),
+    TmIf(Box<Term>, Box<Term>, Box<Term>),
+    // bound name, bound term and body
+    TmLet(String, Box<Term>, Box<Term>),
+    TmPair(Box<Term>, Box<Term>),
+    // pair and component, 1 or 2
+    TmProj(Box<Term>, usize),
+    // the recursive type and a term of its unfolding (TAPL 20.2)
+    TmFold(Type, Box<Term>),
+    // the recursive type and a term of that type
+    TmUnfold(Type, Box<Term>),
+    // general recursion, fix (lambda x:T.t) unrolls to t with x bound to itself
+    TmFix(Box<Term>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    Syntax(SyntaxError),
+    UnboundVariable(String),
+    TrailingInput(String),
+}
+
+impl From<SyntaxError> for ParseError {
+    fn from(error: SyntaxError) -> Self {
+        ParseError::Syntax(error)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Syntax(error) => write!(f, "{}", error),
+            ParseError::UnboundVariable(name) => write!(f, "unbound variable {}", name),
+            ParseError::TrailingInput(input) => write!(f, "unexpected input `{}`", input),
+        }
+    }
+}
+
+pub type ParseResult = Result<Term, ParseError>;
+
+// Names of the enclosing binders, the innermost is at de Bruijn index 0. The
+// binder of a mutual `letrec` names a tuple of its functions, and each of
+// them stands for its component.
+#[derive(Default)]
+pub struct Parser {
+    names: Vec<Vec<String>>,
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(&mut self, input: &str) -> ParseResult {
+        let mut lexer = Lexer::new(input, &SYMBOLS);
+        let term = parse_term(&mut lexer)?;
+        lexer.expect_symbol(";")?;
+        if !lexer.rest().trim().is_empty() {
+            return Err(ParseError::TrailingInput(lexer.rest().trim().to_string()));
+        }
+
+        self.from_ast_term(&term)
+    }
+
+    fn with_binding<T>(&mut self, name: &str, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.with_bindings(vec![name.to_string()], f)
+    }
+
+    fn with_bindings<T>(&mut self, names: Vec<String>, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.names.push(names);
+        let result = f(self);
+        self.names.pop();
+        result
+    }
+
+    fn lookup(&self, name: &str) -> Option<Term> {
+        self.names
+            .iter()
+            .rev()
+            .enumerate()
+            .find_map(|(index, names)| {
+                let position = names.iter().position(|n| n == name)?;
+                Some(component(Term::TmVar(index), position, names.len()))
+            })
+    }
+
+    // letrec f:T = t1 and g:U = t2 in t
+    //   = let f,g = fix (lambda f,g:T*U.{t1, t2}) in t
+    // where f stands for the component f,g.1 and g for f,g.2, so the
+    // functions reach each other through the fixed point (TAPL 11.11). A
+    // single binding is the plain `let f = fix (lambda f:T.t1) in t`.
+    fn letrec_term(&mut self, bindings: &[(String, Type, ASTTerm)], body: &ASTTerm) -> ParseResult {
+        let names = bindings
+            .iter()
+            .map(|(name, _, _)| name.clone())
+            .collect::<Vec<_>>();
+        let name = names.join(",");
+        let (_, last_type, _) = bindings.last().expect("at least one binding");
+        let typ = bindings
+            .iter()
+            .rev()
+            .skip(1)
+            .fold(last_type.clone(), |typ, (_, t, _)| {
+                Type::Product(Box::new(t.clone()), Box::new(typ))
+            });
+        let functions = self.with_bindings(names.clone(), |parser| {
+            bindings
+                .iter()
+                .map(|(_, _, term)| parser.from_ast_term(term))
+                .collect::<Result<Vec<_>, _>>()
+        })?;
+        let mut functions = functions.into_iter().rev();
+        let last = functions.next().unwrap();
+        let tuple = functions.fold(last, |tuple, function| {
+            Term::TmPair(Box::new(function), Box::new(tuple))
+        });
+        let bound = Term::TmFix(Box::new(Term::TmAbs(name.clone(), typ, Box::new(tuple))));
+        let body = self.with_bindings(names, |parser| parser.from_ast_term(body))?;
+        Ok(Term::TmLet(name, Box::new(bound), Box::new(body)))
+    }
+
+    fn from_ast_term(self: &mut Parser, ast_term: &ASTTerm) -> ParseResult {
+        let term = match ast_term {
+            ASTTerm::TmUnit => Term::TmUnit,
+            ASTTerm::TmTrue => Term::TmTrue,
+            ASTTerm::TmFalse => Term::TmFalse,
+            ASTTerm::TmZero => Term::TmZero,
+            ASTTerm::TmSucc(term) => Term::TmSucc(Box::new(self.from_ast_term(term)?)),
+            ASTTerm::TmPred(term) => Term::TmPred(Box::new(self.from_ast_term(term)?)),
+            ASTTerm::TmIsZero(term) => Term::TmIsZero(Box::new(self.from_ast_term(term)?)),
+            ASTTerm::TmVar(name) => match self.lookup(name) {
+                Some(term) => term,
+                None => return Err(ParseError::UnboundVariable(name.clone())),
+            },
+            ASTTerm::TmAbs(name, typ, body) => {
+                let body = self.with_binding(name, |parser| parser.from_ast_term(body))?;
+                Term::TmAbs(name.clone(), typ.clone(), Box::new(body))
+            }
+            ASTTerm::TmApp(left, right) => Term::TmApp(
+                Box::new(self.from_ast_term(left)?),
+                Box::new(self.from_ast_term(right)?),
+            ),
+            ASTTerm::TmIf(if_term, then_term, else_term) => Term::TmIf(
+                Box::new(self.from_ast_term(if_term)?),
+                Box::new(self.from_ast_term(then_term)?),
+                Box::new(self.from_ast_term(else_term)?),
+            ),
+            ASTTerm::TmLet(name, bound, body) => {
+                let bound = self.from_ast_term(bound)?;
+                let body = self.with_binding(name, |parser| parser.from_ast_term(body))?;
+                Term::TmLet(name.clone(), Box::new(bound), Box::new(body))
+            }
+            ASTTerm::TmPair(left, right) => Term::TmPair(
+                Box::new(self.from_ast_term(left)?),
+                Box::new(self.from_ast_term(right)?),
+            ),
+            ASTTerm::TmProj(term, index) => {
+                Term::TmProj(Box::new(self.from_ast_term(term)?), *index)
+            }
+            ASTTerm::TmFold(typ, term) => {
+                Term::TmFold(typ.clone(), Box::new(self.from_ast_term(term)?))
+            }
+            ASTTerm::TmUnfold(typ, term) => {
+                Term::TmUnfold(typ.clone(), Box::new(self.from_ast_term(term)?))
+            }
+            ASTTerm::TmFix(term) => Term::TmFix(Box::new(self.from_ast_term(term)?)),
+            ASTTerm::TmLetRec(bindings, body) => return self.letrec_term(bindings, body),
+        };
+
+        Ok(term)
+    }
+}
+
+// the component `position` of a right nested tuple of `len` terms
+fn component(tuple: Term, position: usize, len: usize) -> Term {
+    match (position, len) {
+        (_, 1) => tuple,
+        (0, _) => Term::TmProj(Box::new(tuple), 1),
+        _ => component(Term::TmProj(Box::new(tuple), 2), position - 1, len - 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let hungry = Type::Rec(
+            "A".to_string(),
+            Box::new(Type::Arrow(
+                Box::new(Type::Number),
+                Box::new(Type::Var("A".to_string())),
+            )),
+        );
+        assert_eq!(
+            Parser::new().parse("lambda h:Rec A.Nat->A.unfold [Rec A.Nat->A] h 0;"),
+            Ok(Term::TmAbs(
+                "h".to_string(),
+                hungry.clone(),
+                Box::new(Term::TmApp(
+                    Box::new(Term::TmUnfold(hungry, Box::new(Term::TmVar(0)))),
+                    Box::new(Term::TmZero)
+                ))
+            ))
+        );
+        assert_eq!(
+            Parser::new().parse("{unit, fix (lambda x:Nat.x)}.2;"),
+            Ok(Term::TmProj(
+                Box::new(Term::TmPair(
+                    Box::new(Term::TmUnit),
+                    Box::new(Term::TmFix(Box::new(Term::TmAbs(
+                        "x".to_string(),
+                        Type::Number,
+                        Box::new(Term::TmVar(0))
+                    ))))
+                )),
+                2
+            ))
+        );
+    }
+
+    #[test]
+    fn test_letrec() {
+        assert_eq!(
+            Parser::new().parse("letrec f:Nat->Nat = lambda n:Nat.f n in f 0;"),
+            Parser::new().parse("let f = fix (lambda f:Nat->Nat.lambda n:Nat.f n) in f 0;")
+        );
+        // the functions are the components of one fixed point
+        let term = Parser::new()
+            .parse("letrec f:Nat->Nat = lambda n:Nat.g n and g:Nat->Nat = lambda n:Nat.f n in g;");
+        let component = |index| Box::new(Term::TmProj(Box::new(Term::TmVar(1)), index));
+        let function = |index| {
+            Term::TmAbs(
+                "n".to_string(),
+                Type::Number,
+                Box::new(Term::TmApp(component(index), Box::new(Term::TmVar(0)))),
+            )
+        };
+        let functions = Type::Product(
+            Box::new(Type::Arrow(Box::new(Type::Number), Box::new(Type::Number))),
+            Box::new(Type::Arrow(Box::new(Type::Number), Box::new(Type::Number))),
+        );
+        assert_eq!(
+            term,
+            Ok(Term::TmLet(
+                "f,g".to_string(),
+                Box::new(Term::TmFix(Box::new(Term::TmAbs(
+                    "f,g".to_string(),
+                    functions,
+                    Box::new(Term::TmPair(Box::new(function(2)), Box::new(function(1))))
+                )))),
+                Box::new(Term::TmProj(Box::new(Term::TmVar(0)), 2))
+            ))
+        );
+        assert_eq!(
+            Parser::new().parse("letrec f:Nat->Nat = lambda n:Nat.g n in f;"),
+            Err(ParseError::UnboundVariable("g".to_string()))
+        );
+    }
+    #[test]
+    fn test_words() {
+        let parse = |input: &str| {
+            Parser::new()
+                .parse(input)
+                .map_err(|error| error.to_string())
+        };
+        // names are whole words of any length, keywords are not split off them
+        assert_eq!(
+            parse("lambda folded:Nat.folded;"),
+            Ok(Term::TmAbs(
+                "folded".to_string(),
+                Type::Number,
+                Box::new(Term::TmVar(0))
+            ))
+        );
+        assert_eq!(
+            parse("lambda fix:Nat.fix;"),
+            Err("1:8: expected a variable, found `fix`".to_string())
+        );
+        assert_eq!(
+            parse("{0, 0}.3;"),
+            Err("1:8: expected `1` or `2`, found `3`".to_string())
+        );
+        assert_eq!(
+            parse("letrec f:Nat = 0 in;"),
+            Err("1:20: expected a term, found `;`".to_string())
+        );
+        // nesting is bounded instead of overflowing the stack
+        let deep = format!("{}0;", "fix ".repeat(300));
+        assert!(parse(&deep)
+            .unwrap_err()
+            .contains("at most 256 nested terms"));
+        let projections = format!("{{0, 0}}{};", ".1".repeat(300));
+        assert!(parse(&projections)
+            .unwrap_err()
+            .contains("at most 256 nested terms"));
+    }
+}
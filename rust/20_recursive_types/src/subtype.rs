@@ -0,0 +1,149 @@
+use std::collections::HashSet;
+
+use crate::typing::{unfold_type, Type};
+
+// Pairs S <: T assumed or already checked. A pair met again on a path is
+// accepted, which makes the check compute the greatest fixed point of the
+// subtyping rules, and pairs proved in one branch are kept for the others.
+type Assumptions = HashSet<(Type, Type)>;
+
+// subtype-ac of TAPL 21.12, growing `assumptions` when S <: T. A failure
+// anywhere fails the whole check, so the pairs it added are in `added` to be
+// taken out again.
+fn subtype_ac(
+    assumptions: &mut Assumptions,
+    added: &mut Vec<(Type, Type)>,
+    s: &Type,
+    t: &Type,
+) -> bool {
+    let pair = (s.clone(), t.clone());
+    if assumptions.contains(&pair) {
+        return true;
+    }
+    assumptions.insert(pair.clone());
+    added.push(pair);
+    match (s, t) {
+        (_, Type::Top) => true,
+        (Type::Unit, Type::Unit)
+        | (Type::Boolean, Type::Boolean)
+        | (Type::Number, Type::Number) => true,
+        (Type::Product(s1, s2), Type::Product(t1, t2)) => {
+            subtype_ac(assumptions, added, s1, t1) && subtype_ac(assumptions, added, s2, t2)
+        }
+        // contravariant in the parameter
+        (Type::Arrow(s1, s2), Type::Arrow(t1, t2)) => {
+            subtype_ac(assumptions, added, t1, s1) && subtype_ac(assumptions, added, s2, t2)
+        }
+        // equi-recursive: a recursive type is interchangeable with its unfolding
+        (_, Type::Rec(_, _)) => match unfold_type(t) {
+            Some(t) => subtype_ac(assumptions, added, s, &t),
+            None => false,
+        },
+        (Type::Rec(_, _), _) => match unfold_type(s) {
+            Some(s) => subtype_ac(assumptions, added, &s, t),
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+// Subtype judgments shared by the checks of one session. The assumptions of
+// a check that succeeded are all true, they support each other, so they
+// start the next check as pairs that are already proved. A pair that failed
+// with only true assumptions is false as well.
+#[derive(Clone, Debug, Default)]
+pub struct SubtypeCache {
+    proved: Assumptions,
+    refuted: HashSet<(Type, Type)>,
+}
+
+impl SubtypeCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subtype(&mut self, s: &Type, t: &Type) -> bool {
+        let pair = (s.clone(), t.clone());
+        if self.refuted.contains(&pair) {
+            return false;
+        }
+        let mut added = vec![];
+        if subtype_ac(&mut self.proved, &mut added, s, t) {
+            return true;
+        }
+        for pair in &added {
+            self.proved.remove(pair);
+        }
+        self.refuted.insert(pair);
+        false
+    }
+
+    // the number of judgments decided so far
+    pub fn len(&self) -> usize {
+        self.proved.len() + self.refuted.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// S <: T for closed types, treating `Rec X.T` as its infinite unfolding
+pub fn subtype(s: &Type, t: &Type) -> bool {
+    SubtypeCache::new().subtype(s, t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tapl_frontend::lexer::Lexer;
+
+    use crate::{parser::SYMBOLS, type_parser::parse_type};
+
+    fn typ(input: &str) -> Type {
+        let mut lexer = Lexer::new(input, &SYMBOLS);
+        let typ = parse_type(&mut lexer).unwrap();
+        assert_eq!(lexer.rest(), "");
+        typ
+    }
+
+    fn subtype_str(s: &str, t: &str) -> bool {
+        subtype(&typ(s), &typ(t))
+    }
+
+    #[test]
+    fn test_subtype() {
+        // different finite descriptions of the same infinite tree
+        assert!(subtype_str("Rec A.Nat->A", "Rec B.Nat->Nat->B"));
+        assert!(subtype_str("Rec B.Nat->Nat->B", "Rec A.Nat->A"));
+        assert!(subtype_str("Rec A.Nat*A", "Nat*(Rec A.Nat*A)"));
+        assert!(subtype_str("Nat*(Rec A.Nat*A)", "Rec A.Nat*A"));
+        assert!(subtype_str("Rec A.Nat*A", "Rec A.Top*A"));
+        assert!(!subtype_str("Rec A.Top*A", "Rec A.Nat*A"));
+        assert!(!subtype_str("Rec A.Nat*A", "Rec A.Bool*A"));
+        // the parameter side flips at every unfolding
+        assert!(subtype_str("Rec A.Top->A", "Rec A.Nat->A"));
+        assert!(!subtype_str("Rec A.A->Nat", "Rec A.A->Top"));
+        assert!(subtype_str("Rec A.A->A", "Rec A.A->A"));
+        assert!(subtype_str("Rec A.Rec B.A->B", "Top"));
+    }
+
+    #[test]
+    fn test_subtype_cache() {
+        let stream = typ("Rec A.Nat->A");
+        let pairs = typ("Rec B.Nat->Nat->B");
+        let mut cache = SubtypeCache::new();
+        assert!(cache.subtype(&stream, &pairs));
+        let proved = cache.len();
+        assert!(proved > 1);
+        assert!(cache.subtype(&stream, &pairs));
+        assert_eq!(cache.len(), proved);
+
+        // a failed check leaves only its own pair behind
+        let len = cache.len();
+        assert!(!cache.subtype(&typ("Rec A.Top*A"), &typ("Rec A.Nat*A")));
+        assert_eq!(cache.len(), len + 1);
+        assert!(!cache.subtype(&typ("Rec A.Top*A"), &typ("Rec A.Nat*A")));
+        assert!(cache.subtype(&typ("Rec A.Nat*A"), &typ("Rec A.Top*A")));
+    }
+}
@@ -0,0 +1,132 @@
+use tapl_frontend::lexer::{Lexer, SyntaxError, Token};
+
+use crate::typing::Type;
+
+pub const TYPE_KEYWORDS: [&str; 5] = ["Top", "Unit", "Bool", "Nat", "Rec"];
+
+fn is_type_var(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_uppercase()) && !TYPE_KEYWORDS.contains(&word)
+}
+
+// type variables are capitalized words
+fn parse_type_var(lexer: &mut Lexer) -> Result<String, SyntaxError> {
+    match lexer.peek()? {
+        (Token::Word(word), _) if is_type_var(word) => {
+            lexer.next_token()?;
+            Ok(word.to_string())
+        }
+        _ => Err(lexer.unexpected("a type variable")),
+    }
+}
+
+fn parse_atom_type(lexer: &mut Lexer) -> Result<Type, SyntaxError> {
+    lexer.nested(|lexer| {
+        let (token, span) = lexer.next_token()?;
+        match token {
+            Token::Word("Top") => Ok(Type::Top),
+            Token::Word("Unit") => Ok(Type::Unit),
+            Token::Word("Bool") => Ok(Type::Boolean),
+            Token::Word("Nat") => Ok(Type::Number),
+            Token::Word(word) if is_type_var(word) => Ok(Type::Var(word.to_string())),
+            Token::Symbol("(") => {
+                let typ = parse_type(lexer)?;
+                lexer.expect_symbol(")")?;
+                Ok(typ)
+            }
+            _ => Err(lexer.error_at("a type", &token.to_string(), span)),
+        }
+    })
+}
+
+// `*` binds tighter than `->` and is right associative
+fn parse_product_type(lexer: &mut Lexer) -> Result<Type, SyntaxError> {
+    lexer.nested(|lexer| {
+        let typ = parse_atom_type(lexer)?;
+        match lexer.eat_symbol("*")? {
+            Some(_) => Ok(Type::Product(
+                Box::new(typ),
+                Box::new(parse_product_type(lexer)?),
+            )),
+            None => Ok(typ),
+        }
+    })
+}
+
+// `Rec X.T` is μX.T, its body extends as far right as possible
+pub fn parse_type(lexer: &mut Lexer) -> Result<Type, SyntaxError> {
+    lexer.nested(|lexer| {
+        if lexer.eat_keyword("Rec")?.is_some() {
+            let name = parse_type_var(lexer)?;
+            lexer.expect_symbol(".")?;
+            return Ok(Type::Rec(name, Box::new(parse_type(lexer)?)));
+        }
+        let typ = parse_product_type(lexer)?;
+        match lexer.eat_symbol("->")? {
+            Some(_) => Ok(Type::Arrow(Box::new(typ), Box::new(parse_type(lexer)?))),
+            None => Ok(typ),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SYMBOLS;
+
+    fn parse(input: &str) -> Result<(&str, Type), String> {
+        let mut lexer = Lexer::new(input, &SYMBOLS);
+        parse_type(&mut lexer)
+            .map(|typ| (lexer.rest(), typ))
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn test_type() {
+        let var = |name: &str| Box::new(Type::Var(name.to_string()));
+        assert_eq!(
+            parse("Rec A.Nat->A"),
+            Ok((
+                "",
+                Type::Rec(
+                    "A".to_string(),
+                    Box::new(Type::Arrow(Box::new(Type::Number), var("A")))
+                )
+            ))
+        );
+        assert_eq!(
+            parse("(Rec A.Unit->Nat*A)->Nat"),
+            Ok((
+                "",
+                Type::Arrow(
+                    Box::new(Type::Rec(
+                        "A".to_string(),
+                        Box::new(Type::Arrow(
+                            Box::new(Type::Unit),
+                            Box::new(Type::Product(Box::new(Type::Number), var("A")))
+                        ))
+                    )),
+                    Box::new(Type::Number)
+                )
+            ))
+        );
+        // type names are whole words
+        assert_eq!(
+            parse("Rec List.Unit*List"),
+            Ok((
+                "",
+                Type::Rec(
+                    "List".to_string(),
+                    Box::new(Type::Product(Box::new(Type::Unit), var("List")))
+                )
+            ))
+        );
+        assert_eq!(
+            parse("Rec Nat.Nat").unwrap_err(),
+            "1:5: expected a type variable, found `Nat`"
+        );
+        assert_eq!(
+            parse("rec A.A").unwrap_err(),
+            "1:1: expected a type, found `rec`"
+        );
+    }
+}
@@ -0,0 +1,340 @@
+use std::fmt::{self, Formatter};
+
+use crate::{context::Context, parser::Term};
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum TypeError {
+    ParameterTypeMismatch,
+    ArrowTypeExpected,
+    NumberExpected,
+    ProductExpected,
+    IfConditionNotBoolean,
+    IfBranchesMismatch,
+    UnboundVariable(usize),
+    UnboundTypeVariable(String),
+    // the annotation of `fold` or `unfold` is not a `Rec X.T`
+    RecursiveTypeExpected(Type),
+    // the term under `fold [U]` does not have the unfolding of U
+    FoldTypeMismatch,
+    // the term under `unfold [U]` does not have type U
+    UnfoldTypeMismatch,
+    FixTypeMismatch,
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeError::ParameterTypeMismatch => write!(f, "parameter type mismatch"),
+            TypeError::ArrowTypeExpected => write!(f, "arrow type expected"),
+            TypeError::NumberExpected => write!(f, "argument is not a number"),
+            TypeError::ProductExpected => write!(f, "product type expected"),
+            TypeError::IfConditionNotBoolean => write!(f, "guard of conditional not a boolean"),
+            TypeError::IfBranchesMismatch => {
+                write!(f, "arms of conditional have different types")
+            }
+            TypeError::UnboundVariable(index) => write!(f, "unbound variable index {}", index),
+            TypeError::UnboundTypeVariable(name) => write!(f, "unbound type variable {}", name),
+            TypeError::RecursiveTypeExpected(typ) => {
+                write!(f, "recursive type expected, found {}", typ)
+            }
+            TypeError::FoldTypeMismatch => {
+                write!(f, "folded term does not have the unfolded recursive type")
+            }
+            TypeError::UnfoldTypeMismatch => {
+                write!(f, "unfolded term does not have the recursive type")
+            }
+            TypeError::FixTypeMismatch => write!(f, "fix expects a function of type T->T"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd)]
+pub enum Type {
+    // only related to other types by the equi-recursive `subtype`
+    Top,
+    Unit,
+    Boolean,
+    Number,
+    Arrow(Box<Type>, Box<Type>),
+    Product(Box<Type>, Box<Type>),
+    // type variable bound by an enclosing `Rec`
+    Var(String),
+    // μX.T, only equal to its unfolding through explicit fold/unfold (TAPL 20.2)
+    Rec(String, Box<Type>),
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Top => write!(f, "Top"),
+            Type::Unit => write!(f, "Unit"),
+            Type::Boolean => write!(f, "Bool"),
+            Type::Number => write!(f, "Nat"),
+            Type::Arrow(param, ret) => match param.as_ref() {
+                Type::Arrow(_, _) | Type::Rec(_, _) => write!(f, "({})->{}", param, ret),
+                _ => write!(f, "{}->{}", param, ret),
+            },
+            Type::Product(left, right) => {
+                match left.as_ref() {
+                    Type::Top | Type::Unit | Type::Boolean | Type::Number | Type::Var(_) => {
+                        write!(f, "{}", left)?
+                    }
+                    _ => write!(f, "({})", left)?,
+                }
+                match right.as_ref() {
+                    Type::Arrow(_, _) | Type::Rec(_, _) => write!(f, "*({})", right),
+                    _ => write!(f, "*{}", right),
+                }
+            }
+            Type::Var(name) => write!(f, "{}", name),
+            Type::Rec(name, body) => write!(f, "Rec {}.{}", name, body),
+        }
+    }
+}
+
+// [name ↦ s]typ, `s` is closed in well-formed programs so nothing is captured
+pub fn substitute_type(typ: &Type, name: &str, s: &Type) -> Type {
+    let subst = |typ: &Type| Box::new(substitute_type(typ, name, s));
+    match typ {
+        Type::Top | Type::Unit | Type::Boolean | Type::Number => typ.clone(),
+        Type::Arrow(param, ret) => Type::Arrow(subst(param), subst(ret)),
+        Type::Product(left, right) => Type::Product(subst(left), subst(right)),
+        Type::Var(var) if var == name => s.clone(),
+        Type::Var(_) => typ.clone(),
+        // the inner binder shadows `name`
+        Type::Rec(var, _) if var == name => typ.clone(),
+        Type::Rec(var, body) => Type::Rec(var.clone(), subst(body)),
+    }
+}
+
+// μX.T unfolds to [X ↦ μX.T]T
+pub fn unfold_type(typ: &Type) -> Option<Type> {
+    match typ {
+        Type::Rec(name, body) => Some(substitute_type(body, name, typ)),
+        _ => None,
+    }
+}
+
+// equality up to renaming of bound type variables, `Rec A.Nat->A` is
+// `Rec B.Nat->B`
+pub fn type_eq(s: &Type, t: &Type) -> bool {
+    fn eq(s: &Type, t: &Type, bound: &mut Vec<(String, String)>) -> bool {
+        match (s, t) {
+            (Type::Top, Type::Top)
+            | (Type::Unit, Type::Unit)
+            | (Type::Boolean, Type::Boolean)
+            | (Type::Number, Type::Number) => true,
+            (Type::Arrow(s1, s2), Type::Arrow(t1, t2))
+            | (Type::Product(s1, s2), Type::Product(t1, t2)) => {
+                eq(s1, t1, bound) && eq(s2, t2, bound)
+            }
+            (Type::Var(x), Type::Var(y)) => {
+                match bound.iter().rev().find(|(a, b)| a == x || b == y) {
+                    Some((a, b)) => a == x && b == y,
+                    None => x == y,
+                }
+            }
+            (Type::Rec(x, s1), Type::Rec(y, t1)) => {
+                bound.push((x.clone(), y.clone()));
+                let result = eq(s1, t1, bound);
+                bound.pop();
+                result
+            }
+            _ => false,
+        }
+    }
+    eq(s, t, &mut vec![])
+}
+
+// annotations may only mention type variables of enclosing `Rec`s
+fn check_closed(typ: &Type, bound: &mut Vec<String>) -> Result<(), TypeError> {
+    match typ {
+        Type::Top | Type::Unit | Type::Boolean | Type::Number => Ok(()),
+        Type::Arrow(left, right) | Type::Product(left, right) => {
+            check_closed(left, bound)?;
+            check_closed(right, bound)
+        }
+        Type::Var(name) if bound.contains(name) => Ok(()),
+        Type::Var(name) => Err(TypeError::UnboundTypeVariable(name.clone())),
+        Type::Rec(name, body) => {
+            bound.push(name.clone());
+            let result = check_closed(body, bound);
+            bound.pop();
+            result
+        }
+    }
+}
+
+fn expect_number(typ: Type) -> Result<(), TypeError> {
+    match typ {
+        Type::Number => Ok(()),
+        _ => Err(TypeError::NumberExpected),
+    }
+}
+
+pub fn type_of(ctx: &mut Context, term: &Term) -> Result<Type, TypeError> {
+    match term {
+        Term::TmUnit => Ok(Type::Unit),
+        Term::TmTrue | Term::TmFalse => Ok(Type::Boolean),
+        Term::TmZero => Ok(Type::Number),
+        Term::TmSucc(term) | Term::TmPred(term) => {
+            expect_number(type_of(ctx, term)?)?;
+            Ok(Type::Number)
+        }
+        Term::TmIsZero(term) => {
+            expect_number(type_of(ctx, term)?)?;
+            Ok(Type::Boolean)
+        }
+        Term::TmVar(index) => ctx
+            .get_type(*index)
+            .ok_or(TypeError::UnboundVariable(*index)),
+        Term::TmAbs(name, typ, body) => {
+            check_closed(typ, &mut vec![])?;
+            ctx.push(name.clone(), typ.clone());
+            let body_type = type_of(ctx, body);
+            ctx.pop();
+            Ok(Type::Arrow(Box::new(typ.clone()), Box::new(body_type?)))
+        }
+        Term::TmApp(left, right) => {
+            let left_type = type_of(ctx, left)?;
+            let right_type = type_of(ctx, right)?;
+            match left_type {
+                Type::Arrow(param_type, return_type) if type_eq(&right_type, &param_type) => {
+                    Ok(*return_type)
+                }
+                Type::Arrow(_, _) => Err(TypeError::ParameterTypeMismatch),
+                _ => Err(TypeError::ArrowTypeExpected),
+            }
+        }
+        Term::TmIf(if_term, then_term, else_term) => {
+            if type_of(ctx, if_term)? != Type::Boolean {
+                return Err(TypeError::IfConditionNotBoolean);
+            }
+            let then_type = type_of(ctx, then_term)?;
+            if type_eq(&then_type, &type_of(ctx, else_term)?) {
+                Ok(then_type)
+            } else {
+                Err(TypeError::IfBranchesMismatch)
+            }
+        }
+        Term::TmLet(name, bound, body) => {
+            let bound_type = type_of(ctx, bound)?;
+            ctx.push(name.clone(), bound_type);
+            let body_type = type_of(ctx, body);
+            ctx.pop();
+            body_type
+        }
+        Term::TmPair(left, right) => Ok(Type::Product(
+            Box::new(type_of(ctx, left)?),
+            Box::new(type_of(ctx, right)?),
+        )),
+        Term::TmProj(term, index) => match type_of(ctx, term)? {
+            Type::Product(left, _) if *index == 1 => Ok(*left),
+            Type::Product(_, right) if *index == 2 => Ok(*right),
+            _ => Err(TypeError::ProductExpected),
+        },
+        // T-Fld
+        Term::TmFold(typ, term) => {
+            check_closed(typ, &mut vec![])?;
+            let unfolded =
+                unfold_type(typ).ok_or_else(|| TypeError::RecursiveTypeExpected(typ.clone()))?;
+            if type_eq(&type_of(ctx, term)?, &unfolded) {
+                Ok(typ.clone())
+            } else {
+                Err(TypeError::FoldTypeMismatch)
+            }
+        }
+        // T-Unfld
+        Term::TmUnfold(typ, term) => {
+            check_closed(typ, &mut vec![])?;
+            let unfolded =
+                unfold_type(typ).ok_or_else(|| TypeError::RecursiveTypeExpected(typ.clone()))?;
+            if type_eq(&type_of(ctx, term)?, typ) {
+                Ok(unfolded)
+            } else {
+                Err(TypeError::UnfoldTypeMismatch)
+            }
+        }
+        Term::TmFix(term) => match type_of(ctx, term)? {
+            Type::Arrow(param_type, return_type) if type_eq(&param_type, &return_type) => {
+                Ok(*param_type)
+            }
+            _ => Err(TypeError::FixTypeMismatch),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tapl_frontend::lexer::Lexer;
+
+    use crate::{
+        language::RecursiveTypes,
+        parser::{Parser, SYMBOLS},
+        type_parser::parse_type,
+    };
+    use tapl_testkit::assert_type_error;
+
+    fn typ(input: &str) -> Type {
+        parse_type(&mut Lexer::new(input, &SYMBOLS)).unwrap()
+    }
+
+    fn type_str(input: &str) -> Result<Type, TypeError> {
+        let term = Parser::new().parse(input).unwrap();
+        type_of(&mut Context::default(), &term)
+    }
+
+    #[test]
+    fn test_unfold_type() {
+        assert_eq!(
+            unfold_type(&typ("Rec A.Nat->A")),
+            Some(typ("Nat->(Rec A.Nat->A)"))
+        );
+        // the inner binder shadows the outer one
+        assert_eq!(
+            unfold_type(&typ("Rec A.A*(Rec A.A)")),
+            Some(typ("(Rec A.A*(Rec A.A))*(Rec A.A)"))
+        );
+        assert!(type_eq(&typ("Rec A.Nat->A"), &typ("Rec B.Nat->B")));
+        assert!(!type_eq(&typ("Rec A.Rec B.A"), &typ("Rec A.Rec B.B")));
+        // iso-recursive: a recursive type differs from its unfolding
+        assert!(!type_eq(&typ("Rec A.Nat->A"), &typ("Nat->(Rec A.Nat->A)")));
+    }
+
+    #[test]
+    fn test_type_of() {
+        let hungry = typ("Rec A.Nat->A");
+        assert_eq!(
+            type_str("fold [Rec A.Nat->A] (lambda n:Nat.fold [Rec A.Nat->A] unit);"),
+            Err(TypeError::FoldTypeMismatch)
+        );
+        assert_eq!(
+            type_str("fix (lambda f:Nat->Rec A.Nat->A.lambda n:Nat.fold [Rec A.Nat->A] f);"),
+            Ok(Type::Arrow(
+                Box::new(Type::Number),
+                Box::new(hungry.clone())
+            ))
+        );
+        // the annotation only has to agree up to renaming
+        assert_eq!(
+            type_str("lambda h:Rec A.Nat->A.unfold [Rec B.Nat->B] h 0;"),
+            Ok(Type::Arrow(Box::new(hungry), Box::new(typ("Rec B.Nat->B"))))
+        );
+        // omega is well typed once self application goes through a recursive type
+        assert_eq!(
+            type_str(
+                "(lambda x:Rec A.A->Nat.(unfold [Rec A.A->Nat] x) x) \
+                 (fold [Rec A.A->Nat] (lambda x:Rec A.A->Nat.(unfold [Rec A.A->Nat] x) x));"
+            ),
+            Ok(Type::Number)
+        );
+        assert_type_error!(
+            RecursiveTypes,
+            "lambda h:Rec A.Nat->A.h 0;",
+            "arrow type expected"
+        );
+        assert_type_error!(RecursiveTypes, "unfold [Nat] 0;", "recursive type expected");
+        assert_type_error!(RecursiveTypes, "lambda x:A.x;", "unbound type variable A");
+    }
+}
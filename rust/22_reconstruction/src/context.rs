@@ -0,0 +1,30 @@
+use crate::typing::Scheme;
+
+// Type schemes assigned to the enclosing binders, the innermost is at de
+// Bruijn index 0
+#[derive(Clone, Debug, Default)]
+pub struct Context {
+    bindings: Vec<(String, Scheme)>,
+}
+
+impl Context {
+    pub fn push(&mut self, name: String, scheme: Scheme) {
+        self.bindings.push((name, scheme));
+    }
+
+    pub fn pop(&mut self) {
+        self.bindings.pop();
+    }
+
+    pub fn get_scheme(&self, index: usize) -> Option<&Scheme> {
+        self.bindings
+            .iter()
+            .rev()
+            .nth(index)
+            .map(|(_, scheme)| scheme)
+    }
+
+    pub fn schemes(&self) -> impl Iterator<Item = &Scheme> {
+        self.bindings.iter().map(|(_, scheme)| scheme)
+    }
+}
@@ -0,0 +1,106 @@
+use tapl_frontend::outcome::Outcome;
+
+use crate::{parser::Term, substitute::substitute_top};
+
+fn is_numeric_val(term: &Term) -> bool {
+    match term {
+        Term::TmZero => true,
+        Term::TmSucc(term) => is_numeric_val(term),
+        _ => false,
+    }
+}
+
+pub fn is_val(term: &Term) -> bool {
+    match term {
+        Term::TmTrue | Term::TmFalse | Term::TmAbs(_, _) => true,
+        _ => is_numeric_val(term),
+    }
+}
+
+// types play no part in evaluation, so this is the untyped call-by-value
+// semantics
+pub fn eval1(term: &Term) -> Option<Term> {
+    let step = |term: &Term| eval1(term).map(Box::new);
+    match term {
+        Term::TmSucc(term) => Some(Term::TmSucc(step(term)?)),
+        Term::TmPred(term) => match term.as_ref() {
+            Term::TmZero => Some(Term::TmZero),
+            Term::TmSucc(number) if is_numeric_val(number) => Some(number.as_ref().clone()),
+            _ => Some(Term::TmPred(step(term)?)),
+        },
+        Term::TmIsZero(term) => match term.as_ref() {
+            Term::TmZero => Some(Term::TmTrue),
+            Term::TmSucc(number) if is_numeric_val(number) => Some(Term::TmFalse),
+            _ => Some(Term::TmIsZero(step(term)?)),
+        },
+        Term::TmApp(left, right) => match left.as_ref() {
+            Term::TmAbs(_, body) if is_val(right) => Some(substitute_top(right, body)),
+            _ if is_val(left) => Some(Term::TmApp(left.clone(), step(right)?)),
+            _ => Some(Term::TmApp(step(left)?, right.clone())),
+        },
+        Term::TmIf(if_term, then_term, else_term) => match if_term.as_ref() {
+            Term::TmTrue => Some(then_term.as_ref().clone()),
+            Term::TmFalse => Some(else_term.as_ref().clone()),
+            _ => Some(Term::TmIf(
+                step(if_term)?,
+                then_term.clone(),
+                else_term.clone(),
+            )),
+        },
+        Term::TmLet(name, bound, body) => {
+            if is_val(bound) {
+                Some(substitute_top(bound, body))
+            } else {
+                Some(Term::TmLet(name.clone(), step(bound)?, body.clone()))
+            }
+        }
+        _ => None,
+    }
+}
+
+// takes small steps until no rule applies, a normal form that is not a
+// value is stuck
+pub fn eval(term: &Term) -> Outcome<Term, Term> {
+    let mut term = term.clone();
+    while let Some(next_term) = eval1(&term) {
+        term = next_term;
+    }
+    if is_val(&term) {
+        Outcome::Value(term)
+    } else {
+        Outcome::Stuck(term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::Reconstruction;
+    use crate::parser::Parser;
+    use tapl_testkit::{assert_evals_to, assert_step_trace};
+
+    #[test]
+    fn test_eval() {
+        assert_evals_to!(
+            Reconstruction,
+            "(lambda f.lambda x.f (f x)) (lambda n.succ(n)) 0;",
+            "succ(succ(0))"
+        );
+        assert_step_trace!(
+            Reconstruction,
+            "(lambda x.iszero(x)) pred(0);",
+            ["(lambda x.iszero(x)) 0", "iszero(0)", "true"]
+        );
+    }
+    #[test]
+    fn test_eval_outcome() {
+        let term = |input: &str| Parser::new().parse(input).unwrap();
+        assert_eq!(
+            eval(&term("iszero(succ(0));")),
+            Outcome::Value(Term::TmFalse)
+        );
+        // an ill-typed term stops at a normal form that is not a value
+        let stuck = term("if 0 then true else false;");
+        assert_eq!(eval(&stuck), Outcome::Stuck(stuck));
+    }
+}
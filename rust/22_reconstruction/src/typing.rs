@@ -0,0 +1,472 @@
+use std::fmt::{self, Formatter};
+
+use misc::Notation;
+
+use crate::{
+    context::Context,
+    parser::Term,
+    unify::{self, free_vars, substitute_var, unify, Unifiable, UnifyError},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Type {
+    Boolean,
+    Number,
+    Arrow(Box<Type>, Box<Type>),
+    // type variable to be solved by unification
+    Var(usize),
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Boolean => write!(f, "Bool"),
+            Type::Number => write!(f, "Nat"),
+            Type::Arrow(param, ret) => match param.as_ref() {
+                Type::Arrow(_, _) => write!(f, "({})->{}", param, ret),
+                _ => write!(f, "{}->{}", param, ret),
+            },
+            Type::Var(index) => write!(f, "X{}", index),
+        }
+    }
+}
+
+impl Unifiable for Type {
+    fn var(&self) -> Option<usize> {
+        match self {
+            Type::Var(var) => Some(*var),
+            _ => None,
+        }
+    }
+
+    fn decompose(&self, other: &Self) -> Option<Vec<(Self, Self)>> {
+        match (self, other) {
+            (Type::Boolean, Type::Boolean) | (Type::Number, Type::Number) => Some(vec![]),
+            (Type::Arrow(s1, s2), Type::Arrow(t1, t2)) => Some(vec![
+                (s1.as_ref().clone(), t1.as_ref().clone()),
+                (s2.as_ref().clone(), t2.as_ref().clone()),
+            ]),
+            _ => None,
+        }
+    }
+
+    fn components(&self) -> Vec<&Self> {
+        match self {
+            Type::Arrow(param, ret) => vec![param, ret],
+            _ => vec![],
+        }
+    }
+
+    fn map_components(&self, mut f: impl FnMut(&Self) -> Self) -> Self {
+        match self {
+            Type::Arrow(param, ret) => Type::Arrow(Box::new(f(param)), Box::new(f(ret))),
+            _ => self.clone(),
+        }
+    }
+}
+
+// the constraint typing rule that produced a constraint and the term it was
+// applied to
+#[derive(Clone, Debug, PartialEq)]
+pub struct Origin {
+    pub rule: &'static str,
+    pub term: Term,
+}
+
+pub type Constraint = unify::Constraint<Type, Origin>;
+
+pub type Substitution = unify::Substitution<Type>;
+
+// ∀X1..Xn.T, the type of a `let` bound name, each use instantiates the
+// variables afresh (TAPL 22.7)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scheme {
+    pub vars: Vec<usize>,
+    pub typ: Type,
+}
+
+impl Scheme {
+    // the type of a lambda bound name, the same at every use
+    pub fn monomorphic(typ: Type) -> Self {
+        Scheme { vars: vec![], typ }
+    }
+
+    // `∀X Y. X→Y`, the quantified variables are named X, Y, Z, A, B, ... in
+    // the order of `vars`, the others keep their number
+    pub fn display(&self, notation: Notation) -> SchemeDisplay<'_> {
+        SchemeDisplay {
+            scheme: self,
+            notation,
+        }
+    }
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.display(Notation::Ascii).fmt(f)
+    }
+}
+
+pub struct SchemeDisplay<'a> {
+    scheme: &'a Scheme,
+    notation: Notation,
+}
+
+const VAR_NAMES: &str = "XYZABCDEFGHIJKLMNOPQRSTUVW";
+
+// X, ..., W, then X', ..., W' and so on
+fn var_name(position: usize) -> String {
+    let letter = VAR_NAMES.as_bytes()[position % VAR_NAMES.len()] as char;
+    let primes = "'".repeat(position / VAR_NAMES.len());
+    format!("{}{}", letter, primes)
+}
+
+impl fmt::Display for SchemeDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Scheme { vars, typ } = self.scheme;
+        if !vars.is_empty() {
+            let names = (0..vars.len()).map(var_name).collect::<Vec<_>>();
+            write!(f, "{}{}. ", self.notation.forall(), names.join(" "))?;
+        }
+        write_type(f, typ, vars, self.notation)
+    }
+}
+
+fn write_type(
+    f: &mut Formatter<'_>,
+    typ: &Type,
+    vars: &[usize],
+    notation: Notation,
+) -> fmt::Result {
+    match typ {
+        Type::Var(var) => match vars.iter().position(|bound| bound == var) {
+            Some(position) => write!(f, "{}", var_name(position)),
+            None => write!(f, "{}", typ),
+        },
+        Type::Arrow(param, ret) => {
+            // arrows associate to the right, so only the left side needs parentheses
+            if let Type::Arrow(_, _) = param.as_ref() {
+                write!(f, "(")?;
+                write_type(f, param, vars, notation)?;
+                write!(f, ")")?;
+            } else {
+                write_type(f, param, vars, notation)?;
+            }
+            write!(f, "{}", notation.arrow())?;
+            write_type(f, ret, vars, notation)
+        }
+        _ => write!(f, "{}", typ),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum InferError {
+    UnboundVariable(usize),
+    // the constraints equate two different types
+    Mismatch(Type, Type, Origin),
+    // a type would have to contain itself, e.g. for `lambda x.x x`
+    InfiniteType(usize, Type, Origin),
+}
+
+impl fmt::Display for InferError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            InferError::UnboundVariable(index) => write!(f, "unbound variable index {}", index),
+            InferError::Mismatch(s, t, origin) => {
+                write!(f, "cannot unify {} with {} ({})", s, t, origin.rule)
+            }
+            InferError::InfiniteType(var, typ, origin) => {
+                write!(
+                    f,
+                    "infinite type: X{} occurs in {} ({})",
+                    var, typ, origin.rule
+                )
+            }
+        }
+    }
+}
+
+impl From<UnifyError<Type, Origin>> for InferError {
+    fn from(error: UnifyError<Type, Origin>) -> Self {
+        match error {
+            UnifyError::Mismatch(s, t, origin) => InferError::Mismatch(s, t, origin),
+            UnifyError::Occurs(var, typ, origin) => InferError::InfiniteType(var, typ, origin),
+        }
+    }
+}
+
+// Supplies type variables not used anywhere else
+#[derive(Default)]
+struct Fresh {
+    next: usize,
+}
+
+impl Fresh {
+    fn var(&mut self) -> Type {
+        self.next += 1;
+        Type::Var(self.next - 1)
+    }
+}
+
+fn instantiate(scheme: &Scheme, fresh: &mut Fresh) -> Type {
+    scheme.vars.iter().fold(scheme.typ.clone(), |typ, var| {
+        substitute_var(&typ, *var, &fresh.var())
+    })
+}
+
+// Quantifies the variables of `typ` that are not free in the context. They are
+// renamed to fresh variables, which no constraint mentions, so solving the
+// constraints again later cannot bind them.
+fn generalize(ctx: &Context, substitution: &Substitution, typ: &Type, fresh: &mut Fresh) -> Scheme {
+    let mut context_vars = vec![];
+    for scheme in ctx.schemes() {
+        let mut vars = vec![];
+        free_vars(&substitution.apply(&scheme.typ), &mut vars);
+        context_vars.extend(vars.into_iter().filter(|var| !scheme.vars.contains(var)));
+    }
+    let mut vars = vec![];
+    free_vars(typ, &mut vars);
+    let mut scheme = Scheme::monomorphic(typ.clone());
+    for var in vars.into_iter().filter(|var| !context_vars.contains(var)) {
+        let generic = fresh.var();
+        scheme.typ = substitute_var(&scheme.typ, var, &generic);
+        if let Type::Var(generic) = generic {
+            scheme.vars.push(generic);
+        }
+    }
+    scheme
+}
+
+// Γ ⊢ t : T | C, the constraint typing rules CT-* of TAPL 22.3
+fn constraint_type(
+    ctx: &mut Context,
+    term: &Term,
+    fresh: &mut Fresh,
+    constraints: &mut Vec<Constraint>,
+) -> Result<Type, InferError> {
+    let origin = |rule| Origin {
+        rule,
+        term: term.clone(),
+    };
+    match term {
+        Term::TmTrue | Term::TmFalse => Ok(Type::Boolean),
+        Term::TmZero => Ok(Type::Number),
+        Term::TmSucc(argument) | Term::TmPred(argument) => {
+            let typ = constraint_type(ctx, argument, fresh, constraints)?;
+            let rule = match term {
+                Term::TmSucc(_) => "CT-Succ",
+                _ => "CT-Pred",
+            };
+            constraints.push(Constraint::new(typ, Type::Number, origin(rule)));
+            Ok(Type::Number)
+        }
+        Term::TmIsZero(argument) => {
+            let typ = constraint_type(ctx, argument, fresh, constraints)?;
+            constraints.push(Constraint::new(typ, Type::Number, origin("CT-IsZero")));
+            Ok(Type::Boolean)
+        }
+        Term::TmVar(index) => ctx
+            .get_scheme(*index)
+            .map(|scheme| instantiate(scheme, fresh))
+            .ok_or(InferError::UnboundVariable(*index)),
+        Term::TmAbs(name, body) => {
+            let param_type = fresh.var();
+            ctx.push(name.clone(), Scheme::monomorphic(param_type.clone()));
+            let body_type = constraint_type(ctx, body, fresh, constraints);
+            ctx.pop();
+            Ok(Type::Arrow(Box::new(param_type), Box::new(body_type?)))
+        }
+        Term::TmApp(left, right) => {
+            let left_type = constraint_type(ctx, left, fresh, constraints)?;
+            let right_type = constraint_type(ctx, right, fresh, constraints)?;
+            let return_type = fresh.var();
+            constraints.push(Constraint::new(
+                left_type,
+                Type::Arrow(Box::new(right_type), Box::new(return_type.clone())),
+                origin("CT-App"),
+            ));
+            Ok(return_type)
+        }
+        Term::TmIf(if_term, then_term, else_term) => {
+            let if_type = constraint_type(ctx, if_term, fresh, constraints)?;
+            let then_type = constraint_type(ctx, then_term, fresh, constraints)?;
+            let else_type = constraint_type(ctx, else_term, fresh, constraints)?;
+            constraints.push(Constraint::new(if_type, Type::Boolean, origin("CT-If")));
+            constraints.push(Constraint::new(
+                then_type.clone(),
+                else_type,
+                origin("CT-If"),
+            ));
+            Ok(then_type)
+        }
+        // CT-LetPoly: solve the constraints of the bound term so far, then
+        // generalize its principal type
+        Term::TmLet(name, bound, body) => {
+            let bound_type = constraint_type(ctx, bound, fresh, constraints)?;
+            let substitution = unify(constraints.clone())?;
+            let scheme = generalize(ctx, &substitution, &substitution.apply(&bound_type), fresh);
+            ctx.push(name.clone(), scheme);
+            let body_type = constraint_type(ctx, body, fresh, constraints);
+            ctx.pop();
+            body_type
+        }
+    }
+}
+
+// renames the variables of `typ` to 0, 1, ... in the order they first occur
+fn canonicalize(typ: &Type, names: &mut Vec<usize>) -> Type {
+    match typ {
+        Type::Var(var) => match names.iter().position(|name| name == var) {
+            Some(index) => Type::Var(index),
+            None => {
+                names.push(*var);
+                Type::Var(names.len() - 1)
+            }
+        },
+        Type::Arrow(param, ret) => {
+            let param = canonicalize(param, names);
+            Type::Arrow(Box::new(param), Box::new(canonicalize(ret, names)))
+        }
+        _ => typ.clone(),
+    }
+}
+
+// The principal type of a closed term: every type of the term is an instance
+// of it (TAPL 22.5)
+pub fn infer(term: &Term) -> Result<Type, InferError> {
+    let mut constraints = vec![];
+    let typ = constraint_type(
+        &mut Context::default(),
+        term,
+        &mut Fresh::default(),
+        &mut constraints,
+    )?;
+    let substitution = unify(constraints)?;
+    Ok(canonicalize(&substitution.apply(&typ), &mut vec![]))
+}
+
+// The principal type of a closed term with its variables quantified, e.g.
+// `∀X. X→X` for `lambda x.x`
+pub fn principal(term: &Term) -> Result<Scheme, InferError> {
+    let typ = infer(term)?;
+    let mut vars = vec![];
+    free_vars(&typ, &mut vars);
+    Ok(Scheme { vars, typ })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{language::Reconstruction, parser::Parser};
+    use tapl_testkit::assert_type_error;
+
+    fn infer_str(input: &str) -> Result<String, InferError> {
+        infer(&Parser::new().parse(input).unwrap()).map(|typ| typ.to_string())
+    }
+
+    #[test]
+    fn test_infer() {
+        assert_eq!(infer_str("lambda x.x;"), Ok("X0->X0".to_string()));
+        assert_eq!(infer_str("lambda x.succ(x);"), Ok("Nat->Nat".to_string()));
+        assert_eq!(
+            infer_str("lambda f.lambda x.f (f x);"),
+            Ok("(X0->X0)->X0->X0".to_string())
+        );
+        assert_eq!(
+            infer_str("lambda f.lambda g.lambda x.f (g x);"),
+            Ok("(X0->X1)->(X2->X0)->X2->X1".to_string())
+        );
+        // TAPL 22.3.3
+        assert_eq!(
+            infer_str("lambda x.lambda y.lambda z.(x z) (y z);"),
+            Ok("(X0->X1->X2)->(X0->X1)->X0->X2".to_string())
+        );
+        assert_eq!(
+            infer_str("lambda b.if b then 0 else succ(0);"),
+            Ok("Bool->Nat".to_string())
+        );
+    }
+
+    #[test]
+    fn test_infer_error() {
+        assert_eq!(
+            infer_str("lambda x.x x;"),
+            Err(InferError::InfiniteType(
+                0,
+                Type::Arrow(Box::new(Type::Var(0)), Box::new(Type::Var(1))),
+                Origin {
+                    rule: "CT-App",
+                    term: Term::TmApp(Box::new(Term::TmVar(0)), Box::new(Term::TmVar(0))),
+                }
+            ))
+        );
+        assert_type_error!(
+            Reconstruction,
+            "(lambda x.x x) (lambda x.x x);",
+            "infinite type"
+        );
+        // the error names the rule whose constraint failed
+        assert_type_error!(
+            Reconstruction,
+            "succ(true);",
+            "cannot unify Bool with Nat (CT-Succ)"
+        );
+        assert_type_error!(
+            Reconstruction,
+            "lambda f.if f then f 0 else 0;",
+            "cannot unify"
+        );
+    }
+
+    #[test]
+    fn test_principal() {
+        let principal = |input: &str, notation: Notation| {
+            let term = Parser::new().parse(input).unwrap();
+            principal(&term).unwrap().display(notation).to_string()
+        };
+        assert_eq!(principal("lambda x.x;", Notation::Unicode), "∀X. X→X");
+        assert_eq!(principal("lambda x.x;", Notation::Ascii), "Forall X. X->X");
+        assert_eq!(
+            principal("lambda f.lambda g.lambda x.f (g x);", Notation::Unicode),
+            "∀X Y Z. (X→Y)→(Z→X)→Z→Y"
+        );
+        assert_eq!(principal("lambda x.succ(x);", Notation::Unicode), "Nat→Nat");
+        let many = Scheme {
+            vars: (0..27).collect(),
+            typ: Type::Arrow(Box::new(Type::Var(25)), Box::new(Type::Var(26))),
+        };
+        assert!(many.to_string().ends_with(". W->X'"));
+    }
+
+    #[test]
+    fn test_let_polymorphism() {
+        assert_eq!(
+            infer_str("let f = lambda x.x in if f true then f 0 else f succ(0);"),
+            Ok("Nat".to_string())
+        );
+        assert_eq!(
+            infer_str("let f = lambda x.x in f;"),
+            Ok("X0->X0".to_string())
+        );
+        assert_eq!(
+            infer_str("let d = lambda f.lambda x.f (f x) in if d (lambda b.b) true then d (lambda n.succ(n)) 0 else 0;"),
+            Ok("Nat".to_string())
+        );
+        // a lambda bound argument stays monomorphic
+        assert_type_error!(
+            Reconstruction,
+            "(lambda f.if f true then f 0 else f succ(0)) (lambda x.x);",
+            "cannot unify"
+        );
+        // variables of the context are not generalized
+        assert_eq!(
+            infer_str("lambda y.let f = lambda x.y in succ(f true);"),
+            Ok("Nat->Nat".to_string())
+        );
+        assert_type_error!(
+            Reconstruction,
+            "lambda y.let f = lambda x.y in if f 0 then succ(f 0) else 0;",
+            "cannot unify"
+        );
+    }
+}
@@ -0,0 +1,302 @@
+// The unification algorithm of TAPL 22.4, independent of the type language it
+// runs on. A language implements `Unifiable` for its types and tags every
+// constraint with an origin of its own choosing, e.g. the typing rule that
+// produced it, and a constraint that cannot be solved is reported with that
+// origin. Later chapters reuse it with their own types.
+
+// A type that may contain unification variables
+pub trait Unifiable: Clone + PartialEq {
+    // the variable this type is, if it is one
+    fn var(&self) -> Option<usize>;
+
+    // The pairs of component types to equate when `self` and `other` are
+    // built by the same type constructor, e.g. both parameter types and both
+    // return types of two arrows, or None for different constructors. Neither
+    // of the two is a variable.
+    fn decompose(&self, other: &Self) -> Option<Vec<(Self, Self)>>;
+
+    // the direct component types, e.g. the parameter and return type of an
+    // arrow
+    fn components(&self) -> Vec<&Self>;
+
+    // the same type constructor applied to `f` of every direct component
+    fn map_components(&self, f: impl FnMut(&Self) -> Self) -> Self;
+}
+
+// S = T, produced by constraint typing
+#[derive(Clone, Debug, PartialEq)]
+pub struct Constraint<T, O = ()> {
+    pub left: T,
+    pub right: T,
+    pub origin: O,
+}
+
+impl<T, O> Constraint<T, O> {
+    pub fn new(left: T, right: T, origin: O) -> Self {
+        Constraint {
+            left,
+            right,
+            origin,
+        }
+    }
+}
+
+// Bindings [X ↦ T] applied one after the other, each binding's type may still
+// mention variables bound later on
+#[derive(Clone, Debug, PartialEq)]
+pub struct Substitution<T> {
+    bindings: Vec<(usize, T)>,
+}
+
+impl<T> Default for Substitution<T> {
+    fn default() -> Self {
+        Substitution { bindings: vec![] }
+    }
+}
+
+impl<T: Unifiable> Substitution<T> {
+    pub fn apply(&self, typ: &T) -> T {
+        self.bindings
+            .iter()
+            .fold(typ.clone(), |typ, (var, s)| substitute_var(&typ, *var, s))
+    }
+
+    // the bindings in the order they are applied
+    pub fn bindings(&self) -> &[(usize, T)] {
+        &self.bindings
+    }
+
+    // the bindings of `later` applied after those of `self`, for a
+    // substitution that solves its constraints one at a time
+    pub fn extend(&mut self, later: Substitution<T>) {
+        self.bindings.extend(later.bindings);
+    }
+}
+
+// Why no substitution solves the constraints, with the origin of the
+// constraint that failed. A mismatch may be between components of the types
+// the constraint equates.
+#[derive(Clone, Debug, PartialEq)]
+pub enum UnifyError<T, O = ()> {
+    // two different type constructors have to be equal
+    Mismatch(T, T, O),
+    // X = T where T mentions X has no finite solution
+    Occurs(usize, T, O),
+}
+
+// [var ↦ s]typ
+pub fn substitute_var<T: Unifiable>(typ: &T, var: usize, s: &T) -> T {
+    match typ.var() {
+        Some(x) if x == var => s.clone(),
+        Some(_) => typ.clone(),
+        None => typ.map_components(|component| substitute_var(component, var, s)),
+    }
+}
+
+// whether the variable appears in `typ`
+pub fn occurs<T: Unifiable>(var: usize, typ: &T) -> bool {
+    match typ.var() {
+        Some(x) => x == var,
+        None => typ
+            .components()
+            .into_iter()
+            .any(|component| occurs(var, component)),
+    }
+}
+
+// adds the variables of `typ` that are not in `vars` yet, in the order they
+// first occur
+pub fn free_vars<T: Unifiable>(typ: &T, vars: &mut Vec<usize>) {
+    match typ.var() {
+        Some(var) if !vars.contains(&var) => vars.push(var),
+        Some(_) => {}
+        None => {
+            for component in typ.components() {
+                free_vars(component, vars);
+            }
+        }
+    }
+}
+
+// The most general unifier of the constraints (TAPL 22.4)
+pub fn unify<T: Unifiable, O: Clone>(
+    constraints: Vec<Constraint<T, O>>,
+) -> Result<Substitution<T>, UnifyError<T, O>> {
+    let mut constraints = constraints;
+    let mut substitution = Substitution::default();
+    while let Some(Constraint {
+        left,
+        right,
+        origin,
+    }) = constraints.pop()
+    {
+        if left == right {
+            continue;
+        }
+        let (x, t) = match (left.var(), right.var()) {
+            (Some(x), _) => (x, right),
+            (None, Some(x)) => (x, left),
+            (None, None) => match left.decompose(&right) {
+                Some(pairs) => {
+                    // the first pair is solved first
+                    constraints.extend(
+                        pairs
+                            .into_iter()
+                            .rev()
+                            .map(|(s, t)| Constraint::new(s, t, origin.clone())),
+                    );
+                    continue;
+                }
+                None => return Err(UnifyError::Mismatch(left, right, origin)),
+            },
+        };
+        if occurs(x, &t) {
+            return Err(UnifyError::Occurs(x, t, origin));
+        }
+        for constraint in constraints.iter_mut() {
+            constraint.left = substitute_var(&constraint.left, x, &t);
+            constraint.right = substitute_var(&constraint.right, x, &t);
+        }
+        substitution.bindings.push((x, t));
+    }
+    Ok(substitution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::typing::Type;
+
+    fn arrow(param: Type, ret: Type) -> Type {
+        Type::Arrow(Box::new(param), Box::new(ret))
+    }
+
+    fn equal(left: Type, right: Type) -> Constraint<Type> {
+        Constraint::new(left, right, ())
+    }
+
+    #[test]
+    fn test_unify() {
+        // TAPL 22.4.3: {X = Nat, Y = X->X}
+        let substitution = unify(vec![
+            equal(Type::Var(0), Type::Number),
+            equal(Type::Var(1), arrow(Type::Var(0), Type::Var(0))),
+        ])
+        .unwrap();
+        assert_eq!(
+            substitution.apply(&Type::Var(1)),
+            arrow(Type::Number, Type::Number)
+        );
+        // {Nat->Nat = X->Y}
+        let substitution = unify(vec![equal(
+            arrow(Type::Number, Type::Number),
+            arrow(Type::Var(0), Type::Var(1)),
+        )])
+        .unwrap();
+        assert_eq!(
+            substitution.apply(&arrow(Type::Var(0), Type::Var(1))),
+            arrow(Type::Number, Type::Number)
+        );
+        assert_eq!(
+            unify(vec![equal(Type::Var(0), arrow(Type::Number, Type::Var(0)))]),
+            Err(UnifyError::Occurs(0, arrow(Type::Number, Type::Var(0)), ()))
+        );
+        assert_eq!(
+            unify(vec![equal(Type::Number, arrow(Type::Var(0), Type::Var(0)))]),
+            Err(UnifyError::Mismatch(
+                Type::Number,
+                arrow(Type::Var(0), Type::Var(0)),
+                ()
+            ))
+        );
+        // the constraints of TAPL 22.4.3 solved one at a time
+        let mut substitution = unify(vec![equal(Type::Var(0), Type::Number)]).unwrap();
+        let later = equal(
+            Type::Var(1),
+            substitution.apply(&arrow(Type::Var(0), Type::Var(0))),
+        );
+        substitution.extend(unify(vec![later]).unwrap());
+        assert_eq!(
+            substitution.apply(&Type::Var(1)),
+            arrow(Type::Number, Type::Number)
+        );
+    }
+
+    // a type language of named constructors, unrelated to `Type`
+    #[derive(Clone, Debug, PartialEq)]
+    enum Tree {
+        Var(usize),
+        Con(&'static str, Vec<Tree>),
+    }
+
+    impl Unifiable for Tree {
+        fn var(&self) -> Option<usize> {
+            match self {
+                Tree::Var(var) => Some(*var),
+                Tree::Con(_, _) => None,
+            }
+        }
+
+        fn decompose(&self, other: &Self) -> Option<Vec<(Self, Self)>> {
+            match (self, other) {
+                (Tree::Con(f, xs), Tree::Con(g, ys)) if f == g && xs.len() == ys.len() => {
+                    Some(xs.iter().cloned().zip(ys.iter().cloned()).collect())
+                }
+                _ => None,
+            }
+        }
+
+        fn components(&self) -> Vec<&Self> {
+            match self {
+                Tree::Var(_) => vec![],
+                Tree::Con(_, args) => args.iter().collect(),
+            }
+        }
+
+        fn map_components(&self, f: impl FnMut(&Self) -> Self) -> Self {
+            match self {
+                Tree::Var(_) => self.clone(),
+                Tree::Con(name, args) => Tree::Con(name, args.iter().map(f).collect()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_origin() {
+        let pair = |s: Tree, t: Tree| Tree::Con("pair", vec![s, t]);
+        let nat = Tree::Con("nat", vec![]);
+        let bool = Tree::Con("bool", vec![]);
+        let substitution = unify(vec![
+            Constraint::new(Tree::Var(0), pair(nat.clone(), Tree::Var(1)), "first"),
+            Constraint::new(Tree::Var(1), bool.clone(), "second"),
+        ])
+        .unwrap();
+        assert_eq!(
+            substitution.apply(&Tree::Var(0)),
+            pair(nat.clone(), bool.clone())
+        );
+        // the components that differ are reported with the whole constraint's origin
+        let error = unify(vec![
+            Constraint::new(
+                pair(Tree::Var(0), nat.clone()),
+                pair(bool, Tree::Var(0)),
+                "pair",
+            ),
+            Constraint::new(Tree::Var(1), Tree::Var(1), "trivial"),
+        ]);
+        assert_eq!(
+            error,
+            Err(UnifyError::Mismatch(
+                nat.clone(),
+                Tree::Con("bool", vec![]),
+                "pair"
+            ))
+        );
+        let error = unify(vec![Constraint::new(
+            Tree::Var(0),
+            pair(Tree::Var(0), nat),
+            "loop",
+        )]);
+        assert!(matches!(error, Err(UnifyError::Occurs(0, _, "loop"))));
+    }
+}
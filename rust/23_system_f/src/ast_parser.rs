@@ -0,0 +1,226 @@
+use tapl_frontend::lexer::{Lexer, Token};
+
+use crate::{
+    parser::ParseError,
+    type_parser::{parse_type, parse_type_var, ASTType},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ASTTerm {
+    TmTrue,
+    TmFalse,
+    TmZero,
+    TmSucc(Box<ASTTerm>),
+    TmPred(Box<ASTTerm>),
+    TmIsZero(Box<ASTTerm>),
+    TmVar(String),
+    // argument ident, argument type and body
+    TmAbs(String, ASTType, Box<ASTTerm>),
+    TmApp(Box<ASTTerm>, Box<ASTTerm>),
+    // condition term, then term, else term
+    TmIf(Box<ASTTerm>, Box<ASTTerm>, Box<ASTTerm>),
+    // bound ident, bound term and body
+    TmLet(String, Box<ASTTerm>, Box<ASTTerm>),
+    // `lambda X.t`
+    TmTAbs(String, Box<ASTTerm>),
+    // `t [T]`
+    TmTApp(Box<ASTTerm>, ASTType),
+    // `{l1=t1, l2=t2}`
+    TmRecord(Vec<(String, ASTTerm)>),
+    // `t.l`
+    TmProj(Box<ASTTerm>, String),
+    // `{*T, t} as {Some X, T2}`
+    TmPack(ASTType, Box<ASTTerm>, ASTType),
+    // `let {X, x} = t1 in t2`
+    TmUnpack(String, String, Box<ASTTerm>, Box<ASTTerm>),
+}
+
+// the words a variable cannot be named
+pub const KEYWORDS: [&str; 12] = [
+    "lambda", "let", "in", "if", "then", "else", "as", "true", "false", "succ", "pred", "iszero",
+];
+
+// the keywords an atom starts with
+const ATOM_KEYWORDS: [&str; 5] = ["true", "false", "succ", "pred", "iszero"];
+
+// term variables are lowercase words, capitalized ones name types
+fn is_variable(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_lowercase()) && !KEYWORDS.contains(&word)
+}
+
+fn variable<'a>(lexer: &mut Lexer<'a>) -> Result<&'a str, ParseError> {
+    match lexer.peek()? {
+        (Token::Word(word), _) if is_variable(word) => {
+            lexer.next_token()?;
+            Ok(word)
+        }
+        _ => Err(lexer.unexpected("a variable").into()),
+    }
+}
+
+fn starts_atom(lexer: &mut Lexer) -> Result<bool, ParseError> {
+    Ok(match lexer.peek()?.0 {
+        Token::Word(word) => is_variable(word) || ATOM_KEYWORDS.contains(&word),
+        Token::Number(_) | Token::Symbol("(") | Token::Symbol("{") => true,
+        _ => false,
+    })
+}
+
+// `(t)` after `succ`, `pred` or `iszero`
+fn parse_argument(lexer: &mut Lexer) -> Result<Box<ASTTerm>, ParseError> {
+    lexer.expect_symbol("(")?;
+    let term = parse_term(lexer)?;
+    lexer.expect_symbol(")")?;
+    Ok(Box::new(term))
+}
+
+// `{l=t, ...}` after the `{`
+fn parse_record(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let mut fields = vec![];
+    if lexer.eat_symbol("}")?.is_none() {
+        loop {
+            let (label, _) = lexer.expect_word("a label", &[])?;
+            lexer.expect_symbol("=")?;
+            fields.push((label.to_string(), parse_term(lexer)?));
+            if lexer.eat_symbol(",")?.is_none() {
+                break;
+            }
+        }
+        lexer.expect_symbol("}")?;
+    }
+    Ok(ASTTerm::TmRecord(fields))
+}
+
+// `T, t} as {Some X, T2}` after `{*` hides the witness type T behind X
+fn parse_pack(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let witness = parse_type(lexer)?;
+    lexer.expect_symbol(",")?;
+    let term = parse_term(lexer)?;
+    lexer.expect_symbol("}")?;
+    lexer.expect_keyword("as")?;
+    let typ = parse_type(lexer)?;
+    Ok(ASTTerm::TmPack(witness, Box::new(term), typ))
+}
+
+fn parse_primary(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let (token, span) = lexer.next_token()?;
+    let term = match token {
+        Token::Word("true") => ASTTerm::TmTrue,
+        Token::Word("false") => ASTTerm::TmFalse,
+        Token::Word("succ") => ASTTerm::TmSucc(parse_argument(lexer)?),
+        Token::Word("pred") => ASTTerm::TmPred(parse_argument(lexer)?),
+        Token::Word("iszero") => ASTTerm::TmIsZero(parse_argument(lexer)?),
+        Token::Word(word) if is_variable(word) => ASTTerm::TmVar(word.to_string()),
+        Token::Number("0") => ASTTerm::TmZero,
+        Token::Symbol("{") => match lexer.eat_symbol("*")? {
+            Some(_) => parse_pack(lexer)?,
+            None => parse_record(lexer)?,
+        },
+        Token::Symbol("(") => {
+            let term = parse_term(lexer)?;
+            lexer.expect_symbol(")")?;
+            term
+        }
+        _ => return Err(lexer.error_at("a term", &token.to_string(), span).into()),
+    };
+    Ok(term)
+}
+
+// `t.l` projects a record field
+fn parse_atom(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    lexer.nested(|lexer| {
+        let mut term = parse_primary(lexer)?;
+        while lexer.eat_symbol(".")?.is_some() {
+            lexer.deepen()?;
+            let (label, _) = lexer.expect_word("a label", &[])?;
+            term = ASTTerm::TmProj(Box::new(term), label.to_string());
+        }
+        Ok(term)
+    })
+}
+
+// term and type arguments associate to the left: `f [Nat] 0` is `(f [Nat]) 0`
+fn parse_application(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let mut term = parse_atom(lexer)?;
+    loop {
+        if lexer.eat_symbol("[")?.is_some() {
+            lexer.deepen()?;
+            let typ = parse_type(lexer)?;
+            lexer.expect_symbol("]")?;
+            term = ASTTerm::TmTApp(Box::new(term), typ);
+        } else if starts_atom(lexer)? {
+            lexer.deepen()?;
+            let right = parse_atom(lexer)?;
+            term = ASTTerm::TmApp(Box::new(term), Box::new(right));
+        } else {
+            return Ok(term);
+        }
+    }
+}
+
+// `X.t` or `x:T.t` after `lambda`
+fn parse_abstraction(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    if let (Token::Word(word), _) = lexer.peek()? {
+        if word.starts_with(|c: char| c.is_ascii_uppercase()) {
+            let name = parse_type_var(lexer)?;
+            lexer.expect_symbol(".")?;
+            return Ok(ASTTerm::TmTAbs(name, Box::new(parse_term(lexer)?)));
+        }
+    }
+    let param = variable(lexer)?.to_string();
+    lexer.expect_symbol(":")?;
+    let typ = parse_type(lexer)?;
+    lexer.expect_symbol(".")?;
+    Ok(ASTTerm::TmAbs(param, typ, Box::new(parse_term(lexer)?)))
+}
+
+fn parse_if(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let condition = parse_term(lexer)?;
+    lexer.expect_keyword("then")?;
+    let then_term = parse_term(lexer)?;
+    lexer.expect_keyword("else")?;
+    let else_term = parse_term(lexer)?;
+    Ok(ASTTerm::TmIf(
+        Box::new(condition),
+        Box::new(then_term),
+        Box::new(else_term),
+    ))
+}
+
+// `x = t1 in t2` after `let`, or `{X, x} = t1 in t2` that opens a package
+// where X stands for its hidden type
+fn parse_let(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    let names = match lexer.eat_symbol("{")? {
+        Some(_) => {
+            let type_name = parse_type_var(lexer)?;
+            lexer.expect_symbol(",")?;
+            let name = variable(lexer)?.to_string();
+            lexer.expect_symbol("}")?;
+            (Some(type_name), name)
+        }
+        None => (None, variable(lexer)?.to_string()),
+    };
+    lexer.expect_symbol("=")?;
+    let bound = Box::new(parse_term(lexer)?);
+    lexer.expect_keyword("in")?;
+    let body = Box::new(parse_term(lexer)?);
+    Ok(match names {
+        (Some(type_name), name) => ASTTerm::TmUnpack(type_name, name, bound, body),
+        (None, name) => ASTTerm::TmLet(name, bound, body),
+    })
+}
+
+// the bodies of abstractions and lets extend as far to the right as possible
+pub fn parse_term(lexer: &mut Lexer) -> Result<ASTTerm, ParseError> {
+    lexer.nested(|lexer| {
+        if lexer.eat_keyword("lambda")?.is_some() {
+            parse_abstraction(lexer)
+        } else if lexer.eat_keyword("let")?.is_some() {
+            parse_let(lexer)
+        } else if lexer.eat_keyword("if")?.is_some() {
+            parse_if(lexer)
+        } else {
+            parse_application(lexer)
+        }
+    })
+}
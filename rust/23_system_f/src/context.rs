@@ -0,0 +1,34 @@
+use crate::typing::Type;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Binding {
+    // a term variable and its type
+    Var(Type),
+    // a type variable bound by `lambda X.`
+    TypeVar,
+}
+
+// Term and type variables share one context, the innermost binding is at
+// de Bruijn index 0 (TAPL 25.1)
+#[derive(Clone, Debug, Default)]
+pub struct Context {
+    bindings: Vec<(String, Binding)>,
+}
+
+impl Context {
+    pub fn push(&mut self, name: String, binding: Binding) {
+        self.bindings.push((name, binding));
+    }
+
+    pub fn pop(&mut self) {
+        self.bindings.pop();
+    }
+
+    pub fn get_binding(&self, index: usize) -> Option<&Binding> {
+        self.bindings
+            .iter()
+            .rev()
+            .nth(index)
+            .map(|(_, binding)| binding)
+    }
+}
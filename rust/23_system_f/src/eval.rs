@@ -0,0 +1,230 @@
+use tapl_frontend::outcome::Outcome;
+
+use crate::{
+    parser::Term,
+    substitute::{shift, substitute_top, type_term_substitute_top},
+};
+
+fn is_numeric_val(term: &Term) -> bool {
+    match term {
+        Term::TmZero => true,
+        Term::TmSucc(term) => is_numeric_val(term),
+        _ => false,
+    }
+}
+
+pub fn is_val(term: &Term) -> bool {
+    match term {
+        Term::TmTrue | Term::TmFalse | Term::TmAbs(_, _, _) | Term::TmTAbs(_, _) => true,
+        Term::TmRecord(fields) => fields.iter().all(|(_, term)| is_val(term)),
+        Term::TmPack(_, term, _) => is_val(term),
+        _ => is_numeric_val(term),
+    }
+}
+
+pub fn eval1(term: &Term) -> Option<Term> {
+    let step = |term: &Term| eval1(term).map(Box::new);
+    match term {
+        Term::TmSucc(term) => Some(Term::TmSucc(step(term)?)),
+        Term::TmPred(term) => match term.as_ref() {
+            Term::TmZero => Some(Term::TmZero),
+            Term::TmSucc(number) if is_numeric_val(number) => Some(number.as_ref().clone()),
+            _ => Some(Term::TmPred(step(term)?)),
+        },
+        Term::TmIsZero(term) => match term.as_ref() {
+            Term::TmZero => Some(Term::TmTrue),
+            Term::TmSucc(number) if is_numeric_val(number) => Some(Term::TmFalse),
+            _ => Some(Term::TmIsZero(step(term)?)),
+        },
+        Term::TmApp(left, right) => match left.as_ref() {
+            Term::TmAbs(_, _, body) if is_val(right) => Some(substitute_top(right, body)),
+            _ if is_val(left) => Some(Term::TmApp(left.clone(), step(right)?)),
+            _ => Some(Term::TmApp(step(left)?, right.clone())),
+        },
+        Term::TmIf(if_term, then_term, else_term) => match if_term.as_ref() {
+            Term::TmTrue => Some(then_term.as_ref().clone()),
+            Term::TmFalse => Some(else_term.as_ref().clone()),
+            _ => Some(Term::TmIf(
+                step(if_term)?,
+                then_term.clone(),
+                else_term.clone(),
+            )),
+        },
+        Term::TmLet(name, bound, body) => {
+            if is_val(bound) {
+                Some(substitute_top(bound, body))
+            } else {
+                Some(Term::TmLet(name.clone(), step(bound)?, body.clone()))
+            }
+        }
+        Term::TmTApp(term, typ) => match term.as_ref() {
+            // E-TappTabs
+            Term::TmTAbs(_, body) => Some(type_term_substitute_top(typ, body)),
+            // E-TApp
+            _ => Some(Term::TmTApp(step(term)?, typ.clone())),
+        },
+        Term::TmRecord(fields) => match fields.iter().position(|(_, term)| !is_val(term)) {
+            // evaluate the fields from left to right
+            Some(i) => {
+                let mut fields = fields.clone();
+                fields[i].1 = eval1(&fields[i].1)?;
+                Some(Term::TmRecord(fields))
+            }
+            None => None,
+        },
+        Term::TmProj(term, label) => match term.as_ref() {
+            Term::TmRecord(fields) if is_val(term) => fields
+                .iter()
+                .find(|(name, _)| name == label)
+                .map(|(_, term)| term.clone()),
+            _ => Some(Term::TmProj(step(term)?, label.clone())),
+        },
+        // E-Pack
+        Term::TmPack(witness, term, typ) if !is_val(term) => {
+            Some(Term::TmPack(witness.clone(), step(term)?, typ.clone()))
+        }
+        Term::TmUnpack(type_name, name, bound, body) => match bound.as_ref() {
+            // E-UnpackPack: the witness and the implementation replace X and x
+            Term::TmPack(witness, term, _) if is_val(term) => Some(type_term_substitute_top(
+                witness,
+                &substitute_top(&shift(term, 1, 0), body),
+            )),
+            // E-Unpack
+            _ => Some(Term::TmUnpack(
+                type_name.clone(),
+                name.clone(),
+                step(bound)?,
+                body.clone(),
+            )),
+        },
+        _ => None,
+    }
+}
+
+// takes small steps until no rule applies, a normal form that is not a
+// value is stuck
+pub fn eval(term: &Term) -> Outcome<Term, Term> {
+    let mut term = term.clone();
+    while let Some(next_term) = eval1(&term) {
+        term = next_term;
+    }
+    if is_val(&term) {
+        Outcome::Value(term)
+    } else {
+        Outcome::Stuck(term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::SystemF;
+    use crate::parser::Parser;
+    use tapl_testkit::{assert_evals_to, assert_step_trace, assert_type_error};
+
+    // Church pairs, Pair X Y = All R.(X->Y->R)->R (TAPL 23.4)
+    const PAIR: &str = "let p = lambda X.lambda Y.lambda x:X.lambda y:Y.\
+                        lambda R.lambda k:X->Y->R.k x y in \
+                        let f = lambda X.lambda Y.lambda q:All R.(X->Y->R)->R.\
+                        q [X] (lambda x:X.lambda y:Y.x) in \
+                        let s = lambda X.lambda Y.lambda q:All R.(X->Y->R)->R.\
+                        q [Y] (lambda x:X.lambda y:Y.y) in ";
+
+    // Church lists, List X = All R.(X->R->R)->R->R
+    const LIST: &str = "let n = lambda X.lambda R.lambda c:X->R->R.lambda z:R.z in \
+                        let c = lambda X.lambda h:X.lambda t:All R.(X->R->R)->R->R.\
+                        lambda R.lambda k:X->R->R.lambda z:R.k h (t [R] k z) in \
+                        let e = lambda X.lambda l:All R.(X->R->R)->R->R.\
+                        l [Bool] (lambda h:X.lambda t:Bool.false) true in \
+                        let l = lambda X.lambda l:All R.(X->R->R)->R->R.\
+                        l [Nat] (lambda h:X.lambda t:Nat.succ(t)) 0 in ";
+
+    #[test]
+    fn test_eval() {
+        assert_evals_to!(
+            SystemF,
+            &format!("{}f [Nat] [Bool] (p [Nat] [Bool] 0 true);", PAIR),
+            "0"
+        );
+        assert_evals_to!(
+            SystemF,
+            &format!("{}s [Nat] [Bool] (p [Nat] [Bool] 0 true);", PAIR),
+            "true"
+        );
+        assert_evals_to!(
+            SystemF,
+            &format!(
+                "{}l [Bool] (c [Bool] true (c [Bool] false (n [Bool])));",
+                LIST
+            ),
+            "succ(succ(0))"
+        );
+        assert_evals_to!(SystemF, &format!("{}e [Nat] (n [Nat]);", LIST), "true");
+        assert_evals_to!(
+            SystemF,
+            &format!("{}e [Nat] (c [Nat] 0 (n [Nat]));", LIST),
+            "false"
+        );
+    }
+
+    // the counter ADT of TAPL 24.2, its representation is a hidden Nat
+    const COUNTER: &str = "let {C, c} = {*Nat, {new=succ(0), get=lambda i:Nat.i, \
+                           inc=lambda i:Nat.succ(i)}} as {Some C, {new:C, get:C->Nat, inc:C->C}} in ";
+
+    #[test]
+    fn test_existential() {
+        assert_evals_to!(
+            SystemF,
+            &format!("{}c.get (c.inc (c.inc c.new));", COUNTER),
+            "succ(succ(succ(0)))"
+        );
+        assert_evals_to!(
+            SystemF,
+            &format!(
+                "{}let a = lambda x:C.c.inc (c.inc x) in c.get (a c.new);",
+                COUNTER
+            ),
+            "succ(succ(succ(0)))"
+        );
+        // clients cannot rely on the counter being a number
+        assert_type_error!(
+            SystemF,
+            &format!("{}succ(c.new);", COUNTER),
+            "argument is not a number"
+        );
+        assert_type_error!(
+            SystemF,
+            &format!("{}c.new;", COUNTER),
+            "type variable C escapes"
+        );
+    }
+
+    #[test]
+    fn test_eval1() {
+        // the type argument replaces the variable in the annotations
+        assert_step_trace!(
+            SystemF,
+            "(lambda X.lambda x:X.x) [Nat] 0;",
+            ["(lambda x:Nat.x) 0", "0"]
+        );
+        assert_step_trace!(
+            SystemF,
+            "(lambda X.lambda Y.lambda f:X->Y.f) [Bool] [Nat];",
+            [
+                "(lambda Y.lambda f:Bool->Y.f) [Nat]",
+                "lambda f:Bool->Nat.f"
+            ]
+        );
+    }
+    #[test]
+    fn test_eval_outcome() {
+        let term = |input: &str| Parser::new().parse(input).unwrap();
+        assert_eq!(
+            eval(&term("iszero(succ(0));")),
+            Outcome::Value(Term::TmFalse)
+        );
+        // an ill-typed term stops at a normal form that is not a value
+        let stuck = term("if 0 then true else false;");
+        assert_eq!(eval(&stuck), Outcome::Stuck(stuck));
+    }
+}
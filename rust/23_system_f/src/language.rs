@@ -0,0 +1,39 @@
+use tapl_frontend::outcome::Outcome;
+use tapl_testkit::{Failure, Language};
+
+use crate::{
+    context::Context,
+    eval::{eval, eval1},
+    parser::{Parser, Term},
+    typing::type_of,
+};
+
+pub struct SystemF;
+
+impl Language for SystemF {
+    type Term = Term;
+    type Value = Term;
+
+    fn parse(input: &str) -> Result<Term, Failure> {
+        Parser::new()
+            .parse(input)
+            .map_err(|error| Failure::Parse(error.to_string()))
+    }
+
+    fn type_check(term: &Term) -> Result<(), Failure> {
+        type_of(&mut Context::default(), term)
+            .map(|_| ())
+            .map_err(|error| Failure::Type(error.to_string()))
+    }
+
+    fn eval(term: &Term) -> Result<Term, Failure> {
+        match eval(term) {
+            Outcome::Value(value) => Ok(value),
+            outcome => Err(Failure::Eval(format!("{:?}", outcome))),
+        }
+    }
+
+    fn step(term: &Term) -> Option<Term> {
+        eval1(term)
+    }
+}
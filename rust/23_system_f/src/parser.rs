@@ -0,0 +1,301 @@
+use std::fmt::{self, Formatter};
+
+use tapl_frontend::lexer::{Lexer, SyntaxError};
+
+use crate::{
+    ast_parser::{parse_term, ASTTerm},
+    type_parser::ASTType,
+    typing::Type,
+};
+
+// punctuation of terms and types
+pub const SYMBOLS: [&str; 13] = [
+    "->", "(", ")", "{", "}", "[", "]", ".", ",", ":", ";", "=", "*",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Term {
+    TmTrue,
+    TmFalse,
+    TmZero,
+    TmSucc(Box<Term>),
+    TmPred(Box<Term>),
+    TmIsZero(Box<Term>),
+    // var DeBrujin index
+    TmVar(usize),
+    // argument name, type and body
+    TmAbs(String, Type, Box<Term>),
+    TmApp(Box<Term>, Box<Term>),
+    TmIf(Box<Term>, Box<Term>, Box<Term>),
+    // bound name, bound term and body
+    TmLet(String, Box<Term>, Box<Term>),
+    // type variable name and body (TAPL 23.3)
+    TmTAbs(String, Box<Term>),
+    TmTApp(Box<Term>, Type),
+    TmRecord(Vec<(String, Term)>),
+    TmProj(Box<Term>, String),
+    // witness type, implementation and the existential type it is packed as
+    // (TAPL 24.1)
+    TmPack(Type, Box<Term>, Type),
+    // type variable and term variable names, package and body
+    TmUnpack(String, String, Box<Term>, Box<Term>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    Syntax(SyntaxError),
+    UnboundVariable(String),
+    UnboundTypeVariable(String),
+    TrailingInput(String),
+}
+
+impl From<SyntaxError> for ParseError {
+    fn from(error: SyntaxError) -> Self {
+        ParseError::Syntax(error)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Syntax(error) => write!(f, "{}", error),
+            ParseError::UnboundVariable(name) => write!(f, "unbound variable {}", name),
+            ParseError::UnboundTypeVariable(name) => write!(f, "unbound type variable {}", name),
+            ParseError::TrailingInput(input) => write!(f, "unexpected input `{}`", input),
+        }
+    }
+}
+
+pub type ParseResult = Result<Term, ParseError>;
+
+// Names of the enclosing term and type binders, the innermost is at de
+// Bruijn index 0
+#[derive(Default)]
+pub struct Parser {
+    names: Vec<String>,
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(&mut self, input: &str) -> ParseResult {
+        let mut lexer = Lexer::new(input, &SYMBOLS);
+        let term = parse_term(&mut lexer)?;
+        lexer.expect_symbol(";")?;
+        if !lexer.rest().trim().is_empty() {
+            return Err(ParseError::TrailingInput(lexer.rest().trim().to_string()));
+        }
+
+        self.from_ast_term(&term)
+    }
+
+    fn with_binding<T>(&mut self, name: &str, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.names.push(name.to_string());
+        let result = f(self);
+        self.names.pop();
+        result
+    }
+
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.names.iter().rev().position(|n| n == name)
+    }
+
+    fn from_ast_type(self: &mut Parser, ast_type: &ASTType) -> Result<Type, ParseError> {
+        let typ = match ast_type {
+            ASTType::Boolean => Type::Boolean,
+            ASTType::Number => Type::Number,
+            ASTType::Var(name) => match self.index_of(name) {
+                Some(index) => Type::Var(index),
+                None => return Err(ParseError::UnboundTypeVariable(name.clone())),
+            },
+            ASTType::Arrow(param, ret) => Type::Arrow(
+                Box::new(self.from_ast_type(param)?),
+                Box::new(self.from_ast_type(ret)?),
+            ),
+            ASTType::All(name, body) => {
+                let body = self.with_binding(name, |parser| parser.from_ast_type(body))?;
+                Type::All(name.clone(), Box::new(body))
+            }
+            ASTType::Some(name, body) => {
+                let body = self.with_binding(name, |parser| parser.from_ast_type(body))?;
+                Type::Some(name.clone(), Box::new(body))
+            }
+            ASTType::Record(fields) => Type::Record(
+                fields
+                    .iter()
+                    .map(|(label, typ)| Ok((label.clone(), self.from_ast_type(typ)?)))
+                    .collect::<Result<Vec<_>, ParseError>>()?,
+            ),
+        };
+
+        Ok(typ)
+    }
+
+    fn from_ast_term(self: &mut Parser, ast_term: &ASTTerm) -> ParseResult {
+        let term = match ast_term {
+            ASTTerm::TmTrue => Term::TmTrue,
+            ASTTerm::TmFalse => Term::TmFalse,
+            ASTTerm::TmZero => Term::TmZero,
+            ASTTerm::TmSucc(term) => Term::TmSucc(Box::new(self.from_ast_term(term)?)),
+            ASTTerm::TmPred(term) => Term::TmPred(Box::new(self.from_ast_term(term)?)),
+            ASTTerm::TmIsZero(term) => Term::TmIsZero(Box::new(self.from_ast_term(term)?)),
+            ASTTerm::TmVar(name) => match self.index_of(name) {
+                Some(index) => Term::TmVar(index),
+                None => return Err(ParseError::UnboundVariable(name.clone())),
+            },
+            ASTTerm::TmAbs(name, typ, body) => {
+                let typ = self.from_ast_type(typ)?;
+                let body = self.with_binding(name, |parser| parser.from_ast_term(body))?;
+                Term::TmAbs(name.clone(), typ, Box::new(body))
+            }
+            ASTTerm::TmApp(left, right) => Term::TmApp(
+                Box::new(self.from_ast_term(left)?),
+                Box::new(self.from_ast_term(right)?),
+            ),
+            ASTTerm::TmIf(if_term, then_term, else_term) => Term::TmIf(
+                Box::new(self.from_ast_term(if_term)?),
+                Box::new(self.from_ast_term(then_term)?),
+                Box::new(self.from_ast_term(else_term)?),
+            ),
+            ASTTerm::TmLet(name, bound, body) => {
+                let bound = self.from_ast_term(bound)?;
+                let body = self.with_binding(name, |parser| parser.from_ast_term(body))?;
+                Term::TmLet(name.clone(), Box::new(bound), Box::new(body))
+            }
+            ASTTerm::TmTAbs(name, body) => {
+                let body = self.with_binding(name, |parser| parser.from_ast_term(body))?;
+                Term::TmTAbs(name.clone(), Box::new(body))
+            }
+            ASTTerm::TmTApp(term, typ) => Term::TmTApp(
+                Box::new(self.from_ast_term(term)?),
+                self.from_ast_type(typ)?,
+            ),
+            ASTTerm::TmRecord(fields) => Term::TmRecord(
+                fields
+                    .iter()
+                    .map(|(label, term)| Ok((label.clone(), self.from_ast_term(term)?)))
+                    .collect::<Result<Vec<_>, ParseError>>()?,
+            ),
+            ASTTerm::TmProj(term, label) => {
+                Term::TmProj(Box::new(self.from_ast_term(term)?), label.clone())
+            }
+            ASTTerm::TmPack(witness, term, typ) => Term::TmPack(
+                self.from_ast_type(witness)?,
+                Box::new(self.from_ast_term(term)?),
+                self.from_ast_type(typ)?,
+            ),
+            ASTTerm::TmUnpack(type_name, name, bound, body) => {
+                let bound = self.from_ast_term(bound)?;
+                let body = self.with_binding(type_name, |parser| {
+                    parser.with_binding(name, |parser| parser.from_ast_term(body))
+                })?;
+                Term::TmUnpack(
+                    type_name.clone(),
+                    name.clone(),
+                    Box::new(bound),
+                    Box::new(body),
+                )
+            }
+        };
+
+        Ok(term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        // `x` and `X` are counted in the same context
+        assert_eq!(
+            Parser::new().parse("lambda X.lambda x:X.lambda Y.x;"),
+            Ok(Term::TmTAbs(
+                "X".to_string(),
+                Box::new(Term::TmAbs(
+                    "x".to_string(),
+                    Type::Var(0),
+                    Box::new(Term::TmTAbs("Y".to_string(), Box::new(Term::TmVar(1))))
+                ))
+            ))
+        );
+        assert_eq!(
+            Parser::new().parse("lambda f:All X.X->X.f [Nat] 0;"),
+            Ok(Term::TmAbs(
+                "f".to_string(),
+                Type::All(
+                    "X".to_string(),
+                    Box::new(Type::Arrow(Box::new(Type::Var(0)), Box::new(Type::Var(0))))
+                ),
+                Box::new(Term::TmApp(
+                    Box::new(Term::TmTApp(Box::new(Term::TmVar(0)), Type::Number)),
+                    Box::new(Term::TmZero)
+                ))
+            ))
+        );
+        assert_eq!(
+            Parser::new().parse("lambda x:X.x;"),
+            Err(ParseError::UnboundTypeVariable("X".to_string()))
+        );
+    }
+    #[test]
+    fn test_words() {
+        let parse = |input: &str| {
+            Parser::new()
+                .parse(input)
+                .map_err(|error| error.to_string())
+        };
+        // names are whole words of any length, keywords are not split off them
+        assert_eq!(
+            parse("lambda Elem.lambda item:Elem.item;"),
+            Ok(Term::TmTAbs(
+                "Elem".to_string(),
+                Box::new(Term::TmAbs(
+                    "item".to_string(),
+                    Type::Var(0),
+                    Box::new(Term::TmVar(0))
+                ))
+            ))
+        );
+        assert_eq!(
+            parse("let {Counter, counter} = {*Nat, {get=0}} as {Some Counter, {get:Counter}} in counter.get;"),
+            Ok(Term::TmUnpack(
+                "Counter".to_string(),
+                "counter".to_string(),
+                Box::new(Term::TmPack(
+                    Type::Number,
+                    Box::new(Term::TmRecord(vec![("get".to_string(), Term::TmZero)])),
+                    Type::Some(
+                        "Counter".to_string(),
+                        Box::new(Type::Record(vec![("get".to_string(), Type::Var(0))]))
+                    )
+                )),
+                Box::new(Term::TmProj(Box::new(Term::TmVar(0)), "get".to_string()))
+            ))
+        );
+        assert_eq!(
+            parse("lambda as:Nat.as;"),
+            Err("1:8: expected a variable, found `as`".to_string())
+        );
+        assert_eq!(
+            parse("{*Nat, 0} Nat;"),
+            Err("1:11: expected `as`, found `Nat`".to_string())
+        );
+        assert_eq!(
+            parse("lambda f:Nat->Nat.f [Nat;"),
+            Err("1:25: expected `]`, found `;`".to_string())
+        );
+        // nesting is bounded instead of overflowing the stack
+        let deep = format!("{}0{};", "(".repeat(300), ")".repeat(300));
+        assert!(parse(&deep)
+            .unwrap_err()
+            .contains("at most 256 nested terms"));
+        let types = format!("lambda f:Nat.f{};", " [Nat]".repeat(300));
+        assert!(parse(&types)
+            .unwrap_err()
+            .contains("at most 256 nested terms"));
+    }
+}
@@ -0,0 +1,165 @@
+use tapl_frontend::lexer::{Lexer, SyntaxError, Token};
+
+pub const TYPE_KEYWORDS: [&str; 5] = ["Bool", "Nat", "All", "Forall", "Some"];
+
+// Types as written, type variables are resolved to de Bruijn indices by the
+// parser together with term variables
+#[derive(Clone, Debug, PartialEq)]
+pub enum ASTType {
+    Boolean,
+    Number,
+    Var(String),
+    Arrow(Box<ASTType>, Box<ASTType>),
+    // `All X.T`
+    All(String, Box<ASTType>),
+    // `{Some X, T}`
+    Some(String, Box<ASTType>),
+    // `{l1:T1, l2:T2}`
+    Record(Vec<(String, ASTType)>),
+}
+
+fn is_type_var(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_uppercase()) && !TYPE_KEYWORDS.contains(&word)
+}
+
+// type variables are capitalized words
+pub fn parse_type_var(lexer: &mut Lexer) -> Result<String, SyntaxError> {
+    match lexer.peek()? {
+        (Token::Word(word), _) if is_type_var(word) => {
+            lexer.next_token()?;
+            Ok(word.to_string())
+        }
+        _ => Err(lexer.unexpected("a type variable")),
+    }
+}
+
+// `X, T}` after `{Some`
+fn parse_existential_type(lexer: &mut Lexer) -> Result<ASTType, SyntaxError> {
+    let name = parse_type_var(lexer)?;
+    lexer.expect_symbol(",")?;
+    let body = parse_type(lexer)?;
+    lexer.expect_symbol("}")?;
+    Ok(ASTType::Some(name, Box::new(body)))
+}
+
+// `{l:T, ...}` after the `{`
+fn parse_record_type(lexer: &mut Lexer) -> Result<ASTType, SyntaxError> {
+    let mut fields = vec![];
+    if lexer.eat_symbol("}")?.is_none() {
+        loop {
+            let (label, _) = lexer.expect_word("a label", &[])?;
+            lexer.expect_symbol(":")?;
+            fields.push((label.to_string(), parse_type(lexer)?));
+            if lexer.eat_symbol(",")?.is_none() {
+                break;
+            }
+        }
+        lexer.expect_symbol("}")?;
+    }
+    Ok(ASTType::Record(fields))
+}
+
+fn parse_atom_type(lexer: &mut Lexer) -> Result<ASTType, SyntaxError> {
+    lexer.nested(|lexer| {
+        let (token, span) = lexer.next_token()?;
+        match token {
+            Token::Word("Bool") => Ok(ASTType::Boolean),
+            Token::Word("Nat") => Ok(ASTType::Number),
+            Token::Word(word) if is_type_var(word) => Ok(ASTType::Var(word.to_string())),
+            Token::Symbol("{") => match lexer.eat_keyword("Some")? {
+                Some(_) => parse_existential_type(lexer),
+                None => parse_record_type(lexer),
+            },
+            Token::Symbol("(") => {
+                let typ = parse_type(lexer)?;
+                lexer.expect_symbol(")")?;
+                Ok(typ)
+            }
+            _ => Err(lexer.error_at("a type", &token.to_string(), span)),
+        }
+    })
+}
+
+// `All X.T`, also written `Forall X.T`, extends as far right as possible and
+// arrow types are right associative: `A->B->C` is `A->(B->C)`
+pub fn parse_type(lexer: &mut Lexer) -> Result<ASTType, SyntaxError> {
+    lexer.nested(|lexer| {
+        if lexer.eat_keyword("All")?.is_some() || lexer.eat_keyword("Forall")?.is_some() {
+            let name = parse_type_var(lexer)?;
+            lexer.expect_symbol(".")?;
+            return Ok(ASTType::All(name, Box::new(parse_type(lexer)?)));
+        }
+        let typ = parse_atom_type(lexer)?;
+        match lexer.eat_symbol("->")? {
+            Some(_) => Ok(ASTType::Arrow(Box::new(typ), Box::new(parse_type(lexer)?))),
+            None => Ok(typ),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SYMBOLS;
+
+    fn parse(input: &str) -> Result<(&str, ASTType), String> {
+        let mut lexer = Lexer::new(input, &SYMBOLS);
+        parse_type(&mut lexer)
+            .map(|typ| (lexer.rest(), typ))
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn test_type() {
+        let var = |name: &str| Box::new(ASTType::Var(name.to_string()));
+        assert_eq!(
+            parse("All X.X->X"),
+            Ok((
+                "",
+                ASTType::All(
+                    "X".to_string(),
+                    Box::new(ASTType::Arrow(var("X"), var("X")))
+                )
+            ))
+        );
+        assert_eq!(
+            parse("(All X.X)->Nat"),
+            Ok((
+                "",
+                ASTType::Arrow(
+                    Box::new(ASTType::All("X".to_string(), var("X"))),
+                    Box::new(ASTType::Number)
+                )
+            ))
+        );
+        assert_eq!(
+            parse("{Some X, {new:X, get:X->Nat}}"),
+            Ok((
+                "",
+                ASTType::Some(
+                    "X".to_string(),
+                    Box::new(ASTType::Record(vec![
+                        ("new".to_string(), ASTType::Var("X".to_string())),
+                        (
+                            "get".to_string(),
+                            ASTType::Arrow(var("X"), Box::new(ASTType::Number))
+                        )
+                    ]))
+                )
+            ))
+        );
+        // type names are whole words
+        assert_eq!(
+            parse("Forall Elem.Elem"),
+            Ok(("", ASTType::All("Elem".to_string(), var("Elem"))))
+        );
+        assert_eq!(
+            parse("All Nat.Nat").unwrap_err(),
+            "1:5: expected a type variable, found `Nat`"
+        );
+        assert_eq!(
+            parse("{Some X X}").unwrap_err(),
+            "1:9: expected `,`, found `X`"
+        );
+    }
+}
@@ -0,0 +1,402 @@
+use std::fmt::{self, Formatter};
+
+use misc::Notation;
+
+use crate::{
+    context::{Binding, Context},
+    parser::Term,
+    substitute::{type_shift, type_substitute_top},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeError {
+    ParameterTypeMismatch,
+    ArrowTypeExpected,
+    UniversalTypeExpected,
+    ExistentialTypeExpected,
+    // the implementation does not have the package type with the witness
+    PackTypeMismatch,
+    // the body of an unpack has a type mentioning the hidden type variable
+    TypeVariableEscapes(String),
+    RecordTypeExpected,
+    LabelNotFound(String),
+    NumberExpected,
+    IfConditionNotBoolean,
+    IfBranchesMismatch,
+    UnboundVariable(usize),
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeError::ParameterTypeMismatch => write!(f, "parameter type mismatch"),
+            TypeError::ArrowTypeExpected => write!(f, "arrow type expected"),
+            TypeError::UniversalTypeExpected => write!(f, "universal type expected"),
+            TypeError::ExistentialTypeExpected => write!(f, "existential type expected"),
+            TypeError::PackTypeMismatch => {
+                write!(f, "package implementation does not match the declared type")
+            }
+            TypeError::TypeVariableEscapes(name) => {
+                write!(f, "type variable {} escapes from its scope", name)
+            }
+            TypeError::RecordTypeExpected => write!(f, "record type expected"),
+            TypeError::LabelNotFound(label) => write!(f, "label {} not found", label),
+            TypeError::NumberExpected => write!(f, "argument is not a number"),
+            TypeError::IfConditionNotBoolean => write!(f, "guard of conditional not a boolean"),
+            TypeError::IfBranchesMismatch => {
+                write!(f, "arms of conditional have different types")
+            }
+            TypeError::UnboundVariable(index) => write!(f, "unbound variable index {}", index),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Type {
+    Boolean,
+    Number,
+    // type variable DeBrujin index, counted in the same context as terms
+    Var(usize),
+    Arrow(Box<Type>, Box<Type>),
+    // bound name, only kept for printing, and body
+    All(String, Box<Type>),
+    // an abstract type X and the interface using it (TAPL 24.1)
+    Some(String, Box<Type>),
+    Record(Vec<(String, Type)>),
+}
+
+// de Bruijn indices make alpha-equivalent types equal, the names of `All`
+// binders are ignored
+impl PartialEq for Type {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Type::Boolean, Type::Boolean) | (Type::Number, Type::Number) => true,
+            (Type::Var(x), Type::Var(y)) => x == y,
+            (Type::Arrow(s1, s2), Type::Arrow(t1, t2)) => s1 == t1 && s2 == t2,
+            (Type::All(_, s), Type::All(_, t)) | (Type::Some(_, s), Type::Some(_, t)) => s == t,
+            (Type::Record(s), Type::Record(t)) => s == t,
+            _ => false,
+        }
+    }
+}
+
+impl Type {
+    pub fn display(&self, notation: Notation) -> TypeDisplay<'_> {
+        TypeDisplay {
+            typ: self,
+            notation,
+        }
+    }
+}
+
+// Prints types in the syntax the parser accepts, type variables get the
+// name of their binder back
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.display(Notation::Ascii).fmt(f)
+    }
+}
+
+// A variable bound outside the printed type shows as its index `#1`
+pub struct TypeDisplay<'a> {
+    typ: &'a Type,
+    notation: Notation,
+}
+
+impl fmt::Display for TypeDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_type(f, self.typ, self.notation, &mut vec![], false)
+    }
+}
+
+// a binder shadowing an enclosing one of the same name gets a number, so
+// that `All Y.All Y.Y` with the outer `Y` in the body prints as
+// `Forall Y.Forall Y1.Y`
+fn fresh_name(name: &str, names: &[String]) -> String {
+    let mut fresh = name.to_string();
+    let mut suffix = 0;
+    while names.contains(&fresh) {
+        suffix += 1;
+        fresh = format!("{}{}", name, suffix);
+    }
+    fresh
+}
+
+// `All` extends as far right as possible, so it is parenthesized on the left
+// of an arrow
+fn write_type(
+    f: &mut Formatter<'_>,
+    typ: &Type,
+    notation: Notation,
+    names: &mut Vec<String>,
+    left_of_arrow: bool,
+) -> fmt::Result {
+    match typ {
+        Type::Boolean => write!(f, "Bool"),
+        Type::Number => write!(f, "Nat"),
+        Type::Var(index) => match names.iter().rev().nth(*index) {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "#{}", index),
+        },
+        Type::Arrow(param, ret) => {
+            if left_of_arrow {
+                write!(f, "(")?;
+            }
+            write_type(f, param, notation, names, true)?;
+            write!(f, "{}", notation.arrow())?;
+            write_type(f, ret, notation, names, false)?;
+            if left_of_arrow {
+                write!(f, ")")?;
+            }
+            Ok(())
+        }
+        Type::All(name, body) => {
+            let name = fresh_name(name, names);
+            if left_of_arrow {
+                write!(f, "(")?;
+            }
+            write!(f, "{}{}.", notation.forall(), name)?;
+            names.push(name);
+            let result = write_type(f, body, notation, names, false);
+            names.pop();
+            result?;
+            if left_of_arrow {
+                write!(f, ")")?;
+            }
+            Ok(())
+        }
+        Type::Some(name, body) => {
+            let name = fresh_name(name, names);
+            write!(f, "{{Some {}, ", name)?;
+            names.push(name);
+            let result = write_type(f, body, notation, names, false);
+            names.pop();
+            result?;
+            write!(f, "}}")
+        }
+        Type::Record(fields) => {
+            write!(f, "{{")?;
+            for (i, (label, typ)) in fields.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}:", label)?;
+                write_type(f, typ, notation, names, false)?;
+            }
+            write!(f, "}}")
+        }
+    }
+}
+
+fn expect_number(typ: Type) -> Result<(), TypeError> {
+    match typ {
+        Type::Number => Ok(()),
+        _ => Err(TypeError::NumberExpected),
+    }
+}
+
+fn mentions_type_var(typ: &Type, index: usize) -> bool {
+    match typ {
+        Type::Boolean | Type::Number => false,
+        Type::Var(i) => *i == index,
+        Type::Arrow(param, ret) => mentions_type_var(param, index) || mentions_type_var(ret, index),
+        Type::All(_, body) | Type::Some(_, body) => mentions_type_var(body, index + 1),
+        Type::Record(fields) => fields.iter().any(|(_, typ)| mentions_type_var(typ, index)),
+    }
+}
+
+// the type of a term variable, shifted from its binding into the current context
+fn get_type(ctx: &Context, index: usize) -> Result<Type, TypeError> {
+    match ctx.get_binding(index) {
+        Some(Binding::Var(typ)) => Ok(type_shift(typ, index as isize + 1, 0)),
+        _ => Err(TypeError::UnboundVariable(index)),
+    }
+}
+
+pub fn type_of(ctx: &mut Context, term: &Term) -> Result<Type, TypeError> {
+    match term {
+        Term::TmTrue | Term::TmFalse => Ok(Type::Boolean),
+        Term::TmZero => Ok(Type::Number),
+        Term::TmSucc(term) | Term::TmPred(term) => {
+            expect_number(type_of(ctx, term)?)?;
+            Ok(Type::Number)
+        }
+        Term::TmIsZero(term) => {
+            expect_number(type_of(ctx, term)?)?;
+            Ok(Type::Boolean)
+        }
+        Term::TmVar(index) => get_type(ctx, *index),
+        Term::TmAbs(name, typ, body) => {
+            ctx.push(name.clone(), Binding::Var(typ.clone()));
+            let body_type = type_of(ctx, body);
+            ctx.pop();
+            Ok(Type::Arrow(
+                Box::new(typ.clone()),
+                Box::new(type_shift(&body_type?, -1, 0)),
+            ))
+        }
+        Term::TmApp(left, right) => {
+            let left_type = type_of(ctx, left)?;
+            let right_type = type_of(ctx, right)?;
+            match left_type {
+                Type::Arrow(param_type, return_type) if *param_type == right_type => {
+                    Ok(*return_type)
+                }
+                Type::Arrow(_, _) => Err(TypeError::ParameterTypeMismatch),
+                _ => Err(TypeError::ArrowTypeExpected),
+            }
+        }
+        Term::TmIf(if_term, then_term, else_term) => {
+            if type_of(ctx, if_term)? != Type::Boolean {
+                return Err(TypeError::IfConditionNotBoolean);
+            }
+            let then_type = type_of(ctx, then_term)?;
+            if then_type == type_of(ctx, else_term)? {
+                Ok(then_type)
+            } else {
+                Err(TypeError::IfBranchesMismatch)
+            }
+        }
+        Term::TmLet(name, bound, body) => {
+            let bound_type = type_of(ctx, bound)?;
+            ctx.push(name.clone(), Binding::Var(bound_type));
+            let body_type = type_of(ctx, body);
+            ctx.pop();
+            Ok(type_shift(&body_type?, -1, 0))
+        }
+        // T-TAbs
+        Term::TmTAbs(name, body) => {
+            ctx.push(name.clone(), Binding::TypeVar);
+            let body_type = type_of(ctx, body);
+            ctx.pop();
+            Ok(Type::All(name.clone(), Box::new(body_type?)))
+        }
+        // T-TApp
+        Term::TmTApp(term, typ) => match type_of(ctx, term)? {
+            Type::All(_, body) => Ok(type_substitute_top(typ, &body)),
+            _ => Err(TypeError::UniversalTypeExpected),
+        },
+        Term::TmRecord(fields) => Ok(Type::Record(
+            fields
+                .iter()
+                .map(|(label, term)| Ok((label.clone(), type_of(ctx, term)?)))
+                .collect::<Result<Vec<_>, TypeError>>()?,
+        )),
+        Term::TmProj(term, label) => match type_of(ctx, term)? {
+            Type::Record(fields) => fields
+                .into_iter()
+                .find(|(name, _)| name == label)
+                .map(|(_, typ)| typ)
+                .ok_or_else(|| TypeError::LabelNotFound(label.clone())),
+            _ => Err(TypeError::RecordTypeExpected),
+        },
+        // T-Pack: the implementation has the interface with X replaced by the witness
+        Term::TmPack(witness, term, typ) => match typ {
+            Type::Some(_, body) => {
+                if type_of(ctx, term)? == type_substitute_top(witness, body) {
+                    Ok(typ.clone())
+                } else {
+                    Err(TypeError::PackTypeMismatch)
+                }
+            }
+            _ => Err(TypeError::ExistentialTypeExpected),
+        },
+        // T-Unpack: the body only sees the interface, never the witness
+        Term::TmUnpack(type_name, name, bound, body) => match type_of(ctx, bound)? {
+            Type::Some(_, interface) => {
+                ctx.push(type_name.clone(), Binding::TypeVar);
+                ctx.push(name.clone(), Binding::Var(*interface));
+                let body_type = type_of(ctx, body);
+                ctx.pop();
+                ctx.pop();
+                let body_type = body_type?;
+                // index 0 is the term variable, which cannot occur in a type
+                if mentions_type_var(&body_type, 1) {
+                    return Err(TypeError::TypeVariableEscapes(type_name.clone()));
+                }
+                Ok(type_shift(&body_type, -2, 0))
+            }
+            _ => Err(TypeError::ExistentialTypeExpected),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{language::SystemF, parser::Parser};
+    use tapl_testkit::assert_type_error;
+
+    fn type_str(input: &str) -> Result<Type, TypeError> {
+        let term = Parser::new().parse(input).unwrap();
+        type_of(&mut Context::default(), &term)
+    }
+
+    #[test]
+    fn test_type_of() {
+        let var = |index| Box::new(Type::Var(index));
+        let id_type = Type::All("X".to_string(), Box::new(Type::Arrow(var(0), var(0))));
+        assert_eq!(type_str("lambda X.lambda x:X.x;"), Ok(id_type.clone()));
+        assert_eq!(
+            type_str("(lambda X.lambda x:X.x) [Nat];"),
+            Ok(Type::Arrow(Box::new(Type::Number), Box::new(Type::Number)))
+        );
+        // instantiating with a universal type, the bound names do not matter
+        assert_eq!(
+            type_str("(lambda X.lambda x:X.x) [All Y.Y->Y] (lambda Z.lambda z:Z.z);"),
+            Ok(id_type)
+        );
+        // a variable bound outside keeps pointing past the inner binder
+        assert_eq!(
+            type_str("lambda Y.(lambda X.lambda Y.lambda x:X.x) [Y];"),
+            Ok(Type::All(
+                "Y".to_string(),
+                Box::new(Type::All(
+                    "Y".to_string(),
+                    Box::new(Type::Arrow(var(1), var(1)))
+                ))
+            ))
+        );
+        assert_type_error!(
+            SystemF,
+            "(lambda x:Nat.x) [Nat];",
+            "universal type expected"
+        );
+        assert_type_error!(
+            SystemF,
+            "(lambda X.lambda x:X.x) [Bool] 0;",
+            "parameter type mismatch"
+        );
+    }
+    #[test]
+    fn test_display() {
+        let display = |input: &str| type_str(input).unwrap().to_string();
+        assert_eq!(display("lambda X.lambda x:X.x;"), "Forall X.X->X");
+        assert_eq!(
+            display("lambda f:(All X.X->X)->Nat.f;"),
+            "((Forall X.X->X)->Nat)->(Forall X.X->X)->Nat"
+        );
+        // the inner binder is renamed instead of capturing the outer `Y`
+        assert_eq!(
+            display("lambda Y.(lambda X.lambda Y.lambda x:X.x) [Y];"),
+            "Forall Y.Forall Y1.Y->Y"
+        );
+        assert_eq!(
+            display("{*Nat, {get=0}} as {Some C, {get:C}};"),
+            "{Some C, {get:C}}"
+        );
+        assert_eq!(
+            type_str("lambda X.lambda x:X.x;")
+                .unwrap()
+                .display(Notation::Unicode)
+                .to_string(),
+            "∀X.X→X"
+        );
+        // printed types parse back to the same type
+        let typ = type_str("lambda Y.(lambda X.lambda Y.lambda x:X.x) [Y];").unwrap();
+        assert_eq!(
+            type_str(&format!("lambda f:{}.f;", typ)),
+            Ok(Type::Arrow(Box::new(typ.clone()), Box::new(typ)))
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use tapl_frontend::outcome::Outcome;
+
+use crate::{
+    parser::Term,
+    substitute::{substitute_top, type_term_substitute_top},
+};
+
+fn is_numeric_val(term: &Term) -> bool {
+    match term {
+        Term::TmZero => true,
+        Term::TmSucc(term) => is_numeric_val(term),
+        _ => false,
+    }
+}
+
+pub fn is_val(term: &Term) -> bool {
+    match term {
+        Term::TmTrue | Term::TmFalse | Term::TmAbs(_, _, _) | Term::TmTAbs(_, _, _) => true,
+        Term::TmRecord(fields) => fields.iter().all(|(_, term)| is_val(term)),
+        _ => is_numeric_val(term),
+    }
+}
+
+pub fn eval1(term: &Term) -> Option<Term> {
+    let step = |term: &Term| eval1(term).map(Box::new);
+    match term {
+        Term::TmSucc(term) => Some(Term::TmSucc(step(term)?)),
+        Term::TmPred(term) => match term.as_ref() {
+            Term::TmZero => Some(Term::TmZero),
+            Term::TmSucc(number) if is_numeric_val(number) => Some(number.as_ref().clone()),
+            _ => Some(Term::TmPred(step(term)?)),
+        },
+        Term::TmIsZero(term) => match term.as_ref() {
+            Term::TmZero => Some(Term::TmTrue),
+            Term::TmSucc(number) if is_numeric_val(number) => Some(Term::TmFalse),
+            _ => Some(Term::TmIsZero(step(term)?)),
+        },
+        Term::TmApp(left, right) => match left.as_ref() {
+            Term::TmAbs(_, _, body) if is_val(right) => Some(substitute_top(right, body)),
+            _ if is_val(left) => Some(Term::TmApp(left.clone(), step(right)?)),
+            _ => Some(Term::TmApp(step(left)?, right.clone())),
+        },
+        Term::TmIf(if_term, then_term, else_term) => match if_term.as_ref() {
+            Term::TmTrue => Some(then_term.as_ref().clone()),
+            Term::TmFalse => Some(else_term.as_ref().clone()),
+            _ => Some(Term::TmIf(
+                step(if_term)?,
+                then_term.clone(),
+                else_term.clone(),
+            )),
+        },
+        Term::TmLet(name, bound, body) => {
+            if is_val(bound) {
+                Some(substitute_top(bound, body))
+            } else {
+                Some(Term::TmLet(name.clone(), step(bound)?, body.clone()))
+            }
+        }
+        Term::TmTApp(term, typ) => match term.as_ref() {
+            // E-TappTabs
+            Term::TmTAbs(_, _, body) => Some(type_term_substitute_top(typ, body)),
+            // E-TApp
+            _ => Some(Term::TmTApp(step(term)?, typ.clone())),
+        },
+        Term::TmRecord(fields) => match fields.iter().position(|(_, term)| !is_val(term)) {
+            // evaluate the fields from left to right
+            Some(i) => {
+                let mut fields = fields.clone();
+                fields[i].1 = eval1(&fields[i].1)?;
+                Some(Term::TmRecord(fields))
+            }
+            None => None,
+        },
+        Term::TmProj(term, label) => match term.as_ref() {
+            Term::TmRecord(fields) if is_val(term) => fields
+                .iter()
+                .find(|(name, _)| name == label)
+                .map(|(_, term)| term.clone()),
+            _ => Some(Term::TmProj(step(term)?, label.clone())),
+        },
+        _ => None,
+    }
+}
+
+// takes small steps until no rule applies, a normal form that is not a
+// value is stuck
+pub fn eval(term: &Term) -> Outcome<Term, Term> {
+    let mut term = term.clone();
+    while let Some(next_term) = eval1(&term) {
+        term = next_term;
+    }
+    if is_val(&term) {
+        Outcome::Value(term)
+    } else {
+        Outcome::Stuck(term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::BoundedQuantification;
+    use crate::parser::Parser;
+    use tapl_testkit::{assert_evals_to, assert_step_trace};
+
+    #[test]
+    fn test_eval() {
+        assert_evals_to!(
+            BoundedQuantification,
+            "((lambda X<:{a:Nat}.lambda x:X.x) [{a:Nat, b:Bool}] {a=0, b=true}).b;",
+            "true"
+        );
+        // a polymorphic function that updates a field keeps the rest of the record type
+        assert_evals_to!(
+            BoundedQuantification,
+            "let f = lambda X<:{a:Nat}.lambda x:X.lambda g:X->X.g x in \
+             let r = {a=0, b=true} in \
+             (f [{a:Nat, b:Bool}] r (lambda y:{a:Nat, b:Bool}.{a=succ(y.a), b=false})).a;",
+            "succ(0)"
+        );
+    }
+
+    #[test]
+    fn test_eval1() {
+        assert_step_trace!(
+            BoundedQuantification,
+            "(lambda X<:Nat.lambda x:X.x) [Nat] 0;",
+            ["(lambda x:Nat.x) 0", "0"]
+        );
+        // the fields of a record are evaluated left to right before projection
+        assert_step_trace!(
+            BoundedQuantification,
+            "{a=pred(succ(0)), b=iszero(0)}.b;",
+            ["{a=0, b=iszero(0)}.b", "{a=0, b=true}.b", "true"]
+        );
+    }
+
+    #[test]
+    fn test_eval_conditional() {
+        assert_evals_to!(
+            BoundedQuantification,
+            "let x = succ(0) in if iszero(pred(x)) then {a=x} else {a=0};",
+            "{a=succ(0)}"
+        );
+        // a type application under a lambda is not reduced
+        assert_evals_to!(
+            BoundedQuantification,
+            "lambda x:Nat.(lambda X.lambda y:X.y) [Nat] x;",
+            "lambda x:Nat.(lambda X.lambda y:X.y) [Nat] x"
+        );
+    }
+    #[test]
+    fn test_eval_outcome() {
+        let term = |input: &str| Parser::new().parse(input).unwrap();
+        assert_eq!(
+            eval(&term("iszero(succ(0));")),
+            Outcome::Value(Term::TmFalse)
+        );
+        // an ill-typed term stops at a normal form that is not a value
+        let stuck = term("if 0 then true else false;");
+        assert_eq!(eval(&stuck), Outcome::Stuck(stuck));
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{
+    context::{Binding, Context},
+    substitute::type_shift,
+    typing::Type,
+};
+
+// the upper bound of a type variable, shifted into the current context
+fn bound_of(ctx: &Context, index: usize) -> Option<Type> {
+    match ctx.get_binding(index) {
+        Some(Binding::TypeVar(bound)) => Some(type_shift(bound, index as isize + 1, 0)),
+        _ => None,
+    }
+}
+
+// Replaces a type variable by its bound until the type has its own shape,
+// the least non-variable supertype used by the algorithmic typing rules
+// (TAPL 28.1)
+pub fn expose(ctx: &Context, typ: &Type) -> Type {
+    match typ {
+        Type::Var(index) => match bound_of(ctx, *index) {
+            Some(bound) => expose(ctx, &bound),
+            None => typ.clone(),
+        },
+        _ => typ.clone(),
+    }
+}
+
+// Algorithmic subtyping of kernel F<: (TAPL 28.3), the bounds of two
+// quantifiers have to be the same
+pub fn subtype(ctx: &mut Context, s: &Type, t: &Type) -> bool {
+    if s == t {
+        return true;
+    }
+    match (s, t) {
+        (_, Type::Top) => true,
+        // SA-Trans-TVar
+        (Type::Var(index), _) => match bound_of(ctx, *index) {
+            Some(bound) => subtype(ctx, &bound, t),
+            None => false,
+        },
+        (Type::Arrow(s1, s2), Type::Arrow(t1, t2)) => subtype(ctx, t1, s1) && subtype(ctx, s2, t2),
+        (Type::Record(s_fields), Type::Record(t_fields)) => {
+            t_fields.iter().all(|(label, t_type)| {
+                s_fields
+                    .iter()
+                    .find(|(name, _)| name == label)
+                    .is_some_and(|(_, s_type)| subtype(ctx, s_type, t_type))
+            })
+        }
+        // SA-All, the bodies are compared under the shared bound
+        (Type::All(_, s_bound, s_body), Type::All(name, t_bound, t_body)) if s_bound == t_bound => {
+            ctx.push(name.clone(), Binding::TypeVar(t_bound.as_ref().clone()));
+            let result = subtype(ctx, s_body, t_body);
+            ctx.pop();
+            result
+        }
+        _ => false,
+    }
+}
+
+// An upper bound of both types. Records keep their common fields, arrows
+// with the same parameter join their results, anything else goes to Top.
+pub fn join(ctx: &mut Context, s: &Type, t: &Type) -> Type {
+    if subtype(ctx, s, t) {
+        return t.clone();
+    }
+    if subtype(ctx, t, s) {
+        return s.clone();
+    }
+    match (expose(ctx, s), expose(ctx, t)) {
+        (Type::Record(s_fields), Type::Record(t_fields)) => Type::Record(
+            s_fields
+                .iter()
+                .filter_map(|(label, s_type)| {
+                    let (_, t_type) = t_fields.iter().find(|(name, _)| name == label)?;
+                    Some((label.clone(), join(ctx, s_type, t_type)))
+                })
+                .collect(),
+        ),
+        (Type::Arrow(s1, s2), Type::Arrow(t1, t2)) if s1 == t1 => {
+            Type::Arrow(s1, Box::new(join(ctx, &s2, &t2)))
+        }
+        _ => Type::Top,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(fields: &[(&str, Type)]) -> Type {
+        Type::Record(
+            fields
+                .iter()
+                .map(|(label, typ)| (label.to_string(), typ.clone()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_subtype() {
+        let a = record(&[("a", Type::Number)]);
+        let ab = record(&[("a", Type::Number), ("b", Type::Boolean)]);
+        let mut ctx = Context::default();
+        assert!(subtype(&mut ctx, &ab, &a));
+        assert!(!subtype(&mut ctx, &a, &ab));
+        // X<:{a:Nat, b:Bool} is below {a:Nat}, but not the other way round
+        ctx.push("X".to_string(), Binding::TypeVar(ab.clone()));
+        assert!(subtype(&mut ctx, &Type::Var(0), &a));
+        assert!(!subtype(&mut ctx, &a, &Type::Var(0)));
+        assert_eq!(expose(&ctx, &Type::Var(0)), ab);
+        ctx.pop();
+        let all = |bound: &Type, ret: Type| {
+            Type::All(
+                "X".to_string(),
+                Box::new(bound.clone()),
+                Box::new(Type::Arrow(Box::new(Type::Var(0)), Box::new(ret))),
+            )
+        };
+        assert!(subtype(
+            &mut ctx,
+            &all(&a, Type::Var(0)),
+            &all(&a, Type::Top)
+        ));
+        // kernel F<: compares quantifiers with the same bound only
+        assert!(!subtype(
+            &mut ctx,
+            &all(&Type::Top, Type::Var(0)),
+            &all(&a, Type::Var(0))
+        ));
+        assert_eq!(
+            join(
+                &mut ctx,
+                &ab,
+                &record(&[("b", Type::Boolean), ("c", Type::Number)])
+            ),
+            record(&[("b", Type::Boolean)])
+        );
+    }
+}
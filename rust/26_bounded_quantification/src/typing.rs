@@ -0,0 +1,469 @@
+use std::fmt::{self, Formatter};
+
+use misc::Notation;
+
+use crate::{
+    context::{Binding, Context},
+    parser::Term,
+    substitute::{type_shift, type_substitute_top},
+    subtype::{expose, join, subtype},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeError {
+    ParameterTypeMismatch,
+    ArrowTypeExpected,
+    UniversalTypeExpected,
+    // the type argument is not below the bound of the type variable
+    TypeArgumentOutOfBounds,
+    RecordTypeExpected,
+    LabelNotFound(String),
+    NumberExpected,
+    IfConditionNotBoolean,
+    UnboundVariable(usize),
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeError::ParameterTypeMismatch => write!(f, "parameter type mismatch"),
+            TypeError::ArrowTypeExpected => write!(f, "arrow type expected"),
+            TypeError::UniversalTypeExpected => write!(f, "universal type expected"),
+            TypeError::TypeArgumentOutOfBounds => {
+                write!(f, "type argument is not a subtype of the bound")
+            }
+            TypeError::RecordTypeExpected => write!(f, "record type expected"),
+            TypeError::LabelNotFound(label) => write!(f, "label {} not found", label),
+            TypeError::NumberExpected => write!(f, "argument is not a number"),
+            TypeError::IfConditionNotBoolean => write!(f, "guard of conditional not a boolean"),
+            TypeError::UnboundVariable(index) => write!(f, "unbound variable index {}", index),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Type {
+    Top,
+    Boolean,
+    Number,
+    // type variable DeBrujin index, counted in the same context as terms
+    Var(usize),
+    Arrow(Box<Type>, Box<Type>),
+    // bound name, only kept for printing, its upper bound and body
+    All(String, Box<Type>, Box<Type>),
+    Record(Vec<(String, Type)>),
+}
+
+// de Bruijn indices make alpha-equivalent types equal, the names of `All`
+// binders are ignored
+impl PartialEq for Type {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Type::Top, Type::Top)
+            | (Type::Boolean, Type::Boolean)
+            | (Type::Number, Type::Number) => true,
+            (Type::Var(x), Type::Var(y)) => x == y,
+            (Type::Arrow(s1, s2), Type::Arrow(t1, t2))
+            | (Type::All(_, s1, s2), Type::All(_, t1, t2)) => s1 == t1 && s2 == t2,
+            (Type::Record(s), Type::Record(t)) => s == t,
+            _ => false,
+        }
+    }
+}
+
+impl Type {
+    pub fn display(&self, notation: Notation) -> TypeDisplay<'_> {
+        TypeDisplay {
+            typ: self,
+            notation,
+        }
+    }
+}
+
+// Prints types in the syntax the parser accepts, type variables get the
+// name of their binder back
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.display(Notation::Ascii).fmt(f)
+    }
+}
+
+// A variable bound outside the printed type shows as its index `#1`
+pub struct TypeDisplay<'a> {
+    typ: &'a Type,
+    notation: Notation,
+}
+
+impl fmt::Display for TypeDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_type(f, self.typ, self.notation, &mut vec![], false)
+    }
+}
+
+// a binder shadowing an enclosing one of the same name gets a number, so
+// that `All Y.All Y.Y` with the outer `Y` in the body prints as
+// `Forall Y.Forall Y1.Y`
+fn fresh_name(name: &str, names: &[String]) -> String {
+    let mut fresh = name.to_string();
+    let mut suffix = 0;
+    while names.contains(&fresh) {
+        suffix += 1;
+        fresh = format!("{}{}", name, suffix);
+    }
+    fresh
+}
+
+// `All` extends as far right as possible, so it is parenthesized on the left
+// of an arrow. A `Top` bound is left out as the parser does.
+fn write_type(
+    f: &mut Formatter<'_>,
+    typ: &Type,
+    notation: Notation,
+    names: &mut Vec<String>,
+    left_of_arrow: bool,
+) -> fmt::Result {
+    match typ {
+        Type::Top => write!(f, "Top"),
+        Type::Boolean => write!(f, "Bool"),
+        Type::Number => write!(f, "Nat"),
+        Type::Var(index) => match names.iter().rev().nth(*index) {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "#{}", index),
+        },
+        Type::Arrow(param, ret) => {
+            if left_of_arrow {
+                write!(f, "(")?;
+            }
+            write_type(f, param, notation, names, true)?;
+            write!(f, "{}", notation.arrow())?;
+            write_type(f, ret, notation, names, false)?;
+            if left_of_arrow {
+                write!(f, ")")?;
+            }
+            Ok(())
+        }
+        Type::All(name, bound, body) => {
+            let name = fresh_name(name, names);
+            if left_of_arrow {
+                write!(f, "(")?;
+            }
+            write!(f, "{}{}", notation.forall(), name)?;
+            // the bound is outside the scope of its variable
+            if **bound != Type::Top {
+                write!(f, "<:")?;
+                write_type(f, bound, notation, names, false)?;
+            }
+            write!(f, ".")?;
+            names.push(name);
+            let result = write_type(f, body, notation, names, false);
+            names.pop();
+            result?;
+            if left_of_arrow {
+                write!(f, ")")?;
+            }
+            Ok(())
+        }
+        Type::Record(fields) => {
+            write!(f, "{{")?;
+            for (i, (label, typ)) in fields.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}:", label)?;
+                write_type(f, typ, notation, names, false)?;
+            }
+            write!(f, "}}")
+        }
+    }
+}
+
+fn expect_number(ctx: &mut Context, typ: Type) -> Result<(), TypeError> {
+    if subtype(ctx, &typ, &Type::Number) {
+        Ok(())
+    } else {
+        Err(TypeError::NumberExpected)
+    }
+}
+
+// the type of a term variable, shifted from its binding into the current context
+fn get_type(ctx: &Context, index: usize) -> Result<Type, TypeError> {
+    match ctx.get_binding(index) {
+        Some(Binding::Var(typ)) => Ok(type_shift(typ, index as isize + 1, 0)),
+        _ => Err(TypeError::UnboundVariable(index)),
+    }
+}
+
+// Algorithmic typing of F<: (TAPL 28.2): the type of a function, record or
+// type abstraction is exposed before it is taken apart
+pub fn type_of(ctx: &mut Context, term: &Term) -> Result<Type, TypeError> {
+    match term {
+        Term::TmTrue | Term::TmFalse => Ok(Type::Boolean),
+        Term::TmZero => Ok(Type::Number),
+        Term::TmSucc(term) | Term::TmPred(term) => {
+            let typ = type_of(ctx, term)?;
+            expect_number(ctx, typ)?;
+            Ok(Type::Number)
+        }
+        Term::TmIsZero(term) => {
+            let typ = type_of(ctx, term)?;
+            expect_number(ctx, typ)?;
+            Ok(Type::Boolean)
+        }
+        Term::TmVar(index) => get_type(ctx, *index),
+        Term::TmAbs(name, typ, body) => {
+            ctx.push(name.clone(), Binding::Var(typ.clone()));
+            let body_type = type_of(ctx, body);
+            ctx.pop();
+            Ok(Type::Arrow(
+                Box::new(typ.clone()),
+                Box::new(type_shift(&body_type?, -1, 0)),
+            ))
+        }
+        // TA-App
+        Term::TmApp(left, right) => {
+            let left_type = type_of(ctx, left)?;
+            let right_type = type_of(ctx, right)?;
+            match expose(ctx, &left_type) {
+                Type::Arrow(param_type, return_type) => {
+                    if subtype(ctx, &right_type, &param_type) {
+                        Ok(*return_type)
+                    } else {
+                        Err(TypeError::ParameterTypeMismatch)
+                    }
+                }
+                _ => Err(TypeError::ArrowTypeExpected),
+            }
+        }
+        Term::TmIf(if_term, then_term, else_term) => {
+            let condition_type = type_of(ctx, if_term)?;
+            if !subtype(ctx, &condition_type, &Type::Boolean) {
+                return Err(TypeError::IfConditionNotBoolean);
+            }
+            let then_type = type_of(ctx, then_term)?;
+            let else_type = type_of(ctx, else_term)?;
+            Ok(join(ctx, &then_type, &else_type))
+        }
+        Term::TmLet(name, bound, body) => {
+            let bound_type = type_of(ctx, bound)?;
+            ctx.push(name.clone(), Binding::Var(bound_type));
+            let body_type = type_of(ctx, body);
+            ctx.pop();
+            Ok(type_shift(&body_type?, -1, 0))
+        }
+        // TA-TAbs
+        Term::TmTAbs(name, bound, body) => {
+            ctx.push(name.clone(), Binding::TypeVar(bound.clone()));
+            let body_type = type_of(ctx, body);
+            ctx.pop();
+            Ok(Type::All(
+                name.clone(),
+                Box::new(bound.clone()),
+                Box::new(body_type?),
+            ))
+        }
+        // TA-TApp
+        Term::TmTApp(term, typ) => {
+            let term_type = type_of(ctx, term)?;
+            match expose(ctx, &term_type) {
+                Type::All(_, bound, body) => {
+                    if subtype(ctx, typ, &bound) {
+                        Ok(type_substitute_top(typ, &body))
+                    } else {
+                        Err(TypeError::TypeArgumentOutOfBounds)
+                    }
+                }
+                _ => Err(TypeError::UniversalTypeExpected),
+            }
+        }
+        Term::TmRecord(fields) => Ok(Type::Record(
+            fields
+                .iter()
+                .map(|(label, term)| Ok((label.clone(), type_of(ctx, term)?)))
+                .collect::<Result<Vec<_>, TypeError>>()?,
+        )),
+        // TA-Proj
+        Term::TmProj(term, label) => {
+            let term_type = type_of(ctx, term)?;
+            match expose(ctx, &term_type) {
+                Type::Record(fields) => fields
+                    .into_iter()
+                    .find(|(name, _)| name == label)
+                    .map(|(_, typ)| typ)
+                    .ok_or_else(|| TypeError::LabelNotFound(label.clone())),
+                _ => Err(TypeError::RecordTypeExpected),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{language::BoundedQuantification, parser::Parser};
+    use tapl_testkit::assert_type_error;
+
+    fn type_str(input: &str) -> Result<Type, TypeError> {
+        let term = Parser::new().parse(input).unwrap();
+        type_of(&mut Context::default(), &term)
+    }
+
+    #[test]
+    fn test_type_of() {
+        let a = Type::Record(vec![("a".to_string(), Type::Number)]);
+        let ab = Type::Record(vec![
+            ("a".to_string(), Type::Number),
+            ("b".to_string(), Type::Boolean),
+        ]);
+        // subsumption alone forgets the extra field
+        assert_eq!(
+            type_str("(lambda x:{a:Nat}.x) {a=0, b=true};"),
+            Ok(a.clone())
+        );
+        // the bounded identity keeps it (TAPL 26.1)
+        assert_eq!(
+            type_str("(lambda X<:{a:Nat}.lambda x:X.x) [{a:Nat, b:Bool}] {a=0, b=true};"),
+            Ok(ab)
+        );
+        // x:X is used as a record through its bound
+        assert_eq!(
+            type_str("lambda X<:{a:Nat}.lambda x:X.succ(x.a);"),
+            Ok(Type::All(
+                "X".to_string(),
+                Box::new(a),
+                Box::new(Type::Arrow(Box::new(Type::Var(0)), Box::new(Type::Number)))
+            ))
+        );
+        assert_type_error!(
+            BoundedQuantification,
+            "((lambda x:{a:Nat}.x) {a=0, b=true}).b;",
+            "label b not found"
+        );
+        assert_type_error!(
+            BoundedQuantification,
+            "(lambda X<:{a:Nat}.lambda x:X.x) [Nat];",
+            "not a subtype of the bound"
+        );
+        assert_type_error!(
+            BoundedQuantification,
+            "lambda X.lambda x:X.x.a;",
+            "record type expected"
+        );
+    }
+
+    #[test]
+    fn test_type_of_subsumption() {
+        let a = Type::Record(vec![("a".to_string(), Type::Number)]);
+        // the branches of a conditional are joined
+        assert_eq!(
+            type_str("if true then {a=0, b=true} else {a=succ(0), c=false};"),
+            Ok(a.clone())
+        );
+        assert_eq!(type_str("if false then {a=0} else true;"), Ok(Type::Top));
+        // a variable bounded by a function type can be applied
+        assert_eq!(
+            type_str("lambda X<:Nat->Nat.lambda f:X.f 0;"),
+            Ok(Type::All(
+                "X".to_string(),
+                Box::new(Type::Arrow(Box::new(Type::Number), Box::new(Type::Number))),
+                Box::new(Type::Arrow(Box::new(Type::Var(0)), Box::new(Type::Number)))
+            ))
+        );
+        // and one bounded by a quantifier can be instantiated
+        assert_eq!(
+            type_str("lambda X<:(All Y.Y->Y).lambda f:X.f [Nat] 0;").map(|_| ()),
+            Ok(())
+        );
+        // a bound of Nat lets x:X be used as a number
+        assert_eq!(
+            type_str("(lambda X<:Nat.lambda x:X.iszero(pred(x))) [Nat] 0;"),
+            Ok(Type::Boolean)
+        );
+        assert_eq!(
+            type_str("let r = {a=0, b=true} in (lambda x:{a:Nat}.x.a) r;"),
+            Ok(Type::Number)
+        );
+    }
+
+    #[test]
+    fn test_type_errors() {
+        assert_type_error!(
+            BoundedQuantification,
+            "(lambda x:{a:Nat, b:Bool}.x) {a=0};",
+            "parameter type mismatch"
+        );
+        assert_type_error!(BoundedQuantification, "0 0;", "arrow type expected");
+        // a variable bounded by Top cannot be applied
+        assert_type_error!(
+            BoundedQuantification,
+            "lambda X.lambda f:X.f 0;",
+            "arrow type expected"
+        );
+        assert_type_error!(
+            BoundedQuantification,
+            "(lambda x:Nat.x) [Nat];",
+            "universal type expected"
+        );
+        // the bound is checked against the argument, not the other way round
+        assert_type_error!(
+            BoundedQuantification,
+            "(lambda X<:{a:Nat, b:Bool}.lambda x:X.x) [{a:Nat}];",
+            "not a subtype of the bound"
+        );
+        assert_type_error!(BoundedQuantification, "true.a;", "record type expected");
+        assert_type_error!(
+            BoundedQuantification,
+            "succ(true);",
+            "argument is not a number"
+        );
+        assert_type_error!(
+            BoundedQuantification,
+            "lambda X<:Bool.lambda x:X.pred(x);",
+            "argument is not a number"
+        );
+        assert_type_error!(
+            BoundedQuantification,
+            "if 0 then true else false;",
+            "guard of conditional not a boolean"
+        );
+        // errors inside a binder leave the context as it was
+        let mut ctx = Context::default();
+        let term = Parser::new().parse("lambda X.lambda x:X.x.a;").unwrap();
+        assert_eq!(type_of(&mut ctx, &term), Err(TypeError::RecordTypeExpected));
+        assert_eq!(
+            type_of(&mut ctx, &Term::TmVar(0)),
+            Err(TypeError::UnboundVariable(0))
+        );
+    }
+    #[test]
+    fn test_display() {
+        let display = |input: &str| type_str(input).unwrap().to_string();
+        assert_eq!(display("lambda X.lambda x:X.x;"), "Forall X.X->X");
+        assert_eq!(
+            display("lambda X<:{a:Nat}.lambda x:X.x.a;"),
+            "Forall X<:{a:Nat}.X->Nat"
+        );
+        assert_eq!(
+            display("lambda f:(All X<:Top.X)->Top.f;"),
+            "((Forall X.X)->Top)->(Forall X.X)->Top"
+        );
+        // the inner binder is renamed instead of capturing the outer `Y`, and
+        // a bound sees the variables outside its binder
+        assert_eq!(
+            display("lambda Y.lambda Y<:Y.lambda y:Y.y;"),
+            "Forall Y.Forall Y1<:Y.Y1->Y1"
+        );
+        assert_eq!(
+            type_str("lambda X<:Nat.lambda x:X.x;")
+                .unwrap()
+                .display(Notation::Unicode)
+                .to_string(),
+            "∀X<:Nat.X→X"
+        );
+        // printed types parse back to the same type
+        let typ = type_str("lambda Y.lambda Y<:Y.lambda y:Y.y;").unwrap();
+        assert_eq!(
+            type_str(&format!("lambda f:{}.f;", typ)),
+            Ok(Type::Arrow(Box::new(typ.clone()), Box::new(typ)))
+        );
+    }
+}
@@ -0,0 +1,34 @@
+use crate::{kinding::Kind, typing::Type};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Binding {
+    // a term variable and its type
+    Var(Type),
+    // a type variable and its kind
+    TypeVar(Kind),
+}
+
+// Term and type variables share one context, the innermost binding is at
+// de Bruijn index 0
+#[derive(Clone, Debug, Default)]
+pub struct Context {
+    bindings: Vec<(String, Binding)>,
+}
+
+impl Context {
+    pub fn push(&mut self, name: String, binding: Binding) {
+        self.bindings.push((name, binding));
+    }
+
+    pub fn pop(&mut self) {
+        self.bindings.pop();
+    }
+
+    pub fn get_binding(&self, index: usize) -> Option<&Binding> {
+        self.bindings
+            .iter()
+            .rev()
+            .nth(index)
+            .map(|(_, binding)| binding)
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{substitute::type_substitute_top, typing::Type};
+
+// One step of parallel reduction T ⇛ T' (TAPL 30.3) that contracts every
+// redex already present in `typ` at once, the complete development of `typ`.
+// Redexes created by the step are left for the next one.
+pub fn parallel_reduce(typ: &Type) -> Type {
+    match typ {
+        Type::Boolean | Type::Number | Type::Var(_) => typ.clone(),
+        Type::Arrow(param, ret) => Type::Arrow(
+            Box::new(parallel_reduce(param)),
+            Box::new(parallel_reduce(ret)),
+        ),
+        Type::Record(fields) => Type::Record(
+            fields
+                .iter()
+                .map(|(label, typ)| (label.clone(), parallel_reduce(typ)))
+                .collect(),
+        ),
+        Type::All(name, kind, body) => {
+            Type::All(name.clone(), kind.clone(), Box::new(parallel_reduce(body)))
+        }
+        Type::Abs(name, kind, body) => {
+            Type::Abs(name.clone(), kind.clone(), Box::new(parallel_reduce(body)))
+        }
+        Type::App(operator, argument) => {
+            let argument = parallel_reduce(argument);
+            match operator.as_ref() {
+                // QR-AppAbs
+                Type::Abs(_, _, body) => type_substitute_top(&argument, &parallel_reduce(body)),
+                _ => Type::App(Box::new(parallel_reduce(operator)), Box::new(argument)),
+            }
+        }
+    }
+}
+
+// Well-kinded types are normalizing, so reducing until nothing changes ends
+// in the normal form
+pub fn normalize(typ: &Type) -> Type {
+    let mut typ = typ.clone();
+    loop {
+        let next = parallel_reduce(&typ);
+        if next == typ {
+            return typ;
+        }
+        typ = next;
+    }
+}
+
+// S ≡ T iff both reduce to a common type (parallel reduction is confluent,
+// TAPL 30.3.8), for well-kinded types their normal forms are the same
+pub fn type_eq(s: &Type, t: &Type) -> bool {
+    normalize(s) == normalize(t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kinding::Kind;
+
+    #[test]
+    fn test_equivalence() {
+        let id = || {
+            Box::new(Type::Abs(
+                "X".to_string(),
+                Kind::Star,
+                Box::new(Type::Var(0)),
+            ))
+        };
+        let nat_to_nat = Type::Arrow(Box::new(Type::Number), Box::new(Type::Number));
+        // (lambda X.X->X) ((lambda X.X) Nat) contracts both redexes in one step
+        let self_arrow = Type::Abs(
+            "X".to_string(),
+            Kind::Star,
+            Box::new(Type::Arrow(Box::new(Type::Var(0)), Box::new(Type::Var(0)))),
+        );
+        let typ = Type::App(
+            Box::new(self_arrow),
+            Box::new(Type::App(id(), Box::new(Type::Number))),
+        );
+        assert_eq!(parallel_reduce(&typ), nat_to_nat);
+        // (lambda F::*=>*.F Nat) (lambda X.X) needs a second step for the new redex
+        let apply_to_nat = Type::Abs(
+            "F".to_string(),
+            Kind::Arrow(Box::new(Kind::Star), Box::new(Kind::Star)),
+            Box::new(Type::App(Box::new(Type::Var(0)), Box::new(Type::Number))),
+        );
+        let typ = Type::App(Box::new(apply_to_nat), id());
+        assert_eq!(
+            parallel_reduce(&typ),
+            Type::App(id(), Box::new(Type::Number))
+        );
+        assert!(type_eq(&typ, &Type::Number));
+        assert!(!type_eq(&typ, &nat_to_nat));
+    }
+}
@@ -0,0 +1,155 @@
+use std::fmt::{self, Formatter};
+
+use crate::{
+    context::{Binding, Context},
+    typing::Type,
+};
+
+// `*` classifies proper types, `K1=>K2` type operators (TAPL 29.1)
+#[derive(Clone, Debug, PartialEq)]
+pub enum Kind {
+    Star,
+    Arrow(Box<Kind>, Box<Kind>),
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::Star => write!(f, "*"),
+            Kind::Arrow(param, result) => match param.as_ref() {
+                Kind::Arrow(_, _) => write!(f, "({})=>{}", param, result),
+                Kind::Star => write!(f, "*=>{}", result),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum KindError {
+    // a type of kind `*` was applied to an argument
+    ArrowKindExpected(Kind),
+    ArgumentKindMismatch { expected: Kind, found: Kind },
+    // a proper type was expected, e.g. as the type of a term variable
+    StarKindExpected(Kind),
+    UnboundTypeVariable(usize),
+}
+
+impl fmt::Display for KindError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            KindError::ArrowKindExpected(kind) => {
+                write!(f, "type of kind {} applied to an argument", kind)
+            }
+            KindError::ArgumentKindMismatch { expected, found } => {
+                write!(f, "type argument has kind {}, expected {}", found, expected)
+            }
+            KindError::StarKindExpected(kind) => {
+                write!(f, "proper type of kind * expected, found kind {}", kind)
+            }
+            KindError::UnboundTypeVariable(index) => {
+                write!(f, "unbound type variable index {}", index)
+            }
+        }
+    }
+}
+
+fn expect_star(ctx: &mut Context, typ: &Type) -> Result<(), KindError> {
+    match kind_of(ctx, typ)? {
+        Kind::Star => Ok(()),
+        kind => Err(KindError::StarKindExpected(kind)),
+    }
+}
+
+// Γ ⊢ T :: K, the rules K-TVar, K-Abs, K-App, K-Arrow and K-All (TAPL 29.1, 30.1)
+pub fn kind_of(ctx: &mut Context, typ: &Type) -> Result<Kind, KindError> {
+    match typ {
+        Type::Boolean | Type::Number => Ok(Kind::Star),
+        Type::Var(index) => match ctx.get_binding(*index) {
+            Some(Binding::TypeVar(kind)) => Ok(kind.clone()),
+            _ => Err(KindError::UnboundTypeVariable(*index)),
+        },
+        Type::Arrow(param, ret) => {
+            expect_star(ctx, param)?;
+            expect_star(ctx, ret)?;
+            Ok(Kind::Star)
+        }
+        Type::Record(fields) => {
+            for (_, typ) in fields {
+                expect_star(ctx, typ)?;
+            }
+            Ok(Kind::Star)
+        }
+        Type::All(name, kind, body) => {
+            ctx.push(name.clone(), Binding::TypeVar(kind.clone()));
+            let result = expect_star(ctx, body);
+            ctx.pop();
+            result.map(|_| Kind::Star)
+        }
+        Type::Abs(name, kind, body) => {
+            ctx.push(name.clone(), Binding::TypeVar(kind.clone()));
+            let body_kind = kind_of(ctx, body);
+            ctx.pop();
+            Ok(Kind::Arrow(Box::new(kind.clone()), Box::new(body_kind?)))
+        }
+        Type::App(operator, argument) => match kind_of(ctx, operator)? {
+            Kind::Arrow(param, result) => {
+                let argument_kind = kind_of(ctx, argument)?;
+                if argument_kind == *param {
+                    Ok(*result)
+                } else {
+                    Err(KindError::ArgumentKindMismatch {
+                        expected: *param,
+                        found: argument_kind,
+                    })
+                }
+            }
+            kind => Err(KindError::ArrowKindExpected(kind)),
+        },
+    }
+}
+
+// proper types are the ones terms can have
+pub fn check_proper(ctx: &mut Context, typ: &Type) -> Result<(), KindError> {
+    expect_star(ctx, typ)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind_of() {
+        let star_to_star = Kind::Arrow(Box::new(Kind::Star), Box::new(Kind::Star));
+        let id = Type::Abs("X".to_string(), Kind::Star, Box::new(Type::Var(0)));
+        let mut ctx = Context::default();
+        assert_eq!(kind_of(&mut ctx, &id), Ok(star_to_star.clone()));
+        assert_eq!(
+            kind_of(
+                &mut ctx,
+                &Type::App(Box::new(id.clone()), Box::new(Type::Number))
+            ),
+            Ok(Kind::Star)
+        );
+        assert_eq!(
+            kind_of(
+                &mut ctx,
+                &Type::App(Box::new(Type::Number), Box::new(Type::Number))
+            ),
+            Err(KindError::ArrowKindExpected(Kind::Star))
+        );
+        assert_eq!(
+            kind_of(
+                &mut ctx,
+                &Type::App(Box::new(id.clone()), Box::new(id.clone()))
+            ),
+            Err(KindError::ArgumentKindMismatch {
+                expected: Kind::Star,
+                found: star_to_star.clone()
+            })
+        );
+        assert_eq!(
+            kind_of(&mut ctx, &Type::Arrow(Box::new(id), Box::new(Type::Number))),
+            Err(KindError::StarKindExpected(star_to_star))
+        );
+    }
+}
@@ -0,0 +1,11 @@
+pub mod ast_parser;
+pub mod context;
+pub mod equivalence;
+pub mod eval;
+pub mod kinding;
+#[cfg(test)]
+mod language;
+pub mod parser;
+pub mod substitute;
+pub mod type_parser;
+pub mod typing;
@@ -0,0 +1,152 @@
+use crate::{parser::Term, typing::Type};
+
+// Rebuilds `typ` replacing every type variable by `on_var(c, index)`, where
+// `c` is the number of `All` and `lambda` binders entered so far
+fn map_type_vars<F>(typ: &Type, c: usize, on_var: &F) -> Type
+where
+    F: Fn(usize, usize) -> Type,
+{
+    match typ {
+        Type::Boolean | Type::Number => typ.clone(),
+        Type::Var(index) => on_var(c, *index),
+        Type::Arrow(param, ret) => Type::Arrow(
+            Box::new(map_type_vars(param, c, on_var)),
+            Box::new(map_type_vars(ret, c, on_var)),
+        ),
+        Type::All(name, kind, body) => Type::All(
+            name.clone(),
+            kind.clone(),
+            Box::new(map_type_vars(body, c + 1, on_var)),
+        ),
+        Type::Abs(name, kind, body) => Type::Abs(
+            name.clone(),
+            kind.clone(),
+            Box::new(map_type_vars(body, c + 1, on_var)),
+        ),
+        Type::App(operator, argument) => Type::App(
+            Box::new(map_type_vars(operator, c, on_var)),
+            Box::new(map_type_vars(argument, c, on_var)),
+        ),
+        Type::Record(fields) => Type::Record(
+            fields
+                .iter()
+                .map(|(label, typ)| (label.clone(), map_type_vars(typ, c, on_var)))
+                .collect(),
+        ),
+    }
+}
+
+pub fn type_shift(typ: &Type, d: isize, cutoff: usize) -> Type {
+    map_type_vars(typ, cutoff, &|c, index| {
+        if index >= c {
+            Type::Var((index as isize + d) as usize)
+        } else {
+            Type::Var(index)
+        }
+    })
+}
+
+// [j ↦ s]typ
+pub fn type_substitute(typ: &Type, j: usize, s: &Type) -> Type {
+    map_type_vars(typ, 0, &|c, index| {
+        if index == j + c {
+            type_shift(s, c as isize, 0)
+        } else {
+            Type::Var(index)
+        }
+    })
+}
+
+// the body of `All X.body` or `lambda X.body` instantiated with `s`
+pub fn type_substitute_top(s: &Type, body: &Type) -> Type {
+    type_shift(&type_substitute(body, 0, &type_shift(s, 1, 0)), -1, 0)
+}
+
+// Rebuilds `term` replacing every variable by `on_var(c, index)` and every
+// type annotation by `on_type(c, typ)`, where `c` is the number of term and
+// type binders entered so far (TAPL 25.2 `tmmap`)
+fn map_vars<F, G>(term: &Term, c: usize, on_var: &F, on_type: &G) -> Term
+where
+    F: Fn(usize, usize) -> Term,
+    G: Fn(usize, &Type) -> Type,
+{
+    let map = |term: &Term| Box::new(map_vars(term, c, on_var, on_type));
+    let map_under_binder = |term: &Term| Box::new(map_vars(term, c + 1, on_var, on_type));
+    match term {
+        Term::TmVar(index) => on_var(c, *index),
+        Term::TmTrue | Term::TmFalse | Term::TmZero => term.clone(),
+        Term::TmSucc(term) => Term::TmSucc(map(term)),
+        Term::TmPred(term) => Term::TmPred(map(term)),
+        Term::TmIsZero(term) => Term::TmIsZero(map(term)),
+        Term::TmAbs(name, typ, body) => {
+            Term::TmAbs(name.clone(), on_type(c, typ), map_under_binder(body))
+        }
+        Term::TmApp(left, right) => Term::TmApp(map(left), map(right)),
+        Term::TmIf(if_term, then_term, else_term) => {
+            Term::TmIf(map(if_term), map(then_term), map(else_term))
+        }
+        Term::TmLet(name, bound, body) => {
+            Term::TmLet(name.clone(), map(bound), map_under_binder(body))
+        }
+        Term::TmTAbs(name, kind, body) => {
+            Term::TmTAbs(name.clone(), kind.clone(), map_under_binder(body))
+        }
+        Term::TmTApp(term, typ) => Term::TmTApp(map(term), on_type(c, typ)),
+        Term::TmRecord(fields) => Term::TmRecord(
+            fields
+                .iter()
+                .map(|(label, term)| (label.clone(), map_vars(term, c, on_var, on_type)))
+                .collect(),
+        ),
+        Term::TmProj(term, label) => Term::TmProj(map(term), label.clone()),
+    }
+}
+
+// ↑d_c for term and type variables alike
+pub fn shift(term: &Term, d: isize, cutoff: usize) -> Term {
+    map_vars(
+        term,
+        cutoff,
+        &|c, index| {
+            if index >= c {
+                Term::TmVar((index as isize + d) as usize)
+            } else {
+                Term::TmVar(index)
+            }
+        },
+        &|c, typ| type_shift(typ, d, c),
+    )
+}
+
+// [j ↦ s]term
+pub fn substitute(term: &Term, j: usize, s: &Term) -> Term {
+    map_vars(
+        term,
+        0,
+        &|c, index| {
+            if index == j + c {
+                shift(s, c as isize, 0)
+            } else {
+                Term::TmVar(index)
+            }
+        },
+        &|_, typ| typ.clone(),
+    )
+}
+
+// the body of a beta reduction: ↑-1([0 ↦ ↑1(s)]body)
+pub fn substitute_top(s: &Term, body: &Term) -> Term {
+    shift(&substitute(body, 0, &shift(s, 1, 0)), -1, 0)
+}
+
+// [j ↦ s] for a type variable in the annotations of `term`
+pub fn type_term_substitute(term: &Term, j: usize, s: &Type) -> Term {
+    map_vars(term, 0, &|_, index| Term::TmVar(index), &|c, typ| {
+        type_substitute(typ, j + c, s)
+    })
+}
+
+// the body of a type application `(lambda X.body) [s]`
+pub fn type_term_substitute_top(s: &Type, body: &Term) -> Term {
+    shift(&type_term_substitute(body, 0, &type_shift(s, 1, 0)), -1, 0)
+}
@@ -0,0 +1,215 @@
+use tapl_frontend::lexer::{Lexer, SyntaxError, Token};
+
+use crate::kinding::Kind;
+
+pub const TYPE_KEYWORDS: [&str; 4] = ["Bool", "Nat", "All", "Forall"];
+
+// Types as written, type variables are resolved to de Bruijn indices by the
+// parser together with term variables
+#[derive(Clone, Debug, PartialEq)]
+pub enum ASTType {
+    Boolean,
+    Number,
+    Var(String),
+    Arrow(Box<ASTType>, Box<ASTType>),
+    // `All X::K.T`
+    All(String, Kind, Box<ASTType>),
+    // `lambda X::K.T`, a type operator
+    Abs(String, Kind, Box<ASTType>),
+    // `T1 T2`
+    App(Box<ASTType>, Box<ASTType>),
+    // `{l1:T1, l2:T2}`
+    Record(Vec<(String, ASTType)>),
+}
+
+fn parse_atom_kind(lexer: &mut Lexer) -> Result<Kind, SyntaxError> {
+    lexer.nested(|lexer| {
+        let (token, span) = lexer.next_token()?;
+        match token {
+            Token::Symbol("*") => Ok(Kind::Star),
+            Token::Symbol("(") => {
+                let kind = parse_kind(lexer)?;
+                lexer.expect_symbol(")")?;
+                Ok(kind)
+            }
+            _ => Err(lexer.error_at("a kind", &token.to_string(), span)),
+        }
+    })
+}
+
+// `K1=>K2=>K3` is `K1=>(K2=>K3)`
+pub fn parse_kind(lexer: &mut Lexer) -> Result<Kind, SyntaxError> {
+    lexer.nested(|lexer| {
+        let kind = parse_atom_kind(lexer)?;
+        match lexer.eat_symbol("=>")? {
+            Some(_) => Ok(Kind::Arrow(Box::new(kind), Box::new(parse_kind(lexer)?))),
+            None => Ok(kind),
+        }
+    })
+}
+
+fn is_type_var(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_uppercase()) && !TYPE_KEYWORDS.contains(&word)
+}
+
+// type variables are capitalized words
+pub fn parse_type_var(lexer: &mut Lexer) -> Result<String, SyntaxError> {
+    match lexer.peek()? {
+        (Token::Word(word), _) if is_type_var(word) => {
+            lexer.next_token()?;
+            Ok(word.to_string())
+        }
+        _ => Err(lexer.unexpected("a type variable")),
+    }
+}
+
+// `X` or `X::K`, the kind of proper types is the default
+pub fn parse_kinded_type_var(lexer: &mut Lexer) -> Result<(String, Kind), SyntaxError> {
+    let name = parse_type_var(lexer)?;
+    let kind = match lexer.eat_symbol("::")? {
+        Some(_) => parse_kind(lexer)?,
+        None => Kind::Star,
+    };
+    Ok((name, kind))
+}
+
+// `{l:T, ...}` after the `{`
+fn parse_record_type(lexer: &mut Lexer) -> Result<ASTType, SyntaxError> {
+    let mut fields = vec![];
+    if lexer.eat_symbol("}")?.is_none() {
+        loop {
+            let (label, _) = lexer.expect_word("a label", &[])?;
+            lexer.expect_symbol(":")?;
+            fields.push((label.to_string(), parse_type(lexer)?));
+            if lexer.eat_symbol(",")?.is_none() {
+                break;
+            }
+        }
+        lexer.expect_symbol("}")?;
+    }
+    Ok(ASTType::Record(fields))
+}
+
+fn starts_atom_type(lexer: &mut Lexer) -> Result<bool, SyntaxError> {
+    Ok(match lexer.peek()?.0 {
+        Token::Word(word) => word == "Bool" || word == "Nat" || is_type_var(word),
+        Token::Symbol("{") | Token::Symbol("(") => true,
+        _ => false,
+    })
+}
+
+fn parse_atom_type(lexer: &mut Lexer) -> Result<ASTType, SyntaxError> {
+    lexer.nested(|lexer| {
+        let (token, span) = lexer.next_token()?;
+        match token {
+            Token::Word("Bool") => Ok(ASTType::Boolean),
+            Token::Word("Nat") => Ok(ASTType::Number),
+            Token::Word(word) if is_type_var(word) => Ok(ASTType::Var(word.to_string())),
+            Token::Symbol("{") => parse_record_type(lexer),
+            Token::Symbol("(") => {
+                let typ = parse_type(lexer)?;
+                lexer.expect_symbol(")")?;
+                Ok(typ)
+            }
+            _ => Err(lexer.error_at("a type", &token.to_string(), span)),
+        }
+    })
+}
+
+// application binds tighter than `->` and associates to the left
+fn parse_application_type(lexer: &mut Lexer) -> Result<ASTType, SyntaxError> {
+    let mut typ = parse_atom_type(lexer)?;
+    while starts_atom_type(lexer)? {
+        lexer.deepen()?;
+        let argument = parse_atom_type(lexer)?;
+        typ = ASTType::App(Box::new(typ), Box::new(argument));
+    }
+    Ok(typ)
+}
+
+// `All X::K.T` and `lambda X::K.T` extend as far right as possible and arrow
+// types are right associative: `A->B->C` is `A->(B->C)`
+pub fn parse_type(lexer: &mut Lexer) -> Result<ASTType, SyntaxError> {
+    lexer.nested(|lexer| {
+        if lexer.eat_keyword("All")?.is_some() || lexer.eat_keyword("Forall")?.is_some() {
+            let (name, kind) = parse_kinded_type_var(lexer)?;
+            lexer.expect_symbol(".")?;
+            return Ok(ASTType::All(name, kind, Box::new(parse_type(lexer)?)));
+        }
+        if lexer.eat_keyword("lambda")?.is_some() {
+            let (name, kind) = parse_kinded_type_var(lexer)?;
+            lexer.expect_symbol(".")?;
+            return Ok(ASTType::Abs(name, kind, Box::new(parse_type(lexer)?)));
+        }
+        let typ = parse_application_type(lexer)?;
+        match lexer.eat_symbol("->")? {
+            Some(_) => Ok(ASTType::Arrow(Box::new(typ), Box::new(parse_type(lexer)?))),
+            None => Ok(typ),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SYMBOLS;
+
+    fn parse<T>(
+        parser: fn(&mut Lexer) -> Result<T, SyntaxError>,
+        input: &str,
+    ) -> Result<(&str, T), String> {
+        let mut lexer = Lexer::new(input, &SYMBOLS);
+        parser(&mut lexer)
+            .map(|result| (lexer.rest(), result))
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn test_type() {
+        let var = |name: &str| Box::new(ASTType::Var(name.to_string()));
+        assert_eq!(
+            parse(parse_kind, "(*=>*)=>*"),
+            Ok((
+                "",
+                Kind::Arrow(
+                    Box::new(Kind::Arrow(Box::new(Kind::Star), Box::new(Kind::Star))),
+                    Box::new(Kind::Star)
+                )
+            ))
+        );
+        assert_eq!(
+            parse(parse_type, "lambda F::*=>*.F Nat->F Bool"),
+            Ok((
+                "",
+                ASTType::Abs(
+                    "F".to_string(),
+                    Kind::Arrow(Box::new(Kind::Star), Box::new(Kind::Star)),
+                    Box::new(ASTType::Arrow(
+                        Box::new(ASTType::App(var("F"), Box::new(ASTType::Number))),
+                        Box::new(ASTType::App(var("F"), Box::new(ASTType::Boolean)))
+                    ))
+                )
+            ))
+        );
+        // type names are whole words
+        assert_eq!(
+            parse(parse_type, "lambda Elem.List Elem"),
+            Ok((
+                "",
+                ASTType::Abs(
+                    "Elem".to_string(),
+                    Kind::Star,
+                    Box::new(ASTType::App(var("List"), var("Elem")))
+                )
+            ))
+        );
+        assert_eq!(
+            parse(parse_type, "All Bool.Bool").unwrap_err(),
+            "1:5: expected a type variable, found `Bool`"
+        );
+        assert_eq!(
+            parse(parse_kind, "*=>").unwrap_err(),
+            "1:4: expected a kind, found end of input"
+        );
+    }
+}
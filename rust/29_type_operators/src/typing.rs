@@ -0,0 +1,396 @@
+use std::fmt::{self, Formatter};
+
+use misc::Notation;
+
+use crate::{
+    context::{Binding, Context},
+    equivalence::{normalize, type_eq},
+    kinding::{check_proper, kind_of, Kind, KindError},
+    parser::Term,
+    substitute::{type_shift, type_substitute_top},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeError {
+    ParameterTypeMismatch,
+    ArrowTypeExpected,
+    UniversalTypeExpected,
+    RecordTypeExpected,
+    LabelNotFound(String),
+    NumberExpected,
+    IfConditionNotBoolean,
+    IfBranchesMismatch,
+    UnboundVariable(usize),
+    // an ill-kinded type annotation or type argument
+    Kind(KindError),
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeError::ParameterTypeMismatch => write!(f, "parameter type mismatch"),
+            TypeError::ArrowTypeExpected => write!(f, "arrow type expected"),
+            TypeError::UniversalTypeExpected => write!(f, "universal type expected"),
+            TypeError::RecordTypeExpected => write!(f, "record type expected"),
+            TypeError::LabelNotFound(label) => write!(f, "label {} not found", label),
+            TypeError::NumberExpected => write!(f, "argument is not a number"),
+            TypeError::IfConditionNotBoolean => write!(f, "guard of conditional not a boolean"),
+            TypeError::IfBranchesMismatch => {
+                write!(f, "arms of conditional have different types")
+            }
+            TypeError::UnboundVariable(index) => write!(f, "unbound variable index {}", index),
+            TypeError::Kind(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<KindError> for TypeError {
+    fn from(error: KindError) -> Self {
+        TypeError::Kind(error)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Type {
+    Boolean,
+    Number,
+    // type variable DeBrujin index, counted in the same context as terms
+    Var(usize),
+    Arrow(Box<Type>, Box<Type>),
+    // bound name, only kept for printing, its kind and body
+    All(String, Kind, Box<Type>),
+    // a type operator, bound name, kind of the parameter and body
+    Abs(String, Kind, Box<Type>),
+    App(Box<Type>, Box<Type>),
+    Record(Vec<(String, Type)>),
+}
+
+// Syntactic equality up to the names of binders, equivalence of types that
+// differ by type-level reduction is `type_eq`
+impl PartialEq for Type {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Type::Boolean, Type::Boolean) | (Type::Number, Type::Number) => true,
+            (Type::Var(x), Type::Var(y)) => x == y,
+            (Type::Arrow(s1, s2), Type::Arrow(t1, t2)) | (Type::App(s1, s2), Type::App(t1, t2)) => {
+                s1 == t1 && s2 == t2
+            }
+            (Type::All(_, k1, s), Type::All(_, k2, t))
+            | (Type::Abs(_, k1, s), Type::Abs(_, k2, t)) => k1 == k2 && s == t,
+            (Type::Record(s), Type::Record(t)) => s == t,
+            _ => false,
+        }
+    }
+}
+
+impl Type {
+    pub fn display(&self, notation: Notation) -> TypeDisplay<'_> {
+        TypeDisplay {
+            typ: self,
+            notation,
+        }
+    }
+}
+
+// Prints types in the syntax the parser accepts, type variables get the
+// name of their binder back
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.display(Notation::Ascii).fmt(f)
+    }
+}
+
+// A variable bound outside the printed type shows as its index `#1`
+pub struct TypeDisplay<'a> {
+    typ: &'a Type,
+    notation: Notation,
+}
+
+impl fmt::Display for TypeDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_type(f, self.typ, self.notation, &mut vec![], Precedence::Arrow)
+    }
+}
+
+// Binding strength of the context a type is printed in, parentheses are
+// only emitted when the type binds looser than its context. Binders extend
+// as far right as possible and bind as loosely as arrows.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+enum Precedence {
+    Arrow,
+    Application,
+    Atom,
+}
+
+// a binder shadowing an enclosing one of the same name gets a number, so
+// that `All Y.All Y.Y` with the outer `Y` in the body prints as
+// `Forall Y.Forall Y1.Y`
+fn fresh_name(name: &str, names: &[String]) -> String {
+    let mut fresh = name.to_string();
+    let mut suffix = 0;
+    while names.contains(&fresh) {
+        suffix += 1;
+        fresh = format!("{}{}", name, suffix);
+    }
+    fresh
+}
+
+// `All X::K.T` or `lambda X::K.T`, the kind `*` is left out as the parser does
+fn write_binder(
+    f: &mut Formatter<'_>,
+    binder: &str,
+    name: &str,
+    kind: &Kind,
+    body: &Type,
+    notation: Notation,
+    names: &mut Vec<String>,
+) -> fmt::Result {
+    let name = fresh_name(name, names);
+    write!(f, "{}{}", binder, name)?;
+    if *kind != Kind::Star {
+        write!(f, "::{}", kind)?;
+    }
+    write!(f, ".")?;
+    names.push(name);
+    let result = write_type(f, body, notation, names, Precedence::Arrow);
+    names.pop();
+    result
+}
+
+fn write_type(
+    f: &mut Formatter<'_>,
+    typ: &Type,
+    notation: Notation,
+    names: &mut Vec<String>,
+    precedence: Precedence,
+) -> fmt::Result {
+    let binds = match typ {
+        Type::Arrow(_, _) | Type::All(_, _, _) | Type::Abs(_, _, _) => Precedence::Arrow,
+        Type::App(_, _) => Precedence::Application,
+        _ => Precedence::Atom,
+    };
+    if binds < precedence {
+        write!(f, "(")?;
+    }
+    match typ {
+        Type::Boolean => write!(f, "Bool")?,
+        Type::Number => write!(f, "Nat")?,
+        Type::Var(index) => match names.iter().rev().nth(*index) {
+            Some(name) => write!(f, "{}", name)?,
+            None => write!(f, "#{}", index)?,
+        },
+        Type::Arrow(param, ret) => {
+            // arrows associate to the right, so only the left side needs parentheses
+            write_type(f, param, notation, names, Precedence::Application)?;
+            write!(f, "{}", notation.arrow())?;
+            write_type(f, ret, notation, names, Precedence::Arrow)?;
+        }
+        Type::All(name, kind, body) => {
+            write_binder(f, notation.forall(), name, kind, body, notation, names)?
+        }
+        Type::Abs(name, kind, body) => {
+            write_binder(f, notation.lambda(), name, kind, body, notation, names)?
+        }
+        Type::App(operator, argument) => {
+            // application associates to the left
+            write_type(f, operator, notation, names, Precedence::Application)?;
+            write!(f, " ")?;
+            write_type(f, argument, notation, names, Precedence::Atom)?;
+        }
+        Type::Record(fields) => {
+            write!(f, "{{")?;
+            for (i, (label, typ)) in fields.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}:", label)?;
+                write_type(f, typ, notation, names, Precedence::Arrow)?;
+            }
+            write!(f, "}}")?;
+        }
+    }
+    if binds < precedence {
+        write!(f, ")")?;
+    }
+    Ok(())
+}
+
+fn expect_number(typ: Type) -> Result<(), TypeError> {
+    if type_eq(&typ, &Type::Number) {
+        Ok(())
+    } else {
+        Err(TypeError::NumberExpected)
+    }
+}
+
+// the type of a term variable, shifted from its binding into the current context
+fn get_type(ctx: &Context, index: usize) -> Result<Type, TypeError> {
+    match ctx.get_binding(index) {
+        Some(Binding::Var(typ)) => Ok(type_shift(typ, index as isize + 1, 0)),
+        _ => Err(TypeError::UnboundVariable(index)),
+    }
+}
+
+// Typing of F-omega (TAPL 30.2): annotations are kind checked, and types are
+// compared and taken apart up to equivalence
+pub fn type_of(ctx: &mut Context, term: &Term) -> Result<Type, TypeError> {
+    match term {
+        Term::TmTrue | Term::TmFalse => Ok(Type::Boolean),
+        Term::TmZero => Ok(Type::Number),
+        Term::TmSucc(term) | Term::TmPred(term) => {
+            expect_number(type_of(ctx, term)?)?;
+            Ok(Type::Number)
+        }
+        Term::TmIsZero(term) => {
+            expect_number(type_of(ctx, term)?)?;
+            Ok(Type::Boolean)
+        }
+        Term::TmVar(index) => get_type(ctx, *index),
+        Term::TmAbs(name, typ, body) => {
+            check_proper(ctx, typ)?;
+            ctx.push(name.clone(), Binding::Var(typ.clone()));
+            let body_type = type_of(ctx, body);
+            ctx.pop();
+            Ok(Type::Arrow(
+                Box::new(typ.clone()),
+                Box::new(type_shift(&body_type?, -1, 0)),
+            ))
+        }
+        Term::TmApp(left, right) => {
+            let left_type = type_of(ctx, left)?;
+            let right_type = type_of(ctx, right)?;
+            match normalize(&left_type) {
+                Type::Arrow(param_type, return_type) if type_eq(&param_type, &right_type) => {
+                    Ok(*return_type)
+                }
+                Type::Arrow(_, _) => Err(TypeError::ParameterTypeMismatch),
+                _ => Err(TypeError::ArrowTypeExpected),
+            }
+        }
+        Term::TmIf(if_term, then_term, else_term) => {
+            if !type_eq(&type_of(ctx, if_term)?, &Type::Boolean) {
+                return Err(TypeError::IfConditionNotBoolean);
+            }
+            let then_type = type_of(ctx, then_term)?;
+            if type_eq(&then_type, &type_of(ctx, else_term)?) {
+                Ok(then_type)
+            } else {
+                Err(TypeError::IfBranchesMismatch)
+            }
+        }
+        Term::TmLet(name, bound, body) => {
+            let bound_type = type_of(ctx, bound)?;
+            ctx.push(name.clone(), Binding::Var(bound_type));
+            let body_type = type_of(ctx, body);
+            ctx.pop();
+            Ok(type_shift(&body_type?, -1, 0))
+        }
+        // T-TAbs
+        Term::TmTAbs(name, kind, body) => {
+            ctx.push(name.clone(), Binding::TypeVar(kind.clone()));
+            let body_type = type_of(ctx, body);
+            ctx.pop();
+            Ok(Type::All(name.clone(), kind.clone(), Box::new(body_type?)))
+        }
+        // T-TApp, the argument must have the kind of the bound variable
+        Term::TmTApp(term, typ) => {
+            let argument_kind = kind_of(ctx, typ)?;
+            match normalize(&type_of(ctx, term)?) {
+                Type::All(_, kind, body) if kind == argument_kind => {
+                    Ok(type_substitute_top(typ, &body))
+                }
+                Type::All(_, kind, _) => Err(TypeError::Kind(KindError::ArgumentKindMismatch {
+                    expected: kind,
+                    found: argument_kind,
+                })),
+                _ => Err(TypeError::UniversalTypeExpected),
+            }
+        }
+        Term::TmRecord(fields) => Ok(Type::Record(
+            fields
+                .iter()
+                .map(|(label, term)| Ok((label.clone(), type_of(ctx, term)?)))
+                .collect::<Result<Vec<_>, TypeError>>()?,
+        )),
+        Term::TmProj(term, label) => match normalize(&type_of(ctx, term)?) {
+            Type::Record(fields) => fields
+                .into_iter()
+                .find(|(name, _)| name == label)
+                .map(|(_, typ)| typ)
+                .ok_or_else(|| TypeError::LabelNotFound(label.clone())),
+            _ => Err(TypeError::RecordTypeExpected),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{language::TypeOperators, parser::Parser};
+    use tapl_testkit::assert_type_error;
+
+    fn type_str(input: &str) -> Result<Type, TypeError> {
+        let term = Parser::new().parse(input).unwrap();
+        type_of(&mut Context::default(), &term)
+    }
+
+    #[test]
+    fn test_type_of() {
+        // the annotation is only equivalent to Nat->Nat
+        assert_eq!(
+            type_str("(lambda f:(lambda X.X->X) Nat.f 0) (lambda x:Nat.succ(x));"),
+            Ok(Type::Number)
+        );
+        // abstraction over a type operator, instantiated with one
+        let term = "lambda F::*=>*.lambda x:F Nat.x";
+        assert!(type_str(&format!("{};", term)).is_ok());
+        assert!(type_str(&format!("({}) [lambda X.X->X] (lambda y:Nat.y);", term)).is_ok());
+        assert_type_error!(
+            TypeOperators,
+            &format!("({}) [Nat];", term),
+            "type argument has kind *, expected *=>*"
+        );
+        assert_type_error!(
+            TypeOperators,
+            "lambda x:lambda X.X.x;",
+            "proper type of kind * expected"
+        );
+        assert_type_error!(
+            TypeOperators,
+            "lambda x:Nat Bool.x;",
+            "type of kind * applied to an argument"
+        );
+    }
+    #[test]
+    fn test_display() {
+        let display = |input: &str| type_str(input).unwrap().to_string();
+        assert_eq!(display("lambda X.lambda x:X.x;"), "Forall X.X->X");
+        assert_eq!(
+            display("lambda F::*=>*.lambda x:F Nat->F Bool.x;"),
+            "Forall F::*=>*.(F Nat->F Bool)->F Nat->F Bool"
+        );
+        // operators are printed as written, they are normalized for checking
+        assert_eq!(
+            display("lambda x:(lambda X.X->X) ((lambda Y.Y) Nat).x;"),
+            "(lambda X.X->X) ((lambda Y.Y) Nat)->(lambda X.X->X) ((lambda Y.Y) Nat)"
+        );
+        // the inner binder is renamed instead of capturing the outer `Y`
+        assert_eq!(
+            display("lambda Y.lambda x:(All Y::*=>*.Y Nat)->Y.x;"),
+            "Forall Y.((Forall Y1::*=>*.Y1 Nat)->Y)->(Forall Y1::*=>*.Y1 Nat)->Y"
+        );
+        assert_eq!(
+            type_str("lambda F::*=>*.lambda x:F Nat.x;")
+                .unwrap()
+                .display(Notation::Unicode)
+                .to_string(),
+            "∀F::*=>*.F Nat→F Nat"
+        );
+        // printed types parse back to the same type
+        let typ = type_str("lambda Y.lambda x:(All Y::*=>*.Y Nat)->Y.x;").unwrap();
+        assert_eq!(
+            type_str(&format!("lambda f:{}.f;", typ)),
+            Ok(Type::Arrow(Box::new(typ.clone()), Box::new(typ)))
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use crate::term::Combinator;
+
+// Weak reduction of combinatory logic, leftmost redex first:
+//
+//   I x     -> x
+//   K x y   -> x
+//   S x y z -> x z (y z)
+
+// splits `f a1 ... an` into the head `f` and its arguments
+fn spine(term: &Combinator) -> (&Combinator, Vec<&Combinator>) {
+    let mut arguments = vec![];
+    let mut head = term;
+    while let Combinator::App(left, right) = head {
+        arguments.push(right.as_ref());
+        head = left;
+    }
+    arguments.reverse();
+    (head, arguments)
+}
+
+fn rebuild(head: Combinator, arguments: &[&Combinator]) -> Combinator {
+    arguments.iter().fold(head, |term, argument| {
+        Combinator::app(term, (*argument).clone())
+    })
+}
+
+pub fn eval1(term: &Combinator) -> Option<Combinator> {
+    let (head, arguments) = spine(term);
+    let contracted = match (head, arguments.as_slice()) {
+        (Combinator::I, [x, rest @ ..]) => Some(rebuild((*x).clone(), rest)),
+        (Combinator::K, [x, _, rest @ ..]) => Some(rebuild((*x).clone(), rest)),
+        (Combinator::S, [x, y, z, rest @ ..]) => {
+            let x_z = Combinator::app((*x).clone(), (*z).clone());
+            let y_z = Combinator::app((*y).clone(), (*z).clone());
+            Some(rebuild(Combinator::app(x_z, y_z), rest))
+        }
+        _ => None,
+    };
+    if contracted.is_some() {
+        return contracted;
+    }
+    // the head is in normal form, reduce the leftmost argument that can step
+    arguments.iter().enumerate().find_map(|(i, argument)| {
+        eval1(argument).map(|argument| {
+            let mut arguments = arguments.clone();
+            arguments[i] = &argument;
+            rebuild(head.clone(), &arguments)
+        })
+    })
+}
+
+// the normal form of `term`, or `None` if there is none within `max_steps`
+pub fn normalize(term: &Combinator, max_steps: usize) -> Option<Combinator> {
+    let mut term = term.clone();
+    for _ in 0..max_steps {
+        match eval1(&term) {
+            Some(next_term) => term = next_term,
+            None => return Some(term),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eval() {
+        let app = Combinator::app;
+        let x = || Combinator::Var(0);
+        let y = || Combinator::Var(1);
+        // S K K is the identity
+        let skk = app(app(Combinator::S, Combinator::K), Combinator::K);
+        assert_eq!(normalize(&app(skk, x()), 10), Some(x()));
+        assert_eq!(normalize(&app(app(Combinator::K, x()), y()), 10), Some(x()));
+        // reduces inside the arguments of a variable
+        let term = app(x(), app(Combinator::I, y()));
+        assert_eq!(eval1(&term), Some(app(x(), y())));
+        assert_eq!(term.to_string(), "#0 (I #1)");
+        // S I I (S I I) reduces to itself
+        let sii = app(app(Combinator::S, Combinator::I), Combinator::I);
+        assert_eq!(normalize(&app(sii.clone(), sii), 100), None);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod eval;
+pub mod term;
+pub mod translate;
+
+pub use term::Combinator;
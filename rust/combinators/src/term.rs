@@ -0,0 +1,35 @@
+use std::fmt::{self, Formatter};
+
+// Terms of combinatory logic over S, K and I. A free variable of the lambda
+// term a combinator was translated from keeps its de Bruijn index.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Combinator {
+    S,
+    K,
+    I,
+    Var(usize),
+    App(Box<Combinator>, Box<Combinator>),
+}
+
+impl Combinator {
+    pub fn app(left: Combinator, right: Combinator) -> Self {
+        Combinator::App(Box::new(left), Box::new(right))
+    }
+}
+
+// application associates to the left, so only an application on the right
+// needs parentheses
+impl fmt::Display for Combinator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Combinator::S => write!(f, "S"),
+            Combinator::K => write!(f, "K"),
+            Combinator::I => write!(f, "I"),
+            Combinator::Var(index) => write!(f, "#{}", index),
+            Combinator::App(left, right) => match right.as_ref() {
+                Combinator::App(_, _) => write!(f, "{} ({})", left, right),
+                _ => write!(f, "{} {}", left, right),
+            },
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::rc::Rc;
+
+use untyped_lambda::nameless::NamelessTerm;
+
+use crate::term::Combinator;
+
+// Translation from lambda terms to combinators by bracket abstraction, and
+// back by reading S, K and I as the lambda terms they abbreviate.
+
+// whether the variable with index `index` occurs in `term`
+fn occurs(term: &Combinator, index: usize) -> bool {
+    match term {
+        Combinator::Var(i) => *i == index,
+        Combinator::App(left, right) => occurs(left, index) || occurs(right, index),
+        _ => false,
+    }
+}
+
+// the variables above 0 move one binder out
+fn unshift(term: &Combinator) -> Combinator {
+    match term {
+        Combinator::Var(index) => Combinator::Var(index - 1),
+        Combinator::App(left, right) => Combinator::app(unshift(left), unshift(right)),
+        _ => term.clone(),
+    }
+}
+
+// [x] c, eliminating the variable with index 0:
+//
+//   [x] x     = I
+//   [x] c     = K c            if x does not occur in c
+//   [x] c1 c2 = S ([x] c1) ([x] c2)
+fn abstraction(term: &Combinator) -> Combinator {
+    match term {
+        Combinator::Var(0) => Combinator::I,
+        _ if !occurs(term, 0) => Combinator::app(Combinator::K, unshift(term)),
+        Combinator::App(left, right) => Combinator::app(
+            Combinator::app(Combinator::S, abstraction(left)),
+            abstraction(right),
+        ),
+        _ => unreachable!("only variables and applications mention a variable"),
+    }
+}
+
+pub fn to_combinator(term: &NamelessTerm) -> Combinator {
+    match term {
+        NamelessTerm::TmVar(index) => Combinator::Var(*index),
+        NamelessTerm::TmAbs(_, body) => abstraction(&to_combinator(body)),
+        NamelessTerm::TmApp(left, right) => {
+            Combinator::app(to_combinator(left), to_combinator(right))
+        }
+    }
+}
+
+fn var(index: usize) -> NamelessTerm {
+    NamelessTerm::TmVar(index)
+}
+
+fn abs(hint: &str, body: NamelessTerm) -> NamelessTerm {
+    NamelessTerm::TmAbs(hint.into(), Rc::new(body))
+}
+
+fn app(left: NamelessTerm, right: NamelessTerm) -> NamelessTerm {
+    NamelessTerm::TmApp(Rc::new(left), Rc::new(right))
+}
+
+pub fn to_lambda(term: &Combinator) -> NamelessTerm {
+    match term {
+        // lambda x.lambda y.lambda z.x z (y z)
+        Combinator::S => abs(
+            "x",
+            abs("y", abs("z", app(app(var(2), var(0)), app(var(1), var(0))))),
+        ),
+        // lambda x.lambda y.x
+        Combinator::K => abs("x", abs("y", var(1))),
+        Combinator::I => abs("x", var(0)),
+        Combinator::Var(index) => var(*index),
+        Combinator::App(left, right) => app(to_lambda(left), to_lambda(right)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use untyped_lambda::{
+        church::{self, apply, boolean, numeral, readback_numeral},
+        nbe,
+    };
+
+    use super::*;
+    use crate::eval::normalize;
+
+    const MAX_STEPS: usize = 10_000;
+
+    // the beta normal form, with every binder renamed to `x`
+    fn normal_form(term: &NamelessTerm) -> NamelessTerm {
+        fn erase(term: &NamelessTerm) -> NamelessTerm {
+            match term {
+                NamelessTerm::TmAbs(_, body) => abs("x", erase(body)),
+                NamelessTerm::TmApp(left, right) => app(erase(left), erase(right)),
+                term => term.clone(),
+            }
+        }
+        erase(&nbe::normalize(term))
+    }
+
+    #[test]
+    fn test_to_combinator() {
+        assert_eq!(to_combinator(&abs("x", var(0))), Combinator::I);
+        assert_eq!(to_combinator(&boolean(true)).to_string(), "S (K K) I");
+        // a numeral applied to two free variables computes `s (s (s z))`
+        let term = apply(church::plus(), vec![numeral(1), numeral(2), var(1), var(0)]);
+        let three = (0..3).fold(Combinator::Var(0), |term, _| {
+            Combinator::app(Combinator::Var(1), term)
+        });
+        assert_eq!(normalize(&to_combinator(&term), MAX_STEPS), Some(three));
+    }
+
+    // translating, reducing in combinatory logic and translating back ends at
+    // a term with the same beta normal form as the original
+    #[test]
+    fn test_reduction_commutes_with_translation() {
+        let terms = vec![
+            apply(church::plus(), vec![numeral(2), numeral(3)]),
+            apply(church::times(), vec![numeral(2), numeral(3)]),
+            apply(church::power(), vec![numeral(2), numeral(3)]),
+            apply(church::test(), vec![boolean(false), numeral(1), numeral(2)]),
+            apply(
+                church::fst(),
+                vec![apply(church::pair(), vec![numeral(1), boolean(true)])],
+            ),
+            abs("x", apply(var(0), vec![abs("y", app(var(1), var(0)))])),
+        ];
+        for term in terms {
+            let reduced = normalize(&to_combinator(&term), MAX_STEPS).unwrap();
+            assert_eq!(
+                normal_form(&to_lambda(&reduced)),
+                normal_form(&term),
+                "{}",
+                reduced
+            );
+        }
+        let power = apply(church::power(), vec![numeral(2), numeral(3)]);
+        let reduced = normalize(&to_combinator(&power), MAX_STEPS).unwrap();
+        assert_eq!(
+            readback_numeral(&nbe::normalize(&to_lambda(&reduced))),
+            Some(8)
+        );
+    }
+}
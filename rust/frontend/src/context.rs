@@ -0,0 +1,48 @@
+// Typing context Γ of the typed chapter crates, the innermost binding is at
+// de Bruijn index 0. `T` is the type of the language.
+#[derive(Clone, Debug)]
+pub struct Context<T> {
+    bindings: Vec<(String, T)>,
+}
+
+// without the `T: Default` bound of the derive
+impl<T> Default for Context<T> {
+    fn default() -> Self {
+        Context { bindings: vec![] }
+    }
+}
+
+impl<T: Clone> Context<T> {
+    pub fn push(&mut self, name: String, typ: T) {
+        self.bindings.push((name, typ));
+    }
+
+    pub fn pop(&mut self) {
+        self.bindings.pop();
+    }
+
+    pub fn get_type(&self, index: usize) -> Option<T> {
+        self.bindings
+            .iter()
+            .rev()
+            .nth(index)
+            .map(|(_, typ)| typ.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context() {
+        let mut ctx = Context::default();
+        ctx.push("x".to_string(), "Nat");
+        ctx.push("y".to_string(), "Bool");
+        assert_eq!(ctx.get_type(0), Some("Bool"));
+        assert_eq!(ctx.get_type(1), Some("Nat"));
+        assert_eq!(ctx.get_type(2), None);
+        ctx.pop();
+        assert_eq!(ctx.get_type(0), Some("Nat"));
+    }
+}
@@ -0,0 +1,77 @@
+// Graphviz DOT output of syntax trees, for `dot -Tsvg` and similar tools
+
+use crate::visit::Node;
+
+// What a node shows in a DOT graph: its constructor and the fields that are
+// not subterms, such as the binder name and type of an abstraction
+pub trait Label {
+    fn label(&self) -> String;
+}
+
+// `text` as a DOT string literal
+pub fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// One DOT node per subterm, numbered in preorder, with edges from every node
+// to its children in order. A heap stack keeps deep terms from overflowing.
+pub fn to_dot<N: Node + Label>(node: &N) -> String {
+    let mut out = String::from("digraph term {\n");
+    let mut next = 0;
+    let mut pending = vec![(node, None)];
+    while let Some((node, parent)) = pending.pop() {
+        let id = next;
+        next += 1;
+        out += &format!("  n{} [label={}];\n", id, quote(&node.label()));
+        if let Some(parent) = parent {
+            out += &format!("  n{} -> n{};\n", parent, id);
+        }
+        let children = node.children().into_iter().rev();
+        pending.extend(children.map(|(_, child)| (child, Some(id))));
+    }
+    out + "}\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    enum Term {
+        Var(&'static str),
+        App(Box<Term>, Box<Term>),
+    }
+
+    impl Node for Term {
+        fn children(&self) -> Vec<(usize, &Self)> {
+            match self {
+                Term::Var(_) => vec![],
+                Term::App(left, right) => vec![(0, left), (0, right)],
+            }
+        }
+    }
+
+    impl Label for Term {
+        fn label(&self) -> String {
+            match self {
+                Term::Var(name) => name.to_string(),
+                Term::App(_, _) => "app".to_string(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let var = |name| Box::new(Term::Var(name));
+        let term = Term::App(Box::new(Term::App(var("f"), var("\"x\""))), var("y"));
+        assert_eq!(
+            to_dot(&term),
+            "digraph term {\n  \
+               n0 [label=\"app\"];\n  \
+               n1 [label=\"app\"];\n  n0 -> n1;\n  \
+               n2 [label=\"f\"];\n  n1 -> n2;\n  \
+               n3 [label=\"\\\"x\\\"\"];\n  n1 -> n3;\n  \
+               n4 [label=\"y\"];\n  n0 -> n4;\n\
+             }\n"
+        );
+    }
+}
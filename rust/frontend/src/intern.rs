@@ -0,0 +1,112 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Formatter},
+    sync::{Mutex, MutexGuard, OnceLock},
+};
+
+// An interned identifier, comparing two symbols compares integers instead of
+// strings. There is one interner for the whole program, so a symbol in a term
+// prints its name without a table at hand. Names are never freed, there are
+// only as many of them as distinct identifiers in the sources read.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+#[derive(Default)]
+struct Interner {
+    symbols: HashMap<&'static str, Symbol>,
+    names: Vec<&'static str>,
+}
+
+fn interner() -> MutexGuard<'static, Interner> {
+    static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+    let interner = INTERNER.get_or_init(Default::default);
+    interner
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl Symbol {
+    pub fn intern(name: &str) -> Symbol {
+        let mut interner = interner();
+        if let Some(symbol) = interner.symbols.get(name) {
+            return *symbol;
+        }
+        let symbol = Symbol(interner.names.len() as u32);
+        let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+        interner.names.push(name);
+        interner.symbols.insert(name, symbol);
+        symbol
+    }
+
+    // the symbol of a name that was interned before, without adding it
+    pub fn get(name: &str) -> Option<Symbol> {
+        interner().symbols.get(name).copied()
+    }
+
+    pub fn as_str(self) -> &'static str {
+        interner().names[self.0 as usize]
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Symbol::intern(name)
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// serialized as the name, the symbol number depends on the order of interning
+#[cfg(feature = "serde")]
+impl serde::Serialize for Symbol {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Symbol {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(Symbol::intern(&name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern() {
+        let x = Symbol::intern("x");
+        let y = Symbol::intern("y");
+        assert_ne!(x, y);
+        assert_eq!(Symbol::intern("x"), x);
+        assert_eq!(Symbol::get("y"), Some(y));
+        assert_eq!(Symbol::get("never interned"), None);
+        assert_eq!(y.as_str(), "y");
+        assert_eq!(format!("{} {:?}", x, x), "x \"x\"");
+    }
+}
@@ -0,0 +1,33 @@
+// LaTeX output of program text, for course notes built from checker runs
+
+// `text` set in typewriter type, with the characters TeX treats specially
+// escaped
+pub fn texttt(text: &str) -> String {
+    let mut out = String::from("\\texttt{");
+    for c in text.chars() {
+        match c {
+            '\\' => out += "\\textbackslash{}",
+            '^' => out += "\\textasciicircum{}",
+            '~' => out += "\\textasciitilde{}",
+            '{' | '}' | '_' | '#' | '%' | '&' | '$' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out + "}"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_texttt() {
+        assert_eq!(
+            texttt("{x=0}.x_1 \\ #0"),
+            "\\texttt{\\{x=0\\}.x\\_1 \\textbackslash{} \\#0}"
+        );
+    }
+}
@@ -0,0 +1,361 @@
+use std::fmt::{self, Formatter};
+
+use crate::span::Span;
+
+// Tokens shared by the hand-written parsers. Keywords are words, each parser
+// decides which words it reserves, so `iffy` is never split into `if` and `fy`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Token<'a> {
+    // a letter or `_` followed by letters, digits and `_`
+    Word(&'a str),
+    Number(&'a str),
+    // the text between the quotes of a `"`-delimited string, which has no
+    // escapes
+    Str(&'a str),
+    // one of the symbols the lexer was created with, the longest one matches
+    Symbol(&'static str),
+    Eof,
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(text) | Token::Number(text) => write!(f, "`{}`", text),
+            Token::Str(text) => write!(f, "`\"{}\"`", text),
+            Token::Symbol(symbol) => write!(f, "`{}`", symbol),
+            Token::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+// What the parser wanted and the token it got instead
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub expected: String,
+    pub found: String,
+    pub span: Span,
+    // 1-based position of the start of `span`
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: expected {}, found {}",
+            self.line, self.column, self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+pub type LexResult<T> = Result<T, SyntaxError>;
+
+// How deeply the parsers nest terms before they give up, the recursive
+// descent would overflow the stack on deeper input
+pub const MAX_DEPTH: usize = 256;
+
+// An on-demand token stream: only the tokens a parser asks for are read, so
+// `rest` is the untouched input after the last consumed token
+#[derive(Clone, Debug)]
+pub struct Lexer<'a> {
+    source: &'a str,
+    offset: usize,
+    symbols: Vec<&'static str>,
+    ignore_case: bool,
+    peeked: Option<(Token<'a>, Span)>,
+    depth: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(source: &'a str, symbols: &[&'static str]) -> Self {
+        let mut symbols = symbols.to_vec();
+        symbols.sort_by_key(|symbol| std::cmp::Reverse(symbol.len()));
+        Lexer {
+            source,
+            offset: 0,
+            symbols,
+            ignore_case: false,
+            peeked: None,
+            depth: 0,
+        }
+    }
+
+    // keywords match regardless of case, `IF` is `if`
+    pub fn ignore_case(mut self) -> Self {
+        self.ignore_case = true;
+        self
+    }
+
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    // the input after the last consumed token
+    pub fn rest(&self) -> &'a str {
+        &self.source[self.offset..]
+    }
+
+    // the end of the last consumed token
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn peek(&mut self) -> LexResult<(Token<'a>, Span)> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.read()?);
+        }
+        Ok(self.peeked.unwrap())
+    }
+
+    pub fn next_token(&mut self) -> LexResult<(Token<'a>, Span)> {
+        let token = self.peek()?;
+        self.peeked = None;
+        self.offset = token.1.end;
+        Ok(token)
+    }
+
+    pub fn is_keyword(&self, token: Token<'_>, keyword: &str) -> bool {
+        match token {
+            Token::Word(word) if self.ignore_case => word.eq_ignore_ascii_case(keyword),
+            Token::Word(word) => word == keyword,
+            _ => false,
+        }
+    }
+
+    // consumes the next token if it is `keyword`
+    pub fn eat_keyword(&mut self, keyword: &str) -> LexResult<Option<Span>> {
+        let (token, span) = self.peek()?;
+        if self.is_keyword(token, keyword) {
+            self.next_token()?;
+            Ok(Some(span))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn eat_symbol(&mut self, symbol: &str) -> LexResult<Option<Span>> {
+        match self.peek()? {
+            (Token::Symbol(found), span) if found == symbol => {
+                self.next_token()?;
+                Ok(Some(span))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn expect_keyword(&mut self, keyword: &str) -> LexResult<Span> {
+        match self.eat_keyword(keyword)? {
+            Some(span) => Ok(span),
+            None => Err(self.unexpected(&format!("`{}`", keyword))),
+        }
+    }
+
+    pub fn expect_symbol(&mut self, symbol: &str) -> LexResult<Span> {
+        match self.eat_symbol(symbol)? {
+            Some(span) => Ok(span),
+            None => Err(self.unexpected(&format!("`{}`", symbol))),
+        }
+    }
+
+    // a word that is none of `keywords`, `what` names it in the error
+    pub fn expect_word(&mut self, what: &str, keywords: &[&str]) -> LexResult<(&'a str, Span)> {
+        match self.peek()? {
+            (Token::Word(word), span)
+                if !keywords
+                    .iter()
+                    .any(|keyword| self.is_keyword(Token::Word(word), keyword)) =>
+            {
+                self.next_token()?;
+                Ok((word, span))
+            }
+            _ => Err(self.unexpected(what)),
+        }
+    }
+
+    pub fn expect_number(&mut self) -> LexResult<(&'a str, Span)> {
+        match self.peek()? {
+            (Token::Number(number), span) => {
+                self.next_token()?;
+                Ok((number, span))
+            }
+            _ => Err(self.unexpected("a number")),
+        }
+    }
+
+    // a string such as the path of an import, without its quotes
+    pub fn expect_string(&mut self) -> LexResult<(&'a str, Span)> {
+        match self.peek()? {
+            (Token::Str(text), span) => {
+                self.next_token()?;
+                Ok((text, span))
+            }
+            _ => Err(self.unexpected("a string")),
+        }
+    }
+
+    // skips everything up to and including the next `symbol`, or to the end
+    // of the input, so a parser can resume after an error
+    pub fn skip_past(&mut self, symbol: &str) {
+        self.peeked = None;
+        self.depth = 0;
+        self.offset = match self.rest().find(symbol) {
+            Some(index) => self.offset + index + symbol.len(),
+            None => self.source.len(),
+        };
+    }
+
+    // runs `parse` one level deeper, nesting past MAX_DEPTH is an error at
+    // the next token
+    pub fn nested<T, E: From<SyntaxError>>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let depth = self.depth;
+        self.deepen()?;
+        let result = parse(self);
+        self.depth = depth;
+        result
+    }
+
+    // one level deeper until the enclosing `nested` returns, for a loop that
+    // wraps each term it parses around the ones before it, as an application
+    // does its function
+    pub fn deepen(&mut self) -> LexResult<()> {
+        if self.depth == MAX_DEPTH {
+            let expected = format!("at most {} nested terms", MAX_DEPTH);
+            return Err(self.unexpected(&expected));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    // an error at the next token, or the error reading it
+    pub fn unexpected(&mut self, expected: &str) -> SyntaxError {
+        match self.peek() {
+            Ok((token, span)) => self.error_at(expected, &token.to_string(), span),
+            Err(error) => error,
+        }
+    }
+
+    pub fn error_at(&self, expected: &str, found: &str, span: Span) -> SyntaxError {
+        let (line, column) = span.line_col(self.source);
+        SyntaxError {
+            expected: expected.to_string(),
+            found: found.to_string(),
+            span,
+            line,
+            column,
+        }
+    }
+
+    fn read(&self) -> LexResult<(Token<'a>, Span)> {
+        let rest = &self.source[self.offset..];
+        let start = self.offset + (rest.len() - rest.trim_start().len());
+        let rest = &self.source[start..];
+        let token_end = |len: usize| Span::new(start, start + len);
+
+        let first = match rest.chars().next() {
+            Some(first) => first,
+            None => return Ok((Token::Eof, token_end(0))),
+        };
+        if first.is_ascii_alphabetic() || first == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            return Ok((Token::Word(&rest[..len]), token_end(len)));
+        }
+        if first.is_ascii_digit() {
+            let len = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            return Ok((Token::Number(&rest[..len]), token_end(len)));
+        }
+        if let Some(body) = rest.strip_prefix('"') {
+            return match body.find('"') {
+                Some(len) => Ok((Token::Str(&body[..len]), token_end(len + 2))),
+                None => {
+                    let end = Span::new(self.source.len(), self.source.len());
+                    Err(self.error_at("a closing `\"`", "end of input", end))
+                }
+            };
+        }
+        match self.symbols.iter().find(|symbol| rest.starts_with(*symbol)) {
+            Some(symbol) => Ok((Token::Symbol(symbol), token_end(symbol.len()))),
+            None => Err(self.error_at(
+                "a token",
+                &format!("`{}`", first),
+                token_end(first.len_utf8()),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens() {
+        let mut lexer = Lexer::new("iffy ->x1 - 42;rest", &["-", "->", ";"]);
+        assert_eq!(lexer.next_token().unwrap().0, Token::Word("iffy"));
+        assert_eq!(lexer.next_token().unwrap().0, Token::Symbol("->"));
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            (Token::Word("x1"), Span::new(7, 9))
+        );
+        assert_eq!(lexer.expect_symbol("-"), Ok(Span::new(10, 11)));
+        assert_eq!(lexer.expect_number().unwrap().0, "42");
+        assert!(lexer.eat_symbol(";").unwrap().is_some());
+        assert_eq!(lexer.rest(), "rest");
+        assert_eq!(
+            lexer.expect_symbol(";").unwrap_err().to_string(),
+            "1:16: expected `;`, found `rest`"
+        );
+
+        let mut lexer = Lexer::new("  IF\n  #", &[]).ignore_case();
+        assert_eq!(lexer.expect_keyword("if"), Ok(Span::new(2, 4)));
+        assert_eq!(
+            lexer.next_token().unwrap_err().to_string(),
+            "2:3: expected a token, found `#`"
+        );
+
+        let mut lexer = Lexer::new(r#" "a/b.f" x "c"#, &[]);
+        assert_eq!(lexer.expect_string(), Ok(("a/b.f", Span::new(1, 8))));
+        assert_eq!(
+            lexer.expect_string().unwrap_err().to_string(),
+            "1:10: expected a string, found `x`"
+        );
+        lexer.next_token().unwrap();
+        assert_eq!(
+            lexer.expect_string().unwrap_err().to_string(),
+            "1:14: expected a closing `\"`, found end of input"
+        );
+
+        let mut lexer = Lexer::new("x # y; z", &[";"]);
+        lexer.next_token().unwrap();
+        assert!(lexer.next_token().is_err());
+        lexer.skip_past(";");
+        assert_eq!(lexer.next_token().unwrap().0, Token::Word("z"));
+        lexer.skip_past(";");
+        assert_eq!(lexer.next_token().unwrap().0, Token::Eof);
+    }
+
+    #[test]
+    fn test_nesting() {
+        let mut lexer = Lexer::new("x", &[]);
+        let deepen = |lexer: &mut Lexer, levels| (0..levels).try_for_each(|_| lexer.deepen());
+        assert_eq!(lexer.nested(|lexer| deepen(lexer, MAX_DEPTH - 1)), Ok(()));
+        // the levels a loop adds end with the enclosing `nested`
+        assert_eq!(lexer.nested(|lexer| deepen(lexer, MAX_DEPTH - 1)), Ok(()));
+        assert_eq!(
+            lexer
+                .nested(|lexer| deepen(lexer, MAX_DEPTH))
+                .unwrap_err()
+                .expected,
+            "at most 256 nested terms"
+        );
+    }
+}
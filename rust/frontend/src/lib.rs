@@ -0,0 +1,13 @@
+// Parsing and syntax tree utilities shared by the chapter crates: the token
+// stream the hand-written parsers read from, source spans, interned
+// identifiers, a visitor over terms with binders, Graphviz and LaTeX output,
+// how evaluation ended and the typing context of the typed languages.
+
+pub mod context;
+pub mod dot;
+pub mod intern;
+pub mod latex;
+pub mod lexer;
+pub mod outcome;
+pub mod span;
+pub mod visit;
@@ -0,0 +1,76 @@
+// How evaluation ended, in the same terms for every language
+
+use std::fmt::{self, Display, Formatter};
+
+// `V` is what a language evaluates to and `T` its terms: evaluation ends at a
+// value, at a stuck term, a normal form that is not a value (TAPL definition
+// 3.5.15), or after `steps` reductions with the term it got to
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome<V, T> {
+    Value(V),
+    Stuck(T),
+    OutOfFuel { steps: usize, term: T },
+}
+
+impl<V, T> Outcome<V, T> {
+    // `None` unless evaluation reached a value
+    pub fn value(self) -> Option<V> {
+        match self {
+            Outcome::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl<V: Display, T: Display> Display for Outcome<V, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Value(value) => write!(f, "{}", value),
+            Outcome::Stuck(term) => write!(f, "stuck at {}", term),
+            Outcome::OutOfFuel { steps, .. } => {
+                write!(f, "no normal form after {} steps", steps)
+            }
+        }
+    }
+}
+
+// Takes up to `max_steps` small steps from `term`, `step` returns `None` for
+// a normal form and `value` turns the normal forms that are values into one
+pub fn evaluate<T: Clone, V>(
+    term: &T,
+    max_steps: usize,
+    step: impl Fn(&T) -> Option<T>,
+    value: impl Fn(&T) -> Option<V>,
+) -> Outcome<V, T> {
+    let mut term = term.clone();
+    for steps in 0..=max_steps {
+        match step(&term) {
+            Some(next_term) if steps < max_steps => term = next_term,
+            Some(_) => return Outcome::OutOfFuel { steps, term },
+            None => break,
+        }
+    }
+    match value(&term) {
+        Some(value) => Outcome::Value(value),
+        None => Outcome::Stuck(term),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate() {
+        // counts down by two, 0 is the only value and 1 is stuck
+        let run = |n: u32, max_steps| {
+            let step = |n: &u32| n.checked_sub(2);
+            evaluate(&n, max_steps, step, |n| Some(*n).filter(|n| *n == 0))
+        };
+        assert_eq!(run(4, 2), Outcome::Value(0));
+        assert_eq!(run(5, 10), Outcome::Stuck(1));
+        assert_eq!(run(6, 2), Outcome::OutOfFuel { steps: 2, term: 2 });
+        assert_eq!(run(6, 2).to_string(), "no normal form after 2 steps");
+        assert_eq!(run(5, 10).value(), None);
+    }
+}
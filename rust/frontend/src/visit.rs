@@ -0,0 +1,94 @@
+// A syntax tree that lists its direct subterms, each together with the number
+// of variables bound around it, e.g. 1 for the body of an abstraction and 0
+// in languages without binders
+pub trait Node {
+    fn children(&self) -> Vec<(usize, &Self)>;
+}
+
+// Sees every node in preorder with the number of binders above it, returning
+// false skips the subterms of that node
+pub trait Visitor<N: Node> {
+    fn visit(&mut self, node: &N, depth: usize) -> bool;
+}
+
+impl<N: Node, F: FnMut(&N, usize) -> bool> Visitor<N> for F {
+    fn visit(&mut self, node: &N, depth: usize) -> bool {
+        self(node, depth)
+    }
+}
+
+pub fn walk<N: Node>(node: &N, visitor: &mut impl Visitor<N>) {
+    walk_at(node, 0, visitor)
+}
+
+fn walk_at<N: Node>(node: &N, depth: usize, visitor: &mut impl Visitor<N>) {
+    if visitor.visit(node, depth) {
+        for (binders, child) in node.children() {
+            walk_at(child, depth + binders, visitor);
+        }
+    }
+}
+
+// whether `pred` holds for some node, `depth` is the number of binders already
+// around `node`
+pub fn any<N: Node>(node: &N, depth: usize, pred: &mut impl FnMut(&N, usize) -> bool) -> bool {
+    pred(node, depth)
+        || node
+            .children()
+            .into_iter()
+            .any(|(binders, child)| any(child, depth + binders, pred))
+}
+
+// the number of nodes in the tree
+pub fn size<N: Node>(node: &N) -> usize {
+    let mut size = 0;
+    walk(node, &mut |_: &N, _| {
+        size += 1;
+        true
+    });
+    size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    enum Term {
+        Var(usize),
+        Abs(Box<Term>),
+        App(Box<Term>, Box<Term>),
+    }
+
+    impl Node for Term {
+        fn children(&self) -> Vec<(usize, &Self)> {
+            match self {
+                Term::Var(_) => vec![],
+                Term::Abs(body) => vec![(1, body)],
+                Term::App(left, right) => vec![(0, left), (0, right)],
+            }
+        }
+    }
+
+    #[test]
+    fn test_walk() {
+        // lambda. 0 (lambda. 1)
+        let term = Term::Abs(Box::new(Term::App(
+            Box::new(Term::Var(0)),
+            Box::new(Term::Abs(Box::new(Term::Var(1)))),
+        )));
+        assert_eq!(size(&term), 5);
+
+        let mut depths = vec![];
+        walk(&term, &mut |term: &Term, depth| {
+            if let Term::Var(index) = term {
+                depths.push((*index, depth));
+            }
+            true
+        });
+        assert_eq!(depths, vec![(0, 1), (1, 2)]);
+
+        // both variables refer to the outermost binder
+        let bound_outside = |term: &Term, depth| matches!(term, Term::Var(i) if *i + 1 == depth);
+        assert!(any(&term, 0, &mut |term, depth| bound_outside(term, depth)));
+    }
+}
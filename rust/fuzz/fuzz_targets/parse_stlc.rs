@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use simply_typed_lambda::parser::Parser;
+
+fuzz_target!(|source: &str| {
+    let _ = Parser::new().parse_with_spans(source);
+});
@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// typed_arith reuses the arith grammar, so this also typechecks and runs
+// every program that parses
+fuzz_target!(|source: &str| {
+    let _ = typed_arith::eval(source);
+});
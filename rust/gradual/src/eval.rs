@@ -0,0 +1,242 @@
+use std::fmt::{self, Formatter};
+
+use crate::{
+    parser::Term,
+    substitute::substitute_top,
+    typing::{cast, consistent, Type},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EvalError {
+    NoRuleApplies,
+    // a value injected into Dyn from the first type is cast to the second
+    CastFailed(Type, Type),
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::NoRuleApplies => write!(f, "no rule applies"),
+            EvalError::CastFailed(from, to) => {
+                write!(f, "cannot cast a value of type {} to {}", from, to)
+            }
+        }
+    }
+}
+
+fn is_numeric_val(term: &Term) -> bool {
+    match term {
+        Term::TmZero => true,
+        Term::TmSucc(term) => is_numeric_val(term),
+        _ => false,
+    }
+}
+
+// Bool, Nat and Dyn->Dyn, the types a value of type Dyn is tagged with
+fn is_ground(typ: &Type) -> bool {
+    match typ {
+        Type::Boolean | Type::Number => true,
+        Type::Arrow(param, ret) => **param == Type::Dyn && **ret == Type::Dyn,
+        Type::Dyn => false,
+    }
+}
+
+// A value of type Dyn is a value injected from a ground type, and a function
+// cast to another arrow type is a value that casts its argument and result
+// when it is applied
+pub fn is_val(term: &Term) -> bool {
+    match term {
+        Term::TmTrue | Term::TmFalse | Term::TmAbs(_, _, _) => true,
+        Term::TmCast(term, from, Type::Dyn) => is_ground(from) && is_val(term),
+        Term::TmCast(term, Type::Arrow(_, _), Type::Arrow(_, _)) => is_val(term),
+        _ => is_numeric_val(term),
+    }
+}
+
+// the value without the injections into Dyn around it
+pub fn unbox(value: &Term) -> &Term {
+    match value {
+        Term::TmCast(value, _, Type::Dyn) => unbox(value),
+        _ => value,
+    }
+}
+
+// a cast of a value that is not already a value itself
+fn eval_cast(value: &Term, from: &Type, to: &Type) -> Result<Term, EvalError> {
+    match (from, to, value) {
+        // an arrow goes into Dyn through Dyn->Dyn
+        (Type::Arrow(_, _), Type::Dyn, _) => {
+            let arrow = Type::Arrow(Box::new(Type::Dyn), Box::new(Type::Dyn));
+            Ok(cast(cast(value.clone(), from, &arrow), &arrow, to))
+        }
+        // a projection out of Dyn checks the tag of the injection
+        (Type::Dyn, _, Term::TmCast(value, tag, Type::Dyn)) => {
+            if consistent(tag, to) {
+                Ok(cast(value.as_ref().clone(), tag, to))
+            } else {
+                Err(EvalError::CastFailed(tag.clone(), to.clone()))
+            }
+        }
+        _ => Err(EvalError::NoRuleApplies),
+    }
+}
+
+pub fn eval1(term: &Term) -> Result<Term, EvalError> {
+    let boxed = |term: Result<Term, EvalError>| term.map(Box::new);
+    match term {
+        Term::TmSucc(term) => Ok(Term::TmSucc(boxed(eval1(term))?)),
+        Term::TmPred(term) => match term.as_ref() {
+            Term::TmZero => Ok(Term::TmZero),
+            Term::TmSucc(number) if is_numeric_val(number) => Ok(number.as_ref().clone()),
+            _ => Ok(Term::TmPred(boxed(eval1(term))?)),
+        },
+        Term::TmIsZero(term) => match term.as_ref() {
+            Term::TmZero => Ok(Term::TmTrue),
+            Term::TmSucc(number) if is_numeric_val(number) => Ok(Term::TmFalse),
+            _ => Ok(Term::TmIsZero(boxed(eval1(term))?)),
+        },
+        Term::TmApp(left, right) => match left.as_ref() {
+            Term::TmAbs(_, _, body) if is_val(right) => Ok(substitute_top(right, body)),
+            // the argument is cast to the parameter type the function was
+            // written with, and the result back to the type it is used at
+            Term::TmCast(function, Type::Arrow(param, ret), Type::Arrow(cast_param, cast_ret))
+                if is_val(left) && is_val(right) =>
+            {
+                let argument = cast(right.as_ref().clone(), cast_param, param);
+                let result = Term::TmApp(function.clone(), Box::new(argument));
+                Ok(cast(result, ret, cast_ret))
+            }
+            _ if is_val(left) => Ok(Term::TmApp(left.clone(), boxed(eval1(right))?)),
+            _ => Ok(Term::TmApp(boxed(eval1(left))?, right.clone())),
+        },
+        Term::TmIf(if_term, then_term, else_term) => match if_term.as_ref() {
+            Term::TmTrue => Ok(then_term.as_ref().clone()),
+            Term::TmFalse => Ok(else_term.as_ref().clone()),
+            _ => Ok(Term::TmIf(
+                boxed(eval1(if_term))?,
+                then_term.clone(),
+                else_term.clone(),
+            )),
+        },
+        Term::TmLet(name, bound, body) => {
+            if is_val(bound) {
+                Ok(substitute_top(bound, body))
+            } else {
+                Ok(Term::TmLet(
+                    name.clone(),
+                    boxed(eval1(bound))?,
+                    body.clone(),
+                ))
+            }
+        }
+        Term::TmCast(_, _, _) if is_val(term) => Err(EvalError::NoRuleApplies),
+        Term::TmCast(value, from, to) if is_val(value) => eval_cast(value, from, to),
+        Term::TmCast(term, from, to) => {
+            Ok(Term::TmCast(boxed(eval1(term))?, from.clone(), to.clone()))
+        }
+        _ => Err(EvalError::NoRuleApplies),
+    }
+}
+
+pub fn eval(term: &Term) -> Result<Term, EvalError> {
+    let mut term = term.clone();
+    loop {
+        match eval1(&term) {
+            Ok(next_term) => term = next_term,
+            Err(EvalError::NoRuleApplies) => return Ok(term),
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::language::Gradual;
+    use tapl_testkit::{assert_evals_to, Failure, Language};
+
+    fn eval_str(input: &str) -> Result<<Gradual as Language>::Value, Failure> {
+        Gradual::eval(&Gradual::parse(input)?)
+    }
+
+    #[test]
+    fn test_eval() {
+        assert_evals_to!(Gradual, "(lambda x.succ(x)) 0;", "succ(0)");
+        assert_evals_to!(
+            Gradual,
+            "(lambda f:Dyn.f 0) (lambda n:Nat.iszero(n));",
+            "true"
+        );
+        assert_evals_to!(
+            Gradual,
+            "let f = lambda x.if x then 0 else succ(0) in f false;",
+            "succ(0)"
+        );
+        // a function passed where one of type Dyn->Dyn is expected casts its
+        // argument and result on every call
+        assert_evals_to!(
+            Gradual,
+            "(lambda f:Dyn->Dyn.f (f 0)) (lambda n:Nat.succ(n));",
+            "succ(succ(0))"
+        );
+    }
+
+    #[test]
+    fn test_cast_failure() {
+        // the static checker accepts these, the casts fail at runtime
+        assert_eq!(
+            eval_str("(lambda x.succ(x)) true;"),
+            Err(Failure::Eval(
+                "cannot cast a value of type Bool to Nat".to_string()
+            ))
+        );
+        assert_eq!(
+            eval_str("(lambda f:Dyn->Dyn.f true) (lambda n:Nat.succ(n));"),
+            Err(Failure::Eval(
+                "cannot cast a value of type Bool to Nat".to_string()
+            ))
+        );
+        assert_eq!(
+            eval_str("(lambda f.f 0) 0;"),
+            Err(Failure::Eval(
+                "cannot cast a value of type Nat to Dyn->Dyn".to_string()
+            ))
+        );
+        // a cast that is never reached never fails
+        assert_evals_to!(
+            Gradual,
+            "(lambda x.if false then succ(x) else 0) true;",
+            "0"
+        );
+        // the failure is found where the value of type Dyn is used
+        assert_eq!(
+            eval_str("let x = lambda y:Nat.y in (lambda b.if b then 0 else 0) x;"),
+            Err(Failure::Eval(
+                "cannot cast a value of type Dyn->Dyn to Bool".to_string()
+            ))
+        );
+        assert_eq!(
+            eval_str("(lambda f:Dyn->Nat.f 0) (lambda n.(lambda x.x) true);"),
+            Err(Failure::Eval(
+                "cannot cast a value of type Bool to Nat".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_eval_dyn() {
+        // a function goes into Dyn and comes out again as a function
+        assert_evals_to!(
+            Gradual,
+            "(lambda g.g (g 0)) (lambda n:Nat.succ(n));",
+            "succ(succ(0))"
+        );
+        assert_evals_to!(Gradual, "(lambda x.lambda y:Nat.x) true 0;", "true");
+        // a value of type Dyn compares as the value it was injected from
+        assert_evals_to!(Gradual, "(lambda x.x) (succ(0));", "succ(0)");
+        assert_evals_to!(
+            Gradual,
+            "let b = (lambda x.x) false in if b then 0 else pred(succ(0));",
+            "0"
+        );
+    }
+}
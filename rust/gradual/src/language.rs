@@ -0,0 +1,37 @@
+use tapl_testkit::{Failure, Language};
+
+use crate::{
+    context::Context,
+    eval::{eval, unbox},
+    parser::{Parser, Term},
+    typing::insert_casts,
+};
+
+pub struct Gradual;
+
+impl Language for Gradual {
+    type Term = Term;
+    type Value = Term;
+
+    fn parse(input: &str) -> Result<Term, Failure> {
+        Parser::new()
+            .parse(input)
+            .map_err(|error| Failure::Parse(error.to_string()))
+    }
+
+    fn type_check(term: &Term) -> Result<(), Failure> {
+        insert_casts(&mut Context::default(), term)
+            .map(|_| ())
+            .map_err(|error| Failure::Type(error.to_string()))
+    }
+
+    // the program runs with its casts, a value of type Dyn is compared
+    // without its injection
+    fn eval(term: &Term) -> Result<Term, Failure> {
+        let (term, _) = insert_casts(&mut Context::default(), term)
+            .map_err(|error| Failure::Type(error.to_string()))?;
+        eval(&term)
+            .map(|value| unbox(&value).clone())
+            .map_err(|error| Failure::Eval(error.to_string()))
+    }
+}
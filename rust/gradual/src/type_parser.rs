@@ -0,0 +1,69 @@
+use tapl_frontend::lexer::{Lexer, SyntaxError, Token};
+
+use crate::typing::Type;
+
+pub const TYPE_KEYWORDS: [&str; 3] = ["Dyn", "Bool", "Nat"];
+
+fn parse_atom_type(lexer: &mut Lexer) -> Result<Type, SyntaxError> {
+    lexer.nested(|lexer| {
+        let (token, span) = lexer.next_token()?;
+        match token {
+            Token::Word("Dyn") => Ok(Type::Dyn),
+            Token::Word("Bool") => Ok(Type::Boolean),
+            Token::Word("Nat") => Ok(Type::Number),
+            Token::Symbol("(") => {
+                let typ = parse_type(lexer)?;
+                lexer.expect_symbol(")")?;
+                Ok(typ)
+            }
+            _ => Err(lexer.error_at("a type", &token.to_string(), span)),
+        }
+    })
+}
+
+// arrow types are right associative: `A->B->C` is `A->(B->C)`
+pub fn parse_type(lexer: &mut Lexer) -> Result<Type, SyntaxError> {
+    lexer.nested(|lexer| {
+        let typ = parse_atom_type(lexer)?;
+        match lexer.eat_symbol("->")? {
+            Some(_) => Ok(Type::Arrow(Box::new(typ), Box::new(parse_type(lexer)?))),
+            None => Ok(typ),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SYMBOLS;
+
+    fn parse(input: &str) -> Result<(&str, Type), String> {
+        let mut lexer = Lexer::new(input, &SYMBOLS);
+        parse_type(&mut lexer)
+            .map(|typ| (lexer.rest(), typ))
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn test_type() {
+        let arrow = |from, to| Type::Arrow(Box::new(from), Box::new(to));
+        assert_eq!(parse("Dyn"), Ok(("", Type::Dyn)));
+        assert_eq!(
+            parse("Nat->Dyn->Bool"),
+            Ok(("", arrow(Type::Number, arrow(Type::Dyn, Type::Boolean))))
+        );
+        assert_eq!(
+            parse("(Nat->Nat)->Bool."),
+            Ok((".", arrow(arrow(Type::Number, Type::Number), Type::Boolean)))
+        );
+        // type names are whole words
+        assert_eq!(
+            parse("Natural").unwrap_err(),
+            "1:1: expected a type, found `Natural`"
+        );
+        assert_eq!(
+            parse("Nat->").unwrap_err(),
+            "1:6: expected a type, found end of input"
+        );
+    }
+}
@@ -0,0 +1,354 @@
+use std::fmt::{self, Formatter};
+
+use crate::{context::Context, parser::Term};
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum TypeError {
+    // the parameter type and the argument type
+    ParameterTypeMismatch(Type, Type),
+    ArrowTypeExpected(Type),
+    NumberExpected(Type),
+    IfConditionNotBoolean(Type),
+    // the types of the two arms
+    IfBranchesMismatch(Type, Type),
+    UnboundVariable(usize),
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeError::ParameterTypeMismatch(param, argument) => write!(
+                f,
+                "argument of type {} is not consistent with the parameter type {}",
+                argument, param
+            ),
+            TypeError::ArrowTypeExpected(found) => {
+                write!(f, "arrow type expected, found {}", found)
+            }
+            TypeError::NumberExpected(found) => {
+                write!(f, "argument is not a number: found {}", found)
+            }
+            TypeError::IfConditionNotBoolean(found) => {
+                write!(f, "guard of conditional not a boolean: found {}", found)
+            }
+            TypeError::IfBranchesMismatch(then_type, else_type) => write!(
+                f,
+                "arms of conditional have inconsistent types {} and {}",
+                then_type, else_type
+            ),
+            TypeError::UnboundVariable(index) => write!(f, "unbound variable index {}", index),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd)]
+pub enum Type {
+    // the dynamic type, checked at runtime instead
+    Dyn,
+    Boolean,
+    Number,
+    Arrow(Box<Type>, Box<Type>),
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Dyn => write!(f, "Dyn"),
+            Type::Boolean => write!(f, "Bool"),
+            Type::Number => write!(f, "Nat"),
+            Type::Arrow(param, ret) => match param.as_ref() {
+                Type::Arrow(_, _) => write!(f, "({})->{}", param, ret),
+                _ => write!(f, "{}->{}", param, ret),
+            },
+        }
+    }
+}
+
+// S ~ T (Siek and Taha 2006): the types agree where both are known. Dyn is
+// consistent with every type, so unlike equality consistency is not
+// transitive: Nat ~ Dyn and Dyn ~ Bool, but not Nat ~ Bool.
+pub fn consistent(s: &Type, t: &Type) -> bool {
+    match (s, t) {
+        (Type::Dyn, _) | (_, Type::Dyn) => true,
+        (Type::Arrow(s1, s2), Type::Arrow(t1, t2)) => consistent(s1, t1) && consistent(s2, t2),
+        _ => s == t,
+    }
+}
+
+// the most precise type consistent with both of two consistent types, Dyn
+// gives way to whatever the other type knows
+fn meet(s: &Type, t: &Type) -> Type {
+    match (s, t) {
+        (Type::Dyn, t) => t.clone(),
+        (s, Type::Dyn) => s.clone(),
+        (Type::Arrow(s1, s2), Type::Arrow(t1, t2)) => {
+            Type::Arrow(Box::new(meet(s1, t1)), Box::new(meet(s2, t2)))
+        }
+        _ => s.clone(),
+    }
+}
+
+// `<to <= from> term`, or just `term` when the types are the same
+pub fn cast(term: Term, from: &Type, to: &Type) -> Term {
+    if from == to {
+        term
+    } else {
+        Term::TmCast(Box::new(term), from.clone(), to.clone())
+    }
+}
+
+fn dyn_arrow() -> Type {
+    Type::Arrow(Box::new(Type::Dyn), Box::new(Type::Dyn))
+}
+
+// Cast insertion (Siek and Taha 2006): the typing rules of the STLC with
+// consistency in place of equality. Wherever a term meets a type that is
+// only consistent with its own, the term gets a cast to it, which the
+// evaluator checks once the value is known. The result is the term with its
+// casts and its type.
+pub fn insert_casts(ctx: &mut Context, term: &Term) -> Result<(Term, Type), TypeError> {
+    match term {
+        Term::TmTrue | Term::TmFalse => Ok((term.clone(), Type::Boolean)),
+        Term::TmZero => Ok((term.clone(), Type::Number)),
+        Term::TmSucc(argument) | Term::TmPred(argument) | Term::TmIsZero(argument) => {
+            let (argument, typ) = insert_casts(ctx, argument)?;
+            if !consistent(&typ, &Type::Number) {
+                return Err(TypeError::NumberExpected(typ));
+            }
+            let argument = Box::new(cast(argument, &typ, &Type::Number));
+            Ok(match term {
+                Term::TmSucc(_) => (Term::TmSucc(argument), Type::Number),
+                Term::TmPred(_) => (Term::TmPred(argument), Type::Number),
+                _ => (Term::TmIsZero(argument), Type::Boolean),
+            })
+        }
+        Term::TmVar(index) => ctx
+            .get_type(*index)
+            .map(|typ| (term.clone(), typ))
+            .ok_or(TypeError::UnboundVariable(*index)),
+        Term::TmAbs(name, typ, body) => {
+            ctx.push(name.clone(), typ.clone());
+            let body = insert_casts(ctx, body);
+            ctx.pop();
+            let (body, body_type) = body?;
+            Ok((
+                Term::TmAbs(name.clone(), typ.clone(), Box::new(body)),
+                Type::Arrow(Box::new(typ.clone()), Box::new(body_type)),
+            ))
+        }
+        Term::TmApp(left, right) => {
+            let (left, left_type) = insert_casts(ctx, left)?;
+            let (right, right_type) = insert_casts(ctx, right)?;
+            let (left, param_type, return_type) = match left_type {
+                // a function of type Dyn is used as one of type Dyn->Dyn
+                Type::Dyn => (cast(left, &Type::Dyn, &dyn_arrow()), Type::Dyn, Type::Dyn),
+                Type::Arrow(param_type, return_type) => (left, *param_type, *return_type),
+                _ => return Err(TypeError::ArrowTypeExpected(left_type)),
+            };
+            if !consistent(&right_type, &param_type) {
+                return Err(TypeError::ParameterTypeMismatch(param_type, right_type));
+            }
+            let right = cast(right, &right_type, &param_type);
+            Ok((Term::TmApp(Box::new(left), Box::new(right)), return_type))
+        }
+        Term::TmIf(if_term, then_term, else_term) => {
+            let (if_term, if_type) = insert_casts(ctx, if_term)?;
+            if !consistent(&if_type, &Type::Boolean) {
+                return Err(TypeError::IfConditionNotBoolean(if_type));
+            }
+            let (then_term, then_type) = insert_casts(ctx, then_term)?;
+            let (else_term, else_type) = insert_casts(ctx, else_term)?;
+            if !consistent(&then_type, &else_type) {
+                return Err(TypeError::IfBranchesMismatch(then_type, else_type));
+            }
+            let typ = meet(&then_type, &else_type);
+            let term = Term::TmIf(
+                Box::new(cast(if_term, &if_type, &Type::Boolean)),
+                Box::new(cast(then_term, &then_type, &typ)),
+                Box::new(cast(else_term, &else_type, &typ)),
+            );
+            Ok((term, typ))
+        }
+        Term::TmLet(name, bound, body) => {
+            let (bound, bound_type) = insert_casts(ctx, bound)?;
+            ctx.push(name.clone(), bound_type);
+            let body = insert_casts(ctx, body);
+            ctx.pop();
+            let (body, body_type) = body?;
+            Ok((
+                Term::TmLet(name.clone(), Box::new(bound), Box::new(body)),
+                body_type,
+            ))
+        }
+        // a cast already in place stays as it is
+        Term::TmCast(term, from, to) => {
+            let (term, _) = insert_casts(ctx, term)?;
+            Ok((
+                Term::TmCast(Box::new(term), from.clone(), to.clone()),
+                to.clone(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tapl_frontend::lexer::Lexer;
+    use tapl_testkit::assert_type_error;
+
+    use crate::{
+        language::Gradual,
+        parser::{Parser, SYMBOLS},
+        type_parser::parse_type,
+    };
+
+    fn typ(input: &str) -> Type {
+        parse_type(&mut Lexer::new(input, &SYMBOLS)).unwrap()
+    }
+
+    fn insert_str(input: &str) -> Result<(Term, Type), TypeError> {
+        let term = Parser::new().parse(input).unwrap();
+        insert_casts(&mut Context::default(), &term)
+    }
+
+    #[test]
+    fn test_consistent() {
+        assert!(consistent(&Type::Dyn, &Type::Number));
+        assert!(consistent(&typ("Nat->Dyn"), &typ("Dyn->Bool")));
+        assert!(consistent(&typ("Dyn"), &typ("(Nat->Nat)->Bool")));
+        assert!(!consistent(&typ("Nat->Nat"), &typ("Bool->Dyn")));
+        assert!(!consistent(&Type::Number, &Type::Boolean));
+        assert_eq!(meet(&typ("Nat->Dyn"), &typ("Dyn->Bool")), typ("Nat->Bool"));
+    }
+
+    #[test]
+    fn test_insert_casts() {
+        let var = Box::new(Term::TmVar(0));
+        assert_eq!(
+            insert_str("(lambda x.succ(x)) 0;"),
+            Ok((
+                Term::TmApp(
+                    Box::new(Term::TmAbs(
+                        "x".to_string(),
+                        Type::Dyn,
+                        Box::new(Term::TmSucc(Box::new(Term::TmCast(
+                            var,
+                            Type::Dyn,
+                            Type::Number
+                        ))))
+                    )),
+                    Box::new(Term::TmCast(
+                        Box::new(Term::TmZero),
+                        Type::Number,
+                        Type::Dyn
+                    ))
+                ),
+                Type::Number
+            ))
+        );
+        let typ_of = |input: &str| insert_str(input).map(|(_, typ)| typ);
+        assert_eq!(typ_of("lambda f.f 0;"), Ok(typ("Dyn->Dyn")));
+        assert_eq!(typ_of("lambda x.if x then 0 else x;"), Ok(typ("Dyn->Nat")));
+        // a fully annotated term is checked as in the STLC
+        assert_eq!(
+            typ_of("succ(true);"),
+            Err(TypeError::NumberExpected(Type::Boolean))
+        );
+        assert_eq!(
+            typ_of("(lambda f:Nat->Nat.f 0) (lambda b:Bool.b);"),
+            Err(TypeError::ParameterTypeMismatch(
+                typ("Nat->Nat"),
+                typ("Bool->Bool")
+            ))
+        );
+        assert_eq!(
+            typ_of("if true then 0 else false;"),
+            Err(TypeError::IfBranchesMismatch(Type::Number, Type::Boolean))
+        );
+    }
+
+    #[test]
+    fn test_insert_casts_dyn() {
+        let typ_of = |input: &str| insert_str(input).map(|(_, typ)| typ);
+        // Dyn in the type of a function is consistent with the known parts
+        // of the argument
+        assert_eq!(
+            typ_of("(lambda f:Nat->Dyn.f 0) (lambda n:Dyn.iszero(n));"),
+            Ok(Type::Dyn)
+        );
+        assert_eq!(
+            typ_of("(lambda f:Dyn->Nat.f true) (lambda n:Nat.n);"),
+            Ok(Type::Number)
+        );
+        // the arms may differ as long as Dyn covers the difference
+        assert_eq!(
+            typ_of("lambda x.lambda f:Dyn->Bool.if true then f else lambda y:Nat.x;"),
+            Ok(typ("Dyn->(Dyn->Bool)->Nat->Bool"))
+        );
+        assert_eq!(typ_of("let x = true in lambda y.x;"), Ok(typ("Dyn->Bool")));
+        // a function of type Dyn is applied through a cast to Dyn->Dyn
+        assert_eq!(
+            insert_str("lambda f.f;").map(|(term, _)| term),
+            Ok(Term::TmAbs(
+                "f".to_string(),
+                Type::Dyn,
+                Box::new(Term::TmVar(0))
+            ))
+        );
+        assert_eq!(
+            insert_str("lambda f.f f;").map(|(term, _)| term),
+            Ok(Term::TmAbs(
+                "f".to_string(),
+                Type::Dyn,
+                Box::new(Term::TmApp(
+                    Box::new(Term::TmCast(
+                        Box::new(Term::TmVar(0)),
+                        Type::Dyn,
+                        dyn_arrow()
+                    )),
+                    Box::new(Term::TmVar(0))
+                ))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_type_errors() {
+        assert_type_error!(Gradual, "0 true;", "arrow type expected, found Nat");
+        assert_type_error!(
+            Gradual,
+            "if 0 then true else false;",
+            "guard of conditional not a boolean: found Nat"
+        );
+        assert_type_error!(
+            Gradual,
+            "lambda f:Nat->Bool.iszero(f);",
+            "argument is not a number: found Nat->Bool"
+        );
+        // Dyn does not hide a mismatch in the known part of an arrow type
+        assert_type_error!(
+            Gradual,
+            "(lambda f:Dyn->Nat.f 0) (lambda n:Nat.true);",
+            "argument of type Nat->Bool is not consistent with the parameter type Dyn->Nat"
+        );
+        assert_type_error!(
+            Gradual,
+            "lambda x.if x then lambda y:Nat.y else lambda y:Bool.y;",
+            "arms of conditional have inconsistent types Nat->Nat and Bool->Bool"
+        );
+        // errors inside a binder leave the context as it was
+        let mut ctx = Context::default();
+        let term = Parser::new().parse("lambda x.x 0 true;").unwrap();
+        assert_eq!(insert_casts(&mut ctx, &term).map(|_| ()), Ok(()));
+        let term = Parser::new().parse("lambda x:Nat.x 0;").unwrap();
+        assert_eq!(
+            insert_casts(&mut ctx, &term),
+            Err(TypeError::ArrowTypeExpected(Type::Number))
+        );
+        assert_eq!(
+            insert_casts(&mut ctx, &Term::TmVar(0)),
+            Err(TypeError::UnboundVariable(0))
+        );
+    }
+}
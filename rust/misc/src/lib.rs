@@ -1 +1,51 @@
 pub const ALPHABET: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+// Symbols used when printing types and terms, shared by every calculus
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Notation {
+    #[default]
+    Ascii,
+    Unicode,
+}
+
+impl Notation {
+    pub fn arrow(self) -> &'static str {
+        match self {
+            Notation::Ascii => "->",
+            Notation::Unicode => "→",
+        }
+    }
+
+    pub fn product(self) -> &'static str {
+        match self {
+            Notation::Ascii => "*",
+            Notation::Unicode => "×",
+        }
+    }
+
+    // followed directly by the bound type variable, e.g. `Forall X.` or `∀X.`
+    pub fn forall(self) -> &'static str {
+        match self {
+            Notation::Ascii => "Forall ",
+            Notation::Unicode => "∀",
+        }
+    }
+
+    pub fn lambda(self) -> &'static str {
+        match self {
+            Notation::Ascii => "lambda ",
+            Notation::Unicode => "λ",
+        }
+    }
+}
+
+// How a printed variable refers to its binder: by name, by its de Bruijn
+// index under nameless binders (TAPL 6.1), or by both, so `lambda x.lambda
+// y.x` shows as `λ.λ.1` in the nameless representation
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DisplayMode {
+    #[default]
+    Named,
+    DeBruijn,
+    Both,
+}
@@ -0,0 +1,81 @@
+// Untyped arithmetic expressions (TAPL chapters 3 and 4)
+
+use std::fmt::{self, Formatter};
+
+use untyped_arith::{
+    eval::{eval_term, eval_with_fuel},
+    parser,
+    trace::{eval_trace, stuck_at},
+};
+
+use crate::{
+    parse_with, program,
+    trace::{rule_names, Step},
+    Error, ErrorKind, Outcome,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Term(parser::Term);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Value(untyped_arith::eval::Value);
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+pub fn parse(input: &str) -> Result<Term, Error> {
+    parse_with(&program(input), parser::parse).map(Term)
+}
+
+// a stuck term, such as `succ(true)`, is an error
+pub fn eval(term: &Term) -> Result<Value, Error> {
+    eval_term(&term.0)
+        .map(Value)
+        .map_err(|error| Error::new(ErrorKind::Eval, error))
+}
+
+// evaluates by at most `max_steps` small steps
+pub fn run(term: &Term, max_steps: usize) -> Outcome<Value, Term> {
+    match eval_with_fuel(&term.0, max_steps) {
+        Outcome::Value(value) => Outcome::Value(Value(value)),
+        Outcome::Stuck(term) => Outcome::Stuck(Term(term)),
+        Outcome::OutOfFuel { steps, term } => Outcome::OutOfFuel {
+            steps,
+            term: Term(term),
+        },
+    }
+}
+
+// the small steps to a normal form, which is a value or a stuck term
+pub fn trace(term: &Term) -> Result<Vec<Step<Term>>, Error> {
+    Ok(eval_trace(&term.0)
+        .into_iter()
+        .map(|step| Step {
+            rules: rule_names(&step.rules),
+            term: Term(step.term),
+        })
+        .collect())
+}
+
+// the redex a stuck normal form is stuck on and why no rule applies to it
+pub fn stuck(term: &Term) -> Option<String> {
+    stuck_at(&term.0).map(|at| at.to_string())
+}
+
+pub fn pretty(term: &Term) -> String {
+    term.to_string()
+}
+
+// the syntax tree of `term` in Graphviz DOT
+pub fn to_dot(term: &Term) -> String {
+    tapl_frontend::dot::to_dot(&term.0)
+}
@@ -0,0 +1,153 @@
+// An interactive loop for the simply typed lambda calculus:
+//
+//     repl [--json] [--verbose]
+//
+// Every input is a program or a command, `:help` lists them and `:quit` or
+// the end of the input leaves. A program continues on the next line until
+// it ends with `;`. Tab completes commands, keywords and defined names, the
+// history is kept in `~/.tapl_history`. `:step` shows the next reduction
+// step every time Enter is pressed, `q` stops it early. `:prelude` switches
+// to the untyped lambda calculus with its prelude of Church encodings.
+// `--verbose` logs the parse, typecheck and eval phases on standard error.
+// `--json` reads the inputs from standard input without line editing and
+// prints what each gives as lines of JSON objects, as `tapl::json` describes,
+// for an editor plugin to drive the REPL.
+
+use std::{
+    env,
+    io::{self, BufRead},
+    path::PathBuf,
+};
+
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter,
+    history::DefaultHistory, validate::Validator, Context, Editor, Helper,
+};
+use tapl::repl::{is_complete, Repl, Reply};
+
+// the words of `Repl::words` as of the last input
+struct Words(Vec<String>);
+
+impl Completer for Words {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos]
+            .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == ':'))
+            .map_or(0, |i| i + 1);
+        let prefix = &line[start..pos];
+        let words = self.0.iter().filter(|word| word.starts_with(prefix));
+        Ok((start, words.cloned().collect()))
+    }
+}
+
+impl Hinter for Words {
+    type Hint = String;
+}
+
+impl Highlighter for Words {}
+
+impl Validator for Words {}
+
+impl Helper for Words {}
+
+fn history_file() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".tapl_history"))
+}
+
+fn main() -> rustyline::Result<()> {
+    if env::args().skip(1).any(|arg| arg == "--verbose") {
+        tapl::enable_tracing();
+    }
+    if env::args().skip(1).any(|arg| arg == "--json") {
+        run_json();
+        return Ok(());
+    }
+    let mut repl = Repl::new();
+    let mut editor = Editor::<Words, DefaultHistory>::new()?;
+    editor.set_helper(Some(Words(repl.words())));
+    let history = history_file();
+    if let Some(history) = &history {
+        // there is no history before the first run
+        editor.load_history(history).ok();
+    }
+    while let Some(input) = read_input(&mut editor) {
+        editor.add_history_entry(input.trim())?;
+        if input.trim() == ":quit" {
+            break;
+        }
+        match repl.execute(&input) {
+            Ok(Reply::Text(text)) if text.is_empty() => {}
+            Ok(Reply::Text(text)) => println!("{}", text),
+            Ok(Reply::Steps(steps)) => {
+                for (i, step) in steps.iter().enumerate() {
+                    if i > 0 {
+                        match editor.readline("") {
+                            Ok(answer) if answer.trim() != "q" => {}
+                            _ => break,
+                        }
+                    }
+                    println!("{}", step);
+                }
+            }
+            Err(error) => eprintln!("{}", error),
+        }
+        editor.set_helper(Some(Words(repl.words())));
+    }
+    if let Some(history) = &history {
+        editor.save_history(history)?;
+    }
+    Ok(())
+}
+
+fn run_json() {
+    let mut repl = Repl::new();
+    let mut input = String::new();
+    for line in io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if input.is_empty() && line.trim().is_empty() {
+            continue;
+        }
+        input += &format!("{}\n", line);
+        if !is_complete(&input) {
+            continue;
+        }
+        if input.trim() == ":quit" {
+            break;
+        }
+        for line in repl.execute_json(&input) {
+            println!("{}", line);
+        }
+        input.clear();
+    }
+    // the last program never got its `;`
+    if !input.trim().is_empty() {
+        println!("{}", tapl::json::end_of_input());
+    }
+}
+
+// the lines up to a complete input, `None` at the end of the input
+fn read_input(editor: &mut Editor<Words, DefaultHistory>) -> Option<String> {
+    let mut input = String::new();
+    loop {
+        let prompt = if input.is_empty() { "> " } else { "| " };
+        match editor.readline(prompt) {
+            Ok(line) if input.is_empty() && line.trim().is_empty() => continue,
+            Ok(line) => input += &format!("{}\n", line),
+            // Ctrl-C drops the input so far
+            Err(ReadlineError::Interrupted) => input.clear(),
+            Err(_) => return None,
+        }
+        if !input.is_empty() && is_complete(&input) {
+            return Some(input);
+        }
+    }
+}
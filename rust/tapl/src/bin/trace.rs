@@ -0,0 +1,156 @@
+// Prints the reduction of a program step by step:
+//
+//     trace [--show-rules] [--dot | --latex | --json] [--verbose] arith|lambda|stlc [FILE]
+//
+// The program is read from FILE, or from standard input without one. An stlc
+// program may define names with `name = t;` statements before its term, and
+// bring in the definitions of other files with `import "path";`, relative to
+// FILE.
+// `--show-rules` names the rules of every step's derivation, as in the
+// derivations of the book, and an untyped program that gets stuck ends with
+// the redex it is stuck on. `--dot` prints the reduction as a Graphviz graph
+// instead, e.g. `trace --dot lambda FILE | dot -Tsvg > trace.svg`, and
+// `--latex` as aligned equations, for stlc after the typing derivation.
+// `--json` prints a JSON object per line for the term, every step, the redex
+// a stuck term is stuck on and an error, on standard output.
+// `--verbose` logs the parse, typecheck and eval phases on standard error.
+
+use std::{env, fmt::Display, fs, io::Read, path::Path, process};
+
+use tapl::{
+    arith, json, lambda, stlc,
+    trace::{render, trace_to_dot, trace_to_latex},
+    Error, Step,
+};
+
+const USAGE: &str =
+    "usage: trace [--show-rules] [--dot | --latex | --json] [--verbose] arith|lambda|stlc [FILE]";
+
+#[derive(Clone, Copy)]
+enum Format {
+    // whether to show the rules
+    Text(bool),
+    Dot,
+    Latex,
+    Json,
+}
+
+fn show<T: Display>(term: &T, steps: &[Step<T>], format: Format) -> String {
+    match format {
+        Format::Text(show_rules) => render(term, steps, show_rules),
+        Format::Dot => trace_to_dot(term, steps),
+        Format::Latex => trace_to_latex(term, steps),
+        Format::Json => json::trace(term, steps)
+            .into_iter()
+            .map(|line| line + "\n")
+            .collect(),
+    }
+}
+
+// the text trace followed by the redex its normal form is stuck on
+fn show_stuck<T: Display>(
+    term: &T,
+    steps: &[Step<T>],
+    format: Format,
+    stuck: impl Fn(&T) -> Option<String>,
+) -> String {
+    let mut out = show(term, steps, format);
+    match (format, stuck(steps.last().map_or(term, |step| &step.term))) {
+        (Format::Text(_), Some(stuck)) => out += &format!("{}\n", stuck),
+        (Format::Json, Some(stuck)) => out += &format!("{}\n", json::stuck(&stuck)),
+        _ => {}
+    }
+    out
+}
+
+fn run(language: &str, path: Option<&Path>, source: &str, format: Format) -> Result<String, Error> {
+    match language {
+        "arith" => {
+            let term = arith::parse(source)?;
+            Ok(show_stuck(
+                &term,
+                &arith::trace(&term)?,
+                format,
+                arith::stuck,
+            ))
+        }
+        "lambda" => {
+            let term = lambda::parse(source)?;
+            Ok(show_stuck(
+                &term,
+                &lambda::trace(&term)?,
+                format,
+                lambda::stuck,
+            ))
+        }
+        _ => {
+            let term = match path {
+                Some(path) => stlc::parse_file(path, source)?,
+                None => stlc::parse(source)?,
+            };
+            let trace = show(&term, &stlc::trace(&term)?, format);
+            match format {
+                Format::Latex => Ok(format!(
+                    "{}\n\n{}",
+                    stlc::derivation_to_latex(&term)?,
+                    trace
+                )),
+                _ => Ok(trace),
+            }
+        }
+    }
+}
+
+fn main() {
+    let mut args = env::args().skip(1).collect::<Vec<_>>();
+    let show_rules = args.iter().any(|arg| arg == "--show-rules");
+    let format = if args.iter().any(|arg| arg == "--dot") {
+        Format::Dot
+    } else if args.iter().any(|arg| arg == "--latex") {
+        Format::Latex
+    } else if args.iter().any(|arg| arg == "--json") {
+        Format::Json
+    } else {
+        Format::Text(show_rules)
+    };
+    if args.iter().any(|arg| arg == "--verbose") {
+        tapl::enable_tracing();
+    }
+    args.retain(|arg| {
+        !["--show-rules", "--dot", "--latex", "--json", "--verbose"].contains(&arg.as_str())
+    });
+    let (language, path) = match args.as_slice() {
+        [language] => (language.as_str(), None),
+        [language, path] => (language.as_str(), Some(path)),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    if !["arith", "lambda", "stlc"].contains(&language) {
+        eprintln!("unknown language `{}`\n{}", language, USAGE);
+        process::exit(2);
+    }
+
+    let source = match path {
+        Some(path) => fs::read_to_string(path),
+        None => {
+            let mut source = String::new();
+            std::io::stdin().read_to_string(&mut source).map(|_| source)
+        }
+    };
+    let source = source.unwrap_or_else(|error| {
+        eprintln!("cannot read the program: {}", error);
+        process::exit(1);
+    });
+    match run(language, path.map(Path::new), &source, format) {
+        Ok(trace) => print!("{}", trace),
+        Err(error) => {
+            match format {
+                Format::Json => println!("{}", json::error(&error)),
+                _ => eprintln!("{}", error),
+            }
+            process::exit(1);
+        }
+    }
+}
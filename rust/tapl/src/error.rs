@@ -0,0 +1,75 @@
+use std::fmt::{self, Formatter};
+
+// The phase a program failed in, more may be added
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    Parse,
+    Type,
+    Eval,
+}
+
+impl ErrorKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorKind::Parse => "parse",
+            ErrorKind::Type => "type",
+            ErrorKind::Eval => "eval",
+        }
+    }
+}
+
+// Where in the source an error is, the byte range `start..end` and the
+// 1-based line and column it starts at
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Location {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    message: String,
+    location: Option<Location>,
+}
+
+impl Error {
+    pub(crate) fn new(kind: ErrorKind, message: impl ToString) -> Self {
+        Error {
+            kind,
+            message: message.to_string(),
+            location: None,
+        }
+    }
+
+    pub(crate) fn at(self, location: Location) -> Self {
+        Error {
+            location: Some(location),
+            ..self
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    // only the languages whose parsers keep spans know where an error is
+    pub fn location(&self) -> Option<Location> {
+        self.location
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} error: {}", self.kind.name(), self.message)
+    }
+}
+
+impl std::error::Error for Error {}
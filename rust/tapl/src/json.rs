@@ -0,0 +1,101 @@
+// The `--json` output of the binaries, one JSON object per line for editor
+// plugins to read. Every object has a "kind": the "result" of a statement,
+// the "term" a reduction starts from and every "step" of it, the redex a
+// normal form is "stuck" on, the "output" of a REPL command or an "error",
+// with the "span" of source it is at when the language knows it. Terms and
+// types are in the syntax of their language.
+
+use std::fmt::Display;
+
+use serde_json::{json, Map, Value};
+
+use crate::{Error, ErrorKind, Step};
+
+// a definition has a name and a term a value, either has a type in a typed
+// language
+pub fn result(name: Option<&str>, value: Option<&str>, typ: Option<&str>) -> String {
+    let mut object = Map::new();
+    object.insert("kind".to_string(), json!("result"));
+    for (key, field) in [("name", name), ("value", value), ("type", typ)] {
+        if let Some(field) = field {
+            object.insert(key.to_string(), json!(field));
+        }
+    }
+    Value::Object(object).to_string()
+}
+
+// a line for `term` and then one per step
+pub fn trace<T: Display>(term: &T, steps: &[Step<T>]) -> Vec<String> {
+    let mut lines = vec![json!({"kind": "term", "term": term.to_string()}).to_string()];
+    lines.extend(steps.iter().map(|step| {
+        json!({"kind": "step", "term": step.term.to_string(), "rules": step.rules}).to_string()
+    }));
+    lines
+}
+
+pub fn stuck(message: &str) -> String {
+    json!({"kind": "stuck", "message": message}).to_string()
+}
+
+pub fn output(text: &str) -> String {
+    json!({"kind": "output", "text": text}).to_string()
+}
+
+pub fn error(error: &Error) -> String {
+    let mut object =
+        json!({"kind": "error", "phase": error.kind().name(), "message": error.message()});
+    if let Some(location) = error.location() {
+        object["span"] = json!({"start": location.start, "end": location.end});
+        object["line"] = json!(location.line);
+        object["column"] = json!(location.column);
+    }
+    object.to_string()
+}
+
+// the input ended in the middle of a program
+pub fn end_of_input() -> String {
+    error(&Error::new(ErrorKind::Parse, "unexpected end of input"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lambda, stlc};
+
+    #[test]
+    fn test_json() -> Result<(), Error> {
+        assert_eq!(
+            result(None, Some("{false, 0}"), Some("Bool*Nat")),
+            r#"{"kind":"result","type":"Bool*Nat","value":"{false, 0}"}"#
+        );
+        assert_eq!(
+            result(Some("id"), None, None),
+            r#"{"kind":"result","name":"id"}"#
+        );
+        let term = lambda::parse("(lambda x.x) (lambda y.y)")?;
+        assert_eq!(
+            trace(&term, &lambda::trace(&term)?),
+            [
+                r#"{"kind":"term","term":"(lambda x.x) (lambda y.y)"}"#,
+                r#"{"kind":"step","rules":["E-AppAbs"],"term":"lambda y.y"}"#,
+            ]
+        );
+        assert_eq!(
+            error(&Error::new(ErrorKind::Parse, "unbound variable \"y\"")),
+            r#"{"kind":"error","message":"unbound variable \"y\"","phase":"parse"}"#
+        );
+        assert_eq!(
+            error(&stlc::parse("id = lambda x:Bool.x;\nid y").unwrap_err()),
+            r#"{"column":1,"kind":"error","line":2,"message":"unbound variable y","phase":"parse","span":{"end":26,"start":22}}"#
+        );
+        assert_eq!(
+            end_of_input(),
+            r#"{"kind":"error","message":"unexpected end of input","phase":"parse"}"#
+        );
+        assert_eq!(
+            stuck("stuck at y"),
+            r#"{"kind":"stuck","message":"stuck at y"}"#
+        );
+        Ok(())
+    }
+}
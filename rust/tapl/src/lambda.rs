@@ -0,0 +1,141 @@
+// The untyped lambda calculus under call by value (TAPL chapters 5 to 7)
+
+use std::fmt::{self, Formatter};
+
+use untyped_lambda::{
+    eval::{self, eval_term},
+    nameless::{remove_names, restore_names, NamingContext},
+    parser::{self, parse_statement},
+    toplevel::Bindings,
+    trace::{eval_trace, stuck_at},
+};
+
+use crate::{
+    parse_with, program,
+    trace::{rule_names, Step},
+    DisplayMode, Error, ErrorKind, Outcome,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Term(parser::Term);
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+pub fn parse(input: &str) -> Result<Term, Error> {
+    parse_with(&program(input), parser::parse).map(Term)
+}
+
+// Programs entered one after another, whose definitions `name = t;` the
+// ones after them use, starting out empty or with the prelude of
+// combinators and Church encodings (TAPL 5.2)
+#[derive(Default)]
+pub struct Session {
+    bindings: Bindings,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session::default()
+    }
+
+    // defines `id`, `Y`, `tru`, `pair`, `c0`, `plus` and the rest of the
+    // prelude, replacing definitions of the same names
+    pub fn load_prelude(&mut self) {
+        self.bindings.load_prelude();
+    }
+
+    // the statements of `input` in order with the name of each definition,
+    // every defined name replaced by what it stands for
+    pub fn parse(&mut self, input: &str) -> Result<Vec<(Option<String>, Term)>, Error> {
+        let input = program(input);
+        let mut input = input.as_str();
+        let mut statements = vec![];
+        while !input.trim().is_empty() {
+            let (rest, (name, term)) =
+                parse_statement(input).map_err(|error| Error::new(ErrorKind::Parse, error))?;
+            statements.push(match name {
+                Some(name) => {
+                    self.bindings.define(name, &term);
+                    let term = self.bindings.get(name.as_str()).unwrap().clone();
+                    (Some(name.to_string()), Term(term))
+                }
+                None => (None, Term(self.bindings.expand(&term))),
+            });
+            input = rest;
+        }
+        Ok(statements)
+    }
+
+    // the names the programs and the prelude defined
+    pub fn definitions(&self) -> Vec<String> {
+        self.bindings
+            .names()
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+}
+
+// evaluates to a normal form, which does not exist for every term: `eval`
+// does not return for a diverging term such as omega, `run` does
+pub fn eval(term: &Term) -> Result<Term, Error> {
+    let mut ctx = NamingContext::default();
+    let nameless = remove_names(&mut ctx, &term.0);
+    Ok(Term(restore_names(&mut ctx, &eval_term(&nameless))))
+}
+
+// gives up after `max_steps` reductions
+pub fn run(term: &Term, max_steps: usize) -> Outcome<Term, Term> {
+    let mut ctx = NamingContext::default();
+    let nameless = remove_names(&mut ctx, &term.0);
+    let mut named = |term| Term(restore_names(&mut ctx, term));
+    match eval::eval_with_fuel(&nameless, max_steps) {
+        Outcome::Value(value) => Outcome::Value(named(&value)),
+        Outcome::Stuck(term) => Outcome::Stuck(named(&term)),
+        Outcome::OutOfFuel { steps, term } => Outcome::OutOfFuel {
+            steps,
+            term: named(&term),
+        },
+    }
+}
+
+// the small steps to a normal form, a diverging term has infinitely many
+pub fn trace(term: &Term) -> Result<Vec<Step<Term>>, Error> {
+    let mut ctx = NamingContext::default();
+    let nameless = remove_names(&mut ctx, &term.0);
+    Ok(eval_trace(&nameless)
+        .into_iter()
+        .map(|step| Step {
+            rules: rule_names(&step.rules),
+            term: Term(restore_names(&mut ctx, &step.term)),
+        })
+        .collect())
+}
+
+// the free variable a stuck normal form is stuck on
+pub fn stuck(term: &Term) -> Option<String> {
+    let mut ctx = NamingContext::default();
+    let at = stuck_at(&remove_names(&mut ctx, &term.0))?;
+    Some(format!(
+        "stuck at the free variable {}",
+        restore_names(&mut ctx, &at.subterm)
+    ))
+}
+
+pub fn pretty(term: &Term) -> String {
+    term.to_string()
+}
+
+// `term` with its variables as names, de Bruijn indices or both
+pub fn display(term: &Term, mode: DisplayMode) -> String {
+    term.0.display(mode).to_string()
+}
+
+// the syntax tree of `term` in Graphviz DOT
+pub fn to_dot(term: &Term) -> String {
+    tapl_frontend::dot::to_dot(&term.0)
+}
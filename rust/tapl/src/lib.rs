@@ -0,0 +1,205 @@
+// A stable facade over the chapter crates of this workspace.
+//
+// Every language module has the same entry points on opaque types: `parse`
+// turns source text into a `Term`, `check` (typed languages) computes its
+// `Type`, `eval` runs it and `pretty` prints a term in the language's own
+// syntax. The untyped languages and `stlc` also `trace` the reduction steps
+// of a term, and `run` it with a bound on the number of steps to an
+// `Outcome`: a value, a stuck term or running out of fuel. `lambda` and
+// `stlc` also `display` a term in a `DisplayMode`, naming its variables or
+// giving their de Bruijn indices. The terminating `;` of a program is
+// optional. The chapter crates' enums and parser error types are not part
+// of this API, so they can change without breaking dependents.
+
+pub mod arith;
+mod error;
+pub mod json;
+pub mod lambda;
+pub mod references;
+pub mod repl;
+pub mod stlc;
+pub mod trace;
+pub mod typed_arith;
+
+use std::fmt::Display;
+
+pub use error::{Error, ErrorKind, Location};
+pub use misc::DisplayMode;
+pub use tapl_frontend::outcome::Outcome;
+pub use trace::Step;
+
+// Prints the `parse`, `typecheck` and `eval` spans of the chapter crates on
+// standard error, down to a `trace` event for every evaluation step, for the
+// `--verbose` flag of the binaries. A subscriber that is already installed
+// is kept
+#[cfg(feature = "cli")]
+pub fn enable_tracing() {
+    let _ = tracing_subscriber::fmt()
+        .with_max_level(tracing_subscriber::filter::LevelFilter::TRACE)
+        .with_writer(std::io::stderr)
+        .without_time()
+        .try_init();
+}
+
+// the chapter crates expect every program to end with `;`
+fn program(input: &str) -> String {
+    let input = input.trim_end();
+    if input.ends_with(';') {
+        input.to_string()
+    } else {
+        format!("{};", input)
+    }
+}
+
+// runs one of the chapter crates' `parse` functions on a whole program
+fn parse_with<'a, T, E: Display>(
+    input: &'a str,
+    parse: impl Fn(&'a str) -> Result<(&'a str, T), E>,
+) -> Result<T, Error> {
+    match parse(input) {
+        Ok((rest, term)) if rest.trim().is_empty() => Ok(term),
+        Ok((rest, _)) => Err(Error::new(
+            ErrorKind::Parse,
+            format!("unexpected input `{}`", rest),
+        )),
+        Err(error) => Err(Error::new(ErrorKind::Parse, error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arith() -> Result<(), Error> {
+        let term = arith::parse("if iszero(0) then succ(1) else 0")?;
+        assert_eq!(arith::pretty(&term), "if iszero(0) then succ(1) else 0");
+        assert_eq!(arith::eval(&term)?.to_string(), "2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_arith_trace() -> Result<(), Error> {
+        let term = arith::parse("pred(if true then 1 else 0)")?;
+        assert_eq!(
+            trace::render(&term, &arith::trace(&term)?, true),
+            "pred(if true then 1 else 0)\n\
+             -> pred(1)    by E-Pred E-IfTrue\n\
+             -> 0    by E-PredSucc\n"
+        );
+        let term = arith::parse("succ(if 0 then true else 0)")?;
+        assert_eq!(
+            arith::stuck(&term).unwrap(),
+            "stuck at if 0 then true else 0: expected a boolean"
+        );
+        assert!(matches!(arith::run(&term, 10), Outcome::Stuck(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_typed_arith() -> Result<(), Error> {
+        let term = typed_arith::parse("iszero(succ(0))")?;
+        assert_eq!(typed_arith::check(&term)?.to_string(), "Bool");
+        let term = typed_arith::parse("succ(true);")?;
+        assert_eq!(
+            typed_arith::eval(&term).unwrap_err().kind(),
+            ErrorKind::Type
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_lambda() -> Result<(), Error> {
+        let term = lambda::parse("(lambda x.x) (lambda y.y)")?;
+        assert_eq!(lambda::pretty(&lambda::eval(&term)?), "lambda y.y");
+        assert_eq!(lambda::trace(&term)?[0].rules, ["E-AppAbs"]);
+        let term = lambda::parse("lambda x.lambda y.x")?;
+        assert_eq!(lambda::display(&term, DisplayMode::DeBruijn), "λ.λ.1");
+        let term = lambda::parse("(lambda x.x) y")?;
+        assert_eq!(
+            lambda::stuck(&term).unwrap(),
+            "stuck at the free variable y"
+        );
+        let omega = lambda::parse("(lambda x.x x) (lambda x.x x)")?;
+        assert_eq!(
+            lambda::run(&omega, 1000).to_string(),
+            "no normal form after 1000 steps"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_stlc() -> Result<(), Error> {
+        let term = stlc::parse("(lambda x:Bool.{x, 0}) true")?;
+        assert_eq!(stlc::check(&term)?.to_string(), "Bool*Nat");
+        assert_eq!(stlc::pretty(&stlc::eval(&term)?), "{true, 0}");
+        assert_eq!(stlc::run(&term, 10)?.to_string(), "{true, 0}");
+        let rules = stlc::trace(&term)?
+            .into_iter()
+            .map(|step| step.rules.join(" "))
+            .collect::<Vec<_>>();
+        assert_eq!(rules, ["E-AppAbs"]);
+        // a parameter type the body determines is reconstructed
+        let term = stlc::parse("lambda x. succ(x)")?;
+        assert_eq!(stlc::pretty(&term), "lambda x:Nat.succ(x)");
+        assert_eq!(stlc::check(&term)?.to_string(), "Nat->Nat");
+        Ok(())
+    }
+
+    #[test]
+    fn test_stlc_export() -> Result<(), Error> {
+        let term = stlc::parse("(lambda x:Bool.{x, 0}) true")?;
+        assert_eq!(
+            trace::trace_to_dot(&term, &stlc::trace(&term)?),
+            "digraph trace {\n  \
+               t0 [label=\"(lambda x:Bool.{x, 0}) true\"];\n  \
+               t1 [label=\"{true, 0}\"];\n  \
+               t0 -> t1 [label=\"E-AppAbs\"];\n\
+             }\n"
+        );
+        assert_eq!(
+            trace::trace_to_latex(&term, &stlc::trace(&term)?),
+            "\\begin{align*}\n  \
+               & \\texttt{(lambda x:Bool.\\{x, 0\\}) true} \\\\\n  \
+               \\longrightarrow{} & \\texttt{\\{true, 0\\}} && \\textsc{E-AppAbs}\n\
+             \\end{align*}\n"
+        );
+        assert!(
+            stlc::derivation_to_latex(&term)?.starts_with("\\inferrule*[right=\\textsc{T-App}]")
+        );
+        assert_eq!(
+            stlc::to_dot(&stlc::parse("lambda x:Bool.x")?),
+            "digraph term {\n  n0 [label=\"lambda x:Bool\"];\n  n1 [label=\"0\"];\n  n0 -> n1;\n}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_stlc_errors() -> Result<(), Error> {
+        let error = stlc::parse("lambda x:Bool.y").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Parse);
+        assert_eq!(error.to_string(), "parse error: unbound variable y");
+        let term = stlc::parse("neg = lambda b:Bool.if b then false else true;\nneg true")?;
+        assert_eq!(stlc::pretty(&stlc::eval(&term)?), "false");
+        let error = stlc::parse("id = lambda x:Bool.x;").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "parse error: expected a term after the definitions"
+        );
+        // a boolean operator is checked as the program wrote it
+        let term = stlc::parse("lambda n:Nat.and(true, n)")?;
+        assert_eq!(
+            stlc::check(&term).unwrap_err().to_string(),
+            "type error: argument of and is not a boolean: found Nat"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_references() -> Result<(), Error> {
+        let term = references::parse("let r = ref 0 in r := succ(!r); !r")?;
+        assert_eq!(references::check(&term)?.to_string(), "Nat");
+        assert_eq!(references::pretty(&references::eval(&term)?), "succ(0)");
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+// The simply typed lambda calculus with references (TAPL chapter 13)
+
+use std::fmt::{self, Formatter};
+
+use references::{
+    context::Context,
+    eval,
+    parser::Parser,
+    store::{self, Store},
+    typing,
+};
+
+use crate::{program, Error, ErrorKind};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Term(references::parser::Term);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Type(typing::Type);
+
+// how the store of an `eval_collecting` run grew
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stats(store::Stats);
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+pub fn parse(input: &str) -> Result<Term, Error> {
+    Parser::new()
+        .parse(&program(input))
+        .map(Term)
+        .map_err(|error| Error::new(ErrorKind::Parse, error))
+}
+
+pub fn check(term: &Term) -> Result<Type, Error> {
+    typing::type_of(&mut Context::default(), &[], &term.0)
+        .map(Type)
+        .map_err(|error| Error::new(ErrorKind::Type, error))
+}
+
+// typechecks the term and evaluates it in an empty store
+pub fn eval(term: &Term) -> Result<Term, Error> {
+    check(term)?;
+    eval::eval(&term.0, &mut Store::new())
+        .map(Term)
+        .map_err(|error| Error::new(ErrorKind::Eval, error))
+}
+
+// `eval` in a store that is garbage collected as it grows
+pub fn eval_collecting(term: &Term) -> Result<(Term, Stats), Error> {
+    check(term)?;
+    let mut store = Store::new();
+    eval::eval_collecting(&term.0, &mut store)
+        .map(|value| (Term(value), Stats(store.stats())))
+        .map_err(|error| Error::new(ErrorKind::Eval, error))
+}
+
+pub fn pretty(term: &Term) -> String {
+    term.to_string()
+}
@@ -0,0 +1,489 @@
+// The stlc REPL of `bin/repl.rs` apart from reading lines. An input is a
+// program, which may span several lines up to its final `;`, or a command
+// on a single line:
+//
+//     t;              typecheck and evaluate t
+//     x = t;          define x for the lines after this one
+//     import "path";  bring in the definitions of another file
+//     :type t         the type of t, without evaluating it
+//     :ast t          the de Bruijn form of t
+//     :step t         the reduction of t one step at a time, with rule names
+//     :ref t          evaluate t of the references chapter
+//     :store          how the store of the last :ref grew
+//     :display mode   print values with names, de Bruijn indices or both
+//     :mode language  run programs of stlc or of the untyped lambda calculus
+//     :prelude        load the untyped lambda prelude and switch to it
+
+use std::fmt::{self, Formatter};
+
+use crate::{
+    json, lambda, references,
+    stlc::{self, Session, Term},
+    trace::render,
+    DisplayMode, Error, ErrorKind, Outcome,
+};
+
+const COMMANDS: [&str; 10] = [
+    ":type", ":ast", ":step", ":ref", ":store", ":display", ":mode", ":prelude", ":help", ":quit",
+];
+
+// the steps a program of the untyped lambda calculus may take, a term such as
+// `Y f` has no normal form
+const MAX_STEPS: usize = 100_000;
+
+pub const HELP: &str = "\
+t;              typecheck and evaluate t
+x = t;          define x for the lines after this one
+import \"path\";  bring in the definitions of another file
+:type t         the type of t, without evaluating it
+:ast t          the de Bruijn form of t
+:step t         the reduction of t one step at a time, with rule names
+:ref t          evaluate t of the references chapter
+:store          how the store of the last :ref grew
+:display mode   print values with names, de Bruijn indices or both, the
+                modes `named`, `debruijn` and `both`
+:mode language  run the programs after it as `stlc` or as untyped `lambda`
+                terms, the commands above always take stlc terms
+:prelude        define the combinators, Church booleans, pairs, numerals
+                and lists of the untyped lambda calculus and switch to it
+:help           this list
+:quit           leave";
+
+// What a line gave. `Steps` is the starting term and then one line per
+// reduction step, for the REPL to show one at a time.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reply {
+    Text(String),
+    Steps(Vec<String>),
+}
+
+// What a statement gave: a definition is shown by its name and a term by its
+// value, in stlc followed by the type of either
+struct Answer {
+    name: Option<String>,
+    value: Option<String>,
+    typ: Option<String>,
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name.as_ref().or(self.value.as_ref()).unwrap())?;
+        match &self.typ {
+            Some(typ) => write!(f, " : {}", typ),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Repl {
+    session: Session,
+    // of the last `:ref`, whose store is garbage collected
+    store: Option<references::Stats>,
+    // how `t;` prints its value
+    display: DisplayMode,
+    // the language of `t;`
+    mode: Mode,
+    lambda: lambda::Session,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Mode {
+    #[default]
+    Stlc,
+    Lambda,
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Repl::default()
+    }
+
+    // the words tab completion offers: commands, keywords and the names
+    // defined so far
+    pub fn words(&self) -> Vec<String> {
+        let mut words = COMMANDS
+            .iter()
+            .map(|command| command.to_string())
+            .collect::<Vec<_>>();
+        words.extend(stlc::keywords().into_iter().map(String::from));
+        words.extend(self.session.definitions());
+        words.extend(self.lambda.definitions());
+        words
+    }
+
+    pub fn execute(&mut self, line: &str) -> Result<Reply, Error> {
+        let line = line.trim();
+        let command = match line.strip_prefix(':') {
+            Some(command) => command,
+            None => {
+                let answers = self.run(line)?;
+                let lines = answers.iter().map(Answer::to_string).collect::<Vec<_>>();
+                return Ok(Reply::Text(lines.join("\n")));
+            }
+        };
+        let mut parts = command.splitn(2, char::is_whitespace);
+        let (command, argument) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+        match command {
+            "type" => Ok(Reply::Text(stlc::check(&self.term(argument)?)?.to_string())),
+            "ast" => Ok(Reply::Text(stlc::ast(&self.term(argument)?))),
+            "step" => {
+                let term = self.term(argument)?;
+                let steps = stlc::trace(&term)?;
+                let lines = render(&term, &steps, true);
+                Ok(Reply::Steps(lines.lines().map(String::from).collect()))
+            }
+            "ref" => {
+                let term = references::parse(argument)?;
+                let typ = references::check(&term)?;
+                let (value, stats) = references::eval_collecting(&term)?;
+                self.store = Some(stats);
+                Ok(Reply::Text(format!(
+                    "{} : {}",
+                    references::pretty(&value),
+                    typ
+                )))
+            }
+            "store" => Ok(Reply::Text(match self.store {
+                Some(stats) => stats.to_string(),
+                None => "no :ref has run".to_string(),
+            })),
+            "display" => {
+                self.display = match argument.trim() {
+                    "named" => DisplayMode::Named,
+                    "debruijn" => DisplayMode::DeBruijn,
+                    "both" => DisplayMode::Both,
+                    mode => {
+                        return Err(Error::new(
+                            ErrorKind::Parse,
+                            format!("unknown mode `{}`, expected named, debruijn or both", mode),
+                        ))
+                    }
+                };
+                Ok(Reply::Text(String::new()))
+            }
+            "mode" => {
+                self.mode = match argument.trim() {
+                    "stlc" => Mode::Stlc,
+                    "lambda" => Mode::Lambda,
+                    mode => {
+                        return Err(Error::new(
+                            ErrorKind::Parse,
+                            format!("unknown mode `{}`, expected stlc or lambda", mode),
+                        ))
+                    }
+                };
+                Ok(Reply::Text(String::new()))
+            }
+            "prelude" => {
+                self.lambda.load_prelude();
+                self.mode = Mode::Lambda;
+                Ok(Reply::Text(format!(
+                    "defined {}",
+                    self.lambda.definitions().join(", ")
+                )))
+            }
+            "help" => Ok(Reply::Text(HELP.to_string())),
+            _ => Err(Error::new(
+                ErrorKind::Parse,
+                format!("unknown command `:{}`, :help lists the commands", command),
+            )),
+        }
+    }
+
+    // `execute` as lines of JSON objects for `--json`: a program gives a
+    // result per statement, `:step` its term and every step, and any other
+    // command its output
+    pub fn execute_json(&mut self, line: &str) -> Vec<String> {
+        let line = line.trim();
+        let lines = match line.strip_prefix(':') {
+            None => self.run(line).map(|answers| {
+                answers
+                    .iter()
+                    .map(|answer| {
+                        json::result(
+                            answer.name.as_deref(),
+                            answer.value.as_deref(),
+                            answer.typ.as_deref(),
+                        )
+                    })
+                    .collect()
+            }),
+            Some(command) if command.split_whitespace().next() == Some("step") => {
+                let argument = command["step".len()..].trim_start();
+                self.term(argument)
+                    .and_then(|term| Ok(json::trace(&term, &stlc::trace(&term)?)))
+            }
+            Some(_) => self.execute(line).map(|reply| match reply {
+                Reply::Text(text) => vec![json::output(&text)],
+                Reply::Steps(steps) => steps.iter().map(|step| json::output(step)).collect(),
+            }),
+        };
+        lines.unwrap_or_else(|error| vec![json::error(&error)])
+    }
+
+    // what each statement gave
+    fn run(&mut self, input: &str) -> Result<Vec<Answer>, Error> {
+        if self.mode == Mode::Lambda {
+            return self.run_lambda(input);
+        }
+        let mut answers = vec![];
+        for (name, term) in self.session.parse(input)? {
+            let typ = Some(stlc::check(&term)?.to_string());
+            answers.push(match name {
+                Some(name) => Answer {
+                    name: Some(name),
+                    value: None,
+                    typ,
+                },
+                None => Answer {
+                    name: None,
+                    value: Some(stlc::display(&stlc::eval(&term)?, self.display)),
+                    typ,
+                },
+            });
+        }
+        Ok(answers)
+    }
+
+    fn run_lambda(&mut self, input: &str) -> Result<Vec<Answer>, Error> {
+        let mut answers = vec![];
+        for (name, term) in self.lambda.parse(input)? {
+            let value = match name {
+                Some(_) => None,
+                None => Some(self.lambda_value(&term)?),
+            };
+            answers.push(Answer {
+                name,
+                value,
+                typ: None,
+            });
+        }
+        Ok(answers)
+    }
+
+    fn lambda_value(&self, term: &lambda::Term) -> Result<String, Error> {
+        match lambda::run(term, MAX_STEPS) {
+            Outcome::Value(value) => Ok(lambda::display(&value, self.display)),
+            Outcome::Stuck(term) => {
+                let stuck = lambda::stuck(&term).unwrap_or_else(|| "stuck".to_string());
+                Err(Error::new(ErrorKind::Eval, stuck))
+            }
+            outcome => Err(Error::new(ErrorKind::Eval, outcome)),
+        }
+    }
+
+    // the argument of a command, a single term
+    fn term(&mut self, input: &str) -> Result<Term, Error> {
+        let mut statements = self.session.parse(input)?;
+        match statements.pop() {
+            Some((None, term)) if statements.is_empty() => Ok(term),
+            _ => Err(Error::new(ErrorKind::Parse, "expected a single term")),
+        }
+    }
+}
+
+// whether `input` can be run, or its program needs more lines
+pub fn is_complete(input: &str) -> bool {
+    let input = input.trim();
+    input.is_empty() || input.starts_with(':') || input.ends_with(';')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_definitions() -> Result<(), Error> {
+        let mut repl = Repl::new();
+        let text = |text: &str| Reply::Text(text.to_string());
+        assert_eq!(
+            repl.execute("neg = lambda b:Bool.if b then false else true;")?,
+            text("neg : Bool->Bool")
+        );
+        assert_eq!(
+            repl.execute("{neg true, 0}")?,
+            text("{false, 0} : Bool*Nat")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_type() -> Result<(), Error> {
+        let mut repl = Repl::new();
+        let text = |text: &str| Reply::Text(text.to_string());
+        repl.execute("neg = lambda b:Bool.if b then false else true;")?;
+        assert_eq!(repl.execute(":type neg")?, text("Bool->Bool"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ast() -> Result<(), Error> {
+        let mut repl = Repl::new();
+        let text = |text: &str| Reply::Text(text.to_string());
+        repl.execute("neg = lambda b:Bool.if b then false else true;")?;
+        assert_eq!(
+            repl.execute(":ast (lambda x:Nat.x) 0")?,
+            text(r#"TmApp(TmAbs("x", Number, TmVar(0)), TmZero)"#)
+        );
+        // a definition is shown by its name, not by the term it stands for
+        assert_eq!(
+            repl.execute(":ast neg true")?,
+            text(r#"TmApp(TmGlobal("neg", ..), TmTrue)"#)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_step() -> Result<(), Error> {
+        let mut repl = Repl::new();
+        repl.execute("neg = lambda b:Bool.if b then false else true;")?;
+        assert_eq!(
+            repl.execute(":step neg true;")?,
+            Reply::Steps(vec![
+                "neg true".to_string(),
+                "-> (lambda b:Bool.if b then false else true) true    by E-App1 E-Global"
+                    .to_string(),
+                "-> if true then false else true    by E-AppAbs".to_string(),
+                "-> false    by E-IfTrue".to_string(),
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut repl = Repl::new();
+        assert_eq!(
+            repl.execute(":type x = true;").unwrap_err().to_string(),
+            "parse error: expected a single term"
+        );
+        assert_eq!(
+            repl.execute(":eval true").unwrap_err().to_string(),
+            "parse error: unknown command `:eval`, :help lists the commands"
+        );
+    }
+
+    #[test]
+    fn test_store() -> Result<(), Error> {
+        let mut repl = Repl::new();
+        let text = |text: &str| Reply::Text(text.to_string());
+        assert_eq!(repl.execute(":store")?, text("no :ref has run"));
+        assert_eq!(
+            repl.execute(":ref let r = ref 0 in (let s = ref true in r := succ(!r)); !r")?,
+            text("succ(0) : Nat")
+        );
+        assert_eq!(
+            repl.execute(":store")?,
+            text("allocated 2, live 0, peak 2, collections 1, freed 2")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_display() -> Result<(), Error> {
+        let mut repl = Repl::new();
+        let text = |text: &str| Reply::Text(text.to_string());
+        assert_eq!(repl.execute(":display debruijn")?, text(""));
+        assert_eq!(
+            repl.execute("lambda x:Nat y:Bool.x;")?,
+            text("λ:Nat.λ:Bool.#1 : Nat->Bool->Nat")
+        );
+        assert_eq!(
+            repl.execute(":display index").unwrap_err().to_string(),
+            "parse error: unknown mode `index`, expected named, debruijn or both"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_words() -> Result<(), Error> {
+        let mut repl = Repl::new();
+        repl.execute("neg = lambda b:Bool.if b then false else true;")?;
+        let words = repl.words();
+        assert!(words.iter().any(|word| word == "neg"));
+        assert!(words.iter().any(|word| word == "lambda"));
+        assert!(is_complete(":type neg"));
+        assert!(!is_complete("neg\n  true"));
+        assert!(is_complete("neg\n  true;\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_json() {
+        let mut repl = Repl::new();
+        assert_eq!(
+            repl.execute_json("neg = lambda b:Bool.if b then false else true; neg true;"),
+            [
+                r#"{"kind":"result","name":"neg","type":"Bool->Bool"}"#,
+                r#"{"kind":"result","type":"Bool","value":"false"}"#,
+            ]
+        );
+        assert_eq!(
+            repl.execute_json(":step neg true"),
+            [
+                r#"{"kind":"term","term":"neg true"}"#,
+                r#"{"kind":"step","rules":["E-App1","E-Global"],"term":"(lambda b:Bool.if b then false else true) true"}"#,
+                r#"{"kind":"step","rules":["E-AppAbs"],"term":"if true then false else true"}"#,
+                r#"{"kind":"step","rules":["E-IfTrue"],"term":"false"}"#,
+            ]
+        );
+        assert_eq!(
+            repl.execute_json(":type neg"),
+            [r#"{"kind":"output","text":"Bool->Bool"}"#]
+        );
+        assert_eq!(
+            repl.execute_json("succ(true);"),
+            [
+                r#"{"kind":"error","message":"argument of succ is not a number: found Bool","phase":"type"}"#
+            ]
+        );
+        assert!(
+            repl.execute_json(":prelude")[0].starts_with(r#"{"kind":"output","text":"defined Y"#)
+        );
+        assert_eq!(
+            repl.execute_json("id = lambda x.x; id;"),
+            [
+                r#"{"kind":"result","name":"id"}"#,
+                r#"{"kind":"result","value":"lambda x.x"}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prelude() -> Result<(), Error> {
+        let mut repl = Repl::new();
+        let text = |text: &str| Reply::Text(text.to_string());
+        let defined = repl.execute(":prelude")?;
+        assert!(matches!(defined, Reply::Text(text) if text.starts_with("defined Y, and, c0, c1")));
+        // the programs after it are untyped and use its definitions
+        assert_eq!(
+            repl.execute("fst (pair tru fls);")?,
+            text("lambda t.lambda f.t")
+        );
+        assert_eq!(
+            repl.execute("two = scc c1; iszro (prd two);")?,
+            text("two\nlambda t.lambda f.f")
+        );
+        assert!(repl.words().iter().any(|word| word == "two"));
+        assert_eq!(
+            repl.execute("(lambda x.x) y;").unwrap_err().to_string(),
+            "eval error: stuck at the free variable y"
+        );
+        assert_eq!(
+            repl.execute("(lambda x.x x) (lambda x.x x);")
+                .unwrap_err()
+                .to_string(),
+            "eval error: no normal form after 100000 steps"
+        );
+        assert_eq!(repl.execute(":mode stlc")?, text(""));
+        assert_eq!(repl.execute("succ(0);")?, text("succ(0) : Nat"));
+        assert_eq!(repl.execute(":mode lambda")?, text(""));
+        assert_eq!(repl.execute("id id;")?, text("lambda x.x"));
+        assert_eq!(
+            repl.execute(":mode typed").unwrap_err().to_string(),
+            "parse error: unknown mode `typed`, expected stlc or lambda"
+        );
+        Ok(())
+    }
+}
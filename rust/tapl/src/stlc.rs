@@ -0,0 +1,228 @@
+// The simply typed lambda calculus with the extensions of TAPL chapter 11
+
+use std::{
+    fmt::{self, Formatter},
+    path::Path,
+    rc::Rc,
+};
+
+use simply_typed_lambda::{
+    ast_parser::KEYWORDS,
+    context::Context,
+    derivation::derive,
+    eval,
+    located::{reconstruct_located, type_of_located, Located},
+    parser::{ParseError, Parser, Statement},
+    span::SpanTree,
+    trace::eval_trace,
+    type_parser::TYPE_KEYWORDS,
+    typing,
+};
+
+use crate::{
+    program,
+    trace::{rule_names, Step},
+    DisplayMode, Error, ErrorKind, Location, Outcome,
+};
+
+// A parsed term keeps the source span of its subterms, so that a type error
+// in an `and`, `or` or `not` names the operator
+#[derive(Clone, Debug)]
+pub struct Term(simply_typed_lambda::parser::Term, Option<Rc<SpanTree>>);
+
+impl Term {
+    fn new(term: simply_typed_lambda::parser::Term) -> Self {
+        Term(term, None)
+    }
+}
+
+impl PartialEq for Term {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Type(typing::Type);
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+// Statements `name = t;` and `import "path";` before the term define names
+// it can use, every use stands for the defined term
+pub fn parse(input: &str) -> Result<Term, Error> {
+    let source = program(input);
+    last_term(&source, Parser::new().parse_program(&source))
+}
+
+// like `parse`, for `source` read from `path`, which its imports are
+// relative to
+pub fn parse_file(path: &Path, source: &str) -> Result<Term, Error> {
+    let source = program(source);
+    last_term(&source, Parser::new().parse_file(path, &source))
+}
+
+// a parse error with where it is in `source`
+fn parse_error(source: &str, located: Located<ParseError>) -> Error {
+    let (line, column) = located.span.line_col(source);
+    Error::new(ErrorKind::Parse, located.error).at(Location {
+        start: located.span.start,
+        end: located.span.end,
+        line,
+        column,
+    })
+}
+
+fn last_term(
+    source: &str,
+    statements: Vec<Result<Statement, Located<ParseError>>>,
+) -> Result<Term, Error> {
+    let mut term = None;
+    for statement in statements {
+        let statement = statement.map_err(|located| parse_error(source, located))?;
+        term = match statement.name {
+            Some(_) => None,
+            None => Some(statement),
+        };
+    }
+    let term =
+        term.ok_or_else(|| Error::new(ErrorKind::Parse, "expected a term after the definitions"))?;
+    reconstructed(term)
+}
+
+// the term of `statement` with the parameter types it leaves out solved
+// (TAPL 22.3)
+fn reconstructed(statement: Statement) -> Result<Term, Error> {
+    reconstruct_located(&mut Context::default(), &statement.term, &statement.spans)
+        .map(|term| Term(term, Some(Rc::new(statement.spans))))
+        .map_err(|located| Error::new(ErrorKind::Type, located.error))
+}
+
+// Programs entered one after another, such as the lines of a REPL. The
+// definitions and imports of each are visible in the ones after it.
+#[derive(Default)]
+pub struct Session {
+    parser: Parser,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session::default()
+    }
+
+    // the statements of `input` in order, with the name of each definition
+    pub fn parse(&mut self, input: &str) -> Result<Vec<(Option<String>, Term)>, Error> {
+        let source = program(input);
+        self.parser
+            .parse_program(&source)
+            .into_iter()
+            .map(|statement| match statement {
+                Ok(statement) => Ok((
+                    statement.name.map(|name| name.to_string()),
+                    reconstructed(statement)?,
+                )),
+                Err(located) => Err(parse_error(&source, located)),
+            })
+            .collect()
+    }
+
+    // the names the programs so far defined
+    pub fn definitions(&self) -> Vec<String> {
+        let mut names = self
+            .parser
+            .definitions()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+}
+
+// the reserved words of terms and the names of the base types
+pub fn keywords() -> Vec<&'static str> {
+    let mut keywords = KEYWORDS.to_vec();
+    keywords.extend(&TYPE_KEYWORDS);
+    keywords
+}
+
+pub fn check(term: &Term) -> Result<Type, Error> {
+    let ctx = &mut Context::default();
+    match &term.1 {
+        Some(spans) => type_of_located(ctx, &term.0, spans).map_err(|located| located.error),
+        None => typing::type_of(ctx, &term.0),
+    }
+    .map(Type)
+    .map_err(|error| Error::new(ErrorKind::Type, error))
+}
+
+// the typing derivation of `term` as mathpartir rules
+pub fn derivation_to_latex(term: &Term) -> Result<String, Error> {
+    derive(&mut Context::default(), &term.0)
+        .map(|derivation| derivation.to_latex())
+        .map_err(|error| Error::new(ErrorKind::Type, error))
+}
+
+// typechecks the term and evaluates it to a value
+pub fn eval(term: &Term) -> Result<Term, Error> {
+    check(term)?;
+    eval::eval(&term.0)
+        .map(Term::new)
+        .map_err(|error| Error::new(ErrorKind::Eval, error))
+}
+
+// typechecks the term and evaluates it by at most `max_steps` small steps
+pub fn run(term: &Term, max_steps: usize) -> Result<Outcome<Term, Term>, Error> {
+    check(term)?;
+    Ok(match eval::eval_with_fuel(&term.0, max_steps) {
+        Outcome::Value(value) => Outcome::Value(Term::new(value.to_term())),
+        Outcome::Stuck(term) => Outcome::Stuck(Term::new(term)),
+        Outcome::OutOfFuel { steps, term } => Outcome::OutOfFuel {
+            steps,
+            term: Term::new(term),
+        },
+    })
+}
+
+// typechecks the term and returns the small steps to its value
+pub fn trace(term: &Term) -> Result<Vec<Step<Term>>, Error> {
+    check(term)?;
+    match eval_trace(&term.0) {
+        (steps, None) => Ok(steps
+            .into_iter()
+            .map(|step| Step {
+                rules: rule_names(&step.rules),
+                term: Term::new(step.term),
+            })
+            .collect()),
+        (_, Some(error)) => Err(Error::new(ErrorKind::Eval, error)),
+    }
+}
+
+pub fn pretty(term: &Term) -> String {
+    term.to_string()
+}
+
+// `term` with its variables as names, de Bruijn indices or both
+pub fn display(term: &Term, mode: DisplayMode) -> String {
+    term.0.display(mode).to_string()
+}
+
+// the syntax tree of `term` in Graphviz DOT
+pub fn to_dot(term: &Term) -> String {
+    tapl_frontend::dot::to_dot(&term.0)
+}
+
+// the de Bruijn form of `term` as the chapter crate spells out its syntax
+// tree, for debugging
+pub fn ast(term: &Term) -> String {
+    format!("{:?}", term.0)
+}
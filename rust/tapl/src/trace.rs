@@ -0,0 +1,62 @@
+use std::fmt::Display;
+
+use tapl_frontend::{dot::quote, latex::texttt};
+
+// One reduction step: the rules of its derivation, from the outermost
+// congruence rule down to the rule that contracts the redex, and the term it
+// reaches
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step<T> {
+    pub rules: Vec<String>,
+    pub term: T,
+}
+
+// `term` and then one `-> term` line per step, followed by the name of every
+// rule of the step's derivation with `show_rules`
+pub fn render<T: Display>(term: &T, steps: &[Step<T>], show_rules: bool) -> String {
+    let mut out = format!("{}\n", term);
+    for step in steps {
+        out += &format!("-> {}", step.term);
+        if show_rules {
+            out += &format!("    by {}", step.rules.join(" "));
+        }
+        out += "\n";
+    }
+    out
+}
+
+// The reduction as a Graphviz DOT chain with a node per term and an edge
+// per step, labeled with the rules of the step's derivation
+pub fn trace_to_dot<T: Display>(term: &T, steps: &[Step<T>]) -> String {
+    let mut out = format!(
+        "digraph trace {{\n  t0 [label={}];\n",
+        quote(&term.to_string())
+    );
+    for (i, step) in steps.iter().enumerate() {
+        out += &format!("  t{} [label={}];\n", i + 1, quote(&step.term.to_string()));
+        let rules = quote(&step.rules.join(" "));
+        out += &format!("  t{} -> t{} [label={}];\n", i, i + 1, rules);
+    }
+    out + "}\n"
+}
+
+// The reduction as the lines of an `align*` environment, one per step with
+// the rules of its derivation on the right
+pub fn trace_to_latex<T: Display>(term: &T, steps: &[Step<T>]) -> String {
+    let mut lines = vec![format!("  & {}", texttt(&term.to_string()))];
+    for step in steps {
+        lines.push(format!(
+            "  \\longrightarrow{{}} & {} && \\textsc{{{}}}",
+            texttt(&step.term.to_string()),
+            step.rules.join(", ")
+        ));
+    }
+    format!(
+        "\\begin{{align*}}\n{}\n\\end{{align*}}\n",
+        lines.join(" \\\\\n")
+    )
+}
+
+pub(crate) fn rule_names(rules: &[impl Display]) -> Vec<String> {
+    rules.iter().map(ToString::to_string).collect()
+}
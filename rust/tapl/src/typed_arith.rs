@@ -0,0 +1,66 @@
+// Typed arithmetic expressions (TAPL chapter 8), with the syntax of `arith`
+
+use std::fmt::{self, Formatter};
+
+use typed_arith::eval::{self, eval_term, type_of_term};
+use untyped_arith::parser;
+
+use crate::{parse_with, program, Error, ErrorKind};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Term(parser::Term);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Type(typed_arith::eval::Type);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Value(typed_arith::eval::Value);
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+pub fn parse(input: &str) -> Result<Term, Error> {
+    parse_with(&program(input), parser::parse).map(Term)
+}
+
+pub fn check(term: &Term) -> Result<Type, Error> {
+    type_of_term(&term.0)
+        .map(Type)
+        .map_err(|error| Error::new(ErrorKind::Type, error))
+}
+
+// the term is typechecked first, an ill-typed term is an `ErrorKind::Type`
+pub fn eval(term: &Term) -> Result<Value, Error> {
+    eval_term(&term.0).map(Value).map_err(|error| {
+        let kind = match error {
+            eval::Error::Parse(_) => ErrorKind::Parse,
+            eval::Error::Type(_) => ErrorKind::Type,
+            eval::Error::Eval(_) => ErrorKind::Eval,
+        };
+        Error::new(kind, error)
+    })
+}
+
+pub fn pretty(term: &Term) -> String {
+    term.to_string()
+}
+
+// the syntax tree of `term` in Graphviz DOT
+pub fn to_dot(term: &Term) -> String {
+    tapl_frontend::dot::to_dot(&term.0)
+}
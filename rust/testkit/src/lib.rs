@@ -0,0 +1,149 @@
+// Assertion helpers shared by the test suites of every chapter crate.
+//
+// A crate describes its language once by implementing `Language` (usually in
+// a `#[cfg(test)]` module), and tests are then written against source text:
+//
+//     assert_evals_to!(Stlc, "(lambda x:Bool.x) true;", "true");
+//     assert_type_error!(Stlc, "succ(true);");
+//     assert_step_trace!(Stlc, "if true then false else true;", ["false"]);
+//
+// Expected values and trace steps are written in the language's own syntax
+// without the terminating `;`.
+
+use std::fmt::{self, Debug, Formatter};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Failure {
+    Parse(String),
+    Type(String),
+    Eval(String),
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Parse(msg) => write!(f, "parse error: {}", msg),
+            Failure::Type(msg) => write!(f, "type error: {}", msg),
+            Failure::Eval(msg) => write!(f, "eval error: {}", msg),
+        }
+    }
+}
+
+pub trait Language {
+    type Term: Clone + Debug + PartialEq;
+    type Value: Debug + PartialEq;
+
+    fn parse(input: &str) -> Result<Self::Term, Failure>;
+
+    // untyped languages accept every term
+    fn type_check(_term: &Self::Term) -> Result<(), Failure> {
+        Ok(())
+    }
+
+    fn eval(term: &Self::Term) -> Result<Self::Value, Failure>;
+
+    // one small step, `None` once no rule applies; languages with only a
+    // big-step evaluator leave this out
+    fn step(_term: &Self::Term) -> Option<Self::Term> {
+        None
+    }
+}
+
+fn parse_value<L: Language>(value: &str) -> L::Term {
+    match L::parse(&format!("{};", value)) {
+        Ok(term) => term,
+        Err(failure) => panic!("expected `{}` does not parse: {}", value, failure),
+    }
+}
+
+#[track_caller]
+pub fn check_evals_to<L: Language>(program: &str, value: &str) {
+    let run = |term: &L::Term| {
+        L::type_check(term)?;
+        L::eval(term)
+    };
+    let actual = L::parse(program).and_then(|term| run(&term));
+    let expected = run(&parse_value::<L>(value));
+    match (actual, expected) {
+        (Ok(actual), Ok(expected)) => assert_eq!(
+            actual, expected,
+            "`{}` should evaluate to `{}`",
+            program, value
+        ),
+        (Err(failure), _) => panic!("`{}` failed: {}", program, failure),
+        (_, Err(failure)) => panic!("expected `{}` failed: {}", value, failure),
+    }
+}
+
+#[track_caller]
+pub fn check_type_error<L: Language>(program: &str, message: Option<&str>) {
+    let term = match L::parse(program) {
+        Ok(term) => term,
+        Err(failure) => panic!("`{}` should parse: {}", program, failure),
+    };
+    match L::type_check(&term) {
+        Err(Failure::Type(msg)) => {
+            if let Some(message) = message {
+                assert!(
+                    msg.contains(message),
+                    "type error of `{}` is `{}`, expected it to mention `{}`",
+                    program,
+                    msg,
+                    message
+                );
+            }
+        }
+        Err(failure) => panic!("`{}` should be a type error, got {}", program, failure),
+        Ok(()) => panic!("`{}` should not typecheck", program),
+    }
+}
+
+const MAX_TRACE_STEPS: usize = 10_000;
+
+#[track_caller]
+pub fn check_step_trace<L: Language>(program: &str, steps: &[&str]) {
+    let mut term = match L::parse(program) {
+        Ok(term) => term,
+        Err(failure) => panic!("`{}` should parse: {}", program, failure),
+    };
+    let mut trace = vec![];
+    while let Some(next_term) = L::step(&term) {
+        assert!(
+            trace.len() < MAX_TRACE_STEPS,
+            "`{}` did not stop within {} steps",
+            program,
+            MAX_TRACE_STEPS
+        );
+        trace.push(next_term.clone());
+        term = next_term;
+    }
+    let expected = steps
+        .iter()
+        .map(|step| parse_value::<L>(step))
+        .collect::<Vec<_>>();
+    assert_eq!(trace, expected, "step trace of `{}`", program);
+}
+
+#[macro_export]
+macro_rules! assert_evals_to {
+    ($lang:ty, $program:expr, $value:expr) => {
+        $crate::check_evals_to::<$lang>($program, $value)
+    };
+}
+
+#[macro_export]
+macro_rules! assert_type_error {
+    ($lang:ty, $program:expr) => {
+        $crate::check_type_error::<$lang>($program, None)
+    };
+    ($lang:ty, $program:expr, $message:expr) => {
+        $crate::check_type_error::<$lang>($program, Some($message))
+    };
+}
+
+#[macro_export]
+macro_rules! assert_step_trace {
+    ($lang:ty, $program:expr, [$($step:expr),* $(,)?]) => {
+        $crate::check_step_trace::<$lang>($program, &[$($step),*])
+    };
+}